@@ -0,0 +1,79 @@
+use anyhow::{Context, anyhow};
+use reqwest::Method;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Talks to the `/admin` endpoints of a signalling node. The admin routes sit
+/// behind the client API key like the rest of the API, requests carry both.
+pub struct AdminClient {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    admin_key: String,
+}
+
+impl AdminClient {
+    pub fn new(base_url: &str, api_key: String, admin_key: String) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            api_key,
+            admin_key,
+        }
+    }
+
+    pub async fn get(&self, path: &str) -> anyhow::Result<Value> {
+        self.send(Method::GET, path, None::<&()>).await
+    }
+
+    pub async fn post(&self, path: &str, body: &impl Serialize) -> anyhow::Result<Value> {
+        self.send(Method::POST, path, Some(body)).await
+    }
+
+    pub async fn put(&self, path: &str) -> anyhow::Result<Value> {
+        self.send(Method::PUT, path, None::<&()>).await
+    }
+
+    pub async fn delete(&self, path: &str) -> anyhow::Result<Value> {
+        self.send(Method::DELETE, path, None::<&()>).await
+    }
+
+    async fn send<B: Serialize + ?Sized>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&B>,
+    ) -> anyhow::Result<Value> {
+        let url = format!("{}/admin/{}", self.base_url, path);
+
+        let mut request = self
+            .http
+            .request(method.clone(), &url)
+            .header("X-API-Key", &self.api_key)
+            .header("X-Admin-Key", &self.admin_key);
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("{method} {url}"))?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+
+        if !status.is_success() {
+            // Problem details explain themselves in `detail`, the key
+            // middlewares answer with a bare error name
+            let reason = body
+                .get("detail")
+                .and_then(Value::as_str)
+                .map(str::to_owned)
+                .unwrap_or_else(|| body.to_string());
+
+            return Err(anyhow!("{method} {url} failed with {status}: {reason}"));
+        }
+
+        Ok(body)
+    }
+}
@@ -0,0 +1,72 @@
+use clap::Subcommand;
+use serde_json::json;
+
+use crate::{
+    client::AdminClient,
+    output::{cell, items, print_json, print_table},
+};
+
+#[derive(Debug, Subcommand)]
+pub enum BotCommand {
+    /// Lists the registered bots
+    List,
+    /// Registers a bot and prints its API key, it is only shown once
+    Create {
+        name: String,
+        /// `chat:read`, `chat:write` or `recording:control`, repeat for several
+        #[arg(long = "scope", required = true)]
+        scopes: Vec<String>,
+    },
+    /// Revokes a bot, its API key stops working immediately
+    Revoke { id: i32 },
+}
+
+impl BotCommand {
+    pub async fn run(self, client: &AdminClient, json: bool) -> anyhow::Result<()> {
+        match self {
+            BotCommand::List => {
+                let bots = client.get("bots").await?;
+
+                if json {
+                    print_json(&bots);
+                    return Ok(());
+                }
+
+                let rows = items(&bots, "bots")
+                    .iter()
+                    .map(|bot| vec![cell(bot, "id"), cell(bot, "name"), cell(bot, "scopes")])
+                    .collect();
+
+                print_table(&["id", "name", "scopes"], rows);
+            }
+            BotCommand::Create { name, scopes } => {
+                let bot = client
+                    .post("bots", &json!({ "name": name, "scopes": scopes }))
+                    .await?;
+
+                if json {
+                    print_json(&bot);
+                } else {
+                    println!(
+                        "Registered bot {} ({})",
+                        cell(&bot, "name"),
+                        cell(&bot, "id")
+                    );
+                    println!("API key: {}", cell(&bot, "token"));
+                    eprintln!("Store the key now, it can't be shown again");
+                }
+            }
+            BotCommand::Revoke { id } => {
+                let bot = client.delete(&format!("bots/{id}")).await?;
+
+                if json {
+                    print_json(&bot);
+                } else {
+                    println!("Revoked bot {} ({id})", cell(&bot, "name"));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+mod bots;
+mod nodes;
+mod rooms;
+mod search;
+mod tokens;
+
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use clap::{Parser, Subcommand};
+
+use crate::{client::AdminClient, migrations};
+
+/// Operates a Waterbus deployment through the signalling admin API
+#[derive(Debug, Parser)]
+#[command(name = "waterbus-cli", version)]
+pub struct Cli {
+    /// Base URL of the signalling API
+    #[arg(
+        long,
+        env = "WATERBUS_URL",
+        default_value = "http://localhost:5998/busapi/v3",
+        global = true
+    )]
+    url: String,
+
+    /// Same as the server's `CLIENT_SECRET_KEY`
+    #[arg(long, env = "CLIENT_SECRET_KEY", hide_env_values = true, global = true)]
+    api_key: Option<String>,
+
+    /// Same as the server's `ADMIN_SECRET_KEY`
+    #[arg(long, env = "ADMIN_SECRET_KEY", hide_env_values = true, global = true)]
+    admin_key: Option<String>,
+
+    /// Print the raw responses instead of tables
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// SFU nodes and draining them for maintenance
+    #[command(subcommand)]
+    Nodes(nodes::NodeCommand),
+    /// Live rooms, their stats and closing them
+    #[command(subcommand)]
+    Rooms(rooms::RoomCommand),
+    /// Bot API keys
+    #[command(subcommand)]
+    Bots(bots::BotCommand),
+    /// Join and observer tokens
+    #[command(subcommand)]
+    Tokens(tokens::TokenCommand),
+    /// Rebuilding the search index
+    #[command(subcommand)]
+    Search(search::SearchCommand),
+    /// Applies pending database migrations, like `diesel migration run`
+    Migrate {
+        #[arg(long, env = "DATABASE_URL", hide_env_values = true)]
+        database_url: String,
+        #[arg(long, default_value = "migrations")]
+        dir: PathBuf,
+        /// Only list the pending migrations
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+impl Cli {
+    pub async fn run(self) -> anyhow::Result<()> {
+        // Built on first use, `migrate` talks to the database directly and
+        // works before the server is up
+        let client = || -> anyhow::Result<AdminClient> {
+            let api_key = self
+                .api_key
+                .clone()
+                .ok_or_else(|| anyhow!("set CLIENT_SECRET_KEY or pass --api-key"))?;
+            let admin_key = self
+                .admin_key
+                .clone()
+                .ok_or_else(|| anyhow!("set ADMIN_SECRET_KEY or pass --admin-key"))?;
+
+            Ok(AdminClient::new(&self.url, api_key, admin_key))
+        };
+
+        match self.command {
+            Command::Nodes(command) => command.run(&client()?, self.json).await,
+            Command::Rooms(command) => command.run(&client()?, self.json).await,
+            Command::Bots(command) => command.run(&client()?, self.json).await,
+            Command::Tokens(command) => command.run(&client()?, self.json).await,
+            Command::Search(command) => command.run(&client()?, self.json).await,
+            Command::Migrate {
+                database_url,
+                dir,
+                dry_run,
+            } => migrations::run(&database_url, &dir, dry_run),
+        }
+    }
+}
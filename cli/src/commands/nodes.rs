@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use clap::Subcommand;
+use serde_json::Value;
+
+use crate::{
+    client::AdminClient,
+    output::{cell, items, print_json, print_table},
+};
+
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Subcommand)]
+pub enum NodeCommand {
+    /// Lists the SFU nodes with their load and drain state
+    List,
+    /// Stops routing new rooms to the node, calls already on it carry on
+    Drain {
+        node_id: String,
+        /// Returns once the node has no clients left
+        #[arg(long)]
+        wait: bool,
+    },
+    /// Puts a drained node back in rotation
+    Undrain { node_id: String },
+}
+
+impl NodeCommand {
+    pub async fn run(self, client: &AdminClient, json: bool) -> anyhow::Result<()> {
+        match self {
+            NodeCommand::List => {
+                let nodes = client.get("nodes").await?;
+
+                if json {
+                    print_json(&nodes);
+                } else {
+                    print_nodes(items(&nodes, "nodes"));
+                }
+            }
+            NodeCommand::Drain { node_id, wait } => {
+                let mut node = client.put(&format!("nodes/{node_id}/drain")).await?;
+                print_node(&node, json);
+
+                while wait && node.get("clients").and_then(Value::as_u64) != Some(0) {
+                    tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+
+                    let nodes = client.get("nodes").await?;
+                    node = items(&nodes, "nodes")
+                        .iter()
+                        .find(|candidate| {
+                            candidate.get("nodeId").and_then(Value::as_str)
+                                == Some(node_id.as_str())
+                        })
+                        .cloned()
+                        .ok_or_else(|| anyhow!("node {node_id} went away while draining"))?;
+
+                    eprintln!("{} clients left on {node_id}", cell(&node, "clients"));
+                }
+            }
+            NodeCommand::Undrain { node_id } => {
+                let node = client.delete(&format!("nodes/{node_id}/drain")).await?;
+                print_node(&node, json);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn print_node(node: &Value, json: bool) {
+    if json {
+        print_json(node);
+    } else {
+        print_nodes(std::slice::from_ref(node));
+    }
+}
+
+fn print_nodes(nodes: &[Value]) {
+    let rows = nodes
+        .iter()
+        .map(|node| {
+            vec![
+                cell(node, "nodeId"),
+                cell(node, "addr"),
+                cell(node, "cohort"),
+                cell(node, "cpu"),
+                cell(node, "ram"),
+                cell(node, "clients"),
+                cell(node, "isDrained"),
+            ]
+        })
+        .collect();
+
+    print_table(
+        &["node", "addr", "cohort", "cpu", "ram", "clients", "drained"],
+        rows,
+    );
+}
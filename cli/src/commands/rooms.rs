@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use anyhow::bail;
+use clap::Subcommand;
+use serde_json::{Value, json};
+
+use crate::{
+    client::AdminClient,
+    output::{cell, confirm, items, print_json, print_table},
+};
+
+#[derive(Debug, Subcommand)]
+pub enum RoomCommand {
+    /// Lists the rooms with a call going on, busiest first
+    List,
+    /// Shows a room by public id
+    Show { room_id: String },
+    /// Ends the call for everyone and deactivates the room
+    Close {
+        room_id: String,
+        /// Don't ask for confirmation
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Publishers, subscribers and bitrate of the room across the SFU nodes
+    Stats {
+        room_id: String,
+        /// Keeps printing the stats until interrupted
+        #[arg(short, long)]
+        follow: bool,
+        /// Seconds between two reads with `--follow`
+        #[arg(long, default_value_t = 2)]
+        interval: u64,
+    },
+}
+
+impl RoomCommand {
+    pub async fn run(self, client: &AdminClient, json: bool) -> anyhow::Result<()> {
+        match self {
+            RoomCommand::List => {
+                let rooms = client.get("rooms").await?;
+
+                if json {
+                    print_json(&rooms);
+                    return Ok(());
+                }
+
+                let rows = items(&rooms, "rooms")
+                    .iter()
+                    .map(|room| {
+                        vec![
+                            cell(room, "roomId"),
+                            cell(room, "clients"),
+                            cell(room, "nodeIds"),
+                        ]
+                    })
+                    .collect();
+
+                print_table(&["room", "clients", "nodes"], rows);
+            }
+            RoomCommand::Show { room_id } => {
+                print_json(&client.get(&format!("rooms/{room_id}")).await?);
+            }
+            RoomCommand::Close { room_id, yes } => {
+                if !yes && !confirm(&format!("Close room {room_id} for everyone in it?"))? {
+                    bail!("aborted");
+                }
+
+                let room = client
+                    .post(&format!("rooms/{room_id}/close"), &json!({}))
+                    .await?;
+
+                if json {
+                    print_json(&room);
+                } else {
+                    println!("Closed room {room_id}");
+                }
+            }
+            RoomCommand::Stats {
+                room_id,
+                follow,
+                interval,
+            } => {
+                let path = format!("rooms/{room_id}/sfu-stats");
+                let interval = Duration::from_secs(interval.max(1));
+
+                loop {
+                    let stats = client.get(&path).await?;
+
+                    if json {
+                        // One document per line so the output can be piped
+                        println!("{stats}");
+                    } else {
+                        print_stats(&stats);
+                    }
+
+                    if !follow {
+                        break;
+                    }
+
+                    tokio::time::sleep(interval).await;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn print_stats(stats: &Value) {
+    let egress_kbps = stats.get("egressBps").and_then(Value::as_u64).unwrap_or(0) / 1_000;
+    let recording = match (
+        stats.get("isRecording").and_then(Value::as_bool),
+        stats.get("isRecordingPaused").and_then(Value::as_bool),
+    ) {
+        (Some(true), Some(true)) => "paused",
+        (Some(true), _) => "yes",
+        _ => "no",
+    };
+
+    println!(
+        "publishers {}  subscribers {}  egress {} kbps  nodes {}  recording {}",
+        items(stats, "participants").len(),
+        cell(stats, "subscribers"),
+        egress_kbps,
+        items(stats, "nodes").len(),
+        recording
+    );
+
+    let unreachable = items(stats, "unreachableNodes");
+    if !unreachable.is_empty() {
+        eprintln!(
+            "  missing the share of unreachable nodes: {}",
+            cell(stats, "unreachableNodes")
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use clap::Subcommand;
+use serde_json::{Value, json};
+
+use crate::{
+    client::AdminClient,
+    output::{cell, items, print_json, print_table},
+};
+
+const REINDEX_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Subcommand)]
+pub enum SearchCommand {
+    /// Starts rebuilding the search index, search keeps answering meanwhile
+    Reindex {
+        /// Returns once the rebuild finished
+        #[arg(long)]
+        wait: bool,
+    },
+    /// Progress of the latest rebuild
+    Status,
+}
+
+impl SearchCommand {
+    pub async fn run(self, client: &AdminClient, json: bool) -> anyhow::Result<()> {
+        let mut progress = match self {
+            SearchCommand::Reindex { .. } => client.post("search/reindex", &json!({})).await?,
+            SearchCommand::Status => client.get("search/reindex").await?,
+        };
+
+        if matches!(self, SearchCommand::Reindex { wait: true }) {
+            while progress.get("isRunning").and_then(Value::as_bool) == Some(true) {
+                tokio::time::sleep(REINDEX_POLL_INTERVAL).await;
+                progress = client.get("search/reindex").await?;
+            }
+        }
+
+        if json {
+            print_json(&progress);
+            return Ok(());
+        }
+
+        let state = match (
+            progress.get("isRunning").and_then(Value::as_bool),
+            progress.get("isFailed").and_then(Value::as_bool),
+        ) {
+            (Some(true), _) => "running",
+            (_, Some(true)) => "failed",
+            _ => "done",
+        };
+        println!("Reindex {state}, started {}", cell(&progress, "startedAt"));
+
+        let rows = items(&progress, "collections")
+            .iter()
+            .map(|collection| {
+                vec![
+                    cell(collection, "name"),
+                    cell(collection, "indexed"),
+                    cell(collection, "total"),
+                ]
+            })
+            .collect();
+
+        print_table(&["collection", "indexed", "total"], rows);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use clap::Subcommand;
+use serde_json::json;
+
+use crate::{
+    client::AdminClient,
+    output::{cell, print_json},
+};
+
+#[derive(Debug, Subcommand)]
+pub enum TokenCommand {
+    /// Admits a user to the call and prints the token for `room.publish`
+    Join {
+        room_id: String,
+        /// Public id of the user
+        #[arg(long)]
+        user: String,
+        /// Only needed for users who aren't members of a protected room
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Lets someone watch the room without joining it
+    Observer {
+        room_id: String,
+        /// Who is watching and why, shown in the logs
+        #[arg(long)]
+        label: String,
+        /// Defaults to an hour, at most a day
+        #[arg(long)]
+        expires_in: Option<i64>,
+    },
+}
+
+impl TokenCommand {
+    pub async fn run(self, client: &AdminClient, json: bool) -> anyhow::Result<()> {
+        let token = match self {
+            TokenCommand::Join {
+                room_id,
+                user,
+                password,
+            } => {
+                client
+                    .post(
+                        &format!("rooms/{room_id}/join-tokens"),
+                        &json!({ "userId": user, "password": password }),
+                    )
+                    .await?
+            }
+            TokenCommand::Observer {
+                room_id,
+                label,
+                expires_in,
+            } => {
+                client
+                    .post(
+                        &format!("rooms/{room_id}/observer-tokens"),
+                        &json!({ "label": label, "expiresInSeconds": expires_in }),
+                    )
+                    .await?
+            }
+        };
+
+        if json {
+            print_json(&token);
+        } else {
+            println!("{}", cell(&token, "token"));
+            eprintln!("Expires at {} (unix time)", cell(&token, "expiresAt"));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,20 @@
+mod client;
+mod commands;
+mod migrations;
+mod output;
+
+use clap::Parser;
+
+use crate::commands::Cli;
+
+#[tokio::main]
+async fn main() {
+    dotenvy::dotenv().ok();
+
+    let cli = Cli::parse();
+
+    if let Err(err) = cli.run().await {
+        eprintln!("error: {err:#}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,184 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use diesel::{
+    Connection, PgConnection, QueryableByName, RunQueryDsl, connection::SimpleConnection,
+    sql_query, sql_types::Text,
+};
+
+/// The table the diesel CLI keeps applied migrations in, shared with it so
+/// either tool can pick up where the other stopped
+const CREATE_MIGRATIONS_TABLE: &str = "CREATE TABLE IF NOT EXISTS __diesel_schema_migrations (
+    version VARCHAR(50) PRIMARY KEY NOT NULL,
+    run_on TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+)";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    pub version: String,
+    pub name: String,
+    pub dir: PathBuf,
+}
+
+#[derive(QueryableByName)]
+struct AppliedMigration {
+    #[diesel(sql_type = Text)]
+    version: String,
+}
+
+/// Applies every migration of `dir` the database hasn't seen yet, oldest
+/// first and each in its own transaction
+pub fn run(database_url: &str, dir: &Path, dry_run: bool) -> anyhow::Result<()> {
+    let migrations = discover(dir)?;
+
+    let mut conn =
+        PgConnection::establish(database_url).context("Failed to connect to the database")?;
+    conn.batch_execute(CREATE_MIGRATIONS_TABLE)?;
+
+    let applied: HashSet<String> = sql_query("SELECT version FROM __diesel_schema_migrations")
+        .load::<AppliedMigration>(&mut conn)?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
+
+    let pending = pending(&migrations, &applied);
+    if pending.is_empty() {
+        println!("Database is up to date");
+        return Ok(());
+    }
+
+    for migration in pending {
+        if dry_run {
+            println!("pending {}", migration.name);
+            continue;
+        }
+
+        let up = fs::read_to_string(migration.dir.join("up.sql"))
+            .with_context(|| format!("Failed to read migration {}", migration.name))?;
+
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            conn.batch_execute(&up)?;
+            sql_query("INSERT INTO __diesel_schema_migrations (version) VALUES ($1)")
+                .bind::<Text, _>(migration.version.as_str())
+                .execute(conn)?;
+            Ok(())
+        })
+        .with_context(|| format!("Migration {} failed", migration.name))?;
+
+        println!("applied {}", migration.name);
+    }
+
+    Ok(())
+}
+
+/// Migrations of `dir` sorted by version, directories without an `up.sql`
+/// are skipped
+pub fn discover(dir: &Path) -> anyhow::Result<Vec<Migration>> {
+    let entries = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read migrations from {}", dir.display()))?;
+
+    let mut migrations = vec![];
+    for entry in entries {
+        let path = entry?.path();
+        if !path.join("up.sql").is_file() {
+            continue;
+        }
+
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let Some(version) = version_of(name) else {
+            continue;
+        };
+
+        migrations.push(Migration {
+            version,
+            name: name.to_owned(),
+            dir: path,
+        });
+    }
+
+    migrations.sort_by(|a, b| a.version.cmp(&b.version));
+
+    Ok(migrations)
+}
+
+fn pending<'a>(migrations: &'a [Migration], applied: &HashSet<String>) -> Vec<&'a Migration> {
+    migrations
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+        .collect()
+}
+
+/// `2025-08-19-090000_bots` is version `20250819090000`, the way the diesel
+/// CLI names migrations
+fn version_of(dir_name: &str) -> Option<String> {
+    let (version, _) = dir_name.split_once('_')?;
+    let version: String = version.chars().filter(|c| *c != '-').collect();
+
+    if version.is_empty() || !version.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    Some(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migration(version: &str) -> Migration {
+        Migration {
+            version: version.to_owned(),
+            name: format!("{version}_test"),
+            dir: PathBuf::from(version),
+        }
+    }
+
+    #[test]
+    fn test_version_of_diesel_names() {
+        assert_eq!(
+            version_of("2025-08-19-090000_bots").as_deref(),
+            Some("20250819090000")
+        );
+        assert_eq!(
+            version_of("00000000000000_diesel_initial_setup").as_deref(),
+            Some("00000000000000")
+        );
+        assert_eq!(version_of("notes"), None);
+        assert_eq!(version_of("draft_bots"), None);
+    }
+
+    #[test]
+    fn test_pending_skips_applied() {
+        let migrations = vec![
+            migration("00000000000000"),
+            migration("20250715120755"),
+            migration("20250819090000"),
+        ];
+        let applied = HashSet::from(["00000000000000".to_owned(), "20250715120755".to_owned()]);
+
+        let pending = pending(&migrations, &applied);
+
+        assert_eq!(pending, vec![&migrations[2]]);
+    }
+
+    #[test]
+    fn test_discover_repo_migrations_in_order() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../migrations");
+
+        let migrations = discover(&dir).unwrap();
+
+        assert!(!migrations.is_empty());
+        assert_eq!(migrations[0].version, "00000000000000");
+        assert!(
+            migrations
+                .windows(2)
+                .all(|pair| pair[0].version < pair[1].version)
+        );
+    }
+}
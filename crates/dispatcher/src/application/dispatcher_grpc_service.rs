@@ -1,20 +1,19 @@
-use async_channel::Sender;
 use tonic::{Request, Response, Status};
 use waterbus_proto::dispatcher_service_server::DispatcherService;
 use waterbus_proto::{
     DispatcherResponse, NewUserJoinedRequest, PublisherCandidateRequest,
-    SubscriberCandidateRequest, SubscriberRenegotiateRequest,
+    SubscriberCandidateRequest, SubscriberDownlinkStarvedRequest, SubscriberRenegotiateRequest,
 };
 
-use crate::domain::DispatcherCallback;
+use crate::{domain::DispatcherCallback, infrastructure::event_queue::EventSender};
 
 #[derive(Debug)]
 pub struct DispatcherGrpcService {
-    sender: Sender<DispatcherCallback>,
+    sender: EventSender<DispatcherCallback>,
 }
 
 impl DispatcherGrpcService {
-    pub fn new(sender: Sender<DispatcherCallback>) -> Self {
+    pub fn new(sender: EventSender<DispatcherCallback>) -> Self {
         Self { sender }
     }
 }
@@ -72,4 +71,17 @@ impl DispatcherService for DispatcherGrpcService {
 
         Ok(Response::new(DispatcherResponse { is_success: true }))
     }
+
+    async fn on_subscriber_downlink_starved(
+        &self,
+        req: Request<SubscriberDownlinkStarvedRequest>,
+    ) -> Result<Response<DispatcherResponse>, Status> {
+        let req = req.into_inner();
+        let _ = self
+            .sender
+            .send(DispatcherCallback::SubscriberDownlinkStarved(req))
+            .await;
+
+        Ok(Response::new(DispatcherResponse { is_success: true }))
+    }
 }
@@ -1,10 +1,15 @@
 use tonic::{Request, Status, transport::Channel};
 use waterbus_proto::{
-    AddPublisherCandidateRequest, AddSubscriberCandidateRequest, JoinRoomRequest, JoinRoomResponse,
-    LeaveRoomRequest, LeaveRoomResponse, MigratePublisherRequest, MigratePublisherResponse,
-    PublisherRenegotiationRequest, PublisherRenegotiationResponse, SetCameraType,
-    SetEnabledRequest, SetScreenSharingRequest, SetSubscriberSdpRequest, StatusResponse,
-    SubscribeRequest, SubscribeResponse, sfu_service_client::SfuServiceClient,
+    AddPublisherCandidateRequest, AddSubscriberCandidateRequest, GetDiagnosticsRequest,
+    GetDiagnosticsResponse, GetRecordingManifestsRequest, GetRecordingManifestsResponse,
+    GetRoomStatsRequest, GetRoomStatsResponse, JoinRoomRequest, JoinRoomResponse, LeaveRoomRequest,
+    LeaveRoomResponse, MigratePublisherRequest, MigratePublisherResponse,
+    PublisherRenegotiationRequest, PublisherRenegotiationResponse, SetBroadcastCutRequest,
+    SetCameraType, SetEgressRequest, SetEnabledRequest, SetQosClassRequest,
+    SetRecordingPausedRequest, SetScreenSharingRequest, SetSpeakerRequest, SetStageModeRequest,
+    SetSubscriberSdpRequest, SetViewportRequest, StartSlateRequest, StatusResponse,
+    StopSlateRequest, SubscribeRequest, SubscribeResponse, UnpublishRequest,
+    sfu_service_client::SfuServiceClient,
 };
 
 #[derive(Debug, Clone, Default)]
@@ -195,4 +200,176 @@ impl SfuGrpcClient {
         let response = client.set_camera_type(Request::new(request)).await?;
         Ok(response)
     }
+
+    pub async fn unpublish(
+        &self,
+        server_address: String,
+        request: UnpublishRequest,
+    ) -> Result<tonic::Response<StatusResponse>, tonic::Status> {
+        let mut client = self
+            .get_client(server_address)
+            .await
+            .map_err(|e| Status::unavailable(format!("Failed to connect to SFU: {e}")))?;
+        let response = client.unpublish(Request::new(request)).await?;
+        Ok(response)
+    }
+
+    pub async fn set_viewport(
+        &self,
+        server_address: String,
+        request: SetViewportRequest,
+    ) -> Result<tonic::Response<StatusResponse>, tonic::Status> {
+        let mut client = self
+            .get_client(server_address)
+            .await
+            .map_err(|e| Status::unavailable(format!("Failed to connect to SFU: {e}")))?;
+        let response = client.set_viewport(Request::new(request)).await?;
+        Ok(response)
+    }
+
+    pub async fn set_stage_mode(
+        &self,
+        server_address: String,
+        request: SetStageModeRequest,
+    ) -> Result<tonic::Response<StatusResponse>, tonic::Status> {
+        let mut client = self
+            .get_client(server_address)
+            .await
+            .map_err(|e| Status::unavailable(format!("Failed to connect to SFU: {e}")))?;
+        let response = client.set_stage_mode(Request::new(request)).await?;
+        Ok(response)
+    }
+
+    pub async fn set_speaker(
+        &self,
+        server_address: String,
+        request: SetSpeakerRequest,
+    ) -> Result<tonic::Response<StatusResponse>, tonic::Status> {
+        let mut client = self
+            .get_client(server_address)
+            .await
+            .map_err(|e| Status::unavailable(format!("Failed to connect to SFU: {e}")))?;
+        let response = client.set_speaker(Request::new(request)).await?;
+        Ok(response)
+    }
+
+    pub async fn set_qos_class(
+        &self,
+        server_address: String,
+        request: SetQosClassRequest,
+    ) -> Result<tonic::Response<StatusResponse>, tonic::Status> {
+        let mut client = self
+            .get_client(server_address)
+            .await
+            .map_err(|e| Status::unavailable(format!("Failed to connect to SFU: {e}")))?;
+        let response = client.set_qos_class(Request::new(request)).await?;
+        Ok(response)
+    }
+
+    pub async fn set_recording_paused(
+        &self,
+        server_address: String,
+        request: SetRecordingPausedRequest,
+    ) -> Result<tonic::Response<StatusResponse>, tonic::Status> {
+        let mut client = self
+            .get_client(server_address)
+            .await
+            .map_err(|e| Status::unavailable(format!("Failed to connect to SFU: {e}")))?;
+        let response = client.set_recording_paused(Request::new(request)).await?;
+        Ok(response)
+    }
+
+    pub async fn set_broadcast_cut(
+        &self,
+        server_address: String,
+        request: SetBroadcastCutRequest,
+    ) -> Result<tonic::Response<StatusResponse>, tonic::Status> {
+        let mut client = self
+            .get_client(server_address)
+            .await
+            .map_err(|e| Status::unavailable(format!("Failed to connect to SFU: {e}")))?;
+        let response = client.set_broadcast_cut(Request::new(request)).await?;
+        Ok(response)
+    }
+
+    pub async fn set_egress(
+        &self,
+        server_address: String,
+        request: SetEgressRequest,
+    ) -> Result<tonic::Response<StatusResponse>, tonic::Status> {
+        let mut client = self
+            .get_client(server_address)
+            .await
+            .map_err(|e| Status::unavailable(format!("Failed to connect to SFU: {e}")))?;
+        let response = client.set_egress(Request::new(request)).await?;
+        Ok(response)
+    }
+
+    pub async fn start_slate(
+        &self,
+        server_address: String,
+        request: StartSlateRequest,
+    ) -> Result<tonic::Response<StatusResponse>, tonic::Status> {
+        let mut client = self
+            .get_client(server_address)
+            .await
+            .map_err(|e| Status::unavailable(format!("Failed to connect to SFU: {e}")))?;
+        let response = client.start_slate(Request::new(request)).await?;
+        Ok(response)
+    }
+
+    pub async fn stop_slate(
+        &self,
+        server_address: String,
+        request: StopSlateRequest,
+    ) -> Result<tonic::Response<StatusResponse>, tonic::Status> {
+        let mut client = self
+            .get_client(server_address)
+            .await
+            .map_err(|e| Status::unavailable(format!("Failed to connect to SFU: {e}")))?;
+        let response = client.stop_slate(Request::new(request)).await?;
+        Ok(response)
+    }
+
+    pub async fn get_recording_manifests(
+        &self,
+        server_address: String,
+        request: GetRecordingManifestsRequest,
+    ) -> Result<tonic::Response<GetRecordingManifestsResponse>, tonic::Status> {
+        let mut client = self
+            .get_client(server_address)
+            .await
+            .map_err(|e| Status::unavailable(format!("Failed to connect to SFU: {e}")))?;
+        let response = client
+            .get_recording_manifests(Request::new(request))
+            .await?;
+        Ok(response)
+    }
+
+    pub async fn get_diagnostics(
+        &self,
+        server_address: String,
+    ) -> Result<tonic::Response<GetDiagnosticsResponse>, tonic::Status> {
+        let mut client = self
+            .get_client(server_address)
+            .await
+            .map_err(|e| Status::unavailable(format!("Failed to connect to SFU: {e}")))?;
+        let response = client
+            .get_diagnostics(Request::new(GetDiagnosticsRequest {}))
+            .await?;
+        Ok(response)
+    }
+
+    pub async fn get_room_stats(
+        &self,
+        server_address: String,
+        request: GetRoomStatsRequest,
+    ) -> Result<tonic::Response<GetRoomStatsResponse>, tonic::Status> {
+        let mut client = self
+            .get_client(server_address)
+            .await
+            .map_err(|e| Status::unavailable(format!("Failed to connect to SFU: {e}")))?;
+        let response = client.get_room_stats(Request::new(request)).await?;
+        Ok(response)
+    }
 }
@@ -1,21 +1,39 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
-use async_channel::Sender;
 use tokio::sync::RwLock;
+use tracing::{info, warn};
 use waterbus_proto::{
-    AddPublisherCandidateRequest, AddSubscriberCandidateRequest, JoinRoomRequest, JoinRoomResponse,
+    AddPublisherCandidateRequest, AddSubscriberCandidateRequest, GetDiagnosticsResponse,
+    GetRecordingManifestsRequest, GetRoomStatsRequest, JoinRoomRequest, JoinRoomResponse,
     LeaveRoomRequest, MigratePublisherRequest, MigratePublisherResponse,
-    PublisherRenegotiationRequest, PublisherRenegotiationResponse, SetCameraType,
-    SetEnabledRequest, SetScreenSharingRequest, SetSubscriberSdpRequest, SubscribeRequest,
-    SubscribeResponse,
+    PublisherRenegotiationRequest, PublisherRenegotiationResponse, RecordingManifest,
+    SetBroadcastCutRequest, SetCameraType, SetEgressRequest, SetEnabledRequest, SetQosClassRequest,
+    SetRecordingPausedRequest, SetScreenSharingRequest, SetSpeakerRequest, SetStageModeRequest,
+    SetSubscriberSdpRequest, SetViewportRequest, StartSlateRequest, StopSlateRequest,
+    SubscribeRequest, SubscribeResponse, UnpublishRequest,
 };
 
 use crate::{
     application::sfu_grpc_client::SfuGrpcClient,
-    domain::DispatcherCallback,
+    domain::{
+        DispatcherCallback,
+        fleet::{LiveRoomView, NodeView},
+        rollout::{RolloutConfig, RoomCohort},
+        room_stats::RoomStatsView,
+    },
     infrastructure::{
-        cache::cache_manager::{CacheKey, CacheManager, ClientMetadata},
+        cache::{
+            cache_manager::{CacheKey, CacheManager, ClientMetadata},
+            dead_letter_queue::{self, DeadLetter, DeadLetterQueue, DeadLetterStore},
+            drain_store::DrainStore,
+            rollout_store::RolloutStore,
+            room_control_store::RoomControlStore,
+        },
         etcd::EtcdDispatcher,
+        event_queue::EventSender,
         grpc::grpc_server::GrpcServer,
     },
 };
@@ -26,15 +44,20 @@ pub struct DispatcherConfigs {
     pub sfu_port: u16,
     pub redis_uris: Vec<String>,
     pub etcd_uri: String,
-    pub sender: Sender<DispatcherCallback>,
+    pub sender: EventSender<DispatcherCallback>,
 }
 
 #[derive(Clone)]
 pub struct DispatcherManager {
     sfu_grpc_client: SfuGrpcClient,
     cache_manager: CacheManager,
+    dead_letter_queue: DeadLetterQueue,
+    rollout_store: RolloutStore,
+    drain_store: DrainStore,
+    room_control_store: RoomControlStore,
     etcd_dispatcher: Arc<RwLock<EtcdDispatcher>>,
     sfu_port: u16,
+    sender: EventSender<DispatcherCallback>,
 }
 
 impl DispatcherManager {
@@ -45,26 +68,338 @@ impl DispatcherManager {
             &[&configs.etcd_uri],
             "/sfu/nodes",
             &configs.group_id,
-            configs.sender,
+            configs.sender.clone(),
         )
         .await
         .unwrap();
 
         let sfu_grpc_client = SfuGrpcClient::default();
-        let cache_manager = CacheManager::new(configs.redis_uris);
+        let cache_manager = CacheManager::new(configs.redis_uris.clone());
+        let dead_letter_queue = DeadLetterQueue::new(configs.redis_uris.clone());
+        let rollout_store = RolloutStore::new(configs.redis_uris.clone());
+        let drain_store = DrainStore::new(configs.redis_uris.clone());
+        let room_control_store = RoomControlStore::new(configs.redis_uris);
 
         Self {
             sfu_grpc_client,
             cache_manager,
+            dead_letter_queue,
+            rollout_store,
+            drain_store,
+            room_control_store,
             etcd_dispatcher: Arc::new(RwLock::new(etcd_dispatcher)),
             sfu_port: configs.sfu_port,
+            sender: configs.sender,
+        }
+    }
+
+    /// Compare the persisted client routing with the SFU nodes. Nodes that went
+    /// away while this dispatcher was down are reported as terminated so their
+    /// clients get failed over like on a live node loss. Call it once the
+    /// callbacks are being received, the report waits on the bounded queue.
+    pub async fn reconcile_routing(&self) {
+        let dead_node_ids = match self.prune_routing().await {
+            Ok(dead_node_ids) => dead_node_ids,
+            Err(e) => {
+                warn!("Failed to reconcile client routing: {}", e);
+                return;
+            }
+        };
+
+        for node_id in dead_node_ids {
+            let _ = self
+                .sender
+                .send(DispatcherCallback::NodeTerminated(node_id))
+                .await;
+        }
+    }
+
+    /// Drop the routing live nodes no longer hold, return the nodes that are
+    /// gone with clients still routed to them
+    async fn prune_routing(&self) -> Result<Vec<String>, anyhow::Error> {
+        let node_ids = self.cache_manager.get_node_ids()?;
+        let mut dead_node_ids = vec![];
+
+        for node_id in node_ids {
+            let metadata = {
+                let etcd_reader = self.etcd_dispatcher.read().await;
+                etcd_reader.get_node_by_id(&node_id)
+            };
+
+            let clients = self.cache_manager.get_by_node_id(&node_id)?;
+
+            if let Some(metadata) = metadata {
+                let server_addr = format!("{}:{}", metadata.addr, self.sfu_port);
+                self.prune_node_routing(&node_id, &server_addr, clients)
+                    .await?;
+                continue;
+            }
+
+            if clients.is_empty() {
+                self.cache_manager.remove_node(&node_id)?;
+                continue;
+            }
+
+            info!(
+                "Node {} is gone, {} clients still routed to it",
+                node_id,
+                clients.len()
+            );
+
+            dead_node_ids.push(node_id);
+        }
+
+        Ok(dead_node_ids)
+    }
+
+    /// Ask a live node which participants it holds in each room routed to it,
+    /// clients it no longer has left while this dispatcher was down
+    async fn prune_node_routing(
+        &self,
+        node_id: &str,
+        server_addr: &str,
+        clients: Vec<(CacheKey, ClientMetadata)>,
+    ) -> Result<(), anyhow::Error> {
+        let mut rooms: HashMap<String, Vec<(CacheKey, ClientMetadata)>> = HashMap::new();
+        for (key, client) in clients {
+            rooms
+                .entry(client.room_id.clone())
+                .or_default()
+                .push((key, client));
+        }
+
+        for (room_id, clients) in rooms {
+            let stats = match self
+                .sfu_grpc_client
+                .get_room_stats(
+                    server_addr.to_string(),
+                    GetRoomStatsRequest {
+                        room_id: room_id.clone(),
+                    },
+                )
+                .await
+            {
+                Ok(response) => response.into_inner(),
+                Err(e) => {
+                    warn!(
+                        "Failed to get room {} from node {}, its routing is kept: {}",
+                        room_id, node_id, e
+                    );
+                    continue;
+                }
+            };
+
+            let stale = stale_clients(clients, &stats.participant_ids);
+            if stale.is_empty() {
+                continue;
+            }
+
+            info!(
+                "Node {} no longer holds {} clients of room {}",
+                node_id,
+                stale.len(),
+                room_id
+            );
+
+            for key in stale {
+                self.cache_manager.remove(&key)?;
+            }
+            self.clear_controls_if_empty(&room_id);
+        }
+
+        Ok(())
+    }
+
+    /// Park a callback that could not be delivered so it can be replayed later
+    pub fn push_dead_letter(&self, callback: &DispatcherCallback, reason: &str) {
+        if let Err(e) = self.dead_letter_queue.push(callback, reason) {
+            warn!(
+                "Failed to push {} callback to dead letter queue: {}",
+                callback.kind(),
+                e
+            );
+        }
+    }
+
+    pub fn get_dead_letters(&self, count: usize) -> Result<Vec<DeadLetter>, anyhow::Error> {
+        self.dead_letter_queue
+            .list(count)
+            .map_err(|e| anyhow::anyhow!("Failed to read dead letter queue: {}", e))
+    }
+
+    /// Remove a dead letter from the queue and deliver its callback again.
+    /// Returns None if no dead letter has the given id.
+    pub async fn replay_dead_letter(&self, id: &str) -> Result<Option<DeadLetter>, anyhow::Error> {
+        dead_letter_queue::replay(&self.dead_letter_queue, &self.sender, id).await
+    }
+
+    pub fn get_rollout(&self) -> Result<RolloutConfig, anyhow::Error> {
+        self.rollout_store
+            .get_config()
+            .map_err(|e| anyhow::anyhow!("Failed to read rollout config: {}", e))
+    }
+
+    pub fn set_rollout(&self, config: &RolloutConfig) -> Result<(), anyhow::Error> {
+        config.validate().map_err(|e| anyhow::anyhow!(e))?;
+
+        self.rollout_store
+            .set_config(config)
+            .map_err(|e| anyhow::anyhow!("Failed to store rollout config: {}", e))
+    }
+
+    /// Nodes tagged to take canary traffic, with their CPU headroom
+    pub async fn get_canary_nodes(&self) -> Vec<(String, f32)> {
+        self.etcd_dispatcher
+            .read()
+            .await
+            .get_nodes()
+            .into_iter()
+            .filter(|(_, metadata)| metadata.cohort() == RoomCohort::Canary)
+            .map(|(node_id, metadata)| (node_id, metadata.cpu))
+            .collect()
+    }
+
+    /// Every node of this group with its load and the clients routed to it
+    pub async fn get_nodes(&self) -> Result<Vec<NodeView>, anyhow::Error> {
+        let drained = self
+            .drain_store
+            .get_drained()
+            .map_err(|e| anyhow::anyhow!("Failed to read drained nodes: {}", e))?;
+        let nodes = {
+            let etcd_reader = self.etcd_dispatcher.read().await;
+            etcd_reader.get_nodes()
+        };
+
+        let mut views = Vec::with_capacity(nodes.len());
+
+        for (node_id, metadata) in nodes {
+            let clients = self.get_clients_by_node(&node_id)?.len();
+
+            views.push(NodeView {
+                is_drained: drained.contains(&node_id),
+                cohort: metadata.cohort(),
+                node_id,
+                addr: metadata.addr,
+                tags: metadata.tags,
+                cpu: metadata.cpu,
+                ram: metadata.ram,
+                clients,
+            });
         }
+
+        views.sort_by(|a, b| a.node_id.cmp(&b.node_id));
+
+        Ok(views)
+    }
+
+    /// Stops routing new rooms and publishers to the node, or lets it take
+    /// them again. Calls already on it carry on until they end.
+    pub async fn set_node_drained(
+        &self,
+        node_id: &str,
+        is_drained: bool,
+    ) -> Result<Option<NodeView>, anyhow::Error> {
+        if self
+            .etcd_dispatcher
+            .read()
+            .await
+            .get_node_by_id(node_id)
+            .is_none()
+        {
+            return Ok(None);
+        }
+
+        self.drain_store
+            .set_drained(node_id, is_drained)
+            .map_err(|e| anyhow::anyhow!("Failed to drain node {}: {}", node_id, e))?;
+
+        info!(
+            "Node {} {}",
+            node_id,
+            if is_drained {
+                "drained"
+            } else {
+                "back in rotation"
+            }
+        );
+
+        Ok(self
+            .get_nodes()
+            .await?
+            .into_iter()
+            .find(|node| node.node_id == node_id))
+    }
+
+    /// Without Redis nothing counts as drained, a join shouldn't fail over it
+    fn get_drained_nodes(&self) -> HashSet<String> {
+        self.drain_store.get_drained().unwrap_or_else(|e| {
+            warn!("Failed to read drained nodes: {}", e);
+            HashSet::new()
+        })
     }
 
-    pub async fn join_room(&self, req: JoinRoomRequest) -> Result<JoinRoomResponse, anyhow::Error> {
+    /// Rooms with clients in a call, from the routing of every node
+    pub fn get_live_rooms(&self) -> Result<Vec<LiveRoomView>, anyhow::Error> {
+        let node_ids = self
+            .cache_manager
+            .get_node_ids()
+            .map_err(|e| anyhow::anyhow!("Failed to get nodes: {}", e))?;
+
+        let mut clients = vec![];
+        for node_id in node_ids {
+            clients.extend(
+                self.get_clients_by_node(&node_id)?
+                    .into_iter()
+                    .map(|(_, client)| client),
+            );
+        }
+
+        Ok(LiveRoomView::group(&clients))
+    }
+
+    /// The room's cohort, decided by whoever gets there first. Routing never
+    /// fails over rollout settings, without Redis a room is stable.
+    fn resolve_cohort(&self, room_id: &str, client_version: Option<&str>) -> RoomCohort {
+        let cohort = match self.rollout_store.get_config() {
+            Ok(config) => config.cohort_for(room_id, client_version),
+            Err(e) => {
+                warn!("Failed to read rollout config: {}", e);
+                RoomCohort::Stable
+            }
+        };
+
+        self.rollout_store
+            .claim_cohort(room_id, cohort)
+            .unwrap_or_else(|e| {
+                warn!("Failed to store cohort of room {}: {}", room_id, e);
+                cohort
+            })
+    }
+
+    /// `client_version` is what the joining client declared, it can put a new
+    /// room in the canary cohort
+    pub async fn join_room(
+        &self,
+        mut req: JoinRoomRequest,
+        client_version: Option<&str>,
+    ) -> Result<JoinRoomResponse, anyhow::Error> {
+        // The node applies these before the publisher exists. Without them a
+        // room on stage would be forwarded openly, so the join fails instead.
+        let controls = self
+            .room_control_store
+            .get(&req.room_id)
+            .map_err(|e| anyhow::anyhow!("Failed to read room controls: {}", e))?;
+        req.is_stage_mode = controls.is_stage_mode;
+        req.speaker_ids = controls.speakers;
+        req.is_recording_paused = controls.is_recording_paused;
+        req.is_broadcast_cut = controls.is_broadcast_cut;
+
+        let cohort = self.resolve_cohort(&req.room_id, client_version);
+        let drained = self.get_drained_nodes();
+
         let etcd_writer = self.etcd_dispatcher.read().await;
 
-        let result = etcd_writer.get_node_least();
+        let result = etcd_writer.get_node_least(cohort, &drained);
 
         match result {
             Some((node_id, metadata)) => {
@@ -112,10 +447,28 @@ impl DispatcherManager {
 
                     let server_addr = format!("{}:{}", node_addr, self.sfu_port);
 
-                    let response = self.sfu_grpc_client.subscribe(server_addr, req).await;
+                    let response = self
+                        .sfu_grpc_client
+                        .subscribe(server_addr, req.clone())
+                        .await;
 
                     match response {
-                        Ok(resp) => Ok(resp.into_inner()),
+                        Ok(resp) => {
+                            // Observers never join, this is what routes their leave
+                            if req.is_observer {
+                                let client_metadata = ClientMetadata {
+                                    room_id: req.room_id,
+                                    participant_id: req.participant_id,
+                                    sfu_node_id: node_id,
+                                    node_addr,
+                                };
+                                let _ = self
+                                    .cache_manager
+                                    .insert(CacheKey::new(req.client_id), &client_metadata);
+                            }
+
+                            Ok(resp.into_inner())
+                        }
                         Err(e) => Err(anyhow::anyhow!(
                             "Failed to join room on node {}: {}",
                             node_id,
@@ -309,6 +662,51 @@ impl DispatcherManager {
         }
     }
 
+    /// Return the clients that were routed to a node, keyed by client id, so they
+    /// can be asked to rejoin on a healthy node after it went away
+    pub fn get_clients_by_node(
+        &self,
+        node_id: &str,
+    ) -> Result<Vec<(String, ClientMetadata)>, anyhow::Error> {
+        let clients = self
+            .cache_manager
+            .get_by_node_id(node_id)
+            .map_err(|e| anyhow::anyhow!("Failed to get clients of node {}: {}", node_id, e))?;
+
+        Ok(clients
+            .into_iter()
+            .map(|(key, client)| (key.key, client))
+            .collect())
+    }
+
+    /// Return the clients of a room, keyed by client id, whichever node and
+    /// signalling node they are on
+    pub fn get_clients_by_room(
+        &self,
+        room_id: &str,
+    ) -> Result<Vec<(String, ClientMetadata)>, anyhow::Error> {
+        let clients = self
+            .cache_manager
+            .get_by_room_id(room_id)
+            .map_err(|e| anyhow::anyhow!("Failed to get clients of room {}: {}", room_id, e))?;
+
+        Ok(clients
+            .into_iter()
+            .map(|(key, client)| (key.key, client))
+            .collect())
+    }
+
+    /// CPU usage reported by each live SFU node of this group, in percent
+    pub async fn get_node_loads(&self) -> Vec<(String, f32)> {
+        let etcd_reader = self.etcd_dispatcher.read().await;
+
+        etcd_reader
+            .get_nodes()
+            .into_iter()
+            .map(|(node_id, metadata)| (node_id, metadata.cpu))
+            .collect()
+    }
+
     pub async fn leave_room(&self, req: LeaveRoomRequest) -> Result<ClientMetadata, anyhow::Error> {
         let cache_key = CacheKey::new(req.clone().client_id);
         let client = self.cache_manager.get(&cache_key);
@@ -318,6 +716,8 @@ impl DispatcherManager {
         match client {
             Ok(client) => {
                 if let Some(client) = client {
+                    self.clear_controls_if_empty(&client.room_id);
+
                     let node_addr = client.clone().node_addr;
 
                     let server_addr = format!("{}:{}", node_addr, self.sfu_port);
@@ -515,4 +915,435 @@ impl DispatcherManager {
             Err(_) => Err(anyhow::anyhow!("Client not found!")),
         }
     }
+
+    pub async fn set_viewport(&self, req: SetViewportRequest) -> Result<(), anyhow::Error> {
+        let cache_key = CacheKey::new(req.client_id.clone());
+        let client = self.cache_manager.get(&cache_key);
+
+        match client {
+            Ok(Some(client)) => {
+                let server_addr = format!("{}:{}", client.node_addr, self.sfu_port);
+
+                self.sfu_grpc_client
+                    .set_viewport(server_addr, req)
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| {
+                        anyhow::anyhow!(
+                            "Failed to set viewport on node {}: {}",
+                            client.sfu_node_id,
+                            e
+                        )
+                    })
+            }
+            _ => Err(anyhow::anyhow!("Client not found!")),
+        }
+    }
+
+    /// Stops forwarding what the client publishes
+    pub async fn unpublish(&self, req: UnpublishRequest) -> Result<(), anyhow::Error> {
+        let cache_key = CacheKey::new(req.client_id.clone());
+        let client = self.cache_manager.get(&cache_key);
+
+        match client {
+            Ok(Some(client)) => {
+                let server_addr = format!("{}:{}", client.node_addr, self.sfu_port);
+
+                if let Err(e) = self.sfu_grpc_client.unpublish(server_addr, req).await {
+                    return Err(anyhow::anyhow!(
+                        "Failed to unpublish on node {}: {}",
+                        client.sfu_node_id,
+                        e
+                    ));
+                }
+
+                Ok(())
+            }
+            _ => Err(anyhow::anyhow!("Client not found!")),
+        }
+    }
+
+    /// Stage mode is room wide, so every node holding part of the room is
+    /// updated. Nodes the room comes to later get it with the join.
+    pub async fn set_stage_mode(&self, req: SetStageModeRequest) -> Result<(), anyhow::Error> {
+        self.room_control_store
+            .set_stage_mode(&req.room_id, req.is_enabled)
+            .map_err(|e| anyhow::anyhow!("Failed to store stage mode: {}", e))?;
+
+        let client = &self.sfu_grpc_client;
+        self.for_each_room_node(&req.room_id, "set stage mode", move |server_addr| {
+            client.set_stage_mode(server_addr, req.clone())
+        })
+        .await
+    }
+
+    pub async fn set_speaker(&self, req: SetSpeakerRequest) -> Result<(), anyhow::Error> {
+        self.room_control_store
+            .set_speaker(&req.room_id, &req.participant_id, req.is_speaker)
+            .map_err(|e| anyhow::anyhow!("Failed to store speaker: {}", e))?;
+
+        let client = &self.sfu_grpc_client;
+        self.for_each_room_node(&req.room_id, "set speaker", move |server_addr| {
+            client.set_speaker(server_addr, req.clone())
+        })
+        .await
+    }
+
+    /// Call every node the room's clients are routed to. A node that fails
+    /// doesn't keep the others from being called, the first error is returned
+    /// once they all were.
+    async fn for_each_room_node<T, F>(
+        &self,
+        room_id: &str,
+        action: &str,
+        call: impl Fn(String) -> F,
+    ) -> Result<(), anyhow::Error>
+    where
+        F: Future<Output = Result<T, tonic::Status>>,
+    {
+        let mut result = Ok(());
+
+        for (node_id, server_addr) in self.get_room_nodes(room_id)? {
+            if let Err(e) = call(server_addr).await {
+                warn!("Failed to {} on node {}: {}", action, node_id, e);
+
+                if result.is_ok() {
+                    result = Err(anyhow::anyhow!(
+                        "Failed to {} on node {}: {}",
+                        action,
+                        node_id,
+                        e
+                    ));
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Every node the room's clients are routed to, with its gRPC address
+    fn get_room_nodes(&self, room_id: &str) -> Result<Vec<(String, String)>, anyhow::Error> {
+        let clients = self
+            .cache_manager
+            .get_by_room_id(room_id)
+            .map_err(|e| anyhow::anyhow!("Failed to get clients of room {}: {}", room_id, e))?;
+
+        let mut nodes = vec![];
+        for (_, client) in clients {
+            if nodes
+                .iter()
+                .any(|(node_id, _)| node_id == &client.sfu_node_id)
+            {
+                continue;
+            }
+
+            let server_addr = format!("{}:{}", client.node_addr, self.sfu_port);
+            nodes.push((client.sfu_node_id, server_addr));
+        }
+
+        Ok(nodes)
+    }
+
+    fn clear_controls_if_empty(&self, room_id: &str) {
+        let is_empty = self
+            .cache_manager
+            .get_by_room_id(room_id)
+            .map(|clients| clients.is_empty())
+            .unwrap_or_default();
+
+        if is_empty && let Err(e) = self.room_control_store.clear(room_id) {
+            warn!("Failed to clear controls of room {}: {}", room_id, e);
+        }
+    }
+
+    pub async fn set_qos_class(&self, req: SetQosClassRequest) -> Result<(), anyhow::Error> {
+        let nodes = {
+            let etcd_reader = self.etcd_dispatcher.read().await;
+            etcd_reader.get_nodes()
+        };
+
+        for (node_id, metadata) in nodes {
+            let server_addr = format!("{}:{}", metadata.addr, self.sfu_port);
+
+            if let Err(e) = self
+                .sfu_grpc_client
+                .set_qos_class(server_addr, req.clone())
+                .await
+            {
+                return Err(anyhow::anyhow!(
+                    "Failed to set QoS class on node {}: {}",
+                    node_id,
+                    e
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Kept with the room's controls, so a node the room comes to later
+    /// doesn't start recording it
+    pub async fn set_recording_paused(
+        &self,
+        req: SetRecordingPausedRequest,
+    ) -> Result<(), anyhow::Error> {
+        self.room_control_store
+            .set_recording_paused(&req.room_id, req.is_paused)
+            .map_err(|e| anyhow::anyhow!("Failed to store recording pause: {}", e))?;
+
+        let client = &self.sfu_grpc_client;
+        self.for_each_room_node(&req.room_id, "set recording paused", move |server_addr| {
+            client.set_recording_paused(server_addr, req.clone())
+        })
+        .await
+    }
+
+    /// Every node holding a publisher of the room drops its delay buffer. The
+    /// cut is kept with the room's controls for nodes the room comes to later.
+    pub async fn set_broadcast_cut(
+        &self,
+        req: SetBroadcastCutRequest,
+    ) -> Result<(), anyhow::Error> {
+        self.room_control_store
+            .set_broadcast_cut(&req.room_id, req.is_cut)
+            .map_err(|e| anyhow::anyhow!("Failed to store broadcast cut: {}", e))?;
+
+        let client = &self.sfu_grpc_client;
+        self.for_each_room_node(&req.room_id, "set broadcast cut", move |server_addr| {
+            client.set_broadcast_cut(server_addr, req.clone())
+        })
+        .await
+    }
+
+    /// Every node holding a publisher of the room starts or ends its HLS stream,
+    /// for a room switched to or from a live stream mid-session
+    pub async fn set_egress(&self, req: SetEgressRequest) -> Result<(), anyhow::Error> {
+        let nodes = {
+            let etcd_reader = self.etcd_dispatcher.read().await;
+            etcd_reader.get_nodes()
+        };
+
+        for (node_id, metadata) in nodes {
+            let server_addr = format!("{}:{}", metadata.addr, self.sfu_port);
+
+            if let Err(e) = self
+                .sfu_grpc_client
+                .set_egress(server_addr, req.clone())
+                .await
+            {
+                return Err(anyhow::anyhow!(
+                    "Failed to set egress on node {}: {}",
+                    node_id,
+                    e
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The slate runs on a single node, the least loaded one like a new publisher
+    pub async fn start_slate(&self, req: StartSlateRequest) -> Result<(), anyhow::Error> {
+        let cohort = self.resolve_cohort(&req.room_id, None);
+        let drained = self.get_drained_nodes();
+
+        let node = {
+            let etcd_reader = self.etcd_dispatcher.read().await;
+            etcd_reader.get_node_least(cohort, &drained)
+        };
+
+        let Some((node_id, metadata)) = node else {
+            return Err(anyhow::anyhow!("No available SFU node found!"));
+        };
+
+        let server_addr = format!("{}:{}", metadata.addr, self.sfu_port);
+
+        self.sfu_grpc_client
+            .start_slate(server_addr, req)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to start slate on node {}: {}", node_id, e))?;
+
+        Ok(())
+    }
+
+    /// Which node runs the slate isn't tracked, every node is asked to stop it
+    pub async fn stop_slate(&self, req: StopSlateRequest) -> Result<(), anyhow::Error> {
+        let nodes = {
+            let etcd_reader = self.etcd_dispatcher.read().await;
+            etcd_reader.get_nodes()
+        };
+
+        for (node_id, metadata) in nodes {
+            let server_addr = format!("{}:{}", metadata.addr, self.sfu_port);
+
+            if let Err(e) = self
+                .sfu_grpc_client
+                .stop_slate(server_addr, req.clone())
+                .await
+            {
+                return Err(anyhow::anyhow!(
+                    "Failed to stop slate on node {}: {}",
+                    node_id,
+                    e
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Track archives stay on the node that recorded them, every node is asked.
+    /// Returns the manifests with the node holding them, and the nodes that couldn't be read.
+    pub async fn get_recording_manifests(
+        &self,
+        req: GetRecordingManifestsRequest,
+    ) -> (Vec<(String, RecordingManifest)>, Vec<String>) {
+        let nodes = {
+            let etcd_reader = self.etcd_dispatcher.read().await;
+            etcd_reader.get_nodes()
+        };
+
+        let mut manifests = vec![];
+        let mut unreachable = vec![];
+
+        for (node_id, metadata) in nodes {
+            let server_addr = format!("{}:{}", metadata.addr, self.sfu_port);
+
+            match self
+                .sfu_grpc_client
+                .get_recording_manifests(server_addr, req.clone())
+                .await
+            {
+                Ok(response) => manifests.extend(
+                    response
+                        .into_inner()
+                        .manifests
+                        .into_iter()
+                        .map(|manifest| (node_id.clone(), manifest)),
+                ),
+                Err(e) => {
+                    warn!(
+                        "Failed to get recording manifests from node {}: {}",
+                        node_id, e
+                    );
+                    unreachable.push(node_id);
+                }
+            }
+        }
+
+        (manifests, unreachable)
+    }
+
+    /// Object and task counts of every node, with the nodes that couldn't be read
+    pub async fn get_node_diagnostics(
+        &self,
+    ) -> (Vec<(String, GetDiagnosticsResponse)>, Vec<String>) {
+        let nodes = {
+            let etcd_reader = self.etcd_dispatcher.read().await;
+            etcd_reader.get_nodes()
+        };
+
+        let mut diagnostics = vec![];
+        let mut unreachable = vec![];
+
+        for (node_id, metadata) in nodes {
+            let server_addr = format!("{}:{}", metadata.addr, self.sfu_port);
+
+            match self.sfu_grpc_client.get_diagnostics(server_addr).await {
+                Ok(response) => diagnostics.push((node_id, response.into_inner())),
+                Err(e) => {
+                    warn!("Failed to get diagnostics from node {}: {}", node_id, e);
+                    unreachable.push(node_id);
+                }
+            }
+        }
+
+        (diagnostics, unreachable)
+    }
+
+    /// The room merged from every node holding part of it, with where each
+    /// publisher is placed
+    pub async fn get_room_stats(&self, room_id: &str) -> RoomStatsView {
+        let nodes = {
+            let etcd_reader = self.etcd_dispatcher.read().await;
+            etcd_reader.get_nodes()
+        };
+
+        let mut shares = vec![];
+        let mut unreachable = vec![];
+
+        for (node_id, metadata) in nodes {
+            let server_addr = format!("{}:{}", metadata.addr, self.sfu_port);
+
+            match self
+                .sfu_grpc_client
+                .get_room_stats(
+                    server_addr,
+                    GetRoomStatsRequest {
+                        room_id: room_id.to_string(),
+                    },
+                )
+                .await
+            {
+                Ok(response) => shares.push((node_id, response.into_inner())),
+                Err(e) => {
+                    warn!("Failed to get room stats from node {}: {}", node_id, e);
+                    unreachable.push(node_id);
+                }
+            }
+        }
+
+        RoomStatsView::merge(room_id, shares, unreachable)
+    }
+}
+
+/// The routing entries of clients a node doesn't hold anymore
+fn stale_clients(
+    clients: Vec<(CacheKey, ClientMetadata)>,
+    participant_ids: &[String],
+) -> Vec<CacheKey> {
+    clients
+        .into_iter()
+        .filter(|(_, client)| !participant_ids.contains(&client.participant_id))
+        .map(|(key, _)| key)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(participant_id: &str) -> (CacheKey, ClientMetadata) {
+        (
+            CacheKey::new(format!("sid-{participant_id}")),
+            ClientMetadata {
+                room_id: "room".to_owned(),
+                participant_id: participant_id.to_owned(),
+                sfu_node_id: "node1".to_owned(),
+                node_addr: "node1.local".to_owned(),
+            },
+        )
+    }
+
+    #[test]
+    fn test_stale_clients_are_the_ones_the_node_lost() {
+        let clients = vec![client("p1"), client("p2"), client("p3")];
+
+        let stale = stale_clients(clients, &["p2".to_owned()]);
+
+        assert_eq!(
+            stale,
+            vec![
+                CacheKey::new("sid-p1".to_owned()),
+                CacheKey::new("sid-p3".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_node_without_the_room_holds_none_of_its_clients() {
+        let stale = stale_clients(vec![client("p1")], &[]);
+
+        assert_eq!(stale, vec![CacheKey::new("sid-p1".to_owned())]);
+    }
 }
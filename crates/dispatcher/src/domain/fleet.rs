@@ -0,0 +1,52 @@
+use std::collections::BTreeMap;
+
+use crate::{domain::rollout::RoomCohort, infrastructure::cache::cache_manager::ClientMetadata};
+
+/// An SFU node of this group as operators see it
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeView {
+    pub node_id: String,
+    pub addr: String,
+    pub cohort: RoomCohort,
+    pub tags: Vec<String>,
+    /// CPU and RAM usage in percent, as last reported to etcd
+    pub cpu: f32,
+    pub ram: f32,
+    /// Clients routed to the node
+    pub clients: usize,
+    pub is_drained: bool,
+}
+
+/// A room with clients in a call right now
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LiveRoomView {
+    pub room_id: String,
+    pub clients: usize,
+    pub node_ids: Vec<String>,
+}
+
+impl LiveRoomView {
+    /// Rooms of the routed clients, busiest first
+    pub fn group<'a>(clients: impl IntoIterator<Item = &'a ClientMetadata>) -> Vec<Self> {
+        let mut rooms: BTreeMap<&str, LiveRoomView> = BTreeMap::new();
+
+        for client in clients {
+            let room = rooms
+                .entry(client.room_id.as_str())
+                .or_insert_with(|| LiveRoomView {
+                    room_id: client.room_id.clone(),
+                    ..Default::default()
+                });
+
+            room.clients += 1;
+            if !room.node_ids.contains(&client.sfu_node_id) {
+                room.node_ids.push(client.sfu_node_id.clone());
+            }
+        }
+
+        let mut rooms: Vec<_> = rooms.into_values().collect();
+        rooms.sort_by(|a, b| b.clients.cmp(&a.clients));
+
+        rooms
+    }
+}
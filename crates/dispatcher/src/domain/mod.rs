@@ -1,12 +1,97 @@
+pub mod fleet;
+pub mod rollout;
+pub mod room_stats;
+
+use prost::Message;
 use waterbus_proto::{
     NewUserJoinedRequest, PublisherCandidateRequest, SubscriberCandidateRequest,
-    SubscriberRenegotiateRequest,
+    SubscriberDownlinkStarvedRequest, SubscriberRenegotiateRequest,
 };
 
+use crate::infrastructure::event_queue::{Overflow, QueuedEvent};
+
+#[derive(Debug, Clone)]
 pub enum DispatcherCallback {
     NewUserJoined(NewUserJoinedRequest),
     SubscriberRenegotiate(SubscriberRenegotiateRequest),
     PublisherCandidate(PublisherCandidateRequest),
     SubscriberCandidate(SubscriberCandidateRequest),
+    SubscriberDownlinkStarved(SubscriberDownlinkStarvedRequest),
     NodeTerminated(String),
 }
+
+impl DispatcherCallback {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DispatcherCallback::NewUserJoined(_) => "new_user_joined",
+            DispatcherCallback::SubscriberRenegotiate(_) => "subscriber_renegotiate",
+            DispatcherCallback::PublisherCandidate(_) => "publisher_candidate",
+            DispatcherCallback::SubscriberCandidate(_) => "subscriber_candidate",
+            DispatcherCallback::SubscriberDownlinkStarved(_) => "subscriber_downlink_starved",
+            DispatcherCallback::NodeTerminated(_) => "node_terminated",
+        }
+    }
+
+    pub fn client_id(&self) -> Option<&str> {
+        match self {
+            DispatcherCallback::NewUserJoined(req) => Some(&req.client_id),
+            DispatcherCallback::SubscriberRenegotiate(req) => Some(&req.client_id),
+            DispatcherCallback::PublisherCandidate(req) => Some(&req.client_id),
+            DispatcherCallback::SubscriberCandidate(req) => Some(&req.client_id),
+            DispatcherCallback::SubscriberDownlinkStarved(req) => Some(&req.client_id),
+            DispatcherCallback::NodeTerminated(_) => None,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            DispatcherCallback::NewUserJoined(req) => req.encode_to_vec(),
+            DispatcherCallback::SubscriberRenegotiate(req) => req.encode_to_vec(),
+            DispatcherCallback::PublisherCandidate(req) => req.encode_to_vec(),
+            DispatcherCallback::SubscriberCandidate(req) => req.encode_to_vec(),
+            DispatcherCallback::SubscriberDownlinkStarved(req) => req.encode_to_vec(),
+            DispatcherCallback::NodeTerminated(node_id) => node_id.as_bytes().to_vec(),
+        }
+    }
+
+    pub fn decode(kind: &str, payload: &[u8]) -> Option<Self> {
+        let callback = match kind {
+            "new_user_joined" => {
+                DispatcherCallback::NewUserJoined(NewUserJoinedRequest::decode(payload).ok()?)
+            }
+            "subscriber_renegotiate" => DispatcherCallback::SubscriberRenegotiate(
+                SubscriberRenegotiateRequest::decode(payload).ok()?,
+            ),
+            "publisher_candidate" => DispatcherCallback::PublisherCandidate(
+                PublisherCandidateRequest::decode(payload).ok()?,
+            ),
+            "subscriber_candidate" => DispatcherCallback::SubscriberCandidate(
+                SubscriberCandidateRequest::decode(payload).ok()?,
+            ),
+            "subscriber_downlink_starved" => DispatcherCallback::SubscriberDownlinkStarved(
+                SubscriberDownlinkStarvedRequest::decode(payload).ok()?,
+            ),
+            "node_terminated" => {
+                DispatcherCallback::NodeTerminated(String::from_utf8(payload.to_vec()).ok()?)
+            }
+            _ => return None,
+        };
+
+        Some(callback)
+    }
+}
+
+impl QueuedEvent for DispatcherCallback {
+    fn kind(&self) -> &'static str {
+        DispatcherCallback::kind(self)
+    }
+
+    /// A starved downlink is reported again on the next probe, everything
+    /// else is signalling a client cannot recover without
+    fn overflow(&self) -> Overflow {
+        match self {
+            DispatcherCallback::SubscriberDownlinkStarved(_) => Overflow::DropOldest,
+            _ => Overflow::Block,
+        }
+    }
+}
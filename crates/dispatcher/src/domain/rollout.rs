@@ -0,0 +1,238 @@
+use serde::{Deserialize, Serialize};
+
+/// Tag an SFU node carries in its etcd metadata to take canary traffic
+pub const CANARY_TAG: &str = "canary";
+
+/// Which rooms go to the canary nodes.
+///
+/// A room is canary when it falls into the first `percentage` of buckets, or
+/// when the client opening it matches one of `client_versions`. The decision
+/// is made when the first participant joins and sticks for the room's lifetime,
+/// so everyone in a room lands on the same build.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RolloutConfig {
+    /// 0 to 100
+    pub percentage: u8,
+    /// `2.5.0` matches that version, `2.5.*` any patch of it and `>=2.5.0`
+    /// that version and anything newer
+    #[serde(default)]
+    pub client_versions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoomCohort {
+    Stable,
+    Canary,
+}
+
+impl RoomCohort {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RoomCohort::Stable => "stable",
+            RoomCohort::Canary => "canary",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "stable" => Some(RoomCohort::Stable),
+            "canary" => Some(RoomCohort::Canary),
+            _ => None,
+        }
+    }
+}
+
+impl RolloutConfig {
+    /// Predicates that can't be parsed are reported back instead of never matching
+    pub fn validate(&self) -> Result<(), String> {
+        if self.percentage > 100 {
+            return Err(format!("percentage {} is over 100", self.percentage));
+        }
+
+        for predicate in &self.client_versions {
+            if VersionPredicate::parse(predicate).is_none() {
+                return Err(format!("invalid client version predicate {predicate:?}"));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn cohort_for(&self, room_id: &str, client_version: Option<&str>) -> RoomCohort {
+        let in_percentage = room_bucket(room_id) < self.percentage as u64;
+
+        let matches_version = client_version.is_some_and(|version| {
+            self.client_versions
+                .iter()
+                .filter_map(|predicate| VersionPredicate::parse(predicate))
+                .any(|predicate| predicate.matches(version))
+        });
+
+        if in_percentage || matches_version {
+            RoomCohort::Canary
+        } else {
+            RoomCohort::Stable
+        }
+    }
+}
+
+enum VersionPredicate {
+    Exact(Vec<u64>),
+    Prefix(Vec<u64>),
+    AtLeast(Vec<u64>),
+}
+
+impl VersionPredicate {
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+
+        if let Some(version) = value.strip_prefix(">=") {
+            return parse_version(version.trim()).map(VersionPredicate::AtLeast);
+        }
+
+        if let Some(prefix) = value.strip_suffix(".*") {
+            return parse_version(prefix).map(VersionPredicate::Prefix);
+        }
+
+        parse_version(value).map(VersionPredicate::Exact)
+    }
+
+    fn matches(&self, version: &str) -> bool {
+        let Some(version) = parse_version(version) else {
+            return false;
+        };
+
+        match self {
+            VersionPredicate::Exact(expected) => trim_zeros(&version) == trim_zeros(expected),
+            VersionPredicate::Prefix(prefix) => version.starts_with(prefix),
+            VersionPredicate::AtLeast(minimum) => {
+                let len = version.len().max(minimum.len());
+                let pad = |parts: &[u64]| {
+                    let mut parts = parts.to_vec();
+                    parts.resize(len, 0);
+                    parts
+                };
+                pad(&version) >= pad(minimum)
+            }
+        }
+    }
+}
+
+/// Dotted numeric versions, a `v` prefix and any `-beta`/`+build` suffix are ignored
+fn parse_version(value: &str) -> Option<Vec<u64>> {
+    let value = value.strip_prefix('v').unwrap_or(value);
+    let value = value.split(['-', '+']).next()?;
+
+    value.split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// `2.5` and `2.5.0` are the same version
+fn trim_zeros(parts: &[u64]) -> &[u64] {
+    let len = parts
+        .iter()
+        .rposition(|part| *part != 0)
+        .map_or(0, |i| i + 1);
+    &parts[..len]
+}
+
+/// Stable across processes and releases, unlike `DefaultHasher`, so every
+/// signalling node puts a room in the same bucket
+fn room_bucket(room_id: &str) -> u64 {
+    const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    let hash = room_id.bytes().fold(FNV_OFFSET, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    });
+
+    hash % 100
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(percentage: u8, client_versions: &[&str]) -> RolloutConfig {
+        RolloutConfig {
+            percentage,
+            client_versions: client_versions.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+
+    fn matches(predicate: &str, version: &str) -> bool {
+        VersionPredicate::parse(predicate).unwrap().matches(version)
+    }
+
+    #[test]
+    fn test_exact_version() {
+        assert!(matches("2.5.0", "2.5.0"));
+        assert!(matches("2.5.0", "2.5"));
+        assert!(matches("2.5.0", "v2.5.0-beta.1"));
+        assert!(!matches("2.5.0", "2.5.1"));
+    }
+
+    #[test]
+    fn test_prefix_version() {
+        assert!(matches("2.5.*", "2.5.3"));
+        assert!(matches("2.5.*", "2.5"));
+        assert!(!matches("2.5.*", "2.50.1"));
+        assert!(!matches("2.5.*", "2.6.0"));
+    }
+
+    #[test]
+    fn test_at_least_version() {
+        assert!(matches(">=2.5.0", "2.5"));
+        assert!(matches(">= 2.5.0", "2.10.0"));
+        assert!(matches(">=2.5", "3.0.0+build7"));
+        assert!(!matches(">=2.5.0", "2.4.9"));
+    }
+
+    #[test]
+    fn test_unparsable_versions() {
+        assert!(VersionPredicate::parse("latest").is_none());
+        assert!(VersionPredicate::parse(">=").is_none());
+        assert!(!matches("2.5.0", "unknown"));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(config(10, &["2.5.*", ">=3.0"]).validate().is_ok());
+        assert!(config(101, &[]).validate().is_err());
+        assert!(config(10, &["2.x"]).validate().is_err());
+    }
+
+    #[test]
+    fn test_percentage_bounds() {
+        for room_id in ["room1", "room2", "room3"] {
+            assert_eq!(config(0, &[]).cohort_for(room_id, None), RoomCohort::Stable);
+            assert_eq!(
+                config(100, &[]).cohort_for(room_id, None),
+                RoomCohort::Canary
+            );
+        }
+    }
+
+    #[test]
+    fn test_matching_client_version_is_canary() {
+        let config = config(0, &[">=2.5.0"]);
+
+        assert_eq!(config.cohort_for("room", Some("2.6.0")), RoomCohort::Canary);
+        assert_eq!(config.cohort_for("room", Some("2.4.0")), RoomCohort::Stable);
+        assert_eq!(config.cohort_for("room", None), RoomCohort::Stable);
+    }
+
+    #[test]
+    fn test_room_bucket_is_stable() {
+        // FNV-1a of the empty string and of "a"
+        assert_eq!(room_bucket(""), 0xcbf29ce484222325 % 100);
+        assert_eq!(room_bucket("a"), 0xaf63dc4c8601ec8c % 100);
+    }
+
+    #[test]
+    fn test_cohort_round_trips_through_str() {
+        for cohort in [RoomCohort::Stable, RoomCohort::Canary] {
+            assert_eq!(RoomCohort::parse(cohort.as_str()), Some(cohort));
+        }
+        assert_eq!(RoomCohort::parse("beta"), None);
+    }
+}
@@ -0,0 +1,189 @@
+use waterbus_proto::GetRoomStatsResponse;
+
+/// A publisher of the room and the nodes it's on. More than one while it's
+/// migrating, or when a room spans nodes and its streams are relayed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParticipantPlacement {
+    pub participant_id: String,
+    pub node_ids: Vec<String>,
+    pub tracks: u64,
+    pub subscribers: u64,
+    pub egress_bps: u64,
+    pub talk_time_ms: u64,
+}
+
+/// What one node holds of the room
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeShare {
+    pub node_id: String,
+    pub publishers: u64,
+    pub subscribers: u64,
+    pub egress_bps: u64,
+}
+
+/// A room as one logical call, merged from the share every node holds of it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RoomStatsView {
+    pub room_id: String,
+    pub participants: Vec<ParticipantPlacement>,
+    /// Only the nodes holding part of the room
+    pub nodes: Vec<NodeShare>,
+    pub subscribers: u64,
+    pub egress_bps: u64,
+    /// Whether any node records the room, or sends it to HLS
+    pub is_recording: bool,
+    pub is_recording_paused: bool,
+    /// Nodes that couldn't be asked, the view may be missing their share
+    pub unreachable_nodes: Vec<String>,
+}
+
+impl RoomStatsView {
+    pub fn merge(
+        room_id: &str,
+        shares: Vec<(String, GetRoomStatsResponse)>,
+        unreachable_nodes: Vec<String>,
+    ) -> Self {
+        let mut view = RoomStatsView {
+            room_id: room_id.to_string(),
+            unreachable_nodes,
+            ..Default::default()
+        };
+
+        for (node_id, share) in shares {
+            if share.publishers.is_empty() && share.subscribers == 0 {
+                continue;
+            }
+
+            view.nodes.push(NodeShare {
+                node_id: node_id.clone(),
+                publishers: share.publishers.len() as u64,
+                subscribers: share.subscribers,
+                egress_bps: share.egress_bps,
+            });
+            view.subscribers += share.subscribers;
+            view.egress_bps += share.egress_bps;
+            view.is_recording |= share.is_recording;
+            view.is_recording_paused |= share.is_recording_paused;
+
+            for publisher in share.publishers {
+                let placement = match view
+                    .participants
+                    .iter_mut()
+                    .find(|placement| placement.participant_id == publisher.participant_id)
+                {
+                    Some(placement) => placement,
+                    None => {
+                        view.participants.push(ParticipantPlacement {
+                            participant_id: publisher.participant_id,
+                            ..Default::default()
+                        });
+                        view.participants.last_mut().unwrap()
+                    }
+                };
+
+                placement.node_ids.push(node_id.clone());
+                // Tracks are the same ones wherever the publisher shows up
+                placement.tracks = placement.tracks.max(publisher.tracks);
+                placement.subscribers += publisher.subscribers;
+                placement.egress_bps += publisher.egress_bps;
+                // Only the node the publisher sends to hears it, relays add nothing
+                placement.talk_time_ms = placement.talk_time_ms.max(publisher.talk_time_ms);
+            }
+        }
+
+        view
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use waterbus_proto::PublisherStats;
+
+    use super::*;
+
+    fn publisher(participant_id: &str, subscribers: u64, talk_time_ms: u64) -> PublisherStats {
+        PublisherStats {
+            participant_id: participant_id.to_string(),
+            tracks: 2,
+            subscribers,
+            egress_bps: subscribers * 1_000,
+            talk_time_ms,
+        }
+    }
+
+    fn share(publishers: Vec<PublisherStats>, subscribers: u64) -> GetRoomStatsResponse {
+        GetRoomStatsResponse {
+            egress_bps: publishers.iter().map(|p| p.egress_bps).sum(),
+            publishers,
+            subscribers,
+            is_recording: false,
+            is_recording_paused: false,
+            participant_ids: vec![],
+        }
+    }
+
+    #[test]
+    fn test_relayed_publisher_is_one_participant() {
+        let view = RoomStatsView::merge(
+            "room",
+            vec![
+                (
+                    "node1".to_string(),
+                    share(vec![publisher("p1", 3, 5_000)], 3),
+                ),
+                ("node2".to_string(), share(vec![publisher("p1", 2, 0)], 2)),
+            ],
+            vec![],
+        );
+
+        assert_eq!(
+            view.participants,
+            [ParticipantPlacement {
+                participant_id: "p1".to_string(),
+                node_ids: vec!["node1".to_string(), "node2".to_string()],
+                tracks: 2,
+                subscribers: 5,
+                egress_bps: 5_000,
+                talk_time_ms: 5_000,
+            }]
+        );
+        assert_eq!(view.subscribers, 5);
+        assert_eq!(view.egress_bps, 5_000);
+        assert_eq!(view.nodes.len(), 2);
+    }
+
+    #[test]
+    fn test_nodes_without_the_room_are_left_out() {
+        let view = RoomStatsView::merge(
+            "room",
+            vec![
+                ("node1".to_string(), share(vec![publisher("p1", 1, 0)], 1)),
+                ("node2".to_string(), share(vec![], 0)),
+            ],
+            vec!["node3".to_string()],
+        );
+
+        let node_ids: Vec<&str> = view.nodes.iter().map(|n| n.node_id.as_str()).collect();
+        assert_eq!(node_ids, ["node1"]);
+        assert_eq!(view.unreachable_nodes, ["node3"]);
+    }
+
+    #[test]
+    fn test_recording_on_any_node_marks_the_room() {
+        let mut recording = share(vec![publisher("p2", 0, 0)], 0);
+        recording.is_recording = true;
+
+        let view = RoomStatsView::merge(
+            "room",
+            vec![
+                ("node1".to_string(), share(vec![publisher("p1", 1, 0)], 1)),
+                ("node2".to_string(), recording),
+            ],
+            vec![],
+        );
+
+        assert!(view.is_recording);
+        assert!(!view.is_recording_paused);
+        assert_eq!(view.participants.len(), 2);
+    }
+}
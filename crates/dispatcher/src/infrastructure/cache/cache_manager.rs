@@ -1,7 +1,13 @@
-use redis::{Commands, cluster::ClusterClient};
+use redis::{Commands, Expiry, RedisResult, cluster::ClusterClient};
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
 
+/// Routing entries outlive a dispatcher restart but not an abandoned client.
+/// Writes refresh every key of a client, a read the key it reads.
+const ROUTING_TTL_SECS: u64 = 60 * 60 * 12;
+
+const NODES_KEY: &str = "sfu_nodes";
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ClientMetadata {
     pub room_id: String,
@@ -21,21 +27,95 @@ impl CacheKey {
     }
 }
 
+/// The Redis commands routing is kept with, so the bookkeeping can be tested
+/// without a cluster
+trait RoutingConnection {
+    /// Reads the key and pushes its expiry forward in one command
+    fn get_ex(&mut self, key: &str, ttl_secs: u64) -> RedisResult<Option<String>>;
+
+    fn set_ex(&mut self, key: &str, value: &str, ttl_secs: u64) -> RedisResult<()>;
+
+    fn del(&mut self, key: &str) -> RedisResult<()>;
+
+    fn exists(&mut self, key: &str) -> RedisResult<bool>;
+
+    fn expire(&mut self, key: &str, ttl_secs: u64) -> RedisResult<()>;
+
+    fn sadd(&mut self, key: &str, member: &str) -> RedisResult<()>;
+
+    /// Redis drops a set along with its last member
+    fn srem(&mut self, key: &str, member: &str) -> RedisResult<()>;
+
+    fn smembers(&mut self, key: &str) -> RedisResult<Vec<String>>;
+}
+
+impl RoutingConnection for redis::cluster::ClusterConnection {
+    fn get_ex(&mut self, key: &str, ttl_secs: u64) -> RedisResult<Option<String>> {
+        Commands::get_ex(self, key, Expiry::EX(ttl_secs))
+    }
+
+    fn set_ex(&mut self, key: &str, value: &str, ttl_secs: u64) -> RedisResult<()> {
+        Commands::set_ex(self, key, value, ttl_secs)
+    }
+
+    fn del(&mut self, key: &str) -> RedisResult<()> {
+        Commands::del(self, key)
+    }
+
+    fn exists(&mut self, key: &str) -> RedisResult<bool> {
+        Commands::exists(self, key)
+    }
+
+    fn expire(&mut self, key: &str, ttl_secs: u64) -> RedisResult<()> {
+        Commands::expire(self, key, ttl_secs as i64)
+    }
+
+    fn sadd(&mut self, key: &str, member: &str) -> RedisResult<()> {
+        Commands::sadd(self, key, member)
+    }
+
+    fn srem(&mut self, key: &str, member: &str) -> RedisResult<()> {
+        Commands::srem(self, key, member)
+    }
+
+    fn smembers(&mut self, key: &str) -> RedisResult<Vec<String>> {
+        Commands::smembers(self, key)
+    }
+}
+
+trait RoutingStore: Send + Sync {
+    fn connect(&self) -> RedisResult<Box<dyn RoutingConnection>>;
+}
+
+struct RedisRoutingStore {
+    client: Mutex<ClusterClient>,
+}
+
+impl RoutingStore for RedisRoutingStore {
+    fn connect(&self) -> RedisResult<Box<dyn RoutingConnection>> {
+        Ok(Box::new(self.client.lock().unwrap().get_connection()?))
+    }
+}
+
 #[derive(Clone)]
 pub struct CacheManager {
-    client: Arc<Mutex<ClusterClient>>,
+    store: Arc<dyn RoutingStore>,
 }
 
 impl CacheManager {
     pub fn new(urls: Vec<String>) -> Self {
         let client = ClusterClient::new(urls).unwrap();
         Self {
-            client: Arc::new(Mutex::new(client)),
+            store: Arc::new(RedisRoutingStore {
+                client: Mutex::new(client),
+            }),
         }
     }
 
     pub fn insert(&self, key: CacheKey, value: &ClientMetadata) -> Result<(), redis::RedisError> {
-        let mut conn = self.client.lock().unwrap().get_connection()?;
+        let mut conn = self.store.connect()?;
+        let previous = Self::_get(conn.as_mut(), &key)?;
+
         let serialized_value = serde_json::to_string(value).map_err(|e| {
             redis::RedisError::from((
                 redis::ErrorKind::TypeError,
@@ -44,17 +124,145 @@ impl CacheManager {
             ))
         })?;
 
-        let _: () = conn.set(key.clone().key, serialized_value)?;
+        conn.set_ex(&key.key, &serialized_value, ROUTING_TTL_SECS)?;
+        conn.set_ex(
+            &Self::participant_key(&value.participant_id),
+            &key.key,
+            ROUTING_TTL_SECS,
+        )?;
 
-        let secondary_key = format!("participant_id:{}", value.participant_id);
-        let _: () = conn.set(secondary_key, &key.key)?;
+        if let Some(previous) = &previous
+            && previous.sfu_node_id != value.sfu_node_id
+        {
+            Self::_untrack(conn.as_mut(), &previous.sfu_node_id, &key.key)?;
+        }
+
+        if let Some(previous) = &previous
+            && previous.room_id != value.room_id
+        {
+            conn.srem(&Self::room_key(&previous.room_id), &key.key)?;
+        }
+
+        let node_key = Self::node_key(&value.sfu_node_id);
+        conn.sadd(&node_key, &key.key)?;
+        conn.expire(&node_key, ROUTING_TTL_SECS)?;
+        conn.sadd(NODES_KEY, &value.sfu_node_id)?;
+
+        let room_key = Self::room_key(&value.room_id);
+        conn.sadd(&room_key, &key.key)?;
+        conn.expire(&room_key, ROUTING_TTL_SECS)?;
 
         Ok(())
     }
 
     pub fn get(&self, key: &CacheKey) -> Result<Option<ClientMetadata>, redis::RedisError> {
-        let mut conn = self.client.lock().unwrap().get_connection()?;
-        let result: Option<String> = conn.get(&key.key)?;
+        let mut conn = self.store.connect()?;
+        Self::_get(conn.as_mut(), key)
+    }
+
+    pub fn get_by_participant_id(
+        &self,
+        participant_id: &str,
+    ) -> Result<Option<ClientMetadata>, redis::RedisError> {
+        let mut conn = self.store.connect()?;
+        let key = conn.get_ex(&Self::participant_key(participant_id), ROUTING_TTL_SECS)?;
+        match key {
+            Some(actual_key) => Self::_get(conn.as_mut(), &CacheKey::new(actual_key)),
+            None => Ok(None),
+        }
+    }
+
+    /// Return every client currently routed to the given SFU node. Entries of
+    /// clients that moved on or expired are dropped from the node's index, a
+    /// node left without any is forgotten until a client is routed to it again.
+    pub fn get_by_node_id(
+        &self,
+        node_id: &str,
+    ) -> Result<Vec<(CacheKey, ClientMetadata)>, redis::RedisError> {
+        let mut conn = self.store.connect()?;
+        let keys = conn.smembers(&Self::node_key(node_id))?;
+
+        let mut clients = Vec::with_capacity(keys.len());
+
+        for key in keys {
+            let key = CacheKey::new(key);
+
+            match Self::_get(conn.as_mut(), &key)? {
+                Some(meta) if meta.sfu_node_id == node_id => clients.push((key, meta)),
+                _ => conn.srem(&Self::node_key(node_id), &key.key)?,
+            }
+        }
+
+        if clients.is_empty() {
+            conn.srem(NODES_KEY, node_id)?;
+        }
+
+        Ok(clients)
+    }
+
+    /// Return every client of the room, on whichever node it is routed to.
+    /// Entries of clients that left or expired are dropped from the room's index.
+    pub fn get_by_room_id(
+        &self,
+        room_id: &str,
+    ) -> Result<Vec<(CacheKey, ClientMetadata)>, redis::RedisError> {
+        let mut conn = self.store.connect()?;
+        let keys = conn.smembers(&Self::room_key(room_id))?;
+
+        let mut clients = Vec::with_capacity(keys.len());
+
+        for key in keys {
+            let key = CacheKey::new(key);
+
+            match Self::_get(conn.as_mut(), &key)? {
+                Some(meta) if meta.room_id == room_id => clients.push((key, meta)),
+                _ => conn.srem(&Self::room_key(room_id), &key.key)?,
+            }
+        }
+
+        Ok(clients)
+    }
+
+    /// Return every SFU node that has had clients routed to it
+    pub fn get_node_ids(&self) -> Result<Vec<String>, redis::RedisError> {
+        let mut conn = self.store.connect()?;
+        conn.smembers(NODES_KEY)
+    }
+
+    /// Forget a node along with its client index, the client entries themselves
+    /// are removed when the clients leave or expire
+    pub fn remove_node(&self, node_id: &str) -> Result<(), redis::RedisError> {
+        let mut conn = self.store.connect()?;
+        conn.del(&Self::node_key(node_id))?;
+        conn.srem(NODES_KEY, node_id)?;
+
+        Ok(())
+    }
+
+    pub fn remove(&self, key: &CacheKey) -> Result<(), redis::RedisError> {
+        let mut conn = self.store.connect()?;
+
+        if let Some(meta) = Self::_get(conn.as_mut(), key)? {
+            conn.del(&Self::participant_key(&meta.participant_id))?;
+            conn.srem(&Self::room_key(&meta.room_id), &key.key)?;
+            Self::_untrack(conn.as_mut(), &meta.sfu_node_id, &key.key)?;
+        }
+
+        conn.del(&key.key)?;
+
+        Ok(())
+    }
+
+    pub fn contains_key(&self, key: &CacheKey) -> Result<bool, redis::RedisError> {
+        let mut conn = self.store.connect()?;
+        conn.exists(&key.key)
+    }
+
+    fn _get(
+        conn: &mut dyn RoutingConnection,
+        key: &CacheKey,
+    ) -> Result<Option<ClientMetadata>, redis::RedisError> {
+        let result = conn.get_ex(&key.key, ROUTING_TTL_SECS)?;
         match result {
             Some(s) => {
                 let metadata: ClientMetadata = serde_json::from_str(&s).map_err(|e| {
@@ -71,33 +279,302 @@ impl CacheManager {
         }
     }
 
-    pub fn get_by_participant_id(
-        &self,
-        participant_id: &str,
-    ) -> Result<Option<ClientMetadata>, redis::RedisError> {
-        let mut conn = self.client.lock().unwrap().get_connection()?;
-        let key: Option<String> = conn.get(format!("participant_id:{participant_id}"))?;
-        match key {
-            Some(actual_key) => self.get(&CacheKey::new(actual_key)),
-            None => Ok(None),
+    /// Drops a client from a node's index, and the node once nobody is left on
+    /// it, like a node whose clients all failed over elsewhere
+    fn _untrack(
+        conn: &mut dyn RoutingConnection,
+        node_id: &str,
+        key: &str,
+    ) -> Result<(), redis::RedisError> {
+        let node_key = Self::node_key(node_id);
+        conn.srem(&node_key, key)?;
+
+        if !conn.exists(&node_key)? {
+            conn.srem(NODES_KEY, node_id)?;
         }
+
+        Ok(())
     }
 
-    pub fn remove(&self, key: &CacheKey) -> Result<(), redis::RedisError> {
-        let mut conn = self.client.lock().unwrap().get_connection()?;
+    fn participant_key(participant_id: &str) -> String {
+        format!("participant_id:{participant_id}")
+    }
 
-        if let Some(meta) = self.get(key)? {
-            let _: () = conn.del(format!("participant_id:{}", meta.participant_id))?;
+    fn node_key(node_id: &str) -> String {
+        format!("node_id:{node_id}")
+    }
+
+    fn room_key(room_id: &str) -> String {
+        format!("room_id:{room_id}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use super::*;
+
+    enum Value {
+        String(String),
+        Set(BTreeSet<String>),
+    }
+
+    /// Keys with the expiry they were last given, time doesn't pass
+    #[derive(Default)]
+    struct Memory {
+        values: HashMap<String, Value>,
+        ttls: HashMap<String, u64>,
+    }
+
+    #[derive(Clone, Default)]
+    struct MemoryRoutingStore {
+        memory: Arc<Mutex<Memory>>,
+    }
+
+    impl MemoryRoutingStore {
+        fn ttl(&self, key: &str) -> Option<u64> {
+            self.memory.lock().unwrap().ttls.get(key).copied()
         }
 
-        conn.del::<_, ()>(&key.key)?;
+        fn set_ttl(&self, key: &str, ttl_secs: u64) {
+            self.memory
+                .lock()
+                .unwrap()
+                .ttls
+                .insert(key.to_owned(), ttl_secs);
+        }
 
-        Ok(())
+        fn members(&self, key: &str) -> Vec<String> {
+            match self.memory.lock().unwrap().values.get(key) {
+                Some(Value::Set(set)) => set.iter().cloned().collect(),
+                _ => vec![],
+            }
+        }
     }
 
-    pub fn contains_key(&self, key: &CacheKey) -> Result<bool, redis::RedisError> {
-        let mut conn = self.client.lock().unwrap().get_connection()?;
-        let exists: i64 = conn.exists(&key.key)?;
-        Ok(exists == 1)
+    impl RoutingStore for MemoryRoutingStore {
+        fn connect(&self) -> RedisResult<Box<dyn RoutingConnection>> {
+            Ok(Box::new(self.clone()))
+        }
+    }
+
+    impl RoutingConnection for MemoryRoutingStore {
+        fn get_ex(&mut self, key: &str, ttl_secs: u64) -> RedisResult<Option<String>> {
+            let mut memory = self.memory.lock().unwrap();
+            let Some(Value::String(value)) = memory.values.get(key) else {
+                return Ok(None);
+            };
+            let value = value.clone();
+            memory.ttls.insert(key.to_owned(), ttl_secs);
+            Ok(Some(value))
+        }
+
+        fn set_ex(&mut self, key: &str, value: &str, ttl_secs: u64) -> RedisResult<()> {
+            let mut memory = self.memory.lock().unwrap();
+            memory
+                .values
+                .insert(key.to_owned(), Value::String(value.to_owned()));
+            memory.ttls.insert(key.to_owned(), ttl_secs);
+            Ok(())
+        }
+
+        fn del(&mut self, key: &str) -> RedisResult<()> {
+            let mut memory = self.memory.lock().unwrap();
+            memory.values.remove(key);
+            memory.ttls.remove(key);
+            Ok(())
+        }
+
+        fn exists(&mut self, key: &str) -> RedisResult<bool> {
+            Ok(self.memory.lock().unwrap().values.contains_key(key))
+        }
+
+        fn expire(&mut self, key: &str, ttl_secs: u64) -> RedisResult<()> {
+            let mut memory = self.memory.lock().unwrap();
+            if memory.values.contains_key(key) {
+                memory.ttls.insert(key.to_owned(), ttl_secs);
+            }
+            Ok(())
+        }
+
+        fn sadd(&mut self, key: &str, member: &str) -> RedisResult<()> {
+            let mut memory = self.memory.lock().unwrap();
+            let value = memory
+                .values
+                .entry(key.to_owned())
+                .or_insert_with(|| Value::Set(BTreeSet::new()));
+            if let Value::Set(set) = value {
+                set.insert(member.to_owned());
+            }
+            Ok(())
+        }
+
+        fn srem(&mut self, key: &str, member: &str) -> RedisResult<()> {
+            let mut memory = self.memory.lock().unwrap();
+            let is_empty = match memory.values.get_mut(key) {
+                Some(Value::Set(set)) => {
+                    set.remove(member);
+                    set.is_empty()
+                }
+                _ => false,
+            };
+            if is_empty {
+                memory.values.remove(key);
+                memory.ttls.remove(key);
+            }
+            Ok(())
+        }
+
+        fn smembers(&mut self, key: &str) -> RedisResult<Vec<String>> {
+            Ok(self.members(key))
+        }
+    }
+
+    fn cache_manager() -> (CacheManager, MemoryRoutingStore) {
+        let store = MemoryRoutingStore::default();
+        let cache_manager = CacheManager {
+            store: Arc::new(store.clone()),
+        };
+        (cache_manager, store)
+    }
+
+    fn client(participant_id: &str, node_id: &str) -> ClientMetadata {
+        ClientMetadata {
+            room_id: "room".to_owned(),
+            participant_id: participant_id.to_owned(),
+            sfu_node_id: node_id.to_owned(),
+            node_addr: format!("{node_id}.local"),
+        }
+    }
+
+    #[test]
+    fn test_insert_sets_expiry_of_every_key() {
+        let (cache_manager, store) = cache_manager();
+
+        cache_manager
+            .insert(CacheKey::new("sid".to_owned()), &client("p1", "node1"))
+            .unwrap();
+
+        assert_eq!(store.ttl("sid"), Some(ROUTING_TTL_SECS));
+        assert_eq!(store.ttl("participant_id:p1"), Some(ROUTING_TTL_SECS));
+        assert_eq!(store.ttl("node_id:node1"), Some(ROUTING_TTL_SECS));
+        assert_eq!(store.ttl("room_id:room"), Some(ROUTING_TTL_SECS));
+    }
+
+    #[test]
+    fn test_reads_refresh_expiry_of_the_key_read() {
+        let (cache_manager, store) = cache_manager();
+        let key = CacheKey::new("sid".to_owned());
+        cache_manager
+            .insert(key.clone(), &client("p1", "node1"))
+            .unwrap();
+
+        store.set_ttl("sid", 5);
+        store.set_ttl("participant_id:p1", 5);
+
+        assert!(cache_manager.get(&key).unwrap().is_some());
+        assert_eq!(store.ttl("sid"), Some(ROUTING_TTL_SECS));
+        // Only written to or read by participant id
+        assert_eq!(store.ttl("participant_id:p1"), Some(5));
+
+        let found = cache_manager.get_by_participant_id("p1").unwrap().unwrap();
+        assert_eq!(found.sfu_node_id, "node1");
+        assert_eq!(store.ttl("participant_id:p1"), Some(ROUTING_TTL_SECS));
+    }
+
+    #[test]
+    fn test_failed_over_client_leaves_old_node_index() {
+        let (cache_manager, store) = cache_manager();
+        let key = CacheKey::new("sid".to_owned());
+        cache_manager
+            .insert(key.clone(), &client("p1", "node1"))
+            .unwrap();
+
+        // Rejoins on a healthy node after node1 went away
+        cache_manager
+            .insert(key.clone(), &client("p1", "node2"))
+            .unwrap();
+
+        assert!(store.members("node_id:node1").is_empty());
+        assert_eq!(store.members("node_id:node2"), vec!["sid".to_owned()]);
+        assert_eq!(
+            cache_manager.get_node_ids().unwrap(),
+            vec!["node2".to_owned()]
+        );
+        assert!(cache_manager.get_by_node_id("node1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_by_node_id_drops_stale_entries() {
+        let (cache_manager, store) = cache_manager();
+        cache_manager
+            .insert(CacheKey::new("sid1".to_owned()), &client("p1", "node1"))
+            .unwrap();
+        cache_manager
+            .insert(CacheKey::new("sid2".to_owned()), &client("p2", "node1"))
+            .unwrap();
+
+        // Expired without leaving
+        store.connect().unwrap().del("sid2").unwrap();
+
+        let clients = cache_manager.get_by_node_id("node1").unwrap();
+        assert_eq!(clients.len(), 1);
+        assert_eq!(clients[0].1.participant_id, "p1");
+        assert_eq!(store.members("node_id:node1"), vec!["sid1".to_owned()]);
+
+        store.connect().unwrap().del("sid1").unwrap();
+
+        assert!(cache_manager.get_by_node_id("node1").unwrap().is_empty());
+        assert!(cache_manager.get_node_ids().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_remove_cleans_up_indexes() {
+        let (cache_manager, store) = cache_manager();
+        let key = CacheKey::new("sid".to_owned());
+        cache_manager
+            .insert(key.clone(), &client("p1", "node1"))
+            .unwrap();
+
+        cache_manager.remove(&key).unwrap();
+
+        assert!(!cache_manager.contains_key(&key).unwrap());
+        assert!(cache_manager.get_by_participant_id("p1").unwrap().is_none());
+        assert!(store.members("node_id:node1").is_empty());
+        assert!(store.members("room_id:room").is_empty());
+        assert!(cache_manager.get_node_ids().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_by_room_id_spans_nodes() {
+        let (cache_manager, store) = cache_manager();
+        cache_manager
+            .insert(CacheKey::new("sid1".to_owned()), &client("p1", "node1"))
+            .unwrap();
+        cache_manager
+            .insert(CacheKey::new("sid2".to_owned()), &client("p2", "node2"))
+            .unwrap();
+        let mut other = client("p3", "node1");
+        other.room_id = "other".to_owned();
+        cache_manager
+            .insert(CacheKey::new("sid3".to_owned()), &other)
+            .unwrap();
+
+        let mut nodes = cache_manager
+            .get_by_room_id("room")
+            .unwrap()
+            .into_iter()
+            .map(|(_, client)| client.sfu_node_id)
+            .collect::<Vec<_>>();
+        nodes.sort();
+        assert_eq!(nodes, ["node1", "node2"]);
+
+        // Expired without leaving
+        store.connect().unwrap().del("sid2").unwrap();
+
+        assert_eq!(cache_manager.get_by_room_id("room").unwrap().len(), 1);
+        assert_eq!(store.members("room_id:room"), vec!["sid1".to_owned()]);
+        assert!(cache_manager.get_by_room_id("missing").unwrap().is_empty());
     }
 }
@@ -0,0 +1,276 @@
+use redis::{
+    Commands,
+    cluster::ClusterClient,
+    streams::{StreamId, StreamMaxlen, StreamRangeReply},
+};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+
+use crate::{domain::DispatcherCallback, infrastructure::event_queue::EventSender};
+
+const DLQ_KEY: &str = "dispatcher:dlq";
+
+/// Oldest entries are trimmed once the stream grows past this
+const DLQ_MAX_LEN: usize = 10_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeadLetter {
+    pub id: String,
+    pub kind: String,
+    pub client_id: Option<String>,
+    pub reason: String,
+}
+
+/// Where callbacks that could not be delivered are parked
+pub trait DeadLetterStore {
+    fn push(
+        &self,
+        callback: &DispatcherCallback,
+        reason: &str,
+    ) -> Result<String, redis::RedisError>;
+
+    /// Return the most recent dead letters, newest first
+    fn list(&self, count: usize) -> Result<Vec<DeadLetter>, redis::RedisError>;
+
+    /// Remove a dead letter and return it along with its callback, the
+    /// callback is None if the payload can no longer be decoded
+    fn take(
+        &self,
+        id: &str,
+    ) -> Result<Option<(DeadLetter, Option<DispatcherCallback>)>, redis::RedisError>;
+}
+
+/// Remove a dead letter from the store and deliver its callback again.
+/// Returns None if no dead letter has the given id, so a second replay of
+/// the same id sends nothing.
+pub async fn replay<S: DeadLetterStore>(
+    store: &S,
+    sender: &EventSender<DispatcherCallback>,
+    id: &str,
+) -> Result<Option<DeadLetter>, anyhow::Error> {
+    let entry = store
+        .take(id)
+        .map_err(|e| anyhow::anyhow!("Failed to take dead letter {}: {}", id, e))?;
+
+    match entry {
+        Some((dead_letter, Some(callback))) => {
+            sender
+                .send(callback)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to replay dead letter {}: {}", id, e))?;
+
+            Ok(Some(dead_letter))
+        }
+        Some((dead_letter, None)) => Err(anyhow::anyhow!(
+            "Failed to decode {} dead letter {}",
+            dead_letter.kind,
+            id
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Redis stream holding dispatcher callbacks that signalling failed to deliver,
+/// kept around so they can be inspected and replayed
+#[derive(Clone)]
+pub struct DeadLetterQueue {
+    client: Arc<Mutex<ClusterClient>>,
+}
+
+impl DeadLetterQueue {
+    pub fn new(urls: Vec<String>) -> Self {
+        let client = ClusterClient::new(urls).unwrap();
+        Self {
+            client: Arc::new(Mutex::new(client)),
+        }
+    }
+
+    fn to_dead_letter(entry: &StreamId) -> DeadLetter {
+        let client_id: String = entry.get("clientId").unwrap_or_default();
+
+        DeadLetter {
+            id: entry.id.clone(),
+            kind: entry.get("kind").unwrap_or_default(),
+            client_id: (!client_id.is_empty()).then_some(client_id),
+            reason: entry.get("reason").unwrap_or_default(),
+        }
+    }
+}
+
+impl DeadLetterStore for DeadLetterQueue {
+    fn push(
+        &self,
+        callback: &DispatcherCallback,
+        reason: &str,
+    ) -> Result<String, redis::RedisError> {
+        let mut conn = self.client.lock().unwrap().get_connection()?;
+
+        let items: [(&str, Vec<u8>); 4] = [
+            ("kind", callback.kind().as_bytes().to_vec()),
+            (
+                "clientId",
+                callback.client_id().unwrap_or_default().as_bytes().to_vec(),
+            ),
+            ("reason", reason.as_bytes().to_vec()),
+            ("payload", callback.encode()),
+        ];
+
+        conn.xadd_maxlen(DLQ_KEY, StreamMaxlen::Approx(DLQ_MAX_LEN), "*", &items)
+    }
+
+    fn list(&self, count: usize) -> Result<Vec<DeadLetter>, redis::RedisError> {
+        let mut conn = self.client.lock().unwrap().get_connection()?;
+        let reply: StreamRangeReply = conn.xrevrange_count(DLQ_KEY, "+", "-", count)?;
+
+        Ok(reply.ids.iter().map(Self::to_dead_letter).collect())
+    }
+
+    fn take(
+        &self,
+        id: &str,
+    ) -> Result<Option<(DeadLetter, Option<DispatcherCallback>)>, redis::RedisError> {
+        let mut conn = self.client.lock().unwrap().get_connection()?;
+        let reply: StreamRangeReply = conn.xrange(DLQ_KEY, id, id)?;
+
+        let Some(entry) = reply.ids.first() else {
+            return Ok(None);
+        };
+
+        let dead_letter = Self::to_dead_letter(entry);
+        let payload: Vec<u8> = entry.get("payload").unwrap_or_default();
+        let callback = DispatcherCallback::decode(&dead_letter.kind, &payload);
+
+        let _: usize = conn.xdel(DLQ_KEY, &[id])?;
+
+        Ok(Some((dead_letter, callback)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use waterbus_proto::SubscriberRenegotiateRequest;
+
+    use super::*;
+    use crate::infrastructure::event_queue::bounded;
+
+    /// Keeps entries the way the Redis stream does, encoded by kind
+    #[derive(Default)]
+    struct MemoryDeadLetters {
+        entries: Mutex<Vec<(DeadLetter, Vec<u8>)>>,
+    }
+
+    impl DeadLetterStore for MemoryDeadLetters {
+        fn push(
+            &self,
+            callback: &DispatcherCallback,
+            reason: &str,
+        ) -> Result<String, redis::RedisError> {
+            let mut entries = self.entries.lock().unwrap();
+            let id = format!("{}-0", entries.len() + 1);
+
+            entries.push((
+                DeadLetter {
+                    id: id.clone(),
+                    kind: callback.kind().to_owned(),
+                    client_id: callback.client_id().map(str::to_owned),
+                    reason: reason.to_owned(),
+                },
+                callback.encode(),
+            ));
+
+            Ok(id)
+        }
+
+        fn list(&self, count: usize) -> Result<Vec<DeadLetter>, redis::RedisError> {
+            let entries = self.entries.lock().unwrap();
+
+            Ok(entries
+                .iter()
+                .rev()
+                .take(count)
+                .map(|(dead_letter, _)| dead_letter.clone())
+                .collect())
+        }
+
+        fn take(
+            &self,
+            id: &str,
+        ) -> Result<Option<(DeadLetter, Option<DispatcherCallback>)>, redis::RedisError> {
+            let mut entries = self.entries.lock().unwrap();
+
+            let Some(index) = entries.iter().position(|(d, _)| d.id == id) else {
+                return Ok(None);
+            };
+
+            let (dead_letter, payload) = entries.remove(index);
+            let callback = DispatcherCallback::decode(&dead_letter.kind, &payload);
+
+            Ok(Some((dead_letter, callback)))
+        }
+    }
+
+    fn renegotiate(client_id: &str) -> DispatcherCallback {
+        DispatcherCallback::SubscriberRenegotiate(SubscriberRenegotiateRequest {
+            client_id: client_id.to_owned(),
+            target_id: "target".to_owned(),
+            sdp: "v=0".to_owned(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_failed_callback_is_replayed_once() {
+        let store = MemoryDeadLetters::default();
+        let (sender, receiver) = bounded("test", 4);
+
+        let id = store
+            .push(&renegotiate("client-1"), "Socket not found")
+            .unwrap();
+
+        let dead_letters = store.list(10).unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].kind, "subscriber_renegotiate");
+        assert_eq!(dead_letters[0].client_id.as_deref(), Some("client-1"));
+        assert_eq!(dead_letters[0].reason, "Socket not found");
+
+        let replayed = replay(&store, &sender, &id).await.unwrap();
+        assert_eq!(replayed.map(|d| d.id), Some(id.clone()));
+
+        match receiver.recv().await {
+            Some(DispatcherCallback::SubscriberRenegotiate(req)) => {
+                assert_eq!(req.client_id, "client-1");
+                assert_eq!(req.target_id, "target");
+                assert_eq!(req.sdp, "v=0");
+            }
+            other => panic!("unexpected callback {other:?}"),
+        }
+
+        assert!(store.list(10).unwrap().is_empty());
+        assert!(replay(&store, &sender, &id).await.unwrap().is_none());
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), receiver.recv())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_of_undecodable_dead_letter_fails() {
+        let store = MemoryDeadLetters::default();
+        let (sender, _receiver) = bounded::<DispatcherCallback>("test", 4);
+
+        store.entries.lock().unwrap().push((
+            DeadLetter {
+                id: "1-0".to_owned(),
+                kind: "unknown".to_owned(),
+                client_id: None,
+                reason: "Socket not found".to_owned(),
+            },
+            Vec::new(),
+        ));
+
+        assert!(replay(&store, &sender, "1-0").await.is_err());
+        assert_eq!(sender.stats().depth(), 0);
+    }
+}
@@ -0,0 +1,38 @@
+use redis::{Commands, cluster::ClusterClient};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+const DRAINED_NODES_KEY: &str = "dispatcher:drained-nodes";
+
+/// SFU nodes an operator is taking out of rotation, shared by every
+/// signalling node. A drained node keeps its calls but gets no new ones.
+#[derive(Clone)]
+pub struct DrainStore {
+    client: Arc<Mutex<ClusterClient>>,
+}
+
+impl DrainStore {
+    pub fn new(urls: Vec<String>) -> Self {
+        let client = ClusterClient::new(urls).unwrap();
+        Self {
+            client: Arc::new(Mutex::new(client)),
+        }
+    }
+
+    pub fn get_drained(&self) -> Result<HashSet<String>, redis::RedisError> {
+        let mut conn = self.client.lock().unwrap().get_connection()?;
+        conn.smembers(DRAINED_NODES_KEY)
+    }
+
+    pub fn set_drained(&self, node_id: &str, is_drained: bool) -> Result<(), redis::RedisError> {
+        let mut conn = self.client.lock().unwrap().get_connection()?;
+
+        if is_drained {
+            conn.sadd(DRAINED_NODES_KEY, node_id)
+        } else {
+            conn.srem(DRAINED_NODES_KEY, node_id)
+        }
+    }
+}
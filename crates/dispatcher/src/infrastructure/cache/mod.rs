@@ -1 +1,5 @@
 pub mod cache_manager;
+pub mod dead_letter_queue;
+pub mod drain_store;
+pub mod rollout_store;
+pub mod room_control_store;
@@ -0,0 +1,83 @@
+use redis::{Commands, cluster::ClusterClient};
+use std::sync::{Arc, Mutex};
+
+use crate::domain::rollout::{RolloutConfig, RoomCohort};
+
+const ROLLOUT_KEY: &str = "dispatcher:rollout";
+
+/// A room keeps its cohort while people keep joining it, every join pushes
+/// the expiry forward
+const COHORT_TTL_SECS: u64 = 60 * 60 * 12;
+
+/// Canary rollout settings and the cohort each room was put in, shared by
+/// every signalling node
+#[derive(Clone)]
+pub struct RolloutStore {
+    client: Arc<Mutex<ClusterClient>>,
+}
+
+impl RolloutStore {
+    pub fn new(urls: Vec<String>) -> Self {
+        let client = ClusterClient::new(urls).unwrap();
+        Self {
+            client: Arc::new(Mutex::new(client)),
+        }
+    }
+
+    /// No rollout configured means every room is stable
+    pub fn get_config(&self) -> Result<RolloutConfig, redis::RedisError> {
+        let mut conn = self.client.lock().unwrap().get_connection()?;
+        let value: Option<String> = conn.get(ROLLOUT_KEY)?;
+
+        Ok(value
+            .and_then(|value| serde_json::from_str(&value).ok())
+            .unwrap_or_default())
+    }
+
+    /// Rooms that already have a cohort keep it, only new rooms follow the change
+    pub fn set_config(&self, config: &RolloutConfig) -> Result<(), redis::RedisError> {
+        let mut conn = self.client.lock().unwrap().get_connection()?;
+        let value = serde_json::to_string(config).map_err(|e| {
+            redis::RedisError::from((
+                redis::ErrorKind::TypeError,
+                "serialization error",
+                e.to_string(),
+            ))
+        })?;
+
+        conn.set(ROLLOUT_KEY, value)
+    }
+
+    /// Stores the cohort unless the room has one already, and returns whichever won
+    pub fn claim_cohort(
+        &self,
+        room_id: &str,
+        cohort: RoomCohort,
+    ) -> Result<RoomCohort, redis::RedisError> {
+        let mut conn = self.client.lock().unwrap().get_connection()?;
+        let key = Self::cohort_key(room_id);
+
+        let claimed: Option<String> = redis::cmd("SET")
+            .arg(&key)
+            .arg(cohort.as_str())
+            .arg("NX")
+            .arg("EX")
+            .arg(COHORT_TTL_SECS)
+            .query(&mut conn)?;
+        if claimed.is_some() {
+            return Ok(cohort);
+        }
+
+        let current: Option<String> = conn.get(&key)?;
+        let _: () = conn.expire(&key, COHORT_TTL_SECS as i64)?;
+
+        Ok(current
+            .as_deref()
+            .and_then(RoomCohort::parse)
+            .unwrap_or(cohort))
+    }
+
+    fn cohort_key(room_id: &str) -> String {
+        format!("dispatcher:rollout:room:{room_id}")
+    }
+}
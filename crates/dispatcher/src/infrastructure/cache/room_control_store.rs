@@ -0,0 +1,112 @@
+use redis::{Commands, cluster::ClusterClient};
+use std::sync::{Arc, Mutex};
+
+/// Outlives a call like its routing entries, every change pushes it forward
+const CONTROLS_TTL_SECS: u64 = 60 * 60 * 12;
+
+/// What the host switched on for the whole room, handed to every node with
+/// each join so a node the room comes to never starts out without it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RoomControls {
+    pub is_stage_mode: bool,
+    pub speakers: Vec<String>,
+    pub is_recording_paused: bool,
+    pub is_broadcast_cut: bool,
+}
+
+/// Room controls of each live room, shared by every signalling node
+#[derive(Clone)]
+pub struct RoomControlStore {
+    client: Arc<Mutex<ClusterClient>>,
+}
+
+impl RoomControlStore {
+    pub fn new(urls: Vec<String>) -> Self {
+        let client = ClusterClient::new(urls).unwrap();
+        Self {
+            client: Arc::new(Mutex::new(client)),
+        }
+    }
+
+    pub fn get(&self, room_id: &str) -> Result<RoomControls, redis::RedisError> {
+        let mut conn = self.client.lock().unwrap().get_connection()?;
+        let is_stage_mode: Option<bool> = conn.get(Self::stage_mode_key(room_id))?;
+        let mut speakers: Vec<String> = conn.smembers(Self::speakers_key(room_id))?;
+        speakers.sort();
+        let is_recording_paused: Option<bool> = conn.get(Self::recording_paused_key(room_id))?;
+        let is_broadcast_cut: Option<bool> = conn.get(Self::broadcast_cut_key(room_id))?;
+
+        Ok(RoomControls {
+            is_stage_mode: is_stage_mode.unwrap_or_default(),
+            speakers,
+            is_recording_paused: is_recording_paused.unwrap_or_default(),
+            is_broadcast_cut: is_broadcast_cut.unwrap_or_default(),
+        })
+    }
+
+    pub fn set_stage_mode(&self, room_id: &str, is_enabled: bool) -> Result<(), redis::RedisError> {
+        let mut conn = self.client.lock().unwrap().get_connection()?;
+        conn.set_ex(Self::stage_mode_key(room_id), is_enabled, CONTROLS_TTL_SECS)
+    }
+
+    pub fn set_speaker(
+        &self,
+        room_id: &str,
+        participant_id: &str,
+        is_speaker: bool,
+    ) -> Result<(), redis::RedisError> {
+        let mut conn = self.client.lock().unwrap().get_connection()?;
+        let key = Self::speakers_key(room_id);
+
+        if is_speaker {
+            let _: () = conn.sadd(&key, participant_id)?;
+        } else {
+            let _: () = conn.srem(&key, participant_id)?;
+        }
+
+        conn.expire(&key, CONTROLS_TTL_SECS as i64)
+    }
+
+    pub fn set_recording_paused(
+        &self,
+        room_id: &str,
+        is_paused: bool,
+    ) -> Result<(), redis::RedisError> {
+        let mut conn = self.client.lock().unwrap().get_connection()?;
+        conn.set_ex(
+            Self::recording_paused_key(room_id),
+            is_paused,
+            CONTROLS_TTL_SECS,
+        )
+    }
+
+    pub fn set_broadcast_cut(&self, room_id: &str, is_cut: bool) -> Result<(), redis::RedisError> {
+        let mut conn = self.client.lock().unwrap().get_connection()?;
+        conn.set_ex(Self::broadcast_cut_key(room_id), is_cut, CONTROLS_TTL_SECS)
+    }
+
+    /// A room nobody is routed to anymore starts over, like it does on the nodes
+    pub fn clear(&self, room_id: &str) -> Result<(), redis::RedisError> {
+        let mut conn = self.client.lock().unwrap().get_connection()?;
+        let _: () = conn.del(Self::stage_mode_key(room_id))?;
+        let _: () = conn.del(Self::speakers_key(room_id))?;
+        let _: () = conn.del(Self::recording_paused_key(room_id))?;
+        conn.del(Self::broadcast_cut_key(room_id))
+    }
+
+    fn stage_mode_key(room_id: &str) -> String {
+        format!("dispatcher:stage-mode:{room_id}")
+    }
+
+    fn speakers_key(room_id: &str) -> String {
+        format!("dispatcher:speakers:{room_id}")
+    }
+
+    fn recording_paused_key(room_id: &str) -> String {
+        format!("dispatcher:recording-paused:{room_id}")
+    }
+
+    fn broadcast_cut_key(room_id: &str) -> String {
+        format!("dispatcher:broadcast-cut:{room_id}")
+    }
+}
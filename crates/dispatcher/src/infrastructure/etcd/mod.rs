@@ -1,11 +1,16 @@
-use async_channel::Sender;
 use etcd_client::{Client, EventType, GetOptions, WatchOptions};
 use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock};
 
-use crate::domain::DispatcherCallback;
+use crate::{
+    domain::{
+        DispatcherCallback,
+        rollout::{CANARY_TAG, RoomCohort},
+    },
+    infrastructure::event_queue::EventSender,
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NodeMetadata {
@@ -13,6 +18,19 @@ pub struct NodeMetadata {
     pub cpu: f32, // e.g. 0.0 to 100.0
     pub ram: f32, // e.g. 0.0 to 100.0
     group_id: String,
+    /// Set by the node operator, nodes registered before tags existed have none
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl NodeMetadata {
+    pub fn cohort(&self) -> RoomCohort {
+        if self.tags.iter().any(|tag| tag == CANARY_TAG) {
+            RoomCohort::Canary
+        } else {
+            RoomCohort::Stable
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -21,7 +39,7 @@ pub struct EtcdDispatcher {
     nodes: Arc<RwLock<HashMap<String, NodeMetadata>>>,
     prefix: String,
     group_id: String,
-    sender: Sender<DispatcherCallback>,
+    sender: EventSender<DispatcherCallback>,
 }
 
 impl EtcdDispatcher {
@@ -29,7 +47,7 @@ impl EtcdDispatcher {
         etcd_endpoints: &[&str],
         prefix: &str,
         group_id: &str,
-        sender: Sender<DispatcherCallback>,
+        sender: EventSender<DispatcherCallback>,
     ) -> anyhow::Result<Self> {
         let client = Client::connect(etcd_endpoints, None).await?;
         let mut etcd = EtcdDispatcher {
@@ -112,18 +130,41 @@ impl EtcdDispatcher {
         Some((id, metadata))
     }
 
-    /// Return the least loaded node based on CPU usage
-    pub fn get_node_least(&self) -> Option<(String, NodeMetadata)> {
+    /// Return the least loaded node of the cohort based on CPU usage. Rooms
+    /// still get a node from the other cohort when theirs has none, a room
+    /// that can't start is worse than one on the wrong build. Drained nodes
+    /// are skipped either way.
+    pub fn get_node_least(
+        &self,
+        cohort: RoomCohort,
+        drained: &HashSet<String>,
+    ) -> Option<(String, NodeMetadata)> {
+        let nodes = self.nodes.read().unwrap();
+        let least = |in_cohort: bool| {
+            nodes
+                .iter()
+                .filter(|(_, meta)| meta.group_id == self.group_id)
+                .filter(|(id, _)| !drained.contains(*id))
+                .filter(|(_, meta)| (meta.cohort() == cohort) == in_cohort)
+                .min_by(|a, b| {
+                    a.1.cpu
+                        .partial_cmp(&b.1.cpu)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .map(|(id, meta)| (id.clone(), meta.clone()))
+        };
+
+        least(true).or_else(|| least(false))
+    }
+
+    /// Return every node that belongs to this dispatcher's group
+    pub fn get_nodes(&self) -> Vec<(String, NodeMetadata)> {
         let nodes = self.nodes.read().unwrap();
         nodes
             .iter()
             .filter(|(_, meta)| meta.group_id == self.group_id)
-            .min_by(|a, b| {
-                a.1.cpu
-                    .partial_cmp(&b.1.cpu)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            })
             .map(|(id, meta)| (id.clone(), meta.clone()))
+            .collect()
     }
 
     pub fn get_node_by_id(&self, id: &str) -> Option<NodeMetadata> {
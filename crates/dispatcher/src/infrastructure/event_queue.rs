@@ -0,0 +1,366 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    },
+};
+
+use tokio::sync::Notify;
+
+/// What a full queue does with one more event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// The oldest droppable event gives way, for events a later one supersedes
+    DropOldest,
+    /// The sender waits for room
+    Block,
+}
+
+pub trait QueuedEvent {
+    fn kind(&self) -> &'static str;
+
+    fn overflow(&self) -> Overflow;
+}
+
+/// Depth and losses of a queue, scraped from the metrics endpoint
+#[derive(Debug)]
+pub struct QueueStats {
+    pub name: &'static str,
+    pub capacity: usize,
+    depth: AtomicUsize,
+    blocked: AtomicU64,
+    dropped: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+impl QueueStats {
+    pub fn depth(&self) -> usize {
+        self.depth.load(Ordering::Relaxed)
+    }
+
+    /// Sends that found the queue full and waited
+    pub fn blocked(&self) -> u64 {
+        self.blocked.load(Ordering::Relaxed)
+    }
+
+    /// Events dropped to make room, by kind
+    pub fn dropped(&self) -> BTreeMap<&'static str, u64> {
+        self.dropped.lock().unwrap().clone()
+    }
+
+    fn count_drop(&self, kind: &'static str) {
+        *self.dropped.lock().unwrap().entry(kind).or_default() += 1;
+    }
+}
+
+/// The event handed back when nobody receives anymore
+pub struct QueueClosed<T>(pub T);
+
+impl<T> fmt::Debug for QueueClosed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("QueueClosed(..)")
+    }
+}
+
+impl<T> fmt::Display for QueueClosed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("sending into a closed queue")
+    }
+}
+
+impl<T> std::error::Error for QueueClosed<T> {}
+
+enum PushError<T> {
+    Full(T),
+    Closed(T),
+}
+
+struct Shared<T> {
+    queue: Mutex<VecDeque<T>>,
+    stats: Arc<QueueStats>,
+    readable: Notify,
+    writable: Notify,
+    senders: AtomicUsize,
+    is_closed: AtomicBool,
+}
+
+impl<T: QueuedEvent> Shared<T> {
+    fn try_push(&self, event: T) -> Result<(), PushError<T>> {
+        if self.is_closed.load(Ordering::Acquire) {
+            return Err(PushError::Closed(event));
+        }
+
+        let mut queue = self.queue.lock().unwrap();
+
+        if queue.len() >= self.stats.capacity {
+            if event.overflow() == Overflow::Block {
+                return Err(PushError::Full(event));
+            }
+
+            // Events that block keep their place, only droppable ones give way
+            match queue
+                .iter()
+                .position(|queued| queued.overflow() == Overflow::DropOldest)
+            {
+                Some(index) => {
+                    let oldest = queue.remove(index).unwrap();
+                    self.stats.count_drop(oldest.kind());
+                }
+                None => {
+                    self.stats.count_drop(event.kind());
+                    return Ok(());
+                }
+            }
+        }
+
+        queue.push_back(event);
+        self.stats.depth.store(queue.len(), Ordering::Relaxed);
+        drop(queue);
+
+        self.readable.notify_one();
+
+        Ok(())
+    }
+}
+
+/// Bounded queue between the producers of events and the loop draining them.
+/// A full queue drops or blocks by each event's `Overflow`, order is kept.
+pub fn bounded<T: QueuedEvent>(
+    name: &'static str,
+    capacity: usize,
+) -> (EventSender<T>, EventReceiver<T>) {
+    let shared = Arc::new(Shared {
+        queue: Mutex::new(VecDeque::new()),
+        stats: Arc::new(QueueStats {
+            name,
+            capacity: capacity.max(1),
+            depth: AtomicUsize::new(0),
+            blocked: AtomicU64::new(0),
+            dropped: Mutex::new(BTreeMap::new()),
+        }),
+        readable: Notify::new(),
+        writable: Notify::new(),
+        senders: AtomicUsize::new(1),
+        is_closed: AtomicBool::new(false),
+    });
+
+    (
+        EventSender {
+            shared: Arc::clone(&shared),
+        },
+        EventReceiver { shared },
+    )
+}
+
+pub struct EventSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T: QueuedEvent> EventSender<T> {
+    pub async fn send(&self, event: T) -> Result<(), QueueClosed<T>> {
+        let mut event = event;
+        let mut is_blocked = false;
+
+        loop {
+            let writable = self.shared.writable.notified();
+            tokio::pin!(writable);
+            writable.as_mut().enable();
+
+            match self.shared.try_push(event) {
+                Ok(()) => return Ok(()),
+                Err(PushError::Closed(closed)) => return Err(QueueClosed(closed)),
+                Err(PushError::Full(full)) => event = full,
+            }
+
+            if !is_blocked {
+                is_blocked = true;
+                self.shared.stats.blocked.fetch_add(1, Ordering::Relaxed);
+            }
+
+            writable.await;
+        }
+    }
+}
+
+impl<T> EventSender<T> {
+    pub fn stats(&self) -> Arc<QueueStats> {
+        Arc::clone(&self.shared.stats)
+    }
+}
+
+impl<T> Clone for EventSender<T> {
+    fn clone(&self) -> Self {
+        self.shared.senders.fetch_add(1, Ordering::AcqRel);
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Drop for EventSender<T> {
+    fn drop(&mut self) {
+        if self.shared.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shared.readable.notify_one();
+        }
+    }
+}
+
+impl<T> fmt::Debug for EventSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventSender")
+            .field("queue", &self.shared.stats.name)
+            .finish_non_exhaustive()
+    }
+}
+
+pub struct EventReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> EventReceiver<T> {
+    /// The next event, `None` once every sender is gone and the queue is drained
+    pub async fn recv(&self) -> Option<T> {
+        loop {
+            let readable = self.shared.readable.notified();
+            tokio::pin!(readable);
+            readable.as_mut().enable();
+
+            {
+                let mut queue = self.shared.queue.lock().unwrap();
+                if let Some(event) = queue.pop_front() {
+                    self.shared
+                        .stats
+                        .depth
+                        .store(queue.len(), Ordering::Relaxed);
+                    drop(queue);
+
+                    self.shared.writable.notify_one();
+                    return Some(event);
+                }
+            }
+
+            if self.shared.senders.load(Ordering::Acquire) == 0 {
+                return None;
+            }
+
+            readable.await;
+        }
+    }
+}
+
+impl<T> Drop for EventReceiver<T> {
+    fn drop(&mut self) {
+        self.shared.is_closed.store(true, Ordering::Release);
+        self.shared.writable.notify_waiters();
+    }
+}
+
+impl<T> fmt::Debug for EventReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventReceiver")
+            .field("queue", &self.shared.stats.name)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum TestEvent {
+        Critical(u32),
+        Ephemeral(u32),
+    }
+
+    impl QueuedEvent for TestEvent {
+        fn kind(&self) -> &'static str {
+            match self {
+                TestEvent::Critical(_) => "critical",
+                TestEvent::Ephemeral(_) => "ephemeral",
+            }
+        }
+
+        fn overflow(&self) -> Overflow {
+            match self {
+                TestEvent::Critical(_) => Overflow::Block,
+                TestEvent::Ephemeral(_) => Overflow::DropOldest,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_drops_oldest_ephemeral_event() {
+        let (sender, receiver) = bounded("test", 3);
+
+        sender.send(TestEvent::Ephemeral(1)).await.unwrap();
+        sender.send(TestEvent::Critical(2)).await.unwrap();
+        sender.send(TestEvent::Ephemeral(3)).await.unwrap();
+        sender.send(TestEvent::Ephemeral(4)).await.unwrap();
+
+        assert_eq!(receiver.recv().await, Some(TestEvent::Critical(2)));
+        assert_eq!(receiver.recv().await, Some(TestEvent::Ephemeral(3)));
+        assert_eq!(receiver.recv().await, Some(TestEvent::Ephemeral(4)));
+
+        let stats = sender.stats();
+        assert_eq!(stats.depth(), 0);
+        assert_eq!(stats.dropped().get("ephemeral"), Some(&1));
+    }
+
+    #[tokio::test]
+    async fn test_ephemeral_event_is_dropped_among_critical_ones() {
+        let (sender, receiver) = bounded("test", 2);
+
+        sender.send(TestEvent::Critical(1)).await.unwrap();
+        sender.send(TestEvent::Critical(2)).await.unwrap();
+        sender.send(TestEvent::Ephemeral(3)).await.unwrap();
+
+        assert_eq!(sender.stats().depth(), 2);
+        assert_eq!(receiver.recv().await, Some(TestEvent::Critical(1)));
+        assert_eq!(receiver.recv().await, Some(TestEvent::Critical(2)));
+        assert_eq!(sender.stats().dropped().get("ephemeral"), Some(&1));
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_blocks_critical_event() {
+        let (sender, receiver) = bounded("test", 1);
+
+        sender.send(TestEvent::Critical(1)).await.unwrap();
+
+        let blocked = tokio::spawn({
+            let sender = sender.clone();
+            async move { sender.send(TestEvent::Critical(2)).await }
+        });
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!blocked.is_finished());
+        assert_eq!(sender.stats().blocked(), 1);
+
+        assert_eq!(receiver.recv().await, Some(TestEvent::Critical(1)));
+        blocked.await.unwrap().unwrap();
+        assert_eq!(receiver.recv().await, Some(TestEvent::Critical(2)));
+        assert!(sender.stats().dropped().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_receiver_ends_once_senders_are_gone() {
+        let (sender, receiver) = bounded("test", 4);
+
+        sender.send(TestEvent::Critical(1)).await.unwrap();
+        drop(sender);
+
+        assert_eq!(receiver.recv().await, Some(TestEvent::Critical(1)));
+        assert_eq!(receiver.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_send_fails_once_receiver_is_gone() {
+        let (sender, receiver) = bounded("test", 4);
+        drop(receiver);
+
+        assert!(sender.send(TestEvent::Critical(1)).await.is_err());
+    }
+}
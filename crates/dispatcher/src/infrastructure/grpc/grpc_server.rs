@@ -1,16 +1,16 @@
-use async_channel::Sender;
 use tonic::transport::Server;
 use tracing::info;
 use waterbus_proto::dispatcher_service_server::DispatcherServiceServer;
 
 use crate::{
     application::dispatcher_grpc_service::DispatcherGrpcService, domain::DispatcherCallback,
+    infrastructure::event_queue::EventSender,
 };
 
 pub struct GrpcServer {}
 
 impl GrpcServer {
-    pub fn start(port: u16, sender: Sender<DispatcherCallback>) {
+    pub fn start(port: u16, sender: EventSender<DispatcherCallback>) {
         info!("GrpcServer is running on port: {}", port);
 
         tokio::spawn(async move {
@@ -21,7 +21,10 @@ impl GrpcServer {
         });
     }
 
-    async fn start_server(port: u16, sender: Sender<DispatcherCallback>) -> anyhow::Result<()> {
+    async fn start_server(
+        port: u16,
+        sender: EventSender<DispatcherCallback>,
+    ) -> anyhow::Result<()> {
         let addr = format!("0.0.0.0:{port}").parse().unwrap();
 
         let dispatcher_grpc_service = DispatcherGrpcService::new(sender);
@@ -1,3 +1,4 @@
 pub mod cache;
 pub mod etcd;
+pub mod event_queue;
 pub mod grpc;
@@ -0,0 +1,127 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use tokio::time::MissedTickBehavior;
+
+/// How often packets that are due go out, well under a frame
+const RELEASE_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Longest delay a room may ask for, a publisher's buffer is a minute of media at most
+pub const MAX_BROADCAST_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EgressTrack {
+    Video,
+    Audio,
+    ScreenAudio,
+}
+
+pub type DelayedPacketCallback = Arc<dyn Fn(&[u8], EgressTrack) + Send + Sync>;
+
+#[derive(Debug)]
+struct DelayedPacket {
+    due_at: Instant,
+    data: Vec<u8>,
+    track: EgressTrack,
+}
+
+/// Holds a publisher's RTP back for a fixed delay before it reaches the
+/// egress pipeline, so moderators of a live event get that long to pull what
+/// they don't want broadcast. WebRTC subscribers aren't behind it.
+#[derive(Debug)]
+pub struct BroadcastDelay {
+    delay: Duration,
+    packets: Arc<Mutex<VecDeque<DelayedPacket>>>,
+    is_cut: Arc<AtomicBool>,
+    is_stopped: Arc<AtomicBool>,
+}
+
+impl BroadcastDelay {
+    pub fn new(delay: Duration, on_packet: DelayedPacketCallback) -> Self {
+        let this = Self {
+            delay: delay.min(MAX_BROADCAST_DELAY),
+            packets: Arc::new(Mutex::new(VecDeque::new())),
+            is_cut: Arc::new(AtomicBool::new(false)),
+            is_stopped: Arc::new(AtomicBool::new(false)),
+        };
+
+        tokio::spawn({
+            let packets = Arc::clone(&this.packets);
+            let is_cut = Arc::clone(&this.is_cut);
+            let is_stopped = Arc::clone(&this.is_stopped);
+
+            async move {
+                let mut tick = tokio::time::interval(RELEASE_INTERVAL);
+                tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+                while !is_stopped.load(Ordering::Relaxed) {
+                    tick.tick().await;
+
+                    let now = Instant::now();
+                    let due = {
+                        let mut packets = packets.lock().unwrap();
+                        let count = packets
+                            .iter()
+                            .take_while(|packet| packet.due_at <= now)
+                            .count();
+                        packets.drain(..count).collect::<Vec<_>>()
+                    };
+
+                    for packet in due {
+                        // A cut lands between two packets, not after the batch
+                        if is_cut.load(Ordering::Relaxed) {
+                            break;
+                        }
+
+                        on_packet(&packet.data, packet.track);
+                    }
+                }
+            }
+        });
+
+        this
+    }
+
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    pub fn push(&self, data: &[u8], track: EgressTrack) {
+        if self.is_cut.load(Ordering::Relaxed) {
+            return;
+        }
+
+        self.packets.lock().unwrap().push_back(DelayedPacket {
+            due_at: Instant::now() + self.delay,
+            data: data.to_vec(),
+            track,
+        });
+    }
+
+    /// Drops everything held back and keeps the broadcast dark until
+    /// `restore`, what happened during the delay never goes out
+    pub fn cut(&self) {
+        self.is_cut.store(true, Ordering::Relaxed);
+        self.packets.lock().unwrap().clear();
+    }
+
+    /// Media flows again, viewers pick it up once the delay has passed
+    pub fn restore(&self) {
+        self.is_cut.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_cut(&self) -> bool {
+        self.is_cut.load(Ordering::Relaxed)
+    }
+
+    pub fn stop(&self) {
+        self.is_stopped.store(true, Ordering::Relaxed);
+        self.packets.lock().unwrap().clear();
+    }
+}
@@ -1,8 +1,7 @@
 use std::{
-    env,
     path::PathBuf,
-    sync::{Arc, Mutex},
-    time::Instant,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
 };
 
 use anyhow::Ok;
@@ -10,20 +9,38 @@ use gst::{
     ClockTime,
     prelude::{ElementExt, GstObjectExt, PipelineExt},
 };
+use serde::Serialize;
 use tokio::task;
 
+use super::broadcast_delay::{BroadcastDelay, EgressTrack};
 use super::utils::{
-    AudioStream, AudioStreamExt, R2Config, R2MasterState, R2Storage, State, VideoStream,
-    VideoStreamExt, init,
+    AudioStream, AudioStreamExt, PausedInterval, R2Config, R2MasterState, R2Storage,
+    RecordingClock, State, VideoStream, VideoStreamExt, init,
 };
 
+const SESSION_FILE: &str = "session.json";
+
+/// Written next to the playlist on every pause and resume and once the stream
+/// ends, lets an editor map the continuous output timeline back to the wall
+/// clock of the call. Uploaded with the segments, a node that goes away
+/// doesn't take the edit list with it.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordingSession {
+    paused_intervals: Vec<PausedInterval>,
+}
+
 #[derive(Debug, Clone)]
 pub struct HlsWriter {
     pipeline: gst::Pipeline,
+    dir: PathBuf,
     state: Arc<Mutex<State>>,
-    start_time: Instant,
+    clock: Arc<Mutex<RecordingClock>>,
     video_offset: Arc<Mutex<u64>>,
     audio_offset: Arc<Mutex<u64>>,
+    screen_audio_offset: Arc<Mutex<u64>>,
+    broadcast_delay: Arc<OnceLock<BroadcastDelay>>,
+    r2_storage: Option<Arc<R2Storage>>,
 }
 
 impl HlsWriter {
@@ -34,27 +51,10 @@ impl HlsWriter {
         let pipeline = gst::Pipeline::default();
         std::fs::create_dir_all(&path).expect("failed to create directory");
 
-        let r2_config: Option<R2Config> = Self::_get_r2_config(prefix_path);
+        let r2_config: Option<R2Config> = R2Config::from_env(prefix_path);
 
         let (r2_storage, master_state) = if let Some(config) = r2_config {
-            // Use new_with_worker instead of new
-            let (r2_storage, upload_receiver) = R2Storage::new_with_worker(config.clone()).await?;
-            let r2_storage = Arc::new(r2_storage);
-
-            // Start the upload worker
-            let worker_storage = r2_storage.clone();
-            worker_storage.start_upload_worker(upload_receiver);
-
-            let cloud_url_base = match &r2_storage.config.custom_domain {
-                Some(domain) => Some(format!("https://{domain}")),
-                None => {
-                    let account = r2_storage.config.account_id.clone();
-                    let bucket = r2_storage.config.bucket_name.clone();
-                    Some(format!(
-                        "https://{account}.r2.cloudflarestorage.com/{bucket}"
-                    ))
-                }
-            };
+            let r2_storage = R2Storage::start(config).await?;
 
             let mut manifest_path = path.clone();
             manifest_path.push("manifest.m3u8");
@@ -62,7 +62,6 @@ impl HlsWriter {
             let master_state = Arc::new(std::sync::Mutex::new(R2MasterState::new(
                 manifest_path.clone(),
                 r2_storage.clone(),
-                cloud_url_base.clone(),
             )));
 
             (Some(r2_storage), Some(master_state))
@@ -88,6 +87,7 @@ impl HlsWriter {
                 default: true,
                 wave: "sine".to_string(),
                 audio_src: None,
+                screen_audio_src: None,
             }],
             all_mimes: vec![],
             path: manifest_path.clone(),
@@ -125,9 +125,13 @@ impl HlsWriter {
         let this = Self {
             state,
             pipeline: pipeline.clone(),
-            start_time: Instant::now(),
+            dir: path,
+            clock: Arc::new(Mutex::new(RecordingClock::new())),
             video_offset: Arc::new(Mutex::new(0)),
             audio_offset: Arc::new(Mutex::new(0)),
+            screen_audio_offset: Arc::new(Mutex::new(0)),
+            broadcast_delay: Arc::new(OnceLock::new()),
+            r2_storage,
         };
 
         let hls_writer_arc = Arc::new(this.clone());
@@ -193,16 +197,129 @@ impl HlsWriter {
     pub fn set_video_codec(&self, _codec: &str) {}
 
     pub fn stop(&self) {
+        if let Some(delay) = self.broadcast_delay.get() {
+            delay.stop();
+        }
+
         let _ = self.pipeline.set_state(gst::State::Null);
+
+        // A stream ending while paused closes its last interval here
+        self.clock.lock().unwrap().resume();
+        self._save_session();
+    }
+
+    fn _save_session(&self) {
+        let session = RecordingSession {
+            paused_intervals: self.clock.lock().unwrap().paused_intervals(),
+        };
+
+        let path = self.dir.join(SESSION_FILE);
+
+        let result = serde_json::to_vec_pretty(&session)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| Ok(std::fs::write(&path, json)?))
+            .and_then(|_| match &self.r2_storage {
+                Some(r2_storage) => r2_storage.upload_file(&path, SESSION_FILE, "application/json"),
+                None => Ok(()),
+            });
+
+        if let Err(err) = result {
+            tracing::warn!("Failed to save recording session: {:?}", err);
+        }
+    }
+
+    /// Holds the stream back by `delay` from here on, set once per writer
+    pub fn set_broadcast_delay(&self, delay: Duration) {
+        if delay.is_zero() || self.broadcast_delay.get().is_some() {
+            return;
+        }
+
+        let writer = self.clone();
+        let _ = self.broadcast_delay.set(BroadcastDelay::new(
+            delay,
+            Arc::new(move |data: &[u8], track: EgressTrack| {
+                let _ = match track {
+                    EgressTrack::Video => writer._write_rtp_now(data, true),
+                    EgressTrack::Audio => writer._write_rtp_now(data, false),
+                    EgressTrack::ScreenAudio => writer._write_screen_audio_rtp_now(data),
+                };
+            }),
+        ));
+    }
+
+    /// Panic cut, a no-op for writers without a delay as they have nothing held back
+    pub fn set_broadcast_cut(&self, is_cut: bool) {
+        let Some(delay) = self.broadcast_delay.get() else {
+            return;
+        };
+
+        if is_cut {
+            delay.cut();
+        } else {
+            delay.restore();
+        }
+    }
+
+    pub fn pause(&self) {
+        if self.clock.lock().unwrap().pause() {
+            tracing::info!("Recording paused");
+            self._save_session();
+        }
+    }
+
+    pub fn resume(&self) {
+        if self.clock.lock().unwrap().resume() {
+            tracing::info!("Recording resumed");
+            self._save_session();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.clock.lock().unwrap().is_paused()
     }
 
     pub fn write_rtp(&self, data: &[u8], is_video: bool) -> Result<(), anyhow::Error> {
+        // Packets received while paused are dropped, the timeline resumes where it left off
+        if self.is_paused() {
+            return Ok(());
+        }
+
+        if let Some(delay) = self.broadcast_delay.get() {
+            let track = if is_video {
+                EgressTrack::Video
+            } else {
+                EgressTrack::Audio
+            };
+            delay.push(data, track);
+            return Ok(());
+        }
+
+        self._write_rtp_now(data, is_video)
+    }
+
+    /// Screen-share system audio, mixed with the microphone into the audio rendition
+    pub fn write_screen_audio_rtp(&self, data: &[u8]) -> Result<(), anyhow::Error> {
+        if self.is_paused() {
+            return Ok(());
+        }
+
+        if let Some(delay) = self.broadcast_delay.get() {
+            delay.push(data, EgressTrack::ScreenAudio);
+            return Ok(());
+        }
+
+        self._write_screen_audio_rtp_now(data)
+    }
+
+    fn _write_rtp_now(&self, data: &[u8], is_video: bool) -> Result<(), anyhow::Error> {
+        let start_time = self.clock.lock().unwrap().effective_start_time();
+
         if is_video {
             {
                 let state_lock = self.state.lock().unwrap();
 
                 for stream in &state_lock.video_streams {
-                    let _ = stream.write_rtp(data, self.start_time, self.video_offset.clone());
+                    let _ = stream.write_rtp(data, start_time, self.video_offset.clone());
                 }
             }
         } else {
@@ -210,7 +327,7 @@ impl HlsWriter {
                 let state_lock = self.state.lock().unwrap();
 
                 for stream in &state_lock.audio_streams {
-                    let _ = stream.write_rtp(data, self.start_time, self.audio_offset.clone());
+                    let _ = stream.write_rtp(data, start_time, self.audio_offset.clone());
                 }
             }
         }
@@ -218,20 +335,16 @@ impl HlsWriter {
         Ok(())
     }
 
-    fn _get_r2_config(path_prefix: String) -> Option<R2Config> {
-        dotenvy::dotenv().ok();
+    fn _write_screen_audio_rtp_now(&self, data: &[u8]) -> Result<(), anyhow::Error> {
+        let start_time = self.clock.lock().unwrap().effective_start_time();
 
-        let account_id = env::var("STORAGE_ACCOUNT_ID").ok()?;
-        let bucket_name = env::var("STORAGE_BUCKET_NAME").ok()?;
-        let custom_domain = env::var("STORAGE_CUSTOM_DOMAIN").ok();
+        let state_lock = self.state.lock().unwrap();
 
-        let r2_config = R2Config {
-            account_id,
-            bucket_name,
-            custom_domain,
-            path_prefix: Some(path_prefix),
-        };
+        for stream in &state_lock.audio_streams {
+            let _ =
+                stream.write_screen_audio_rtp(data, start_time, self.screen_audio_offset.clone());
+        }
 
-        Some(r2_config)
+        Ok(())
     }
 }
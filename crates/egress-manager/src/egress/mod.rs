@@ -1,4 +1,7 @@
+pub mod broadcast_delay;
 pub mod hls_writer;
 pub mod moq_writer;
+pub mod slate_writer;
+pub mod track_archive;
 // pub mod temp;
 pub mod utils;
@@ -1,7 +1,7 @@
 use std::{
     path::PathBuf,
-    sync::{Arc, Mutex},
-    time::Instant,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
 };
 
 use anyhow::Ok;
@@ -10,7 +10,10 @@ use tokio::task;
 
 use crate::egress::utils::{AudioStreamExt, VideoStreamExt, init};
 
-use super::utils::{AudioStream, State, VideoStream};
+use super::{
+    broadcast_delay::{BroadcastDelay, EgressTrack},
+    utils::{AudioStream, State, VideoStream},
+};
 
 #[derive(Debug, Clone)]
 pub struct MoQWriter {
@@ -19,6 +22,7 @@ pub struct MoQWriter {
     start_time: Instant,
     video_offset: Arc<Mutex<u64>>,
     audio_offset: Arc<Mutex<u64>>,
+    broadcast_delay: Arc<OnceLock<BroadcastDelay>>,
 }
 
 impl MoQWriter {
@@ -50,6 +54,7 @@ impl MoQWriter {
                 default: true,
                 wave: "sine".to_string(),
                 audio_src: None,
+                screen_audio_src: None,
             }],
             all_mimes: vec![],
             path: manifest_path.clone(),
@@ -84,6 +89,7 @@ impl MoQWriter {
             start_time: Instant::now(),
             video_offset: Arc::new(Mutex::new(0)),
             audio_offset: Arc::new(Mutex::new(0)),
+            broadcast_delay: Arc::new(OnceLock::new()),
         };
 
         let hls_writer_arc = Arc::new(this.clone());
@@ -156,10 +162,55 @@ impl MoQWriter {
     }
 
     pub fn stop(&self) {
+        if let Some(delay) = self.broadcast_delay.get() {
+            delay.stop();
+        }
+
         let _ = self.pipeline.set_state(gst::State::Null);
     }
 
+    /// Holds the stream back by `delay` from here on, set once per writer
+    pub fn set_broadcast_delay(&self, delay: Duration) {
+        if delay.is_zero() || self.broadcast_delay.get().is_some() {
+            return;
+        }
+
+        let writer = self.clone();
+        let _ = self.broadcast_delay.set(BroadcastDelay::new(
+            delay,
+            Arc::new(move |data: &[u8], track: EgressTrack| {
+                let _ = writer._write_rtp_now(data, track == EgressTrack::Video);
+            }),
+        ));
+    }
+
+    pub fn set_broadcast_cut(&self, is_cut: bool) {
+        let Some(delay) = self.broadcast_delay.get() else {
+            return;
+        };
+
+        if is_cut {
+            delay.cut();
+        } else {
+            delay.restore();
+        }
+    }
+
     pub fn write_rtp(&self, data: &[u8], is_video: bool) -> Result<(), anyhow::Error> {
+        if let Some(delay) = self.broadcast_delay.get() {
+            let track = if is_video {
+                EgressTrack::Video
+            } else {
+                EgressTrack::Audio
+            };
+            delay.push(data, track);
+            return Ok(());
+        }
+
+        self._write_rtp_now(data, is_video)
+    }
+
+    fn _write_rtp_now(&self, data: &[u8], is_video: bool) -> Result<(), anyhow::Error> {
         if is_video {
             {
                 let state_lock = self.state.lock().unwrap();
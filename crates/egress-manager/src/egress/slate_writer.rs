@@ -0,0 +1,203 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use anyhow::Ok;
+use gst::prelude::*;
+use tokio::task;
+
+use super::utils::init;
+
+const SLATE_WIDTH: i32 = 1280;
+const SLATE_HEIGHT: i32 = 720;
+const SLATE_FPS: i32 = 30;
+const SEGMENT_DURATION_SECS: u32 = 2;
+
+/// Pre-roll of a scheduled live stream: loops an image or video (black when none
+/// is configured) into an HLS playlist until the host goes live.
+///
+/// The slate is written to `<dir>/<room_id>/slate/`, apart from the per-publisher
+/// playlists, so it can be torn down without touching the live output.
+#[derive(Debug, Clone)]
+pub struct SlateWriter {
+    pipeline: gst::Pipeline,
+    is_stopped: Arc<AtomicBool>,
+}
+
+impl SlateWriter {
+    pub fn new(dir: &str, room_id: &str, slate_uri: Option<&str>) -> Result<Self, anyhow::Error> {
+        init()?;
+
+        let path = PathBuf::from(dir).join(room_id).join("slate");
+        std::fs::create_dir_all(&path)?;
+
+        let pipeline = gst::Pipeline::default();
+
+        let convert = gst::ElementFactory::make("videoconvert").build()?;
+        let scale = gst::ElementFactory::make("videoscale").build()?;
+        let rate = gst::ElementFactory::make("videorate").build()?;
+        let caps = gst::ElementFactory::make("capsfilter")
+            .property(
+                "caps",
+                gst::Caps::builder("video/x-raw")
+                    .field("width", SLATE_WIDTH)
+                    .field("height", SLATE_HEIGHT)
+                    .field("framerate", gst::Fraction::new(SLATE_FPS, 1))
+                    .build(),
+            )
+            .build()?;
+        let encoder = gst::ElementFactory::make("x264enc")
+            .property_from_str("tune", "zerolatency")
+            .property("key-int-max", (SLATE_FPS as u32) * SEGMENT_DURATION_SECS)
+            .build()?;
+        let parse = gst::ElementFactory::make("h264parse").build()?;
+        let sink = gst::ElementFactory::make("hlssink2")
+            .property(
+                "location",
+                path.join("segment%05d.ts").to_string_lossy().to_string(),
+            )
+            .property(
+                "playlist-location",
+                path.join("playlist.m3u8").to_string_lossy().to_string(),
+            )
+            .property("target-duration", SEGMENT_DURATION_SECS)
+            .build()?;
+
+        pipeline.add_many([&convert, &scale, &rate, &caps, &encoder, &parse, &sink])?;
+        gst::Element::link_many([&convert, &scale, &rate, &caps, &encoder, &parse])?;
+        parse.link_pads(None, &sink, Some("video"))?;
+
+        match slate_uri {
+            Some(uri) if Self::_is_image(uri) => {
+                // A still image decodes to a single frame, imagefreeze repeats it live
+                let source = gst::ElementFactory::make("uridecodebin")
+                    .property("uri", uri)
+                    .build()?;
+                let image_convert = gst::ElementFactory::make("videoconvert").build()?;
+                let freeze = gst::ElementFactory::make("imagefreeze")
+                    .property("is-live", true)
+                    .build()?;
+
+                pipeline.add_many([&source, &image_convert, &freeze])?;
+                gst::Element::link_many([&image_convert, &freeze, &convert])?;
+                Self::_link_video_pad(&source, &image_convert);
+            }
+            Some(uri) => {
+                let source = gst::ElementFactory::make("uridecodebin")
+                    .property("uri", uri)
+                    .build()?;
+
+                pipeline.add(&source)?;
+                Self::_link_video_pad(&source, &convert);
+            }
+            None => {
+                let source = gst::ElementFactory::make("videotestsrc")
+                    .property("is-live", true)
+                    .property_from_str("pattern", "black")
+                    .build()?;
+
+                pipeline.add(&source)?;
+                source.link(&convert)?;
+            }
+        }
+
+        let this = Self {
+            pipeline,
+            is_stopped: Arc::new(AtomicBool::new(false)),
+        };
+
+        task::spawn_blocking({
+            let pipeline = this.pipeline.clone();
+            let is_stopped = Arc::clone(&this.is_stopped);
+            move || Self::run_pipeline_blocking(pipeline, is_stopped)
+        });
+
+        Ok(this)
+    }
+
+    /// Ends the playlist cleanly, players that were on the slate see it finish
+    pub fn stop(&self) {
+        if !self.is_stopped.swap(true, Ordering::Relaxed) {
+            self.pipeline.send_event(gst::event::Eos::new());
+        }
+    }
+
+    fn _is_image(uri: &str) -> bool {
+        let uri = uri.to_lowercase();
+        [".png", ".jpg", ".jpeg", ".webp"]
+            .iter()
+            .any(|ext| uri.ends_with(ext))
+    }
+
+    /// Decoders expose their pads once the stream is typed, only video is used
+    fn _link_video_pad(source: &gst::Element, next: &gst::Element) {
+        let next = next.downgrade();
+
+        source.connect_pad_added(move |_, pad| {
+            let Some(next) = next.upgrade() else {
+                return;
+            };
+
+            let is_video = pad
+                .current_caps()
+                .and_then(|caps| caps.structure(0).map(|s| s.name().starts_with("video/")))
+                .unwrap_or(false);
+
+            let Some(sink_pad) = next.static_pad("sink") else {
+                return;
+            };
+
+            if is_video && !sink_pad.is_linked() {
+                let _ = pad.link(&sink_pad);
+            }
+        });
+    }
+
+    fn run_pipeline_blocking(
+        pipeline: gst::Pipeline,
+        is_stopped: Arc<AtomicBool>,
+    ) -> Result<(), anyhow::Error> {
+        pipeline.set_state(gst::State::Playing)?;
+
+        let bus = pipeline
+            .bus()
+            .expect("Pipeline without bus. Shouldn't happen!");
+
+        for msg in bus.iter_timed(gst::ClockTime::NONE) {
+            use gst::MessageView;
+
+            match msg.view() {
+                // A slate video loops until the host goes live
+                MessageView::Eos(..) => {
+                    if is_stopped.load(Ordering::Relaxed)
+                        || pipeline
+                            .seek_simple(gst::SeekFlags::FLUSH, gst::ClockTime::ZERO)
+                            .is_err()
+                    {
+                        break;
+                    }
+                }
+                MessageView::Error(err) => {
+                    tracing::warn!(
+                        "Slate error from {}: {} ({})",
+                        msg.src()
+                            .map(|s| String::from(s.path_string()))
+                            .unwrap_or_else(|| "None".into()),
+                        err.error(),
+                        err.debug().unwrap_or_else(|| "".into()),
+                    );
+                    break;
+                }
+                _ => (),
+            }
+        }
+
+        let _ = pipeline.set_state(gst::State::Null);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,432 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use anyhow::Ok;
+use chrono::Utc;
+use gst::prelude::*;
+use gst_app::AppSrc;
+use serde::Serialize;
+use tokio::task;
+
+use super::utils::{PausedInterval, R2Config, R2Storage, RecordingClock, init};
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Written next to the track files, lets an editor line the files up again.
+/// With R2 configured both are uploaded, the manifest on every change and each
+/// file once it is closed.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArchiveManifest {
+    room_id: String,
+    participant_id: String,
+    files: Vec<ArchivedFile>,
+    /// Packets aren't written while paused, so every file has a gap here
+    paused_intervals: Vec<PausedInterval>,
+}
+
+impl ArchiveManifest {
+    /// Adds a file for the track, numbered after the files it already had, returns its index
+    fn add_file(
+        &mut self,
+        track_id: &str,
+        mime_type: &str,
+        clock_rate: u32,
+    ) -> Result<usize, anyhow::Error> {
+        let (kind, codec) = mime_type
+            .split_once('/')
+            .map(|(kind, codec)| (kind.to_lowercase(), codec.to_lowercase()))
+            .ok_or_else(|| anyhow::anyhow!("Invalid mime type {mime_type}"))?;
+
+        let safe_id: String = track_id
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+            .collect();
+
+        let take = self
+            .files
+            .iter()
+            .filter(|file| file.track_id == track_id)
+            .count();
+
+        self.files.push(ArchivedFile {
+            track_id: track_id.to_owned(),
+            file: format!("{kind}_{safe_id}_{take}.mkv"),
+            kind,
+            codec,
+            clock_rate,
+            started_at: None,
+            first_rtp_timestamp: None,
+            ended_at: None,
+        });
+
+        Ok(self.files.len() - 1)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedFile {
+    pub track_id: String,
+    pub kind: String,
+    pub codec: String,
+    pub file: String,
+    pub clock_rate: u32,
+    /// Wall clock of the first packet in unix milliseconds, the common timeline across files
+    pub started_at: Option<i64>,
+    /// RTP timestamp of that first packet
+    pub first_rtp_timestamp: Option<u32>,
+    pub ended_at: Option<i64>,
+}
+
+/// Raw archive of one publisher: every track goes to its own Matroska file as it
+/// was received, without transcoding or compositing.
+///
+/// A track that reappears after a renegotiation starts a new file instead of
+/// appending, so each file stays a single continuous stream.
+#[derive(Debug, Clone)]
+pub struct TrackArchive {
+    dir: PathBuf,
+    manifest: Arc<Mutex<ArchiveManifest>>,
+    clock: Arc<Mutex<RecordingClock>>,
+    r2_storage: Option<Arc<R2Storage>>,
+}
+
+impl TrackArchive {
+    pub async fn new(
+        dir: &str,
+        room_id: &str,
+        participant_id: &str,
+    ) -> Result<Self, anyhow::Error> {
+        init()?;
+
+        let dir = PathBuf::from(dir).join(room_id).join(participant_id);
+        std::fs::create_dir_all(&dir)?;
+
+        let r2_storage = match R2Config::from_env(format!("archive/{room_id}/{participant_id}")) {
+            Some(config) => Some(R2Storage::start(config).await?),
+            None => None,
+        };
+
+        let this = Self {
+            dir,
+            manifest: Arc::new(Mutex::new(ArchiveManifest {
+                room_id: room_id.to_owned(),
+                participant_id: participant_id.to_owned(),
+                files: vec![],
+                paused_intervals: vec![],
+            })),
+            clock: Arc::new(Mutex::new(RecordingClock::new())),
+            r2_storage,
+        };
+
+        this.save_manifest();
+
+        Ok(this)
+    }
+
+    pub fn start_track(
+        &self,
+        track_id: &str,
+        mime_type: &str,
+        clock_rate: u32,
+    ) -> Result<Arc<TrackRecorder>, anyhow::Error> {
+        let (index, kind, codec, name) = {
+            let mut manifest = self.manifest.lock().unwrap();
+            let index = manifest.add_file(track_id, mime_type, clock_rate)?;
+
+            let file = &manifest.files[index];
+            (
+                index,
+                file.kind.clone(),
+                file.codec.clone(),
+                file.file.clone(),
+            )
+        };
+
+        let location = self.dir.join(&name);
+        let (pipeline, src) = Self::_build_pipeline(&kind, &codec, clock_rate, &location)?;
+
+        task::spawn_blocking({
+            let pipeline = pipeline.clone();
+            let archive = self.clone();
+            move || {
+                let result = TrackRecorder::run_pipeline_blocking(pipeline);
+                // Whatever was written, a file cut short by an error is still evidence
+                archive._upload(&name, &format!("{kind}/x-matroska"));
+                result
+            }
+        });
+
+        self.save_manifest();
+
+        // A track added mid-pause waits for the resume like the others
+        let is_paused = self.clock.lock().unwrap().is_paused();
+
+        Ok(Arc::new(TrackRecorder {
+            archive: self.clone(),
+            index,
+            src,
+            has_started: AtomicBool::new(false),
+            is_paused: AtomicBool::new(is_paused),
+            is_finished: AtomicBool::new(false),
+        }))
+    }
+
+    /// Records the pause in the manifest, the recorders of the tracks are paused on their own
+    pub fn set_paused(&self, is_paused: bool) {
+        let paused_intervals = {
+            let mut clock = self.clock.lock().unwrap();
+            let is_changed = if is_paused {
+                clock.pause()
+            } else {
+                clock.resume()
+            };
+
+            if !is_changed {
+                return;
+            }
+
+            clock.paused_intervals()
+        };
+
+        self.manifest.lock().unwrap().paused_intervals = paused_intervals;
+        self.save_manifest();
+    }
+
+    fn save_manifest(&self) {
+        let result = {
+            let manifest = self.manifest.lock().unwrap();
+
+            serde_json::to_vec_pretty(&*manifest)
+                .map_err(anyhow::Error::from)
+                .and_then(|json| Ok(std::fs::write(self.dir.join(MANIFEST_FILE), json)?))
+        };
+
+        match result {
+            Err(err) => tracing::warn!("Failed to write track archive manifest: {:?}", err),
+            Ok(()) => self._upload(MANIFEST_FILE, "application/json"),
+        }
+    }
+
+    /// Queues a file of the archive for upload, a no-op without R2
+    fn _upload(&self, name: &str, content_type: &str) {
+        let Some(r2_storage) = &self.r2_storage else {
+            return;
+        };
+
+        if let Err(err) = r2_storage.upload_file(&self.dir.join(name), name, content_type) {
+            tracing::warn!("Failed to queue upload of archive file {}: {:?}", name, err);
+        }
+    }
+
+    fn _build_pipeline(
+        kind: &str,
+        codec: &str,
+        clock_rate: u32,
+        location: &std::path::Path,
+    ) -> Result<(gst::Pipeline, AppSrc), anyhow::Error> {
+        let (encoding_name, depay, parse) = match codec {
+            "h264" => ("H264", "rtph264depay", Some("h264parse")),
+            "vp8" => ("VP8", "rtpvp8depay", None),
+            "vp9" => ("VP9", "rtpvp9depay", None),
+            "av1" => ("AV1", "rtpav1depay", Some("av1parse")),
+            "opus" => ("OPUS", "rtpopusdepay", Some("opusparse")),
+            _ => return Err(anyhow::anyhow!("Unsupported codec {codec}")),
+        };
+
+        let caps = gst::Caps::builder("application/x-rtp")
+            .field("media", kind)
+            .field("encoding-name", encoding_name)
+            .field("clock-rate", clock_rate as i32)
+            .build();
+
+        let src = AppSrc::builder()
+            .is_live(true)
+            .format(gst::Format::Time)
+            .do_timestamp(true)
+            .caps(&caps)
+            .build();
+
+        // Reorders and drops late packets, the timestamps written come from RTP
+        let jitter_buffer = gst::ElementFactory::make("rtpjitterbuffer").build()?;
+        let depay = gst::ElementFactory::make(depay).build()?;
+        let mux = gst::ElementFactory::make("matroskamux")
+            .property("streamable", false)
+            .build()?;
+        let sink = gst::ElementFactory::make("filesink")
+            .property("location", location.to_string_lossy().to_string())
+            .property("async", false)
+            .build()?;
+
+        let pipeline = gst::Pipeline::default();
+        pipeline.add_many([src.upcast_ref(), &jitter_buffer, &depay, &mux, &sink])?;
+
+        let mut chain = vec![
+            src.upcast_ref::<gst::Element>().clone(),
+            jitter_buffer,
+            depay,
+        ];
+        if let Some(parse) = parse {
+            let parse = gst::ElementFactory::make(parse).build()?;
+            pipeline.add(&parse)?;
+            chain.push(parse);
+        }
+        chain.push(mux);
+        chain.push(sink);
+
+        gst::Element::link_many(chain.iter())?;
+
+        Ok((pipeline, src))
+    }
+}
+
+/// Writes one track of a `TrackArchive`, created by `TrackArchive::start_track`
+#[derive(Debug)]
+pub struct TrackRecorder {
+    archive: TrackArchive,
+    index: usize,
+    src: AppSrc,
+    has_started: AtomicBool,
+    is_paused: AtomicBool,
+    is_finished: AtomicBool,
+}
+
+impl TrackRecorder {
+    pub fn write_rtp(&self, data: &[u8], rtp_timestamp: u32) -> Result<(), anyhow::Error> {
+        if self.is_paused.load(Ordering::Relaxed) || self.is_finished.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        if !self.has_started.swap(true, Ordering::Relaxed) {
+            {
+                let mut manifest = self.archive.manifest.lock().unwrap();
+                let file = &mut manifest.files[self.index];
+                file.started_at = Some(Utc::now().timestamp_millis());
+                file.first_rtp_timestamp = Some(rtp_timestamp);
+            }
+            self.archive.save_manifest();
+        }
+
+        self.src
+            .push_buffer(gst::Buffer::from_mut_slice(data.to_vec()))
+            .map_err(|err| anyhow::anyhow!("Failed to push track buffer: {err:?}"))?;
+
+        Ok(())
+    }
+
+    pub fn set_paused(&self, is_paused: bool) {
+        self.is_paused.store(is_paused, Ordering::Relaxed);
+    }
+
+    /// Closes the file, the muxer only writes a seekable index after EOS
+    pub fn finish(&self) {
+        if self.is_finished.swap(true, Ordering::Relaxed) {
+            return;
+        }
+
+        let _ = self.src.end_of_stream();
+
+        {
+            let mut manifest = self.archive.manifest.lock().unwrap();
+            manifest.files[self.index].ended_at = Some(Utc::now().timestamp_millis());
+        }
+        self.archive.save_manifest();
+    }
+
+    fn run_pipeline_blocking(pipeline: gst::Pipeline) -> Result<(), anyhow::Error> {
+        pipeline.set_state(gst::State::Playing)?;
+
+        let bus = pipeline
+            .bus()
+            .expect("Pipeline without bus. Shouldn't happen!");
+
+        for msg in bus.iter_timed(gst::ClockTime::NONE) {
+            use gst::MessageView;
+
+            match msg.view() {
+                MessageView::Eos(..) => break,
+                MessageView::Error(err) => {
+                    tracing::warn!(
+                        "Track archive error from {}: {} ({})",
+                        msg.src()
+                            .map(|s| String::from(s.path_string()))
+                            .unwrap_or_else(|| "None".into()),
+                        err.error(),
+                        err.debug().unwrap_or_else(|| "".into()),
+                    );
+                    break;
+                }
+                _ => (),
+            }
+        }
+
+        let _ = pipeline.set_state(gst::State::Null);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest() -> ArchiveManifest {
+        ArchiveManifest {
+            room_id: "room".to_owned(),
+            participant_id: "publisher".to_owned(),
+            files: vec![],
+            paused_intervals: vec![],
+        }
+    }
+
+    #[test]
+    fn test_file_is_named_after_the_track() {
+        let mut manifest = manifest();
+
+        let index = manifest.add_file("cam-1", "video/VP8", 90000).unwrap();
+
+        let file = &manifest.files[index];
+        assert_eq!(file.kind, "video");
+        assert_eq!(file.codec, "vp8");
+        assert_eq!(file.file, "video_cam-1_0.mkv");
+        assert_eq!(file.clock_rate, 90000);
+        assert!(file.started_at.is_none());
+    }
+
+    #[test]
+    fn test_track_id_is_made_safe_for_the_file_name() {
+        let mut manifest = manifest();
+
+        let index = manifest
+            .add_file("{../mic 1}", "audio/opus", 48000)
+            .unwrap();
+
+        assert_eq!(manifest.files[index].file, "audio_mic1_0.mkv");
+    }
+
+    #[test]
+    fn test_reappearing_track_starts_a_new_file() {
+        let mut manifest = manifest();
+
+        manifest.add_file("cam", "video/H264", 90000).unwrap();
+        manifest.add_file("mic", "audio/opus", 48000).unwrap();
+        let index = manifest.add_file("cam", "video/H264", 90000).unwrap();
+
+        assert_eq!(index, 2);
+        assert_eq!(manifest.files[index].file, "video_cam_1.mkv");
+    }
+
+    #[test]
+    fn test_invalid_mime_type_is_rejected() {
+        let mut manifest = manifest();
+
+        assert!(manifest.add_file("cam", "vp8", 90000).is_err());
+        assert!(manifest.files.is_empty());
+    }
+}
@@ -36,6 +36,13 @@ pub trait AudioStreamExt {
         start_time: Instant,
         offset: Arc<Mutex<u64>>,
     ) -> Result<(), Error>;
+
+    fn write_screen_audio_rtp(
+        &self,
+        data: &[u8],
+        start_time: Instant,
+        offset: Arc<Mutex<u64>>,
+    ) -> Result<(), Error>;
 }
 
 impl AudioStreamExt for AudioStream {
@@ -47,24 +54,9 @@ impl AudioStreamExt for AudioStream {
         pipeline: &gst::Pipeline,
         path: &Path,
     ) -> Result<(), Error> {
-        let caps = gst::Caps::builder("application/x-rtp")
-            .field("media", "audio")
-            .field("encoding-name", "OPUS")
-            .field("payload", 97i32)
-            .field("clock-rate", 48000i32)
-            .build();
-
-        let src = gst::ElementFactory::make("appsrc")
-            .property("is-live", true)
-            .property("format", gst::Format::Time)
-            .property("do-timestamp", true)
-            .property("caps", caps)
-            .build()?;
-
-        let rtp_depay = gst::ElementFactory::make("rtpopusdepay").build()?;
-        let opusdec = gst::ElementFactory::make("opusdec").build()?;
+        // Microphone and screen-share audio are decoded separately and mixed into one rendition
+        let mixer = gst::ElementFactory::make("audiomixer").build()?;
         let audioconvert = gst::ElementFactory::make("audioconvert").build()?;
-        let audioresample = gst::ElementFactory::make("audioresample").build()?;
         let aacenc = gst::ElementFactory::make("avenc_aac").build()?;
         let aacparse = gst::ElementFactory::make("aacparse").build()?;
         let mux = gst::ElementFactory::make("cmafmux")
@@ -75,11 +67,8 @@ impl AudioStreamExt for AudioStream {
         let appsink = gst_app::AppSink::builder().buffer_list(true).build();
 
         pipeline.add_many([
-            &src,
-            &rtp_depay,
-            &opusdec,
+            &mixer,
             &audioconvert,
-            &audioresample,
             &aacenc,
             &aacparse,
             &mux,
@@ -87,11 +76,8 @@ impl AudioStreamExt for AudioStream {
         ])?;
 
         gst::Element::link_many([
-            &src,
-            &rtp_depay,
-            &opusdec,
+            &mixer,
             &audioconvert,
-            &audioresample,
             &aacenc,
             &aacparse,
             &mux,
@@ -108,13 +94,8 @@ impl AudioStreamExt for AudioStream {
             setup_r2_appsink(&appsink, &self.name, path, false, r2_storage);
         };
 
-        let audio_src = src.downcast::<AppSrc>().expect("Element is not an AppSrc");
-
-        audio_src.set_is_live(true);
-        audio_src.set_stream_type(AppStreamType::Stream);
-        // audio_src.set_latency(ClockTime::from_mseconds(0), ClockTime::from_mseconds(200));
-
-        self.audio_src = Some(audio_src);
+        self.audio_src = Some(add_opus_input(pipeline, &mixer)?);
+        self.screen_audio_src = Some(add_opus_input(pipeline, &mixer)?);
 
         Ok(())
     }
@@ -198,61 +179,128 @@ impl AudioStreamExt for AudioStream {
         start_time: Instant,
         offset: Arc<Mutex<u64>>,
     ) -> Result<(), Error> {
-        // Create a GStreamer buffer from the RTP packet data
-        let mut buffer = gst::Buffer::from_mut_slice(data.to_vec());
-        // Get the current elapsed time since the stream started
-        let now = start_time.elapsed().as_nanos() as u64;
-
-        // Lock the offset mutex to update and get the current offset
-        let mut offset_lock = offset.lock().unwrap();
-        let offset = *offset_lock;
-        let offset_end = offset + data.len() as u64;
-
-        {
-            // Get a mutable reference to the buffer's metadata
-            let buffer_mut = buffer
-                .get_mut()
-                .ok_or_else(|| anyhow::anyhow!("Failed to get mutable buffer"))?;
-            // Set PTS and DTS based on elapsed time.
-            // Note: For accurate synchronization with audio or other streams,
-            // you might need to use the RTP timestamps from the packet itself
-            // and convert them to GStreamer time.
-            buffer_mut.set_pts(gst::ClockTime::from_nseconds(now));
-            buffer_mut.set_dts(gst::ClockTime::from_nseconds(now));
-            // Mark the buffer as live data
-            buffer_mut.set_flags(BufferFlags::LIVE);
-            // Set the buffer offset and end offset (useful for tracking data flow)
-            buffer_mut.set_offset(offset);
-            buffer_mut.set_offset_end(offset_end);
-        }
+        push_rtp(self.audio_src.as_ref(), data, start_time, offset)
+    }
 
-        // Update the offset for the next buffer
-        *offset_lock = offset_end;
-
-        match &self.audio_src {
-            Some(src) => {
-                // Push the buffer to the appsrc element
-                let result = src.push_buffer(buffer);
-
-                // Handle the result of the push operation
-                match result {
-                    Ok(gst::FlowSuccess::Ok) => Ok(()), // Buffer pushed successfully
-                    Ok(other_flow) => {
-                        // Handle other successful flow states if necessary
-                        error!("Unexpected FlowReturn from audio_src: {:?}", other_flow);
-                        Err(anyhow::anyhow!(
-                            "Unexpected GStreamer FlowReturn: {:?}",
-                            other_flow
-                        ))
-                    }
-                    Err(err) => {
-                        // Handle errors during buffer push
-                        error!("Failed to push RTP packet to audio_src: {:?}", err);
-                        Err(anyhow::Error::from(err))
-                    }
+    fn write_screen_audio_rtp(
+        &self,
+        data: &[u8],
+        start_time: Instant,
+        offset: Arc<Mutex<u64>>,
+    ) -> Result<(), Error> {
+        push_rtp(self.screen_audio_src.as_ref(), data, start_time, offset)
+    }
+}
+
+/// Depayload and decode one Opus RTP input and feed it into the mixer
+fn add_opus_input(pipeline: &gst::Pipeline, mixer: &gst::Element) -> Result<AppSrc, Error> {
+    let caps = gst::Caps::builder("application/x-rtp")
+        .field("media", "audio")
+        .field("encoding-name", "OPUS")
+        .field("payload", 97i32)
+        .field("clock-rate", 48000i32)
+        .build();
+
+    let src = gst::ElementFactory::make("appsrc")
+        .property("is-live", true)
+        .property("format", gst::Format::Time)
+        .property("do-timestamp", true)
+        .property("caps", caps)
+        .build()?;
+
+    let rtp_depay = gst::ElementFactory::make("rtpopusdepay").build()?;
+    let opusdec = gst::ElementFactory::make("opusdec").build()?;
+    let audioconvert = gst::ElementFactory::make("audioconvert").build()?;
+    let audioresample = gst::ElementFactory::make("audioresample").build()?;
+    let queue = gst::ElementFactory::make("queue").build()?;
+
+    pipeline.add_many([
+        &src,
+        &rtp_depay,
+        &opusdec,
+        &audioconvert,
+        &audioresample,
+        &queue,
+    ])?;
+
+    gst::Element::link_many([
+        &src,
+        &rtp_depay,
+        &opusdec,
+        &audioconvert,
+        &audioresample,
+        &queue,
+        mixer,
+    ])?;
+
+    let audio_src = src.downcast::<AppSrc>().expect("Element is not an AppSrc");
+
+    audio_src.set_is_live(true);
+    audio_src.set_stream_type(AppStreamType::Stream);
+
+    Ok(audio_src)
+}
+
+fn push_rtp(
+    src: Option<&AppSrc>,
+    data: &[u8],
+    start_time: Instant,
+    offset: Arc<Mutex<u64>>,
+) -> Result<(), Error> {
+    // Create a GStreamer buffer from the RTP packet data
+    let mut buffer = gst::Buffer::from_mut_slice(data.to_vec());
+    // Get the current elapsed time since the stream started
+    let now = start_time.elapsed().as_nanos() as u64;
+
+    // Lock the offset mutex to update and get the current offset
+    let mut offset_lock = offset.lock().unwrap();
+    let offset = *offset_lock;
+    let offset_end = offset + data.len() as u64;
+
+    {
+        // Get a mutable reference to the buffer's metadata
+        let buffer_mut = buffer
+            .get_mut()
+            .ok_or_else(|| anyhow::anyhow!("Failed to get mutable buffer"))?;
+        // Set PTS and DTS based on elapsed time.
+        // Note: For accurate synchronization with audio or other streams,
+        // you might need to use the RTP timestamps from the packet itself
+        // and convert them to GStreamer time.
+        buffer_mut.set_pts(gst::ClockTime::from_nseconds(now));
+        buffer_mut.set_dts(gst::ClockTime::from_nseconds(now));
+        // Mark the buffer as live data
+        buffer_mut.set_flags(BufferFlags::LIVE);
+        // Set the buffer offset and end offset (useful for tracking data flow)
+        buffer_mut.set_offset(offset);
+        buffer_mut.set_offset_end(offset_end);
+    }
+
+    // Update the offset for the next buffer
+    *offset_lock = offset_end;
+
+    match src {
+        Some(src) => {
+            // Push the buffer to the appsrc element
+            let result = src.push_buffer(buffer);
+
+            // Handle the result of the push operation
+            match result {
+                Ok(gst::FlowSuccess::Ok) => Ok(()), // Buffer pushed successfully
+                Ok(other_flow) => {
+                    // Handle other successful flow states if necessary
+                    error!("Unexpected FlowReturn from audio_src: {:?}", other_flow);
+                    Err(anyhow::anyhow!(
+                        "Unexpected GStreamer FlowReturn: {:?}",
+                        other_flow
+                    ))
+                }
+                Err(err) => {
+                    // Handle errors during buffer push
+                    error!("Failed to push RTP packet to audio_src: {:?}", err);
+                    Err(anyhow::Error::from(err))
                 }
             }
-            None => Ok(()),
         }
+        None => Ok(()),
     }
 }
@@ -10,15 +10,13 @@ use super::{AudioStream, State, VideoStream, cloud_upload::R2Storage};
 pub struct R2MasterState {
     pub state: State,
     pub r2_storage: Arc<R2Storage>,
-    pub cloud_url_base: Option<String>,
 }
 
 impl R2MasterState {
-    pub fn new(path: PathBuf, r2_storage: Arc<R2Storage>, cloud_url_base: Option<String>) -> Self {
+    pub fn new(path: PathBuf, r2_storage: Arc<R2Storage>) -> Self {
         Self {
             state: State::new(path),
             r2_storage,
-            cloud_url_base,
         }
     }
 
@@ -38,7 +36,8 @@ impl R2MasterState {
         all_mimes.sort();
         all_mimes.dedup();
 
-        // First, create the master playlist for local storage
+        // Variant URIs stay relative, so the same playlist plays from the
+        // bucket, from a CDN in front of it, or through an HLS edge
         let playlist = MasterPlaylist {
             version: Some(7),
             variants: self
@@ -50,15 +49,8 @@ impl R2MasterState {
                     path.push(&stream.name);
                     path.push("manifest.m3u8");
 
-                    // If we have a cloud URL base, use it to create absolute URLs
-                    let uri = if let Some(base_url) = &self.cloud_url_base {
-                        format!("{}/{}/{}", base_url, stream.name, "manifest.m3u8")
-                    } else {
-                        path.as_path().display().to_string()
-                    };
-
                     VariantStream {
-                        uri,
+                        uri: path.as_path().display().to_string(),
                         bandwidth: stream.bitrate,
                         codecs: Some(all_mimes.join(",")),
                         resolution: Some(m3u8_rs::Resolution {
@@ -79,16 +71,9 @@ impl R2MasterState {
                     path.push(&stream.name);
                     path.push("manifest.m3u8");
 
-                    // If we have a cloud URL base, use it to create absolute URLs
-                    let uri = if let Some(base_url) = &self.cloud_url_base {
-                        format!("{}/{}/{}", base_url, stream.name, "manifest.m3u8")
-                    } else {
-                        path.as_path().display().to_string()
-                    };
-
                     AlternativeMedia {
                         media_type: AlternativeMediaType::Audio,
-                        uri: Some(uri),
+                        uri: Some(path.as_path().display().to_string()),
                         group_id: "audio".to_string(),
                         language: Some(stream.lang.clone()),
                         name: stream.name.clone(),
@@ -114,10 +99,10 @@ impl R2MasterState {
         // Mark as written in local state
         self.state.wrote_manifest = true;
 
-        // Now queue upload to R2
+        // Same name as the local copy, viewers ask for `{participant}/manifest.m3u8` either way
         self.r2_storage.upload_file(
             &self.state.path,
-            "master.m3u8",
+            "manifest.m3u8",
             "application/vnd.apple.mpegurl",
         )?;
 
@@ -11,7 +11,7 @@ use tokio::fs::File;
 use tokio::io::AsyncReadExt;
 use tokio::sync::mpsc;
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 /// Configuration for Cloudflare R2 storage
 pub struct R2Config {
     pub account_id: String,
@@ -20,6 +20,26 @@ pub struct R2Config {
     pub path_prefix: Option<String>,
 }
 
+impl R2Config {
+    /// None unless the storage account and bucket are configured
+    pub fn from_env(path_prefix: String) -> Option<Self> {
+        dotenvy::dotenv().ok();
+
+        Some(Self {
+            account_id: std::env::var("STORAGE_ACCOUNT_ID").ok()?,
+            bucket_name: std::env::var("STORAGE_BUCKET_NAME").ok()?,
+            custom_domain: std::env::var("STORAGE_CUSTOM_DOMAIN").ok(),
+            path_prefix: Some(path_prefix),
+        })
+    }
+}
+
+/// Playlists are rewritten every segment and the JSON records next to them on
+/// every change, so edges and CDNs may only hold them for a moment. Segment,
+/// init and archive files never change once uploaded.
+const PLAYLIST_CACHE_CONTROL: &str = "public, max-age=1";
+const SEGMENT_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
 /// Upload task message
 #[derive(Debug)]
 pub struct UploadTask {
@@ -29,6 +49,7 @@ pub struct UploadTask {
 }
 
 /// R2 storage manager for handling uploads
+#[derive(Debug)]
 pub struct R2Storage {
     client: Client,
     pub config: R2Config,
@@ -63,6 +84,16 @@ impl R2Storage {
         Ok((storage, rx))
     }
 
+    /// Create a new R2Storage instance with its upload worker already running
+    pub async fn start(config: R2Config) -> Result<Arc<Self>> {
+        let (storage, receiver) = Self::new_with_worker(config).await?;
+        let storage = Arc::new(storage);
+
+        storage.clone().start_upload_worker(receiver);
+
+        Ok(storage)
+    }
+
     /// Start the upload worker task
     pub fn start_upload_worker(self: Arc<Self>, mut receiver: mpsc::UnboundedReceiver<UploadTask>) {
         tokio::spawn(async move {
@@ -107,6 +138,7 @@ impl R2Storage {
             .key(&key)
             .body(contents.into())
             .content_type(content_type)
+            .cache_control(cache_control(&key))
             .send()
             .await?;
 
@@ -174,25 +206,40 @@ impl R2Storage {
     }
 }
 
+fn cache_control(key: &str) -> &'static str {
+    if key.ends_with(".m3u8") || key.ends_with(".json") {
+        PLAYLIST_CACHE_CONTROL
+    } else {
+        SEGMENT_CACHE_CONTROL
+    }
+}
+
 /// Extended StreamState for R2 storage integration
 pub struct R2StreamState {
     pub state: StreamState,
     pub r2_storage: Arc<R2Storage>,
+    /// Stream directory, the bucket mirrors the local layout below the prefix
+    pub name: String,
     pub uploaded_segments: Vec<String>,
     pub manifest_url: Option<String>,
 }
 
 impl R2StreamState {
     /// Create a new R2StreamState with R2 storage integration
-    pub fn new(path: PathBuf, r2_storage: Arc<R2Storage>) -> Self {
+    pub fn new(path: PathBuf, name: &str, r2_storage: Arc<R2Storage>) -> Self {
         Self {
             state: StreamState::new(path),
             r2_storage,
+            name: name.to_string(),
             uploaded_segments: Vec::new(),
             manifest_url: None,
         }
     }
 
+    fn _key(&self, file: &str) -> String {
+        format!("{}/{file}", self.name)
+    }
+
     /// Add a segment and queue it for upload to R2
     pub fn add_segment(&mut self, segment: Segment) -> Result<()> {
         // Add segment to local state
@@ -215,7 +262,7 @@ impl R2StreamState {
         };
 
         self.r2_storage
-            .upload_file(&path, &segment.path, content_type)?;
+            .upload_file(&path, &self._key(&segment.path), content_type)?;
 
         Ok(())
     }
@@ -242,7 +289,7 @@ impl R2StreamState {
         };
 
         let r2_storage = Arc::clone(&self.r2_storage);
-        let segment_path = segment.path.clone();
+        let segment_path = self._key(&segment.path);
 
         // Use the message queue instead of tokio::spawn
         if let Err(e) = r2_storage.upload_file(&path, &segment_path, content_type) {
@@ -257,7 +304,7 @@ impl R2StreamState {
 
         if path.exists() {
             self.r2_storage
-                .upload_file(&path, "init.cmfi", "video/mp4")?;
+                .upload_file(&path, &self._key("init.cmfi"), "video/mp4")?;
             Ok(())
         } else {
             Err(anyhow::anyhow!("Initialization segment not found"))
@@ -270,7 +317,10 @@ impl R2StreamState {
         path.push("init.cmfi");
 
         if path.exists() {
-            if let Err(e) = self.r2_storage.upload_file(&path, "init.cmfi", "video/mp4") {
+            if let Err(e) = self
+                .r2_storage
+                .upload_file(&path, &self._key("init.cmfi"), "video/mp4")
+            {
                 eprintln!("Failed to queue init segment upload: {e}");
             }
         } else {
@@ -287,8 +337,11 @@ impl R2StreamState {
         let mut path = self.state.path.clone();
         path.push("manifest.m3u8");
 
-        self.r2_storage
-            .upload_file(&path, "manifest.m3u8", "application/vnd.apple.mpegurl")?;
+        self.r2_storage.upload_file(
+            &path,
+            &self._key("manifest.m3u8"),
+            "application/vnd.apple.mpegurl",
+        )?;
 
         Ok(())
     }
@@ -302,10 +355,11 @@ impl R2StreamState {
         let mut path = self.state.path.clone();
         path.push("manifest.m3u8");
 
-        if let Err(e) =
-            self.r2_storage
-                .upload_file(&path, "manifest.m3u8", "application/vnd.apple.mpegurl")
-        {
+        if let Err(e) = self.r2_storage.upload_file(
+            &path,
+            &self._key("manifest.m3u8"),
+            "application/vnd.apple.mpegurl",
+        ) {
             eprintln!("Failed to queue manifest upload: {e}");
         }
     }
@@ -338,7 +392,7 @@ pub fn setup_r2_appsink(
 
     let name_arc = Arc::new(name.to_string());
 
-    let state = Arc::new(Mutex::new(R2StreamState::new(path, r2_storage)));
+    let state = Arc::new(Mutex::new(R2StreamState::new(path, name, r2_storage)));
 
     appsink.set_callbacks(
         gst_app::AppSinkCallbacks::builder()
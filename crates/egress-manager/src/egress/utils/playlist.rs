@@ -53,7 +53,8 @@ pub fn update_manifest(state: &mut StreamState) {
             })
             .collect(),
         end_list: false,
-        playlist_type: Some(m3u8_rs::MediaPlaylistType::Vod),
+        // A live playlist, VOD ones may not change and can't be blocked on
+        playlist_type: None,
         i_frames_only: false,
         start: None,
         independent_segments: true,
@@ -0,0 +1,184 @@
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Wall clock interval during which a recording was paused
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PausedInterval {
+    pub started_at: DateTime<Utc>,
+    /// None while the recording is still paused
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+/// Tracks the running time of a recording with paused intervals cut out.
+///
+/// Buffers are timestamped relative to `effective_start_time`, which moves forward by
+/// the length of every pause, so the output timeline stays continuous instead of
+/// showing a frozen gap while the recording was paused.
+#[derive(Debug)]
+pub struct RecordingClock {
+    start_time: Instant,
+    paused_since: Option<Instant>,
+    paused_total: Duration,
+    paused_intervals: Vec<PausedInterval>,
+}
+
+impl Default for RecordingClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RecordingClock {
+    pub fn new() -> Self {
+        Self::started_at(Instant::now())
+    }
+
+    fn started_at(start_time: Instant) -> Self {
+        Self {
+            start_time,
+            paused_since: None,
+            paused_total: Duration::ZERO,
+            paused_intervals: Vec::new(),
+        }
+    }
+
+    /// Returns false if the recording was already paused
+    pub fn pause(&mut self) -> bool {
+        self.pause_at(Instant::now(), Utc::now())
+    }
+
+    fn pause_at(&mut self, now: Instant, wall_clock: DateTime<Utc>) -> bool {
+        if self.paused_since.is_some() {
+            return false;
+        }
+
+        self.paused_since = Some(now);
+        self.paused_intervals.push(PausedInterval {
+            started_at: wall_clock,
+            ended_at: None,
+        });
+
+        true
+    }
+
+    /// Returns false if the recording wasn't paused
+    pub fn resume(&mut self) -> bool {
+        self.resume_at(Instant::now(), Utc::now())
+    }
+
+    fn resume_at(&mut self, now: Instant, wall_clock: DateTime<Utc>) -> bool {
+        let Some(paused_since) = self.paused_since.take() else {
+            return false;
+        };
+
+        self.paused_total += now.saturating_duration_since(paused_since);
+
+        if let Some(interval) = self.paused_intervals.last_mut() {
+            interval.ended_at = Some(wall_clock);
+        }
+
+        true
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_since.is_some()
+    }
+
+    pub fn effective_start_time(&self) -> Instant {
+        self.start_time + self.paused_total
+    }
+
+    pub fn paused_intervals(&self) -> Vec<PausedInterval> {
+        self.paused_intervals.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn wall_clock(secs: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    #[test]
+    fn test_pause_moves_start_time_by_paused_duration() {
+        let start = Instant::now();
+        let mut clock = RecordingClock::started_at(start);
+
+        assert!(clock.pause_at(start + Duration::from_secs(10), wall_clock(10)));
+        assert!(clock.is_paused());
+        // Nothing moves until the pause ends
+        assert_eq!(clock.effective_start_time(), start);
+
+        assert!(clock.resume_at(start + Duration::from_secs(25), wall_clock(25)));
+        assert!(!clock.is_paused());
+        assert_eq!(
+            clock.effective_start_time(),
+            start + Duration::from_secs(15)
+        );
+
+        // A packet 30s after start lands 15s into the output timeline
+        let now = start + Duration::from_secs(30);
+        assert_eq!(now - clock.effective_start_time(), Duration::from_secs(15));
+    }
+
+    #[test]
+    fn test_pauses_accumulate() {
+        let start = Instant::now();
+        let mut clock = RecordingClock::started_at(start);
+
+        clock.pause_at(start + Duration::from_secs(5), wall_clock(5));
+        clock.resume_at(start + Duration::from_secs(8), wall_clock(8));
+        clock.pause_at(start + Duration::from_secs(20), wall_clock(20));
+        clock.resume_at(start + Duration::from_secs(27), wall_clock(27));
+
+        assert_eq!(
+            clock.effective_start_time(),
+            start + Duration::from_secs(10)
+        );
+        assert_eq!(
+            clock.paused_intervals(),
+            vec![
+                PausedInterval {
+                    started_at: wall_clock(5),
+                    ended_at: Some(wall_clock(8)),
+                },
+                PausedInterval {
+                    started_at: wall_clock(20),
+                    ended_at: Some(wall_clock(27)),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repeated_pause_and_resume_are_ignored() {
+        let start = Instant::now();
+        let mut clock = RecordingClock::started_at(start);
+
+        assert!(!clock.resume_at(start + Duration::from_secs(1), wall_clock(1)));
+
+        assert!(clock.pause_at(start + Duration::from_secs(2), wall_clock(2)));
+        assert!(!clock.pause_at(start + Duration::from_secs(4), wall_clock(4)));
+
+        // The interval still runs from the first pause
+        assert_eq!(
+            clock.paused_intervals(),
+            vec![PausedInterval {
+                started_at: wall_clock(2),
+                ended_at: None,
+            }]
+        );
+
+        assert!(clock.resume_at(start + Duration::from_secs(6), wall_clock(6)));
+        assert!(!clock.resume_at(start + Duration::from_secs(9), wall_clock(9)));
+        assert_eq!(clock.effective_start_time(), start + Duration::from_secs(4));
+        assert_eq!(clock.paused_intervals().len(), 1);
+    }
+}
@@ -128,6 +128,8 @@ pub struct AudioStream {
     pub default: bool,
     pub wave: String,
     pub audio_src: Option<AppSrc>,
+    /// System audio shared with the screen, mixed into the same rendition
+    pub screen_audio_src: Option<AppSrc>,
 }
 
 /// Probes the encoder to extract codec information
@@ -0,0 +1 @@
+pub mod noise_suppressor;
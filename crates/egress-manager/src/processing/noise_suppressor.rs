@@ -0,0 +1,174 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use gst::prelude::*;
+use gst_app::{AppSrc, AppStreamType};
+use tokio::task;
+
+use crate::egress::utils::init;
+
+/// Speech bitrate of the re-encoded stream, what browsers send for a microphone
+const OPUS_BITRATE: i32 = 32_000;
+
+pub type DenoisedPacketCallback = Arc<dyn Fn(&[u8]) + Send + Sync>;
+
+/// Runs a publisher's Opus RTP through RNNoise (`audiornnoise` from
+/// gst-plugins-rs) and hands back Opus RTP again, one packet out per packet
+/// in as both sides use 20ms frames. The packets come out with the pipeline's
+/// own headers, the caller is expected to put the publisher's back on.
+#[derive(Debug, Clone)]
+pub struct NoiseSuppressor {
+    pipeline: gst::Pipeline,
+    src: AppSrc,
+    is_stopped: Arc<AtomicBool>,
+}
+
+impl NoiseSuppressor {
+    pub fn new(payload_type: u8, on_packet: DenoisedPacketCallback) -> Result<Self, anyhow::Error> {
+        init()?;
+
+        let pipeline = gst::Pipeline::default();
+
+        let caps = gst::Caps::builder("application/x-rtp")
+            .field("media", "audio")
+            .field("encoding-name", "OPUS")
+            .field("payload", payload_type as i32)
+            .field("clock-rate", 48000i32)
+            .build();
+
+        let src = gst::ElementFactory::make("appsrc")
+            .property("is-live", true)
+            .property("format", gst::Format::Time)
+            .property("do-timestamp", true)
+            .property("caps", caps)
+            .build()?;
+        let depay = gst::ElementFactory::make("rtpopusdepay").build()?;
+        let decoder = gst::ElementFactory::make("opusdec")
+            .property("plc", true)
+            .build()?;
+        let convert = gst::ElementFactory::make("audioconvert").build()?;
+        let resample = gst::ElementFactory::make("audioresample").build()?;
+        let denoise = gst::ElementFactory::make("audiornnoise").build()?;
+        let encode_convert = gst::ElementFactory::make("audioconvert").build()?;
+        let encoder = gst::ElementFactory::make("opusenc")
+            .property_from_str("audio-type", "voice")
+            .property_from_str("frame-size", "20")
+            .property("bitrate", OPUS_BITRATE)
+            .build()?;
+        let pay = gst::ElementFactory::make("rtpopuspay")
+            .property("pt", payload_type as u32)
+            .build()?;
+        let appsink = gst_app::AppSink::builder().sync(false).build();
+
+        pipeline.add_many([
+            &src,
+            &depay,
+            &decoder,
+            &convert,
+            &resample,
+            &denoise,
+            &encode_convert,
+            &encoder,
+            &pay,
+            appsink.upcast_ref(),
+        ])?;
+        gst::Element::link_many([
+            &src,
+            &depay,
+            &decoder,
+            &convert,
+            &resample,
+            &denoise,
+            &encode_convert,
+            &encoder,
+            &pay,
+            appsink.upcast_ref(),
+        ])?;
+
+        appsink.set_callbacks(
+            gst_app::AppSinkCallbacks::builder()
+                .new_sample(move |sink| {
+                    let sample = sink.pull_sample().map_err(|_| gst::FlowError::Eos)?;
+                    let buffer = sample.buffer().ok_or(gst::FlowError::Error)?;
+                    let map = buffer.map_readable().map_err(|_| gst::FlowError::Error)?;
+
+                    on_packet(map.as_slice());
+
+                    Ok(gst::FlowSuccess::Ok)
+                })
+                .build(),
+        );
+
+        let src = src.downcast::<AppSrc>().expect("Element is not an AppSrc");
+        src.set_stream_type(AppStreamType::Stream);
+
+        let this = Self {
+            pipeline,
+            src,
+            is_stopped: Arc::new(AtomicBool::new(false)),
+        };
+
+        task::spawn_blocking({
+            let pipeline = this.pipeline.clone();
+            move || Self::run_pipeline_blocking(pipeline)
+        });
+
+        Ok(this)
+    }
+
+    pub fn write_rtp(&self, data: &[u8]) -> Result<(), anyhow::Error> {
+        if self.is_stopped.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        let mut buffer = gst::Buffer::from_mut_slice(data.to_vec());
+        buffer
+            .get_mut()
+            .ok_or_else(|| anyhow::anyhow!("Failed to get mutable buffer"))?
+            .set_flags(gst::BufferFlags::LIVE);
+
+        self.src.push_buffer(buffer)?;
+
+        Ok(())
+    }
+
+    pub fn stop(&self) {
+        if !self.is_stopped.swap(true, Ordering::Relaxed) {
+            let _ = self.src.end_of_stream();
+        }
+    }
+
+    fn run_pipeline_blocking(pipeline: gst::Pipeline) -> Result<(), anyhow::Error> {
+        pipeline.set_state(gst::State::Playing)?;
+
+        let bus = pipeline
+            .bus()
+            .expect("Pipeline without bus. Shouldn't happen!");
+
+        for msg in bus.iter_timed(gst::ClockTime::NONE) {
+            use gst::MessageView;
+
+            match msg.view() {
+                MessageView::Eos(..) => break,
+                MessageView::Error(err) => {
+                    tracing::warn!(
+                        "Noise suppression error from {}: {} ({})",
+                        msg.src()
+                            .map(|s| String::from(s.path_string()))
+                            .unwrap_or_else(|| "None".into()),
+                        err.error(),
+                        err.debug().unwrap_or_else(|| "".into()),
+                    );
+                    break;
+                }
+                _ => (),
+            }
+        }
+
+        let _ = pipeline.set_state(gst::State::Null);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,374 @@
+use serde::{Deserialize, Serialize};
+
+use crate::enums::ReportReason;
+
+/// Sent in the socket.io handshake `auth` payload, everything is optional so
+/// older clients still connect
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "salvo", derive(salvo::oapi::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct ClientMetadataDto {
+    /// App version, e.g. `2.4.1`
+    pub client_version: Option<String>,
+    /// `ios`, `android`, `web`, `macos`, `windows` or `linux`
+    pub platform: Option<String>,
+    pub device_model: Option<String>,
+    /// `wifi`, `cellular`, `ethernet`, as far as the client can tell
+    pub network_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct JoinRoomDto {
+    pub sdp: String,
+    /// Has to be the room the join token was minted for
+    pub room_id: String,
+    /// Minted by `POST rooms/{id}/join-token`, names the participant the
+    /// client publishes as. Good for one publish.
+    pub join_token: String,
+    pub is_video_enabled: bool,
+    pub is_audio_enabled: bool,
+    pub is_e2ee_enabled: bool,
+    pub total_tracks: u8,
+    /// Ignored, the room's type decides whether peers connect directly
+    #[serde(default)]
+    pub connection_type: u8,
+    #[serde(default)]
+    pub tracks: Vec<TrackInfoDto>,
+    /// The client suppresses noise itself, the server leaves its microphone be
+    #[serde(default)]
+    pub is_noise_suppressed: bool,
+}
+
+/// Declares what a published track is, so subscribers can tell a document camera from the webcam
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct TrackInfoDto {
+    pub track_id: String,
+    /// 0 camera, 1 microphone, 2 screen, 3 screen audio
+    #[serde(default)]
+    pub source: u8,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SubscribeDto {
+    pub target_id: String,
+    /// Ignored, the socket subscribes from the call it published in
+    pub room_id: String,
+    /// Ignored, like `room_id`
+    pub participant_id: String,
+    /// Video codecs the client decodes, like `vp8` or `video/H264`. Left out,
+    /// the target's video is forwarded whatever its codec.
+    #[serde(default)]
+    pub video_codecs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct AnswerSubscribeDto {
+    pub room_id: String,
+    pub target_id: String,
+    pub sdp: String,
+    /// Ignored, see `JoinRoomDto::connection_type`
+    #[serde(default)]
+    pub connection_type: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct PublisherRenegotiationDto {
+    pub sdp: String,
+    /// Ignored, the socket renegotiates the call it published in
+    pub room_id: String,
+    /// Ignored, see `JoinRoomDto::connection_type`
+    #[serde(default)]
+    pub connection_type: u8,
+    /// Ignored, `tracks` are announced as the socket's own participant
+    #[serde(default)]
+    pub participant_id: Option<String>,
+    /// Tracks added by this offer
+    #[serde(default)]
+    pub tracks: Vec<TrackInfoDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct MigrateConnectionDto {
+    pub sdp: String,
+    pub room_id: String,
+    pub participant_id: String,
+    /// Ignored, see `JoinRoomDto::connection_type`
+    #[serde(default)]
+    pub connection_type: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct CandidateDto {
+    pub candidate: String,
+    pub sdp_mid: Option<String>,
+    pub sdp_m_line_index: Option<u16>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct PublisherCandidateDto {
+    /// Ignored, see `JoinRoomDto::connection_type`
+    #[serde(default)]
+    pub connection_type: u8,
+    pub candidate: CandidateDto,
+    pub room_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriberCandidateDto {
+    pub target_id: String,
+    /// Ignored, see `JoinRoomDto::connection_type`
+    #[serde(default)]
+    pub connection_type: u8,
+    pub candidate: CandidateDto,
+    pub room_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SetEnabledDto {
+    pub is_enabled: bool,
+    /// Set when the OS or the device muted the track, e.g. a hardware mute
+    /// switch or a system privacy toggle. Left out, the last value is kept.
+    #[serde(default)]
+    pub is_hardware_muted: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SetScreenSharingDto {
+    pub is_sharing: bool,
+    pub screen_track_id: Option<String>,
+    /// Set when the client also shares system audio
+    #[serde(default)]
+    pub screen_audio_track_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SetCameraTypeDto {
+    #[serde(rename = "type")]
+    pub type_: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SetHandRaisingDto {
+    pub is_raising: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SetStageModeDto {
+    pub room_id: String,
+    pub is_enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SetSpeakerDto {
+    pub room_id: String,
+    pub participant_id: String,
+    pub is_speaker: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SetQosClassDto {
+    pub room_id: String,
+    pub participant_id: String,
+    /// 0 attendee, 1 presenter, 2 host
+    pub qos_class: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SetRecordingPausedDto {
+    pub room_id: String,
+    pub is_paused: bool,
+}
+
+/// Panic cut of a delayed broadcast, `is_cut: false` puts it back on air
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SetBroadcastCutDto {
+    pub room_id: String,
+    pub is_cut: bool,
+}
+
+/// Switches a running room between a conference, `0`, and a live stream, `1`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SetRoomTypeDto {
+    pub room_id: String,
+    pub room_type: i16,
+}
+
+/// The video tiles a client has on screen, sent whenever its layout changes.
+/// Anyone it subscribes to without a tile here stops getting video to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct ViewportUpdateDto {
+    pub tiles: Vec<ViewportTileDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct ViewportTileDto {
+    pub participant_id: String,
+    /// Rendered size in CSS pixels
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Compact snapshot of a client's `RTCPeerConnection.getStats()` report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct ClientStatsDto {
+    pub room_id: String,
+    pub participant_id: String,
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub timestamp: i64,
+    pub rtt_ms: Option<f64>,
+    pub jitter_ms: Option<f64>,
+    pub packet_loss: Option<f64>,
+    pub available_outgoing_bitrate: Option<f64>,
+    #[serde(default)]
+    pub tracks: Vec<TrackStatsDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct TrackStatsDto {
+    pub track_id: String,
+    pub kind: String,
+    pub direction: String,
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub bytes: u64,
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub packets: u64,
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub packets_lost: i64,
+    pub jitter_ms: Option<f64>,
+    pub frames_per_second: Option<f64>,
+}
+
+/// Sent on `/chat` to start or stop receiving the chat of a room
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct ChatRoomDto {
+    pub room_id: String,
+}
+
+/// Sent on `/chat` to react to the call, open to its HLS viewers as well
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct ReactionDto {
+    pub room_id: String,
+    pub emoji: String,
+}
+
+/// Sent by bots to start receiving the chat and events of a room they are a member of
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct BotJoinRoomDto {
+    pub room_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct BotMessageDto {
+    pub room_id: String,
+    pub data: String,
+}
+
+/// In-call version of `POST /rooms/{room_id}/reports`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct RoomReportDto {
+    pub room_id: String,
+    pub target_id: String,
+    pub reason: ReportReason,
+    pub details: Option<String>,
+    pub snapshot_url: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_join_room_optional_fields_default() {
+        let dto: JoinRoomDto = serde_json::from_value(json!({
+            "sdp": "v=0",
+            "roomId": "room",
+            "joinToken": "token",
+            "isVideoEnabled": true,
+            "isAudioEnabled": false,
+            "isE2eeEnabled": false,
+            "totalTracks": 2,
+        }))
+        .unwrap();
+
+        assert_eq!(dto.room_id, "room");
+        assert_eq!(dto.connection_type, 0);
+        assert!(dto.tracks.is_empty());
+        assert!(!dto.is_noise_suppressed);
+    }
+
+    #[test]
+    fn test_client_metadata_fields_are_optional() {
+        let client: ClientMetadataDto = serde_json::from_value(json!({
+            "clientVersion": "2.4.1",
+        }))
+        .unwrap();
+
+        assert_eq!(
+            client,
+            ClientMetadataDto {
+                client_version: Some("2.4.1".to_string()),
+                ..Default::default()
+            }
+        );
+    }
+}
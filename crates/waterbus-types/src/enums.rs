@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+/// Why a participant was reported, moderators triage the queue by it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "salvo", derive(salvo::oapi::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum ReportReason {
+    Harassment,
+    HateSpeech,
+    Spam,
+    /// Nudity, violence or other explicit media on camera or screen share
+    InappropriateContent,
+    Impersonation,
+    Other,
+}
+
+impl ReportReason {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            ReportReason::Harassment => "harassment",
+            ReportReason::HateSpeech => "hate_speech",
+            ReportReason::Spam => "spam",
+            ReportReason::InappropriateContent => "inappropriate_content",
+            ReportReason::Impersonation => "impersonation",
+            ReportReason::Other => "other",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wire_name_is_to_str() {
+        for reason in [
+            ReportReason::Harassment,
+            ReportReason::HateSpeech,
+            ReportReason::Spam,
+            ReportReason::InappropriateContent,
+            ReportReason::Impersonation,
+            ReportReason::Other,
+        ] {
+            let json = serde_json::to_string(&reason).unwrap();
+            assert_eq!(json, format!("\"{}\"", reason.to_str()));
+            assert_eq!(serde_json::from_str::<ReportReason>(&json).unwrap(), reason);
+        }
+    }
+}
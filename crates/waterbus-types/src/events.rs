@@ -0,0 +1,258 @@
+use serde::{Deserialize, Serialize};
+
+/// Socket.io events, client to server and back. The wire name is what
+/// `to_str` gives and what the enum (de)serializes as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub enum WsEvent {
+    #[serde(rename = "room.publish")]
+    RoomPublish,
+    #[serde(rename = "room.subscribe")]
+    RoomSubscribe,
+    #[serde(rename = "room.answer_subscriber")]
+    RoomAnswerSubscriber,
+    #[serde(rename = "room.leave")]
+    RoomLeave,
+    #[serde(rename = "room.reconnect")]
+    RoomReconnect,
+    #[serde(rename = "room.migrate")]
+    RoomMigrate,
+    #[serde(rename = "room.resync")]
+    RoomResync,
+
+    #[serde(rename = "room.publisher_renegotiation")]
+    RoomPublisherRenegotiation,
+    #[serde(rename = "room.subscriber_renegotiation")]
+    RoomSubscriberRenegotiation,
+    #[serde(rename = "room.published_tracks")]
+    RoomPublishedTracks,
+    #[serde(rename = "room.suggest_hls")]
+    RoomSuggestHls,
+    #[serde(rename = "room.codec_mismatch")]
+    RoomCodecMismatch,
+
+    #[serde(rename = "room.hls_streams")]
+    RoomHlsStreams,
+
+    #[serde(rename = "room.publisher_candidate")]
+    RoomPublisherCandidate,
+    #[serde(rename = "room.subscriber_candidate")]
+    RoomSubscriberCandidate,
+
+    #[serde(rename = "room.new_participant")]
+    RoomNewParticipant,
+    #[serde(rename = "room.participant_left")]
+    RoomParticipantLeft,
+
+    #[serde(rename = "room.video_enabled")]
+    RoomVideoEnabled,
+    #[serde(rename = "room.camera_type")]
+    RoomCameraType,
+    #[serde(rename = "room.audio_enabled")]
+    RoomAudioEnabled,
+    #[serde(rename = "room.screen_sharing")]
+    RoomScreenSharing,
+    #[serde(rename = "room.hand_raising")]
+    RoomHandRaising,
+    #[serde(rename = "room.subscribe_subtitle")]
+    RoomSubtitleTrack,
+    #[serde(rename = "room.stage_mode")]
+    RoomStageMode,
+    #[serde(rename = "room.speaker")]
+    RoomSpeaker,
+    #[serde(rename = "room.qos_class")]
+    RoomQosClass,
+    #[serde(rename = "room.recording_paused")]
+    RoomRecordingPaused,
+    #[serde(rename = "room.broadcast_cut")]
+    RoomBroadcastCut,
+    #[serde(rename = "room.live_started")]
+    RoomLiveStarted,
+    #[serde(rename = "room.client_stats")]
+    RoomClientStats,
+    #[serde(rename = "room.bot_join")]
+    RoomBotJoin,
+    #[serde(rename = "room.bot_message")]
+    RoomBotMessage,
+    #[serde(rename = "room.report")]
+    RoomReport,
+    #[serde(rename = "room.viewport_update")]
+    RoomViewportUpdate,
+    #[serde(rename = "room.observe")]
+    RoomObserve,
+    #[serde(rename = "room.observers")]
+    RoomObservers,
+    #[serde(rename = "room.slot_available")]
+    RoomSlotAvailable,
+    #[serde(rename = "room.insights")]
+    RoomInsights,
+    #[serde(rename = "room.type")]
+    RoomType,
+    #[serde(rename = "room.closed")]
+    RoomClosed,
+
+    #[serde(rename = "chat.send")]
+    ChatSend,
+    #[serde(rename = "chat.update")]
+    ChatUpdate,
+    #[serde(rename = "chat.delete")]
+    ChatDelete,
+    #[serde(rename = "chat.join")]
+    ChatJoin,
+    #[serde(rename = "chat.leave")]
+    ChatLeave,
+    #[serde(rename = "chat.reaction")]
+    ChatReaction,
+
+    #[serde(rename = "system.destroy")]
+    SystemDestroy,
+    #[serde(rename = "system.error")]
+    SystemError,
+
+    #[serde(rename = "connection")]
+    Connection,
+    #[serde(rename = "disconnect")]
+    Disconnect,
+}
+
+impl WsEvent {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            WsEvent::RoomPublish => "room.publish",
+            WsEvent::RoomSubscribe => "room.subscribe",
+            WsEvent::RoomAnswerSubscriber => "room.answer_subscriber",
+            WsEvent::RoomLeave => "room.leave",
+            WsEvent::RoomReconnect => "room.reconnect",
+            WsEvent::RoomMigrate => "room.migrate",
+            WsEvent::RoomResync => "room.resync",
+
+            WsEvent::RoomPublisherRenegotiation => "room.publisher_renegotiation",
+            WsEvent::RoomSubscriberRenegotiation => "room.subscriber_renegotiation",
+            WsEvent::RoomPublishedTracks => "room.published_tracks",
+            WsEvent::RoomSuggestHls => "room.suggest_hls",
+            WsEvent::RoomCodecMismatch => "room.codec_mismatch",
+            WsEvent::RoomHlsStreams => "room.hls_streams",
+
+            WsEvent::RoomPublisherCandidate => "room.publisher_candidate",
+            WsEvent::RoomSubscriberCandidate => "room.subscriber_candidate",
+
+            WsEvent::RoomNewParticipant => "room.new_participant",
+            WsEvent::RoomParticipantLeft => "room.participant_left",
+
+            WsEvent::RoomVideoEnabled => "room.video_enabled",
+            WsEvent::RoomCameraType => "room.camera_type",
+            WsEvent::RoomAudioEnabled => "room.audio_enabled",
+            WsEvent::RoomScreenSharing => "room.screen_sharing",
+            WsEvent::RoomHandRaising => "room.hand_raising",
+            WsEvent::RoomSubtitleTrack => "room.subscribe_subtitle",
+            WsEvent::RoomStageMode => "room.stage_mode",
+            WsEvent::RoomSpeaker => "room.speaker",
+            WsEvent::RoomQosClass => "room.qos_class",
+            WsEvent::RoomRecordingPaused => "room.recording_paused",
+            WsEvent::RoomBroadcastCut => "room.broadcast_cut",
+            WsEvent::RoomLiveStarted => "room.live_started",
+            WsEvent::RoomClientStats => "room.client_stats",
+            WsEvent::RoomBotJoin => "room.bot_join",
+            WsEvent::RoomBotMessage => "room.bot_message",
+            WsEvent::RoomReport => "room.report",
+            WsEvent::RoomViewportUpdate => "room.viewport_update",
+            WsEvent::RoomObserve => "room.observe",
+            WsEvent::RoomObservers => "room.observers",
+            WsEvent::RoomSlotAvailable => "room.slot_available",
+            WsEvent::RoomInsights => "room.insights",
+            WsEvent::RoomType => "room.type",
+            WsEvent::RoomClosed => "room.closed",
+
+            WsEvent::ChatSend => "chat.send",
+            WsEvent::ChatUpdate => "chat.update",
+            WsEvent::ChatDelete => "chat.delete",
+            WsEvent::ChatJoin => "chat.join",
+            WsEvent::ChatLeave => "chat.leave",
+            WsEvent::ChatReaction => "chat.reaction",
+
+            WsEvent::SystemDestroy => "system.destroy",
+            WsEvent::SystemError => "system.error",
+
+            WsEvent::Connection => "connection",
+            WsEvent::Disconnect => "disconnect",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: &[WsEvent] = &[
+        WsEvent::RoomPublish,
+        WsEvent::RoomSubscribe,
+        WsEvent::RoomAnswerSubscriber,
+        WsEvent::RoomLeave,
+        WsEvent::RoomReconnect,
+        WsEvent::RoomMigrate,
+        WsEvent::RoomResync,
+        WsEvent::RoomPublisherRenegotiation,
+        WsEvent::RoomSubscriberRenegotiation,
+        WsEvent::RoomPublishedTracks,
+        WsEvent::RoomSuggestHls,
+        WsEvent::RoomCodecMismatch,
+        WsEvent::RoomHlsStreams,
+        WsEvent::RoomPublisherCandidate,
+        WsEvent::RoomSubscriberCandidate,
+        WsEvent::RoomNewParticipant,
+        WsEvent::RoomParticipantLeft,
+        WsEvent::RoomVideoEnabled,
+        WsEvent::RoomCameraType,
+        WsEvent::RoomAudioEnabled,
+        WsEvent::RoomScreenSharing,
+        WsEvent::RoomHandRaising,
+        WsEvent::RoomSubtitleTrack,
+        WsEvent::RoomStageMode,
+        WsEvent::RoomSpeaker,
+        WsEvent::RoomQosClass,
+        WsEvent::RoomRecordingPaused,
+        WsEvent::RoomBroadcastCut,
+        WsEvent::RoomLiveStarted,
+        WsEvent::RoomClientStats,
+        WsEvent::RoomBotJoin,
+        WsEvent::RoomBotMessage,
+        WsEvent::RoomReport,
+        WsEvent::RoomViewportUpdate,
+        WsEvent::RoomObserve,
+        WsEvent::RoomObservers,
+        WsEvent::RoomSlotAvailable,
+        WsEvent::RoomInsights,
+        WsEvent::RoomType,
+        WsEvent::RoomClosed,
+        WsEvent::ChatSend,
+        WsEvent::ChatUpdate,
+        WsEvent::ChatDelete,
+        WsEvent::ChatJoin,
+        WsEvent::ChatLeave,
+        WsEvent::ChatReaction,
+        WsEvent::SystemDestroy,
+        WsEvent::SystemError,
+        WsEvent::Connection,
+        WsEvent::Disconnect,
+    ];
+
+    #[test]
+    fn test_wire_name_is_to_str() {
+        for event in ALL {
+            let json = serde_json::to_string(event).unwrap();
+            assert_eq!(json, format!("\"{}\"", event.to_str()));
+
+            let parsed: WsEvent = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed, *event);
+        }
+    }
+
+    #[test]
+    fn test_wire_names_are_unique() {
+        let mut names: Vec<&str> = ALL.iter().map(WsEvent::to_str).collect();
+        names.sort_unstable();
+        names.dedup();
+
+        assert_eq!(names.len(), ALL.len());
+    }
+}
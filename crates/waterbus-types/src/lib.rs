@@ -0,0 +1,8 @@
+//! Payloads and event names of the signalling socket, shared by the server
+//! and its clients. Only depends on serde so it builds for `wasm32` as well,
+//! the web client uses it directly or through the TypeScript bindings.
+
+pub mod dtos;
+pub mod enums;
+pub mod events;
+pub mod responses;
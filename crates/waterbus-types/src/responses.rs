@@ -0,0 +1,332 @@
+use serde::{Deserialize, Serialize};
+
+use crate::dtos::TrackInfoDto;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct ParticipantHasLeftResponse {
+    pub target_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct JoinRoomResponse {
+    pub sdp: String,
+    pub is_recording: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct RenegotiateResponse {
+    pub sdp: String,
+}
+
+/// Sent on `room.migrate` when the SFU node serving the client went away, the
+/// server's instruction rather than an answer to the client's own migrate.
+/// The client publishes a fresh offer on `room.publish` with the token, keeping
+/// its participant, and gets offers for the others once it is back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct NodeFailoverResponse {
+    pub room_id: String,
+    pub participant_id: String,
+    /// Good for one `room.publish`, like a token from the REST API
+    pub join_token: String,
+    /// What the client had on before the node went away, restored on rejoin
+    pub is_audio_enabled: bool,
+    pub is_video_enabled: bool,
+    pub is_hand_raising: bool,
+}
+
+/// Sent on `room.resync` when the server failed on one of the client's events
+/// mid-call. The client renegotiates its connection on `room.migrate`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct ResyncResponse {
+    pub room_id: String,
+    pub participant_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SubscribeParticipantResponse {
+    pub target_id: String,
+    #[serde(flatten)]
+    pub subscribe_response: SubscribeResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SubscribeResponse {
+    pub offer: String,
+    pub camera_type: u8,
+    pub video_enabled: bool,
+    pub audio_enabled: bool,
+    /// Muted by the OS or device, so UIs can tell it apart from a user mute
+    pub is_video_hardware_muted: bool,
+    pub is_audio_hardware_muted: bool,
+    pub is_screen_sharing: bool,
+    pub is_hand_raising: bool,
+    pub is_e2ee_enabled: bool,
+    pub video_codec: String,
+    pub screen_track_id: Option<String>,
+    pub screen_audio_track_id: Option<String>,
+    /// Every track the target publishes, a participant may send more than one camera
+    pub tracks: Vec<PublishedTrackResponse>,
+    /// Tile to render while the target's video is off
+    pub placeholder_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct PublishedTrackResponse {
+    pub track_id: String,
+    pub kind: String,
+    pub source: u8,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct PublishedTracksResponse {
+    pub participant_id: String,
+    pub tracks: Vec<TrackInfoDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "salvo", derive(salvo::oapi::ToSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct HlsStreamResponse {
+    pub participant_id: String,
+    pub url: String,
+}
+
+/// The subscriber's WebRTC peers are gone, it should play these instead.
+/// Sent again as `room.hls_streams` before the URLs expire and whenever
+/// someone joins or leaves the call, players swap to the new list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SuggestHlsResponse {
+    pub room_id: String,
+    pub streams: Vec<HlsStreamResponse>,
+    /// When the signed URLs stop working, as a unix timestamp
+    pub expires_at: i64,
+}
+
+/// The subscriber can't decode the target's video, sent instead of an offer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct CodecMismatchResponse {
+    pub target_id: String,
+    /// What the target publishes, like `av1`
+    pub codec: String,
+    /// The room over HLS, which any player decodes. `None` for observers and
+    /// when the room has no stream to watch.
+    pub fallback: Option<SuggestHlsResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct HandleRaisingResponse {
+    pub participant_id: String,
+    pub is_raising: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenSharingResponse {
+    pub participant_id: String,
+    pub is_sharing: bool,
+    pub screen_track_id: Option<String>,
+    pub screen_audio_track_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct EnabledResponse {
+    pub participant_id: String,
+    pub is_enabled: bool,
+    /// Only sent when the client reported it with the change
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_hardware_muted: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct CameraTypeResponse {
+    pub participant_id: String,
+    #[serde(rename = "type")]
+    pub type_: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriberRenegotiationResponse {
+    pub target_id: String,
+    pub sdp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SubsriberCandidateResponse {
+    pub target_id: String,
+    pub candidate: IceCandidate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct IceCandidate {
+    pub candidate: String,
+    pub sdp_mid: Option<String>,
+    pub sdp_m_line_index: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct StageModeResponse {
+    pub is_enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SpeakerResponse {
+    pub participant_id: String,
+    pub is_speaker: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct QosClassResponse {
+    pub participant_id: String,
+    pub qos_class: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingPausedResponse {
+    pub is_paused: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastCutResponse {
+    pub is_cut: bool,
+}
+
+/// A scheduled live stream went from its slate to the host's composition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct LiveStartedResponse {
+    pub room_id: String,
+    pub participant_id: String,
+}
+
+/// The host switched the room between a conference and a live stream. Only
+/// hosts keep publishing in a live stream, the others should unpublish.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct RoomTypeResponse {
+    pub room_id: String,
+    pub room_type: i16,
+    pub streaming_protocol: i16,
+}
+
+/// Sent back to the reporter once the report is queued for the moderators
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct ReportSubmittedResponse {
+    pub report_id: i32,
+    pub target_id: String,
+}
+
+/// Sent to the hosts whenever an observer starts or stops watching
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct ObserversResponse {
+    pub room_id: String,
+    pub count: usize,
+}
+
+/// A reaction relayed to everyone reading the room's chat
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct ReactionResponse {
+    pub room_id: String,
+    pub emoji: String,
+}
+
+/// Sent to the viewer at the head of a full call's line, joining the room
+/// again takes the slot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SlotAvailableResponse {
+    pub room_id: String,
+    pub viewer_id: String,
+}
+
+/// How the call is going, pushed to the hosts every few seconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct RoomInsightsResponse {
+    pub room_id: String,
+    pub participant_count: usize,
+    /// Participants gained, or lost when negative, over the last minute
+    pub participant_trend: i64,
+    /// From 0 to 100 over the participants' latest client stats, none when
+    /// nobody reported any lately
+    pub average_network_score: Option<f64>,
+    /// From 0, one participant does all the talking, to 1, everyone talks
+    /// as much. None with fewer than two participants or before anyone spoke.
+    pub talk_time_balance: Option<f64>,
+    pub is_recording: bool,
+    pub is_recording_paused: bool,
+}
+
+/// An operator closed the room, the socket is disconnected right after
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct RoomClosedResponse {
+    pub room_id: String,
+}
+
+/// A handler failed on the server, no reply to `event` is coming
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "camelCase")]
+pub struct SocketErrorResponse {
+    pub event: String,
+    pub code: String,
+    pub message: String,
+}
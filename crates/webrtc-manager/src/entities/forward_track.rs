@@ -1,33 +1,61 @@
-use std::sync::{
-    Arc,
-    atomic::{AtomicU8, Ordering},
+use std::{
+    borrow::Cow,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU8, Ordering},
+    },
+    time::{Duration, Instant},
 };
 
 use crossbeam::channel::{Receiver, TryRecvError};
 use dashmap::DashMap;
+use parking_lot::Mutex;
 
 use tracing::{debug, warn};
 use webrtc::{
     Error,
     rtp::{
-        codecs::vp9::Vp9Packet,
         extension::{HeaderExtension, transport_cc_extension::TransportCcExtension},
         packet::Packet,
-        packetizer::Depacketizer,
     },
     rtp_transceiver::rtp_codec::RTCRtpCodecCapability,
     track::track_local::track_local_static_rtp::TrackLocalStaticRTP,
+    util::marshal::MarshalSize,
+};
+
+use crate::{
+    entities::track::CodecType,
+    models::{quality::TrackQuality, rtp_foward_info::RtpForwardInfo, svc::SvcTarget},
+    utils::{
+        keyframe::is_keyframe,
+        layer_switch::{LayerPacket, LayerSwitch},
+        live_objects::{LiveGuard, LiveObject},
+        pacer::Pacer,
+        svc::{SvcFilter, SvcPacket},
+    },
 };
 
-use crate::models::{quality::TrackQuality, rtp_foward_info::RtpForwardInfo};
+/// Below the timer resolution, the slot is still reserved so later packets absorb it
+const MIN_PACING_SLEEP: Duration = Duration::from_millis(1);
 
 pub struct ForwardTrack {
     pub local_track: Arc<TrackLocalStaticRTP>,
     pub track_id: String,
     requested_quality: Arc<AtomicU8>,
     effective_quality: Arc<AtomicU8>,
+    is_forwarding_allowed: AtomicBool,
     ssrc: u32,
     keyframe_request_callback: Option<Arc<dyn Fn(u32) + Send + Sync>>,
+    // Audio is tiny and latency sensitive, it skips the pacer
+    is_audio: bool,
+    /// Shared screens keep their layer under bandwidth pressure, see `QosPolicy`
+    is_screen: bool,
+    codec_type: CodecType,
+    /// Simulcast layer changes wait for a key frame of the new layer
+    layer_switch: Mutex<LayerSwitch>,
+    svc_filter: Mutex<SvcFilter>,
+    pacer: Arc<Pacer>,
+    _live: LiveGuard,
 }
 
 impl ForwardTrack {
@@ -39,14 +67,27 @@ impl ForwardTrack {
         forward_track_id: String,
         ssrc: u32,
         keyframe_request_callback: Option<Arc<dyn Fn(u32) + Send + Sync>>,
+        pacer: Arc<Pacer>,
+        is_screen: bool,
     ) -> Arc<Self> {
+        let is_audio = codec.mime_type.to_lowercase().starts_with("audio/");
+        let codec_type = CodecType::from_mime_type(&codec.mime_type);
+
         let this = Arc::new(Self {
             local_track: Arc::new(TrackLocalStaticRTP::new(codec, track_id.clone(), sid)),
             track_id: forward_track_id,
             requested_quality: Arc::new(AtomicU8::new(TrackQuality::Medium.as_u8())),
             effective_quality: Arc::new(AtomicU8::new(TrackQuality::Medium.as_u8())),
+            is_forwarding_allowed: AtomicBool::new(true),
             ssrc,
             keyframe_request_callback,
+            is_audio,
+            is_screen,
+            codec_type,
+            layer_switch: Mutex::new(LayerSwitch::new()),
+            svc_filter: Mutex::new(SvcFilter::new()),
+            pacer,
+            _live: LiveGuard::new(LiveObject::ForwardTrack),
         });
 
         Self::_receive_rtp(Arc::clone(&this), receiver);
@@ -76,6 +117,32 @@ impl ForwardTrack {
         }
     }
 
+    pub fn set_forwarding_allowed(&self, is_allowed: bool) {
+        let was_allowed = self
+            .is_forwarding_allowed
+            .swap(is_allowed, Ordering::SeqCst);
+
+        // Resume from a keyframe so the subscriber doesn't decode garbage
+        if is_allowed
+            && !was_allowed
+            && let Some(cb) = &self.keyframe_request_callback
+        {
+            cb(self.ssrc);
+        }
+    }
+
+    pub fn is_forwarding_allowed(&self) -> bool {
+        self.is_forwarding_allowed.load(Ordering::Relaxed)
+    }
+
+    pub fn is_audio(&self) -> bool {
+        self.is_audio
+    }
+
+    pub fn is_screen(&self) -> bool {
+        self.is_screen
+    }
+
     fn _receive_rtp(this: Arc<Self>, receiver: Receiver<RtpForwardInfo>) {
         tokio::spawn(async move {
             // Use blocking receiver in a spawn_blocking to avoid blocking the async runtime
@@ -123,8 +190,11 @@ impl ForwardTrack {
     }
 
     async fn _process_batch(this: &Arc<Self>, batch: Vec<RtpForwardInfo>) {
+        if !this.is_forwarding_allowed() {
+            return;
+        }
+
         for info in batch {
-            let is_svc = info.is_svc;
             let is_simulcast = info.is_simulcast;
             let current_quality = info.track_quality.clone();
             let acceptable_map = info.acceptable_map.clone();
@@ -135,39 +205,95 @@ impl ForwardTrack {
                 continue;
             }
 
-            let is_video = true;
-
-            if !is_svc
-                && is_video
-                && !Self::_is_acceptable_track(
-                    &acceptable_map,
-                    current_quality.clone(),
-                    desired_quality.clone(),
-                    is_simulcast,
-                )
-            {
-                continue;
-            }
+            let is_video = !this.is_audio;
+            let mut packet = Cow::Borrowed(info.packet.as_ref());
+            let header = &info.packet.header;
+
+            // Simulcast layers of an SVC codec are told apart by the stream
+            let svc_layer = info.svc_layer.filter(|_| !is_simulcast);
+
+            if let Some(layer) = svc_layer {
+                let (result, wants_keyframe) = {
+                    let mut svc_filter = this.svc_filter.lock();
+                    let result = svc_filter.process(
+                        this.get_svc_target(),
+                        &layer,
+                        header.sequence_number,
+                        header.marker,
+                        Instant::now(),
+                    );
+                    (result, svc_filter.take_keyframe_request())
+                };
 
-            let should_forward = if is_svc && is_video {
-                let mut vp9_packet = Vp9Packet::default();
-                match vp9_packet.depacketize(&info.packet.payload) {
-                    Ok(_) => desired_quality.should_forward_vp9_svc(&vp9_packet),
-                    Err(err) => {
-                        warn!("Failed to depacketize VP9: {}", err);
-                        true
+                if wants_keyframe && let Some(cb) = &this.keyframe_request_callback {
+                    cb(header.ssrc);
+                }
+
+                match result {
+                    SvcPacket::Drop => continue,
+                    SvcPacket::Forward {
+                        sequence_number,
+                        marker,
+                    } => {
+                        if sequence_number != header.sequence_number || marker != header.marker {
+                            let packet = packet.to_mut();
+                            packet.header.sequence_number = sequence_number;
+                            packet.header.marker = marker;
+                        }
                     }
                 }
-            } else {
-                true
-            };
+            } else if is_video {
+                let target = if is_simulcast {
+                    Self::_served_quality(&acceptable_map, &desired_quality)
+                } else {
+                    Some(current_quality.clone())
+                };
 
-            if !should_forward {
-                continue;
+                let Some(target) = target else {
+                    continue;
+                };
+
+                let result = this.layer_switch.lock().process(
+                    &target,
+                    &current_quality,
+                    header.sequence_number,
+                    header.timestamp,
+                    is_keyframe(&this.codec_type, &info.packet.payload).unwrap_or(true),
+                    Instant::now(),
+                );
+
+                match result {
+                    LayerPacket::Drop => continue,
+                    LayerPacket::RequestKeyframe => {
+                        if let Some(cb) = &this.keyframe_request_callback {
+                            cb(header.ssrc);
+                        }
+                        continue;
+                    }
+                    LayerPacket::Forward {
+                        sequence_number,
+                        timestamp,
+                    } => {
+                        if sequence_number != header.sequence_number
+                            || timestamp != header.timestamp
+                        {
+                            let packet = packet.to_mut();
+                            packet.header.sequence_number = sequence_number;
+                            packet.header.timestamp = timestamp;
+                        }
+                    }
+                }
+            }
+
+            if !this.is_audio {
+                let delay = this.pacer.schedule(packet.marshal_size());
+                if delay >= MIN_PACING_SLEEP {
+                    tokio::time::sleep(delay).await;
+                }
             }
 
             // Write RTP packet
-            Self::_write_rtp(&this.local_track, &info.packet).await;
+            Self::_write_rtp(&this.local_track, &packet).await;
         }
     }
 
@@ -177,6 +303,13 @@ impl ForwardTrack {
         requested.min(effective)
     }
 
+    /// The SVC layers sent, by the tile's size and the downlink
+    pub fn get_svc_target(&self) -> SvcTarget {
+        let requested = TrackQuality::from_u8(self.requested_quality.load(Ordering::Relaxed));
+        let effective = TrackQuality::from_u8(self.effective_quality.load(Ordering::Relaxed));
+        SvcTarget::new(&requested, &effective)
+    }
+
     async fn _write_rtp(local_track: &Arc<TrackLocalStaticRTP>, rtp: &Packet) {
         if let Err(err) = local_track
             .write_rtp_with_extensions(
@@ -193,7 +326,21 @@ impl ForwardTrack {
         }
     }
 
-    fn _is_acceptable_track(
+    /// The simulcast layer sent to a subscriber wanting `desired`
+    fn _served_quality(
+        acceptable_map: &DashMap<(TrackQuality, TrackQuality), bool>,
+        desired: &TrackQuality,
+    ) -> Option<TrackQuality> {
+        [TrackQuality::Low, TrackQuality::Medium, TrackQuality::High]
+            .into_iter()
+            .find(|current| {
+                acceptable_map
+                    .get(&(current.clone(), desired.clone()))
+                    .is_some_and(|acceptable| *acceptable)
+            })
+    }
+
+    pub(crate) fn _is_acceptable_track(
         acceptable_map: &Arc<DashMap<(TrackQuality, TrackQuality), bool>>,
         current: TrackQuality,
         desired: TrackQuality,
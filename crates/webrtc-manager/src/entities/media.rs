@@ -1,19 +1,36 @@
-use std::{fs, path::Path, sync::Arc};
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    sync::{Arc, atomic::Ordering},
+    time::Duration,
+};
 
 use dashmap::DashMap;
-use egress_manager::egress::{hls_writer::HlsWriter, moq_writer::MoQWriter};
+use egress_manager::egress::{
+    hls_writer::HlsWriter,
+    moq_writer::MoQWriter,
+    track_archive::{TrackArchive, TrackRecorder},
+};
 use nanoid::nanoid;
 use parking_lot::RwLock;
 use tokio::sync::mpsc;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use webrtc::{rtp_transceiver::rtp_codec::RTPCodecType, track::track_remote::TrackRemote};
 
-use crate::models::{
-    data_channel_msg::TrackSubscribedMessage,
-    params::{AddTrackResponse, TrackMutexWrapper},
+use crate::{
+    models::{
+        data_channel_msg::TrackSubscribedMessage,
+        params::{AddTrackResponse, TrackMutexWrapper},
+        track_info::{PublishedTrack, TrackInfo, TrackSource},
+    },
+    utils::{
+        buffer_pool::RtpBufferPool,
+        noise_suppression::{NoiseSuppressionBudget, NoiseSuppressionPermit},
+    },
 };
 
-use super::track::Track;
+use super::track::{HlsWriterSlot, Track};
 
 pub type TrackSubscribedCallback = Arc<dyn Fn(TrackSubscribedMessage) + Send + Sync>;
 
@@ -21,26 +38,37 @@ pub struct Media {
     pub media_id: String,
     pub participant_id: String,
     pub tracks: Arc<DashMap<String, TrackMutexWrapper>>,
+    track_infos: Arc<DashMap<String, TrackInfo>>,
     pub state: Arc<RwLock<MediaState>>,
-    hls_writer: Option<Arc<HlsWriter>>,
+    /// Shared with the egress tracks, so a stream can start or end mid-call
+    hls_writer: HlsWriterSlot,
     moq_writer: Option<Arc<MoQWriter>>,
+    track_archive: Option<TrackArchive>,
     output_dir: String,
     sdp: Option<String>,
     pub track_subscribed_callback: Option<TrackSubscribedCallback>,
     pub track_event_sender: Option<mpsc::UnboundedSender<TrackSubscribedMessage>>,
     pub keyframe_request_callback: Option<Arc<dyn Fn(u32) + Send + Sync>>,
+    rtp_buffer_pool: Arc<RtpBufferPool>,
+    /// Set when the microphone is denoised on the node, from the node's budget
+    noise_suppression: Option<Arc<NoiseSuppressionBudget>>,
+    broadcast_delay: Duration,
 }
 
 #[derive(Debug)]
 pub struct MediaState {
     pub video_enabled: bool,
     pub audio_enabled: bool,
+    /// Muted by the OS or device, kept apart from what the user asked for
+    pub video_hardware_muted: bool,
+    pub audio_hardware_muted: bool,
     pub is_e2ee_enabled: bool,
     pub is_screen_sharing: bool,
     pub is_hand_raising: bool,
     pub camera_type: u8,
     pub codec: String,
     pub screen_track_id: Option<String>,
+    pub screen_audio_track_id: Option<String>,
 }
 
 impl Media {
@@ -49,6 +77,7 @@ impl Media {
         is_video_enabled: bool,
         is_audio_enabled: bool,
         is_e2ee_enabled: bool,
+        rtp_buffer_pool: Arc<RtpBufferPool>,
     ) -> Self {
         let output_dir = format!("./hls/{publisher_id}");
 
@@ -60,38 +89,115 @@ impl Media {
             media_id: format!("m_{}", nanoid!(12)),
             participant_id: publisher_id,
             tracks: Arc::new(DashMap::new()),
-            hls_writer: None,
+            track_infos: Arc::new(DashMap::new()),
+            hls_writer: Arc::new(RwLock::new(None)),
             moq_writer: None,
+            track_archive: None,
             output_dir,
             sdp: None,
             track_subscribed_callback: None,
             track_event_sender: None,
             keyframe_request_callback: None,
+            rtp_buffer_pool,
+            noise_suppression: None,
+            broadcast_delay: Duration::ZERO,
             state: Arc::new(RwLock::new(MediaState {
                 video_enabled: is_video_enabled,
                 audio_enabled: is_audio_enabled,
+                video_hardware_muted: false,
+                audio_hardware_muted: false,
                 is_e2ee_enabled,
                 is_screen_sharing: false,
                 is_hand_raising: false,
                 camera_type: 0,
                 codec: String::new(),
                 screen_track_id: None,
+                screen_audio_track_id: None,
             })),
         }
     }
 
     pub async fn initialize_hls_writer(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         let hls_writer = HlsWriter::new(&self.output_dir, self.participant_id.clone()).await?;
-        self.hls_writer = Some(Arc::new(hls_writer));
+        hls_writer.set_broadcast_delay(self.broadcast_delay);
+        *self.hls_writer.write() = Some(Arc::new(hls_writer));
         Ok(())
     }
 
+    /// Starts the HLS stream of a media already publishing, its egress tracks
+    /// pick the writer up from their next packet
+    pub async fn start_hls_writer(media: &RwLock<Self>) -> Result<(), Box<dyn std::error::Error>> {
+        let (output_dir, participant_id, broadcast_delay, codec) = {
+            let media = media.read();
+            // The audience of a live stream joins without tracks, nothing to stream
+            if media.hls_writer.read().is_some() || media.tracks.is_empty() {
+                return Ok(());
+            }
+
+            let codec = media.state.read().codec.clone();
+            (
+                media.output_dir.clone(),
+                media.participant_id.clone(),
+                media.broadcast_delay,
+                codec,
+            )
+        };
+
+        let hls_writer = HlsWriter::new(&output_dir, participant_id).await?;
+        hls_writer.set_broadcast_delay(broadcast_delay);
+        if !codec.is_empty() {
+            hls_writer.set_video_codec(Self::_egress_codec(&codec));
+        }
+
+        let media = media.read();
+        {
+            let mut slot = media.hls_writer.write();
+            if slot.is_some() {
+                hls_writer.stop();
+                return Ok(());
+            }
+            *slot = Some(Arc::new(hls_writer));
+        }
+
+        // The stream has to open on a frame decodable on its own
+        media._request_keyframes();
+
+        Ok(())
+    }
+
+    /// Ends the HLS stream, WebRTC subscribers aren't affected
+    pub fn stop_hls_writer(&self) {
+        if let Some(writer) = self.hls_writer.write().take() {
+            writer.stop();
+        }
+    }
+
     pub fn initialize_moq_writer(&mut self) -> Result<(), anyhow::Error> {
         let moq_writer = MoQWriter::new(&self.participant_id.clone())?;
+        moq_writer.set_broadcast_delay(self.broadcast_delay);
         self.moq_writer = Some(Arc::new(moq_writer));
         Ok(())
     }
 
+    pub async fn initialize_track_archive(
+        &mut self,
+        dir: &str,
+        room_id: &str,
+    ) -> Result<(), anyhow::Error> {
+        let track_archive = TrackArchive::new(dir, room_id, &self.participant_id).await?;
+        self.track_archive = Some(track_archive);
+        Ok(())
+    }
+
+    pub fn enable_noise_suppression(&mut self, budget: Arc<NoiseSuppressionBudget>) {
+        self.noise_suppression = Some(budget);
+    }
+
+    /// Applies to the HLS and MoQ writers started after this
+    pub fn set_broadcast_delay(&mut self, delay: Duration) {
+        self.broadcast_delay = delay;
+    }
+
     pub fn cache_sdp(&mut self, sdp: String) {
         self.sdp = Some(sdp);
     }
@@ -110,12 +216,14 @@ impl Media {
         is_video_enabled: bool,
         is_audio_enabled: bool,
         is_e2ee_enabled: bool,
+        rtp_buffer_pool: Arc<RtpBufferPool>,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         let mut media = Self::new(
             publisher_id,
             is_video_enabled,
             is_audio_enabled,
             is_e2ee_enabled,
+            rtp_buffer_pool,
         );
         media.initialize_hls_writer().await?;
         Ok(media)
@@ -135,22 +243,26 @@ impl Media {
             return AddTrackResponse::AddSimulcastTrackSuccess(existing_track_arc.clone());
         }
 
-        if rtp_track.kind() == RTPCodecType::Video {
-            let codec = match rtp_track
-                .codec()
-                .capability
-                .mime_type
-                .to_lowercase()
-                .as_str()
-            {
-                s if s.contains("vp8") => "vp8",
-                s if s.contains("vp9") => "vp9",
-                s if s.contains("av1") => "av1",
-                s if s.contains("h264") => "h264",
-                _ => "h264",
-            };
-
-            if let Some(hls_writer) = &self.hls_writer {
+        // Only the first camera and microphone are recorded, a second camera would
+        // otherwise be interleaved into the same egress video stream
+        let source = self._resolve_source(&rtp_track.id(), rtp_track.kind());
+        let is_egress_source = match source {
+            TrackSource::Camera | TrackSource::Microphone => !self.tracks.iter().any(|entry| {
+                let track = entry.value().read();
+                self._resolve_source(&track.id, track.kind) == source
+            }),
+            TrackSource::Screen | TrackSource::ScreenAudio => true,
+        };
+        let (hls_writer, moq_writer) = if is_egress_source {
+            (Some(Arc::clone(&self.hls_writer)), self.moq_writer.clone())
+        } else {
+            (None, None)
+        };
+
+        if is_egress_source && rtp_track.kind() == RTPCodecType::Video {
+            let codec = Self::_egress_codec(&rtp_track.codec().capability.mime_type);
+
+            if let Some(hls_writer) = self.hls_writer.read().as_ref() {
                 hls_writer.set_video_codec(codec);
             }
 
@@ -159,18 +271,35 @@ impl Media {
             }
         }
 
+        let recorder = self._start_recorder(&rtp_track);
+        let noise_suppression = self._acquire_noise_suppression(&rtp_track, source);
+
         let new_track = Arc::new(RwLock::new(Track::new(
             rtp_track.clone(),
             room_id,
             self.participant_id.clone(),
-            self.hls_writer.clone(),
-            self.moq_writer.clone(),
+            hls_writer,
+            moq_writer,
+            recorder,
             self.keyframe_request_callback.clone(),
+            Arc::clone(&self.rtp_buffer_pool),
+            noise_suppression,
         )));
 
-        if rtp_track.kind() == RTPCodecType::Video {
+        {
             let mut state = self.state.write();
-            state.codec = rtp_track.codec().capability.mime_type;
+            if rtp_track.kind() == RTPCodecType::Video {
+                state.codec = rtp_track.codec().capability.mime_type;
+                new_track
+                    .read()
+                    .is_screen
+                    .store(source == TrackSource::Screen, Ordering::Relaxed);
+            } else if source == TrackSource::ScreenAudio {
+                new_track
+                    .read()
+                    .is_screen_audio
+                    .store(true, Ordering::Relaxed);
+            }
         }
 
         self.tracks.insert(rtp_track.id(), new_track.clone());
@@ -180,7 +309,31 @@ impl Media {
         AddTrackResponse::AddTrackSuccess(new_track)
     }
 
-    pub fn set_screen_sharing(&self, is_enabled: bool, screen_track_id: Option<String>) {
+    /// Unlike HLS every track is archived, including second cameras and simulcast
+    /// tracks (from the highest layer the publisher is sending)
+    fn _start_recorder(&self, rtp_track: &TrackRemote) -> Option<Arc<TrackRecorder>> {
+        let track_archive = self.track_archive.as_ref()?;
+        let capability = rtp_track.codec().capability;
+
+        match track_archive.start_track(
+            &rtp_track.id(),
+            &capability.mime_type,
+            capability.clock_rate,
+        ) {
+            Ok(recorder) => Some(recorder),
+            Err(err) => {
+                warn!("Failed to archive track {}: {:?}", rtp_track.id(), err);
+                None
+            }
+        }
+    }
+
+    pub fn set_screen_sharing(
+        &self,
+        is_enabled: bool,
+        screen_track_id: Option<String>,
+        screen_audio_track_id: Option<String>,
+    ) {
         let mut state = self.state.write();
         if state.is_screen_sharing != is_enabled {
             state.is_screen_sharing = is_enabled;
@@ -191,24 +344,126 @@ impl Media {
                 self.remove_screen_track();
             } else {
                 state.screen_track_id = screen_track_id;
+                state.screen_audio_track_id = screen_audio_track_id.clone();
+                drop(state);
+
+                // The audio track usually arrives before the signalling message does
+                if let Some(track) = screen_audio_track_id.and_then(|id| self.tracks.get(&id)) {
+                    track.read().is_screen_audio.store(true, Ordering::Relaxed);
+                }
             }
         }
     }
 
+    /// Record what the publisher told us about its tracks, ahead of or after the tracks themselves
+    pub fn set_track_infos(&self, track_infos: Vec<TrackInfo>) {
+        for info in track_infos {
+            if info.source == TrackSource::ScreenAudio
+                && let Some(track) = self.tracks.get(&info.track_id)
+            {
+                track.read().is_screen_audio.store(true, Ordering::Relaxed);
+            }
+
+            self.track_infos.insert(info.track_id.clone(), info);
+        }
+    }
+
+    /// Every track currently published, with the source and label subscribers show for it
+    pub fn published_tracks(&self) -> Vec<PublishedTrack> {
+        let mut tracks: Vec<(String, RTPCodecType, TrackSource)> = self
+            .tracks
+            .iter()
+            .map(|entry| {
+                let track = entry.value().read();
+                let source = self._resolve_source(&track.id, track.kind);
+                (track.id.clone(), track.kind, source)
+            })
+            .collect();
+
+        tracks.sort_by(|a, b| (a.2 as u8, &a.0).cmp(&(b.2 as u8, &b.0)));
+
+        let mut per_source: HashMap<TrackSource, usize> = HashMap::new();
+
+        tracks
+            .into_iter()
+            .map(|(track_id, kind, source)| {
+                let nth = per_source.entry(source).or_default();
+                *nth += 1;
+
+                let label = self
+                    .track_infos
+                    .get(&track_id)
+                    .and_then(|info| info.label.clone());
+
+                PublishedTrack::new(track_id, kind.to_string(), source, label, *nth)
+            })
+            .collect()
+    }
+
+    /// Only the microphone is denoised, system audio shared with the screen isn't speech
+    fn _acquire_noise_suppression(
+        &self,
+        rtp_track: &TrackRemote,
+        source: TrackSource,
+    ) -> Option<NoiseSuppressionPermit> {
+        let budget = self.noise_suppression.as_ref()?;
+
+        let is_opus = rtp_track
+            .codec()
+            .capability
+            .mime_type
+            .eq_ignore_ascii_case("audio/opus");
+        if source != TrackSource::Microphone || !is_opus {
+            return None;
+        }
+
+        let permit = budget.try_acquire();
+        if permit.is_none() {
+            warn!(
+                "Noise suppression budget spent, the microphone of {} is forwarded as is",
+                self.participant_id
+            );
+        }
+
+        permit
+    }
+
+    fn _resolve_source(&self, track_id: &str, kind: RTPCodecType) -> TrackSource {
+        if let Some(info) = self.track_infos.get(track_id) {
+            return info.source;
+        }
+
+        let state = self.state.read();
+        if state.screen_track_id.as_deref() == Some(track_id) {
+            TrackSource::Screen
+        } else if state.screen_audio_track_id.as_deref() == Some(track_id) {
+            TrackSource::ScreenAudio
+        } else if kind == RTPCodecType::Video {
+            TrackSource::Camera
+        } else {
+            TrackSource::Microphone
+        }
+    }
+
     pub fn set_hand_rasing(&self, is_enabled: bool) {
         let mut state = self.state.write();
         state.is_hand_raising = is_enabled;
     }
 
     fn remove_screen_track(&self) {
-        let screen_track_id_opt = {
-            let state = self.state.read();
-            state.screen_track_id.clone()
+        let screen_track_ids = {
+            let mut state = self.state.write();
+            [
+                state.screen_track_id.take(),
+                state.screen_audio_track_id.take(),
+            ]
         };
 
-        if let Some(screen_track_id) = screen_track_id_opt {
+        for screen_track_id in screen_track_ids.into_iter().flatten() {
+            self.track_infos.remove(&screen_track_id);
             let removed = self.tracks.remove(&screen_track_id);
-            if removed.is_some() {
+            if let Some((_, track)) = removed {
+                track.write().stop();
                 info!("[screen_track_removed]: id: {}", screen_track_id);
             } else {
                 info!(
@@ -216,10 +471,6 @@ impl Media {
                     screen_track_id
                 );
             }
-
-            // Clear the screen_track_id
-            let mut state = self.state.write();
-            state.screen_track_id = None;
         }
     }
 
@@ -232,30 +483,110 @@ impl Media {
         }
 
         self.tracks.clear();
+        self.track_infos.clear();
     }
 
     pub fn set_camera_type(&self, camera_type: u8) {
         self.state.write().camera_type = camera_type;
     }
 
-    pub fn set_video_enabled(&self, is_enabled: bool) {
-        self.state.write().video_enabled = is_enabled;
+    pub fn set_video_enabled(&self, is_enabled: bool, is_hardware_muted: Option<bool>) {
+        let mut state = self.state.write();
+        state.video_enabled = is_enabled;
+        if let Some(is_hardware_muted) = is_hardware_muted {
+            state.video_hardware_muted = is_hardware_muted;
+        }
     }
 
-    pub fn set_audio_enabled(&self, is_enabled: bool) {
-        self.state.write().audio_enabled = is_enabled;
+    pub fn set_audio_enabled(&self, is_enabled: bool, is_hardware_muted: Option<bool>) {
+        let mut state = self.state.write();
+        state.audio_enabled = is_enabled;
+        if let Some(is_hardware_muted) = is_hardware_muted {
+            state.audio_hardware_muted = is_hardware_muted;
+        }
     }
 
     pub fn set_e2ee_enabled(&self, is_enabled: bool) {
         self.state.write().is_e2ee_enabled = is_enabled;
     }
 
+    /// Whether the media goes to an archive or an HLS stream
+    pub fn is_recording(&self) -> bool {
+        self.hls_writer.read().is_some() || self.track_archive.is_some()
+    }
+
+    /// How long the microphone carried speech, screen audio aside
+    pub fn talk_time_ms(&self) -> u64 {
+        self.tracks
+            .iter()
+            .map(|entry| {
+                let track = entry.value().read();
+                if track.is_screen_audio.load(Ordering::Relaxed) {
+                    0
+                } else {
+                    track.talk_time_ms()
+                }
+            })
+            .sum()
+    }
+
+    /// Pauses the archive and the HLS stream. MoQ is a live view rather than a
+    /// recording and keeps going, same as WebRTC subscribers.
+    pub fn set_recording_paused(&self, is_paused: bool) {
+        if let Some(track_archive) = &self.track_archive {
+            track_archive.set_paused(is_paused);
+        }
+
+        for entry in self.tracks.iter() {
+            entry.value().read().set_archive_paused(is_paused);
+        }
+
+        let Some(hls_writer) = self.hls_writer.read().clone() else {
+            return;
+        };
+
+        if is_paused {
+            hls_writer.pause();
+        } else {
+            hls_writer.resume();
+
+            // The first frame after a resume has to be decodable on its own
+            self._request_keyframes();
+        }
+    }
+
+    /// Drops the media held back for HLS and MoQ viewers and keeps their
+    /// stream dark until it's lifted. WebRTC subscribers aren't affected.
+    pub fn set_broadcast_cut(&self, is_cut: bool) {
+        if let Some(writer) = self.hls_writer.read().as_ref() {
+            writer.set_broadcast_cut(is_cut);
+        }
+        if let Some(writer) = &self.moq_writer {
+            writer.set_broadcast_cut(is_cut);
+        }
+
+        if !is_cut {
+            self._request_keyframes();
+        }
+    }
+
+    fn _request_keyframes(&self) {
+        let Some(cb) = &self.keyframe_request_callback else {
+            return;
+        };
+
+        for entry in self.tracks.iter() {
+            let track = entry.value().read();
+            if track.kind == RTPCodecType::Video {
+                cb(track.ssrc);
+            }
+        }
+    }
+
     pub fn stop(&self) {
         self.remove_all_tracks();
 
-        if let Some(writer) = &self.hls_writer {
-            writer.stop();
-        }
+        self.stop_hls_writer();
         if let Some(writer) = &self.moq_writer {
             writer.stop();
         }
@@ -263,8 +594,11 @@ impl Media {
         {
             let mut state = self.state.write();
             state.screen_track_id = None;
+            state.screen_audio_track_id = None;
             state.video_enabled = false;
             state.audio_enabled = false;
+            state.video_hardware_muted = false;
+            state.audio_hardware_muted = false;
             state.is_screen_sharing = false;
             state.is_hand_raising = false;
             state.camera_type = 0;
@@ -272,6 +606,16 @@ impl Media {
         }
     }
 
+    fn _egress_codec(mime_type: &str) -> &'static str {
+        match mime_type.to_lowercase().as_str() {
+            s if s.contains("vp8") => "vp8",
+            s if s.contains("vp9") => "vp9",
+            s if s.contains("av1") => "av1",
+            s if s.contains("h264") => "h264",
+            _ => "h264",
+        }
+    }
+
     fn _log_track_added(&self, rtp_track: Arc<TrackRemote>) {
         let rid = if rtp_track.kind() == RTPCodecType::Audio {
             "audio"
@@ -292,3 +636,179 @@ impl Media {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use webrtc::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
+
+    use super::*;
+
+    fn media() -> Media {
+        Media::new(
+            "publisher".to_owned(),
+            true,
+            true,
+            false,
+            Arc::new(RtpBufferPool::new(1)),
+        )
+    }
+
+    fn add_track(media: &Media, id: &str, kind: RTPCodecType) {
+        let mime_type = match kind {
+            RTPCodecType::Video => "video/VP8",
+            _ => "audio/opus",
+        };
+        let capability = RTCRtpCodecCapability {
+            mime_type: mime_type.to_owned(),
+            ..Default::default()
+        };
+
+        let track = Track::detached(id, kind, capability);
+        media
+            .tracks
+            .insert(id.to_owned(), Arc::new(RwLock::new(track)));
+    }
+
+    fn is_screen_audio(media: &Media, id: &str) -> bool {
+        let track = media.tracks.get(id).unwrap();
+        track.read().is_screen_audio.load(Ordering::Relaxed)
+    }
+
+    fn sources(media: &Media) -> Vec<(String, TrackSource)> {
+        media
+            .published_tracks()
+            .into_iter()
+            .map(|track| (track.track_id, track.source))
+            .collect()
+    }
+
+    #[test]
+    fn test_screen_audio_that_arrived_first_is_flagged() {
+        let media = media();
+        add_track(&media, "mic", RTPCodecType::Audio);
+        add_track(&media, "system", RTPCodecType::Audio);
+
+        media.set_screen_sharing(true, Some("screen".to_owned()), Some("system".to_owned()));
+
+        assert!(is_screen_audio(&media, "system"));
+        assert!(!is_screen_audio(&media, "mic"));
+    }
+
+    #[test]
+    fn test_sources_follow_the_screen_share() {
+        let media = media();
+        add_track(&media, "camera", RTPCodecType::Video);
+        add_track(&media, "mic", RTPCodecType::Audio);
+        add_track(&media, "screen", RTPCodecType::Video);
+        add_track(&media, "system", RTPCodecType::Audio);
+
+        media.set_screen_sharing(true, Some("screen".to_owned()), Some("system".to_owned()));
+
+        assert_eq!(
+            sources(&media),
+            [
+                ("camera".to_owned(), TrackSource::Camera),
+                ("mic".to_owned(), TrackSource::Microphone),
+                ("screen".to_owned(), TrackSource::Screen),
+                ("system".to_owned(), TrackSource::ScreenAudio),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_declared_track_infos_label_the_tracks() {
+        let media = media();
+        add_track(&media, "camera", RTPCodecType::Video);
+        add_track(&media, "document", RTPCodecType::Video);
+        add_track(&media, "webcam", RTPCodecType::Video);
+
+        media.set_track_infos(vec![TrackInfo {
+            track_id: "document".to_owned(),
+            source: TrackSource::Camera,
+            label: Some("Document camera".to_owned()),
+        }]);
+
+        let labels = media
+            .published_tracks()
+            .into_iter()
+            .map(|track| (track.track_id, track.label))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            [
+                ("camera".to_owned(), "Camera".to_owned()),
+                ("document".to_owned(), "Document camera".to_owned()),
+                ("webcam".to_owned(), "Camera 3".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_track_declared_as_screen_audio_is_flagged() {
+        let media = media();
+        add_track(&media, "system", RTPCodecType::Audio);
+
+        media.set_track_infos(vec![TrackInfo {
+            track_id: "system".to_owned(),
+            source: TrackSource::ScreenAudio,
+            label: None,
+        }]);
+
+        assert!(is_screen_audio(&media, "system"));
+    }
+
+    #[test]
+    fn test_stopping_the_screen_share_removes_both_tracks() {
+        let media = media();
+        add_track(&media, "mic", RTPCodecType::Audio);
+        add_track(&media, "screen", RTPCodecType::Video);
+        add_track(&media, "system", RTPCodecType::Audio);
+        media.set_screen_sharing(true, Some("screen".to_owned()), Some("system".to_owned()));
+
+        media.set_screen_sharing(false, None, None);
+
+        assert_eq!(
+            sources(&media),
+            [("mic".to_owned(), TrackSource::Microphone)]
+        );
+
+        let state = media.state.read();
+        assert!(!state.is_screen_sharing);
+        assert!(state.screen_track_id.is_none());
+        assert!(state.screen_audio_track_id.is_none());
+    }
+
+    #[test]
+    fn test_device_mute_is_kept_apart_from_user_mute() {
+        let media = media();
+
+        media.set_audio_enabled(true, Some(true));
+        media.set_video_enabled(false, Some(false));
+        {
+            let state = media.state.read();
+            assert!(state.audio_enabled);
+            assert!(state.audio_hardware_muted);
+            assert!(!state.video_enabled);
+            assert!(!state.video_hardware_muted);
+        }
+
+        // Clients that don't report the device leave it as it was
+        media.set_audio_enabled(false, None);
+        let state = media.state.read();
+        assert!(!state.audio_enabled);
+        assert!(state.audio_hardware_muted);
+    }
+
+    #[test]
+    fn test_stop_clears_device_mute() {
+        let media = media();
+        media.set_audio_enabled(true, Some(true));
+        media.set_video_enabled(true, Some(true));
+
+        media.stop();
+
+        let state = media.state.read();
+        assert!(!state.audio_hardware_muted);
+        assert!(!state.video_hardware_muted);
+    }
+}
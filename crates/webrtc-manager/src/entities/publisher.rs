@@ -14,7 +14,10 @@ use webrtc::{
     rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication,
 };
 
-use crate::models::{connection_type::ConnectionType, data_channel_msg::TrackSubscribedMessage};
+use crate::{
+    models::{connection_type::ConnectionType, data_channel_msg::TrackSubscribedMessage},
+    utils::live_objects::{LiveGuard, LiveObject},
+};
 
 use super::media::Media;
 
@@ -25,6 +28,7 @@ pub struct Publisher {
     pub cancel_token: CancellationToken,
     pub data_channel: Option<Arc<RTCDataChannel>>,
     pub track_event_receiver: Option<mpsc::UnboundedReceiver<TrackSubscribedMessage>>,
+    _live: LiveGuard,
 }
 
 impl Publisher {
@@ -40,6 +44,7 @@ impl Publisher {
             cancel_token: CancellationToken::new(),
             data_channel: None,
             track_event_receiver: None,
+            _live: LiveGuard::new(LiveObject::Publisher),
         });
 
         let publisher_clone = Arc::clone(&publisher);
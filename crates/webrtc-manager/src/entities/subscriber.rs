@@ -3,7 +3,7 @@ use std::{
     collections::VecDeque,
     sync::{
         Arc,
-        atomic::{AtomicU8, Ordering},
+        atomic::{AtomicBool, AtomicU8, Ordering},
     },
     time::{Duration, Instant},
 };
@@ -17,6 +17,7 @@ use webrtc::{
         payload_feedbacks::{
             full_intra_request::{FirEntry, FullIntraRequest},
             picture_loss_indication::PictureLossIndication,
+            receiver_estimated_maximum_bitrate::ReceiverEstimatedMaximumBitrate,
         },
         transport_feedbacks::transport_layer_cc::TransportLayerCc,
     },
@@ -29,9 +30,15 @@ use webrtc::{
 use crate::{
     errors::WebRTCError,
     models::{
-        params::TrackMutexWrapper, quality::TrackQuality,
+        params::{DownlinkStarvedCallback, TrackMutexWrapper},
+        qos::QosPolicy,
+        quality::TrackQuality,
         track_quality_request::TrackQualityRequest,
     },
+    utils::{
+        live_objects::{LiveGuard, LiveObject},
+        pacer::{Pacer, PacerStats},
+    },
 };
 
 use super::forward_track::ForwardTrack;
@@ -51,6 +58,9 @@ const MIN_QUALITY_CHANGE_INTERVAL: Duration = Duration::from_secs(2);
 // History sizes for better stability
 const HISTORY_SIZE: usize = 10;
 
+// How long the downlink has to stay under the lowest layer before giving up on WebRTC
+const STARVED_GRACE: Duration = Duration::from_secs(10);
+
 type TrackMap = Arc<DashMap<String, Arc<ForwardTrack>>>;
 
 #[derive(Debug)]
@@ -159,6 +169,48 @@ impl NetworkStats {
     }
 }
 
+/// Watches for a downlink that can't carry even the lowest simulcast layer, and
+/// reports it once so the viewer can be moved to HLS
+struct DownlinkWatch {
+    min_bps: f64,
+    starved_since: parking_lot::Mutex<Option<Instant>>,
+    has_fired: AtomicBool,
+    callback: DownlinkStarvedCallback,
+}
+
+impl DownlinkWatch {
+    fn observe(&self, quality: TrackQuality, remb_bps: Option<f64>) {
+        self._observe(quality, remb_bps, Instant::now());
+    }
+
+    fn _observe(&self, quality: TrackQuality, remb_bps: Option<f64>, now: Instant) {
+        if self.min_bps <= 0.0 || self.has_fired.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let is_starved =
+            quality == TrackQuality::Low && remb_bps.is_some_and(|bps| bps < self.min_bps);
+
+        let mut starved_since = self.starved_since.lock();
+
+        if !is_starved {
+            *starved_since = None;
+            return;
+        }
+
+        let since = *starved_since.get_or_insert(now);
+        let starved_for = now.duration_since(since);
+
+        if starved_for >= STARVED_GRACE && !self.has_fired.swap(true, Ordering::Relaxed) {
+            info!(
+                "Downlink starved for {:?} (REMB {:?} bps), suggesting HLS",
+                starved_for, remb_bps
+            );
+            tokio::spawn((self.callback)());
+        }
+    }
+}
+
 pub struct Subscriber {
     pub peer_connection: Arc<RTCPeerConnection>,
     cancel_token: CancellationToken,
@@ -166,13 +218,32 @@ pub struct Subscriber {
     network_stats: Arc<RwLock<NetworkStats>>,
     tracks: Arc<DashMap<String, TrackMutexWrapper>>,
     track_map: TrackMap,
+    pub target_id: String,
     user_id: String,
+    is_forwarding_allowed: AtomicBool,
     data_channel: Option<Arc<RTCDataChannel>>,
     client_requested_quality: Arc<RwLock<Option<TrackQuality>>>,
+    /// Layer the target's tile on the client's screen calls for, `None` pauses
+    /// its video. Medium, the forward tracks' default, until the client reports one.
+    viewport_quality: AtomicU8,
+    pacer: Arc<Pacer>,
+    downlink_watch: Arc<DownlinkWatch>,
+    qos: Arc<QosPolicy>,
+    _live: LiveGuard,
 }
 
 impl Subscriber {
-    pub async fn new(peer_connection: Arc<RTCPeerConnection>, user_id: String) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn new(
+        peer_connection: Arc<RTCPeerConnection>,
+        target_id: String,
+        user_id: String,
+        is_forwarding_allowed: bool,
+        pacing_multiplier: f64,
+        hls_fallback_min_bps: f64,
+        on_downlink_starved: DownlinkStarvedCallback,
+        qos: Arc<QosPolicy>,
+    ) -> Self {
         let cancel_token = CancellationToken::new();
         let (tx, _rx) = watch::channel(());
 
@@ -183,9 +254,21 @@ impl Subscriber {
             network_stats: Arc::new(RwLock::new(NetworkStats::default())),
             tracks: Arc::new(DashMap::new()),
             track_map: Arc::new(DashMap::new()),
+            target_id,
             user_id,
+            is_forwarding_allowed: AtomicBool::new(is_forwarding_allowed),
             data_channel: None,
             client_requested_quality: Arc::new(RwLock::new(None)),
+            viewport_quality: AtomicU8::new(TrackQuality::Medium.as_u8()),
+            pacer: Arc::new(Pacer::new(pacing_multiplier, qos.egress_meter())),
+            downlink_watch: Arc::new(DownlinkWatch {
+                min_bps: hls_fallback_min_bps,
+                starved_since: parking_lot::Mutex::new(None),
+                has_fired: AtomicBool::new(false),
+                callback: on_downlink_starved,
+            }),
+            qos,
+            _live: LiveGuard::new(LiveObject::Subscriber),
         };
 
         this.spawn_rtcp_monitor(cancel_token, tx.clone());
@@ -336,9 +419,14 @@ impl Subscriber {
         let forward_track = {
             let track_guard = remote_track.read();
             let ssrc = track_guard.ssrc;
-            track_guard.new_forward_track(&self.user_id, ssrc)?
+            track_guard.new_forward_track(&self.user_id, ssrc, Arc::clone(&self.pacer))?
         };
 
+        forward_track.set_forwarding_allowed(self.is_forwarding_allowed());
+        if !forward_track.is_audio() {
+            forward_track.set_requested_quality(&self.viewport_quality());
+        }
+
         let local_track = { forward_track.local_track.clone() };
 
         let _ = peer_connection
@@ -355,17 +443,99 @@ impl Subscriber {
         self.track_map
             .insert(track_id.to_owned(), Arc::clone(&forward_track));
 
+        self.apply_qos();
+
         Ok(())
     }
 
+    /// Pause or resume forwarding of every track without renegotiating the peer
+    pub fn set_forwarding_allowed(&self, is_allowed: bool) {
+        self.is_forwarding_allowed
+            .store(is_allowed, Ordering::Relaxed);
+
+        for entry in self.track_map.iter() {
+            entry.value().set_forwarding_allowed(is_allowed);
+        }
+    }
+
+    pub fn is_forwarding_allowed(&self) -> bool {
+        self.is_forwarding_allowed.load(Ordering::Relaxed)
+    }
+
+    /// Caps the video layers forwarded to what the target's tile needs, audio
+    /// keeps flowing whatever the tile. Network conditions can still lower it.
+    pub fn set_viewport_quality(&self, quality: TrackQuality) {
+        let previous = self
+            .viewport_quality
+            .swap(quality.as_u8(), Ordering::Relaxed);
+        if previous == quality.as_u8() {
+            return;
+        }
+
+        for entry in self.track_map.iter() {
+            let forward_track = entry.value();
+            if !forward_track.is_audio() {
+                forward_track.set_requested_quality(&quality);
+            }
+        }
+    }
+
+    pub fn viewport_quality(&self) -> TrackQuality {
+        TrackQuality::from_u8(self.viewport_quality.load(Ordering::Relaxed))
+    }
+
+    pub fn pacer_stats(&self) -> PacerStats {
+        self.pacer.stats()
+    }
+
+    /// Re-applies the room's QoS policy to the layers and pacing of the
+    /// target's streams, e.g. after the target's class changed
+    pub fn apply_qos(&self) {
+        let preferred = TrackQuality::from_u8(self.preferred_quality.load(Ordering::Relaxed));
+
+        Self::_apply_qos(
+            &self.qos,
+            &self.target_id,
+            preferred,
+            &self.track_map,
+            &self.pacer,
+        );
+    }
+
+    fn _apply_qos(
+        qos: &QosPolicy,
+        target_id: &str,
+        preferred: TrackQuality,
+        track_map: &TrackMap,
+        pacer: &Pacer,
+    ) {
+        let mut has_screen = false;
+
+        for entry in track_map.iter() {
+            let forward_track = entry.value();
+            has_screen |= forward_track.is_screen();
+
+            let quality = qos.cap_quality(target_id, forward_track.is_screen(), preferred.clone());
+            forward_track.set_effective_quality(&quality);
+        }
+
+        pacer.set_rate_scale(qos.pacing_scale(target_id, has_screen));
+    }
+
     fn spawn_rtcp_monitor(&self, cancel_token: CancellationToken, tx: watch::Sender<()>) {
         let pc = Arc::downgrade(&self.peer_connection);
         let preferred_quality = Arc::clone(&self.preferred_quality);
         let network_stats = Arc::clone(&self.network_stats);
+        let pacer = Arc::clone(&self.pacer);
+        let downlink_watch = Arc::clone(&self.downlink_watch);
+        let qos = Arc::clone(&self.qos);
+        let user_id = self.user_id.clone();
+        let target_id = self.target_id.clone();
 
         tokio::spawn(async move {
             let mut interval = tokio::time::interval(RTCP_MONITOR_INTERVAL);
             interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            let mut was_under_pressure = false;
 
             loop {
                 tokio::select! {
@@ -374,7 +544,19 @@ impl Subscriber {
                     }
                     _ = interval.tick() => {
                         if let Some(pc_strong) = pc.upgrade() {
-                            Self::monitor_rtcp(pc_strong, preferred_quality.clone(), network_stats.clone(), tx.clone()).await;
+                            Self::monitor_rtcp(pc_strong, preferred_quality.clone(), network_stats.clone(), &pacer, tx.clone()).await;
+
+                            let quality = TrackQuality::from_u8(preferred_quality.load(Ordering::Relaxed));
+                            qos.report_downlink(&user_id, &target_id, &quality);
+                            downlink_watch.observe(quality, pacer.remb_bps());
+
+                            // Pressure comes and goes with the rest of the room and
+                            // node too, not only with this downlink
+                            let is_under_pressure = qos.is_under_pressure();
+                            if is_under_pressure != was_under_pressure {
+                                was_under_pressure = is_under_pressure;
+                                let _ = tx.send(());
+                            }
                         } else {
                             break; // PeerConnection was dropped
                         }
@@ -388,6 +570,7 @@ impl Subscriber {
         peer_connection: Arc<RTCPeerConnection>,
         preferred_quality: Arc<AtomicU8>,
         network_stats: Arc<RwLock<NetworkStats>>,
+        pacer: &Pacer,
         tx: watch::Sender<()>,
     ) {
         let senders = peer_connection.get_senders().await;
@@ -398,11 +581,18 @@ impl Subscriber {
             match result {
                 Ok(Ok((rtcp_packets, _attrs))) => {
                     for packet in rtcp_packets {
-                        // Process TWCC packets only
+                        // TWCC drives quality, REMB feeds the pacer's bandwidth estimate
                         if let Some(tcc) = packet.as_any().downcast_ref::<TransportLayerCc>() {
                             twcc_processed = true;
                             Self::process_twcc_feedback(tcc, &network_stats).await;
                         }
+
+                        if let Some(remb) = packet
+                            .as_any()
+                            .downcast_ref::<ReceiverEstimatedMaximumBitrate>()
+                        {
+                            pacer.set_remb(remb.bitrate as f64);
+                        }
                     }
                 }
                 Ok(Err(e)) => {
@@ -473,34 +663,18 @@ impl Subscriber {
     }
 
     fn spawn_track_update_loop(&self, tx: watch::Sender<()>) {
-        // let tracks = Arc::clone(&self.tracks);
         let preferred_quality = Arc::clone(&self.preferred_quality);
         let track_map = Arc::clone(&self.track_map);
+        let qos = Arc::clone(&self.qos);
+        let pacer = Arc::clone(&self.pacer);
+        let target_id = self.target_id.clone();
         let mut rx = tx.subscribe();
 
         tokio::spawn(async move {
             while rx.changed().await.is_ok() {
                 let preferred = TrackQuality::from_u8(preferred_quality.load(Ordering::Relaxed));
 
-                // Batch update all tracks for better performance
-                let update_tasks: Vec<_> = track_map
-                    .iter()
-                    .map(|entry| {
-                        let _track_id = entry.key().clone();
-                        let forward_track = entry.value().clone();
-                        let quality = preferred.clone();
-
-                        tokio::spawn(async move {
-                            forward_track.set_effective_quality(&quality);
-                            // debug!("Track {} quality -> {:?}", track_id, quality);
-                        })
-                    })
-                    .collect();
-
-                // Wait for all updates to complete
-                for task in update_tasks {
-                    let _ = task.await;
-                }
+                Self::_apply_qos(&qos, &target_id, preferred, &track_map, &pacer);
             }
         });
     }
@@ -601,6 +775,7 @@ impl Subscriber {
 
     pub fn close(&self) {
         self.cancel_token.cancel();
+        self.qos.forget_downlink(&self.user_id, &self.target_id);
         self.clear_all_forward_tracks();
 
         let pc = Arc::clone(&self.peer_connection);
@@ -637,3 +812,83 @@ impl Subscriber {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+
+    const MIN_BPS: f64 = 150_000.0;
+
+    fn watch(min_bps: f64) -> (DownlinkWatch, Arc<AtomicUsize>) {
+        let fired = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&fired);
+
+        let watch = DownlinkWatch {
+            min_bps,
+            starved_since: parking_lot::Mutex::new(None),
+            has_fired: AtomicBool::new(false),
+            callback: Arc::new(move || {
+                counter.fetch_add(1, Ordering::Relaxed);
+                Box::pin(async {})
+            }),
+        };
+
+        (watch, fired)
+    }
+
+    #[tokio::test]
+    async fn test_fires_once_after_the_grace_period() {
+        let (watch, fired) = watch(MIN_BPS);
+        let now = Instant::now();
+
+        watch._observe(TrackQuality::Low, Some(100_000.0), now);
+        watch._observe(TrackQuality::Low, Some(100_000.0), now + STARVED_GRACE / 2);
+        assert_eq!(fired.load(Ordering::Relaxed), 0);
+
+        watch._observe(TrackQuality::Low, Some(100_000.0), now + STARVED_GRACE);
+        watch._observe(TrackQuality::Low, Some(100_000.0), now + STARVED_GRACE * 2);
+        assert_eq!(fired.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_recovery_restarts_the_grace_period() {
+        let (watch, fired) = watch(MIN_BPS);
+        let now = Instant::now();
+
+        watch._observe(TrackQuality::Low, Some(100_000.0), now);
+        watch._observe(TrackQuality::Low, Some(200_000.0), now + STARVED_GRACE / 2);
+        watch._observe(TrackQuality::Low, Some(100_000.0), now + STARVED_GRACE);
+        assert_eq!(fired.load(Ordering::Relaxed), 0);
+
+        watch._observe(TrackQuality::Low, Some(100_000.0), now + STARVED_GRACE * 2);
+        assert_eq!(fired.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_only_the_lowest_layer_with_a_remb_counts() {
+        let (watch, fired) = watch(MIN_BPS);
+        let now = Instant::now();
+
+        for quality in [TrackQuality::Medium, TrackQuality::High] {
+            watch._observe(quality, Some(100_000.0), now);
+            watch._observe(quality, Some(100_000.0), now + STARVED_GRACE);
+        }
+        watch._observe(TrackQuality::Low, None, now);
+        watch._observe(TrackQuality::Low, None, now + STARVED_GRACE);
+
+        assert_eq!(fired.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn test_zero_threshold_disables_the_fallback() {
+        let (watch, fired) = watch(0.0);
+        let now = Instant::now();
+
+        watch._observe(TrackQuality::Low, Some(0.0), now);
+        watch._observe(TrackQuality::Low, Some(0.0), now + STARVED_GRACE);
+
+        assert_eq!(fired.load(Ordering::Relaxed), 0);
+    }
+}
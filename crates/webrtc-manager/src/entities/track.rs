@@ -1,20 +1,40 @@
+use bytes::Bytes;
+use crossbeam::channel::Receiver;
 use dashmap::DashMap;
 use egress_manager::egress::hls_writer::HlsWriter;
 use egress_manager::egress::moq_writer::MoQWriter;
+use egress_manager::egress::track_archive::TrackRecorder;
+use parking_lot::RwLock;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
-use tracing::debug;
+use std::time::Instant;
+use tracing::{debug, warn};
+use webrtc::rtp::packet::Packet;
 use webrtc::rtp_transceiver::rtp_codec::{RTCRtpCodecCapability, RTPCodecType};
 use webrtc::track::track_remote::TrackRemote;
+use webrtc::util::marshal::Marshal;
 
 use crate::errors::WebRTCError;
 use crate::models::quality::TrackQuality;
 use crate::models::rtp_foward_info::RtpForwardInfo;
-use crate::utils::multicast_sender::MulticastSender;
+use crate::utils::{
+    buffer_pool::RtpBufferPool,
+    egress_layer::{EgressLayer, EgressLayerSelector},
+    keyframe::is_keyframe,
+    live_objects::{LiveGuard, LiveObject},
+    multicast_sender::MulticastSender,
+    noise_suppression::{DenoisedAudio, NoiseSuppressionPermit},
+    pacer::Pacer,
+    svc::{DEPENDENCY_DESCRIPTOR_URI, SvcParser},
+    talk_time::{AUDIO_LEVEL_URI, TalkTimeMeter},
+};
 
 use super::forward_track::ForwardTrack;
 
+/// The HLS writer of a media, empty while it isn't streamed
+pub type HlsWriterSlot = Arc<RwLock<Option<Arc<HlsWriter>>>>;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum CodecType {
     H264,
@@ -24,12 +44,45 @@ pub enum CodecType {
     Other,
 }
 
+impl CodecType {
+    pub fn from_mime_type(mime_type: &str) -> Self {
+        match mime_type.to_lowercase().as_str() {
+            s if s.contains("vp8") => CodecType::VP8,
+            s if s.contains("vp9") => CodecType::VP9,
+            s if s.contains("av1") => CodecType::AV1,
+            s if s.contains("h264") => CodecType::H264,
+            _ => CodecType::Other,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CodecType::H264 => "h264",
+            CodecType::VP8 => "vp8",
+            CodecType::VP9 => "vp9",
+            CodecType::AV1 => "av1",
+            CodecType::Other => "other",
+        }
+    }
+
+    /// Whether `codecs` names this codec, as a bare name or a mime type and in
+    /// any case
+    pub fn is_in(&self, codecs: &[String]) -> bool {
+        codecs.iter().any(|codec| {
+            let codec = codec.to_lowercase();
+            codec.strip_prefix("video/").unwrap_or(&codec) == self.as_str()
+        })
+    }
+}
+
 #[derive(Clone)]
 pub struct Track {
     pub id: String,
     pub room_id: String,
     pub participant_id: String,
     pub is_simulcast: Arc<AtomicBool>,
+    /// Whether the codec can carry SVC layers, whether a stream does is
+    /// told per packet
     pub is_svc: bool,
     pub codec_type: CodecType,
     pub stream_id: String,
@@ -38,36 +91,49 @@ pub struct Track {
     pub remote_tracks: Vec<Arc<TrackRemote>>,
     pub forward_tracks: Arc<DashMap<String, Arc<ForwardTrack>>>,
     pub ssrc: u32,
+    /// System audio shared with the screen, recorded on its own mixer input
+    pub is_screen_audio: Arc<AtomicBool>,
+    pub is_screen: Arc<AtomicBool>,
+    /// Set on egress sources only
+    hls_writer: Option<HlsWriterSlot>,
+    moq_writer: Option<Arc<MoQWriter>>,
+    /// Raw archive of this track, of the highest simulcast layer sent
+    recorder: Option<Arc<TrackRecorder>>,
+    /// The simulcast layer the archive and live egress are fed from
+    egress_layer: Arc<EgressLayerSelector>,
+    /// How long the publisher spoke, audio only
+    talk_time: Option<Arc<TalkTimeMeter>>,
     acceptable_map: Arc<DashMap<(TrackQuality, TrackQuality), bool>>,
     rtp_multicast: MulticastSender,
+    rtp_buffer_pool: Arc<RtpBufferPool>,
     keyframe_request_callback: Option<Arc<dyn Fn(u32) + Send + Sync>>,
+    _live: LiveGuard,
 }
 
 impl Track {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         track: Arc<TrackRemote>,
         room_id: String,
         participant_id: String,
-        hls_writer: Option<Arc<HlsWriter>>,
+        hls_writer: Option<HlsWriterSlot>,
         moq_writer: Option<Arc<MoQWriter>>,
+        recorder: Option<Arc<TrackRecorder>>,
         keyframe_request_callback: Option<Arc<dyn Fn(u32) + Send + Sync>>,
+        rtp_buffer_pool: Arc<RtpBufferPool>,
+        noise_suppression: Option<NoiseSuppressionPermit>,
     ) -> Self {
         let kind = track.kind();
 
-        // Determine codec type from mime type
-        let codec_type = match track.codec().capability.mime_type.to_lowercase().as_str() {
-            s if s.contains("vp8") => CodecType::VP8,
-            s if s.contains("vp9") => CodecType::VP9,
-            s if s.contains("av1") => CodecType::AV1,
-            s if s.contains("h264") => CodecType::H264,
-            _ => CodecType::Other,
-        };
+        let codec_type = CodecType::from_mime_type(&track.codec().capability.mime_type);
 
-        // Determine if SVC is used based on codec
-        let is_svc = matches!(codec_type, CodecType::VP9);
+        let is_svc = matches!(codec_type, CodecType::VP9 | CodecType::AV1);
 
         let rtp_multicast = MulticastSender::new();
 
+        let talk_time = (kind == RTPCodecType::Audio)
+            .then(|| Arc::new(TalkTimeMeter::new(track.codec().capability.clock_rate)));
+
         let handler = Track {
             id: track.id(),
             room_id,
@@ -82,13 +148,29 @@ impl Track {
             forward_tracks: Arc::new(DashMap::new()),
             acceptable_map: Arc::new(DashMap::new()),
             ssrc: track.ssrc(),
+            is_screen_audio: Arc::new(AtomicBool::new(false)),
+            is_screen: Arc::new(AtomicBool::new(false)),
+            hls_writer: hls_writer.clone(),
+            moq_writer: moq_writer.clone(),
+            recorder: recorder.clone(),
+            egress_layer: Arc::new(EgressLayerSelector::new()),
+            talk_time,
             rtp_multicast,
+            rtp_buffer_pool,
             keyframe_request_callback: keyframe_request_callback.clone(),
+            _live: LiveGuard::new(LiveObject::Track),
         };
 
         handler.rebuild_acceptable_map();
 
-        handler._forward_rtp(track, hls_writer, moq_writer, kind);
+        handler._forward_rtp(
+            track,
+            hls_writer,
+            moq_writer,
+            recorder,
+            noise_suppression,
+            kind,
+        );
 
         handler
     }
@@ -98,7 +180,15 @@ impl Track {
 
         self.rebuild_acceptable_map();
 
-        self._forward_rtp(track, None, None, self.kind);
+        // Egress taps every layer and keeps the highest one flowing
+        self._forward_rtp(
+            track,
+            self.hls_writer.clone(),
+            self.moq_writer.clone(),
+            self.recorder.clone(),
+            None,
+            self.kind,
+        );
 
         self.is_simulcast.store(true, Ordering::Relaxed);
     }
@@ -106,9 +196,30 @@ impl Track {
     pub fn stop(&mut self) {
         self.remote_tracks.clear();
         self.forward_tracks.clear();
+
+        if let Some(recorder) = &self.recorder {
+            recorder.finish();
+        }
     }
 
-    pub fn new_forward_track(&self, id: &str, ssrc: u32) -> Result<Arc<ForwardTrack>, WebRTCError> {
+    pub fn set_archive_paused(&self, is_paused: bool) {
+        if let Some(recorder) = &self.recorder {
+            recorder.set_paused(is_paused);
+        }
+    }
+
+    pub fn talk_time_ms(&self) -> u64 {
+        self.talk_time
+            .as_ref()
+            .map_or(0, |talk_time| talk_time.talk_time_ms())
+    }
+
+    pub fn new_forward_track(
+        &self,
+        id: &str,
+        ssrc: u32,
+        pacer: Arc<Pacer>,
+    ) -> Result<Arc<ForwardTrack>, WebRTCError> {
         if self.forward_tracks.contains_key(id) {
             return Err(WebRTCError::FailedToAddTrack);
         }
@@ -121,6 +232,8 @@ impl Track {
             id.to_string(),
             ssrc,
             self.keyframe_request_callback.clone(),
+            pacer,
+            self.is_screen.load(Ordering::Relaxed),
         );
         self.forward_tracks
             .insert(id.to_owned(), forward_track.clone());
@@ -132,6 +245,26 @@ impl Track {
         self.forward_tracks.remove(id);
     }
 
+    /// Tap the forwarding bus directly, for subscribers not backed by a `ForwardTrack`
+    pub fn add_bus_receiver(&self, id: &str) -> Receiver<RtpForwardInfo> {
+        self.rtp_multicast.add_receiver(id.to_string())
+    }
+
+    pub fn remove_bus_receiver(&self, id: &str) {
+        self.rtp_multicast.remove_receiver(id);
+    }
+
+    /// Channels fanning this track's RTP out, forward tracks and bus taps alike
+    pub fn forwarding_channel_count(&self) -> usize {
+        self.rtp_multicast.receiver_count()
+    }
+
+    pub fn request_keyframe(&self) {
+        if let Some(cb) = &self.keyframe_request_callback {
+            cb(self.ssrc);
+        }
+    }
+
     pub fn rebuild_acceptable_map(&self) {
         let available_qualities: Vec<TrackQuality> = self
             .remote_tracks
@@ -200,8 +333,10 @@ impl Track {
     pub fn _forward_rtp(
         &self,
         remote_track: Arc<TrackRemote>,
-        _hls_writer: Option<Arc<HlsWriter>>,
-        _moq_writer: Option<Arc<MoQWriter>>,
+        hls_writer: Option<HlsWriterSlot>,
+        moq_writer: Option<Arc<MoQWriter>>,
+        recorder: Option<Arc<TrackRecorder>>,
+        noise_suppression: Option<NoiseSuppressionPermit>,
         kind: RTPCodecType,
     ) {
         let multicast = self.rtp_multicast.clone();
@@ -209,20 +344,139 @@ impl Track {
         let acceptable_map = Arc::clone(&self.acceptable_map);
         let is_svc = self.is_svc;
         let is_simulcast = Arc::clone(&self.is_simulcast);
+        let rtp_buffer_pool = Arc::clone(&self.rtp_buffer_pool);
+        let is_screen_audio = Arc::clone(&self.is_screen_audio);
+        let egress_layer = Arc::clone(&self.egress_layer);
+        let codec_type = self.codec_type.clone();
+        let keyframe_request_callback = self.keyframe_request_callback.clone();
+        let talk_time = self.talk_time.clone();
+
+        // Denoised packets take the rest of the way from the suppressor's thread
+        let denoiser = noise_suppression.and_then(|permit| {
+            let multicast = multicast.clone();
+            let acceptable_map = Arc::clone(&acceptable_map);
+            let current_quality = Arc::clone(&current_quality);
+            let hls_writer = hls_writer.clone();
+            let moq_writer = moq_writer.clone();
+
+            let on_packet = Arc::new(move |rtp: Packet| {
+                if let Ok(data) = rtp.marshal() {
+                    let hls_writer = hls_writer.as_ref().and_then(|slot| slot.read().clone());
+                    Self::_write_egress(&hls_writer, &moq_writer, &data, false, false);
+                }
+
+                multicast.send(RtpForwardInfo {
+                    packet: Arc::new(rtp),
+                    acceptable_map: acceptable_map.clone(),
+                    svc_layer: None,
+                    is_simulcast: false,
+                    track_quality: (*current_quality).clone(),
+                });
+            });
+
+            DenoisedAudio::new(remote_track.payload_type(), permit, on_packet)
+                .inspect_err(|err| warn!("Failed to start noise suppression: {:?}", err))
+                .ok()
+        });
 
         tokio::spawn(async move {
-            let _is_video = kind == RTPCodecType::Video;
+            let is_video = kind == RTPCodecType::Video;
+
+            let mut svc_parser = if is_svc {
+                let dependency_descriptor_id = remote_track
+                    .params()
+                    .await
+                    .header_extensions
+                    .iter()
+                    .find(|extension| extension.uri == DEPENDENCY_DESCRIPTOR_URI)
+                    .map(|extension| extension.id as u8);
+
+                Some(SvcParser::new(codec_type.clone(), dependency_descriptor_id))
+            } else {
+                None
+            };
+
+            let audio_level_id = match &talk_time {
+                Some(_) => remote_track
+                    .params()
+                    .await
+                    .header_extensions
+                    .iter()
+                    .find(|extension| extension.uri == AUDIO_LEVEL_URI)
+                    .map(|extension| extension.id as u8),
+                None => None,
+            };
 
             loop {
-                let result = remote_track.read_rtp().await;
+                // The payload is copied out on unmarshal, so the buffer goes back right away
+                let result = {
+                    let mut buf = rtp_buffer_pool.acquire();
+                    remote_track.read(&mut buf).await
+                };
 
                 match result {
                     Ok((rtp, _)) => {
                         if !rtp.payload.is_empty() {
+                            if let (Some(talk_time), Some(id)) = (&talk_time, audio_level_id)
+                                && let Some(level) = rtp.header.get_extension(id)
+                            {
+                                talk_time.record(rtp.header.timestamp, &level);
+                            }
+
+                            let hls = hls_writer.as_ref().and_then(|slot| slot.read().clone());
+
+                            let has_sink = hls.is_some()
+                                || moq_writer.is_some()
+                                || recorder.is_some()
+                                || denoiser.is_some();
+
+                            let data = if has_sink { rtp.marshal().ok() } else { None };
+
+                            if let Some(data) = &data {
+                                let tapped = if is_video {
+                                    Self::_tap_layer(
+                                        &egress_layer,
+                                        &current_quality,
+                                        &codec_type,
+                                        &rtp,
+                                        data,
+                                        &keyframe_request_callback,
+                                    )
+                                } else {
+                                    Some((data.clone(), rtp.header.timestamp))
+                                };
+
+                                if let Some((egress_data, timestamp)) = &tapped {
+                                    // The archive keeps what the publisher sent
+                                    if let Some(recorder) = &recorder {
+                                        let _ = recorder.write_rtp(egress_data, *timestamp);
+                                    }
+                                }
+
+                                if let Some(denoiser) = &denoiser
+                                    && denoiser.write(&rtp, data)
+                                {
+                                    continue;
+                                }
+
+                                if let Some((egress_data, _)) = &tapped {
+                                    Self::_write_egress(
+                                        &hls,
+                                        &moq_writer,
+                                        egress_data,
+                                        is_video,
+                                        is_screen_audio.load(Ordering::Relaxed),
+                                    );
+                                }
+                            }
+
+                            let svc_layer =
+                                svc_parser.as_mut().and_then(|parser| parser.parse(&rtp));
+
                             let info = RtpForwardInfo {
                                 packet: Arc::new(rtp),
                                 acceptable_map: acceptable_map.clone(),
-                                is_svc,
+                                svc_layer,
                                 is_simulcast: is_simulcast.load(Ordering::Relaxed),
                                 track_quality: (*current_quality).clone(),
                             };
@@ -237,7 +491,168 @@ impl Track {
                 }
             }
 
+            // The track went away in a renegotiation, a republished track gets a new file
+            if let Some(recorder) = &recorder {
+                recorder.finish();
+            }
+
             debug!("[track] exit track loop {}", remote_track.rid());
         });
     }
+
+    /// The packet of a video layer as egress writes it, with its timestamp.
+    /// `None` when egress is tapping another layer, or moving to this one
+    /// and waiting on its key frame.
+    fn _tap_layer(
+        egress_layer: &EgressLayerSelector,
+        quality: &TrackQuality,
+        codec_type: &CodecType,
+        rtp: &Packet,
+        data: &Bytes,
+        keyframe_request_callback: &Option<Arc<dyn Fn(u32) + Send + Sync>>,
+    ) -> Option<(Bytes, u32)> {
+        let header = &rtp.header;
+
+        let tapped = egress_layer.observe(
+            quality,
+            header.ssrc,
+            header.sequence_number,
+            header.timestamp,
+            is_keyframe(codec_type, &rtp.payload).unwrap_or(true),
+            Instant::now(),
+        );
+
+        let (ssrc, sequence_number, timestamp) = match tapped {
+            EgressLayer::Write {
+                ssrc,
+                sequence_number,
+                timestamp,
+            } => (ssrc, sequence_number, timestamp),
+            EgressLayer::RequestKeyframe => {
+                if let Some(cb) = keyframe_request_callback {
+                    cb(header.ssrc);
+                }
+                return None;
+            }
+            EgressLayer::Skip => return None,
+        };
+
+        if ssrc == header.ssrc
+            && sequence_number == header.sequence_number
+            && timestamp == header.timestamp
+        {
+            return Some((data.clone(), timestamp));
+        }
+
+        let mut packet = rtp.clone();
+        packet.header.ssrc = ssrc;
+        packet.header.sequence_number = sequence_number;
+        packet.header.timestamp = timestamp;
+
+        packet.marshal().ok().map(|data| (data, timestamp))
+    }
+
+    fn _write_egress(
+        hls_writer: &Option<Arc<HlsWriter>>,
+        moq_writer: &Option<Arc<MoQWriter>>,
+        data: &[u8],
+        is_video: bool,
+        is_screen_audio: bool,
+    ) {
+        if let Some(writer) = hls_writer {
+            let _ = if is_screen_audio {
+                writer.write_screen_audio_rtp(data)
+            } else {
+                writer.write_rtp(data, is_video)
+            };
+        }
+
+        // MoQ carries a single audio track, the microphone
+        if let Some(writer) = moq_writer
+            && !is_screen_audio
+        {
+            let _ = writer.write_rtp(data, is_video);
+        }
+    }
+}
+
+#[cfg(test)]
+impl Track {
+    /// A track without a publisher behind it, its packets are handed in by the test
+    pub(crate) fn detached(
+        id: &str,
+        kind: RTPCodecType,
+        capability: RTCRtpCodecCapability,
+    ) -> Self {
+        let codec_type = CodecType::from_mime_type(&capability.mime_type);
+
+        Track {
+            id: id.to_owned(),
+            room_id: "room".to_owned(),
+            participant_id: "publisher".to_owned(),
+            is_simulcast: Arc::new(AtomicBool::new(false)),
+            is_svc: false,
+            codec_type,
+            stream_id: "stream".to_owned(),
+            capability,
+            kind,
+            remote_tracks: vec![],
+            forward_tracks: Arc::new(DashMap::new()),
+            acceptable_map: Arc::new(DashMap::new()),
+            ssrc: 1,
+            is_screen_audio: Arc::new(AtomicBool::new(false)),
+            is_screen: Arc::new(AtomicBool::new(false)),
+            hls_writer: None,
+            moq_writer: None,
+            recorder: None,
+            egress_layer: Arc::new(EgressLayerSelector::new()),
+            talk_time: None,
+            rtp_multicast: MulticastSender::new(),
+            rtp_buffer_pool: Arc::new(RtpBufferPool::new(1)),
+            keyframe_request_callback: None,
+            _live: LiveGuard::new(LiveObject::Track),
+        }
+    }
+
+    /// Fans a packet out as if the publisher had sent it
+    pub(crate) fn forward(&self, packet: Packet) {
+        self.rtp_multicast.send(RtpForwardInfo {
+            packet: Arc::new(packet),
+            acceptable_map: Arc::clone(&self.acceptable_map),
+            svc_layer: None,
+            is_simulcast: false,
+            track_quality: TrackQuality::Medium,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codecs(codecs: &[&str]) -> Vec<String> {
+        codecs.iter().map(|codec| codec.to_string()).collect()
+    }
+
+    #[test]
+    fn test_codec_from_mime_type() {
+        assert_eq!(CodecType::from_mime_type("video/VP8"), CodecType::VP8);
+        assert_eq!(CodecType::from_mime_type("video/H264"), CodecType::H264);
+        assert_eq!(CodecType::from_mime_type("video/AV1"), CodecType::AV1);
+        assert_eq!(CodecType::from_mime_type("audio/opus"), CodecType::Other);
+    }
+
+    #[test]
+    fn test_codec_is_named_by_bare_name_or_mime_type() {
+        assert!(CodecType::VP8.is_in(&codecs(&["vp8"])));
+        assert!(CodecType::VP8.is_in(&codecs(&["h264", "video/VP8"])));
+        assert!(CodecType::H264.is_in(&codecs(&["H264"])));
+    }
+
+    #[test]
+    fn test_codec_missing_from_the_list() {
+        assert!(!CodecType::VP9.is_in(&codecs(&["vp8", "h264"])));
+        assert!(!CodecType::VP8.is_in(&codecs(&["audio/vp8"])));
+        assert!(!CodecType::AV1.is_in(&[]));
+    }
 }
@@ -43,4 +43,16 @@ pub enum WebRTCError {
 
     #[error("Room not found")]
     RoomNotFound,
+
+    #[error("Failed to start slate")]
+    FailedToStartSlate,
+
+    #[error("Failed to start egress")]
+    FailedToStartEgress,
+
+    #[error("Observers can only subscribe")]
+    ObserverCannotPublish,
+
+    #[error("Subscriber can't decode {0}")]
+    CodecMismatch(String),
 }
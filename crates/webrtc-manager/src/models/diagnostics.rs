@@ -0,0 +1,87 @@
+use std::ops::AddAssign;
+
+use crate::utils::live_objects::LiveObject;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ObjectCounts {
+    pub rooms: usize,
+    pub publishers: usize,
+    pub subscribers: usize,
+    pub tracks: usize,
+    pub forward_tracks: usize,
+}
+
+impl ObjectCounts {
+    /// Every instance still allocated, reachable or not
+    pub fn live() -> Self {
+        Self {
+            rooms: LiveObject::Room.count(),
+            publishers: LiveObject::Publisher.count(),
+            subscribers: LiveObject::Subscriber.count(),
+            tracks: LiveObject::Track.count(),
+            forward_tracks: LiveObject::ForwardTrack.count(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl AddAssign for ObjectCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.rooms += other.rooms;
+        self.publishers += other.publishers;
+        self.subscribers += other.subscribers;
+        self.tracks += other.tracks;
+        self.forward_tracks += other.forward_tracks;
+    }
+}
+
+/// What a node holds right now. `live` above `reachable` means objects that
+/// outlived their room, something still holds on to them.
+#[derive(Debug, Clone, Default)]
+pub struct NodeDiagnostics {
+    pub clients: usize,
+    /// Walked from the room map
+    pub reachable: ObjectCounts,
+    pub live: ObjectCounts,
+    pub str0m_subscribers: usize,
+    pub forwarding_channels: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_add_up_per_object() {
+        let mut total = ObjectCounts::default();
+        assert!(total.is_empty());
+
+        total += ObjectCounts {
+            rooms: 1,
+            publishers: 2,
+            tracks: 4,
+            ..Default::default()
+        };
+        total += ObjectCounts {
+            rooms: 1,
+            subscribers: 3,
+            forward_tracks: 6,
+            ..Default::default()
+        };
+
+        assert!(!total.is_empty());
+        assert_eq!(
+            total,
+            ObjectCounts {
+                rooms: 2,
+                publishers: 2,
+                subscribers: 3,
+                tracks: 4,
+                forward_tracks: 6,
+            }
+        );
+    }
+}
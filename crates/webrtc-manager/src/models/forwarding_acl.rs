@@ -0,0 +1,55 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use dashmap::DashSet;
+
+/// Decides whether a publisher's tracks may be forwarded to subscribers of a room.
+///
+/// Outside of stage mode every publisher is forwarded. Once stage mode is enabled,
+/// only participants designated as speakers are forwarded, so the audience only
+/// receives the stage while speakers keep seeing each other.
+#[derive(Debug, Default)]
+pub struct ForwardingAcl {
+    is_stage_mode: AtomicBool,
+    speakers: DashSet<String>,
+}
+
+impl ForwardingAcl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_stage_mode(&self, is_enabled: bool) {
+        self.is_stage_mode.store(is_enabled, Ordering::Relaxed);
+    }
+
+    pub fn is_stage_mode(&self) -> bool {
+        self.is_stage_mode.load(Ordering::Relaxed)
+    }
+
+    pub fn set_speaker(&self, participant_id: &str, is_speaker: bool) {
+        if is_speaker {
+            self.speakers.insert(participant_id.to_owned());
+        } else {
+            self.speakers.remove(participant_id);
+        }
+    }
+
+    /// Take over an ACL kept elsewhere, speakers not in `speakers` are dropped
+    pub fn replace(&self, is_stage_mode: bool, speakers: &[String]) {
+        self.speakers
+            .retain(|speaker| speakers.iter().any(|other| other == speaker));
+        for speaker in speakers {
+            self.speakers.insert(speaker.to_owned());
+        }
+
+        self.set_stage_mode(is_stage_mode);
+    }
+
+    pub fn is_speaker(&self, participant_id: &str) -> bool {
+        self.speakers.contains(participant_id)
+    }
+
+    pub fn can_forward(&self, publisher_id: &str) -> bool {
+        !self.is_stage_mode() || self.is_speaker(publisher_id)
+    }
+}
@@ -1,6 +1,13 @@
 pub mod connection_type;
 pub mod data_channel_msg;
+pub mod diagnostics;
+pub mod forwarding_acl;
 pub mod params;
+pub mod qos;
 pub mod quality;
+pub mod room_stats;
 pub mod rtp_foward_info;
+pub mod subscriber_backend;
+pub mod svc;
+pub mod track_info;
 pub mod track_quality_request;
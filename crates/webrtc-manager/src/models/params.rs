@@ -1,16 +1,28 @@
-use std::{pin::Pin, sync::Arc};
+use std::{pin::Pin, sync::Arc, time::Duration};
 
 use parking_lot::RwLock;
 use serde::Serialize;
 
-use crate::entities::track::Track;
+use crate::{
+    entities::track::Track,
+    utils::{
+        buffer_pool::RtpBufferPool, egress_meter::EgressMeter,
+        noise_suppression::NoiseSuppressionBudget,
+    },
+};
 
-use super::connection_type::ConnectionType;
+use super::{
+    connection_type::ConnectionType,
+    subscriber_backend::SubscriberBackend,
+    track_info::{PublishedTrack, TrackInfo},
+};
 
 pub type IceCandidateCallback =
     Arc<dyn Fn(IceCandidate) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
 pub type RenegotiationCallback =
     Arc<dyn Fn(String) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+pub type DownlinkStarvedCallback =
+    Arc<dyn Fn() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
 pub type JoinedCallback =
     Arc<dyn Fn(bool) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
 
@@ -19,12 +31,27 @@ pub struct WebRTCManagerConfigs {
     pub public_ip: String,
     pub port_min: u16,
     pub port_max: u16,
+    pub subscriber_backend: SubscriberBackend,
+    pub rtp_buffer_pool: Arc<RtpBufferPool>,
+    /// Subscriber egress is paced at this multiple of the estimated bandwidth, 0 disables pacing
+    pub pacing_multiplier: f64,
+    /// Subscribers whose REMB stays below this on the lowest layer are moved to HLS, 0 disables
+    pub hls_fallback_min_bps: f64,
+    /// Every published track is also archived untouched under this directory, one file per track
+    pub raw_recording_dir: Option<String>,
+    /// Caps the microphones denoised at once, for rooms with noise suppression on
+    pub noise_suppression_budget: Arc<NoiseSuppressionBudget>,
+    /// Video forwarded by the node against its uplink, attendees are degraded
+    /// first once it runs short
+    pub egress_meter: Arc<EgressMeter>,
 }
 
 #[derive(Debug, Clone)]
 pub struct WClient {
     pub participant_id: String,
     pub room_id: String,
+    /// Only ever subscribes, never becomes a publisher of the room
+    pub is_observer: bool,
 }
 
 #[derive(Clone)]
@@ -35,7 +62,11 @@ pub struct JoinRoomParams {
     pub is_audio_enabled: bool,
     pub is_e2ee_enabled: bool,
     pub total_tracks: u8,
+    pub tracks: Vec<TrackInfo>,
     pub connection_type: ConnectionType,
+    pub start_egress: bool,
+    pub noise_suppression: bool,
+    pub broadcast_delay: Duration,
     pub callback: JoinedCallback,
     pub on_candidate: IceCandidateCallback,
 }
@@ -53,6 +84,9 @@ pub struct SubscribeParams {
     pub participant_id: String,
     pub on_negotiation_needed: RenegotiationCallback,
     pub on_candidate: IceCandidateCallback,
+    pub on_downlink_starved: DownlinkStarvedCallback,
+    /// Video codecs the subscriber decodes, empty when it didn't say
+    pub video_codecs: Vec<String>,
 }
 
 #[derive(Serialize)]
@@ -62,11 +96,15 @@ pub struct SubscribeResponse {
     pub camera_type: u8,
     pub video_enabled: bool,
     pub audio_enabled: bool,
+    pub is_video_hardware_muted: bool,
+    pub is_audio_hardware_muted: bool,
     pub is_screen_sharing: bool,
     pub is_hand_raising: bool,
     pub is_e2ee_enabled: bool,
     pub video_codec: String,
     pub screen_track_id: Option<String>,
+    pub screen_audio_track_id: Option<String>,
+    pub tracks: Vec<PublishedTrack>,
 }
 
 #[derive(Debug, Serialize)]
@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use serde::Serialize;
+
+use crate::{models::quality::TrackQuality, utils::egress_meter::EgressMeter};
+
+/// Share of a room's subscriber downlinks down to the lowest layer at which
+/// the room counts as short of bandwidth
+const ROOM_PRESSURE_SHARE: f64 = 0.25;
+
+/// Attendees under pressure are paced at this share of their estimate, the
+/// headroom goes to the protected streams' key frames
+const ATTENDEE_PACING_SCALE: f64 = 0.5;
+
+/// How a participant's video fares when the room or node runs short of
+/// bandwidth. Attendees are degraded first, hosts and presenters keep the
+/// layer their subscribers' downlinks can carry, and so does screen sharing
+/// whoever publishes it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[repr(u8)]
+pub enum QosClass {
+    #[default]
+    Attendee = 0,
+    Presenter = 1,
+    Host = 2,
+}
+
+impl From<i32> for QosClass {
+    fn from(val: i32) -> Self {
+        match val {
+            1 => QosClass::Presenter,
+            2 => QosClass::Host,
+            _ => QosClass::Attendee,
+        }
+    }
+}
+
+impl QosClass {
+    pub fn is_protected(&self) -> bool {
+        *self != QosClass::Attendee
+    }
+}
+
+/// The QoS classes of a room's participants, and whether the room or the node
+/// is short of bandwidth right now. Subscribers report their downlinks and
+/// look up the layer and pacing each publisher's streams get.
+#[derive(Debug)]
+pub struct QosPolicy {
+    classes: DashMap<String, QosClass>,
+    /// Whether each subscriber downlink, by subscriber and target, is down to
+    /// the lowest layer
+    downlinks: DashMap<(String, String), bool>,
+    egress_meter: Arc<EgressMeter>,
+}
+
+impl QosPolicy {
+    pub fn new(egress_meter: Arc<EgressMeter>) -> Self {
+        Self {
+            classes: DashMap::new(),
+            downlinks: DashMap::new(),
+            egress_meter,
+        }
+    }
+
+    pub fn egress_meter(&self) -> Arc<EgressMeter> {
+        Arc::clone(&self.egress_meter)
+    }
+
+    pub fn set_class(&self, participant_id: &str, class: QosClass) {
+        if class == QosClass::Attendee {
+            self.classes.remove(participant_id);
+        } else {
+            self.classes.insert(participant_id.to_owned(), class);
+        }
+    }
+
+    pub fn class(&self, participant_id: &str) -> QosClass {
+        self.classes
+            .get(participant_id)
+            .map(|class| *class)
+            .unwrap_or_default()
+    }
+
+    pub fn report_downlink(&self, participant_id: &str, target_id: &str, quality: &TrackQuality) {
+        self.downlinks.insert(
+            (participant_id.to_owned(), target_id.to_owned()),
+            *quality <= TrackQuality::Low,
+        );
+    }
+
+    pub fn forget_downlink(&self, participant_id: &str, target_id: &str) {
+        self.downlinks
+            .remove(&(participant_id.to_owned(), target_id.to_owned()));
+    }
+
+    pub fn is_under_pressure(&self) -> bool {
+        if self.egress_meter.is_under_pressure() {
+            return true;
+        }
+
+        let total = self.downlinks.len();
+        let constrained = self.downlinks.iter().filter(|entry| *entry.value()).count();
+
+        constrained > 0 && constrained as f64 >= total as f64 * ROOM_PRESSURE_SHARE
+    }
+
+    /// The layer a stream of `publisher_id` gets, given the one the downlink
+    /// can carry. Attendees drop to the lowest layer under pressure.
+    pub fn cap_quality(
+        &self,
+        publisher_id: &str,
+        is_screen: bool,
+        quality: TrackQuality,
+    ) -> TrackQuality {
+        if is_screen || self.class(publisher_id).is_protected() || !self.is_under_pressure() {
+            return quality;
+        }
+
+        quality.min(TrackQuality::Low)
+    }
+
+    /// Scale of the pacing rate of a subscriber to `publisher_id`, whose
+    /// streams include a shared screen when `is_screen`
+    pub fn pacing_scale(&self, publisher_id: &str, is_screen: bool) -> f64 {
+        if is_screen || self.class(publisher_id).is_protected() || !self.is_under_pressure() {
+            return 1.0;
+        }
+
+        ATTENDEE_PACING_SCALE
+    }
+}
@@ -1,7 +1,6 @@
 use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
-use webrtc::rtp::codecs::vp9::Vp9Packet;
 
 #[derive(Debug, Clone, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
 #[repr(u8)]
@@ -39,24 +38,59 @@ impl TrackQuality {
         self.clone() as u8
     }
 
-    // Convert TrackQuality to SVC layer IDs for VP9/AV1
-    fn quality_to_svc_layers(&self) -> (u8, u8) {
-        match self {
-            TrackQuality::Low => (0, 0),
-            TrackQuality::Medium => (1, 1),
-            TrackQuality::High => (2, 2),
-            TrackQuality::None => (0, 0),
+    /// The layer worth sending to a tile of this size, by its short side so
+    /// portrait tiles aren't overfed. Matches the quarter, half and full
+    /// resolution layers of a 720p simulcast publisher.
+    pub fn for_viewport(width: u32, height: u32) -> TrackQuality {
+        match width.min(height) {
+            0 => TrackQuality::None,
+            1..=180 => TrackQuality::Low,
+            181..=360 => TrackQuality::Medium,
+            _ => TrackQuality::High,
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_viewport_picks_the_layer_by_its_short_side() {
+        assert_eq!(TrackQuality::for_viewport(320, 180), TrackQuality::Low);
+        assert_eq!(TrackQuality::for_viewport(640, 360), TrackQuality::Medium);
+        assert_eq!(TrackQuality::for_viewport(640, 361), TrackQuality::High);
+        assert_eq!(TrackQuality::for_viewport(1280, 720), TrackQuality::High);
+
+        // A portrait tile as tall as a 720p frame is still only 180 wide
+        assert_eq!(TrackQuality::for_viewport(180, 720), TrackQuality::Low);
+    }
+
+    #[test]
+    fn test_hidden_tile_pauses_video() {
+        assert_eq!(TrackQuality::for_viewport(0, 0), TrackQuality::None);
+        assert_eq!(TrackQuality::for_viewport(640, 0), TrackQuality::None);
+    }
 
-    // Check if an SVC packet should be forwarded based on desired quality
-    pub fn should_forward_vp9_svc(&self, vp9_packet: &Vp9Packet) -> bool {
-        if !vp9_packet.l || vp9_packet.tid == 0 {
-            return true;
+    #[test]
+    fn test_round_trips_through_u8() {
+        for quality in [
+            TrackQuality::None,
+            TrackQuality::Low,
+            TrackQuality::Medium,
+            TrackQuality::High,
+        ] {
+            assert_eq!(TrackQuality::from_u8(quality.as_u8()), quality);
         }
 
-        let (desired_spatial_id, _) = self.quality_to_svc_layers();
+        assert_eq!(TrackQuality::from_u8(42), TrackQuality::None);
+    }
 
-        vp9_packet.sid == desired_spatial_id
+    #[test]
+    fn test_simulcast_rids() {
+        assert_eq!("q".parse(), Ok(TrackQuality::Low));
+        assert_eq!("h".parse(), Ok(TrackQuality::Medium));
+        assert_eq!("f".parse(), Ok(TrackQuality::High));
+        assert_eq!("".parse(), Ok(TrackQuality::None));
     }
 }
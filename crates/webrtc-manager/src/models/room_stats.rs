@@ -0,0 +1,24 @@
+/// A publisher of a room, with what the node forwards of it
+#[derive(Debug, Clone, Default)]
+pub struct PublisherStats {
+    pub participant_id: String,
+    pub tracks: usize,
+    /// Subscribers the node forwards the publisher's streams to
+    pub subscribers: usize,
+    /// Pacing rate summed over those subscribers
+    pub egress_bps: u64,
+    /// How long the publisher's microphone carried speech
+    pub talk_time_ms: u64,
+}
+
+/// A room as one node sees it. A room that spans nodes, or is moving between
+/// them, has a share on each and the dispatcher merges them.
+#[derive(Debug, Clone, Default)]
+pub struct RoomStats {
+    pub publishers: Vec<PublisherStats>,
+    pub subscribers: usize,
+    pub egress_bps: u64,
+    /// Whether a publisher's media is being recorded or sent to HLS
+    pub is_recording: bool,
+    pub is_recording_paused: bool,
+}
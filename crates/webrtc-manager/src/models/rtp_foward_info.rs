@@ -3,13 +3,14 @@ use std::sync::Arc;
 use dashmap::DashMap;
 use webrtc::rtp::packet::Packet;
 
-use super::quality::TrackQuality;
+use super::{quality::TrackQuality, svc::SvcLayer};
 
 #[derive(Debug, Clone)]
 pub struct RtpForwardInfo {
     pub packet: Arc<Packet>,
     pub acceptable_map: Arc<DashMap<(TrackQuality, TrackQuality), bool>>,
-    pub is_svc: bool,
+    /// Set for packets of a stream sent with SVC
+    pub svc_layer: Option<SvcLayer>,
     pub is_simulcast: bool,
     pub track_quality: TrackQuality,
 }
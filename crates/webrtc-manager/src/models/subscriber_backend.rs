@@ -0,0 +1,20 @@
+/// Which WebRTC stack terminates subscriber peer connections. Publishers always
+/// run on webrtc-rs, str0m subscribers read the same forwarding bus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubscriberBackend {
+    WebRtcRs,
+    /// `workers` threads, each owning one UDP socket starting at `port`
+    Str0m {
+        port: u16,
+        workers: u16,
+    },
+}
+
+impl SubscriberBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubscriberBackend::WebRtcRs => "webrtc-rs",
+            SubscriberBackend::Str0m { .. } => "str0m",
+        }
+    }
+}
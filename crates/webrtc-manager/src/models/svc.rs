@@ -0,0 +1,51 @@
+use super::quality::TrackQuality;
+
+/// The highest temporal layer browsers publish, L3T3 being the richest mode
+const MAX_TEMPORAL_ID: u8 = 2;
+
+/// Where a packet of an SVC stream sits among its layers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SvcLayer {
+    pub spatial_id: u8,
+    pub temporal_id: u8,
+    /// First packet of a picture, where the layers sent can change
+    pub is_picture_start: bool,
+    /// Last packet of this layer's frame of the picture
+    pub is_layer_end: bool,
+    pub is_keyframe: bool,
+}
+
+/// The highest layers of an SVC stream a subscriber gets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SvcTarget {
+    pub spatial_id: u8,
+    pub temporal_id: u8,
+}
+
+impl SvcTarget {
+    /// The spatial layer fits both the tile, `requested`, and the downlink,
+    /// `effective`. A downlink too short even for the viewport's lowest
+    /// spatial layer loses the top temporal layer too, half the frame rate.
+    pub fn new(requested: &TrackQuality, effective: &TrackQuality) -> Self {
+        let spatial_id = match requested.min(effective) {
+            TrackQuality::High => 2,
+            TrackQuality::Medium => 1,
+            _ => 0,
+        };
+
+        let temporal_id = if effective < requested && *effective <= TrackQuality::Low {
+            MAX_TEMPORAL_ID - 1
+        } else {
+            MAX_TEMPORAL_ID
+        };
+
+        Self {
+            spatial_id,
+            temporal_id,
+        }
+    }
+
+    pub fn accepts(&self, layer: &SvcLayer) -> bool {
+        layer.spatial_id <= self.spatial_id && layer.temporal_id <= self.temporal_id
+    }
+}
@@ -0,0 +1,128 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[repr(u8)]
+pub enum TrackSource {
+    Camera = 0,
+    Microphone = 1,
+    Screen = 2,
+    ScreenAudio = 3,
+}
+
+impl From<u8> for TrackSource {
+    fn from(val: u8) -> Self {
+        match val {
+            1 => TrackSource::Microphone,
+            2 => TrackSource::Screen,
+            3 => TrackSource::ScreenAudio,
+            _ => TrackSource::Camera,
+        }
+    }
+}
+
+impl From<TrackSource> for u8 {
+    fn from(source: TrackSource) -> Self {
+        source as u8
+    }
+}
+
+impl TrackSource {
+    fn default_label(&self) -> &'static str {
+        match self {
+            TrackSource::Camera => "Camera",
+            TrackSource::Microphone => "Microphone",
+            TrackSource::Screen => "Screen",
+            TrackSource::ScreenAudio => "Screen audio",
+        }
+    }
+}
+
+/// What the publisher declared about a track, keyed by its MediaStreamTrack id
+#[derive(Debug, Clone)]
+pub struct TrackInfo {
+    pub track_id: String,
+    pub source: TrackSource,
+    pub label: Option<String>,
+}
+
+/// A track a subscriber can receive from a participant
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishedTrack {
+    pub track_id: String,
+    pub kind: String,
+    pub source: TrackSource,
+    pub label: String,
+}
+
+impl PublishedTrack {
+    /// Undeclared tracks are labelled after their source, numbered when a
+    /// participant publishes more than one of the same kind ("Camera 2")
+    pub fn new(
+        track_id: String,
+        kind: String,
+        source: TrackSource,
+        label: Option<String>,
+        nth: usize,
+    ) -> Self {
+        let label = label.filter(|label| !label.is_empty()).unwrap_or_else(|| {
+            if nth > 1 {
+                format!("{} {nth}", source.default_label())
+            } else {
+                source.default_label().to_string()
+            }
+        });
+
+        Self {
+            track_id,
+            kind,
+            source,
+            label,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(source: TrackSource, label: Option<&str>, nth: usize) -> String {
+        let label = label.map(str::to_owned);
+        PublishedTrack::new("track".to_owned(), "video".to_owned(), source, label, nth).label
+    }
+
+    #[test]
+    fn test_declared_label_wins() {
+        assert_eq!(
+            label(TrackSource::Camera, Some("Document camera"), 2),
+            "Document camera"
+        );
+    }
+
+    #[test]
+    fn test_undeclared_tracks_are_numbered_per_source() {
+        assert_eq!(label(TrackSource::Camera, None, 1), "Camera");
+        assert_eq!(label(TrackSource::Camera, None, 2), "Camera 2");
+        assert_eq!(label(TrackSource::ScreenAudio, None, 1), "Screen audio");
+    }
+
+    #[test]
+    fn test_empty_label_counts_as_undeclared() {
+        assert_eq!(label(TrackSource::Screen, Some(""), 3), "Screen 3");
+    }
+
+    #[test]
+    fn test_source_round_trips_through_u8() {
+        for source in [
+            TrackSource::Camera,
+            TrackSource::Microphone,
+            TrackSource::Screen,
+            TrackSource::ScreenAudio,
+        ] {
+            assert_eq!(TrackSource::from(u8::from(source)), source);
+        }
+
+        assert_eq!(TrackSource::from(42), TrackSource::Camera);
+    }
+}
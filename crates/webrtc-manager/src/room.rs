@@ -1,4 +1,10 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
 
 use dashmap::DashMap;
 use parking_lot::{Mutex, RwLock};
@@ -31,14 +37,28 @@ use webrtc::{
 };
 
 use crate::{
-    entities::{media::Media, publisher::Publisher, subscriber::Subscriber},
+    entities::{media::Media, publisher::Publisher, subscriber::Subscriber, track::CodecType},
     errors::WebRTCError,
     models::{
         connection_type::ConnectionType,
+        diagnostics::ObjectCounts,
+        forwarding_acl::ForwardingAcl,
         params::{
-            AddTrackResponse, IceCandidate, JoinRoomParams, JoinRoomResponse, SubscribeParams,
-            SubscribeResponse, TrackMutexWrapper, WebRTCManagerConfigs,
+            AddTrackResponse, DownlinkStarvedCallback, IceCandidate, JoinRoomParams,
+            JoinRoomResponse, SubscribeParams, SubscribeResponse, TrackMutexWrapper,
+            WebRTCManagerConfigs,
         },
+        qos::QosPolicy,
+        quality::TrackQuality,
+        room_stats::{PublisherStats, RoomStats},
+        track_info::TrackInfo,
+    },
+    services::str0m_pool::Str0mSubscriberPool,
+    utils::{
+        live_objects::{LiveGuard, LiveObject},
+        pacer::PacerStats,
+        svc::DEPENDENCY_DESCRIPTOR_URI,
+        talk_time::AUDIO_LEVEL_URI,
     },
 };
 
@@ -46,15 +66,35 @@ use crate::{
 pub struct Room {
     publishers: Arc<DashMap<String, Arc<Publisher>>>,
     subscribers: Arc<DashMap<String, Arc<Subscriber>>>,
+    // Peer id -> target id of the subscribers living in the str0m pool
+    str0m_subscribers: Arc<DashMap<String, String>>,
+    str0m_pool: Option<Arc<Str0mSubscriberPool>>,
+    acl: Arc<ForwardingAcl>,
+    qos: Arc<QosPolicy>,
     configs: WebRTCManagerConfigs,
+    is_recording_paused: Arc<AtomicBool>,
+    is_broadcast_cut: Arc<AtomicBool>,
+    _live: LiveGuard,
 }
 
 impl Room {
-    pub fn new(configs: WebRTCManagerConfigs) -> Self {
+    pub fn new(
+        configs: WebRTCManagerConfigs,
+        acl: Arc<ForwardingAcl>,
+        qos: Arc<QosPolicy>,
+        str0m_pool: Option<Arc<Str0mSubscriberPool>>,
+    ) -> Self {
         Self {
             publishers: Arc::new(DashMap::new()),
             subscribers: Arc::new(DashMap::new()),
+            str0m_subscribers: Arc::new(DashMap::new()),
+            str0m_pool,
+            acl,
+            qos,
             configs,
+            is_recording_paused: Arc::new(AtomicBool::new(false)),
+            is_broadcast_cut: Arc::new(AtomicBool::new(false)),
+            _live: LiveGuard::new(LiveObject::Room),
         }
     }
 
@@ -72,13 +112,49 @@ impl Room {
             params.is_video_enabled,
             params.is_audio_enabled,
             params.is_e2ee_enabled,
+            Arc::clone(&self.configs.rtp_buffer_pool),
         );
 
+        media.set_track_infos(params.tracks);
+
+        if params.noise_suppression {
+            media.enable_noise_suppression(Arc::clone(&self.configs.noise_suppression_budget));
+        }
+
+        media.set_broadcast_delay(params.broadcast_delay);
+
+        if let Some(dir) = &self.configs.raw_recording_dir
+            && let Err(err) = media.initialize_track_archive(dir, room_id).await
+        {
+            warn!(
+                "Failed to start track archive for {participant_id}: {:?}",
+                err
+            );
+        }
+
         if params.connection_type == ConnectionType::P2P {
             media.cache_sdp(params.sdp.clone());
         }
 
         // let _ = media.initialize_hls_writer().await;
+        let is_recording = params.start_egress
+            && match media.initialize_hls_writer().await {
+                Ok(()) => true,
+                Err(err) => {
+                    warn!("Failed to start egress for {participant_id}: {:?}", err);
+                    false
+                }
+            };
+
+        // Someone joining mid-pause isn't recorded until the resume either
+        if self.is_recording_paused() {
+            media.set_recording_paused(true);
+        }
+
+        // Nor does a cut broadcast pick them up
+        if self.is_broadcast_cut() {
+            media.set_broadcast_cut(true);
+        }
 
         let publisher = Publisher::new(
             Arc::new(RwLock::new(media)),
@@ -130,6 +206,8 @@ impl Room {
             let room_id = room_id.to_string();
             let participant_id = participant_id.clone();
             let subscribers = Arc::clone(&self.subscribers);
+            let str0m_subscribers = Arc::clone(&self.str0m_subscribers);
+            let str0m_pool = self.str0m_pool.clone();
             let publisher = publisher.clone();
 
             let track_counter = Arc::clone(&track_counter);
@@ -141,6 +219,8 @@ impl Room {
             pc.on_track(Box::new(move |track, _, _| {
                 let media = Arc::clone(&media);
                 let subscribers = Arc::clone(&subscribers);
+                let str0m_subscribers = Arc::clone(&str0m_subscribers);
+                let str0m_pool = str0m_pool.clone();
                 let room_id = room_id.clone();
                 let participant_id = participant_id.clone();
                 let track_counter = Arc::clone(&track_counter);
@@ -166,6 +246,15 @@ impl Room {
                         };
 
                         if let Some(track) = maybe_track {
+                            if let Some(pool) = &str0m_pool {
+                                Self::_add_track_to_str0m_subscribers(
+                                    pool,
+                                    &str0m_subscribers,
+                                    track.clone(),
+                                    &participant_id,
+                                );
+                            }
+
                             if let Err(e) = Self::_add_track_to_subscribers(
                                 Arc::clone(&subscribers),
                                 track,
@@ -235,7 +324,7 @@ impl Room {
 
             return Ok(Some(JoinRoomResponse {
                 sdp: answer.sdp.clone(),
-                is_recording: false,
+                is_recording,
             }));
         } else {
             let callback = params.callback.clone();
@@ -256,6 +345,10 @@ impl Room {
 
         let media_arc = self._get_media(target_id)?;
 
+        if let Some(codec) = Self::_unsupported_codec(&media_arc, &params.video_codecs) {
+            return Err(WebRTCError::CodecMismatch(codec));
+        }
+
         let subscribe_response = self._extract_subscribe_response(&media_arc).await;
 
         let sdp_cached = {
@@ -280,10 +373,46 @@ impl Room {
 
                 let peer_id = self._get_subscriber_peer_id(target_id, participant_id);
 
+                if let Some(pool) = &self.str0m_pool {
+                    let tracks = {
+                        let media = media_arc.read();
+                        media
+                            .tracks
+                            .iter()
+                            .map(|entry| Arc::clone(entry.value()))
+                            .collect::<Vec<_>>()
+                    };
+
+                    let offer = pool
+                        .subscribe(
+                            &peer_id,
+                            tracks,
+                            self.acl.can_forward(target_id),
+                            params.on_negotiation_needed.clone(),
+                        )
+                        .await?;
+
+                    self.str0m_subscribers.insert(peer_id, target_id.clone());
+
+                    return Ok(SubscribeResponse {
+                        offer,
+                        ..subscribe_response
+                    });
+                }
+
                 let pc = self._create_pc().await?;
 
-                self._add_subscriber(&peer_id, &pc, participant_id.clone())
-                    .await;
+                let on_downlink_starved =
+                    self._wrap_downlink_starved(participant_id, params.on_downlink_starved.clone());
+
+                self._add_subscriber(
+                    &peer_id,
+                    &pc,
+                    target_id.clone(),
+                    participant_id.clone(),
+                    on_downlink_starved,
+                )
+                .await;
 
                 // Clone for callbacks
                 let peer_clone = pc.clone();
@@ -355,6 +484,22 @@ impl Room {
         }
     }
 
+    /// The str0m pool and peer id of a subscriber created in it, its answer is
+    /// awaited outside the room lock
+    pub fn get_str0m_subscriber(
+        &self,
+        target_id: &str,
+        participant_id: &str,
+    ) -> Option<(Arc<Str0mSubscriberPool>, String)> {
+        let pool = self._get_str0m_pool(target_id, participant_id)?;
+
+        Some((
+            Arc::clone(pool),
+            self._get_subscriber_peer_id(target_id, participant_id),
+        ))
+    }
+
+    /// webrtc-rs subscribers only, str0m ones are looked up with `get_str0m_subscriber`
     pub fn set_subscriber_remote_sdp(
         &self,
         target_id: &str,
@@ -386,9 +531,13 @@ impl Room {
         &self,
         participant_id: &str,
         sdp: &str,
+        tracks: Vec<TrackInfo>,
     ) -> Result<String, WebRTCError> {
         let participant = self._get_publisher(participant_id)?;
 
+        // Known before the offer is applied, so new tracks are labelled as they arrive
+        participant.media.read().set_track_infos(tracks);
+
         let peer = &participant.peer_connection;
 
         let offer_desc = RTCSessionDescription::offer(sdp.to_string())
@@ -490,6 +639,11 @@ impl Room {
         participant_id: &str,
         candidate: IceCandidate,
     ) -> Result<(), WebRTCError> {
+        if let Some(pool) = self._get_str0m_pool(target_id, participant_id) {
+            let peer_id = self._get_subscriber_peer_id(target_id, participant_id);
+            return pool.add_candidate(&peer_id, candidate);
+        }
+
         let peer = self._get_subscriber_peer(target_id, participant_id)?;
 
         let candidate_init = RTCIceCandidateInit {
@@ -515,6 +669,11 @@ impl Room {
     }
 
     pub fn leave_room(&mut self, participant_id: &str) {
+        self.unpublish(participant_id);
+    }
+
+    /// Closes the participant's publisher and everyone subscribed to it
+    pub fn unpublish(&mut self, participant_id: &str) {
         self._remove_all_subscribers_with_target_id(participant_id);
 
         if let Some((_id, publisher)) = self.publishers.remove(participant_id) {
@@ -522,6 +681,23 @@ impl Room {
         }
     }
 
+    /// Nobody publishes or subscribes on this node anymore
+    pub fn is_empty(&self) -> bool {
+        self.publishers.is_empty()
+            && self.subscribers.is_empty()
+            && self.str0m_subscribers.is_empty()
+    }
+
+    /// An observer only owns subscriber peers, there is no publisher to close
+    pub fn leave_as_observer(&mut self, participant_id: &str) {
+        Self::_remove_all_subscribers_of(
+            &self.subscribers,
+            &self.str0m_subscribers,
+            self.str0m_pool.as_deref(),
+            participant_id,
+        );
+    }
+
     pub fn set_e2ee_enabled(
         &self,
         participant_id: &str,
@@ -554,12 +730,13 @@ impl Room {
         &self,
         participant_id: &str,
         is_enabled: bool,
+        is_hardware_muted: Option<bool>,
     ) -> Result<(), WebRTCError> {
         let media = self._get_media(participant_id)?;
 
         let media = media.write();
 
-        media.set_video_enabled(is_enabled);
+        media.set_video_enabled(is_enabled, is_hardware_muted);
 
         Ok(())
     }
@@ -568,12 +745,13 @@ impl Room {
         &self,
         participant_id: &str,
         is_enabled: bool,
+        is_hardware_muted: Option<bool>,
     ) -> Result<(), WebRTCError> {
         let media = self._get_media(participant_id)?;
 
         let media = media.write();
 
-        media.set_audio_enabled(is_enabled);
+        media.set_audio_enabled(is_enabled, is_hardware_muted);
 
         Ok(())
     }
@@ -583,12 +761,13 @@ impl Room {
         participant_id: &str,
         is_enabled: bool,
         screen_track_id: Option<String>,
+        screen_audio_track_id: Option<String>,
     ) -> Result<(), WebRTCError> {
         let media = self._get_media(participant_id)?;
 
         let media = media.write();
 
-        media.set_screen_sharing(is_enabled, screen_track_id);
+        media.set_screen_sharing(is_enabled, screen_track_id, screen_audio_track_id);
 
         Ok(())
     }
@@ -607,6 +786,156 @@ impl Room {
         Ok(())
     }
 
+    pub fn set_recording_paused(&self, is_paused: bool) {
+        self.is_recording_paused.store(is_paused, Ordering::Relaxed);
+
+        for entry in self.publishers.iter() {
+            let media = entry.value().media.read();
+            media.set_recording_paused(is_paused);
+        }
+    }
+
+    pub fn set_broadcast_cut(&self, is_cut: bool) {
+        self.is_broadcast_cut.store(is_cut, Ordering::Relaxed);
+
+        for entry in self.publishers.iter() {
+            let media = entry.value().media.read();
+            media.set_broadcast_cut(is_cut);
+        }
+    }
+
+    pub fn is_recording_paused(&self) -> bool {
+        self.is_recording_paused.load(Ordering::Relaxed)
+    }
+
+    pub fn is_broadcast_cut(&self) -> bool {
+        self.is_broadcast_cut.load(Ordering::Relaxed)
+    }
+
+    pub fn medias(&self) -> Vec<Arc<RwLock<Media>>> {
+        self.publishers
+            .iter()
+            .map(|entry| Arc::clone(&entry.value().media))
+            .collect()
+    }
+
+    /// Re-evaluate the forwarding ACL for every subscriber, e.g. after the host
+    /// toggles stage mode or promotes a speaker
+    pub fn apply_forwarding_acl(&self) {
+        for entry in self.subscribers.iter() {
+            let subscriber = entry.value();
+            subscriber.set_forwarding_allowed(self.acl.can_forward(&subscriber.target_id));
+        }
+
+        if let Some(pool) = &self.str0m_pool {
+            for entry in self.str0m_subscribers.iter() {
+                pool.set_forwarding_allowed(entry.key(), self.acl.can_forward(entry.value()));
+            }
+        }
+    }
+
+    /// Re-evaluate every subscriber's layers and pacing against the QoS
+    /// classes, after one changed mid-call. Subscribers in the str0m pool
+    /// aren't covered, the pool doesn't switch layers yet.
+    pub fn apply_qos(&self) {
+        for entry in self.subscribers.iter() {
+            entry.value().apply_qos();
+        }
+    }
+
+    /// Fit what `participant_id` receives to the tiles on its screen, keyed by
+    /// target. Targets without a tile get no video. Subscribers in the str0m
+    /// pool stay on the medium layer, the pool doesn't switch layers yet.
+    pub fn set_viewport(&self, participant_id: &str, tiles: &HashMap<String, TrackQuality>) {
+        for entry in self.subscribers.iter() {
+            let subscriber = entry.value();
+            if *entry.key() != self._get_subscriber_peer_id(&subscriber.target_id, participant_id) {
+                continue;
+            }
+
+            let quality = tiles
+                .get(&subscriber.target_id)
+                .cloned()
+                .unwrap_or(TrackQuality::None);
+
+            subscriber.set_viewport_quality(quality);
+        }
+    }
+
+    /// Egress pacing per subscriber peer, keyed by peer id
+    pub fn subscriber_pacer_stats(&self) -> Vec<(String, PacerStats)> {
+        self.subscribers
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().pacer_stats()))
+            .collect()
+    }
+
+    pub fn stats(&self) -> RoomStats {
+        let mut stats = RoomStats {
+            subscribers: self.subscribers.len() + self.str0m_subscribers.len(),
+            is_recording_paused: self.is_recording_paused.load(Ordering::Relaxed),
+            ..Default::default()
+        };
+
+        for publisher in self.publishers.iter() {
+            let participant_id = publisher.key();
+
+            let mut publisher_stats = {
+                let media = publisher.media.read();
+                stats.is_recording |= media.is_recording();
+
+                PublisherStats {
+                    participant_id: participant_id.clone(),
+                    tracks: media.tracks.len(),
+                    talk_time_ms: media.talk_time_ms(),
+                    ..Default::default()
+                }
+            };
+
+            for subscriber in self.subscribers.iter() {
+                if subscriber.target_id == *participant_id {
+                    publisher_stats.subscribers += 1;
+                    publisher_stats.egress_bps += subscriber.pacer_stats().rate_bps;
+                }
+            }
+
+            // The str0m pool paces on its own, only its subscribers are counted
+            publisher_stats.subscribers += self
+                .str0m_subscribers
+                .iter()
+                .filter(|entry| entry.value() == participant_id)
+                .count();
+
+            stats.egress_bps += publisher_stats.egress_bps;
+            stats.publishers.push(publisher_stats);
+        }
+
+        stats
+    }
+
+    /// Objects reachable from this room, with the channels forwarding its tracks
+    pub fn object_counts(&self) -> (ObjectCounts, usize) {
+        let mut counts = ObjectCounts {
+            rooms: 1,
+            publishers: self.publishers.len(),
+            subscribers: self.subscribers.len(),
+            ..Default::default()
+        };
+        let mut forwarding_channels = 0;
+
+        for publisher in self.publishers.iter() {
+            let media = publisher.media.read();
+            for track in media.tracks.iter() {
+                let track = track.read();
+                counts.tracks += 1;
+                counts.forward_tracks += track.forward_tracks.len();
+                forwarding_channels += track.forwarding_channel_count();
+            }
+        }
+
+        (counts, forwarding_channels)
+    }
+
     fn _get_publisher(&self, participant_id: &str) -> Result<Arc<Publisher>, WebRTCError> {
         let result = self
             .publishers
@@ -622,8 +951,26 @@ impl Room {
             .insert(participant_id.to_owned(), participant.clone());
     }
 
-    async fn _add_subscriber(&self, peer_id: &str, pc: &Arc<RTCPeerConnection>, user_id: String) {
-        let subscriber = Subscriber::new(pc.clone(), user_id).await;
+    async fn _add_subscriber(
+        &self,
+        peer_id: &str,
+        pc: &Arc<RTCPeerConnection>,
+        target_id: String,
+        user_id: String,
+        on_downlink_starved: DownlinkStarvedCallback,
+    ) {
+        let is_forwarding_allowed = self.acl.can_forward(&target_id);
+        let subscriber = Subscriber::new(
+            pc.clone(),
+            target_id,
+            user_id,
+            is_forwarding_allowed,
+            self.configs.pacing_multiplier,
+            self.configs.hls_fallback_min_bps,
+            on_downlink_starved,
+            Arc::clone(&self.qos),
+        )
+        .await;
         let subscriber = Arc::new(subscriber);
 
         self.subscribers.insert(peer_id.to_owned(), subscriber);
@@ -663,12 +1010,50 @@ impl Room {
         }
     }
 
+    /// The str0m pool, if this subscriber was created in it
+    fn _get_str0m_pool(
+        &self,
+        target_id: &str,
+        participant_id: &str,
+    ) -> Option<&Arc<Str0mSubscriberPool>> {
+        let key = self._get_subscriber_peer_id(target_id, participant_id);
+
+        self.str0m_pool
+            .as_ref()
+            .filter(|_| self.str0m_subscribers.contains_key(&key))
+    }
+
     fn _get_subscriber_peer_id(&self, target_id: &str, participant_id: &str) -> String {
         let key = format!("p_{target_id}_{participant_id}");
 
         key
     }
 
+    /// The codec of the target's video the subscriber can't decode, if it
+    /// said what it can and none of the video is in it. Forwarding would
+    /// leave it with black tiles.
+    fn _unsupported_codec(media: &Arc<RwLock<Media>>, video_codecs: &[String]) -> Option<String> {
+        if video_codecs.is_empty() {
+            return None;
+        }
+
+        let media = media.read();
+        let codecs: Vec<CodecType> = media
+            .tracks
+            .iter()
+            .filter_map(|entry| {
+                let track = entry.value().read();
+                (track.kind == RTPCodecType::Video).then(|| track.codec_type.clone())
+            })
+            .collect();
+
+        if codecs.is_empty() || codecs.iter().any(|codec| codec.is_in(video_codecs)) {
+            return None;
+        }
+
+        codecs.first().map(|codec| codec.as_str().to_string())
+    }
+
     fn _get_media(&self, participant_id: &str) -> Result<Arc<RwLock<Media>>, WebRTCError> {
         let participant = self._get_publisher(participant_id)?;
         Ok(Arc::clone(&participant.media))
@@ -692,6 +1077,87 @@ impl Room {
                 subscriber_clone.close();
             }
         }
+
+        if let Some(pool) = &self.str0m_pool {
+            self.str0m_subscribers.retain(|peer_id, _| {
+                if peer_id.starts_with(&prefix) {
+                    pool.close(peer_id);
+                    return false;
+                }
+                true
+            });
+        }
+    }
+
+    /// Drops every subscriber peer of the viewer, not just the starved one, so its
+    /// share of the node is actually freed before the viewer is pointed at HLS
+    fn _wrap_downlink_starved(
+        &self,
+        participant_id: &str,
+        callback: DownlinkStarvedCallback,
+    ) -> DownlinkStarvedCallback {
+        // Weak, the subscribers map ends up owning this callback
+        let subscribers = Arc::downgrade(&self.subscribers);
+        let str0m_subscribers = Arc::downgrade(&self.str0m_subscribers);
+        let str0m_pool = self.str0m_pool.clone();
+        let participant_id = participant_id.to_string();
+
+        Arc::new(move || {
+            let removed = match (subscribers.upgrade(), str0m_subscribers.upgrade()) {
+                (Some(subscribers), Some(str0m_subscribers)) => Self::_remove_all_subscribers_of(
+                    &subscribers,
+                    &str0m_subscribers,
+                    str0m_pool.as_deref(),
+                    &participant_id,
+                ),
+                _ => 0,
+            };
+            let callback = callback.clone();
+
+            Box::pin(async move {
+                // Another of the viewer's peers got there first
+                if removed > 0 {
+                    (callback)().await;
+                }
+            })
+        })
+    }
+
+    /// Subscriber peers owned by `participant_id`, as opposed to the ones watching it
+    fn _remove_all_subscribers_of(
+        subscribers: &DashMap<String, Arc<Subscriber>>,
+        str0m_subscribers: &DashMap<String, String>,
+        str0m_pool: Option<&Str0mSubscriberPool>,
+        participant_id: &str,
+    ) -> usize {
+        let suffix = format!("_{participant_id}");
+
+        let keys_to_remove: Vec<String> = subscribers
+            .iter()
+            .filter(|entry| entry.key().ends_with(&suffix))
+            .map(|entry| entry.key().clone())
+            .collect();
+
+        let mut removed = 0;
+        for key in keys_to_remove {
+            if let Some((_id, subscriber)) = subscribers.remove(&key) {
+                subscriber.close();
+                removed += 1;
+            }
+        }
+
+        if let Some(pool) = str0m_pool {
+            str0m_subscribers.retain(|peer_id, _| {
+                if peer_id.ends_with(&suffix) {
+                    pool.close(peer_id);
+                    removed += 1;
+                    return false;
+                }
+                true
+            });
+        }
+
+        removed
     }
 
     async fn _add_track_to_subscribers(
@@ -716,6 +1182,21 @@ impl Room {
         Ok(())
     }
 
+    fn _add_track_to_str0m_subscribers(
+        pool: &Str0mSubscriberPool,
+        str0m_subscribers: &DashMap<String, String>,
+        remote_track: TrackMutexWrapper,
+        target_id: &str,
+    ) {
+        for entry in str0m_subscribers.iter() {
+            if entry.value() == target_id
+                && let Err(e) = pool.add_track(entry.key(), remote_track.clone())
+            {
+                warn!("Failed to add track to str0m subscriber: {:?}", e);
+            }
+        }
+    }
+
     pub async fn _create_pc(&self) -> Result<Arc<RTCPeerConnection>, WebRTCError> {
         let config = RTCConfiguration {
             ice_servers: vec![],
@@ -754,6 +1235,8 @@ impl Room {
             "urn:ietf:params:rtp-hdrext:sdes:rtp-stream-id",
             "urn:ietf:params:rtp-hdrext:sdes:repaired-rtp-stream-id",
             "http://www.webrtc.org/experiments/rtp-hdrext/abs-send-time",
+            // The layers of AV1 SVC packets
+            DEPENDENCY_DESCRIPTOR_URI,
         ] {
             m.register_header_extension(
                 RTCRtpHeaderExtensionCapability {
@@ -765,6 +1248,16 @@ impl Room {
             .ok();
         }
 
+        // Speech levels, for each publisher's talk time
+        m.register_header_extension(
+            RTCRtpHeaderExtensionCapability {
+                uri: AUDIO_LEVEL_URI.to_owned(),
+            },
+            RTPCodecType::Audio,
+            None,
+        )
+        .ok();
+
         let mut setting_engine = SettingEngine::default();
         setting_engine.set_lite(true);
         setting_engine.set_network_types(vec![NetworkType::Udp4]);
@@ -802,16 +1295,22 @@ impl Room {
         media_arc: &Arc<RwLock<Media>>,
     ) -> SubscribeResponse {
         let media = media_arc.read();
+        // Resolving sources reads the state too, so this goes before the state lock
+        let tracks = media.published_tracks();
         let media_state = media.state.read();
 
         SubscribeResponse {
             camera_type: media_state.camera_type,
             video_enabled: media_state.video_enabled,
             audio_enabled: media_state.audio_enabled,
+            is_video_hardware_muted: media_state.video_hardware_muted,
+            is_audio_hardware_muted: media_state.audio_hardware_muted,
             is_hand_raising: media_state.is_hand_raising,
             is_e2ee_enabled: media_state.is_e2ee_enabled,
             is_screen_sharing: media_state.is_screen_sharing,
             screen_track_id: media_state.screen_track_id.clone(),
+            screen_audio_track_id: media_state.screen_audio_track_id.clone(),
+            tracks,
             video_codec: media_state.codec.clone(),
             offer: String::new(),
         }
@@ -834,3 +1333,73 @@ impl Room {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use webrtc::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
+
+    use super::*;
+    use crate::{entities::track::Track, utils::buffer_pool::RtpBufferPool};
+
+    fn media(tracks: &[(&str, RTPCodecType, &str)]) -> Arc<RwLock<Media>> {
+        let media = Media::new(
+            "publisher".to_owned(),
+            true,
+            true,
+            false,
+            Arc::new(RtpBufferPool::new(1)),
+        );
+
+        for (id, kind, mime_type) in tracks {
+            let capability = RTCRtpCodecCapability {
+                mime_type: mime_type.to_string(),
+                ..Default::default()
+            };
+            let track = Track::detached(id, *kind, capability);
+            media
+                .tracks
+                .insert(id.to_string(), Arc::new(RwLock::new(track)));
+        }
+
+        Arc::new(RwLock::new(media))
+    }
+
+    fn codecs(codecs: &[&str]) -> Vec<String> {
+        codecs.iter().map(|codec| codec.to_string()).collect()
+    }
+
+    #[test]
+    fn test_undecodable_video_is_reported() {
+        let media = media(&[
+            ("mic", RTPCodecType::Audio, "audio/opus"),
+            ("camera", RTPCodecType::Video, "video/AV1"),
+        ]);
+
+        assert_eq!(
+            Room::_unsupported_codec(&media, &codecs(&["vp8", "h264"])),
+            Some("av1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_one_decodable_video_track_is_enough() {
+        let media = media(&[
+            ("camera", RTPCodecType::Video, "video/AV1"),
+            ("screen", RTPCodecType::Video, "video/VP8"),
+        ]);
+
+        assert_eq!(Room::_unsupported_codec(&media, &codecs(&["vp8"])), None);
+    }
+
+    #[test]
+    fn test_no_codec_list_or_no_video_is_never_a_mismatch() {
+        let video = media(&[("camera", RTPCodecType::Video, "video/AV1")]);
+        assert_eq!(Room::_unsupported_codec(&video, &[]), None);
+
+        let audio_only = media(&[("mic", RTPCodecType::Audio, "audio/opus")]);
+        assert_eq!(
+            Room::_unsupported_codec(&audio_only, &codecs(&["vp8"])),
+            None
+        );
+    }
+}
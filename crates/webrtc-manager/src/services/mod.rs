@@ -1,3 +1,4 @@
 pub mod media_monitor;
 pub mod publisher_messenger;
+pub mod str0m_pool;
 pub mod track_monitor;
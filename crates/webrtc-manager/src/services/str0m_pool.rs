@@ -0,0 +1,944 @@
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    net::{IpAddr, SocketAddr, UdpSocket},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam::channel::{self, Receiver, Sender, TryRecvError};
+use dashmap::DashMap;
+use str0m::{
+    Candidate, Event, IceConnectionState, Input, Output, Rtc, RtcConfig,
+    change::{SdpAnswer, SdpPendingOffer},
+    format::Codec,
+    media::{Direction, MediaKind, Mid, Pt},
+    net::{Protocol, Receive},
+    rtp::ExtensionValues,
+};
+use tracing::{debug, info, warn};
+use webrtc::rtp_transceiver::rtp_codec::RTPCodecType;
+
+use crate::{
+    entities::{
+        forward_track::ForwardTrack,
+        track::{CodecType, Track},
+    },
+    errors::WebRTCError,
+    models::{
+        params::{IceCandidate, RenegotiationCallback, TrackMutexWrapper},
+        quality::TrackQuality,
+        rtp_foward_info::RtpForwardInfo,
+        svc::SvcTarget,
+    },
+};
+
+/// Longest a worker blocks on its socket before draining the forwarding bus again
+const POLL_INTERVAL: Duration = Duration::from_millis(2);
+/// Packets taken from one bus receiver per loop, so a busy track can't starve the others
+const MAX_PACKETS_PER_TRACK: usize = 64;
+/// Datagrams read off the socket per loop, so a flood can't starve commands and forwarding
+const MAX_DATAGRAMS_PER_LOOP: usize = 256;
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+enum Command {
+    Subscribe {
+        peer_id: String,
+        tracks: Vec<TrackMutexWrapper>,
+        is_forwarding_allowed: bool,
+        on_negotiation_needed: RenegotiationCallback,
+        reply: tokio::sync::oneshot::Sender<Result<String, WebRTCError>>,
+    },
+    AddTrack {
+        peer_id: String,
+        track: TrackMutexWrapper,
+    },
+    SetAnswer {
+        peer_id: String,
+        sdp: String,
+        reply: tokio::sync::oneshot::Sender<Result<(), WebRTCError>>,
+    },
+    AddCandidate {
+        peer_id: String,
+        candidate: String,
+    },
+    SetForwardingAllowed {
+        peer_id: String,
+        is_allowed: bool,
+    },
+    Close {
+        peer_id: String,
+    },
+}
+
+/// Runs subscriber peer connections on str0m while publishers stay on webrtc-rs.
+/// Every worker thread owns one UDP socket and drives many sans-IO `Rtc`s over it,
+/// reading packets straight off the publishers' forwarding bus.
+///
+/// Experimental: subscribers get a fixed medium layer since there is no quality
+/// data channel on this path yet.
+pub struct Str0mSubscriberPool {
+    workers: Vec<Sender<Command>>,
+    loads: Vec<Arc<AtomicUsize>>,
+    peers: DashMap<String, usize>,
+}
+
+impl Str0mSubscriberPool {
+    pub fn new(public_ip: &str, port: u16, workers: u16) -> std::io::Result<Self> {
+        let ip = match public_ip.parse::<IpAddr>() {
+            Ok(ip) => ip,
+            Err(_) => Self::_detect_local_ip()?,
+        };
+
+        let runtime = tokio::runtime::Handle::current();
+        let mut senders = Vec::new();
+        let mut loads = Vec::new();
+
+        for index in 0..workers.max(1) {
+            let port = port + index;
+            let socket = UdpSocket::bind(("0.0.0.0", port))?;
+            let (tx, rx) = channel::unbounded();
+            let load = Arc::new(AtomicUsize::new(0));
+
+            let worker = Worker {
+                socket,
+                local_addr: SocketAddr::new(ip, port),
+                commands: rx,
+                sessions: HashMap::new(),
+                load: Arc::clone(&load),
+                runtime: runtime.clone(),
+            };
+
+            thread::Builder::new()
+                .name(format!("str0m-worker-{index}"))
+                .spawn(move || worker.run())?;
+
+            senders.push(tx);
+            loads.push(load);
+        }
+
+        info!(
+            "Started {} str0m subscriber workers on {}:{}",
+            senders.len(),
+            ip,
+            port
+        );
+
+        Ok(Self {
+            workers: senders,
+            loads,
+            peers: DashMap::new(),
+        })
+    }
+
+    /// Create a send-only peer for `tracks`, returns the offer
+    pub async fn subscribe(
+        &self,
+        peer_id: &str,
+        tracks: Vec<TrackMutexWrapper>,
+        is_forwarding_allowed: bool,
+        on_negotiation_needed: RenegotiationCallback,
+    ) -> Result<String, WebRTCError> {
+        // Resubscribing replaces the old peer, which may sit on another worker
+        self.close(peer_id);
+
+        let index = self._least_loaded();
+        let (reply, rx) = tokio::sync::oneshot::channel();
+
+        self.peers.insert(peer_id.to_owned(), index);
+        self._send(
+            index,
+            Command::Subscribe {
+                peer_id: peer_id.to_owned(),
+                tracks,
+                is_forwarding_allowed,
+                on_negotiation_needed,
+                reply,
+            },
+        )?;
+
+        match tokio::time::timeout(REPLY_TIMEOUT, rx).await {
+            Ok(Ok(result)) => result,
+            _ => Err(WebRTCError::FailedToCreateOffer),
+        }
+    }
+
+    /// Publisher added a track after the peer was created, renegotiated via `on_negotiation_needed`
+    pub fn add_track(&self, peer_id: &str, track: TrackMutexWrapper) -> Result<(), WebRTCError> {
+        let index = self._get_worker(peer_id)?;
+
+        self._send(
+            index,
+            Command::AddTrack {
+                peer_id: peer_id.to_owned(),
+                track,
+            },
+        )
+    }
+
+    pub async fn set_remote_sdp(&self, peer_id: &str, sdp: &str) -> Result<(), WebRTCError> {
+        let index = self._get_worker(peer_id)?;
+        let (reply, rx) = tokio::sync::oneshot::channel();
+
+        self._send(
+            index,
+            Command::SetAnswer {
+                peer_id: peer_id.to_owned(),
+                sdp: sdp.to_owned(),
+                reply,
+            },
+        )?;
+
+        match tokio::time::timeout(REPLY_TIMEOUT, rx).await {
+            Ok(Ok(result)) => result,
+            _ => Err(WebRTCError::FailedToSetSdp),
+        }
+    }
+
+    pub fn add_candidate(&self, peer_id: &str, candidate: IceCandidate) -> Result<(), WebRTCError> {
+        let index = self._get_worker(peer_id)?;
+
+        self._send(
+            index,
+            Command::AddCandidate {
+                peer_id: peer_id.to_owned(),
+                candidate: candidate.candidate,
+            },
+        )
+    }
+
+    pub fn set_forwarding_allowed(&self, peer_id: &str, is_allowed: bool) {
+        if let Ok(index) = self._get_worker(peer_id) {
+            let _ = self._send(
+                index,
+                Command::SetForwardingAllowed {
+                    peer_id: peer_id.to_owned(),
+                    is_allowed,
+                },
+            );
+        }
+    }
+
+    pub fn close(&self, peer_id: &str) {
+        if let Some((_, index)) = self.peers.remove(peer_id) {
+            let _ = self._send(
+                index,
+                Command::Close {
+                    peer_id: peer_id.to_owned(),
+                },
+            );
+        }
+    }
+
+    /// Live peers across all workers
+    pub fn peer_count(&self) -> usize {
+        self.loads.iter().map(|l| l.load(Ordering::Relaxed)).sum()
+    }
+
+    fn _least_loaded(&self) -> usize {
+        self.loads
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, load)| load.load(Ordering::Relaxed))
+            .map(|(index, _)| index)
+            .unwrap_or(0)
+    }
+
+    fn _get_worker(&self, peer_id: &str) -> Result<usize, WebRTCError> {
+        self.peers
+            .get(peer_id)
+            .map(|index| *index)
+            .ok_or(WebRTCError::PeerNotFound)
+    }
+
+    fn _send(&self, index: usize, command: Command) -> Result<(), WebRTCError> {
+        self.workers[index]
+            .send(command)
+            .map_err(|_| WebRTCError::PeerNotFound)
+    }
+
+    /// Address of the interface that routes to the internet, nothing is sent
+    fn _detect_local_ip() -> std::io::Result<IpAddr> {
+        let probe = UdpSocket::bind("0.0.0.0:0")?;
+        probe.connect("8.8.8.8:80")?;
+        Ok(probe.local_addr()?.ip())
+    }
+}
+
+struct BusTrack {
+    mid: Mid,
+    codec: Option<Codec>,
+    pt: Option<Pt>,
+    track: TrackMutexWrapper,
+    receiver: Receiver<RtpForwardInfo>,
+    last_seq: Option<u64>,
+}
+
+impl BusTrack {
+    /// Widen the publisher's 16 bit sequence number, str0m wants it extended
+    fn extend_seq(&mut self, seq: u16) -> u64 {
+        let extended = match self.last_seq {
+            None => seq as u64 + (1 << 16),
+            Some(last) => {
+                let rollover = last & !0xFFFF;
+                let candidates = [
+                    rollover.wrapping_sub(1 << 16) | seq as u64,
+                    rollover | seq as u64,
+                    (rollover + (1 << 16)) | seq as u64,
+                ];
+
+                candidates
+                    .into_iter()
+                    .min_by_key(|c| c.abs_diff(last))
+                    .unwrap()
+            }
+        };
+
+        self.last_seq = Some(self.last_seq.map_or(extended, |last| last.max(extended)));
+
+        extended
+    }
+}
+
+struct Session {
+    rtc: Rtc,
+    tracks: Vec<BusTrack>,
+    pending: Option<SdpPendingOffer>,
+    // Tracks that arrived while an offer was in flight, offered once it's answered
+    queued: Vec<TrackMutexWrapper>,
+    is_forwarding_allowed: bool,
+    on_negotiation_needed: RenegotiationCallback,
+    /// When str0m next wants `Input::Timeout`, as told by `poll_output`
+    deadline: Instant,
+}
+
+struct Worker {
+    socket: UdpSocket,
+    local_addr: SocketAddr,
+    commands: Receiver<Command>,
+    sessions: HashMap<String, Session>,
+    load: Arc<AtomicUsize>,
+    runtime: tokio::runtime::Handle,
+}
+
+impl Worker {
+    fn run(mut self) {
+        let mut buf = vec![0u8; 2000];
+
+        loop {
+            if !self._drain_commands() {
+                break;
+            }
+
+            for session in self.sessions.values_mut() {
+                Self::_forward(session);
+            }
+
+            let next_timeout = self._poll_sessions();
+            let wait = next_timeout
+                .saturating_duration_since(Instant::now())
+                .clamp(Duration::from_millis(1), POLL_INTERVAL);
+
+            self._receive(&mut buf, wait);
+
+            // Only peers whose deadline passed are woken, the rest would do nothing with it
+            let now = Instant::now();
+            for session in self.sessions.values_mut() {
+                if session.deadline <= now {
+                    let _ = session.rtc.handle_input(Input::Timeout(now));
+                }
+            }
+        }
+
+        for (peer_id, session) in self.sessions.drain() {
+            Self::_release(&peer_id, &session);
+        }
+
+        debug!("[str0m] worker on {} stopped", self.local_addr);
+    }
+
+    /// Waits up to `wait` for a datagram, then takes whatever else already queued
+    /// up without blocking
+    fn _receive(&mut self, buf: &mut [u8], wait: Duration) {
+        let _ = self.socket.set_read_timeout(Some(wait));
+
+        let mut is_blocking = true;
+        for _ in 0..MAX_DATAGRAMS_PER_LOOP {
+            match self.socket.recv_from(buf) {
+                Ok((n, source)) => self._route(source, &buf[..n]),
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => break,
+                Err(e) => {
+                    warn!("[str0m] socket error: {:?}", e);
+                    break;
+                }
+            }
+
+            if is_blocking {
+                let _ = self.socket.set_nonblocking(true);
+                is_blocking = false;
+            }
+        }
+
+        if !is_blocking {
+            let _ = self.socket.set_nonblocking(false);
+        }
+    }
+
+    /// Returns false once the pool is gone
+    fn _drain_commands(&mut self) -> bool {
+        loop {
+            match self.commands.try_recv() {
+                Ok(command) => self._handle_command(command),
+                Err(TryRecvError::Empty) => return true,
+                Err(TryRecvError::Disconnected) => return false,
+            }
+        }
+    }
+
+    fn _handle_command(&mut self, command: Command) {
+        match command {
+            Command::Subscribe {
+                peer_id,
+                tracks,
+                is_forwarding_allowed,
+                on_negotiation_needed,
+                reply,
+            } => {
+                let _ = reply.send(self._subscribe(
+                    &peer_id,
+                    tracks,
+                    is_forwarding_allowed,
+                    on_negotiation_needed,
+                ));
+            }
+            Command::AddTrack { peer_id, track } => {
+                if let Some(session) = self.sessions.get_mut(&peer_id) {
+                    if session.pending.is_some() {
+                        session.queued.push(track);
+                    } else {
+                        self._renegotiate(&peer_id, vec![track]);
+                    }
+                }
+            }
+            Command::SetAnswer {
+                peer_id,
+                sdp,
+                reply,
+            } => {
+                let _ = reply.send(self._accept_answer(&peer_id, &sdp));
+            }
+            Command::AddCandidate { peer_id, candidate } => {
+                if let Some(session) = self.sessions.get_mut(&peer_id) {
+                    match Candidate::from_sdp_string(&candidate) {
+                        Ok(candidate) => session.rtc.add_remote_candidate(candidate),
+                        Err(e) => debug!("[str0m] invalid candidate for {}: {:?}", peer_id, e),
+                    }
+                }
+            }
+            Command::SetForwardingAllowed {
+                peer_id,
+                is_allowed,
+            } => {
+                if let Some(session) = self.sessions.get_mut(&peer_id) {
+                    let was_allowed = session.is_forwarding_allowed;
+                    session.is_forwarding_allowed = is_allowed;
+
+                    // Resume from a keyframe so the subscriber doesn't decode garbage
+                    if is_allowed && !was_allowed {
+                        Self::_request_keyframes(session);
+                    }
+                }
+            }
+            Command::Close { peer_id } => self._close(&peer_id),
+        }
+    }
+
+    fn _subscribe(
+        &mut self,
+        peer_id: &str,
+        tracks: Vec<TrackMutexWrapper>,
+        is_forwarding_allowed: bool,
+        on_negotiation_needed: RenegotiationCallback,
+    ) -> Result<String, WebRTCError> {
+        self._close(peer_id);
+
+        let mut rtc = RtcConfig::new()
+            .set_ice_lite(true)
+            .set_rtp_mode(true)
+            .build();
+
+        let candidate = Candidate::host(self.local_addr, Protocol::Udp)
+            .map_err(|_| WebRTCError::FailedToCreatePeer)?;
+        rtc.add_local_candidate(candidate);
+
+        let mut session = Session {
+            rtc,
+            tracks: Vec::new(),
+            pending: None,
+            queued: Vec::new(),
+            is_forwarding_allowed,
+            on_negotiation_needed,
+            deadline: Instant::now(),
+        };
+
+        let offer = Self::_offer(&mut session, peer_id, tracks)?;
+
+        self.sessions.insert(peer_id.to_owned(), session);
+        self.load.fetch_add(1, Ordering::Relaxed);
+
+        Ok(offer)
+    }
+
+    fn _offer(
+        session: &mut Session,
+        peer_id: &str,
+        tracks: Vec<TrackMutexWrapper>,
+    ) -> Result<String, WebRTCError> {
+        let mut api = session.rtc.sdp_api();
+        let mut added = Vec::with_capacity(tracks.len());
+
+        for track in tracks {
+            let (kind, stream_id, track_id, codec) = {
+                let reader = track.read();
+                (
+                    media_kind(&reader),
+                    reader.stream_id.clone(),
+                    reader.id.clone(),
+                    codec_of(&reader),
+                )
+            };
+
+            let mid = api.add_media(
+                kind,
+                Direction::SendOnly,
+                Some(stream_id),
+                Some(track_id),
+                None,
+            );
+            added.push((mid, codec, track));
+        }
+
+        let (offer, pending) = api.apply().ok_or(WebRTCError::FailedToCreateOffer)?;
+        session.pending = Some(pending);
+
+        for (mid, codec, track) in added {
+            let receiver = track.read().add_bus_receiver(peer_id);
+            session.tracks.push(BusTrack {
+                mid,
+                codec,
+                pt: None,
+                track,
+                receiver,
+                last_seq: None,
+            });
+        }
+
+        Ok(offer.to_sdp_string())
+    }
+
+    fn _renegotiate(&mut self, peer_id: &str, tracks: Vec<TrackMutexWrapper>) {
+        let Some(session) = self.sessions.get_mut(peer_id) else {
+            return;
+        };
+
+        match Self::_offer(session, peer_id, tracks) {
+            Ok(offer) => {
+                let callback = session.on_negotiation_needed.clone();
+                self.runtime.spawn((callback)(offer));
+            }
+            Err(e) => warn!("[str0m] failed to renegotiate {}: {:?}", peer_id, e),
+        }
+    }
+
+    fn _accept_answer(&mut self, peer_id: &str, sdp: &str) -> Result<(), WebRTCError> {
+        let session = self
+            .sessions
+            .get_mut(peer_id)
+            .ok_or(WebRTCError::PeerNotFound)?;
+
+        let pending = session.pending.take().ok_or(WebRTCError::FailedToSetSdp)?;
+        let answer = SdpAnswer::from_sdp_string(sdp).map_err(|_| WebRTCError::FailedToSetSdp)?;
+
+        session
+            .rtc
+            .sdp_api()
+            .accept_answer(pending, answer)
+            .map_err(|_| WebRTCError::FailedToSetSdp)?;
+
+        // Answerers reuse the offer's payload types, so they're known from here on
+        for bus in session.tracks.iter_mut().filter(|bus| bus.pt.is_none()) {
+            bus.pt = bus
+                .codec
+                .and_then(|codec| resolve_pt(&session.rtc, bus.mid, codec));
+        }
+
+        Self::_request_keyframes(session);
+
+        let queued = std::mem::take(&mut session.queued);
+        if !queued.is_empty() {
+            self._renegotiate(peer_id, queued);
+        }
+
+        Ok(())
+    }
+
+    fn _forward(session: &mut Session) {
+        let Session {
+            rtc,
+            tracks,
+            is_forwarding_allowed,
+            ..
+        } = session;
+        let now = Instant::now();
+
+        for bus in tracks.iter_mut() {
+            for _ in 0..MAX_PACKETS_PER_TRACK {
+                // Keep draining while paused or unnegotiated so the bus doesn't back up
+                let Ok(info) = bus.receiver.try_recv() else {
+                    break;
+                };
+
+                let Some(pt) = bus.pt else {
+                    continue;
+                };
+
+                if !*is_forwarding_allowed || !should_forward(&info) {
+                    continue;
+                }
+
+                let header = &info.packet.header;
+                let seq_no = bus.extend_seq(header.sequence_number);
+
+                let mut direct = rtc.direct_api();
+                let Some(stream) = direct.stream_tx_by_mid(bus.mid, None) else {
+                    continue;
+                };
+
+                if let Err(e) = stream.write_rtp(
+                    pt,
+                    seq_no.into(),
+                    header.timestamp,
+                    now,
+                    header.marker,
+                    ExtensionValues::default(),
+                    true,
+                    info.packet.payload.to_vec(),
+                ) {
+                    debug!("[str0m] write_rtp failed: {:?}", e);
+                }
+            }
+        }
+    }
+
+    /// Flush pending output and drop dead peers, returns when the next timeout is due
+    fn _poll_sessions(&mut self) -> Instant {
+        let mut next_timeout = Instant::now() + POLL_INTERVAL;
+        let mut dead = Vec::new();
+
+        for (peer_id, session) in self.sessions.iter_mut() {
+            Self::_poll_session(&self.socket, peer_id, session);
+            next_timeout = next_timeout.min(session.deadline);
+
+            if !session.rtc.is_alive() {
+                dead.push(peer_id.clone());
+            }
+        }
+
+        for peer_id in dead {
+            self._close(&peer_id);
+        }
+
+        next_timeout
+    }
+
+    /// Polls a peer until str0m has nothing left to say and records its next deadline
+    fn _poll_session(socket: &UdpSocket, peer_id: &str, session: &mut Session) {
+        loop {
+            match session.rtc.poll_output() {
+                Ok(Output::Timeout(t)) => {
+                    session.deadline = t;
+                    break;
+                }
+                Ok(Output::Transmit(t)) => {
+                    let _ = socket.send_to(&t.contents, t.destination);
+                }
+                Ok(Output::Event(event)) => Self::_handle_event(peer_id, session, event),
+                Err(e) => {
+                    warn!("[str0m] peer {} failed: {:?}", peer_id, e);
+                    session.rtc.disconnect();
+                    break;
+                }
+            }
+        }
+    }
+
+    fn _handle_event(peer_id: &str, session: &mut Session, event: Event) {
+        match event {
+            Event::IceConnectionStateChange(IceConnectionState::Disconnected) => {
+                debug!("[str0m] peer {} disconnected", peer_id);
+                session.rtc.disconnect();
+            }
+            Event::KeyframeRequest(request) => {
+                if let Some(bus) = session.tracks.iter().find(|bus| bus.mid == request.mid) {
+                    bus.track.read().request_keyframe();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Hand a datagram to whichever peer owns it, ICE or DTLS tell them apart
+    fn _route(&mut self, source: SocketAddr, contents: &[u8]) {
+        let Ok(contents) = contents.try_into() else {
+            return;
+        };
+
+        let input = Input::Receive(
+            Instant::now(),
+            Receive {
+                proto: Protocol::Udp,
+                source,
+                destination: self.local_addr,
+                contents,
+            },
+        );
+
+        let Some((peer_id, session)) = self
+            .sessions
+            .iter_mut()
+            .find(|(_, session)| session.rtc.accepts(&input))
+        else {
+            return;
+        };
+
+        if let Err(e) = session.rtc.handle_input(input) {
+            debug!("[str0m] failed to handle input from {}: {:?}", source, e);
+        }
+
+        // str0m wants its output drained before the next input
+        Self::_poll_session(&self.socket, peer_id, session);
+    }
+
+    fn _request_keyframes(session: &Session) {
+        for bus in &session.tracks {
+            bus.track.read().request_keyframe();
+        }
+    }
+
+    fn _close(&mut self, peer_id: &str) {
+        if let Some(mut session) = self.sessions.remove(peer_id) {
+            session.rtc.disconnect();
+            Self::_release(peer_id, &session);
+            self.load.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    fn _release(peer_id: &str, session: &Session) {
+        for bus in &session.tracks {
+            bus.track.read().remove_bus_receiver(peer_id);
+        }
+    }
+}
+
+/// Without a quality data channel str0m subscribers stay on the medium layer
+fn should_forward(info: &RtpForwardInfo) -> bool {
+    let desired = TrackQuality::Medium;
+
+    // str0m numbers the packets it sends itself, dropping layers leaves no gaps
+    if let Some(layer) = &info.svc_layer
+        && !info.is_simulcast
+    {
+        return SvcTarget::new(&desired, &desired).accepts(layer);
+    }
+
+    ForwardTrack::_is_acceptable_track(
+        &info.acceptable_map,
+        info.track_quality.clone(),
+        desired,
+        info.is_simulcast,
+    )
+}
+
+fn media_kind(track: &Track) -> MediaKind {
+    match track.kind {
+        RTPCodecType::Audio => MediaKind::Audio,
+        _ => MediaKind::Video,
+    }
+}
+
+fn codec_of(track: &Track) -> Option<Codec> {
+    if track.kind == RTPCodecType::Audio {
+        return Some(Codec::Opus);
+    }
+
+    match track.codec_type {
+        CodecType::VP8 => Some(Codec::Vp8),
+        CodecType::VP9 => Some(Codec::Vp9),
+        CodecType::H264 => Some(Codec::H264),
+        CodecType::AV1 => Some(Codec::Av1),
+        CodecType::Other => None,
+    }
+}
+
+fn resolve_pt(rtc: &Rtc, mid: Mid, codec: Codec) -> Option<Pt> {
+    let media = rtc.media(mid)?;
+
+    rtc.codec_config()
+        .params()
+        .iter()
+        .filter(|params| params.spec().codec == codec)
+        .map(|params| params.pt())
+        .find(|pt| media.remote_pts().contains(pt))
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use parking_lot::RwLock;
+    use str0m::change::SdpOffer;
+    use webrtc::rtp::{header::Header, packet::Packet};
+    use webrtc::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
+
+    use super::*;
+
+    const OPUS_PT: u8 = 111;
+
+    fn opus_track() -> TrackMutexWrapper {
+        let capability = RTCRtpCodecCapability {
+            mime_type: "audio/opus".to_owned(),
+            clock_rate: 48000,
+            channels: 2,
+            sdp_fmtp_line: "minptime=10;useinbandfec=1".to_owned(),
+            rtcp_feedback: vec![],
+        };
+
+        Arc::new(RwLock::new(Track::detached(
+            "audio",
+            RTPCodecType::Audio,
+            capability,
+        )))
+    }
+
+    fn opus_packet(sequence_number: u16) -> Packet {
+        Packet {
+            header: Header {
+                version: 2,
+                payload_type: OPUS_PT,
+                sequence_number,
+                timestamp: sequence_number as u32 * 960,
+                ssrc: 1,
+                ..Default::default()
+            },
+            payload: Bytes::from_static(&[0xfc, 0xff, 0xfe]),
+        }
+    }
+
+    fn free_port() -> u16 {
+        UdpSocket::bind("127.0.0.1:0")
+            .and_then(|socket| socket.local_addr())
+            .map(|addr| addr.port())
+            .unwrap()
+    }
+
+    /// Runs the subscriber's side until it got RTP from the pool, returns whether it did
+    fn receive_rtp(mut client: Rtc, socket: UdpSocket, track: TrackMutexWrapper) -> bool {
+        let local_addr = socket.local_addr().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut buf = vec![0u8; 2000];
+        let mut is_connected = false;
+        let mut sequence_number = 0u16;
+
+        socket
+            .set_read_timeout(Some(Duration::from_millis(5)))
+            .unwrap();
+
+        while Instant::now() < deadline {
+            loop {
+                match client.poll_output().unwrap() {
+                    Output::Timeout(_) => break,
+                    Output::Transmit(t) => {
+                        socket.send_to(&t.contents, t.destination).unwrap();
+                    }
+                    Output::Event(Event::Connected) => is_connected = true,
+                    Output::Event(Event::RtpPacket(_)) => return true,
+                    Output::Event(_) => {}
+                }
+            }
+
+            if is_connected {
+                sequence_number = sequence_number.wrapping_add(1);
+                track.read().forward(opus_packet(sequence_number));
+            }
+
+            let input = match socket.recv_from(&mut buf) {
+                Ok((n, source)) => Input::Receive(
+                    Instant::now(),
+                    Receive {
+                        proto: Protocol::Udp,
+                        source,
+                        destination: local_addr,
+                        contents: buf[..n].try_into().unwrap(),
+                    },
+                ),
+                Err(_) => Input::Timeout(Instant::now()),
+            };
+            client.handle_input(input).unwrap();
+        }
+
+        false
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_loopback_subscriber_receives_rtp() {
+        let pool = Str0mSubscriberPool::new("127.0.0.1", free_port(), 1).unwrap();
+        let track = opus_track();
+        let on_negotiation_needed: RenegotiationCallback = Arc::new(|_| Box::pin(async {}));
+
+        let offer = pool
+            .subscribe(
+                "peer",
+                vec![Arc::clone(&track)],
+                true,
+                on_negotiation_needed,
+            )
+            .await
+            .unwrap();
+
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut client = RtcConfig::new().set_rtp_mode(true).build();
+        client.add_local_candidate(
+            Candidate::host(socket.local_addr().unwrap(), Protocol::Udp).unwrap(),
+        );
+
+        let answer = client
+            .sdp_api()
+            .accept_offer(SdpOffer::from_sdp_string(&offer).unwrap())
+            .unwrap();
+
+        pool.set_remote_sdp("peer", &answer.to_sdp_string())
+            .await
+            .unwrap();
+        assert_eq!(pool.peer_count(), 1);
+
+        let is_received = tokio::task::spawn_blocking(move || receive_rtp(client, socket, track))
+            .await
+            .unwrap();
+        assert!(is_received);
+
+        pool.close("peer");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_answer_for_unknown_peer_fails() {
+        let pool = Str0mSubscriberPool::new("127.0.0.1", free_port(), 1).unwrap();
+
+        assert!(matches!(
+            pool.set_remote_sdp("nobody", "v=0").await,
+            Err(WebRTCError::PeerNotFound)
+        ));
+    }
+}
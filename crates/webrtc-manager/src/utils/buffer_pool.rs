@@ -0,0 +1,183 @@
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+};
+
+use crossbeam::queue::ArrayQueue;
+use serde::Serialize;
+
+/// Fits any RTP packet that made it through a 1500 byte MTU
+pub const RTP_BUFFER_SIZE: usize = 1500;
+
+/// Lock-free pool of RTP read buffers shared by every track ingestion loop on the node.
+/// Buffers return to the pool on drop; an empty pool falls back to allocating, which
+/// is counted as a miss so undersized pools show up in the stats.
+pub struct RtpBufferPool {
+    buffers: ArrayQueue<Box<[u8]>>,
+    in_use: AtomicUsize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RtpBufferPoolStats {
+    pub capacity: usize,
+    pub available: usize,
+    pub in_use: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl fmt::Debug for RtpBufferPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RtpBufferPool")
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl RtpBufferPool {
+    pub fn new(capacity: usize) -> Self {
+        let buffers = ArrayQueue::new(capacity.max(1));
+
+        while buffers.push(Self::_allocate()).is_ok() {}
+
+        Self {
+            buffers,
+            in_use: AtomicUsize::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn acquire(&self) -> PooledBuffer<'_> {
+        let buffer = match self.buffers.pop() {
+            Some(buffer) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                buffer
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Self::_allocate()
+            }
+        };
+
+        self.in_use.fetch_add(1, Ordering::Relaxed);
+
+        PooledBuffer {
+            buffer: Some(buffer),
+            pool: self,
+        }
+    }
+
+    pub fn stats(&self) -> RtpBufferPoolStats {
+        RtpBufferPoolStats {
+            capacity: self.buffers.capacity(),
+            available: self.buffers.len(),
+            in_use: self.in_use.load(Ordering::Relaxed),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    fn _release(&self, buffer: Box<[u8]>) {
+        self.in_use.fetch_sub(1, Ordering::Relaxed);
+
+        // A full pool means this one was allocated on a miss, let it go
+        let _ = self.buffers.push(buffer);
+    }
+
+    fn _allocate() -> Box<[u8]> {
+        vec![0u8; RTP_BUFFER_SIZE].into_boxed_slice()
+    }
+}
+
+pub struct PooledBuffer<'a> {
+    buffer: Option<Box<[u8]>>,
+    pool: &'a RtpBufferPool,
+}
+
+impl Deref for PooledBuffer<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.buffer.as_deref().unwrap_or_default()
+    }
+}
+
+impl DerefMut for PooledBuffer<'_> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.buffer.as_deref_mut().unwrap_or_default()
+    }
+}
+
+impl Drop for PooledBuffer<'_> {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            self.pool._release(buffer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_released_buffer_returns_to_pool() {
+        let pool = RtpBufferPool::new(2);
+
+        let mut buffer = pool.acquire();
+        assert_eq!(buffer.len(), RTP_BUFFER_SIZE);
+        buffer[0] = 0x80;
+
+        let stats = pool.stats();
+        assert_eq!(stats.available, 1);
+        assert_eq!(stats.in_use, 1);
+
+        drop(buffer);
+
+        let stats = pool.stats();
+        assert_eq!(stats.capacity, 2);
+        assert_eq!(stats.available, 2);
+        assert_eq!(stats.in_use, 0);
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 0);
+    }
+
+    #[test]
+    fn test_empty_pool_counts_misses() {
+        let pool = RtpBufferPool::new(1);
+
+        let first = pool.acquire();
+        let second = pool.acquire();
+        let third = pool.acquire();
+        assert_eq!(third.len(), RTP_BUFFER_SIZE);
+
+        let stats = pool.stats();
+        assert_eq!(stats.available, 0);
+        assert_eq!(stats.in_use, 3);
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 2);
+
+        drop((first, second, third));
+    }
+
+    #[test]
+    fn test_full_pool_lets_extra_buffers_go() {
+        let pool = RtpBufferPool::new(1);
+
+        let first = pool.acquire();
+        let second = pool.acquire();
+
+        drop(first);
+        // Allocated on a miss, there is no room left for it
+        drop(second);
+
+        let stats = pool.stats();
+        assert_eq!(stats.capacity, 1);
+        assert_eq!(stats.available, 1);
+        assert_eq!(stats.in_use, 0);
+    }
+}
@@ -0,0 +1,340 @@
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use crate::{
+    models::quality::TrackQuality,
+    utils::layer_switch::{LayerPacket, LayerSwitch},
+};
+
+/// A layer with no packet for this long has stopped, the publisher dropped it
+/// for bandwidth or CPU
+const LAYER_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// How long a layer that came back has to keep flowing before egress moves up
+/// to it, so a flapping high layer doesn't switch the recording back and forth
+const RESTORE_DELAY: Duration = Duration::from_millis(500);
+
+/// What to do with a packet of one of the layers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EgressLayer {
+    /// Not a packet egress writes
+    Skip,
+    /// Skip, egress is moving to this layer and waits on a key frame of it to
+    /// be asked for
+    RequestKeyframe,
+    /// Write with this header, which keeps one SSRC and carries on the
+    /// sequence numbers and timestamps across switches
+    Write {
+        ssrc: u32,
+        sequence_number: u16,
+        timestamp: u32,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LayerSeen {
+    /// Since when the layer has been flowing without a gap
+    since: Instant,
+    last: Instant,
+    /// Came back after stopping, rather than flowing since it was first seen
+    is_restored: bool,
+}
+
+#[derive(Debug, Default)]
+struct SelectorState {
+    layers: [Option<LayerSeen>; 4],
+    switch: LayerSwitch,
+    ssrc: Option<u32>,
+}
+
+/// Picks the simulcast layer recording and live egress tap: the highest one
+/// the publisher is sending, moving down when it stops and back up once it
+/// has been flowing again for a moment. Every layer's read loop reports its
+/// packets here, so whichever layer arrived first doesn't matter.
+#[derive(Debug, Default)]
+pub struct EgressLayerSelector {
+    state: Mutex<SelectorState>,
+}
+
+impl EgressLayerSelector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn observe(
+        &self,
+        quality: &TrackQuality,
+        ssrc: u32,
+        sequence_number: u16,
+        timestamp: u32,
+        is_keyframe: bool,
+        now: Instant,
+    ) -> EgressLayer {
+        let mut state = self.state.lock();
+
+        let index = quality.as_u8() as usize;
+        state.layers[index] = Some(match state.layers[index] {
+            Some(seen) if now.saturating_duration_since(seen.last) <= LAYER_TIMEOUT => {
+                LayerSeen { last: now, ..seen }
+            }
+            seen => LayerSeen {
+                since: now,
+                last: now,
+                is_restored: seen.is_some(),
+            },
+        });
+
+        let Some(target) = state.target(now) else {
+            return EgressLayer::Skip;
+        };
+
+        match state.switch.process(
+            &target,
+            quality,
+            sequence_number,
+            timestamp,
+            is_keyframe,
+            now,
+        ) {
+            LayerPacket::Drop => EgressLayer::Skip,
+            LayerPacket::RequestKeyframe => EgressLayer::RequestKeyframe,
+            LayerPacket::Forward {
+                sequence_number,
+                timestamp,
+            } => EgressLayer::Write {
+                ssrc: *state.ssrc.get_or_insert(ssrc),
+                sequence_number,
+                timestamp,
+            },
+        }
+    }
+}
+
+impl SelectorState {
+    fn is_active(&self, quality: &TrackQuality, now: Instant) -> bool {
+        self.layers[quality.as_u8() as usize]
+            .is_some_and(|seen| now.saturating_duration_since(seen.last) <= LAYER_TIMEOUT)
+    }
+
+    /// Whether egress can move to the layer, it's new or has been back a while
+    fn is_stable(&self, quality: &TrackQuality, now: Instant) -> bool {
+        self.layers[quality.as_u8() as usize].is_some_and(|seen| {
+            !seen.is_restored || now.saturating_duration_since(seen.since) >= RESTORE_DELAY
+        })
+    }
+
+    /// The highest layer flowing, among the tapped one and those egress can
+    /// move to. The highest flowing at all when none is.
+    fn target(&self, now: Instant) -> Option<TrackQuality> {
+        let qualities = [
+            TrackQuality::High,
+            TrackQuality::Medium,
+            TrackQuality::Low,
+            TrackQuality::None,
+        ];
+
+        qualities
+            .iter()
+            .find(|quality| {
+                self.is_active(quality, now)
+                    && (self.switch.current() == Some(*quality) || self.is_stable(quality, now))
+            })
+            .or_else(|| {
+                qualities
+                    .iter()
+                    .find(|quality| self.is_active(quality, now))
+            })
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(33);
+    const KEYFRAME_INTERVAL: u16 = 10;
+
+    /// Sends a packet of each of `layers` per frame from `start` for `frames`,
+    /// a key frame on the first frame and every `KEYFRAME_INTERVAL` frames
+    /// after. Returns the last instant and the qualities written.
+    fn run(
+        selector: &EgressLayerSelector,
+        layers: &[TrackQuality],
+        start: Instant,
+        frames: u32,
+        sequence: &mut u16,
+    ) -> (Instant, Vec<(TrackQuality, EgressLayer)>) {
+        let mut written = vec![];
+        let mut now = start;
+
+        for _ in 0..frames {
+            now += FRAME;
+            *sequence = sequence.wrapping_add(1);
+
+            for quality in layers {
+                let layer = quality.as_u8() as u32;
+                let result = selector.observe(
+                    quality,
+                    1000 + layer,
+                    sequence.wrapping_add(layer as u16 * 10_000),
+                    3000 * *sequence as u32 + layer * 1_000_000,
+                    *sequence % KEYFRAME_INTERVAL == 1,
+                    now,
+                );
+
+                if matches!(result, EgressLayer::Write { .. }) {
+                    written.push((quality.clone(), result));
+                }
+            }
+        }
+
+        (now, written)
+    }
+
+    fn all_layers() -> Vec<TrackQuality> {
+        vec![TrackQuality::Low, TrackQuality::Medium, TrackQuality::High]
+    }
+
+    #[test]
+    fn test_taps_high_layer_whichever_arrives_first() {
+        let selector = EgressLayerSelector::new();
+        let mut sequence = 0;
+
+        // The first frame moves up as the layers show up
+        let (now, first) = run(&selector, &all_layers(), Instant::now(), 1, &mut sequence);
+        assert_eq!(first.last().unwrap().0, TrackQuality::High);
+
+        let (_, written) = run(&selector, &all_layers(), now, 30, &mut sequence);
+
+        assert_eq!(written.len(), 30);
+        assert!(
+            written
+                .iter()
+                .all(|(quality, _)| *quality == TrackQuality::High)
+        );
+    }
+
+    #[test]
+    fn test_falls_back_when_high_layer_stops() {
+        let selector = EgressLayerSelector::new();
+        let mut sequence = 0;
+
+        let (now, _) = run(&selector, &all_layers(), Instant::now(), 30, &mut sequence);
+        let (_, written) = run(
+            &selector,
+            &[TrackQuality::Low, TrackQuality::Medium],
+            now,
+            90,
+            &mut sequence,
+        );
+
+        let (first, _) = written.first().unwrap();
+        assert_eq!(*first, TrackQuality::Medium);
+        assert!(
+            written
+                .iter()
+                .all(|(quality, _)| *quality == TrackQuality::Medium)
+        );
+        // The high layer counts as flowing for another 45 frames, then the
+        // medium one waits 5 frames on its next key frame
+        assert_eq!(written.len(), 40);
+    }
+
+    #[test]
+    fn test_restores_high_layer_after_delay() {
+        let selector = EgressLayerSelector::new();
+        let mut sequence = 0;
+
+        let (now, _) = run(&selector, &all_layers(), Instant::now(), 30, &mut sequence);
+        let (now, _) = run(
+            &selector,
+            &[TrackQuality::Low, TrackQuality::Medium],
+            now,
+            90,
+            &mut sequence,
+        );
+        let (_, written) = run(&selector, &all_layers(), now, 60, &mut sequence);
+
+        let switch_at = written
+            .iter()
+            .position(|(quality, _)| *quality == TrackQuality::High)
+            .unwrap();
+
+        // Stays down until the high layer has flowed for the restore delay
+        // and sent a key frame
+        assert!(switch_at as u32 * FRAME.as_millis() as u32 >= RESTORE_DELAY.as_millis() as u32);
+        assert!(
+            written[..switch_at]
+                .iter()
+                .all(|(quality, _)| *quality == TrackQuality::Medium)
+        );
+        assert!(
+            written[switch_at..]
+                .iter()
+                .all(|(quality, _)| *quality == TrackQuality::High)
+        );
+    }
+
+    #[test]
+    fn test_switch_keeps_one_continuous_stream() {
+        let selector = EgressLayerSelector::new();
+        let mut sequence = 0;
+
+        let (now, before) = run(&selector, &all_layers(), Instant::now(), 30, &mut sequence);
+        let (_, after) = run(
+            &selector,
+            &[TrackQuality::Low, TrackQuality::Medium],
+            now,
+            90,
+            &mut sequence,
+        );
+
+        let EgressLayer::Write {
+            ssrc: ssrc_before,
+            sequence_number: sequence_before,
+            timestamp: timestamp_before,
+            ..
+        } = before.last().unwrap().1
+        else {
+            panic!("expected a written packet");
+        };
+        let EgressLayer::Write {
+            ssrc: ssrc_after,
+            sequence_number: sequence_after,
+            timestamp: timestamp_after,
+            ..
+        } = after.first().unwrap().1
+        else {
+            panic!("expected a written packet");
+        };
+
+        assert_eq!(ssrc_before, ssrc_after);
+        assert_eq!(sequence_after, sequence_before.wrapping_add(1));
+        assert!(timestamp_after > timestamp_before);
+    }
+
+    #[test]
+    fn test_single_layer_passes_through() {
+        let selector = EgressLayerSelector::new();
+        let now = Instant::now();
+
+        assert_eq!(
+            selector.observe(&TrackQuality::None, 42, 7, 900, true, now),
+            EgressLayer::Write {
+                ssrc: 42,
+                sequence_number: 7,
+                timestamp: 900,
+            }
+        );
+        assert_eq!(
+            selector.observe(&TrackQuality::None, 42, 8, 3900, false, now + FRAME),
+            EgressLayer::Write {
+                ssrc: 42,
+                sequence_number: 8,
+                timestamp: 3900,
+            }
+        );
+    }
+}
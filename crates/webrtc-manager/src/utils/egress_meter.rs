@@ -0,0 +1,52 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Past this share of the budget the node counts as under pressure, before
+/// the uplink itself starts dropping packets
+const PRESSURE_SHARE: f64 = 0.9;
+
+/// Measures the video the node forwards to subscribers against what its
+/// uplink is sized for
+#[derive(Debug)]
+pub struct EgressMeter {
+    budget_bps: f64,
+    window: Mutex<(Instant, usize)>,
+    rate_bps: AtomicU64,
+}
+
+impl EgressMeter {
+    /// `0` leaves the node never under pressure, rooms still can be
+    pub fn new(budget_bps: f64) -> Self {
+        Self {
+            budget_bps,
+            window: Mutex::new((Instant::now(), 0)),
+            rate_bps: AtomicU64::new(0),
+        }
+    }
+
+    pub fn record(&self, size: usize) {
+        let mut window = self.window.lock();
+        window.1 += size;
+
+        let elapsed = window.0.elapsed();
+        if elapsed >= RATE_WINDOW {
+            let rate = window.1 as f64 * 8.0 / elapsed.as_secs_f64();
+            self.rate_bps.store(rate as u64, Ordering::Relaxed);
+            *window = (Instant::now(), 0);
+        }
+    }
+
+    pub fn rate_bps(&self) -> u64 {
+        self.rate_bps.load(Ordering::Relaxed)
+    }
+
+    pub fn is_under_pressure(&self) -> bool {
+        self.budget_bps > 0.0 && self.rate_bps() as f64 >= self.budget_bps * PRESSURE_SHARE
+    }
+}
@@ -0,0 +1,144 @@
+use crate::entities::track::CodecType;
+
+const H264_NALU_TYPE_MASK: u8 = 0x1F;
+const H264_NALU_IDR: u8 = 5;
+const H264_NALU_SPS: u8 = 7;
+const H264_NALU_STAP_A: u8 = 24;
+const H264_NALU_FU_A: u8 = 28;
+
+/// Whether the RTP payload starts a key frame, where a subscriber moved to
+/// this stream can start decoding. `None` for codecs it can't tell for.
+pub fn is_keyframe(codec_type: &CodecType, payload: &[u8]) -> Option<bool> {
+    match codec_type {
+        CodecType::VP8 => Some(is_vp8_keyframe(payload)),
+        CodecType::VP9 => Some(is_vp9_keyframe(payload)),
+        CodecType::H264 => Some(is_h264_keyframe(payload)),
+        CodecType::AV1 => Some(is_av1_keyframe(payload)),
+        CodecType::Other => None,
+    }
+}
+
+/// RFC 7741, the first packet of partition 0 with the inverse key frame
+/// flag of the VP8 payload header clear
+fn is_vp8_keyframe(payload: &[u8]) -> bool {
+    let Some(&descriptor) = payload.first() else {
+        return false;
+    };
+
+    let is_partition_start = descriptor & 0x10 != 0 && descriptor & 0x07 == 0;
+    if !is_partition_start {
+        return false;
+    }
+
+    let mut offset = 1;
+    if descriptor & 0x80 != 0 {
+        let Some(&extension) = payload.get(1) else {
+            return false;
+        };
+        offset += 1;
+
+        // Picture id, seven or fifteen bits
+        if extension & 0x80 != 0 {
+            let Some(&picture_id) = payload.get(offset) else {
+                return false;
+            };
+            offset += if picture_id & 0x80 != 0 { 2 } else { 1 };
+        }
+        // TL0PICIDX
+        if extension & 0x40 != 0 {
+            offset += 1;
+        }
+        // TID and KEYIDX share a byte
+        if extension & 0x30 != 0 {
+            offset += 1;
+        }
+    }
+
+    payload.get(offset).is_some_and(|header| header & 0x01 == 0)
+}
+
+/// The start of a frame not predicted from earlier ones
+fn is_vp9_keyframe(payload: &[u8]) -> bool {
+    payload
+        .first()
+        .is_some_and(|descriptor| descriptor & 0x40 == 0 && descriptor & 0x08 != 0)
+}
+
+/// An IDR slice or the SPS sent ahead of one, alone, aggregated or as the
+/// first fragment
+fn is_h264_keyframe(payload: &[u8]) -> bool {
+    let Some(&header) = payload.first() else {
+        return false;
+    };
+
+    let is_keyframe_nalu = |nalu_type| nalu_type == H264_NALU_IDR || nalu_type == H264_NALU_SPS;
+
+    match header & H264_NALU_TYPE_MASK {
+        H264_NALU_STAP_A => {
+            let mut offset = 1;
+            while offset + 2 < payload.len() {
+                let size = u16::from_be_bytes([payload[offset], payload[offset + 1]]) as usize;
+                if is_keyframe_nalu(payload[offset + 2] & H264_NALU_TYPE_MASK) {
+                    return true;
+                }
+                offset += 2 + size;
+            }
+            false
+        }
+        H264_NALU_FU_A => payload.get(1).is_some_and(|fu_header| {
+            fu_header & 0x80 != 0 && fu_header & H264_NALU_TYPE_MASK == H264_NALU_IDR
+        }),
+        nalu_type => is_keyframe_nalu(nalu_type),
+    }
+}
+
+/// The aggregation header flags the first packet of a coded video sequence
+fn is_av1_keyframe(payload: &[u8]) -> bool {
+    payload.first().is_some_and(|header| header & 0x08 != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vp8_keyframe() {
+        // Partition start, then a key frame payload header
+        assert_eq!(is_keyframe(&CodecType::VP8, &[0x10, 0x00]), Some(true));
+        // With a fifteen bit picture id
+        assert_eq!(
+            is_keyframe(&CodecType::VP8, &[0x90, 0x80, 0x81, 0x23, 0x00]),
+            Some(true)
+        );
+        // Inter frame
+        assert_eq!(is_keyframe(&CodecType::VP8, &[0x10, 0x01]), Some(false));
+        // Continuation of a key frame
+        assert_eq!(is_keyframe(&CodecType::VP8, &[0x00, 0x00]), Some(false));
+    }
+
+    #[test]
+    fn test_h264_keyframe() {
+        assert_eq!(is_keyframe(&CodecType::H264, &[0x65, 0x88]), Some(true));
+        assert_eq!(is_keyframe(&CodecType::H264, &[0x41, 0x9a]), Some(false));
+        // STAP-A carrying SPS and PPS
+        assert_eq!(
+            is_keyframe(
+                &CodecType::H264,
+                &[0x78, 0x00, 0x02, 0x67, 0x42, 0x00, 0x01, 0x68]
+            ),
+            Some(true)
+        );
+        // First and middle FU-A fragments of an IDR slice
+        assert_eq!(is_keyframe(&CodecType::H264, &[0x7c, 0x85]), Some(true));
+        assert_eq!(is_keyframe(&CodecType::H264, &[0x7c, 0x05]), Some(false));
+    }
+
+    #[test]
+    fn test_vp9_and_av1_keyframe() {
+        assert_eq!(is_keyframe(&CodecType::VP9, &[0x88]), Some(true));
+        assert_eq!(is_keyframe(&CodecType::VP9, &[0xc8]), Some(false));
+        assert_eq!(is_keyframe(&CodecType::AV1, &[0x18]), Some(true));
+        assert_eq!(is_keyframe(&CodecType::AV1, &[0x10]), Some(false));
+        assert_eq!(is_keyframe(&CodecType::Other, &[0x00]), None);
+    }
+}
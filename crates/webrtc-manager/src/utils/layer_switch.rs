@@ -0,0 +1,378 @@
+use std::time::{Duration, Instant};
+
+use crate::models::quality::TrackQuality;
+
+/// A target layer still without a key frame gets one asked for again after this
+const KEYFRAME_RETRY: Duration = Duration::from_secs(1);
+
+const VIDEO_CLOCK_RATE: f64 = 90_000.0;
+
+/// Packets this far past a switch are beyond reordering, no packet from
+/// before the switch can show up anymore
+const SWITCH_REORDER_WINDOW: u16 = 1 << 14;
+
+/// What to do with a packet of one of the layers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerPacket {
+    Drop,
+    /// Drop, the target layer waits on a key frame to be asked for
+    RequestKeyframe,
+    /// Send with this sequence number and timestamp
+    Forward {
+        sequence_number: u16,
+        timestamp: u32,
+    },
+}
+
+/// Moves a stream between simulcast layers on key frames only, keeping the
+/// layer it sends until the target one starts a key frame so the receiver
+/// never gets a frame it can't decode. Sequence numbers and timestamps carry
+/// on across switches, the receiver sees one stream without gaps or repeats.
+#[derive(Debug, Default)]
+pub struct LayerSwitch {
+    current: Option<TrackQuality>,
+    /// The target layer and when its key frame was last asked for
+    keyframe_requested: Option<(TrackQuality, Instant)>,
+    sequence_offset: u16,
+    timestamp_offset: u32,
+    /// The last sequence number sent before the switch, packets of the new
+    /// layer from before its key frame would land on or behind it
+    switch_floor: Option<u16>,
+    /// Sequence number, timestamp and time of the newest packet sent
+    last_sent: Option<(u16, u32, Instant)>,
+}
+
+impl LayerSwitch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The layer being sent
+    pub fn current(&self) -> Option<&TrackQuality> {
+        self.current.as_ref()
+    }
+
+    pub fn process(
+        &mut self,
+        target: &TrackQuality,
+        quality: &TrackQuality,
+        sequence_number: u16,
+        timestamp: u32,
+        is_keyframe: bool,
+        now: Instant,
+    ) -> LayerPacket {
+        if self.current.as_ref() == Some(quality) {
+            return self.forward(sequence_number, timestamp, now);
+        }
+
+        if quality != target {
+            return LayerPacket::Drop;
+        }
+
+        if !is_keyframe {
+            let is_due = match &self.keyframe_requested {
+                Some((requested, at)) if requested == quality => {
+                    now.saturating_duration_since(*at) >= KEYFRAME_RETRY
+                }
+                _ => true,
+            };
+
+            if is_due {
+                self.keyframe_requested = Some((quality.clone(), now));
+                return LayerPacket::RequestKeyframe;
+            }
+
+            return LayerPacket::Drop;
+        }
+
+        self.switch_to(quality, sequence_number, timestamp, now);
+        self.forward(sequence_number, timestamp, now)
+    }
+
+    fn switch_to(
+        &mut self,
+        quality: &TrackQuality,
+        sequence_number: u16,
+        timestamp: u32,
+        now: Instant,
+    ) {
+        self.current = Some(quality.clone());
+        self.keyframe_requested = None;
+
+        // Carry on from the last packet sent, as if the new layer had been
+        // the one sent all along
+        if let Some((last_sequence, last_timestamp, last_at)) = self.last_sent {
+            let elapsed = now.saturating_duration_since(last_at).as_secs_f64();
+            let ticks = ((elapsed * VIDEO_CLOCK_RATE) as u32).max(1);
+
+            self.sequence_offset = last_sequence.wrapping_add(1).wrapping_sub(sequence_number);
+            self.timestamp_offset = last_timestamp.wrapping_add(ticks).wrapping_sub(timestamp);
+            self.switch_floor = Some(last_sequence);
+        }
+    }
+
+    fn forward(&mut self, sequence_number: u16, timestamp: u32, now: Instant) -> LayerPacket {
+        let sequence_number = sequence_number.wrapping_add(self.sequence_offset);
+        let timestamp = timestamp.wrapping_add(self.timestamp_offset);
+
+        if let Some(floor) = self.switch_floor {
+            let ahead = sequence_number.wrapping_sub(floor);
+            if ahead == 0 || ahead > u16::MAX / 2 {
+                return LayerPacket::Drop;
+            }
+            if ahead > SWITCH_REORDER_WINDOW {
+                self.switch_floor = None;
+            }
+        }
+
+        // Reordered packets don't move the point switches carry on from
+        let is_newest = self.last_sent.is_none_or(|(last_sequence, _, _)| {
+            sequence_number.wrapping_sub(last_sequence) <= u16::MAX / 2
+        });
+        if is_newest {
+            self.last_sent = Some((sequence_number, timestamp, now));
+        }
+
+        LayerPacket::Forward {
+            sequence_number,
+            timestamp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(33);
+    const PACKETS_PER_FRAME: u16 = 3;
+    const KEYFRAME_INTERVAL: u32 = 60;
+
+    struct SyntheticLayer {
+        quality: TrackQuality,
+        sequence_number: u16,
+        timestamp: u32,
+    }
+
+    impl SyntheticLayer {
+        fn new(quality: TrackQuality, sequence_number: u16, timestamp: u32) -> Self {
+            Self {
+                quality,
+                sequence_number,
+                timestamp,
+            }
+        }
+    }
+
+    /// Runs `frames` frames of every layer through the switch, key frames
+    /// every `KEYFRAME_INTERVAL` frames offset by `keyframe_phase`. Returns
+    /// what was sent, with whether it was a key frame start, and how many key
+    /// frames were asked for.
+    fn run(
+        switch: &mut LayerSwitch,
+        layers: &mut [SyntheticLayer],
+        target: &TrackQuality,
+        frames: u32,
+        keyframe_phase: u32,
+        now: &mut Instant,
+        frame_index: &mut u32,
+    ) -> (Vec<(TrackQuality, u16, u32, bool)>, usize) {
+        let mut sent = vec![];
+        let mut keyframe_requests = 0;
+
+        for _ in 0..frames {
+            *now += FRAME;
+            *frame_index += 1;
+            let is_keyframe_frame = (*frame_index + keyframe_phase) % KEYFRAME_INTERVAL == 0;
+
+            for layer in layers.iter_mut() {
+                layer.timestamp = layer.timestamp.wrapping_add(3000);
+
+                for packet in 0..PACKETS_PER_FRAME {
+                    layer.sequence_number = layer.sequence_number.wrapping_add(1);
+                    let is_keyframe = is_keyframe_frame && packet == 0;
+
+                    match switch.process(
+                        target,
+                        &layer.quality,
+                        layer.sequence_number,
+                        layer.timestamp,
+                        is_keyframe,
+                        *now,
+                    ) {
+                        LayerPacket::Forward {
+                            sequence_number,
+                            timestamp,
+                        } => sent.push((
+                            layer.quality.clone(),
+                            sequence_number,
+                            timestamp,
+                            is_keyframe,
+                        )),
+                        LayerPacket::RequestKeyframe => keyframe_requests += 1,
+                        LayerPacket::Drop => {}
+                    }
+                }
+            }
+        }
+
+        (sent, keyframe_requests)
+    }
+
+    fn layers() -> Vec<SyntheticLayer> {
+        vec![
+            SyntheticLayer::new(TrackQuality::Low, 100, 5_000),
+            SyntheticLayer::new(TrackQuality::Medium, 40_000, 900_000),
+            SyntheticLayer::new(TrackQuality::High, 65_500, u32::MAX - 10_000),
+        ]
+    }
+
+    fn assert_continuous(sent: &[(TrackQuality, u16, u32, bool)]) {
+        for pair in sent.windows(2) {
+            let (_, previous_sequence, previous_timestamp, _) = pair[0];
+            let (_, sequence, timestamp, _) = pair[1];
+
+            assert_eq!(sequence, previous_sequence.wrapping_add(1));
+            assert!(timestamp.wrapping_sub(previous_timestamp) < u32::MAX / 2);
+        }
+    }
+
+    #[test]
+    fn test_starts_on_keyframe() {
+        let mut switch = LayerSwitch::new();
+        let mut layers = layers();
+        let (mut now, mut frame_index) = (Instant::now(), 0);
+
+        let (sent, keyframe_requests) = run(
+            &mut switch,
+            &mut layers,
+            &TrackQuality::Medium,
+            40,
+            35,
+            &mut now,
+            &mut frame_index,
+        );
+
+        assert_eq!(keyframe_requests, 1);
+        assert!(sent[0].3);
+        assert!(
+            sent.iter()
+                .all(|(quality, ..)| *quality == TrackQuality::Medium)
+        );
+        assert_continuous(&sent);
+    }
+
+    #[test]
+    fn test_switches_up_and_down_on_keyframes_only() {
+        let mut switch = LayerSwitch::new();
+        let mut layers = layers();
+        let (mut now, mut frame_index) = (Instant::now(), 0);
+        let mut sent = vec![];
+
+        for (target, frames) in [
+            (TrackQuality::Low, 60),
+            (TrackQuality::High, 60),
+            (TrackQuality::Medium, 60),
+            (TrackQuality::Low, 60),
+        ] {
+            let (batch, _) = run(
+                &mut switch,
+                &mut layers,
+                &target,
+                frames,
+                7,
+                &mut now,
+                &mut frame_index,
+            );
+            sent.extend(batch);
+        }
+
+        // Every layer change lands on a key frame, the layer left is sent
+        // until then
+        for pair in sent.windows(2) {
+            if pair[0].0 != pair[1].0 {
+                assert!(pair[1].3);
+            }
+        }
+        assert_eq!(
+            sent.windows(2)
+                .filter(|pair| pair[0].0 != pair[1].0)
+                .count(),
+            3
+        );
+        assert_continuous(&sent);
+    }
+
+    #[test]
+    fn test_keeps_current_layer_while_target_waits() {
+        let mut switch = LayerSwitch::new();
+        let mut layers = layers();
+        let (mut now, mut frame_index) = (Instant::now(), 0);
+
+        run(
+            &mut switch,
+            &mut layers,
+            &TrackQuality::High,
+            60,
+            0,
+            &mut now,
+            &mut frame_index,
+        );
+
+        // The low layer's next key frame is far off
+        let (sent, keyframe_requests) = run(
+            &mut switch,
+            &mut layers,
+            &TrackQuality::Low,
+            40,
+            5,
+            &mut now,
+            &mut frame_index,
+        );
+
+        assert!(
+            sent.iter()
+                .all(|(quality, ..)| *quality == TrackQuality::High)
+        );
+        assert_eq!(sent.len(), 40 * PACKETS_PER_FRAME as usize);
+        // Asked for again once the first request went unanswered
+        assert_eq!(keyframe_requests, 2);
+    }
+
+    #[test]
+    fn test_drops_packets_reordered_across_switch() {
+        let mut switch = LayerSwitch::new();
+        let now = Instant::now();
+
+        let sent = |result| match result {
+            LayerPacket::Forward {
+                sequence_number, ..
+            } => Some(sequence_number),
+            _ => None,
+        };
+
+        assert_eq!(
+            sent(switch.process(&TrackQuality::Low, &TrackQuality::Low, 10, 0, true, now)),
+            Some(10)
+        );
+        assert_eq!(
+            sent(switch.process(&TrackQuality::High, &TrackQuality::High, 500, 0, true, now)),
+            Some(11)
+        );
+        // A packet of the high layer from before its key frame, it would
+        // repeat sequence number 10
+        assert_eq!(
+            sent(switch.process(&TrackQuality::High, &TrackQuality::High, 499, 0, false, now)),
+            None
+        );
+        // The low layer isn't sent anymore
+        assert_eq!(
+            sent(switch.process(&TrackQuality::High, &TrackQuality::Low, 11, 0, true, now)),
+            None
+        );
+        assert_eq!(
+            sent(switch.process(&TrackQuality::High, &TrackQuality::High, 501, 0, false, now)),
+            Some(12)
+        );
+    }
+}
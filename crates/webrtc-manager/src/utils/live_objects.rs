@@ -0,0 +1,64 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub enum LiveObject {
+    Room,
+    Publisher,
+    Subscriber,
+    Track,
+    ForwardTrack,
+}
+
+static LIVE_COUNTS: [AtomicUsize; 5] = [const { AtomicUsize::new(0) }; 5];
+
+impl LiveObject {
+    /// Instances allocated right now, wherever they are held from
+    pub fn count(self) -> usize {
+        LIVE_COUNTS[self as usize].load(Ordering::Relaxed)
+    }
+}
+
+/// Counts the object holding it as alive. Clones share the guard, so an
+/// object whose clones share state is counted once until the last is dropped.
+#[derive(Debug, Clone)]
+pub struct LiveGuard(Arc<GuardInner>);
+
+#[derive(Debug)]
+struct GuardInner(LiveObject);
+
+impl LiveGuard {
+    pub fn new(object: LiveObject) -> Self {
+        LIVE_COUNTS[object as usize].fetch_add(1, Ordering::Relaxed);
+        Self(Arc::new(GuardInner(object)))
+    }
+}
+
+impl Drop for GuardInner {
+    fn drop(&mut self) {
+        LIVE_COUNTS[self.0 as usize].fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Publishers are the one object no other test creates, the counts are process wide
+    #[test]
+    fn test_guard_counts_until_the_last_clone_is_dropped() {
+        let before = LiveObject::Publisher.count();
+
+        let guard = LiveGuard::new(LiveObject::Publisher);
+        let clone = guard.clone();
+        assert_eq!(LiveObject::Publisher.count(), before + 1);
+
+        drop(guard);
+        assert_eq!(LiveObject::Publisher.count(), before + 1);
+
+        drop(clone);
+        assert_eq!(LiveObject::Publisher.count(), before);
+    }
+}
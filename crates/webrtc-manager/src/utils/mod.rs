@@ -1 +1,11 @@
+pub mod buffer_pool;
+pub mod egress_layer;
+pub mod egress_meter;
+pub mod keyframe;
+pub mod layer_switch;
+pub mod live_objects;
 pub mod multicast_sender;
+pub mod noise_suppression;
+pub mod pacer;
+pub mod svc;
+pub mod talk_time;
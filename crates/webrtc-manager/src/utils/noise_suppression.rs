@@ -0,0 +1,166 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+};
+
+use egress_manager::processing::noise_suppressor::NoiseSuppressor;
+use parking_lot::Mutex;
+use tracing::warn;
+use webrtc::{
+    rtp::{header::Header, packet::Packet},
+    util::marshal::Unmarshal,
+};
+
+/// Packets the pipeline may sit on before it counts as falling behind, half a
+/// second of 20ms frames
+const MAX_BACKLOG: usize = 25;
+
+pub type DenoisedCallback = Arc<dyn Fn(Packet) + Send + Sync>;
+
+/// How many microphones a node denoises at once. Each one decodes, filters and
+/// re-encodes its audio, so the cap is what keeps rooms that turn it on from
+/// starving the node's forwarding.
+#[derive(Debug)]
+pub struct NoiseSuppressionBudget {
+    max_streams: usize,
+    active: AtomicUsize,
+}
+
+impl NoiseSuppressionBudget {
+    /// `0` turns server-side noise suppression off on the node
+    pub fn new(max_streams: usize) -> Self {
+        Self {
+            max_streams,
+            active: AtomicUsize::new(0),
+        }
+    }
+
+    /// `None` once the node runs as many suppressors as it may
+    pub fn try_acquire(self: &Arc<Self>) -> Option<NoiseSuppressionPermit> {
+        self.active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |active| {
+                (active < self.max_streams).then_some(active + 1)
+            })
+            .ok()
+            .map(|_| NoiseSuppressionPermit(Arc::clone(self)))
+    }
+
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::Relaxed)
+    }
+}
+
+/// One slot of the node's budget, given back on drop
+#[derive(Debug)]
+pub struct NoiseSuppressionPermit(Arc<NoiseSuppressionBudget>);
+
+impl Drop for NoiseSuppressionPermit {
+    fn drop(&mut self) {
+        self.0.active.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// A microphone on its way through the noise suppressor. The publisher's
+/// headers are kept aside while the audio is processed and put back on the
+/// denoised payloads, so subscribers see one continuous stream, sequence
+/// numbers, timestamps and audio levels included, processed or not.
+pub struct DenoisedAudio {
+    suppressor: NoiseSuppressor,
+    headers: Arc<Mutex<VecDeque<Header>>>,
+    is_bypassed: Arc<AtomicBool>,
+    permit: Mutex<Option<NoiseSuppressionPermit>>,
+}
+
+impl DenoisedAudio {
+    pub fn new(
+        payload_type: u8,
+        permit: NoiseSuppressionPermit,
+        on_packet: DenoisedCallback,
+    ) -> Result<Self, anyhow::Error> {
+        let headers: Arc<Mutex<VecDeque<Header>>> = Arc::new(Mutex::new(VecDeque::new()));
+        let is_bypassed = Arc::new(AtomicBool::new(false));
+
+        let suppressor = NoiseSuppressor::new(
+            payload_type,
+            Arc::new({
+                let headers = Arc::clone(&headers);
+                let is_bypassed = Arc::clone(&is_bypassed);
+
+                move |data: &[u8]| {
+                    if is_bypassed.load(Ordering::Relaxed) {
+                        return;
+                    }
+
+                    let Ok(denoised) = Packet::unmarshal(&mut &data[..]) else {
+                        return;
+                    };
+
+                    // More out than went in, nothing to send it as
+                    let Some(header) = headers.lock().pop_front() else {
+                        return;
+                    };
+
+                    on_packet(Packet {
+                        header,
+                        payload: denoised.payload,
+                    });
+                }
+            }),
+        )?;
+
+        Ok(Self {
+            suppressor,
+            headers,
+            is_bypassed,
+            permit: Mutex::new(Some(permit)),
+        })
+    }
+
+    /// Hands a packet to the suppressor, false when the caller should forward
+    /// it as is. That's for good once the suppressor falls behind, the node is
+    /// short on CPU then and the microphone goes through unprocessed.
+    pub fn write(&self, packet: &Packet, data: &[u8]) -> bool {
+        if self.is_bypassed.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        {
+            let mut headers = self.headers.lock();
+            if headers.len() >= MAX_BACKLOG {
+                drop(headers);
+                self._bypass("fell behind");
+                return false;
+            }
+
+            headers.push_back(packet.header.clone());
+        }
+
+        if let Err(err) = self.suppressor.write_rtp(data) {
+            self._bypass(&format!("failed ({err})"));
+            return false;
+        }
+
+        true
+    }
+
+    fn _bypass(&self, reason: &str) {
+        if self.is_bypassed.swap(true, Ordering::Relaxed) {
+            return;
+        }
+
+        warn!("Noise suppression {reason}, forwarding the microphone unprocessed");
+
+        self.headers.lock().clear();
+        self.suppressor.stop();
+        self.permit.lock().take();
+    }
+}
+
+impl Drop for DenoisedAudio {
+    fn drop(&mut self) {
+        self.suppressor.stop();
+    }
+}
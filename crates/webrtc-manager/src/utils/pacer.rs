@@ -0,0 +1,252 @@
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use super::egress_meter::EgressMeter;
+
+/// Floor for the pacing rate so a fresh subscriber isn't throttled before anything is measured
+const MIN_RATE_BPS: f64 = 300_000.0;
+/// Past this the queue is flushed instead of delaying packets further
+const MAX_QUEUE_DELAY: Duration = Duration::from_millis(300);
+const RATE_WINDOW: Duration = Duration::from_millis(500);
+const RATE_SMOOTHING: f64 = 0.3;
+const DELAY_SAMPLES: usize = 512;
+
+/// Spreads a subscriber's egress over time so key frames don't leave as one burst.
+/// Packets are released at `multiplier` times the estimated bandwidth, which is the
+/// receiver's REMB when it sends one and the measured forwarding rate otherwise.
+pub struct Pacer {
+    multiplier: f64,
+    state: Mutex<PacerState>,
+    delays: Mutex<VecDeque<Duration>>,
+    egress_meter: Arc<EgressMeter>,
+}
+
+struct PacerState {
+    next_send: Instant,
+    remb_bps: Option<f64>,
+    /// Below 1 while the QoS policy holds this subscriber back for others
+    rate_scale: f64,
+    measured_bps: f64,
+    window_start: Instant,
+    window_bytes: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PacerStats {
+    pub rate_bps: u64,
+    pub queue_delay_p50_ms: f64,
+    pub queue_delay_p95_ms: f64,
+    pub queue_delay_p99_ms: f64,
+}
+
+impl Pacer {
+    pub fn new(multiplier: f64, egress_meter: Arc<EgressMeter>) -> Self {
+        let now = Instant::now();
+
+        Self {
+            multiplier,
+            state: Mutex::new(PacerState {
+                next_send: now,
+                remb_bps: None,
+                rate_scale: 1.0,
+                measured_bps: 0.0,
+                window_start: now,
+                window_bytes: 0,
+            }),
+            delays: Mutex::new(VecDeque::with_capacity(DELAY_SAMPLES)),
+            egress_meter,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.multiplier > 0.0
+    }
+
+    pub fn set_remb(&self, bitrate_bps: f64) {
+        self.state.lock().remb_bps = Some(bitrate_bps);
+    }
+
+    pub fn remb_bps(&self) -> Option<f64> {
+        self.state.lock().remb_bps
+    }
+
+    pub fn set_rate_scale(&self, scale: f64) {
+        self.state.lock().rate_scale = scale;
+    }
+
+    /// Reserve a send slot for `size` bytes, returns how long to hold the packet.
+    /// Counted towards the node's egress whether pacing is on or not.
+    pub fn schedule(&self, size: usize) -> Duration {
+        self.egress_meter.record(size);
+
+        if !self.is_enabled() {
+            return Duration::ZERO;
+        }
+
+        self._schedule(size, Instant::now())
+    }
+
+    pub fn stats(&self) -> PacerStats {
+        let rate_bps = self._rate_bps(&self.state.lock()) as u64;
+
+        let mut samples = self.delays.lock().iter().copied().collect::<Vec<_>>();
+        samples.sort_unstable();
+
+        let percentile = |p: f64| -> f64 {
+            if samples.is_empty() {
+                return 0.0;
+            }
+            let index = ((samples.len() - 1) as f64 * p).round() as usize;
+            samples[index].as_secs_f64() * 1000.0
+        };
+
+        PacerStats {
+            rate_bps,
+            queue_delay_p50_ms: percentile(0.50),
+            queue_delay_p95_ms: percentile(0.95),
+            queue_delay_p99_ms: percentile(0.99),
+        }
+    }
+
+    fn _schedule(&self, size: usize, now: Instant) -> Duration {
+        let delay = {
+            let mut state = self.state.lock();
+
+            state.window_bytes += size;
+            let elapsed = now.duration_since(state.window_start);
+            if elapsed >= RATE_WINDOW {
+                let rate = state.window_bytes as f64 * 8.0 / elapsed.as_secs_f64();
+                state.measured_bps = if state.measured_bps == 0.0 {
+                    rate
+                } else {
+                    state.measured_bps * (1.0 - RATE_SMOOTHING) + rate * RATE_SMOOTHING
+                };
+                state.window_start = now;
+                state.window_bytes = 0;
+            }
+
+            let rate = self._rate_bps(&state);
+            let start = state.next_send.max(now);
+            let delay = start - now;
+
+            if delay > MAX_QUEUE_DELAY {
+                state.next_send = now;
+                Duration::ZERO
+            } else {
+                state.next_send = start + Duration::from_secs_f64(size as f64 * 8.0 / rate);
+                delay
+            }
+        };
+
+        let mut delays = self.delays.lock();
+        if delays.len() >= DELAY_SAMPLES {
+            delays.pop_front();
+        }
+        delays.push_back(delay);
+
+        delay
+    }
+
+    fn _rate_bps(&self, state: &PacerState) -> f64 {
+        let estimate = state.remb_bps.unwrap_or(state.measured_bps);
+
+        estimate.max(MIN_RATE_BPS) * self.multiplier * state.rate_scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Takes 10ms at 1 Mbps
+    const PACKET_SIZE: usize = 1250;
+
+    fn pacer(multiplier: f64) -> Pacer {
+        Pacer::new(multiplier, Arc::new(EgressMeter::new(0.0)))
+    }
+
+    #[test]
+    fn test_disabled_pacer_never_delays() {
+        let pacer = pacer(0.0);
+
+        assert!(!pacer.is_enabled());
+        assert_eq!(pacer.schedule(PACKET_SIZE), Duration::ZERO);
+        assert_eq!(pacer.schedule(PACKET_SIZE), Duration::ZERO);
+        assert!(pacer.delays.lock().is_empty());
+    }
+
+    #[test]
+    fn test_rate_floor_before_anything_is_measured() {
+        let pacer = pacer(2.0);
+
+        assert_eq!(pacer.stats().rate_bps, (MIN_RATE_BPS * 2.0) as u64);
+    }
+
+    #[test]
+    fn test_packets_are_spread_at_the_remb_rate() {
+        let pacer = pacer(1.0);
+        pacer.set_remb(1_000_000.0);
+        let now = Instant::now();
+
+        assert_eq!(pacer._schedule(PACKET_SIZE, now), Duration::ZERO);
+        assert_eq!(pacer._schedule(PACKET_SIZE, now), Duration::from_millis(10));
+        assert_eq!(pacer._schedule(PACKET_SIZE, now), Duration::from_millis(20));
+
+        // Its slot came and went, nothing to wait for
+        let later = now + Duration::from_millis(100);
+        assert_eq!(pacer._schedule(PACKET_SIZE, later), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_rate_scale_holds_the_subscriber_back() {
+        let pacer = pacer(1.0);
+        pacer.set_remb(1_000_000.0);
+        pacer.set_rate_scale(0.5);
+        let now = Instant::now();
+
+        pacer._schedule(PACKET_SIZE, now);
+        assert_eq!(pacer._schedule(PACKET_SIZE, now), Duration::from_millis(20));
+        assert_eq!(pacer.stats().rate_bps, 500_000);
+    }
+
+    #[test]
+    fn test_queue_is_flushed_past_max_delay() {
+        let pacer = pacer(1.0);
+        pacer.set_remb(1_000_000.0);
+        let now = Instant::now();
+
+        for i in 0..=30 {
+            assert_eq!(
+                pacer._schedule(PACKET_SIZE, now),
+                Duration::from_millis(i * 10)
+            );
+        }
+
+        assert_eq!(pacer._schedule(PACKET_SIZE, now), Duration::ZERO);
+        assert_eq!(pacer._schedule(PACKET_SIZE, now), Duration::ZERO);
+        assert_eq!(pacer._schedule(PACKET_SIZE, now), Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_queue_delay_percentiles() {
+        let pacer = pacer(1.0);
+        pacer.set_remb(1_000_000.0);
+        let now = Instant::now();
+
+        for _ in 0..11 {
+            pacer._schedule(PACKET_SIZE, now);
+        }
+
+        let stats = pacer.stats();
+        assert_eq!(stats.queue_delay_p50_ms, 50.0);
+        assert_eq!(stats.queue_delay_p95_ms, 100.0);
+        assert_eq!(stats.queue_delay_p99_ms, 100.0);
+    }
+}
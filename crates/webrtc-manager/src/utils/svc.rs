@@ -0,0 +1,383 @@
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use webrtc::rtp::{codecs::vp9::Vp9Packet, packet::Packet, packetizer::Depacketizer};
+
+use crate::{
+    entities::track::CodecType,
+    models::svc::{SvcLayer, SvcTarget},
+    utils::keyframe::is_keyframe,
+};
+
+/// Carries the layers of AV1 packets, the payload itself doesn't reliably
+pub const DEPENDENCY_DESCRIPTOR_URI: &str =
+    "https://aomediacodec.github.io/av1-rtp-spec/#dependency-descriptor-rtp-header-extension";
+
+/// The dependency descriptor's template ids wrap at this
+const TEMPLATE_ID_MODULO: usize = 64;
+
+/// A spatial up-switch still without a key frame gets one asked for again after this
+const KEYFRAME_RETRY: Duration = Duration::from_secs(1);
+
+/// Reads the layer of each packet of one SVC stream. AV1 layers come from
+/// the dependency descriptor's templates, sent along key frames, so the
+/// parser keeps the last ones it saw.
+#[derive(Debug)]
+pub struct SvcParser {
+    codec_type: CodecType,
+    dependency_descriptor_id: Option<u8>,
+    template_id_offset: usize,
+    /// Spatial and temporal ids by template index
+    templates: Vec<(u8, u8)>,
+}
+
+impl SvcParser {
+    /// `dependency_descriptor_id` as negotiated for the stream, AV1 layers
+    /// can't be told without it
+    pub fn new(codec_type: CodecType, dependency_descriptor_id: Option<u8>) -> Self {
+        Self {
+            codec_type,
+            dependency_descriptor_id,
+            template_id_offset: 0,
+            templates: vec![],
+        }
+    }
+
+    /// `None` for packets carrying no layer information, a stream sent
+    /// without SVC
+    pub fn parse(&mut self, rtp: &Packet) -> Option<SvcLayer> {
+        match self.codec_type {
+            CodecType::VP9 => parse_vp9(&rtp.payload),
+            CodecType::AV1 => {
+                let descriptor = rtp.header.get_extension(self.dependency_descriptor_id?)?;
+                self.parse_dependency_descriptor(&descriptor, &rtp.payload)
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_dependency_descriptor(
+        &mut self,
+        descriptor: &[u8],
+        payload: &[u8],
+    ) -> Option<SvcLayer> {
+        let mut bits = BitReader::new(descriptor);
+
+        let is_frame_start = bits.read(1)? == 1;
+        let is_frame_end = bits.read(1)? == 1;
+        let template_id = bits.read(6)? as usize;
+        let _frame_number = bits.read(16)?;
+
+        if descriptor.len() > 3 {
+            let has_structure = bits.read(1)? == 1;
+            // Active decode targets and the custom dtis, fdiffs and chains flags
+            let _flags = bits.read(4)?;
+
+            if has_structure {
+                self.template_id_offset = bits.read(6)? as usize;
+                let _decode_target_count = bits.read(5)?;
+                self.templates = read_template_layers(&mut bits)?;
+            }
+        }
+
+        let index =
+            (template_id + TEMPLATE_ID_MODULO - self.template_id_offset) % TEMPLATE_ID_MODULO;
+        let &(spatial_id, temporal_id) = self.templates.get(index)?;
+
+        // A single layer has nothing to drop
+        if self.templates.iter().all(|&layer| layer == (0, 0)) {
+            return None;
+        }
+
+        Some(SvcLayer {
+            spatial_id,
+            temporal_id,
+            is_picture_start: is_frame_start && spatial_id == 0,
+            is_layer_end: is_frame_end,
+            is_keyframe: is_frame_start
+                && spatial_id == 0
+                && is_keyframe(&CodecType::AV1, payload).unwrap_or(false),
+        })
+    }
+}
+
+/// The layer indices of the VP9 payload descriptor, present when the
+/// publisher sends spatial or temporal layers
+fn parse_vp9(payload: &Bytes) -> Option<SvcLayer> {
+    let mut vp9_packet = Vp9Packet::default();
+    vp9_packet.depacketize(payload).ok()?;
+
+    if !vp9_packet.l {
+        return None;
+    }
+
+    let is_picture_start = vp9_packet.b && vp9_packet.sid == 0;
+
+    Some(SvcLayer {
+        spatial_id: vp9_packet.sid,
+        temporal_id: vp9_packet.tid,
+        is_picture_start,
+        is_layer_end: vp9_packet.e,
+        is_keyframe: is_picture_start && !vp9_packet.p,
+    })
+}
+
+/// The template layers of a template dependency structure, by template index
+fn read_template_layers(bits: &mut BitReader) -> Option<Vec<(u8, u8)>> {
+    let mut templates = vec![];
+    let (mut spatial_id, mut temporal_id) = (0u8, 0u8);
+
+    loop {
+        templates.push((spatial_id, temporal_id));
+        if templates.len() > TEMPLATE_ID_MODULO {
+            return None;
+        }
+
+        match bits.read(2)? {
+            0 => {}
+            1 => temporal_id += 1,
+            2 => {
+                temporal_id = 0;
+                spatial_id += 1;
+            }
+            _ => return Some(templates),
+        }
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn read(&mut self, count: usize) -> Option<u32> {
+        let mut value = 0;
+
+        for _ in 0..count {
+            let byte = self.data.get(self.position / 8)?;
+            let bit = (byte >> (7 - self.position % 8)) & 1;
+            value = (value << 1) | bit as u32;
+            self.position += 1;
+        }
+
+        Some(value)
+    }
+}
+
+/// What to do with a packet of an SVC stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SvcPacket {
+    Drop,
+    /// Send with this sequence number and marker
+    Forward {
+        sequence_number: u16,
+        marker: bool,
+    },
+}
+
+/// Sends one subscriber the layers of an SVC stream up to its target. The
+/// layers sent change at picture boundaries, up to a higher spatial layer
+/// only on a key frame. Sequence numbers close over the packets dropped and
+/// the marker moves to the end of the top layer sent, so the subscriber sees
+/// an ordinary stream.
+#[derive(Debug, Default)]
+pub struct SvcFilter {
+    current: Option<SvcTarget>,
+    /// Packets dropped so far, taken off the sequence numbers of those sent
+    dropped: u16,
+    keyframe_requested_at: Option<Instant>,
+    wants_keyframe: bool,
+}
+
+impl SvcFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn process(
+        &mut self,
+        target: SvcTarget,
+        layer: &SvcLayer,
+        sequence_number: u16,
+        marker: bool,
+        now: Instant,
+    ) -> SvcPacket {
+        if self.current != Some(target) && layer.is_picture_start {
+            let is_up = self
+                .current
+                .is_none_or(|current| target.spatial_id > current.spatial_id);
+
+            if !is_up || layer.is_keyframe {
+                self.current = Some(target);
+                self.keyframe_requested_at = None;
+            } else if self
+                .keyframe_requested_at
+                .is_none_or(|at| now.saturating_duration_since(at) >= KEYFRAME_RETRY)
+            {
+                self.keyframe_requested_at = Some(now);
+                self.wants_keyframe = true;
+            }
+        }
+
+        let Some(current) = self.current.filter(|current| current.accepts(layer)) else {
+            self.dropped = self.dropped.wrapping_add(1);
+            return SvcPacket::Drop;
+        };
+
+        SvcPacket::Forward {
+            sequence_number: sequence_number.wrapping_sub(self.dropped),
+            marker: marker || (layer.is_layer_end && layer.spatial_id == current.spatial_id),
+        }
+    }
+
+    /// Whether a key frame is to be asked for, a spatial up-switch waits on it
+    pub fn take_keyframe_request(&mut self) -> bool {
+        std::mem::take(&mut self.wants_keyframe)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A picture of an L3T3 stream, one packet per layer frame
+    fn picture(temporal_id: u8, is_keyframe: bool) -> Vec<SvcLayer> {
+        (0..3)
+            .map(|spatial_id| SvcLayer {
+                spatial_id,
+                temporal_id,
+                is_picture_start: spatial_id == 0,
+                is_layer_end: true,
+                is_keyframe: is_keyframe && spatial_id == 0,
+            })
+            .collect()
+    }
+
+    /// Runs pictures through the filter, returning the layers, sequence
+    /// numbers and markers sent
+    fn run(
+        filter: &mut SvcFilter,
+        target: SvcTarget,
+        pictures: &[Vec<SvcLayer>],
+        sequence_number: &mut u16,
+    ) -> Vec<(SvcLayer, u16, bool)> {
+        let mut sent = vec![];
+
+        for picture in pictures {
+            for (index, layer) in picture.iter().enumerate() {
+                *sequence_number = sequence_number.wrapping_add(1);
+                let marker = index == picture.len() - 1;
+
+                if let SvcPacket::Forward {
+                    sequence_number: sent_sequence_number,
+                    marker: sent_marker,
+                } = filter.process(target, layer, *sequence_number, marker, Instant::now())
+                {
+                    sent.push((*layer, sent_sequence_number, sent_marker));
+                }
+            }
+        }
+
+        sent
+    }
+
+    fn pictures(count: usize) -> Vec<Vec<SvcLayer>> {
+        (0..count)
+            .map(|index| picture([0, 2, 1, 2][index % 4], index == 0))
+            .collect()
+    }
+
+    #[test]
+    fn test_drops_layers_above_target() {
+        let mut filter = SvcFilter::new();
+        let mut sequence_number = 65_530;
+        let target = SvcTarget {
+            spatial_id: 1,
+            temporal_id: 1,
+        };
+
+        let sent = run(&mut filter, target, &pictures(8), &mut sequence_number);
+
+        assert!(sent.iter().all(|(layer, ..)| target.accepts(layer)));
+        // Two spatial layers of the pictures on temporal layers 0 and 1
+        assert_eq!(sent.len(), 8);
+        for pair in sent.windows(2) {
+            assert_eq!(pair[1].1, pair[0].1.wrapping_add(1));
+        }
+        // Every picture ends on the top spatial layer sent
+        for (layer, _, marker) in &sent {
+            assert_eq!(*marker, layer.spatial_id == 1);
+        }
+    }
+
+    #[test]
+    fn test_spatial_up_switch_waits_for_keyframe() {
+        let mut filter = SvcFilter::new();
+        let mut sequence_number = 0;
+        let low = SvcTarget {
+            spatial_id: 0,
+            temporal_id: 2,
+        };
+        let high = SvcTarget {
+            spatial_id: 2,
+            temporal_id: 2,
+        };
+
+        run(&mut filter, low, &pictures(4), &mut sequence_number);
+
+        let sent = run(&mut filter, high, &pictures(4)[1..], &mut sequence_number);
+        assert!(sent.iter().all(|(layer, ..)| layer.spatial_id == 0));
+        assert!(filter.take_keyframe_request());
+        assert!(!filter.take_keyframe_request());
+
+        let sent = run(&mut filter, high, &pictures(1), &mut sequence_number);
+        assert_eq!(sent.len(), 3);
+
+        // Down again right away, at the next picture
+        let sent = run(&mut filter, low, &pictures(4)[1..2], &mut sequence_number);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0.spatial_id, 0);
+        assert!(sent[0].2);
+    }
+
+    #[test]
+    fn test_parse_vp9_layers() {
+        // I and L set, B set, picture id 5, TID 1 and SID 1, TL0PICIDX
+        let layer = parse_vp9(&Bytes::from_static(&[0xa8, 0x05, 0x22, 0x00, 0xaa])).unwrap();
+
+        assert_eq!(layer.spatial_id, 1);
+        assert_eq!(layer.temporal_id, 1);
+        assert!(!layer.is_picture_start);
+        assert!(!layer.is_layer_end);
+
+        // No layer indices, a stream sent without SVC
+        assert_eq!(parse_vp9(&Bytes::from_static(&[0x08, 0xaa])), None);
+    }
+
+    #[test]
+    fn test_parse_dependency_descriptor() {
+        let mut parser = SvcParser::new(CodecType::AV1, Some(1));
+
+        // Start of frame, template 1, frame 1, then an L2T2 template
+        // structure: (0, 0), (0, 1), (1, 0), (1, 1)
+        let descriptor = [0x81, 0x00, 0x01, 0x80, 0x00, 0x67];
+        let layer = parser
+            .parse_dependency_descriptor(&descriptor, &[0x10])
+            .unwrap();
+        assert_eq!((layer.spatial_id, layer.temporal_id), (0, 1));
+        assert!(layer.is_picture_start);
+
+        // Later packets refer to the structure seen, end of frame, template 2
+        let layer = parser
+            .parse_dependency_descriptor(&[0x42, 0x00, 0x02], &[0x10])
+            .unwrap();
+        assert_eq!((layer.spatial_id, layer.temporal_id), (1, 0));
+        assert!(layer.is_layer_end);
+        assert!(!layer.is_picture_start);
+    }
+}
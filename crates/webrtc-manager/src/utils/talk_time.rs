@@ -0,0 +1,99 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use parking_lot::Mutex;
+
+/// Sent with the level of each audio packet, RFC 6464
+pub const AUDIO_LEVEL_URI: &str = "urn:ietf:params:rtp-hdrext:ssrc-audio-level";
+
+/// Packets at least this loud, in -dBov, count as speech when the publisher
+/// doesn't flag voice activity itself
+const SPEECH_LEVEL: u8 = 50;
+
+/// Longer gaps between packets are silence the publisher left out, not speech
+const MAX_PACKET_GAP_SECS: f64 = 0.1;
+
+/// How long a publisher's microphone carried speech
+#[derive(Debug)]
+pub struct TalkTimeMeter {
+    clock_rate: u32,
+    /// In RTP clock ticks
+    talk_time: AtomicU64,
+    last_timestamp: Mutex<Option<u32>>,
+}
+
+impl TalkTimeMeter {
+    pub fn new(clock_rate: u32) -> Self {
+        Self {
+            clock_rate: clock_rate.max(1),
+            talk_time: AtomicU64::new(0),
+            last_timestamp: Mutex::new(None),
+        }
+    }
+
+    /// `extension` is the audio level extension of a packet, the time since
+    /// the previous one counts when it's speech
+    pub fn record(&self, rtp_timestamp: u32, extension: &[u8]) {
+        let Some(&level) = extension.first() else {
+            return;
+        };
+
+        let elapsed = self
+            .last_timestamp
+            .lock()
+            .replace(rtp_timestamp)
+            .map(|last| rtp_timestamp.wrapping_sub(last));
+
+        let Some(elapsed) = elapsed
+            .filter(|elapsed| (*elapsed as f64) <= self.clock_rate as f64 * MAX_PACKET_GAP_SECS)
+        else {
+            return;
+        };
+
+        let is_voice_activity = level & 0x80 != 0;
+        if is_voice_activity || level & 0x7f <= SPEECH_LEVEL {
+            self.talk_time.fetch_add(elapsed as u64, Ordering::Relaxed);
+        }
+    }
+
+    pub fn talk_time_ms(&self) -> u64 {
+        self.talk_time.load(Ordering::Relaxed) * 1000 / self.clock_rate as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 20ms Opus packets
+    const PACKET_TICKS: u32 = 960;
+
+    #[test]
+    fn test_counts_loud_and_flagged_packets() {
+        let meter = TalkTimeMeter::new(48_000);
+        let mut timestamp = u32::MAX - PACKET_TICKS * 10;
+
+        // 50 loud packets, 50 silent ones, then 50 quiet ones flagged as voice
+        for level in [30, 0x7f, 0x80 | 90] {
+            for _ in 0..50 {
+                timestamp = timestamp.wrapping_add(PACKET_TICKS);
+                meter.record(timestamp, &[level]);
+            }
+        }
+
+        // The first packet has nothing to measure from
+        assert_eq!(meter.talk_time_ms(), 49 * 20 + 50 * 20);
+    }
+
+    #[test]
+    fn test_skips_gaps_and_missing_levels() {
+        let meter = TalkTimeMeter::new(48_000);
+
+        meter.record(0, &[20]);
+        meter.record(PACKET_TICKS, &[20]);
+        // The publisher left out two seconds of silence
+        meter.record(PACKET_TICKS + 96_000, &[20]);
+        meter.record(PACKET_TICKS * 2 + 96_000, &[]);
+
+        assert_eq!(meter.talk_time_ms(), 20);
+    }
+}
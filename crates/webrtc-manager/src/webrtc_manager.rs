@@ -1,21 +1,36 @@
-use std::sync::Arc;
+use std::{collections::HashMap, path::Path, sync::Arc, time::Duration};
 
 use dashmap::DashMap;
+use egress_manager::egress::slate_writer::SlateWriter;
 use parking_lot::RwLock;
+use tracing::warn;
 
 use crate::{
+    entities::media::Media,
     errors::WebRTCError,
     models::{
         connection_type::ConnectionType,
+        diagnostics::{NodeDiagnostics, ObjectCounts},
+        forwarding_acl::ForwardingAcl,
         params::{
-            IceCandidate, IceCandidateCallback, JoinRoomParams, JoinRoomResponse, JoinedCallback,
-            RenegotiationCallback, SubscribeParams, SubscribeResponse, WClient,
-            WebRTCManagerConfigs,
+            DownlinkStarvedCallback, IceCandidate, IceCandidateCallback, JoinRoomParams,
+            JoinRoomResponse, JoinedCallback, RenegotiationCallback, SubscribeParams,
+            SubscribeResponse, WClient, WebRTCManagerConfigs,
         },
+        qos::{QosClass, QosPolicy},
+        quality::TrackQuality,
+        room_stats::RoomStats,
+        subscriber_backend::SubscriberBackend,
+        track_info::TrackInfo,
     },
     room::Room,
+    services::str0m_pool::Str0mSubscriberPool,
+    utils::{buffer_pool::RtpBufferPoolStats, pacer::PacerStats},
 };
 
+/// Same root as the per-publisher playlists, see `Media::new`
+const SLATE_OUTPUT_DIR: &str = "./hls";
+
 pub struct JoinRoomReq {
     pub client_id: String,
     pub participant_id: String,
@@ -25,7 +40,22 @@ pub struct JoinRoomReq {
     pub is_audio_enabled: bool,
     pub is_e2ee_enabled: bool,
     pub total_tracks: u8,
+    pub tracks: Vec<TrackInfo>,
     pub connection_type: u8,
+    pub start_egress: bool,
+    /// Denoise the microphone on the node, the client doesn't
+    pub noise_suppression: bool,
+    /// How far HLS and MoQ viewers are behind, zero for none
+    pub broadcast_delay: Duration,
+    pub qos_class: QosClass,
+    /// The room's forwarding ACL as the dispatcher keeps it, replaces whatever
+    /// this node had
+    pub is_stage_mode: bool,
+    pub speaker_ids: Vec<String>,
+    /// Like the ACL, the room's recording pause as the dispatcher keeps it
+    pub is_recording_paused: bool,
+    /// And whether its broadcast is cut
+    pub is_broadcast_cut: bool,
     pub callback: JoinedCallback,
     pub ice_candidate_callback: IceCandidateCallback,
 }
@@ -34,18 +64,99 @@ pub struct JoinRoomReq {
 pub struct WebRTCManager {
     rooms: Arc<DashMap<String, Arc<RwLock<Room>>>>,
     clients: Arc<DashMap<String, WClient>>,
+    acls: Arc<DashMap<String, Arc<ForwardingAcl>>>,
+    qos_policies: Arc<DashMap<String, Arc<QosPolicy>>>,
+    /// Pre-roll slates of scheduled live streams, keyed by room
+    slates: Arc<DashMap<String, SlateWriter>>,
+    str0m_pool: Option<Arc<Str0mSubscriberPool>>,
     configs: WebRTCManagerConfigs,
 }
 
 impl WebRTCManager {
     pub fn new(configs: WebRTCManagerConfigs) -> Self {
+        let str0m_pool = match configs.subscriber_backend {
+            SubscriberBackend::Str0m { port, workers } => Some(Arc::new(
+                Str0mSubscriberPool::new(&configs.public_ip, port, workers)
+                    .expect("Failed to start str0m subscriber workers"),
+            )),
+            SubscriberBackend::WebRtcRs => None,
+        };
+
         Self {
             rooms: Arc::new(DashMap::new()),
             clients: Arc::new(DashMap::new()),
+            acls: Arc::new(DashMap::new()),
+            qos_policies: Arc::new(DashMap::new()),
+            slates: Arc::new(DashMap::new()),
+            str0m_pool,
             configs,
         }
     }
 
+    /// Subscribers currently served by str0m, zero on the webrtc-rs backend
+    pub fn str0m_subscriber_count(&self) -> usize {
+        self.str0m_pool
+            .as_ref()
+            .map(|pool| pool.peer_count())
+            .unwrap_or(0)
+    }
+
+    pub fn rtp_buffer_pool_stats(&self) -> RtpBufferPoolStats {
+        self.configs.rtp_buffer_pool.stats()
+    }
+
+    /// Counts of everything the node holds, for telling leaks apart from load
+    pub fn diagnostics(&self) -> NodeDiagnostics {
+        let mut reachable = ObjectCounts::default();
+        let mut forwarding_channels = 0;
+
+        let rooms: Vec<_> = self
+            .rooms
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        for room in rooms {
+            let (counts, channels) = room.read().object_counts();
+            reachable += counts;
+            forwarding_channels += channels;
+        }
+
+        NodeDiagnostics {
+            clients: self.clients.len(),
+            reachable,
+            live: ObjectCounts::live(),
+            str0m_subscribers: self.str0m_subscriber_count(),
+            forwarding_channels,
+        }
+    }
+
+    /// Participants of the room connected to this node, publishing or not
+    pub fn room_participants(&self, room_id: &str) -> Vec<String> {
+        self.clients
+            .iter()
+            .filter(|client| client.room_id == room_id)
+            .map(|client| client.participant_id.clone())
+            .collect()
+    }
+
+    /// This node's share of the room, `RoomNotFound` when it hosts none of it
+    pub fn room_stats(&self, room_id: &str) -> Result<RoomStats, WebRTCError> {
+        let room = self._get_room_by_id(room_id)?;
+        let room = room.read();
+
+        Ok(room.stats())
+    }
+
+    pub fn subscriber_pacer_stats(
+        &self,
+        room_id: &str,
+    ) -> Result<Vec<(String, PacerStats)>, WebRTCError> {
+        let room = self._get_room_by_id(room_id)?;
+        let room = room.read();
+
+        Ok(room.subscriber_pacer_stats())
+    }
+
     #[allow(clippy::all)]
     pub async fn join_room(
         &self,
@@ -55,11 +166,20 @@ impl WebRTCManager {
         let room_id = &req.room_id;
         let participant_id = &req.participant_id;
 
+        if self
+            .clients
+            .get(client_id)
+            .is_some_and(|client| client.is_observer)
+        {
+            return Err(WebRTCError::ObserverCannotPublish);
+        }
+
         self._add_client(
             client_id,
             WClient {
                 participant_id: participant_id.clone(),
                 room_id: room_id.clone(),
+                is_observer: false,
             },
         );
 
@@ -71,6 +191,13 @@ impl WebRTCManager {
             }
         };
 
+        self._get_qos(room_id)
+            .set_class(participant_id, req.qos_class);
+
+        self._get_acl(room_id)
+            .replace(req.is_stage_mode, &req.speaker_ids);
+        self._apply_forwarding_acl(room_id);
+
         let params = JoinRoomParams {
             participant_id: participant_id.to_string(),
             sdp: req.sdp,
@@ -78,13 +205,28 @@ impl WebRTCManager {
             is_video_enabled: req.is_video_enabled,
             is_e2ee_enabled: req.is_e2ee_enabled,
             total_tracks: req.total_tracks,
+            tracks: req.tracks,
             connection_type: ConnectionType::from(req.connection_type),
+            start_egress: req.start_egress,
+            noise_suppression: req.noise_suppression,
+            broadcast_delay: req.broadcast_delay,
             callback: req.callback,
             on_candidate: req.ice_candidate_callback,
         };
 
+        // The host went live, its own egress takes over from the slate
+        if req.start_egress {
+            self.stop_slate(room_id);
+        }
+
         let res = {
             let mut room = room.write();
+            if room.is_recording_paused() != req.is_recording_paused {
+                room.set_recording_paused(req.is_recording_paused);
+            }
+            if room.is_broadcast_cut() != req.is_broadcast_cut {
+                room.set_broadcast_cut(req.is_broadcast_cut);
+            }
             room.join_room(params, room_id).await?
         };
 
@@ -98,14 +240,18 @@ impl WebRTCManager {
         target_id: &str,
         participant_id: &str,
         room_id: &str,
+        is_observer: bool,
         renegotiation_callback: RenegotiationCallback,
         ice_candidate_callback: IceCandidateCallback,
+        downlink_starved_callback: DownlinkStarvedCallback,
+        video_codecs: Vec<String>,
     ) -> Result<SubscribeResponse, WebRTCError> {
         self._add_client(
             client_id,
             WClient {
                 participant_id: participant_id.to_owned(),
                 room_id: room_id.to_owned(),
+                is_observer,
             },
         );
 
@@ -117,6 +263,8 @@ impl WebRTCManager {
             target_id: (&target_id).to_string(),
             on_candidate: ice_candidate_callback,
             on_negotiation_needed: renegotiation_callback,
+            on_downlink_starved: downlink_starved_callback,
+            video_codecs,
         };
 
         let res = room.subscribe(params).await?;
@@ -125,7 +273,7 @@ impl WebRTCManager {
     }
 
     #[allow(clippy::all)]
-    pub fn set_subscriber_desc(
+    pub async fn set_subscriber_desc(
         &self,
         client_id: &str,
         target_id: &str,
@@ -139,9 +287,14 @@ impl WebRTCManager {
         let participant_id = &client.participant_id;
 
         let room = self._get_room_by_id(room_id)?;
-        let room = room.read();
 
-        room.set_subscriber_remote_sdp(target_id, participant_id, sdp)?;
+        let str0m_subscriber = room.read().get_str0m_subscriber(target_id, participant_id);
+        if let Some((pool, peer_id)) = str0m_subscriber {
+            return pool.set_remote_sdp(&peer_id, sdp).await;
+        }
+
+        room.read()
+            .set_subscriber_remote_sdp(target_id, participant_id, sdp)?;
 
         Ok(())
     }
@@ -151,6 +304,7 @@ impl WebRTCManager {
         &self,
         client_id: &str,
         sdp: &str,
+        tracks: Vec<TrackInfo>,
     ) -> Result<String, WebRTCError> {
         let client = self.get_client_by_id(client_id)?;
 
@@ -163,7 +317,7 @@ impl WebRTCManager {
         let room = room.read();
 
         let sdp = room
-            .handle_publisher_renegotiation(participant_id, sdp)
+            .handle_publisher_renegotiation(participant_id, sdp, tracks)
             .await?;
 
         Ok(sdp)
@@ -246,14 +400,29 @@ impl WebRTCManager {
             room_guard.clone()
         };
 
-        room_clone_for_leave.leave_room(&participant_id);
+        if client.is_observer {
+            room_clone_for_leave.leave_as_observer(&participant_id);
+        } else {
+            room_clone_for_leave.leave_room(&participant_id);
+            self._get_qos(room_id)
+                .set_class(&participant_id, QosClass::Attendee);
+        }
 
         self._remove_client(client_id);
 
+        if room_clone_for_leave.is_empty() {
+            self._remove_room(room_id);
+        }
+
         Ok(client)
     }
 
-    pub fn set_audio_enabled(&self, client_id: &str, is_enabled: bool) -> Result<(), WebRTCError> {
+    pub fn set_audio_enabled(
+        &self,
+        client_id: &str,
+        is_enabled: bool,
+        is_hardware_muted: Option<bool>,
+    ) -> Result<(), WebRTCError> {
         let client = self.get_client_by_id(client_id)?;
 
         let client = client.clone();
@@ -264,12 +433,17 @@ impl WebRTCManager {
         let room = self._get_room_by_id(&room_id)?;
         let room = room.read();
 
-        room.set_audio_enabled(&participant_id, is_enabled)?;
+        room.set_audio_enabled(&participant_id, is_enabled, is_hardware_muted)?;
 
         Ok(())
     }
 
-    pub fn set_video_enabled(&self, client_id: &str, is_enabled: bool) -> Result<(), WebRTCError> {
+    pub fn set_video_enabled(
+        &self,
+        client_id: &str,
+        is_enabled: bool,
+        is_hardware_muted: Option<bool>,
+    ) -> Result<(), WebRTCError> {
         let client = self.get_client_by_id(client_id)?;
 
         let client = client.clone();
@@ -280,7 +454,7 @@ impl WebRTCManager {
         let room = self._get_room_by_id(&room_id)?;
         let room = room.read();
 
-        room.set_video_enabled(&participant_id, is_enabled)?;
+        room.set_video_enabled(&participant_id, is_enabled, is_hardware_muted)?;
 
         Ok(())
     }
@@ -301,6 +475,26 @@ impl WebRTCManager {
         Ok(())
     }
 
+    pub fn set_viewport(
+        &self,
+        client_id: &str,
+        tiles: &HashMap<String, TrackQuality>,
+    ) -> Result<(), WebRTCError> {
+        let client = self.get_client_by_id(client_id)?;
+
+        let client = client.clone();
+
+        let room_id = client.room_id;
+        let participant_id = client.participant_id;
+
+        let room = self._get_room_by_id(&room_id)?;
+        let room = room.read();
+
+        room.set_viewport(&participant_id, tiles);
+
+        Ok(())
+    }
+
     pub fn set_e2ee_enabled(&self, client_id: &str, is_enabled: bool) -> Result<(), WebRTCError> {
         let client = self.get_client_by_id(client_id)?;
 
@@ -322,6 +516,7 @@ impl WebRTCManager {
         client_id: &str,
         is_enabled: bool,
         screen_track_id: Option<String>,
+        screen_audio_track_id: Option<String>,
     ) -> Result<(), WebRTCError> {
         let client = self.get_client_by_id(client_id)?;
 
@@ -333,7 +528,12 @@ impl WebRTCManager {
         let room = self._get_room_by_id(&room_id)?;
         let room = room.read();
 
-        room.set_screen_sharing(&participant_id, is_enabled, screen_track_id)?;
+        room.set_screen_sharing(
+            &participant_id,
+            is_enabled,
+            screen_track_id,
+            screen_audio_track_id,
+        )?;
 
         Ok(())
     }
@@ -354,6 +554,185 @@ impl WebRTCManager {
         Ok(())
     }
 
+    /// Drops the client's publisher, for an attendee of a room switched to a
+    /// live stream. The client stays in the room and keeps what it subscribed to.
+    pub fn unpublish(&self, client_id: &str) -> Result<(), WebRTCError> {
+        let client = self.get_client_by_id(client_id)?;
+
+        let room = self._get_room_by_id(&client.room_id)?;
+        let mut room = room.write();
+
+        room.unpublish(&client.participant_id);
+
+        Ok(())
+    }
+
+    pub fn set_stage_mode(&self, room_id: &str, is_enabled: bool) {
+        self._get_acl(room_id).set_stage_mode(is_enabled);
+
+        self._apply_forwarding_acl(room_id);
+    }
+
+    pub fn set_speaker(&self, room_id: &str, participant_id: &str, is_speaker: bool) {
+        self._get_acl(room_id)
+            .set_speaker(participant_id, is_speaker);
+
+        self._apply_forwarding_acl(room_id);
+    }
+
+    /// Takes effect on the streams already forwarded, mid-call
+    pub fn set_qos_class(&self, room_id: &str, participant_id: &str, class: QosClass) {
+        self._get_qos(room_id).set_class(participant_id, class);
+
+        // Like the ACL, kept for when the room comes to this node
+        if let Ok(room) = self._get_room_by_id(room_id) {
+            let room = room.read();
+            room.apply_qos();
+        }
+    }
+
+    pub fn set_recording_paused(&self, room_id: &str, is_paused: bool) {
+        // Publishers of a room may be spread across nodes, only the local ones are recorded here
+        if let Ok(room) = self._get_room_by_id(room_id) {
+            let room = room.read();
+            room.set_recording_paused(is_paused);
+        }
+    }
+
+    pub fn set_broadcast_cut(&self, room_id: &str, is_cut: bool) {
+        if let Ok(room) = self._get_room_by_id(room_id) {
+            let room = room.read();
+            room.set_broadcast_cut(is_cut);
+        }
+    }
+
+    /// Starts or ends the HLS stream of everyone publishing in the room, for a
+    /// room switched to or from a live stream mid-session. A node without
+    /// the room has nothing to do.
+    pub async fn set_egress(&self, room_id: &str, is_enabled: bool) -> Result<(), WebRTCError> {
+        let Ok(room) = self._get_room_by_id(room_id) else {
+            return Ok(());
+        };
+
+        let (medias, is_recording_paused, is_broadcast_cut) = {
+            let room = room.read();
+            (
+                room.medias(),
+                room.is_recording_paused(),
+                room.is_broadcast_cut(),
+            )
+        };
+
+        if !is_enabled {
+            for media in medias {
+                media.read().stop_hls_writer();
+            }
+            return Ok(());
+        }
+
+        // The host went live, the publishers' own egress takes over from the slate
+        self.stop_slate(room_id);
+
+        let mut result = Ok(());
+        for media in medias {
+            if let Err(err) = Media::start_hls_writer(&media).await {
+                warn!("Failed to start egress in {room_id}: {:?}", err);
+                result = Err(WebRTCError::FailedToStartEgress);
+                continue;
+            }
+
+            if is_recording_paused {
+                media.read().set_recording_paused(true);
+            }
+
+            if is_broadcast_cut {
+                media.read().set_broadcast_cut(true);
+            }
+        }
+
+        result
+    }
+
+    /// Rooms don't exist on a node before someone joins, the slate is kept apart from them
+    pub fn start_slate(&self, room_id: &str, slate_uri: Option<&str>) -> Result<(), WebRTCError> {
+        if self.slates.contains_key(room_id) {
+            return Ok(());
+        }
+
+        let slate = SlateWriter::new(SLATE_OUTPUT_DIR, room_id, slate_uri).map_err(|err| {
+            warn!("Failed to start slate for {room_id}: {:?}", err);
+            WebRTCError::FailedToStartSlate
+        })?;
+
+        self.slates.insert(room_id.to_owned(), slate);
+
+        Ok(())
+    }
+
+    pub fn stop_slate(&self, room_id: &str) {
+        if let Some((_, slate)) = self.slates.remove(room_id) {
+            slate.stop();
+        }
+    }
+
+    /// Manifests of the track archives this node wrote for the room, keyed by
+    /// participant. They stay on disk after the room ends, so this works for past rooms too.
+    pub fn get_recording_manifests(
+        &self,
+        room_id: &str,
+    ) -> Result<Vec<(String, String)>, WebRTCError> {
+        let Some(dir) = &self.configs.raw_recording_dir else {
+            return Ok(vec![]);
+        };
+
+        // The room id becomes a path segment, anything that could leave the directory is refused
+        if room_id.is_empty() || room_id.contains(['/', '\\']) || room_id.starts_with('.') {
+            return Err(WebRTCError::RoomNotFound);
+        }
+
+        let Ok(entries) = std::fs::read_dir(Path::new(dir).join(room_id)) else {
+            return Ok(vec![]);
+        };
+
+        let mut manifests = vec![];
+        for entry in entries.flatten() {
+            let path = entry.path().join("manifest.json");
+
+            match std::fs::read_to_string(&path) {
+                Ok(manifest) => {
+                    manifests.push((entry.file_name().to_string_lossy().to_string(), manifest))
+                }
+                Err(err) => warn!("Failed to read {}: {:?}", path.display(), err),
+            }
+        }
+
+        manifests.sort();
+
+        Ok(manifests)
+    }
+
+    fn _apply_forwarding_acl(&self, room_id: &str) {
+        // The room may not live on this node yet; the ACL is kept so it applies once it does
+        if let Ok(room) = self._get_room_by_id(room_id) {
+            let room = room.read();
+            room.apply_forwarding_acl();
+        }
+    }
+
+    fn _get_acl(&self, room_id: &str) -> Arc<ForwardingAcl> {
+        self.acls
+            .entry(room_id.to_owned())
+            .or_insert_with(|| Arc::new(ForwardingAcl::new()))
+            .clone()
+    }
+
+    fn _get_qos(&self, room_id: &str) -> Arc<QosPolicy> {
+        self.qos_policies
+            .entry(room_id.to_owned())
+            .or_insert_with(|| Arc::new(QosPolicy::new(Arc::clone(&self.configs.egress_meter))))
+            .clone()
+    }
+
     pub fn _add_client(&self, client_id: &str, info: WClient) {
         if !self.clients.contains_key(client_id) {
             self.clients.insert(client_id.to_string(), info);
@@ -365,7 +744,12 @@ impl WebRTCManager {
     }
 
     fn _add_room(&self, room_id: &str) -> Result<Arc<RwLock<Room>>, WebRTCError> {
-        let room_value = Arc::new(RwLock::new(Room::new(self.configs.clone())));
+        let room_value = Arc::new(RwLock::new(Room::new(
+            self.configs.clone(),
+            self._get_acl(room_id),
+            self._get_qos(room_id),
+            self.str0m_pool.clone(),
+        )));
 
         self.rooms
             .insert(room_id.to_string(), Arc::clone(&room_value));
@@ -373,6 +757,19 @@ impl WebRTCManager {
         Ok(room_value)
     }
 
+    /// Tears down a room nobody is left in, along with its ACL and QoS
+    /// policy. Another room of the same id starts out with fresh ones.
+    fn _remove_room(&self, room_id: &str) {
+        if self
+            .rooms
+            .remove_if(room_id, |_, room| room.read().is_empty())
+            .is_some()
+        {
+            self.acls.remove(room_id);
+            self.qos_policies.remove(room_id);
+        }
+    }
+
     pub fn get_client_by_id(&self, client_id: &str) -> Result<WClient, WebRTCError> {
         if let Some(client) = self.clients.get(client_id) {
             Ok(client.clone())
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS idx_matrix_rooms_room_id;
+DROP INDEX IF EXISTS idx_matrix_rooms_matrix_room_id;
+
+DROP TABLE IF EXISTS matrix_rooms;
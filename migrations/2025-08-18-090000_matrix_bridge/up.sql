@@ -0,0 +1,10 @@
+CREATE TABLE matrix_rooms (
+    id SERIAL PRIMARY KEY,
+    room_id INTEGER NOT NULL,
+    matrix_room_id VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (room_id) REFERENCES rooms(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_matrix_rooms_room_id ON matrix_rooms(room_id);
+CREATE UNIQUE INDEX idx_matrix_rooms_matrix_room_id ON matrix_rooms(matrix_room_id);
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS idx_bots_user_id;
+DROP INDEX IF EXISTS idx_bots_deleted_at;
+
+DROP TABLE IF EXISTS bots;
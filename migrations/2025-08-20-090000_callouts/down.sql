@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS idx_callouts_room_id;
+DROP INDEX IF EXISTS idx_callouts_created_at;
+
+DROP TABLE IF EXISTS callouts;
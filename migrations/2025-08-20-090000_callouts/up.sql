@@ -0,0 +1,17 @@
+CREATE TABLE callouts (
+    id SERIAL PRIMARY KEY,
+    room_id INTEGER NOT NULL,
+    requested_by_id INTEGER NOT NULL,
+    phone_number VARCHAR(32) NOT NULL,
+    provider VARCHAR(32) NOT NULL,
+    provider_call_id VARCHAR(255),
+    status VARCHAR(32) NOT NULL,
+    cost_cents INTEGER NOT NULL DEFAULT 0,
+    error VARCHAR(255),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (room_id) REFERENCES rooms(id) ON DELETE CASCADE,
+    FOREIGN KEY (requested_by_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_callouts_room_id ON callouts(room_id);
+CREATE INDEX idx_callouts_created_at ON callouts(created_at);
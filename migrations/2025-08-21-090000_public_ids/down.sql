@@ -0,0 +1,9 @@
+DROP INDEX IF EXISTS idx_users_public_id;
+DROP INDEX IF EXISTS idx_rooms_public_id;
+DROP INDEX IF EXISTS idx_participants_public_id;
+
+ALTER TABLE users DROP COLUMN IF EXISTS public_id;
+ALTER TABLE rooms DROP COLUMN IF EXISTS public_id;
+ALTER TABLE participants DROP COLUMN IF EXISTS public_id;
+
+DROP FUNCTION IF EXISTS generate_ulid();
@@ -0,0 +1,30 @@
+-- 48 bit millisecond timestamp followed by 80 random bits, Crockford base32 encoded
+CREATE OR REPLACE FUNCTION generate_ulid() RETURNS VARCHAR(26) AS $$
+DECLARE
+    encoding CONSTANT TEXT := '0123456789ABCDEFGHJKMNPQRSTVWXYZ';
+    millis BIGINT := (EXTRACT(EPOCH FROM clock_timestamp()) * 1000)::BIGINT;
+    ulid BYTEA;
+    bits BIT(130);
+    output TEXT := '';
+BEGIN
+    ulid := decode(lpad(to_hex(millis), 12, '0'), 'hex')
+        || substring(uuid_send(gen_random_uuid()) FROM 11 FOR 6)
+        || substring(uuid_send(gen_random_uuid()) FROM 1 FOR 4);
+
+    bits := B'00' || ('x' || encode(ulid, 'hex'))::BIT(128);
+
+    FOR i IN 0..25 LOOP
+        output := output || substring(encoding FROM substring(bits FROM i * 5 + 1 FOR 5)::INTEGER + 1 FOR 1);
+    END LOOP;
+
+    RETURN output;
+END
+$$ LANGUAGE plpgsql VOLATILE;
+
+ALTER TABLE users ADD COLUMN public_id VARCHAR(26) NOT NULL DEFAULT generate_ulid();
+ALTER TABLE rooms ADD COLUMN public_id VARCHAR(26) NOT NULL DEFAULT generate_ulid();
+ALTER TABLE participants ADD COLUMN public_id VARCHAR(26) NOT NULL DEFAULT generate_ulid();
+
+CREATE UNIQUE INDEX idx_users_public_id ON users(public_id);
+CREATE UNIQUE INDEX idx_rooms_public_id ON rooms(public_id);
+CREATE UNIQUE INDEX idx_participants_public_id ON participants(public_id);
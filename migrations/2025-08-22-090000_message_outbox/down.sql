@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_message_outbox_pending;
+
+DROP TABLE IF EXISTS message_outbox;
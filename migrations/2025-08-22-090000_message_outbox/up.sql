@@ -0,0 +1,12 @@
+CREATE TABLE message_outbox (
+    id SERIAL PRIMARY KEY,
+    message_id INTEGER NOT NULL,
+    event SMALLINT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    locked_until TIMESTAMP,
+    delivered_at TIMESTAMP,
+    FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE
+);
+
+-- The relay only ever scans what is still pending, in insertion order
+CREATE INDEX idx_message_outbox_pending ON message_outbox(id) WHERE delivered_at IS NULL;
@@ -0,0 +1,14 @@
+CREATE TABLE live_schedules (
+    id SERIAL PRIMARY KEY,
+    room_id INTEGER NOT NULL UNIQUE,
+    created_by_id INTEGER NOT NULL,
+    start_at TIMESTAMP NOT NULL,
+    slate_url VARCHAR(2048),
+    status VARCHAR(32) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (room_id) REFERENCES rooms(id) ON DELETE CASCADE,
+    FOREIGN KEY (created_by_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_live_schedules_due ON live_schedules(start_at) WHERE status = 'scheduled';
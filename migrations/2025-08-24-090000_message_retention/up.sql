@@ -0,0 +1 @@
+ALTER TABLE rooms ADD COLUMN message_retention_days INTEGER;
@@ -0,0 +1 @@
+ALTER TABLE rooms ADD COLUMN legal_hold_at TIMESTAMP;
@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS idx_participants_client_version;
+
+ALTER TABLE participants DROP COLUMN IF EXISTS network_type;
+ALTER TABLE participants DROP COLUMN IF EXISTS device_model;
+ALTER TABLE participants DROP COLUMN IF EXISTS client_platform;
+ALTER TABLE participants DROP COLUMN IF EXISTS client_version;
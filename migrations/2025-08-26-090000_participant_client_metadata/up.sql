@@ -0,0 +1,6 @@
+ALTER TABLE participants ADD COLUMN client_version VARCHAR(64);
+ALTER TABLE participants ADD COLUMN client_platform VARCHAR(64);
+ALTER TABLE participants ADD COLUMN device_model VARCHAR(64);
+ALTER TABLE participants ADD COLUMN network_type VARCHAR(64);
+
+CREATE INDEX IF NOT EXISTS idx_participants_client_version ON participants (client_version);
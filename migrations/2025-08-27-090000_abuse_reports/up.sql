@@ -0,0 +1,19 @@
+CREATE TABLE abuse_reports (
+    id SERIAL PRIMARY KEY,
+    room_id INTEGER NOT NULL,
+    reporter_id INTEGER NOT NULL,
+    target_id INTEGER NOT NULL,
+    reason VARCHAR(32) NOT NULL,
+    details VARCHAR(1000),
+    snapshot_url VARCHAR(2048),
+    status VARCHAR(32) NOT NULL,
+    resolution_note VARCHAR(1000),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    resolved_at TIMESTAMP,
+    FOREIGN KEY (room_id) REFERENCES rooms(id) ON DELETE CASCADE,
+    FOREIGN KEY (reporter_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (target_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_abuse_reports_queue ON abuse_reports(status, created_at);
+CREATE INDEX idx_abuse_reports_target ON abuse_reports(target_id);
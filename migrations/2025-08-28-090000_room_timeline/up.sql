@@ -0,0 +1,29 @@
+CREATE TABLE room_sessions (
+    id SERIAL PRIMARY KEY,
+    public_id VARCHAR(26) NOT NULL DEFAULT generate_ulid(),
+    room_id INTEGER NOT NULL,
+    started_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ended_at TIMESTAMP,
+    FOREIGN KEY (room_id) REFERENCES rooms(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_room_sessions_public_id ON room_sessions(public_id);
+-- A room has at most one session in progress
+CREATE UNIQUE INDEX idx_room_sessions_open ON room_sessions(room_id) WHERE ended_at IS NULL;
+CREATE INDEX idx_room_sessions_room ON room_sessions(room_id, started_at);
+
+CREATE TABLE room_events (
+    id SERIAL PRIMARY KEY,
+    session_id INTEGER NOT NULL,
+    kind VARCHAR(32) NOT NULL,
+    participant_id VARCHAR(26),
+    user_id INTEGER,
+    is_enabled BOOLEAN,
+    message_id INTEGER,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (session_id) REFERENCES room_sessions(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL,
+    FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_room_events_session ON room_events(session_id, created_at);
@@ -0,0 +1 @@
+ALTER TABLE rooms ADD COLUMN noise_suppression BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,2 @@
+ALTER TABLE rooms DROP COLUMN IF EXISTS org_id;
+DROP TABLE IF EXISTS organizations;
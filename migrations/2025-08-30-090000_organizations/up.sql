@@ -0,0 +1,18 @@
+CREATE TABLE organizations (
+    id SERIAL PRIMARY KEY,
+    public_id VARCHAR(26) NOT NULL DEFAULT generate_ulid(),
+    name VARCHAR(255) NOT NULL,
+    logo_url VARCHAR(500),
+    primary_color VARCHAR(7),
+    accent_color VARCHAR(7),
+    -- Waiting room backdrop, also the HLS slate while a broadcast is off air
+    background_url VARCHAR(500),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX idx_organizations_public_id ON organizations(public_id);
+
+ALTER TABLE rooms ADD COLUMN org_id INTEGER REFERENCES organizations(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_rooms_org_id ON rooms(org_id);
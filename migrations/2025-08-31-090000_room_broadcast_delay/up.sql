@@ -0,0 +1 @@
+ALTER TABLE rooms ADD COLUMN broadcast_delay_secs INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+-- Feature flags turned on for the organization's clients on top of the server's
+ALTER TABLE organizations ADD COLUMN client_features TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1 @@
+ALTER TABLE rooms DROP COLUMN IF EXISTS presenter_member_ids;
@@ -0,0 +1,2 @@
+-- Members whose video keeps its quality like the host's when the call runs short of bandwidth
+ALTER TABLE rooms ADD COLUMN presenter_member_ids INTEGER[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,2 @@
+-- How a live stream reaches its viewers, switched along with the room type mid-session
+ALTER TABLE rooms ADD COLUMN streaming_protocol SMALLINT NOT NULL DEFAULT 0;
@@ -0,0 +1,9 @@
+DROP INDEX IF EXISTS idx_calendar_events_account_id;
+DROP INDEX IF EXISTS idx_calendar_events_schedule_account;
+
+DROP TABLE IF EXISTS calendar_events;
+
+DROP INDEX IF EXISTS idx_calendar_accounts_channel_id;
+DROP INDEX IF EXISTS idx_calendar_accounts_user_provider;
+
+DROP TABLE IF EXISTS calendar_accounts;
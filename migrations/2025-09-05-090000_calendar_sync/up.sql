@@ -0,0 +1,31 @@
+CREATE TABLE calendar_accounts (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    provider VARCHAR(32) NOT NULL,
+    access_token TEXT NOT NULL,
+    refresh_token TEXT NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    channel_id VARCHAR(255),
+    channel_token VARCHAR(64) NOT NULL,
+    channel_expires_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_calendar_accounts_user_provider ON calendar_accounts(user_id, provider);
+CREATE UNIQUE INDEX idx_calendar_accounts_channel_id ON calendar_accounts(channel_id);
+
+CREATE TABLE calendar_events (
+    id SERIAL PRIMARY KEY,
+    live_schedule_id INTEGER NOT NULL,
+    calendar_account_id INTEGER NOT NULL,
+    provider_event_id VARCHAR(1024) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (live_schedule_id) REFERENCES live_schedules(id) ON DELETE CASCADE,
+    FOREIGN KEY (calendar_account_id) REFERENCES calendar_accounts(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_calendar_events_schedule_account ON calendar_events(live_schedule_id, calendar_account_id);
+CREATE INDEX idx_calendar_events_account_id ON calendar_events(calendar_account_id);
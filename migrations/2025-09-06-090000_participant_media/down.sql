@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS participants_detached_at_idx;
+ALTER TABLE participants DROP COLUMN IF EXISTS detached_at;
+ALTER TABLE participants DROP COLUMN IF EXISTS is_hand_raising;
+ALTER TABLE participants DROP COLUMN IF EXISTS is_video_enabled;
+ALTER TABLE participants DROP COLUMN IF EXISTS is_audio_enabled;
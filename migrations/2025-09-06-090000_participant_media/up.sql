@@ -0,0 +1,7 @@
+-- What a participant had switched on, a participant failed over from a dead node gets it back
+ALTER TABLE participants ADD COLUMN is_audio_enabled BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE participants ADD COLUMN is_video_enabled BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE participants ADD COLUMN is_hand_raising BOOLEAN NOT NULL DEFAULT FALSE;
+-- Set while the participant's node is gone, participants who never rejoin are purged after a while
+ALTER TABLE participants ADD COLUMN detached_at TIMESTAMP;
+CREATE INDEX participants_detached_at_idx ON participants (detached_at) WHERE detached_at IS NOT NULL;
@@ -0,0 +1 @@
+DROP INDEX IF EXISTS idx_callouts_requested_by_id_created_at;
@@ -0,0 +1 @@
+CREATE INDEX idx_callouts_requested_by_id_created_at ON callouts(requested_by_id, created_at);
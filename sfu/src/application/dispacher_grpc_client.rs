@@ -2,7 +2,8 @@ use tonic::{Request, Status, transport::Channel};
 use tracing::warn;
 use waterbus_proto::{
     NewUserJoinedRequest, PublisherCandidateRequest, SubscriberCandidateRequest,
-    SubscriberRenegotiateRequest, dispatcher_service_client::DispatcherServiceClient,
+    SubscriberDownlinkStarvedRequest, SubscriberRenegotiateRequest,
+    dispatcher_service_client::DispatcherServiceClient,
 };
 
 #[derive(Debug, Clone, Default)]
@@ -95,4 +96,23 @@ impl DispatcherGrpcClient {
                 e
             })
     }
+
+    pub async fn on_subscriber_downlink_starved(
+        &self,
+        req: SubscriberDownlinkStarvedRequest,
+    ) -> Result<(), Status> {
+        let mut client = self
+            .get_client()
+            .await
+            .map_err(|e| Status::unavailable(format!("Failed to connect to dispatcher: {e}")))?;
+
+        client
+            .on_subscriber_downlink_starved(Request::new(req))
+            .await
+            .map(|_| ())
+            .map_err(|e| {
+                warn!("Error sending on_subscriber_downlink_starved: {:?}", e);
+                e
+            })
+    }
 }
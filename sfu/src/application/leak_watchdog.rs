@@ -0,0 +1,145 @@
+use std::{sync::Arc, time::Duration};
+
+use parking_lot::RwLock;
+use tracing::{info, warn};
+use webrtc_manager::{models::diagnostics::NodeDiagnostics, webrtc_manager::WebRTCManager};
+
+/// Idle checks to wait before judging, closing rooms tear down in the background
+const SETTLE_CHECKS: u32 = 2;
+
+/// Tasks alive on the runtime this is called from
+pub fn alive_tasks() -> usize {
+    tokio::runtime::Handle::current()
+        .metrics()
+        .num_alive_tasks()
+}
+
+/// Warns when a node that has no rooms left still holds room objects, or runs
+/// more tokio tasks than it did when it was idle before.
+pub struct LeakWatchdog;
+
+impl LeakWatchdog {
+    pub fn spawn(webrtc_manager: Arc<RwLock<WebRTCManager>>, interval: Duration) {
+        info!("Leak watchdog checks every {:?}", interval);
+
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(interval);
+            let mut check = LeakCheck::default();
+
+            loop {
+                tick.tick().await;
+
+                let diagnostics = webrtc_manager.read().diagnostics();
+                let tasks = alive_tasks();
+
+                if let Some(baseline) = check.observe(&diagnostics, tasks) {
+                    warn!(
+                        "[leak_watchdog] no rooms left but still alive: {:?}, str0m subscribers: {}, tokio tasks: {} (idle baseline {})",
+                        diagnostics.live, diagnostics.str0m_subscribers, tasks, baseline
+                    );
+                }
+            }
+        });
+    }
+}
+
+#[derive(Debug, Default)]
+struct LeakCheck {
+    idle_checks: u32,
+    baseline_tasks: Option<usize>,
+    has_warned: bool,
+}
+
+impl LeakCheck {
+    /// The idle task baseline when this check should warn. Once per idle
+    /// stretch, the counts are on the diagnostics endpoint meanwhile.
+    fn observe(&mut self, diagnostics: &NodeDiagnostics, tasks: usize) -> Option<usize> {
+        if diagnostics.reachable.rooms > 0 || diagnostics.clients > 0 {
+            self.idle_checks = 0;
+            self.has_warned = false;
+            return None;
+        }
+
+        self.idle_checks += 1;
+        if self.idle_checks < SETTLE_CHECKS {
+            return None;
+        }
+
+        let baseline = *self.baseline_tasks.get_or_insert(tasks);
+        let is_leaking =
+            !diagnostics.live.is_empty() || diagnostics.str0m_subscribers > 0 || tasks > baseline;
+
+        if !is_leaking {
+            self.baseline_tasks = Some(baseline.min(tasks));
+            return None;
+        }
+
+        if self.has_warned {
+            return None;
+        }
+        self.has_warned = true;
+
+        Some(baseline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use webrtc_manager::models::diagnostics::ObjectCounts;
+
+    use super::*;
+
+    fn idle() -> NodeDiagnostics {
+        NodeDiagnostics::default()
+    }
+
+    fn busy() -> NodeDiagnostics {
+        NodeDiagnostics {
+            clients: 1,
+            ..Default::default()
+        }
+    }
+
+    fn leaking() -> NodeDiagnostics {
+        NodeDiagnostics {
+            live: ObjectCounts {
+                tracks: 2,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_waits_for_rooms_to_settle() {
+        let mut check = LeakCheck::default();
+
+        assert_eq!(check.observe(&leaking(), 10), None);
+        assert_eq!(check.observe(&leaking(), 10), Some(10));
+    }
+
+    #[test]
+    fn test_warns_once_per_idle_stretch() {
+        let mut check = LeakCheck::default();
+        for _ in 0..SETTLE_CHECKS {
+            check.observe(&leaking(), 10);
+        }
+        assert_eq!(check.observe(&leaking(), 10), None);
+
+        check.observe(&busy(), 30);
+        assert_eq!(check.observe(&leaking(), 10), None);
+        assert_eq!(check.observe(&leaking(), 10), Some(10));
+    }
+
+    #[test]
+    fn test_tasks_above_the_idle_baseline() {
+        let mut check = LeakCheck::default();
+        for _ in 0..SETTLE_CHECKS {
+            assert_eq!(check.observe(&idle(), 12), None);
+        }
+
+        // The baseline follows the quietest idle check
+        assert_eq!(check.observe(&idle(), 8), None);
+        assert_eq!(check.observe(&idle(), 9), Some(8));
+    }
+}
@@ -1,2 +1,3 @@
 pub mod dispacher_grpc_client;
+pub mod leak_watchdog;
 pub mod sfu_grpc_service;
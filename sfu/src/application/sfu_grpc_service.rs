@@ -1,28 +1,39 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use parking_lot::RwLock;
 use tokio::sync::Mutex;
 use tonic::{Request, Response, Status};
 use waterbus_proto::{
-    AddPublisherCandidateRequest, AddSubscriberCandidateRequest, JoinRoomRequest, JoinRoomResponse,
-    LeaveRoomRequest, LeaveRoomResponse, MigratePublisherRequest, MigratePublisherResponse,
-    NewUserJoinedRequest, PublisherCandidateRequest, PublisherRenegotiationRequest,
-    PublisherRenegotiationResponse, SetCameraType, SetEnabledRequest, SetScreenSharingRequest,
-    SetSubscriberSdpRequest, StatusResponse, SubscribeRequest, SubscribeResponse,
-    SubscriberCandidateRequest, SubscriberRenegotiateRequest, sfu_service_server::SfuService,
+    AddPublisherCandidateRequest, AddSubscriberCandidateRequest, GetDiagnosticsRequest,
+    GetDiagnosticsResponse, GetRecordingManifestsRequest, GetRecordingManifestsResponse,
+    GetRoomStatsRequest, GetRoomStatsResponse, JoinRoomRequest, JoinRoomResponse, LeaveRoomRequest,
+    LeaveRoomResponse, MigratePublisherRequest, MigratePublisherResponse, NewUserJoinedRequest,
+    PublisherCandidateRequest, PublisherRenegotiationRequest, PublisherRenegotiationResponse,
+    RecordingManifest, SetBroadcastCutRequest, SetCameraType, SetEgressRequest, SetEnabledRequest,
+    SetQosClassRequest, SetRecordingPausedRequest, SetScreenSharingRequest, SetSpeakerRequest,
+    SetStageModeRequest, SetSubscriberSdpRequest, SetViewportRequest, StartSlateRequest,
+    StatusResponse, StopSlateRequest, SubscribeRequest, SubscribeResponse,
+    SubscriberCandidateRequest, SubscriberDownlinkStarvedRequest, SubscriberRenegotiateRequest,
+    UnpublishRequest, sfu_service_server::SfuService,
 };
 use webrtc_manager::{
+    errors::WebRTCError,
     models::{
         connection_type::ConnectionType,
+        diagnostics::ObjectCounts,
         params::{
-            IceCandidate, IceCandidateCallback, JoinedCallback, RenegotiationCallback,
-            WebRTCManagerConfigs,
+            DownlinkStarvedCallback, IceCandidate, IceCandidateCallback, JoinedCallback,
+            RenegotiationCallback, WebRTCManagerConfigs,
         },
+        qos::QosClass,
+        quality::TrackQuality,
+        room_stats::PublisherStats,
+        track_info::{PublishedTrack, TrackInfo},
     },
     webrtc_manager::{JoinRoomReq, WebRTCManager},
 };
 
-use super::dispacher_grpc_client::DispatcherGrpcClient;
+use super::{dispacher_grpc_client::DispatcherGrpcClient, leak_watchdog::alive_tasks};
 
 pub struct SfuGrpcService {
     webrtc_manager: Arc<RwLock<WebRTCManager>>,
@@ -44,6 +55,10 @@ impl SfuGrpcService {
             node_id,
         }
     }
+
+    pub fn webrtc_manager(&self) -> Arc<RwLock<WebRTCManager>> {
+        Arc::clone(&self.webrtc_manager)
+    }
 }
 
 #[tonic::async_trait]
@@ -120,7 +135,16 @@ impl SfuService for SfuGrpcService {
                         is_audio_enabled: req.is_audio_enabled,
                         is_e2ee_enabled: req.is_e2ee_enabled,
                         total_tracks: req.total_tracks as u8,
+                        tracks: to_track_infos(req.tracks),
                         connection_type: req.connection_type as u8,
+                        start_egress: req.start_egress,
+                        noise_suppression: req.noise_suppression,
+                        broadcast_delay: Duration::from_millis(req.broadcast_delay_ms as u64),
+                        qos_class: QosClass::from(req.qos_class),
+                        is_stage_mode: req.is_stage_mode,
+                        speaker_ids: req.speaker_ids,
+                        is_recording_paused: req.is_recording_paused,
+                        is_broadcast_cut: req.is_broadcast_cut,
                         callback: joined_callback,
                         ice_candidate_callback,
                     })
@@ -203,6 +227,29 @@ impl SfuService for SfuGrpcService {
             })
         });
 
+        let dispatcher = Arc::clone(&self.dispatcher_grpc_client);
+        let client_id = req.client_id.clone();
+        let participant_id = req.participant_id.clone();
+        let room_id = req.room_id.clone();
+        let downlink_starved_callback: DownlinkStarvedCallback = Arc::new(move || {
+            let dispatcher = Arc::clone(&dispatcher);
+            let client_id = client_id.clone();
+            let participant_id = participant_id.clone();
+            let room_id = room_id.clone();
+
+            Box::pin(async move {
+                let dispatcher = dispatcher.lock().await;
+
+                let _ = dispatcher
+                    .on_subscriber_downlink_starved(SubscriberDownlinkStarvedRequest {
+                        client_id,
+                        participant_id,
+                        room_id,
+                    })
+                    .await;
+            })
+        });
+
         let webrtc_manager = self.webrtc_manager.clone();
 
         let response = tokio::task::spawn_blocking(move || {
@@ -215,8 +262,11 @@ impl SfuService for SfuGrpcService {
                         &req.target_id,
                         &req.participant_id,
                         &req.room_id,
+                        req.is_observer,
                         renegotiation_callback,
                         ice_candidate_callback,
+                        downlink_starved_callback,
+                        req.video_codecs,
                     )
                     .await
             })
@@ -231,14 +281,24 @@ impl SfuService for SfuGrpcService {
                     camera_type: response.camera_type as u32,
                     video_enabled: response.video_enabled,
                     audio_enabled: response.audio_enabled,
+                    is_video_hardware_muted: response.is_video_hardware_muted,
+                    is_audio_hardware_muted: response.is_audio_hardware_muted,
                     is_screen_sharing: response.is_screen_sharing,
                     is_hand_raising: response.is_hand_raising,
                     is_e2ee_enabled: response.is_e2ee_enabled,
                     video_codec: response.video_codec,
                     screen_track_id: response.screen_track_id,
+                    screen_audio_track_id: response.screen_audio_track_id,
+                    tracks: response.tracks.into_iter().map(to_proto_track).collect(),
+                    codec_mismatch: String::new(),
                 };
                 Ok(Response::new(subscribe_response))
             }
+            // Not a failure of the node, the client is told what to fall back to
+            Err(WebRTCError::CodecMismatch(codec)) => Ok(Response::new(SubscribeResponse {
+                codec_mismatch: codec,
+                ..Default::default()
+            })),
             Err(err) => Err(Status::internal(format!("Failed to join room: {err}"))),
         }
     }
@@ -249,9 +309,19 @@ impl SfuService for SfuGrpcService {
     ) -> Result<Response<StatusResponse>, Status> {
         let req = req.into_inner();
 
-        let writer = self.webrtc_manager.read();
+        let webrtc_manager = self.webrtc_manager.clone();
+
+        let response = tokio::task::spawn_blocking(move || {
+            let writer = webrtc_manager.read();
 
-        let response = writer.set_subscriber_desc(&req.client_id, &req.target_id, &req.sdp);
+            tokio::runtime::Handle::current().block_on(async {
+                writer
+                    .set_subscriber_desc(&req.client_id, &req.target_id, &req.sdp)
+                    .await
+            })
+        })
+        .await
+        .map_err(|e| Status::internal(format!("Task join error: {e}")))?;
 
         match response {
             Ok(()) => {
@@ -275,12 +345,13 @@ impl SfuService for SfuGrpcService {
             let webrtc_manager = self.webrtc_manager.clone();
             let client_id = req.client_id.clone();
             let sdp = req.sdp.clone();
+            let tracks = to_track_infos(req.tracks);
 
             move || {
                 let writer = webrtc_manager.read();
 
                 tokio::runtime::Handle::current()
-                    .block_on(writer.handle_publisher_renegotiation(&client_id, &sdp))
+                    .block_on(writer.handle_publisher_renegotiation(&client_id, &sdp, tracks))
             }
         })
         .await
@@ -411,7 +482,8 @@ impl SfuService for SfuGrpcService {
 
         let writer = self.webrtc_manager.read();
 
-        let response = writer.set_video_enabled(&req.client_id, req.is_enabled);
+        let response =
+            writer.set_video_enabled(&req.client_id, req.is_enabled, req.is_hardware_muted);
 
         match response {
             Ok(()) => Ok(Response::new(StatusResponse { is_success: true })),
@@ -429,7 +501,8 @@ impl SfuService for SfuGrpcService {
 
         let writer = self.webrtc_manager.read();
 
-        let response = writer.set_audio_enabled(&req.client_id, req.is_enabled);
+        let response =
+            writer.set_audio_enabled(&req.client_id, req.is_enabled, req.is_hardware_muted);
 
         match response {
             Ok(()) => Ok(Response::new(StatusResponse { is_success: true })),
@@ -465,8 +538,12 @@ impl SfuService for SfuGrpcService {
 
         let writer = self.webrtc_manager.write();
 
-        let response =
-            writer.set_screen_sharing(&req.client_id, req.is_enabled, req.screen_track_id);
+        let response = writer.set_screen_sharing(
+            &req.client_id,
+            req.is_enabled,
+            req.screen_track_id,
+            req.screen_audio_track_id,
+        );
 
         match response {
             Ok(()) => Ok(Response::new(StatusResponse { is_success: true })),
@@ -493,4 +570,281 @@ impl SfuService for SfuGrpcService {
             ))),
         }
     }
+
+    async fn set_viewport(
+        &self,
+        req: Request<SetViewportRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        let req = req.into_inner();
+
+        let tiles: HashMap<String, TrackQuality> = req
+            .tiles
+            .into_iter()
+            .map(|tile| {
+                let quality = TrackQuality::for_viewport(tile.width, tile.height);
+                (tile.participant_id, quality)
+            })
+            .collect();
+
+        let writer = self.webrtc_manager.write();
+
+        let response = writer.set_viewport(&req.client_id, &tiles);
+
+        match response {
+            Ok(()) => Ok(Response::new(StatusResponse { is_success: true })),
+            Err(err) => Err(Status::internal(format!("Failed to set viewport: {err}"))),
+        }
+    }
+
+    async fn set_stage_mode(
+        &self,
+        req: Request<SetStageModeRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        let req = req.into_inner();
+
+        let writer = self.webrtc_manager.read();
+
+        writer.set_stage_mode(&req.room_id, req.is_enabled);
+
+        Ok(Response::new(StatusResponse { is_success: true }))
+    }
+
+    async fn set_speaker(
+        &self,
+        req: Request<SetSpeakerRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        let req = req.into_inner();
+
+        let writer = self.webrtc_manager.read();
+
+        writer.set_speaker(&req.room_id, &req.participant_id, req.is_speaker);
+
+        Ok(Response::new(StatusResponse { is_success: true }))
+    }
+
+    async fn set_qos_class(
+        &self,
+        req: Request<SetQosClassRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        let req = req.into_inner();
+
+        let writer = self.webrtc_manager.read();
+
+        writer.set_qos_class(
+            &req.room_id,
+            &req.participant_id,
+            QosClass::from(req.qos_class),
+        );
+
+        Ok(Response::new(StatusResponse { is_success: true }))
+    }
+
+    async fn set_recording_paused(
+        &self,
+        req: Request<SetRecordingPausedRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        let req = req.into_inner();
+
+        let writer = self.webrtc_manager.read();
+
+        writer.set_recording_paused(&req.room_id, req.is_paused);
+
+        Ok(Response::new(StatusResponse { is_success: true }))
+    }
+
+    async fn set_broadcast_cut(
+        &self,
+        req: Request<SetBroadcastCutRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        let req = req.into_inner();
+
+        let writer = self.webrtc_manager.read();
+
+        writer.set_broadcast_cut(&req.room_id, req.is_cut);
+
+        Ok(Response::new(StatusResponse { is_success: true }))
+    }
+
+    async fn set_egress(
+        &self,
+        req: Request<SetEgressRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        let req = req.into_inner();
+
+        let webrtc_manager = self.webrtc_manager.clone();
+        let response = tokio::task::spawn_blocking(move || {
+            let writer = webrtc_manager.read();
+
+            tokio::runtime::Handle::current()
+                .block_on(writer.set_egress(&req.room_id, req.is_enabled))
+        })
+        .await
+        .map_err(|e| Status::internal(format!("Task join error: {e}")))?;
+
+        match response {
+            Ok(()) => Ok(Response::new(StatusResponse { is_success: true })),
+            Err(err) => Err(Status::internal(format!("Failed to set egress: {err}"))),
+        }
+    }
+
+    async fn unpublish(
+        &self,
+        req: Request<UnpublishRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        let req = req.into_inner();
+
+        let writer = self.webrtc_manager.write();
+
+        let response = writer.unpublish(&req.client_id);
+
+        match response {
+            Ok(()) => Ok(Response::new(StatusResponse { is_success: true })),
+            Err(err) => Err(Status::internal(format!("Failed to unpublish: {err}"))),
+        }
+    }
+
+    async fn start_slate(
+        &self,
+        req: Request<StartSlateRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        let req = req.into_inner();
+
+        let slate_uri = Some(req.slate_uri).filter(|uri| !uri.is_empty());
+
+        let writer = self.webrtc_manager.read();
+
+        match writer.start_slate(&req.room_id, slate_uri.as_deref()) {
+            Ok(_) => Ok(Response::new(StatusResponse { is_success: true })),
+            Err(err) => Err(Status::internal(format!("Failed to start slate: {err}"))),
+        }
+    }
+
+    async fn stop_slate(
+        &self,
+        req: Request<StopSlateRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        let req = req.into_inner();
+
+        let writer = self.webrtc_manager.read();
+
+        writer.stop_slate(&req.room_id);
+
+        Ok(Response::new(StatusResponse { is_success: true }))
+    }
+
+    async fn get_recording_manifests(
+        &self,
+        req: Request<GetRecordingManifestsRequest>,
+    ) -> Result<Response<GetRecordingManifestsResponse>, Status> {
+        let req = req.into_inner();
+
+        let reader = self.webrtc_manager.read();
+
+        match reader.get_recording_manifests(&req.room_id) {
+            Ok(manifests) => Ok(Response::new(GetRecordingManifestsResponse {
+                manifests: manifests
+                    .into_iter()
+                    .map(|(participant_id, manifest_json)| RecordingManifest {
+                        participant_id,
+                        manifest_json,
+                    })
+                    .collect(),
+            })),
+            Err(err) => Err(Status::invalid_argument(format!(
+                "Failed to read recording manifests: {err}"
+            ))),
+        }
+    }
+
+    async fn get_diagnostics(
+        &self,
+        _req: Request<GetDiagnosticsRequest>,
+    ) -> Result<Response<GetDiagnosticsResponse>, Status> {
+        let diagnostics = self.webrtc_manager.read().diagnostics();
+
+        Ok(Response::new(GetDiagnosticsResponse {
+            clients: diagnostics.clients as u64,
+            reachable: Some(to_proto_counts(diagnostics.reachable)),
+            live: Some(to_proto_counts(diagnostics.live)),
+            str0m_subscribers: diagnostics.str0m_subscribers as u64,
+            forwarding_channels: diagnostics.forwarding_channels as u64,
+            tokio_tasks: alive_tasks() as u64,
+        }))
+    }
+
+    async fn get_room_stats(
+        &self,
+        req: Request<GetRoomStatsRequest>,
+    ) -> Result<Response<GetRoomStatsResponse>, Status> {
+        let req = req.into_inner();
+
+        let (stats, participant_ids) = {
+            let webrtc_manager = self.webrtc_manager.read();
+            (
+                webrtc_manager.room_stats(&req.room_id),
+                webrtc_manager.room_participants(&req.room_id),
+            )
+        };
+
+        let stats = match stats {
+            Ok(stats) => stats,
+            Err(WebRTCError::RoomNotFound) => return Ok(Response::new(Default::default())),
+            Err(err) => {
+                return Err(Status::internal(format!("Failed to get room stats: {err}")));
+            }
+        };
+
+        Ok(Response::new(GetRoomStatsResponse {
+            publishers: stats
+                .publishers
+                .into_iter()
+                .map(to_proto_publisher_stats)
+                .collect(),
+            subscribers: stats.subscribers as u64,
+            egress_bps: stats.egress_bps,
+            is_recording: stats.is_recording,
+            is_recording_paused: stats.is_recording_paused,
+            participant_ids,
+        }))
+    }
+}
+
+fn to_proto_publisher_stats(stats: PublisherStats) -> waterbus_proto::PublisherStats {
+    waterbus_proto::PublisherStats {
+        participant_id: stats.participant_id,
+        tracks: stats.tracks as u64,
+        subscribers: stats.subscribers as u64,
+        egress_bps: stats.egress_bps,
+        talk_time_ms: stats.talk_time_ms,
+    }
+}
+
+fn to_track_infos(tracks: Vec<waterbus_proto::TrackInfo>) -> Vec<TrackInfo> {
+    tracks
+        .into_iter()
+        .map(|track| TrackInfo {
+            track_id: track.track_id,
+            source: (track.source as u8).into(),
+            label: Some(track.label).filter(|label| !label.is_empty()),
+        })
+        .collect()
+}
+
+fn to_proto_counts(counts: ObjectCounts) -> waterbus_proto::ObjectCounts {
+    waterbus_proto::ObjectCounts {
+        rooms: counts.rooms as u64,
+        publishers: counts.publishers as u64,
+        subscribers: counts.subscribers as u64,
+        tracks: counts.tracks as u64,
+        forward_tracks: counts.forward_tracks as u64,
+    }
+}
+
+fn to_proto_track(track: PublishedTrack) -> waterbus_proto::TrackInfo {
+    waterbus_proto::TrackInfo {
+        track_id: track.track_id,
+        source: u8::from(track.source) as i32,
+        label: track.label,
+        kind: track.kind,
+    }
 }
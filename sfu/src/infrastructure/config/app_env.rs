@@ -1,15 +1,28 @@
 use dotenvy::dotenv;
 use nanoid::nanoid;
-use std::env;
+use std::{env, time::Duration};
+use webrtc_manager::models::subscriber_backend::SubscriberBackend;
 
 #[derive(Debug, Clone)]
 pub struct AppEnv {
     pub group_id: String,
+    pub node_tags: Vec<String>,
     pub public_ip: String,
     pub node_id: String,
     pub etcd_addr: String,
     pub grpc_configs: GrpcConfigs,
     pub udp_port_range: UdpPortRange,
+    pub subscriber_backend: SubscriberBackend,
+    pub rtp_buffer_pool_size: usize,
+    pub pacing_multiplier: f64,
+    pub hls_fallback_min_bps: f64,
+    pub raw_recording_dir: Option<String>,
+    /// Microphones the node denoises at once, 0 leaves them all unprocessed
+    pub noise_suppression_max_streams: usize,
+    /// Video the node may forward before attendees are degraded, 0 for no limit
+    pub node_egress_budget_bps: f64,
+    /// Off unless set, meant for soak tests
+    pub leak_watchdog_interval: Option<Duration>,
 }
 
 #[derive(Debug, Clone)]
@@ -38,6 +51,12 @@ impl AppEnv {
 
         Self {
             group_id: env::var("GROUP_ID").unwrap_or_else(|_| "waterbus-group-1".to_string()),
+            node_tags: env::var("NODE_TAGS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|tag| tag.trim().to_lowercase())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
             public_ip: env::var("PUBLIC_IP").unwrap_or_else(|_| "".to_string()),
             node_id: Self::get_node_id(),
             etcd_addr: env::var("ETCD_URI").expect("ETCD_URI must be set"),
@@ -45,6 +64,19 @@ impl AppEnv {
                 port_min: Self::get_env("PORT_MIN_UDP", 19200),
                 port_max: Self::get_env("PORT_MAX_UDP", 19250),
             },
+            subscriber_backend: Self::get_subscriber_backend(),
+            rtp_buffer_pool_size: Self::get_env("RTP_BUFFER_POOL_SIZE", 1024) as usize,
+            pacing_multiplier: Self::get_f64_env("PACING_RATE_MULTIPLIER", 2.5),
+            hls_fallback_min_bps: Self::get_f64_env("HLS_FALLBACK_MIN_BITRATE", 150_000.0),
+            raw_recording_dir: env::var("RAW_RECORDING_DIR")
+                .ok()
+                .filter(|dir| !dir.is_empty()),
+            noise_suppression_max_streams: Self::get_env("NOISE_SUPPRESSION_MAX_STREAMS", 32)
+                as usize,
+            node_egress_budget_bps: Self::get_f64_env("NODE_EGRESS_BUDGET_MBPS", 0.0) * 1_000_000.0,
+            leak_watchdog_interval: Some(Self::get_env("LEAK_WATCHDOG_INTERVAL_SECS", 0))
+                .filter(|secs| *secs > 0)
+                .map(|secs| Duration::from_secs(secs.into())),
             grpc_configs: GrpcConfigs {
                 sfu_host: Self::get_str_env("SFU_HOST", "http://[::1]".to_owned()),
                 sfu_port: Self::get_env("SFU_PORT", 50051),
@@ -61,6 +93,13 @@ impl AppEnv {
             .unwrap_or(default)
     }
 
+    fn get_f64_env(var: &str, default: f64) -> f64 {
+        env::var(var)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+    }
+
     fn get_str_env(var: &str, default: String) -> String {
         env::var(var)
             .ok()
@@ -68,6 +107,18 @@ impl AppEnv {
             .unwrap_or(default)
     }
 
+    /// `webrtc-rs` (default) or the experimental `str0m` subscriber pool, whose
+    /// sockets must sit outside the webrtc-rs UDP port range
+    fn get_subscriber_backend() -> SubscriberBackend {
+        match Self::get_str_env("SUBSCRIBER_BACKEND", "webrtc-rs".to_owned()).as_str() {
+            "str0m" => SubscriberBackend::Str0m {
+                port: Self::get_env("STR0M_PORT_UDP", 18900),
+                workers: Self::get_env("STR0M_WORKERS", 2),
+            },
+            _ => SubscriberBackend::WebRtcRs,
+        }
+    }
+
     fn get_node_id() -> String {
         env::var("POD_ID")
             .ok()
@@ -1,9 +1,10 @@
 use etcd_client::{Client, PutOptions};
 use serde::Serialize;
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 use sysinfo::System;
 use tokio::{sync::oneshot, time::interval};
 use tracing::{debug, error, info};
+use webrtc_manager::utils::buffer_pool::{RtpBufferPool, RtpBufferPoolStats};
 
 #[derive(Debug, Serialize)]
 struct NodeMetadata {
@@ -11,6 +12,11 @@ struct NodeMetadata {
     cpu: f32,
     ram: f32,
     group_id: String,
+    // The dispatcher sends canary rooms to nodes tagged `canary`
+    tags: Vec<String>,
+    // Lets dashboards compare cpu and ram between subscriber backends
+    subscriber_backend: &'static str,
+    rtp_buffer_pool: RtpBufferPoolStats,
 }
 
 pub struct EtcdNode {
@@ -20,11 +26,15 @@ pub struct EtcdNode {
 }
 
 impl EtcdNode {
+    #[allow(clippy::too_many_arguments)]
     pub async fn register(
         etcd_addr: String,
         node_id: String,
         node_ip: String,
         group_id: String,
+        tags: Vec<String>,
+        subscriber_backend: &'static str,
+        rtp_buffer_pool: Arc<RtpBufferPool>,
         ttl: i64,
     ) -> anyhow::Result<Self> {
         let mut client = Client::connect([etcd_addr], None).await?;
@@ -36,6 +46,9 @@ impl EtcdNode {
             cpu: 0.0,
             ram: 0.0,
             group_id: group_id.clone(),
+            tags: tags.clone(),
+            subscriber_backend,
+            rtp_buffer_pool: rtp_buffer_pool.stats(),
         };
         let value = serde_json::to_string(&metadata)?;
 
@@ -81,6 +94,9 @@ impl EtcdNode {
                             cpu: cpu_free,
                             ram: ram_free,
                             group_id: group_id.clone(),
+                            tags: tags.clone(),
+                            subscriber_backend,
+                            rtp_buffer_pool: rtp_buffer_pool.stats(),
                         };
 
                         let new_value = serde_json::to_string(&updated_metadata).unwrap();
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use tokio::sync::Mutex;
 use tonic::transport::Server;
@@ -7,7 +7,8 @@ use waterbus_proto::sfu_service_server::SfuServiceServer;
 use webrtc_manager::models::params::WebRTCManagerConfigs;
 
 use crate::application::{
-    dispacher_grpc_client::DispatcherGrpcClient, sfu_grpc_service::SfuGrpcService,
+    dispacher_grpc_client::DispatcherGrpcClient, leak_watchdog::LeakWatchdog,
+    sfu_grpc_service::SfuGrpcService,
 };
 
 pub struct GrpcServer {}
@@ -19,11 +20,20 @@ impl GrpcServer {
         dispatcher_port: u16,
         configs: WebRTCManagerConfigs,
         node_id: String,
+        leak_watchdog_interval: Option<Duration>,
     ) {
         info!("GrpcServer is running on port: {}", port);
 
         tokio::spawn(async move {
-            match Self::start_server(port, dispatcher_host, dispatcher_port, configs, node_id).await
+            match Self::start_server(
+                port,
+                dispatcher_host,
+                dispatcher_port,
+                configs,
+                node_id,
+                leak_watchdog_interval,
+            )
+            .await
             {
                 Ok(_) => info!("GrpcServer stopped successfully"),
                 Err(e) => info!("GrpcServer stopped with an error: {:?}", e),
@@ -37,6 +47,7 @@ impl GrpcServer {
         dispatcher_port: u16,
         configs: WebRTCManagerConfigs,
         node_id: String,
+        leak_watchdog_interval: Option<Duration>,
     ) -> anyhow::Result<()> {
         let addr = format!("0.0.0.0:{port}").parse().unwrap();
 
@@ -47,6 +58,10 @@ impl GrpcServer {
 
         let sfu_grpc_service = SfuGrpcService::new(configs, dispatcher_grpc_client, node_id);
 
+        if let Some(interval) = leak_watchdog_interval {
+            LeakWatchdog::spawn(sfu_grpc_service.webrtc_manager(), interval);
+        }
+
         let shutdown_signal = async {
             tokio::signal::ctrl_c()
                 .await
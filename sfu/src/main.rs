@@ -1,10 +1,21 @@
 use sfu::infrastructure::{config::app_env::AppEnv, etcd::EtcdNode, grpc::GrpcServer};
+use std::sync::Arc;
 use tracing::{Metadata, warn};
 use tracing_subscriber::{
-    EnvFilter, Layer, filter::FilterFn, fmt, layer::SubscriberExt, registry,
+    EnvFilter, Layer,
+    filter::{FilterExt, FilterFn},
+    fmt,
+    layer::SubscriberExt,
+    registry,
     util::SubscriberInitExt,
 };
-use webrtc_manager::models::params::WebRTCManagerConfigs;
+use webrtc_manager::{
+    models::params::WebRTCManagerConfigs,
+    utils::{
+        buffer_pool::RtpBufferPool, egress_meter::EgressMeter,
+        noise_suppression::NoiseSuppressionBudget,
+    },
+};
 
 use mimalloc::MiMalloc;
 
@@ -32,9 +43,16 @@ async fn main() -> Result<(), anyhow::Error> {
         !(is_webrtc_session || is_webrtc_ice || is_webrtc_pc_internal)
     });
 
+    // tokio-console needs the runtime's trace events, so the log filter only
+    // applies to the log output
+    #[cfg(feature = "tokio-console")]
+    let console_layer = Some(console_subscriber::spawn());
+    #[cfg(not(feature = "tokio-console"))]
+    let console_layer: Option<tracing_subscriber::layer::Identity> = None;
+
     registry()
-        .with(filter)
-        .with(fmt::layer().with_filter(filter_fn))
+        .with(console_layer)
+        .with(fmt::layer().with_filter(filter.and(filter_fn)))
         .init();
 
     rustls::crypto::ring::default_provider()
@@ -43,10 +61,21 @@ async fn main() -> Result<(), anyhow::Error> {
 
     let app_env = AppEnv::new();
 
+    let rtp_buffer_pool = Arc::new(RtpBufferPool::new(app_env.rtp_buffer_pool_size));
+
     let webrtc_configs = WebRTCManagerConfigs {
         public_ip: app_env.public_ip,
         port_min: app_env.udp_port_range.port_min,
         port_max: app_env.udp_port_range.port_max,
+        subscriber_backend: app_env.subscriber_backend.clone(),
+        rtp_buffer_pool: Arc::clone(&rtp_buffer_pool),
+        pacing_multiplier: app_env.pacing_multiplier,
+        hls_fallback_min_bps: app_env.hls_fallback_min_bps,
+        raw_recording_dir: app_env.raw_recording_dir.clone(),
+        noise_suppression_budget: Arc::new(NoiseSuppressionBudget::new(
+            app_env.noise_suppression_max_streams,
+        )),
+        egress_meter: Arc::new(EgressMeter::new(app_env.node_egress_budget_bps)),
     };
 
     let ttl = 5;
@@ -56,6 +85,9 @@ async fn main() -> Result<(), anyhow::Error> {
         app_env.node_id.clone(),
         app_env.grpc_configs.sfu_host,
         app_env.group_id,
+        app_env.node_tags,
+        app_env.subscriber_backend.as_str(),
+        rtp_buffer_pool,
         ttl,
     )
     .await?;
@@ -66,6 +98,7 @@ async fn main() -> Result<(), anyhow::Error> {
         app_env.grpc_configs.dispatcher_port,
         webrtc_configs,
         app_env.node_id,
+        app_env.leak_watchdog_interval,
     );
 
     tokio::signal::ctrl_c().await?;
@@ -0,0 +1,386 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use dispatcher::dispatcher_manager::DispatcherManager;
+use socketioxide_redis::drivers::redis::redis_client::{
+    self as redis, cluster_async::ClusterConnection,
+};
+use tracing::warn;
+
+use crate::core::env::app_env::AlertingConfigs;
+
+use super::notifier::AlertNotifier;
+
+/// Rules are evaluated over the samples recorded in this window
+const WINDOW: Duration = Duration::from_secs(5 * 60);
+
+const EVALUATION_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Below this many samples a single bad join or client would trip the rules
+const MIN_JOIN_SAMPLES: usize = 20;
+const MIN_PACKET_LOSS_SAMPLES: usize = 10;
+
+/// Upper bound per sample queue so a burst of traffic can't grow memory unbounded
+const MAX_SAMPLES: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertSeverity {
+    Warning,
+    Critical,
+}
+
+impl AlertSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertSeverity::Warning => "warning",
+            AlertSeverity::Critical => "critical",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Alert {
+    /// Identifies the condition, alerts with the same key are deduplicated
+    pub key: String,
+    pub severity: AlertSeverity,
+    pub summary: String,
+}
+
+#[derive(Default)]
+struct Samples {
+    /// Join latency, `None` when the join failed
+    joins: VecDeque<(Instant, Option<Duration>)>,
+    packet_loss: VecDeque<(Instant, f64)>,
+}
+
+impl Samples {
+    fn prune(&mut self, now: Instant) {
+        while self
+            .joins
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > WINDOW)
+        {
+            self.joins.pop_front();
+        }
+
+        while self
+            .packet_loss
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > WINDOW)
+        {
+            self.packet_loss.pop_front();
+        }
+    }
+
+    fn record_join(&mut self, now: Instant, latency: Option<Duration>) {
+        self.prune(now);
+        if self.joins.len() >= MAX_SAMPLES {
+            self.joins.pop_front();
+        }
+        self.joins.push_back((now, latency));
+    }
+
+    fn record_packet_loss(&mut self, now: Instant, packet_loss: f64) {
+        self.prune(now);
+        if self.packet_loss.len() >= MAX_SAMPLES {
+            self.packet_loss.pop_front();
+        }
+        self.packet_loss.push_back((now, packet_loss));
+    }
+
+    /// The rules violated by the samples of the window ending at `now` and by
+    /// the nodes' current CPU usage
+    fn evaluate(
+        &mut self,
+        configs: &AlertingConfigs,
+        node_loads: &[(String, f32)],
+        now: Instant,
+    ) -> Vec<Alert> {
+        let mut alerts = Vec::new();
+
+        self.prune(now);
+
+        if self.joins.len() >= MIN_JOIN_SAMPLES {
+            let total = self.joins.len();
+            let mut latencies: Vec<Duration> = self.joins.iter().filter_map(|(_, l)| *l).collect();
+            let failures = total - latencies.len();
+
+            let failure_rate = failures as f64 / total as f64;
+            if failure_rate > configs.max_join_failure_rate {
+                alerts.push(Alert {
+                    key: "join_failure_rate".to_string(),
+                    severity: AlertSeverity::Critical,
+                    summary: format!(
+                        "Join failure rate is {:.1}% ({} of {} joins) over the last {} minutes",
+                        failure_rate * 100.0,
+                        failures,
+                        total,
+                        WINDOW.as_secs() / 60
+                    ),
+                });
+            }
+
+            if !latencies.is_empty() {
+                latencies.sort_unstable();
+                let index = ((latencies.len() as f64 * 0.95).ceil() as usize).max(1) - 1;
+                let p95_ms = latencies[index].as_millis() as i64;
+
+                if p95_ms > configs.max_p95_join_latency_ms {
+                    alerts.push(Alert {
+                        key: "join_latency_p95".to_string(),
+                        severity: AlertSeverity::Warning,
+                        summary: format!(
+                            "p95 join latency is {}ms, above the {}ms objective",
+                            p95_ms, configs.max_p95_join_latency_ms
+                        ),
+                    });
+                }
+            }
+        }
+
+        if self.packet_loss.len() >= MIN_PACKET_LOSS_SAMPLES {
+            let average = self.packet_loss.iter().map(|(_, l)| l).sum::<f64>()
+                / self.packet_loss.len() as f64;
+
+            if average > configs.max_packet_loss {
+                alerts.push(Alert {
+                    key: "packet_loss".to_string(),
+                    severity: AlertSeverity::Warning,
+                    summary: format!(
+                        "Average client packet loss is {:.1}% across {} reports",
+                        average * 100.0,
+                        self.packet_loss.len()
+                    ),
+                });
+            }
+        }
+
+        for (node_id, cpu) in node_loads {
+            if *cpu as f64 > configs.max_node_cpu {
+                alerts.push(Alert {
+                    key: format!("node_cpu:{node_id}"),
+                    severity: AlertSeverity::Critical,
+                    summary: format!("SFU node {} is saturated at {:.0}% CPU", node_id, cpu),
+                });
+            }
+        }
+
+        alerts
+    }
+}
+
+/// Evaluates SLO rules over join outcomes, client packet loss and SFU node load,
+/// and notifies the configured webhooks when one is violated.
+///
+/// Alerts are deduplicated across signalling instances with a Redis key that
+/// lives for the cool-down, so an ongoing incident pages once per cool-down.
+#[derive(Clone)]
+pub struct AlertManager {
+    configs: AlertingConfigs,
+    samples: Arc<Mutex<Samples>>,
+    notifier: AlertNotifier,
+    conn: ClusterConnection,
+}
+
+impl fmt::Debug for AlertManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AlertManager").finish_non_exhaustive()
+    }
+}
+
+impl AlertManager {
+    pub fn new(configs: AlertingConfigs, conn: ClusterConnection) -> Self {
+        let notifier = AlertNotifier::new(&configs);
+
+        Self {
+            configs,
+            samples: Arc::new(Mutex::new(Samples::default())),
+            notifier,
+            conn,
+        }
+    }
+
+    pub fn record_join_success(&self, latency: Duration) {
+        self.record_join(Some(latency));
+    }
+
+    pub fn record_join_failure(&self) {
+        self.record_join(None);
+    }
+
+    /// Record the packet loss reported by a client, as a fraction between 0 and 1
+    pub fn record_packet_loss(&self, packet_loss: f64) {
+        if !packet_loss.is_finite() {
+            return;
+        }
+
+        let mut samples = self.samples.lock().unwrap();
+        samples.record_packet_loss(Instant::now(), packet_loss);
+    }
+
+    /// Evaluate the rules periodically for as long as the process runs
+    pub fn spawn(self, dispatcher_manager: DispatcherManager) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(EVALUATION_INTERVAL);
+
+            loop {
+                interval.tick().await;
+
+                let node_loads = dispatcher_manager.get_node_loads().await;
+                for alert in self.evaluate(&node_loads) {
+                    self.fire(alert).await;
+                }
+            }
+        });
+    }
+
+    fn record_join(&self, latency: Option<Duration>) {
+        let mut samples = self.samples.lock().unwrap();
+        samples.record_join(Instant::now(), latency);
+    }
+
+    fn evaluate(&self, node_loads: &[(String, f32)]) -> Vec<Alert> {
+        let mut samples = self.samples.lock().unwrap();
+        samples.evaluate(&self.configs, node_loads, Instant::now())
+    }
+
+    async fn fire(&self, alert: Alert) {
+        if !self.acquire(&alert.key).await {
+            return;
+        }
+
+        warn!("[{}] {}", alert.severity.as_str(), alert.summary);
+
+        self.notifier.notify(&alert).await;
+    }
+
+    /// Claim the alert for the cool-down, false if it was already fired.
+    /// Fails open so an unreachable Redis doesn't swallow alerts.
+    async fn acquire(&self, key: &str) -> bool {
+        let mut conn = self.conn.clone();
+
+        let result: Result<Option<String>, redis::RedisError> = redis::cmd("SET")
+            .arg(format!("alert:{key}"))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(self.configs.cooldown_seconds)
+            .query_async(&mut conn)
+            .await;
+
+        match result {
+            Ok(reply) => reply.is_some(),
+            Err(err) => {
+                warn!("Failed to deduplicate alert {}: {:?}", key, err);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configs() -> AlertingConfigs {
+        AlertingConfigs {
+            slack_webhook_url: None,
+            pagerduty_routing_key: None,
+            cooldown_seconds: 900,
+            max_join_failure_rate: 0.05,
+            max_p95_join_latency_ms: 3_000,
+            max_node_cpu: 85.0,
+            max_packet_loss: 0.05,
+        }
+    }
+
+    fn keys(alerts: &[Alert]) -> Vec<&str> {
+        alerts.iter().map(|alert| alert.key.as_str()).collect()
+    }
+
+    #[test]
+    fn test_too_few_joins_dont_alert() {
+        let now = Instant::now();
+        let mut samples = Samples::default();
+        for _ in 0..MIN_JOIN_SAMPLES - 1 {
+            samples.record_join(now, None);
+        }
+
+        assert!(samples.evaluate(&configs(), &[], now).is_empty());
+    }
+
+    #[test]
+    fn test_join_failure_rate() {
+        let now = Instant::now();
+        let mut samples = Samples::default();
+        for i in 0..MIN_JOIN_SAMPLES {
+            let latency = (i % 4 != 0).then(|| Duration::from_millis(200));
+            samples.record_join(now, latency);
+        }
+
+        let alerts = samples.evaluate(&configs(), &[], now);
+        assert_eq!(keys(&alerts), ["join_failure_rate"]);
+        assert_eq!(alerts[0].severity, AlertSeverity::Critical);
+    }
+
+    #[test]
+    fn test_p95_join_latency() {
+        let now = Instant::now();
+        let mut samples = Samples::default();
+        for _ in 0..MIN_JOIN_SAMPLES - 1 {
+            samples.record_join(now, Some(Duration::from_millis(500)));
+        }
+        samples.record_join(now, Some(Duration::from_secs(10)));
+
+        // One slow join out of twenty sits right at the 95th percentile
+        assert!(samples.evaluate(&configs(), &[], now).is_empty());
+
+        samples.record_join(now, Some(Duration::from_secs(10)));
+
+        let alerts = samples.evaluate(&configs(), &[], now);
+        assert_eq!(keys(&alerts), ["join_latency_p95"]);
+        assert_eq!(alerts[0].severity, AlertSeverity::Warning);
+    }
+
+    #[test]
+    fn test_average_packet_loss() {
+        let now = Instant::now();
+        let mut samples = Samples::default();
+        for _ in 0..MIN_PACKET_LOSS_SAMPLES - 1 {
+            samples.record_packet_loss(now, 0.01);
+        }
+        samples.record_packet_loss(now, 0.5);
+
+        let alerts = samples.evaluate(&configs(), &[], now);
+        assert_eq!(keys(&alerts), ["packet_loss"]);
+    }
+
+    #[test]
+    fn test_samples_leave_the_window() {
+        let now = Instant::now();
+        let mut samples = Samples::default();
+        for _ in 0..MIN_JOIN_SAMPLES {
+            samples.record_join(now, None);
+        }
+        for _ in 0..MIN_PACKET_LOSS_SAMPLES {
+            samples.record_packet_loss(now, 0.5);
+        }
+
+        let later = now + WINDOW + Duration::from_secs(1);
+        assert!(samples.evaluate(&configs(), &[], later).is_empty());
+        assert!(samples.joins.is_empty());
+        assert!(samples.packet_loss.is_empty());
+    }
+
+    #[test]
+    fn test_saturated_nodes() {
+        let node_loads = [("node1".to_string(), 90.0), ("node2".to_string(), 40.0)];
+
+        let alerts = Samples::default().evaluate(&configs(), &node_loads, Instant::now());
+        assert_eq!(keys(&alerts), ["node_cpu:node1"]);
+    }
+}
@@ -0,0 +1,2 @@
+pub mod alert_manager;
+pub mod notifier;
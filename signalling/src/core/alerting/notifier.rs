@@ -0,0 +1,87 @@
+use serde_json::json;
+use tracing::warn;
+
+use crate::core::env::app_env::AlertingConfigs;
+
+use super::alert_manager::Alert;
+
+const PAGERDUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+const ALERT_SOURCE: &str = "waterbus-signalling";
+
+/// Delivers alerts to Slack incoming webhooks and the PagerDuty Events API.
+/// Destinations that aren't configured are skipped.
+#[derive(Debug, Clone)]
+pub struct AlertNotifier {
+    client: reqwest::Client,
+    slack_webhook_url: Option<String>,
+    pagerduty_routing_key: Option<String>,
+}
+
+impl AlertNotifier {
+    pub fn new(configs: &AlertingConfigs) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            slack_webhook_url: configs.slack_webhook_url.clone(),
+            pagerduty_routing_key: configs.pagerduty_routing_key.clone(),
+        }
+    }
+
+    pub async fn notify(&self, alert: &Alert) {
+        if let Some(url) = &self.slack_webhook_url {
+            let result = self.notify_slack(url, alert).await;
+            if let Err(err) = result {
+                warn!("Failed to send alert {} to Slack: {:?}", alert.key, err);
+            }
+        }
+
+        if let Some(routing_key) = &self.pagerduty_routing_key {
+            let result = self.notify_pagerduty(routing_key, alert).await;
+            if let Err(err) = result {
+                warn!("Failed to send alert {} to PagerDuty: {:?}", alert.key, err);
+            }
+        }
+    }
+
+    async fn notify_slack(&self, url: &str, alert: &Alert) -> Result<(), reqwest::Error> {
+        let body = json!({
+            "text": format!("[{}] {}", alert.severity.as_str().to_uppercase(), alert.summary),
+        });
+
+        self.client
+            .post(url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    async fn notify_pagerduty(
+        &self,
+        routing_key: &str,
+        alert: &Alert,
+    ) -> Result<(), reqwest::Error> {
+        // PagerDuty groups events sharing a dedup key into a single incident
+        let body = json!({
+            "routing_key": routing_key,
+            "event_action": "trigger",
+            "dedup_key": alert.key,
+            "payload": {
+                "summary": alert.summary,
+                "source": ALERT_SOURCE,
+                "severity": alert.severity.as_str(),
+            },
+        });
+
+        self.client
+            .post(PAGERDUTY_EVENTS_URL)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
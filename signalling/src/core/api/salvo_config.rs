@@ -1,7 +1,10 @@
+use std::sync::Arc;
+
 use diesel::{
     PgConnection,
     r2d2::{ConnectionManager, Pool},
 };
+use dispatcher::infrastructure::event_queue;
 use rust_embed::RustEmbed;
 use salvo::{
     catcher::Catcher,
@@ -14,20 +17,87 @@ use salvo::{
     rate_limiter::{BasicQuota, FixedGuard, MokaStore, RateLimiter, RemoteIpIssuer},
     serve_static::static_embed,
 };
+use socketioxide_redis::drivers::redis::redis_client as redis;
 
 use crate::{
     core::{
         database::db::establish_connection,
         env::app_env::AppEnv,
+        metrics::{
+            db_pool_metrics::DbPoolMetrics, join_metrics::JoinMetrics, queue_metrics::QueueMetrics,
+            socket_metrics::SocketMetrics,
+        },
         socket::get_socket_router,
-        types::app_channel::AppEvent,
-        utils::{api_key_utils::api_key_middleware, jwt_utils::JwtUtils},
+        types::{app_channel::AppEvent, responses::room_response::RESPONSE_VERSION_HEADER},
+        utils::{
+            api_key_utils::api_key_middleware, client_stats_store::ClientStatsStore,
+            hls_edge::HlsEdge, hls_session_store::HlsSessionStore,
+            hls_telemetry_store::HlsTelemetryStore, jwt_utils::JwtUtils,
+            overflow_queue::OverflowQueue, retention_progress_store::RetentionProgressStore,
+            room_state_cache::RoomStateCache, search_reindex_store::SearchReindexStore,
+            viewer_count_store::ViewerCountStore,
+        },
     },
     features::{
+        admin::router::get_admin_router,
+        analytics::router::{get_analytics_router, get_hls_beacon_router},
         auth::{repository::AuthRepositoryImpl, router::get_auth_router, service::AuthServiceImpl},
-        chat::{repository::ChatRepositoryImpl, router::get_chat_router, service::ChatServiceImpl},
-        room::{repository::RoomRepositoryImpl, router::get_room_router, service::RoomServiceImpl},
-        user::{repository::UserRepositoryImpl, router::get_user_router, service::UserServiceImpl},
+        bot::{repository::BotRepositoryImpl, service::BotServiceImpl},
+        calendar::{
+            provider::{CalendarProviders, build_providers},
+            renewer::CalendarWatchRenewer,
+            repository::CalendarRepositoryImpl,
+            router::{get_calendar_provider_router, get_calendar_router},
+            service::CalendarServiceImpl,
+        },
+        callout::{
+            provider::{CalloutProvider, build_provider},
+            repository::CalloutRepositoryImpl,
+            router::get_callout_router,
+            service::CalloutServiceImpl,
+        },
+        chat::{
+            outbox::{MatrixRelays, OutboxRelay, OutboxSignal},
+            repository::ChatRepositoryImpl,
+            retention::RetentionPurger,
+            router::get_chat_router,
+            service::ChatServiceImpl,
+        },
+        client_config::{router::get_client_config_router, service::ClientConfigServiceImpl},
+        compliance::{repository::ComplianceRepositoryImpl, service::ComplianceServiceImpl},
+        matrix::{
+            client::MatrixClient,
+            repository::MatrixRepositoryImpl,
+            router::{get_matrix_appservice_router, get_matrix_router},
+            service::MatrixServiceImpl,
+        },
+        moderation::{
+            notifier::ReportNotifier, repository::ModerationRepositoryImpl,
+            router::get_moderation_router, service::ModerationServiceImpl,
+        },
+        org::{repository::OrgRepositoryImpl, router::get_org_router, service::OrgServiceImpl},
+        room::{
+            repository::RoomRepositoryImpl,
+            router::{get_room_router, get_room_viewers_router},
+            service::RoomServiceImpl,
+        },
+        schedule::{
+            repository::ScheduleRepositoryImpl, router::get_schedule_router,
+            scheduler::SlateScheduler, service::ScheduleServiceImpl,
+        },
+        search::{
+            reindex::SearchReindexer, repository::SearchRepositoryImpl, router::get_search_router,
+            service::SearchServiceImpl, sync::SearchSync, typesense::TypesenseClient,
+        },
+        timeline::{
+            repository::TimelineRepositoryImpl, router::get_timeline_router,
+            service::TimelineServiceImpl,
+        },
+        user::{
+            repository::UserRepositoryImpl,
+            router::{get_avatar_router, get_user_router},
+            service::UserServiceImpl,
+        },
     },
 };
 
@@ -44,6 +114,25 @@ async fn health_check(res: &mut Response) {
     res.render("[v3] Waterbus Service written in Rust");
 }
 
+/// Prometheus scrape target, the counters are per signalling instance
+#[handler(tags("system"))]
+async fn metrics(res: &mut Response, depot: &mut Depot) {
+    let socket_metrics = depot.obtain::<SocketMetrics>().unwrap();
+    let db_pool_metrics = depot.obtain::<DbPoolMetrics>().unwrap();
+    let queue_metrics = depot.obtain::<QueueMetrics>().unwrap();
+    let join_metrics = depot.obtain::<JoinMetrics>().unwrap();
+    let pool = depot.obtain::<DbConnection>().unwrap();
+
+    res.add_header("Content-Type", "text/plain; version=0.0.4", true)
+        .ok();
+    res.render(
+        socket_metrics.render()
+            + &db_pool_metrics.render(&pool.0)
+            + &queue_metrics.render()
+            + &join_metrics.render(),
+    );
+}
+
 #[handler]
 async fn set_services(depot: &mut Depot) {
     let pool = depot.obtain::<DbConnection>().unwrap();
@@ -54,26 +143,133 @@ async fn set_services(depot: &mut Depot) {
     let room_repository = RoomRepositoryImpl::new(pool.clone().0);
 
     let auth_service = AuthServiceImpl::new(auth_repository.clone());
+    let default_retention_days = depot
+        .obtain::<AppEnv>()
+        .unwrap()
+        .chat_retention
+        .default_days;
     let chat_service = ChatServiceImpl::new(
         chat_repository.clone(),
         room_repository.clone(),
         user_repository.clone(),
-    );
+    )
+    .with_retention_days(default_retention_days);
 
     let user_service = UserServiceImpl::new(user_repository.clone());
-    let room_service = RoomServiceImpl::new(room_repository.clone(), user_repository.clone());
+    let bot_service = BotServiceImpl::new(BotRepositoryImpl::new(pool.clone().0));
+    let org_service = OrgServiceImpl::new(OrgRepositoryImpl::new(pool.clone().0));
+    let env = depot.obtain::<AppEnv>().unwrap();
+    let client_config_service =
+        ClientConfigServiceImpl::new(org_service.clone(), env.client.clone()).with_features(
+            [
+                env.callout.as_ref().map(|_| "callouts"),
+                env.matrix.as_ref().map(|_| "matrix_bridge"),
+                env.calendar.as_ref().map(|_| "calendar_sync"),
+            ]
+            .into_iter()
+            .flatten(),
+        );
+    let compliance_service =
+        ComplianceServiceImpl::new(ComplianceRepositoryImpl::new(pool.clone().0));
+    let room_state_cache = depot.obtain::<RoomStateCache>().unwrap().clone();
+    let room_service = RoomServiceImpl::new(room_repository.clone(), user_repository.clone())
+        .with_cache(room_state_cache);
+
+    if let Ok(matrix_client) = depot.obtain::<MatrixClient>() {
+        let matrix_service = MatrixServiceImpl::new(
+            MatrixRepositoryImpl::new(pool.clone().0),
+            chat_service.clone(),
+            room_service.clone(),
+            matrix_client.clone(),
+        );
+
+        depot.inject(matrix_service);
+    }
 
+    if let Ok(provider) = depot.obtain::<Arc<dyn CalloutProvider>>() {
+        let provider = provider.clone();
+        let env = depot.obtain::<AppEnv>().unwrap();
+
+        if let Some(configs) = &env.callout {
+            let callout_service = CalloutServiceImpl::new(
+                CalloutRepositoryImpl::new(pool.clone().0),
+                room_service.clone(),
+                provider,
+                configs,
+            );
+
+            depot.inject(callout_service);
+        }
+    }
+
+    let schedule_service = ScheduleServiceImpl::new(
+        ScheduleRepositoryImpl::new(pool.clone().0),
+        room_service.clone(),
+    );
+
+    if let Ok(providers) = depot.obtain::<CalendarProviders>() {
+        let providers = providers.clone();
+        let env = depot.obtain::<AppEnv>().unwrap();
+
+        if let Some(configs) = &env.calendar {
+            let calendar_service = CalendarServiceImpl::new(
+                CalendarRepositoryImpl::new(pool.clone().0),
+                schedule_service.clone(),
+                room_service.clone(),
+                providers,
+                configs,
+            );
+
+            depot.inject(calendar_service);
+        }
+    }
+
+    let mut moderation_service = ModerationServiceImpl::new(
+        ModerationRepositoryImpl::new(pool.clone().0),
+        room_service.clone(),
+    );
+    if let Ok(notifier) = depot.obtain::<ReportNotifier>() {
+        moderation_service = moderation_service.with_notifier(notifier.clone());
+    }
+
+    let timeline_service = TimelineServiceImpl::new(
+        TimelineRepositoryImpl::new(pool.clone().0),
+        room_service.clone(),
+    );
+
+    let search_repository = SearchRepositoryImpl::new(pool.clone().0);
+    let mut search_service = SearchServiceImpl::new(search_repository.clone());
+    if let Ok(typesense) = depot.obtain::<TypesenseClient>() {
+        search_service = search_service.with_typesense(typesense.clone());
+
+        let reindex_store = depot.obtain::<SearchReindexStore>().unwrap();
+        let search_reindexer =
+            SearchReindexer::new(search_repository, typesense.clone(), reindex_store.clone());
+
+        depot.inject(search_reindexer);
+    }
+
+    depot.inject(schedule_service);
+    depot.inject(moderation_service);
+    depot.inject(timeline_service);
+    depot.inject(search_service);
     depot.inject(auth_service);
     depot.inject(user_service);
     depot.inject(chat_service);
     depot.inject(room_service);
+    depot.inject(bot_service);
+    depot.inject(org_service);
+    depot.inject(client_config_service);
+    depot.inject(compliance_service);
 }
 
 pub async fn get_salvo_service(env: &AppEnv) -> Service {
-    let pool = establish_connection(env.clone());
+    let db_pool_metrics = DbPoolMetrics::new();
+    let pool = establish_connection(env.clone(), db_pool_metrics.clone());
 
     let db_pooled_connection = DbConnection(pool.clone());
     let jwt_utils = JwtUtils::new(env.clone());
+    let hls_jwt_utils = jwt_utils.clone();
 
     let limiter = RateLimiter::new(
         FixedGuard::new(),
@@ -83,44 +279,310 @@ pub async fn get_salvo_service(env: &AppEnv) -> Service {
     );
 
     let health_router = Router::new().path("/health-check").get(health_check);
+    let metrics_router = Router::new().path("/metrics").get(metrics);
     let auth_router = get_auth_router(jwt_utils.clone());
     let user_router = get_user_router(jwt_utils.clone());
     let chat_router = get_chat_router(jwt_utils.clone());
     let room_router = get_room_router(jwt_utils.clone());
+    let admin_router = get_admin_router();
+    let matrix_router = get_matrix_router(jwt_utils.clone());
+    let callout_router = get_callout_router(jwt_utils.clone());
+    let calendar_router = get_calendar_router(jwt_utils.clone());
+    let schedule_router = get_schedule_router(jwt_utils.clone());
+    let search_router = get_search_router(jwt_utils.clone());
+    let moderation_router = get_moderation_router(jwt_utils.clone());
+    let timeline_router = get_timeline_router(jwt_utils.clone());
+    let client_config_router = get_client_config_router(jwt_utils.clone());
 
-    let (message_sender, message_receiver) = async_channel::unbounded::<AppEvent>();
+    let queue_metrics = QueueMetrics::new();
+    let (message_sender, message_receiver) =
+        event_queue::bounded::<AppEvent>("app_events", env.event_queues.app_events);
+    queue_metrics.register(message_sender.stats());
+    // Admin endpoints queue room closes and bot revocations behind the chat messages
+    let app_event_sender = message_sender.clone();
 
     let room_repository = RoomRepositoryImpl::new(pool.clone());
     let user_repository = UserRepositoryImpl::new(pool.clone());
-    let room_service = RoomServiceImpl::new(room_repository, user_repository);
-    let socket_router = get_socket_router(env, jwt_utils.clone(), room_service, message_receiver)
-        .await
-        .expect("Failed to config socket.io");
+    let chat_service = ChatServiceImpl::new(
+        ChatRepositoryImpl::new(pool.clone()),
+        room_repository.clone(),
+        user_repository.clone(),
+    );
+    let bot_service = BotServiceImpl::new(BotRepositoryImpl::new(pool.clone()));
+    let redis_client = redis::cluster::ClusterClient::new(env.redis_uris.clone())
+        .expect("Failed to create redis cluster client");
+    let room_state_cache = RoomStateCache::new(
+        redis_client
+            .get_async_connection()
+            .await
+            .expect("Failed to connect to redis"),
+    );
+
+    let viewer_count_store = ViewerCountStore::new(
+        redis_client
+            .get_async_connection()
+            .await
+            .expect("Failed to connect to redis"),
+    );
+
+    let hls_telemetry_store = HlsTelemetryStore::new(
+        redis_client
+            .get_async_connection()
+            .await
+            .expect("Failed to connect to redis"),
+    );
+
+    let client_stats_store = ClientStatsStore::new(
+        redis_client
+            .get_async_connection()
+            .await
+            .expect("Failed to connect to redis"),
+    );
+
+    let retention_progress_store = RetentionProgressStore::new(
+        redis_client
+            .get_async_connection()
+            .await
+            .expect("Failed to connect to redis"),
+    );
+
+    let search_reindex_store = SearchReindexStore::new(
+        redis_client
+            .get_async_connection()
+            .await
+            .expect("Failed to connect to redis"),
+    );
+
+    let hls_session_store = HlsSessionStore::new(
+        redis_client
+            .get_async_connection()
+            .await
+            .expect("Failed to connect to redis"),
+    );
+
+    let overflow_queue = OverflowQueue::new(
+        redis_client
+            .get_async_connection()
+            .await
+            .expect("Failed to connect to redis"),
+    );
+
+    let matrix_client = match &env.matrix {
+        Some(configs) => Some(MatrixClient::new(
+            configs.clone(),
+            redis_client
+                .get_async_connection()
+                .await
+                .expect("Failed to connect to redis"),
+        )),
+        None => None,
+    };
+
+    // Search works without Typesense, the index is filled in the background
+    let typesense_client = env.typesense.clone().map(TypesenseClient::new);
+    if let Some(typesense_client) = &typesense_client {
+        let search_repository = SearchRepositoryImpl::new(pool.clone());
+        let search_reindexer = SearchReindexer::new(
+            search_repository.clone(),
+            typesense_client.clone(),
+            search_reindex_store.clone(),
+        );
+
+        SearchSync::new(
+            search_repository,
+            typesense_client.clone(),
+            search_reindexer,
+        )
+        .spawn();
+    }
+
+    let room_service =
+        RoomServiceImpl::new(room_repository, user_repository).with_cache(room_state_cache.clone());
+
+    let outbox_signal = OutboxSignal::default();
+    let mut outbox_relay = OutboxRelay::new(
+        ChatRepositoryImpl::new(pool.clone()),
+        message_sender,
+        outbox_signal.clone(),
+    );
+    if let Some(matrix_client) = &matrix_client {
+        let matrix_relays = MatrixRelays::spawn(MatrixServiceImpl::new(
+            MatrixRepositoryImpl::new(pool.clone()),
+            chat_service.clone(),
+            room_service.clone(),
+            matrix_client.clone(),
+        ));
+        queue_metrics.register(matrix_relays.stats());
+        outbox_relay = outbox_relay.with_matrix_relays(matrix_relays);
+    }
+    outbox_relay.spawn();
+
+    RetentionPurger::new(
+        ChatRepositoryImpl::new(pool.clone()),
+        retention_progress_store.clone(),
+        env.chat_retention.clone(),
+    )
+    .spawn();
+
+    let schedule_service = ScheduleServiceImpl::new(
+        ScheduleRepositoryImpl::new(pool.clone()),
+        room_service.clone(),
+    );
+
+    let report_notifier = env.moderation_webhook_url.clone().map(ReportNotifier::new);
+    let mut moderation_service = ModerationServiceImpl::new(
+        ModerationRepositoryImpl::new(pool.clone()),
+        room_service.clone(),
+    );
+    if let Some(report_notifier) = &report_notifier {
+        moderation_service = moderation_service.with_notifier(report_notifier.clone());
+    }
+
+    let timeline_service = TimelineServiceImpl::new(
+        TimelineRepositoryImpl::new(pool.clone()),
+        room_service.clone(),
+    );
+
+    let socket_metrics = SocketMetrics::new();
+
+    let (socket_router, dispatcher_manager) = get_socket_router(
+        env,
+        jwt_utils.clone(),
+        room_service.clone(),
+        schedule_service.clone(),
+        chat_service,
+        bot_service,
+        moderation_service,
+        timeline_service,
+        outbox_signal.clone(),
+        socket_metrics.clone(),
+        queue_metrics.clone(),
+        message_receiver,
+    )
+    .await
+    .expect("Failed to config socket.io");
+
+    let calendar_providers = env.calendar.as_ref().map(build_providers);
+    if let (Some(configs), Some(providers)) = (&env.calendar, &calendar_providers) {
+        CalendarWatchRenewer::new(CalendarServiceImpl::new(
+            CalendarRepositoryImpl::new(pool.clone()),
+            schedule_service.clone(),
+            room_service.clone(),
+            providers.clone(),
+            configs,
+        ))
+        .spawn();
+    }
+
+    SlateScheduler::new(schedule_service, room_service, dispatcher_manager.clone()).spawn();
 
     let cors = Cors::new()
         .allow_origin(Any)
         .allow_methods(Any)
-        .allow_headers(vec!["Authorization", "Content-Type", "X-API-Key"])
+        .allow_headers(vec![
+            "Authorization",
+            "Content-Type",
+            "X-API-Key",
+            RESPONSE_VERSION_HEADER,
+        ])
         .into_handler();
 
-    let router = Router::with_path("busapi/v3")
+    let api_router = Router::with_hoop(api_key_middleware())
+        .push(auth_router)
+        .push(chat_router)
+        .push(user_router)
+        .push(get_room_viewers_router())
+        .push(get_org_router())
+        .push(room_router)
+        .push(admin_router)
+        .push(get_analytics_router())
+        .push(matrix_router)
+        .push(callout_router)
+        .push(calendar_router)
+        .push(schedule_router)
+        .push(search_router)
+        .push(moderation_router)
+        .push(timeline_router)
+        .push(client_config_router)
+        .push(health_router)
+        .push(metrics_router);
+
+    let mut router = Router::with_path("busapi/v3")
         .hoop(Logger::new())
         .hoop(affix_state::inject(db_pooled_connection))
         .hoop(affix_state::inject(jwt_utils))
         .hoop(affix_state::inject(env.clone()))
-        .hoop(affix_state::inject(message_sender))
+        .hoop(affix_state::inject(outbox_signal))
+        .hoop(affix_state::inject(dispatcher_manager))
+        .hoop(affix_state::inject(room_state_cache))
+        .hoop(affix_state::inject(viewer_count_store.clone()))
+        .hoop(affix_state::inject(hls_telemetry_store.clone()))
+        .hoop(affix_state::inject(client_stats_store))
+        .hoop(affix_state::inject(retention_progress_store))
+        .hoop(affix_state::inject(search_reindex_store))
+        .hoop(affix_state::inject(overflow_queue))
+        .hoop(affix_state::inject(hls_session_store.clone()))
+        .hoop(affix_state::inject(socket_metrics))
+        .hoop(affix_state::inject(db_pool_metrics))
+        .hoop(affix_state::inject(queue_metrics))
+        .hoop(affix_state::inject(JoinMetrics::new()))
+        .hoop(affix_state::inject(app_event_sender));
+
+    if let Some(configs) = &env.callout {
+        router = router.hoop(affix_state::inject(build_provider(configs)));
+    }
+
+    if let Some(typesense_client) = typesense_client {
+        router = router.hoop(affix_state::inject(typesense_client));
+    }
+
+    if let Some(report_notifier) = report_notifier {
+        router = router.hoop(affix_state::inject(report_notifier));
+    }
+
+    // Avatar tiles are requested by `<img>` tags, which can't send our API key
+    router = router.push(get_avatar_router());
+
+    // Providers and their OAuth redirects can't send our API key, webhooks carry
+    // the channel token and redirects the signed state instead
+    if let Some(providers) = calendar_providers {
+        router = router
+            .hoop(affix_state::inject(providers))
+            .push(get_calendar_provider_router());
+    }
+
+    // The homeserver authenticates with its own token instead of our API key
+    if let Some(matrix_client) = matrix_client {
+        router = router
+            .hoop(affix_state::inject(matrix_client))
+            .push(get_matrix_appservice_router());
+    }
+
+    let router = router
         .hoop(CatchPanic::new())
         .hoop(CachingHeaders::new())
         .hoop(Compression::new().min_length(2048)) // 2 KB
         .hoop(limiter)
         .hoop(set_services)
-        .hoop(api_key_middleware())
-        .push(auth_router)
-        .push(chat_router)
-        .push(user_router)
-        .push(room_router)
-        .push(health_router);
+        .push(api_router);
 
+    // Handed out when a subscriber is moved off WebRTC, see `JwtUtils::sign_hls_url`
+    let signed_hls_router = Router::with_path("hls/{token}/{*path}")
+        .hoop(affix_state::inject(hls_jwt_utils.clone()))
+        .hoop(affix_state::inject(viewer_count_store))
+        .hoop(affix_state::inject(hls_session_store))
+        .hoop(hls_jwt_utils.hls_middleware());
+    let signed_hls_router = match &env.hls_edge {
+        Some(configs) => {
+            let hls_edge = HlsEdge::new(configs.clone());
+            signed_hls_router
+                .hoop(affix_state::inject(hls_edge.clone()))
+                .get(hls_edge.handler())
+        }
+        None => signed_hls_router.get(static_embed::<HlsAssets>()),
+    };
+    let hls_beacon_router =
+        get_hls_beacon_router(hls_jwt_utils.clone()).hoop(affix_state::inject(hls_telemetry_store));
     let static_hls_router =
         Router::with_path("{*path}").get(static_embed::<HlsAssets>().fallback("index.html"));
     let static_router = Router::with_path("html/{*path}")
@@ -130,6 +592,8 @@ pub async fn get_salvo_service(env: &AppEnv) -> Service {
         .push(router)
         .push(socket_router)
         .push(static_router)
+        .push(hls_beacon_router)
+        .push(signed_hls_router)
         .push(static_hls_router);
 
     // Config
@@ -1,23 +1,139 @@
+use std::time::Duration;
+
 use diesel::pg::PgConnection;
-use diesel::r2d2::{self, ConnectionManager, PooledConnection};
-use tracing::{error, info};
+use diesel::r2d2::{self, ConnectionManager, CustomizeConnection, PooledConnection};
+use diesel::{RunQueryDsl, sql_query};
+use tracing::{error, info, warn};
 
-use crate::core::env::app_env::AppEnv;
+use crate::core::{
+    env::app_env::{AppEnv, DbPoolConfigs},
+    metrics::db_pool_metrics::DbPoolMetrics,
+    types::errors::general::GeneralError,
+};
 
 pub type DbPool = r2d2::Pool<ConnectionManager<PgConnection>>;
 pub type DbPooledConnection = PooledConnection<ConnectionManager<PgConnection>>;
 
-pub fn establish_connection(env: AppEnv) -> DbPool {
+pub fn establish_connection(env: AppEnv, metrics: DbPoolMetrics) -> DbPool {
     let database_url = &env.db_uri.0;
+    let configs = &env.db_pool;
+
+    let workers = tokio::runtime::Handle::try_current()
+        .map(|runtime| runtime.metrics().num_workers())
+        .unwrap_or(1);
+    for warning in pool_size_warnings(configs, workers) {
+        warn!("{}", warning);
+    }
 
     let manager = ConnectionManager::<PgConnection>::new(database_url);
 
-    let pool = r2d2::Pool::builder().build(manager).unwrap_or_else(|e| {
+    let max_size = configs.max_size.max(1);
+    let mut builder = r2d2::Pool::builder()
+        .max_size(max_size)
+        .min_idle(configs.min_idle.map(|min_idle| min_idle.min(max_size)))
+        .connection_timeout(Duration::from_millis(
+            configs.checkout_timeout_ms.max(1) as u64
+        ))
+        .event_handler(Box::new(metrics));
+
+    if configs.statement_timeout_ms > 0 {
+        builder =
+            builder.connection_customizer(Box::new(StatementTimeout(configs.statement_timeout_ms)));
+    }
+
+    let pool = builder.build(manager).unwrap_or_else(|e| {
         error!("Failed to create pool: {}", e);
         panic!("Database pool creation failed");
     });
 
-    info!("Connected to database: {}", database_url);
+    info!(
+        "Connected to database: {} (pool of {}, {} workers)",
+        database_url, max_size, workers
+    );
 
     pool
 }
+
+/// Checks a connection out for a repository. A checkout that timed out while
+/// every connection was in use is reported as the pool being exhausted, that
+/// calls for a bigger pool rather than a look at Postgres.
+pub fn checkout(pool: &DbPool) -> Result<DbPooledConnection, GeneralError> {
+    pool.get().map_err(|err| {
+        let state = pool.state();
+
+        if state.idle_connections == 0 && state.connections >= pool.max_size() {
+            warn!(
+                "Database pool exhausted, all {} connections in use",
+                state.connections
+            );
+            GeneralError::DbPoolExhausted
+        } else {
+            warn!("Failed to check out a database connection: {}", err);
+            GeneralError::DbConnectionError
+        }
+    })
+}
+
+/// What's off about a pool for this many async workers, logged at startup
+pub fn pool_size_warnings(configs: &DbPoolConfigs, workers: usize) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    if configs.max_size == 0 {
+        warnings.push("DB_POOL_MAX_SIZE is 0, the pool is opened with 1 connection".to_string());
+    } else if (configs.max_size as usize) < workers {
+        warnings.push(format!(
+            "DB_POOL_MAX_SIZE ({}) is below the {} async workers, requests will queue for \
+             connections under load",
+            configs.max_size, workers
+        ));
+    }
+
+    if let Some(min_idle) = configs.min_idle
+        && min_idle > configs.max_size
+    {
+        warnings.push(format!(
+            "DB_POOL_MIN_IDLE ({}) is above DB_POOL_MAX_SIZE ({}), capped to it",
+            min_idle, configs.max_size
+        ));
+    }
+
+    warnings
+}
+
+/// Caps how long any single query may run on the connection
+#[derive(Debug)]
+struct StatementTimeout(i64);
+
+impl CustomizeConnection<PgConnection, r2d2::Error> for StatementTimeout {
+    fn on_acquire(&self, conn: &mut PgConnection) -> Result<(), r2d2::Error> {
+        sql_query(format!("SET statement_timeout = {}", self.0))
+            .execute(conn)
+            .map(|_| ())
+            .map_err(r2d2::Error::QueryError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_configs(max_size: u32) -> DbPoolConfigs {
+        DbPoolConfigs {
+            max_size,
+            min_idle: None,
+            checkout_timeout_ms: 5_000,
+            statement_timeout_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_pool_size_warnings() {
+        assert!(pool_size_warnings(&sample_configs(16), 8).is_empty());
+        assert_eq!(pool_size_warnings(&sample_configs(4), 8).len(), 1);
+        assert_eq!(pool_size_warnings(&sample_configs(0), 8).len(), 1);
+
+        let mut configs = sample_configs(16);
+        configs.min_idle = Some(32);
+        assert_eq!(pool_size_warnings(&configs, 8).len(), 1);
+    }
+}
@@ -1,2 +1,3 @@
 pub mod db;
 pub mod schema;
+pub mod soft_delete;
@@ -1,5 +1,117 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    abuse_reports (id) {
+        id -> Int4,
+        room_id -> Int4,
+        reporter_id -> Int4,
+        target_id -> Int4,
+        #[max_length = 32]
+        reason -> Varchar,
+        #[max_length = 1000]
+        details -> Nullable<Varchar>,
+        #[max_length = 2048]
+        snapshot_url -> Nullable<Varchar>,
+        #[max_length = 32]
+        status -> Varchar,
+        #[max_length = 1000]
+        resolution_note -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        resolved_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    bots (id) {
+        id -> Int4,
+        user_id -> Int4,
+        #[max_length = 100]
+        name -> Varchar,
+        #[max_length = 255]
+        token_hash -> Varchar,
+        #[max_length = 255]
+        scopes -> Varchar,
+        created_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    calendar_accounts (id) {
+        id -> Int4,
+        user_id -> Int4,
+        #[max_length = 32]
+        provider -> Varchar,
+        access_token -> Text,
+        refresh_token -> Text,
+        expires_at -> Timestamp,
+        #[max_length = 255]
+        channel_id -> Nullable<Varchar>,
+        #[max_length = 64]
+        channel_token -> Varchar,
+        channel_expires_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    calendar_events (id) {
+        id -> Int4,
+        live_schedule_id -> Int4,
+        calendar_account_id -> Int4,
+        #[max_length = 1024]
+        provider_event_id -> Varchar,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    callouts (id) {
+        id -> Int4,
+        room_id -> Int4,
+        requested_by_id -> Int4,
+        #[max_length = 32]
+        phone_number -> Varchar,
+        #[max_length = 32]
+        provider -> Varchar,
+        #[max_length = 255]
+        provider_call_id -> Nullable<Varchar>,
+        #[max_length = 32]
+        status -> Varchar,
+        cost_cents -> Int4,
+        #[max_length = 255]
+        error -> Nullable<Varchar>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    live_schedules (id) {
+        id -> Int4,
+        room_id -> Int4,
+        created_by_id -> Int4,
+        start_at -> Timestamp,
+        #[max_length = 2048]
+        slate_url -> Nullable<Varchar>,
+        #[max_length = 32]
+        status -> Varchar,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    matrix_rooms (id) {
+        id -> Int4,
+        room_id -> Int4,
+        #[max_length = 255]
+        matrix_room_id -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     members (id) {
         id -> Int4,
@@ -12,6 +124,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    message_outbox (id) {
+        id -> Int4,
+        message_id -> Int4,
+        event -> Int2,
+        created_at -> Timestamp,
+        locked_until -> Nullable<Timestamp>,
+        delivered_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     messages (id) {
         id -> Int4,
@@ -27,6 +150,27 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    organizations (id) {
+        id -> Int4,
+        #[max_length = 26]
+        public_id -> Varchar,
+        #[max_length = 255]
+        name -> Varchar,
+        #[max_length = 500]
+        logo_url -> Nullable<Varchar>,
+        #[max_length = 7]
+        primary_color -> Nullable<Varchar>,
+        #[max_length = 7]
+        accent_color -> Nullable<Varchar>,
+        #[max_length = 500]
+        background_url -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        client_features -> Array<Text>,
+    }
+}
+
 diesel::table! {
     participants (id) {
         id -> Int4,
@@ -37,6 +181,46 @@ diesel::table! {
         #[max_length = 100]
         node_id -> Nullable<Varchar>,
         status -> Int2,
+        #[max_length = 26]
+        public_id -> Varchar,
+        #[max_length = 64]
+        client_version -> Nullable<Varchar>,
+        #[max_length = 64]
+        client_platform -> Nullable<Varchar>,
+        #[max_length = 64]
+        device_model -> Nullable<Varchar>,
+        #[max_length = 64]
+        network_type -> Nullable<Varchar>,
+        is_audio_enabled -> Bool,
+        is_video_enabled -> Bool,
+        is_hand_raising -> Bool,
+        detached_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    room_events (id) {
+        id -> Int4,
+        session_id -> Int4,
+        #[max_length = 32]
+        kind -> Varchar,
+        #[max_length = 26]
+        participant_id -> Nullable<Varchar>,
+        user_id -> Nullable<Int4>,
+        is_enabled -> Nullable<Bool>,
+        message_id -> Nullable<Int4>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    room_sessions (id) {
+        id -> Int4,
+        #[max_length = 26]
+        public_id -> Varchar,
+        room_id -> Int4,
+        started_at -> Timestamp,
+        ended_at -> Nullable<Timestamp>,
     }
 }
 
@@ -59,6 +243,16 @@ diesel::table! {
         status -> Int2,
         #[sql_name = "type"]
         type_ -> Int2,
+        #[max_length = 26]
+        public_id -> Varchar,
+        message_retention_days -> Nullable<Int4>,
+        legal_hold_at -> Nullable<Timestamp>,
+        noise_suppression -> Bool,
+        org_id -> Nullable<Int4>,
+        broadcast_delay_secs -> Int4,
+        capacity -> Nullable<Int4>,
+        presenter_member_ids -> Array<Int4>,
+        streaming_protocol -> Int2,
     }
 }
 
@@ -78,14 +272,53 @@ diesel::table! {
         updated_at -> Timestamp,
         deleted_at -> Nullable<Timestamp>,
         last_seen_at -> Nullable<Timestamp>,
+        #[max_length = 26]
+        public_id -> Varchar,
+        #[max_length = 35]
+        locale -> Varchar,
+        #[max_length = 64]
+        timezone -> Varchar,
     }
 }
 
+diesel::joinable!(abuse_reports -> rooms (room_id));
+diesel::joinable!(bots -> users (user_id));
+diesel::joinable!(calendar_accounts -> users (user_id));
+diesel::joinable!(calendar_events -> calendar_accounts (calendar_account_id));
+diesel::joinable!(calendar_events -> live_schedules (live_schedule_id));
+diesel::joinable!(callouts -> rooms (room_id));
+diesel::joinable!(callouts -> users (requested_by_id));
+diesel::joinable!(live_schedules -> rooms (room_id));
+diesel::joinable!(live_schedules -> users (created_by_id));
+diesel::joinable!(matrix_rooms -> rooms (room_id));
 diesel::joinable!(members -> rooms (room_id));
 diesel::joinable!(members -> users (user_id));
+diesel::joinable!(message_outbox -> messages (message_id));
 diesel::joinable!(messages -> rooms (room_id));
 diesel::joinable!(messages -> users (created_by_id));
 diesel::joinable!(participants -> rooms (room_id));
 diesel::joinable!(participants -> users (user_id));
+diesel::joinable!(room_events -> messages (message_id));
+diesel::joinable!(room_events -> room_sessions (session_id));
+diesel::joinable!(room_events -> users (user_id));
+diesel::joinable!(room_sessions -> rooms (room_id));
+diesel::joinable!(rooms -> organizations (org_id));
 
-diesel::allow_tables_to_appear_in_same_query!(members, messages, participants, rooms, users,);
+diesel::allow_tables_to_appear_in_same_query!(
+    abuse_reports,
+    bots,
+    calendar_accounts,
+    calendar_events,
+    callouts,
+    live_schedules,
+    matrix_rooms,
+    members,
+    message_outbox,
+    messages,
+    organizations,
+    participants,
+    room_events,
+    room_sessions,
+    rooms,
+    users,
+);
@@ -0,0 +1,112 @@
+use chrono::NaiveDateTime;
+use diesel::{
+    BoxableExpression, ExpressionMethods, SelectableExpression, dsl::sql, pg::Pg, sql_types::Bool,
+};
+
+use super::schema::{messages, rooms, users};
+
+/// Whether soft-deleted rows are part of a query. Only admin endpoints ask for them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DeletedScope {
+    #[default]
+    Exclude,
+    Include,
+}
+
+impl DeletedScope {
+    pub fn from_include_deleted(include_deleted: bool) -> Self {
+        if include_deleted {
+            DeletedScope::Include
+        } else {
+            DeletedScope::Exclude
+        }
+    }
+
+    /// Same check as the query filters, for rows that are already loaded
+    pub fn allows(&self, deleted_at: Option<NaiveDateTime>) -> bool {
+        *self == DeletedScope::Include || deleted_at.is_none()
+    }
+}
+
+pub type SoftDeleteFilter<QS> = Box<dyn BoxableExpression<QS, Pg, SqlType = Bool>>;
+
+/// Generic over the query source so the filter works on joins as well,
+/// as long as the table isn't on the nullable side of a left join
+macro_rules! soft_delete_filter {
+    ($name:ident, $table:ident) => {
+        pub fn $name<QS>(scope: DeletedScope) -> SoftDeleteFilter<QS>
+        where
+            $table::deleted_at: SelectableExpression<QS>,
+        {
+            match scope {
+                DeletedScope::Exclude => Box::new($table::deleted_at.is_null()),
+                DeletedScope::Include => Box::new(sql::<Bool>("TRUE")),
+            }
+        }
+    };
+}
+
+soft_delete_filter!(users_in_scope, users);
+soft_delete_filter!(rooms_in_scope, rooms);
+soft_delete_filter!(messages_in_scope, messages);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+    use diesel::{JoinOnDsl, QueryDsl, debug_query};
+
+    #[test]
+    fn test_users_excluded_by_default() {
+        let query = users::table
+            .filter(users::id.eq(1))
+            .filter(users_in_scope(DeletedScope::default()))
+            .select(users::id);
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(r#""users"."deleted_at" IS NULL"#));
+    }
+
+    #[test]
+    fn test_users_include_deleted() {
+        let query = users::table
+            .filter(users::id.eq(1))
+            .filter(users_in_scope(DeletedScope::Include))
+            .select(users::id);
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(!sql.contains("deleted_at"));
+    }
+
+    #[test]
+    fn test_rooms_excluded_by_default() {
+        let query = rooms::table
+            .filter(rooms::public_id.eq("R0000000000000000000000001"))
+            .filter(rooms_in_scope(DeletedScope::Exclude))
+            .select(rooms::id);
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(r#""rooms"."deleted_at" IS NULL"#));
+    }
+
+    #[test]
+    fn test_messages_filter_on_join() {
+        let query = messages::table
+            .inner_join(rooms::table.on(messages::room_id.eq(rooms::id)))
+            .filter(messages_in_scope(DeletedScope::Exclude))
+            .filter(rooms_in_scope(DeletedScope::Exclude))
+            .select(messages::id);
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(r#""messages"."deleted_at" IS NULL"#));
+        assert!(sql.contains(r#""rooms"."deleted_at" IS NULL"#));
+    }
+
+    #[test]
+    fn test_allows_loaded_rows() {
+        let deleted_at = Some(DateTime::from_timestamp(0, 0).unwrap().naive_utc());
+        assert!(DeletedScope::Exclude.allows(None));
+        assert!(!DeletedScope::Exclude.allows(deleted_at));
+        assert!(DeletedScope::Include.allows(deleted_at));
+        assert_eq!(
+            DeletedScope::from_include_deleted(true),
+            DeletedScope::Include
+        );
+    }
+}
@@ -0,0 +1,22 @@
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+
+/// Beacon posted by an HLS player every few seconds and when playback stops.
+/// Counters cover only what happened since the previous beacon of the session.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[salvo(schema(example = json!({"sessionId": "a1b2c3", "startupMs": 1850, "rebufferCount": 1, "rebufferMs": 620, "watchedMs": 10000, "rendition": "720p"})))]
+pub struct HlsTelemetryDto {
+    /// Player-generated id, one per playback session
+    pub session_id: String,
+    /// Time from load to the first frame, sent once per session
+    pub startup_ms: Option<u64>,
+    #[serde(default)]
+    pub rebuffer_count: u64,
+    #[serde(default)]
+    pub rebuffer_ms: u64,
+    #[serde(default)]
+    pub watched_ms: u64,
+    /// Rendition selected at the time of the beacon, e.g. `720p`
+    pub rendition: Option<String>,
+}
@@ -0,0 +1 @@
+pub mod hls_telemetry_dto;
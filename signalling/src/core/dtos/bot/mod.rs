@@ -0,0 +1 @@
+pub mod register_bot_dto;
@@ -0,0 +1,13 @@
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+use validator_derive::Validate;
+
+use crate::core::types::enums::bot_scope::BotScope;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+#[salvo(schema(example = json!({"name": "Standup bot", "scopes": ["chat:read", "chat:write"]})))]
+pub struct RegisterBotDto {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    pub scopes: Vec<BotScope>,
+}
@@ -0,0 +1,12 @@
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+use validator_derive::Validate;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+#[salvo(schema(example = json!({"phoneNumber": "+14155550123"})))]
+pub struct CreateCalloutDto {
+    /// E.164 number to dial
+    #[validate(length(min = 8, max = 16))]
+    pub phone_number: String,
+}
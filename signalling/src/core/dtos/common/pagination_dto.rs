@@ -2,6 +2,9 @@ use salvo::oapi::ToParameters;
 use serde::{Deserialize, Serialize};
 use validator_derive::Validate;
 
+/// Largest page a client gets, asking for more is cut down to this
+pub const MAX_PAGE_SIZE: i64 = 100;
+
 fn default_skip() -> i64 {
     0
 }
@@ -19,3 +22,15 @@ pub struct PaginationDto {
     #[serde(default = "default_limit")]
     pub limit: i64,
 }
+
+impl PaginationDto {
+    /// Negative values can't go into a query
+    pub fn is_valid(&self) -> bool {
+        self.skip >= 0 && self.limit >= 0
+    }
+
+    /// `limit` capped at `MAX_PAGE_SIZE`
+    pub fn page_limit(&self) -> i64 {
+        self.limit.min(MAX_PAGE_SIZE)
+    }
+}
@@ -0,0 +1,11 @@
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+use validator_derive::Validate;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+#[salvo(schema(example = json!({"matrixRoomId": "!abcdefghijkl:matrix.org"})))]
+pub struct LinkMatrixRoomDto {
+    #[validate(length(min = 1, max = 255))]
+    pub matrix_room_id: String,
+}
@@ -0,0 +1,20 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Body of `PUT /_matrix/app/v1/transactions/{txnId}` pushed by the homeserver
+#[derive(Debug, Clone, Deserialize)]
+pub struct MatrixTransactionDto {
+    #[serde(default)]
+    pub events: Vec<MatrixEventDto>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MatrixEventDto {
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub room_id: String,
+    pub sender: String,
+    pub state_key: Option<String>,
+    #[serde(default)]
+    pub content: Value,
+}
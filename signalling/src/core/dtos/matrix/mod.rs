@@ -0,0 +1,2 @@
+pub mod link_matrix_room_dto;
+pub mod matrix_transaction_dto;
@@ -1,6 +1,14 @@
+pub mod analytics;
 pub mod auth;
+pub mod bot;
+pub mod callout;
 pub mod chat;
 pub mod common;
+pub mod matrix;
+pub mod moderation;
+pub mod org;
+pub mod rollout;
 pub mod room;
+pub mod schedule;
 pub mod socket;
 pub mod user;
@@ -0,0 +1,2 @@
+pub mod report_participant_dto;
+pub mod resolve_report_dto;
@@ -0,0 +1,19 @@
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+use validator_derive::Validate;
+
+use crate::core::types::enums::report_reason::ReportReason;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+#[salvo(schema(example = json!({"targetId": "P0000000000000000000000001", "reason": "harassment", "details": "Shouting slurs at other participants"})))]
+pub struct ReportParticipantDto {
+    /// Participant being reported, as sent in room events
+    pub target_id: String,
+    pub reason: ReportReason,
+    #[validate(length(max = 1000))]
+    pub details: Option<String>,
+    /// Frame the reporter captured, e.g. an object uploaded with a presigned URL
+    #[validate(length(max = 2048))]
+    pub snapshot_url: Option<String>,
+}
@@ -0,0 +1,15 @@
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+use validator_derive::Validate;
+
+use crate::core::types::enums::report_status::ReportStatus;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+#[salvo(schema(example = json!({"status": "resolved", "note": "Removed from the room and banned"})))]
+pub struct ResolveReportDto {
+    /// `resolved` or `dismissed`
+    pub status: ReportStatus,
+    #[validate(length(max = 1000))]
+    pub note: Option<String>,
+}
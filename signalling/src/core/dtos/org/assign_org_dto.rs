@@ -0,0 +1,10 @@
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+#[serde(rename_all = "camelCase")]
+#[salvo(schema(example = json!({"orgId": "01J8Z3K4M5N6P7Q8R9S0T1V2W3"})))]
+pub struct AssignOrgDto {
+    /// `null` takes the room out of its organization
+    pub org_id: Option<String>,
+}
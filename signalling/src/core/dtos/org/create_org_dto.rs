@@ -0,0 +1,16 @@
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+use validator_derive::Validate;
+
+use super::update_branding_dto::UpdateBrandingDto;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate, Clone)]
+#[serde(rename_all = "camelCase")]
+#[salvo(schema(example = json!({"name": "Acme", "primaryColor": "#1A73E8"})))]
+pub struct CreateOrgDto {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+
+    #[serde(flatten)]
+    pub branding: UpdateBrandingDto,
+}
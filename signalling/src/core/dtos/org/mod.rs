@@ -0,0 +1,4 @@
+pub mod assign_org_dto;
+pub mod create_org_dto;
+pub mod update_branding_dto;
+pub mod update_client_features_dto;
@@ -0,0 +1,27 @@
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+use validator_derive::Validate;
+
+/// Replaces the organization's branding as a whole, what's left out is cleared
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema, Validate, Clone)]
+#[serde(rename_all = "camelCase")]
+#[salvo(schema(example = json!({
+    "logoUrl": "https://cdn.acme.com/logo.png",
+    "primaryColor": "#1A73E8",
+    "accentColor": "#FBBC04",
+    "backgroundUrl": "https://cdn.acme.com/lobby.jpg"
+})))]
+pub struct UpdateBrandingDto {
+    #[validate(url)]
+    pub logo_url: Option<String>,
+
+    /// `#RRGGBB`
+    pub primary_color: Option<String>,
+
+    /// `#RRGGBB`
+    pub accent_color: Option<String>,
+
+    /// Waiting room backdrop, also the slate of the room's HLS stream
+    #[validate(url)]
+    pub background_url: Option<String>,
+}
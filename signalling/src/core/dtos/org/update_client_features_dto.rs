@@ -0,0 +1,11 @@
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+#[serde(rename_all = "camelCase")]
+#[salvo(schema(example = json!({"features": ["live_captions", "whiteboard"]})))]
+pub struct UpdateClientFeaturesDto {
+    /// Replaces the organization's flags, an empty list leaves it with the
+    /// server's alone
+    pub features: Vec<String>,
+}
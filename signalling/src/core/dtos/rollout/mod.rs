@@ -0,0 +1 @@
+pub mod update_rollout_dto;
@@ -0,0 +1,16 @@
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+use validator_derive::Validate;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+#[salvo(schema(example = json!({"percentage": 5, "clientVersions": [">=2.6.0"]})))]
+pub struct UpdateRolloutDto {
+    /// Share of new rooms sent to canary nodes, 0 to 100
+    #[validate(range(max = 100))]
+    pub percentage: u8,
+    /// New rooms opened by a matching client go to canary nodes too: `2.6.0`,
+    /// `2.6.*` or `>=2.6.0`
+    #[serde(default)]
+    pub client_versions: Vec<String>,
+}
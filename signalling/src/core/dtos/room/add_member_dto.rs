@@ -5,5 +5,5 @@ use validator_derive::Validate;
 #[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 pub struct AddMemberDto {
     #[serde(rename = "userId")]
-    pub user_id: i32,
+    pub user_id: String,
 }
@@ -0,0 +1,12 @@
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[salvo(schema(example = json!({"userId": "V1StGXR8_Z5jdHi6B-myT"})))]
+pub struct CreateJoinTokenDto {
+    /// Public id of the user joining
+    pub user_id: String,
+    /// Only asked of users who aren't members of a protected room
+    pub password: Option<String>,
+}
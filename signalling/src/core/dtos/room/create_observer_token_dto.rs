@@ -0,0 +1,12 @@
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[salvo(schema(example = json!({"label": "QA review", "expiresInSeconds": 3600})))]
+pub struct CreateObserverTokenDto {
+    /// Who is watching and why, shown in the logs
+    pub label: String,
+    /// Defaults to an hour, at most a day
+    pub expires_in_seconds: Option<i64>,
+}
@@ -27,5 +27,7 @@ pub struct CreateRoomDto {
     #[serde(default = "default_streaming_protocol")]
     pub streaming_protocol: StreamingProtocol,
 
+    /// Participants admitted over WebRTC, joiners past it watch the call over HLS
+    #[validate(range(min = 1))]
     pub capacity: Option<i32>,
 }
@@ -1,4 +1,6 @@
 pub mod add_member_dto;
+pub mod create_join_token_dto;
+pub mod create_observer_token_dto;
 pub mod create_room_dto;
 pub mod join_room_dto;
 pub mod update_room_dto;
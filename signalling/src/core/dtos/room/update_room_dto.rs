@@ -20,5 +20,25 @@ pub struct UpdateRoomDto {
 
     pub streaming_protocol: Option<StreamingProtocol>,
 
+    /// Participants admitted over WebRTC, `0` lifts the limit
+    #[validate(range(min = 0))]
     pub capacity: Option<i32>,
+
+    /// Days chat messages are kept, `0` goes back to the deployment default
+    #[validate(range(min = 0, max = 36500))]
+    pub message_retention_days: Option<i32>,
+
+    /// Denoise microphones on the server for clients that don't do it themselves
+    pub noise_suppression: Option<bool>,
+
+    /// Seconds HLS and MoQ viewers lag behind the call, `0` for realtime. Takes
+    /// effect for publishers joining after the change.
+    #[validate(range(min = 0, max = 60))]
+    pub broadcast_delay_secs: Option<i32>,
+
+    /// Members whose video is degraded only after the attendees' when the call
+    /// runs short of bandwidth, replacing the current presenters. Takes effect
+    /// for publishers joining after the change, `room.qos_class` switches
+    /// someone already in the call.
+    pub presenter_member_ids: Option<Vec<i32>>,
 }
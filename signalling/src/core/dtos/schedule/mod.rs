@@ -0,0 +1 @@
+pub mod schedule_live_dto;
@@ -0,0 +1,15 @@
+use chrono::NaiveDateTime;
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+use validator_derive::Validate;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+#[salvo(schema(example = json!({"startAt": "2025-09-01T18:00:00", "slateUrl": "https://cdn.example.com/starting-soon.png"})))]
+pub struct ScheduleLiveDto {
+    /// UTC time the slate goes up
+    pub start_at: NaiveDateTime,
+    /// Image or video looped until the host goes live, black when omitted
+    #[validate(length(max = 2048))]
+    pub slate_url: Option<String>,
+}
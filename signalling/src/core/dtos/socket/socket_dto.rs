@@ -1,99 +1,14 @@
-use serde::{Deserialize, Serialize};
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct JoinRoomDto {
-    pub sdp: String,
-    pub room_id: String,
-    pub participant_id: String,
-    pub is_video_enabled: bool,
-    pub is_audio_enabled: bool,
-    pub is_e2ee_enabled: bool,
-    pub total_tracks: u8,
-    pub connection_type: u8,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct SubscribeDto {
-    pub target_id: String,
-    pub room_id: String,
-    pub participant_id: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct AnswerSubscribeDto {
-    pub room_id: String,
-    pub target_id: String,
-    pub sdp: String,
-    pub connection_type: u8,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct PublisherRenegotiationDto {
-    pub sdp: String,
-    pub room_id: String,
-    pub connection_type: u8,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct MigrateConnectionDto {
-    pub sdp: String,
-    pub room_id: String,
-    pub participant_id: String,
-    pub connection_type: u8,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct CandidateDto {
-    pub candidate: String,
-    pub sdp_mid: Option<String>,
-    pub sdp_m_line_index: Option<u16>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct PublisherCandidateDto {
-    pub connection_type: u8,
-    pub candidate: CandidateDto,
-    pub room_id: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct SubscriberCandidateDto {
-    pub target_id: String,
-    pub connection_type: u8,
-    pub candidate: CandidateDto,
-    pub room_id: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct SetEnabledDto {
-    pub is_enabled: bool,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct SetScreenSharingDto {
-    pub is_sharing: bool,
-    pub screen_track_id: Option<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct SetCameraTypeDto {
-    #[serde(rename = "type")]
-    pub type_: i32,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct SetHandRaisingDto {
-    pub is_raising: bool,
+pub use waterbus_types::dtos::*;
+
+use crate::core::dtos::moderation::report_participant_dto::ReportParticipantDto;
+
+impl From<RoomReportDto> for ReportParticipantDto {
+    fn from(data: RoomReportDto) -> Self {
+        ReportParticipantDto {
+            target_id: data.target_id,
+            reason: data.reason,
+            details: data.details,
+            snapshot_url: data.snapshot_url,
+        }
+    }
 }
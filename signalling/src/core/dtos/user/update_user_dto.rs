@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use validator_derive::Validate;
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, Validate, Clone)]
-#[salvo(schema(example = json!({"fullName": "Kai", "bio": "waterbus"})))]
+#[salvo(schema(example = json!({"fullName": "Kai", "bio": "waterbus", "locale": "vi-VN", "timezone": "Asia/Ho_Chi_Minh"})))]
 pub struct UpdateUserDto {
     #[validate(length(min = 1))]
     #[serde(rename = "fullName")]
@@ -13,4 +13,10 @@ pub struct UpdateUserDto {
     pub avatar: Option<String>,
 
     pub bio: Option<String>,
+
+    /// BCP 47 tag, e.g. `vi-VN`
+    pub locale: Option<String>,
+
+    /// IANA zone name, e.g. `Asia/Ho_Chi_Minh`
+    pub timezone: Option<String>,
 }
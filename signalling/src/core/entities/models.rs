@@ -10,18 +10,21 @@ use serde::{Deserialize, Serialize};
 use crate::{core::database::schema::*, impl_from_i16_with_default};
 
 #[repr(i16)]
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub enum RoomType {
     Conferencing = 0,
     LiveStreaming = 1,
+    /// Two people calling each other directly, the SFU carries none of the media
+    P2P = 2,
 }
 impl_from_i16_with_default!(RoomType {
     Conferencing = 0,
     LiveStreaming = 1,
+    P2P = 2,
 });
 
 #[repr(i16)]
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub enum StreamingProtocol {
     SFU = 0,
     HLS = 1,
@@ -66,6 +69,19 @@ impl_from_i16_with_default!(MessagesStatusEnum {
     Inactive = 1,
 });
 
+#[repr(i16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutboxEventEnum {
+    Created = 0,
+    Updated = 1,
+    Deleted = 2,
+}
+impl_from_i16_with_default!(OutboxEventEnum {
+    Created = 0,
+    Updated = 1,
+    Deleted = 2,
+});
+
 #[repr(i16)]
 #[derive(Debug, Clone, Copy)]
 pub enum RecordsStatusEnum {
@@ -128,6 +144,7 @@ impl TryFrom<i32> for RoomStatusEnum {
 #[serde(rename_all = "camelCase")]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Room {
+    #[serde(skip)]
     pub id: i32,
     pub title: String,
     #[serde(skip_serializing)]
@@ -141,6 +158,29 @@ pub struct Room {
     pub deleted_at: Option<NaiveDateTime>,
     pub latest_message_id: Option<i32>,
     pub type_: i16,
+    #[serde(rename = "id")]
+    pub public_id: String,
+    /// Overrides the deployment's chat retention, `None` follows it
+    pub message_retention_days: Option<i32>,
+    /// Set while the room is under legal hold, nothing of it is purged then.
+    /// Kept from members, only the admin API shows it.
+    #[serde(skip)]
+    pub legal_hold_at: Option<NaiveDateTime>,
+    /// Microphones of participants without client-side suppression are denoised on the SFU
+    #[serde(default)]
+    pub noise_suppression: bool,
+    /// HLS and MoQ viewers are this far behind, giving moderators time for a panic cut
+    #[serde(default)]
+    pub broadcast_delay_secs: i32,
+    /// Participants the call takes over WebRTC, later joiners watch over HLS
+    #[serde(default)]
+    pub capacity: Option<i32>,
+    /// Members whose video is protected like the host's when bandwidth runs short
+    #[serde(default)]
+    pub presenter_member_ids: Vec<i32>,
+    /// How viewers of a live stream get it, SFU while the room is a conference
+    #[serde(default)]
+    pub streaming_protocol: i16,
 }
 
 #[derive(
@@ -166,7 +206,9 @@ pub struct Member {
     pub created_at: NaiveDateTime,
     pub deleted_at: Option<NaiveDateTime>,
     pub soft_deleted_at: Option<NaiveDateTime>,
+    #[serde(skip)]
     pub user_id: i32,
+    #[serde(skip)]
     pub room_id: i32,
 }
 
@@ -194,7 +236,9 @@ pub struct Message {
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub deleted_at: Option<NaiveDateTime>,
+    #[serde(skip)]
     pub created_by_id: i32,
+    #[serde(skip)]
     pub room_id: i32,
     pub type_: i16,
     pub status: i16,
@@ -218,14 +262,38 @@ pub struct Message {
 #[diesel(belongs_to(User))]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Participant {
+    #[serde(skip)]
     pub id: i32,
     pub created_at: NaiveDateTime,
     pub deleted_at: Option<NaiveDateTime>,
+    #[serde(skip)]
     pub user_id: i32,
+    #[serde(skip)]
     pub room_id: i32,
     pub status: i16,
     #[serde(skip_serializing)]
     pub node_id: Option<String>,
+    #[serde(rename = "id")]
+    pub public_id: String,
+    /// Declared by the client when its socket connected, not shown to other participants
+    #[serde(skip)]
+    pub client_version: Option<String>,
+    #[serde(skip)]
+    pub client_platform: Option<String>,
+    #[serde(skip)]
+    pub device_model: Option<String>,
+    #[serde(skip)]
+    pub network_type: Option<String>,
+    /// Last acknowledged by the participant's node, restored when it fails over
+    #[serde(skip)]
+    pub is_audio_enabled: bool,
+    #[serde(skip)]
+    pub is_video_enabled: bool,
+    #[serde(skip)]
+    pub is_hand_raising: bool,
+    /// When the participant's node went away, purged if it doesn't rejoin in time
+    #[serde(skip)]
+    pub detached_at: Option<NaiveDateTime>,
 }
 
 #[derive(
@@ -243,6 +311,7 @@ pub struct Participant {
 #[serde(rename_all = "camelCase")]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct User {
+    #[serde(skip)]
     pub id: i32,
     pub full_name: Option<String>,
     pub user_name: String,
@@ -253,6 +322,12 @@ pub struct User {
     pub updated_at: NaiveDateTime,
     pub deleted_at: Option<NaiveDateTime>,
     pub last_seen_at: Option<NaiveDateTime>,
+    #[serde(rename = "id")]
+    pub public_id: String,
+    /// BCP 47 tag, e.g. `vi-VN`
+    pub locale: String,
+    /// IANA zone name, e.g. `Asia/Ho_Chi_Minh`
+    pub timezone: String,
 }
 
 #[derive(Insertable)]
@@ -279,6 +354,26 @@ pub struct NewMessage<'a> {
     pub updated_at: NaiveDateTime,
 }
 
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = message_outbox)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OutboxEntry {
+    pub id: i32,
+    pub message_id: i32,
+    pub event: i16,
+    pub created_at: NaiveDateTime,
+    pub locked_until: Option<NaiveDateTime>,
+    pub delivered_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = message_outbox)]
+pub struct NewOutboxEntry {
+    pub message_id: i32,
+    pub event: i16,
+    pub created_at: NaiveDateTime,
+}
+
 #[derive(Insertable)]
 #[diesel(table_name = rooms)]
 pub struct NewRoom<'a> {
@@ -290,6 +385,8 @@ pub struct NewRoom<'a> {
     pub latest_message_created_at: NaiveDateTime,
     pub status: i16,
     pub type_: i16,
+    pub streaming_protocol: i16,
+    pub capacity: Option<i32>,
 }
 
 #[derive(Insertable)]
@@ -309,3 +406,430 @@ pub struct NewParticipant<'a> {
     pub created_at: NaiveDateTime,
     pub status: i16,
 }
+
+#[derive(
+    Queryable,
+    Selectable,
+    Debug,
+    Clone,
+    Serialize,
+    Deserialize,
+    QueryableByName,
+    Associations,
+    Identifiable,
+    ToSchema,
+)]
+#[diesel(table_name = matrix_rooms)]
+#[serde(rename_all = "camelCase")]
+#[diesel(belongs_to(Room))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct MatrixRoom {
+    pub id: i32,
+    #[serde(skip)]
+    pub room_id: i32,
+    pub matrix_room_id: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = matrix_rooms)]
+pub struct NewMatrixRoom<'a> {
+    pub room_id: &'a i32,
+    pub matrix_room_id: &'a str,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(
+    Queryable,
+    Selectable,
+    Debug,
+    Clone,
+    Serialize,
+    Deserialize,
+    QueryableByName,
+    Associations,
+    Identifiable,
+    ToSchema,
+)]
+#[diesel(table_name = bots)]
+#[serde(rename_all = "camelCase")]
+#[diesel(belongs_to(User))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Bot {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub scopes: String,
+    pub created_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = bots)]
+pub struct NewBot<'a> {
+    pub user_id: &'a i32,
+    pub name: &'a str,
+    pub token_hash: &'a str,
+    pub scopes: &'a str,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(
+    Queryable,
+    Selectable,
+    Debug,
+    Clone,
+    Serialize,
+    Deserialize,
+    QueryableByName,
+    Associations,
+    Identifiable,
+    ToSchema,
+)]
+#[diesel(table_name = callouts)]
+#[serde(rename_all = "camelCase")]
+#[diesel(belongs_to(Room))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Callout {
+    pub id: i32,
+    #[serde(skip)]
+    pub room_id: i32,
+    #[serde(skip)]
+    pub requested_by_id: i32,
+    pub phone_number: String,
+    pub provider: String,
+    pub provider_call_id: Option<String>,
+    pub status: String,
+    pub cost_cents: i32,
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = callouts)]
+pub struct NewCallout<'a> {
+    pub room_id: &'a i32,
+    pub requested_by_id: &'a i32,
+    pub phone_number: &'a str,
+    pub provider: &'a str,
+    pub provider_call_id: Option<&'a str>,
+    pub status: &'a str,
+    pub cost_cents: i32,
+    pub error: Option<&'a str>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(
+    Queryable,
+    Selectable,
+    Debug,
+    Clone,
+    Serialize,
+    Deserialize,
+    QueryableByName,
+    Associations,
+    Identifiable,
+    ToSchema,
+)]
+#[diesel(table_name = live_schedules)]
+#[serde(rename_all = "camelCase")]
+#[diesel(belongs_to(Room))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct LiveSchedule {
+    pub id: i32,
+    #[serde(skip)]
+    pub room_id: i32,
+    #[serde(skip)]
+    pub created_by_id: i32,
+    pub start_at: NaiveDateTime,
+    pub slate_url: Option<String>,
+    pub status: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = live_schedules)]
+pub struct NewLiveSchedule<'a> {
+    pub room_id: &'a i32,
+    pub created_by_id: &'a i32,
+    pub start_at: NaiveDateTime,
+    pub slate_url: Option<&'a str>,
+    pub status: &'a str,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// A calendar the user connected over OAuth, scheduled live streams are put on it
+#[derive(
+    Queryable,
+    Selectable,
+    Debug,
+    Clone,
+    Serialize,
+    Deserialize,
+    QueryableByName,
+    Associations,
+    Identifiable,
+    ToSchema,
+)]
+#[diesel(table_name = calendar_accounts)]
+#[serde(rename_all = "camelCase")]
+#[diesel(belongs_to(User))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CalendarAccount {
+    pub id: i32,
+    #[serde(skip)]
+    pub user_id: i32,
+    pub provider: String,
+    #[serde(skip)]
+    pub access_token: String,
+    #[serde(skip)]
+    pub refresh_token: String,
+    #[serde(skip)]
+    pub expires_at: NaiveDateTime,
+    /// Push channel the provider notifies event changes on
+    #[serde(skip)]
+    pub channel_id: Option<String>,
+    /// Sent back with every notification, proves it came from the provider
+    #[serde(skip)]
+    pub channel_token: String,
+    #[serde(skip)]
+    pub channel_expires_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = calendar_accounts)]
+pub struct NewCalendarAccount<'a> {
+    pub user_id: &'a i32,
+    pub provider: &'a str,
+    pub access_token: &'a str,
+    pub refresh_token: &'a str,
+    pub expires_at: NaiveDateTime,
+    pub channel_token: &'a str,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// The calendar event a live schedule was put on, one per connected account
+#[derive(
+    Queryable,
+    Selectable,
+    Debug,
+    Clone,
+    Serialize,
+    Deserialize,
+    QueryableByName,
+    Associations,
+    Identifiable,
+    ToSchema,
+)]
+#[diesel(table_name = calendar_events)]
+#[serde(rename_all = "camelCase")]
+#[diesel(belongs_to(LiveSchedule))]
+#[diesel(belongs_to(CalendarAccount))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CalendarEvent {
+    pub id: i32,
+    pub live_schedule_id: i32,
+    pub calendar_account_id: i32,
+    pub provider_event_id: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = calendar_events)]
+pub struct NewCalendarEvent<'a> {
+    pub live_schedule_id: &'a i32,
+    pub calendar_account_id: &'a i32,
+    pub provider_event_id: &'a str,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(
+    Queryable,
+    Selectable,
+    Debug,
+    Clone,
+    Serialize,
+    Deserialize,
+    QueryableByName,
+    Associations,
+    Identifiable,
+    ToSchema,
+)]
+#[diesel(table_name = abuse_reports)]
+#[serde(rename_all = "camelCase")]
+#[diesel(belongs_to(Room))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AbuseReport {
+    pub id: i32,
+    #[serde(skip)]
+    pub room_id: i32,
+    #[serde(skip)]
+    pub reporter_id: i32,
+    #[serde(skip)]
+    pub target_id: i32,
+    pub reason: String,
+    pub details: Option<String>,
+    /// Reference to a frame the reporter captured, usually an uploaded object
+    pub snapshot_url: Option<String>,
+    pub status: String,
+    pub resolution_note: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub resolved_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = abuse_reports)]
+pub struct NewAbuseReport<'a> {
+    pub room_id: &'a i32,
+    pub reporter_id: &'a i32,
+    pub target_id: &'a i32,
+    pub reason: &'a str,
+    pub details: Option<&'a str>,
+    pub snapshot_url: Option<&'a str>,
+    pub status: &'a str,
+    pub created_at: NaiveDateTime,
+}
+
+/// One stretch of a room having people in it, from the first join until the
+/// last participant left
+#[derive(
+    Queryable,
+    Selectable,
+    Debug,
+    Clone,
+    Serialize,
+    Deserialize,
+    QueryableByName,
+    Associations,
+    Identifiable,
+    ToSchema,
+)]
+#[diesel(table_name = room_sessions)]
+#[serde(rename_all = "camelCase")]
+#[diesel(belongs_to(Room))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RoomSession {
+    #[serde(skip)]
+    pub id: i32,
+    #[serde(rename = "id")]
+    pub public_id: String,
+    #[serde(skip)]
+    pub room_id: i32,
+    pub started_at: NaiveDateTime,
+    /// `None` while the session is in progress
+    pub ended_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = room_sessions)]
+pub struct NewRoomSession {
+    pub room_id: i32,
+    pub started_at: NaiveDateTime,
+}
+
+#[derive(
+    Queryable,
+    Selectable,
+    Debug,
+    Clone,
+    Serialize,
+    Deserialize,
+    QueryableByName,
+    Associations,
+    Identifiable,
+    ToSchema,
+)]
+#[diesel(table_name = room_events)]
+#[serde(rename_all = "camelCase")]
+#[diesel(belongs_to(RoomSession, foreign_key = session_id))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RoomEvent {
+    pub id: i32,
+    #[serde(skip)]
+    pub session_id: i32,
+    pub kind: String,
+    pub participant_id: Option<String>,
+    #[serde(skip)]
+    pub user_id: Option<i32>,
+    /// State after the change, for the kinds that toggle
+    pub is_enabled: Option<bool>,
+    /// The chat message a `message` event refers to
+    pub message_id: Option<i32>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = room_events)]
+pub struct NewRoomEvent<'a> {
+    pub session_id: i32,
+    pub kind: &'a str,
+    pub participant_id: Option<&'a str>,
+    pub user_id: Option<i32>,
+    pub is_enabled: Option<bool>,
+    pub message_id: Option<i32>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(
+    Queryable,
+    Selectable,
+    Debug,
+    Clone,
+    Serialize,
+    Deserialize,
+    QueryableByName,
+    Identifiable,
+    ToSchema,
+)]
+#[diesel(table_name = organizations)]
+#[serde(rename_all = "camelCase")]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Organization {
+    #[serde(skip)]
+    pub id: i32,
+    #[serde(rename = "id")]
+    pub public_id: String,
+    pub name: String,
+    pub logo_url: Option<String>,
+    pub primary_color: Option<String>,
+    pub accent_color: Option<String>,
+    /// Waiting room backdrop, and the slate HLS viewers see off air
+    pub background_url: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    /// Turned on for clients in the organization, next to the server's own
+    #[serde(default)]
+    pub client_features: Vec<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = organizations)]
+pub struct NewOrganization<'a> {
+    pub name: &'a str,
+    pub logo_url: Option<&'a str>,
+    pub primary_color: Option<&'a str>,
+    pub accent_color: Option<&'a str>,
+    pub background_url: Option<&'a str>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Replaces an organization's branding as a whole, `None` clears the field
+#[derive(AsChangeset)]
+#[diesel(table_name = organizations, treat_none_as_null = true)]
+pub struct OrganizationBranding<'a> {
+    pub logo_url: Option<&'a str>,
+    pub primary_color: Option<&'a str>,
+    pub accent_color: Option<&'a str>,
+    pub background_url: Option<&'a str>,
+    pub updated_at: NaiveDateTime,
+}
@@ -1,6 +1,8 @@
 use dotenvy::dotenv;
 use std::env;
 
+use crate::core::types::enums::observer_disclosure::ObserverDisclosure;
+
 #[derive(Debug, Clone)]
 pub struct AppEnv {
     pub group_id: String,
@@ -8,17 +10,50 @@ pub struct AppEnv {
     pub public_ip: String,
     pub app_port: u16,
     pub client_api_key: String,
+    pub admin_api_key: String,
     pub db_uri: DbUri,
+    pub db_pool: DbPoolConfigs,
     pub redis_uris: Vec<String>,
     pub jwt: JwtConfig,
     pub udp_port_range: UdpPortRange,
     pub grpc_configs: GrpcConfigs,
+    pub alerting: AlertingConfigs,
+    pub matrix: Option<MatrixConfigs>,
+    pub callout: Option<CalloutConfigs>,
+    pub calendar: Option<CalendarConfigs>,
+    pub chat_retention: ChatRetentionConfigs,
+    pub typesense: Option<TypesenseConfigs>,
+    /// New abuse reports are posted here for the moderators
+    pub moderation_webhook_url: Option<String>,
+    pub socket_rate_limits: SocketRateLimits,
+    pub event_queues: EventQueueConfigs,
+    /// Whether hosts are told about observers watching their room
+    pub observer_disclosure: ObserverDisclosure,
+    /// Set with `HLS_MODE=EDGE`, HLS is then served from the origin bucket
+    /// instead of the files this node can see
+    pub hls_edge: Option<HlsEdgeConfigs>,
+    /// Handed to clients by `GET /client-config` instead of built into them
+    pub client: ClientConfigs,
     pub tls_enabled: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct DbUri(pub String);
 
+/// Repositories check connections out on the async workers and block until
+/// one is free, so the pool wants at least a connection per worker
+#[derive(Debug, Clone)]
+pub struct DbPoolConfigs {
+    pub max_size: u32,
+    /// Connections kept open while idle, `None` keeps `max_size` of them
+    pub min_idle: Option<u32>,
+    /// How long a request waits for a free connection before it fails
+    pub checkout_timeout_ms: i64,
+    /// Set as `statement_timeout` on every connection, `0` lets queries run
+    /// as long as they take
+    pub statement_timeout_ms: i64,
+}
+
 #[derive(Debug, Clone)]
 pub struct JwtConfig {
     pub jwt_token: String,
@@ -41,6 +76,130 @@ pub struct GrpcConfigs {
     pub dispatcher_port: u16,
 }
 
+#[derive(Debug, Clone)]
+pub struct AlertingConfigs {
+    pub slack_webhook_url: Option<String>,
+    pub pagerduty_routing_key: Option<String>,
+    pub cooldown_seconds: i64,
+    pub max_join_failure_rate: f64,
+    pub max_p95_join_latency_ms: i64,
+    pub max_node_cpu: f64,
+    pub max_packet_loss: f64,
+}
+
+/// Application service registration of the Matrix bridge, the bridge is
+/// disabled unless `MATRIX_HOMESERVER_URL` is set
+#[derive(Debug, Clone)]
+pub struct MatrixConfigs {
+    pub homeserver_url: String,
+    pub server_name: String,
+    pub as_token: String,
+    pub hs_token: String,
+    pub bot_localpart: String,
+    pub puppet_prefix: String,
+}
+
+/// Outbound phone calls, disabled unless `CALLOUT_PROVIDER` is set.
+/// `account_id` and `secret` are the Twilio account SID and auth token, or the
+/// Vonage application id and its private key in PEM format.
+#[derive(Debug, Clone)]
+pub struct CalloutConfigs {
+    pub provider: String,
+    pub account_id: String,
+    pub secret: String,
+    pub from_number: String,
+    pub cost_per_call_cents: i64,
+    /// Across the deployment, a backstop for the per room cap below
+    pub daily_spend_cap_cents: i64,
+    pub room_daily_spend_cap_cents: i64,
+    /// Calls a host may place over the last hour, across all their rooms
+    pub host_calls_per_hour: i64,
+}
+
+/// Calendar sync of scheduled live streams, disabled unless `CALENDAR_PUBLIC_URL`
+/// is set. A provider is offered once its OAuth client is configured.
+#[derive(Debug, Clone)]
+pub struct CalendarConfigs {
+    /// Where this API is reached from outside, OAuth redirects and webhooks point here
+    pub public_url: String,
+    /// Join links put on the events are this followed by the room code
+    pub join_url: String,
+    pub google: Option<OAuthClientConfigs>,
+    pub outlook: Option<OAuthClientConfigs>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OAuthClientConfigs {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+/// Deployment-wide chat retention, rooms can set their own period on top.
+/// Messages are kept forever unless `CHAT_RETENTION_DAYS` is set.
+#[derive(Debug, Clone)]
+pub struct ChatRetentionConfigs {
+    pub default_days: Option<i32>,
+    pub batch_size: i64,
+    pub interval_seconds: i64,
+}
+
+/// Search index, unless `TYPESENSE_URL` is set search runs on Postgres alone
+#[derive(Debug, Clone)]
+pub struct TypesenseConfigs {
+    pub url: String,
+    pub api_key: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct HlsEdgeConfigs {
+    /// Public URL of the bucket, or CDN, the SFU nodes upload segments to
+    pub origin_url: String,
+    /// Memory kept for segments, shared by every stream this edge serves
+    pub cache_bytes: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientConfigs {
+    /// `stun:`, `turn:` and `turns:` URLs
+    pub ice_servers: Vec<String>,
+    /// Credentials of the `turn:` and `turns:` servers
+    pub turn_username: Option<String>,
+    pub turn_credential: Option<String>,
+    pub max_video_bitrate_kbps: u32,
+    pub max_screen_bitrate_kbps: u32,
+    pub max_audio_bitrate_kbps: u32,
+    /// Most preferred first
+    pub video_codecs: Vec<String>,
+    /// On for every client, organizations can turn on more
+    pub features: Vec<String>,
+    /// Where to open the socket when it isn't served next to the API
+    pub socket_url: Option<String>,
+}
+
+/// Events a single socket may send, per namespace. The legacy `/` namespace
+/// carries RTC signalling and is held to the RTC limits.
+#[derive(Debug, Clone)]
+pub struct SocketRateLimits {
+    pub rtc: EventRate,
+    pub chat: EventRate,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EventRate {
+    pub per_second: f64,
+    pub burst: u32,
+}
+
+/// How many events wait between their producers and the socket side. Once
+/// full, starved downlink reports are dropped and everything else waits.
+#[derive(Debug, Clone, Copy)]
+pub struct EventQueueConfigs {
+    /// Chat events relayed from the outbox
+    pub app_events: usize,
+    /// Callbacks from the SFU nodes and etcd
+    pub dispatcher_callbacks: usize,
+}
+
 impl Default for AppEnv {
     fn default() -> Self {
         Self::new()
@@ -78,11 +237,19 @@ impl AppEnv {
             public_ip: env::var("PUBLIC_IP").unwrap_or_else(|_| "".to_string()),
             app_port: Self::get_env("APP_PORT", 3000),
             client_api_key: env::var("CLIENT_SECRET_KEY").unwrap_or_else(|_| "".to_string()),
+            admin_api_key: env::var("ADMIN_SECRET_KEY").unwrap_or_else(|_| "".to_string()),
             udp_port_range: UdpPortRange {
                 port_min: Self::get_env("PORT_MIN_UDP", 19000),
                 port_max: Self::get_env("PORT_MAX_UDP", 60000),
             },
             db_uri: DbUri(env::var("DATABASE_URL").expect("DATABASE_URL must be set")),
+            db_pool: DbPoolConfigs {
+                max_size: Self::get_env("DB_POOL_MAX_SIZE", 10) as u32,
+                min_idle: Self::get_opt_env("DB_POOL_MIN_IDLE")
+                    .and_then(|min_idle| min_idle.parse::<u32>().ok()),
+                checkout_timeout_ms: Self::get_dur_env("DB_POOL_TIMEOUT_MS", 5_000),
+                statement_timeout_ms: Self::get_dur_env("DB_STATEMENT_TIMEOUT_MS", 0),
+            },
             redis_uris,
             jwt: JwtConfig {
                 jwt_token: env::var("AUTH_JWT_SECRET").expect("AUTH_JWT_SECRET must be set"),
@@ -100,6 +267,118 @@ impl AppEnv {
                 dispatcher_host: Self::get_str_env("DISPATCHER_HOST", "http://[::1]".to_owned()),
                 dispatcher_port: Self::get_env("DISPATCHER_PORT", 50052),
             },
+            alerting: AlertingConfigs {
+                slack_webhook_url: Self::get_opt_env("ALERT_SLACK_WEBHOOK_URL"),
+                pagerduty_routing_key: Self::get_opt_env("ALERT_PAGERDUTY_ROUTING_KEY"),
+                cooldown_seconds: Self::get_dur_env("ALERT_COOLDOWN_SECONDS", 900), // 15 minutes
+                max_join_failure_rate: Self::get_f64_env("ALERT_MAX_JOIN_FAILURE_RATE", 0.05),
+                max_p95_join_latency_ms: Self::get_dur_env("ALERT_MAX_P95_JOIN_LATENCY_MS", 3_000),
+                max_node_cpu: Self::get_f64_env("ALERT_MAX_NODE_CPU", 85.0),
+                max_packet_loss: Self::get_f64_env("ALERT_MAX_PACKET_LOSS", 0.05),
+            },
+            matrix: Self::get_opt_env("MATRIX_HOMESERVER_URL").map(|homeserver_url| {
+                MatrixConfigs {
+                    homeserver_url,
+                    server_name: env::var("MATRIX_SERVER_NAME")
+                        .expect("MATRIX_SERVER_NAME must be set"),
+                    as_token: env::var("MATRIX_AS_TOKEN").expect("MATRIX_AS_TOKEN must be set"),
+                    hs_token: env::var("MATRIX_HS_TOKEN").expect("MATRIX_HS_TOKEN must be set"),
+                    bot_localpart: Self::get_str_env("MATRIX_BOT_LOCALPART", "waterbus".to_owned()),
+                    puppet_prefix: Self::get_str_env(
+                        "MATRIX_PUPPET_PREFIX",
+                        "waterbus_".to_owned(),
+                    ),
+                }
+            }),
+            callout: Self::get_opt_env("CALLOUT_PROVIDER").map(|provider| CalloutConfigs {
+                provider: provider.to_lowercase(),
+                account_id: env::var("CALLOUT_ACCOUNT_ID").expect("CALLOUT_ACCOUNT_ID must be set"),
+                secret: env::var("CALLOUT_SECRET").expect("CALLOUT_SECRET must be set"),
+                from_number: env::var("CALLOUT_FROM_NUMBER")
+                    .expect("CALLOUT_FROM_NUMBER must be set"),
+                cost_per_call_cents: Self::get_i64_env("CALLOUT_COST_PER_CALL_CENTS", 5),
+                daily_spend_cap_cents: Self::get_i64_env("CALLOUT_DAILY_SPEND_CAP_CENTS", 1_000),
+                room_daily_spend_cap_cents: Self::get_i64_env(
+                    "CALLOUT_ROOM_DAILY_SPEND_CAP_CENTS",
+                    200,
+                ),
+                host_calls_per_hour: Self::get_i64_env("CALLOUT_HOST_CALLS_PER_HOUR", 10),
+            }),
+            calendar: Self::get_opt_env("CALENDAR_PUBLIC_URL").map(|public_url| CalendarConfigs {
+                public_url: public_url.trim_end_matches('/').to_owned(),
+                join_url: env::var("CALENDAR_JOIN_URL")
+                    .expect("CALENDAR_JOIN_URL must be set")
+                    .trim_end_matches('/')
+                    .to_owned(),
+                google: Self::get_opt_env("GOOGLE_CALENDAR_CLIENT_ID").map(|client_id| {
+                    OAuthClientConfigs {
+                        client_id,
+                        client_secret: env::var("GOOGLE_CALENDAR_CLIENT_SECRET")
+                            .expect("GOOGLE_CALENDAR_CLIENT_SECRET must be set"),
+                    }
+                }),
+                outlook: Self::get_opt_env("OUTLOOK_CALENDAR_CLIENT_ID").map(|client_id| {
+                    OAuthClientConfigs {
+                        client_id,
+                        client_secret: env::var("OUTLOOK_CALENDAR_CLIENT_SECRET")
+                            .expect("OUTLOOK_CALENDAR_CLIENT_SECRET must be set"),
+                    }
+                }),
+            }),
+            chat_retention: ChatRetentionConfigs {
+                default_days: Self::get_opt_env("CHAT_RETENTION_DAYS")
+                    .and_then(|days| days.parse::<i32>().ok())
+                    .filter(|days| *days > 0),
+                batch_size: Self::get_i64_env("CHAT_RETENTION_BATCH_SIZE", 1_000),
+                interval_seconds: Self::get_dur_env("CHAT_RETENTION_INTERVAL_SECONDS", 3_600), // an hour
+            },
+            typesense: Self::get_opt_env("TYPESENSE_URL").map(|url| TypesenseConfigs {
+                url: url.trim_end_matches('/').to_owned(),
+                api_key: env::var("TYPESENSE_API_KEY").expect("TYPESENSE_API_KEY must be set"),
+            }),
+            moderation_webhook_url: Self::get_opt_env("MODERATION_WEBHOOK_URL"),
+            socket_rate_limits: SocketRateLimits {
+                // Joining sends a burst of ICE candidates and subscriptions
+                rtc: EventRate {
+                    per_second: Self::get_f64_env("SOCKET_RTC_EVENTS_PER_SECOND", 50.0),
+                    burst: Self::get_env("SOCKET_RTC_EVENT_BURST", 200) as u32,
+                },
+                chat: EventRate {
+                    per_second: Self::get_f64_env("SOCKET_CHAT_EVENTS_PER_SECOND", 5.0),
+                    burst: Self::get_env("SOCKET_CHAT_EVENT_BURST", 20) as u32,
+                },
+            },
+            event_queues: EventQueueConfigs {
+                app_events: Self::get_usize_env("APP_EVENT_QUEUE_CAPACITY", 10_000).max(1),
+                dispatcher_callbacks: Self::get_usize_env("DISPATCHER_QUEUE_CAPACITY", 10_000)
+                    .max(1),
+            },
+            observer_disclosure: Self::get_opt_env("OBSERVER_DISCLOSURE")
+                .and_then(|disclosure| disclosure.to_lowercase().parse().ok())
+                .unwrap_or(ObserverDisclosure::Hosts),
+            hls_edge: Self::get_opt_env("HLS_MODE")
+                .filter(|mode| mode.eq_ignore_ascii_case("edge"))
+                .map(|_| HlsEdgeConfigs {
+                    origin_url: env::var("HLS_ORIGIN_URL")
+                        .expect("HLS_ORIGIN_URL must be set")
+                        .trim_end_matches('/')
+                        .to_owned(),
+                    cache_bytes: Self::get_env("HLS_EDGE_CACHE_MB", 256) as usize * 1024 * 1024,
+                }),
+            client: ClientConfigs {
+                ice_servers: Self::get_list_env(
+                    "CLIENT_ICE_SERVERS",
+                    "stun:stun.l.google.com:19302",
+                ),
+                turn_username: Self::get_opt_env("CLIENT_TURN_USERNAME"),
+                turn_credential: Self::get_opt_env("CLIENT_TURN_CREDENTIAL"),
+                max_video_bitrate_kbps: Self::get_u32_env("CLIENT_MAX_VIDEO_BITRATE_KBPS", 1_500),
+                max_screen_bitrate_kbps: Self::get_u32_env("CLIENT_MAX_SCREEN_BITRATE_KBPS", 2_500),
+                max_audio_bitrate_kbps: Self::get_u32_env("CLIENT_MAX_AUDIO_BITRATE_KBPS", 64),
+                video_codecs: Self::get_list_env("CLIENT_VIDEO_CODECS", "vp8,h264,vp9,av1"),
+                features: Self::get_list_env("CLIENT_FEATURES", ""),
+                socket_url: Self::get_opt_env("CLIENT_SOCKET_URL"),
+            },
             tls_enabled: std::env::var("TLS_ENABLED")
                 .unwrap_or_else(|_| "false".into())
                 .to_lowercase()
@@ -127,4 +406,46 @@ impl AppEnv {
             .and_then(|v| v.parse().ok())
             .unwrap_or(default)
     }
+
+    fn get_u32_env(var: &str, default: u32) -> u32 {
+        env::var(var)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+    }
+
+    fn get_i64_env(var: &str, default: i64) -> i64 {
+        env::var(var)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+    }
+
+    fn get_usize_env(var: &str, default: usize) -> usize {
+        env::var(var)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+    }
+
+    fn get_f64_env(var: &str, default: f64) -> f64 {
+        env::var(var)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+    }
+
+    fn get_opt_env(var: &str) -> Option<String> {
+        env::var(var).ok().filter(|v| !v.trim().is_empty())
+    }
+
+    /// Comma separated, blanks dropped
+    fn get_list_env(var: &str, default: &str) -> Vec<String> {
+        env::var(var)
+            .unwrap_or_else(|_| default.to_owned())
+            .split(',')
+            .map(|s| s.trim().to_owned())
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
 }
@@ -0,0 +1,167 @@
+use std::{
+    fmt::{self, Write},
+    sync::{Arc, Mutex},
+};
+
+use diesel::r2d2::{
+    HandleEvent,
+    event::{CheckoutEvent, TimeoutEvent},
+};
+
+use crate::core::database::db::DbPool;
+
+/// Upper bounds of the checkout wait buckets, in seconds. A healthy pool
+/// hands out connections well under the first one.
+const BUCKETS: [f64; 9] = [0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 2.5, 5.0];
+
+#[derive(Debug, Default)]
+struct CheckoutStats {
+    /// Cumulative, like the buckets of a Prometheus histogram
+    buckets: [u64; BUCKETS.len()],
+    count: u64,
+    sum_seconds: f64,
+    timeouts: u64,
+}
+
+/// How long requests wait for a database connection and how often they give
+/// up, fed by the pool's event handler. Rendered next to the pool's own
+/// gauges on the metrics endpoint.
+#[derive(Clone, Default)]
+pub struct DbPoolMetrics {
+    stats: Arc<Mutex<CheckoutStats>>,
+}
+
+impl fmt::Debug for DbPoolMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DbPoolMetrics").finish_non_exhaustive()
+    }
+}
+
+impl DbPoolMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn record_checkout(&self, seconds: f64) {
+        let mut stats = self.stats.lock().unwrap();
+
+        for (bucket, bound) in stats.buckets.iter_mut().zip(BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        stats.count += 1;
+        stats.sum_seconds += seconds;
+    }
+
+    fn record_timeout(&self) {
+        self.stats.lock().unwrap().timeouts += 1;
+    }
+
+    /// Prometheus text exposition format
+    pub fn render(&self, pool: &DbPool) -> String {
+        let state = pool.state();
+        let stats = self.stats.lock().unwrap();
+        let mut out = String::new();
+
+        for (name, help, value) in [
+            (
+                "waterbus_db_pool_connections_in_use",
+                "Connections checked out of the pool",
+                state.connections - state.idle_connections,
+            ),
+            (
+                "waterbus_db_pool_connections_idle",
+                "Open connections waiting in the pool",
+                state.idle_connections,
+            ),
+            (
+                "waterbus_db_pool_connections_max",
+                "Connections the pool may open",
+                pool.max_size(),
+            ),
+        ] {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} gauge");
+            let _ = writeln!(out, "{name} {value}");
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP waterbus_db_pool_wait_seconds Time spent waiting for a connection"
+        );
+        let _ = writeln!(out, "# TYPE waterbus_db_pool_wait_seconds histogram");
+        for (bucket, bound) in stats.buckets.iter().zip(BUCKETS) {
+            let _ = writeln!(
+                out,
+                "waterbus_db_pool_wait_seconds_bucket{{le=\"{bound}\"}} {bucket}"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "waterbus_db_pool_wait_seconds_bucket{{le=\"+Inf\"}} {}",
+            stats.count
+        );
+        let _ = writeln!(
+            out,
+            "waterbus_db_pool_wait_seconds_sum {}",
+            stats.sum_seconds
+        );
+        let _ = writeln!(out, "waterbus_db_pool_wait_seconds_count {}", stats.count);
+
+        let _ = writeln!(
+            out,
+            "# HELP waterbus_db_pool_timeouts_total Checkouts that gave up waiting for a connection"
+        );
+        let _ = writeln!(out, "# TYPE waterbus_db_pool_timeouts_total counter");
+        let _ = writeln!(out, "waterbus_db_pool_timeouts_total {}", stats.timeouts);
+
+        out
+    }
+}
+
+impl HandleEvent for DbPoolMetrics {
+    fn handle_checkout(&self, event: CheckoutEvent) {
+        self.record_checkout(event.duration().as_secs_f64());
+    }
+
+    fn handle_timeout(&self, _event: TimeoutEvent) {
+        self.record_timeout();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use diesel::{
+        pg::PgConnection,
+        r2d2::{ConnectionManager, Pool},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_render_waits_and_timeouts() {
+        // Never connects, nothing is checked out of it
+        let pool = Pool::builder()
+            .max_size(4)
+            .min_idle(Some(0))
+            .build_unchecked(ConnectionManager::<PgConnection>::new(
+                "postgres://localhost/unused",
+            ));
+
+        let metrics = DbPoolMetrics::new();
+        metrics.record_checkout(0.002);
+        metrics.record_checkout(0.3);
+        metrics.record_timeout();
+
+        let out = metrics.render(&pool);
+
+        assert!(out.contains("waterbus_db_pool_connections_in_use 0"));
+        assert!(out.contains("waterbus_db_pool_connections_max 4"));
+        assert!(out.contains("waterbus_db_pool_wait_seconds_bucket{le=\"0.001\"} 0"));
+        assert!(out.contains("waterbus_db_pool_wait_seconds_bucket{le=\"0.005\"} 1"));
+        assert!(out.contains("waterbus_db_pool_wait_seconds_bucket{le=\"0.5\"} 2"));
+        assert!(out.contains("waterbus_db_pool_wait_seconds_count 2"));
+        assert!(out.contains("waterbus_db_pool_timeouts_total 1"));
+    }
+}
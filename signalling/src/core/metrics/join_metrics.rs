@@ -0,0 +1,104 @@
+use std::{
+    fmt::{self, Write},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Upper bounds of the join latency buckets, in seconds. A join served from
+/// the room state cache lands in the first few.
+const BUCKETS: [f64; 10] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+#[derive(Debug, Default)]
+struct JoinStats {
+    /// Cumulative, like the buckets of a Prometheus histogram
+    buckets: [u64; BUCKETS.len()],
+    count: u64,
+    sum_seconds: f64,
+}
+
+/// How long the REST room join takes, from resolving the room to the response
+#[derive(Clone, Default)]
+pub struct JoinMetrics {
+    stats: Arc<Mutex<JoinStats>>,
+}
+
+impl fmt::Debug for JoinMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JoinMetrics").finish_non_exhaustive()
+    }
+}
+
+impl JoinMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, latency: Duration) {
+        let seconds = latency.as_secs_f64();
+        let mut stats = self.stats.lock().unwrap();
+
+        for (bucket, bound) in stats.buckets.iter_mut().zip(BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        stats.count += 1;
+        stats.sum_seconds += seconds;
+    }
+
+    /// Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let stats = self.stats.lock().unwrap();
+        let mut out = String::new();
+
+        let _ = writeln!(
+            out,
+            "# HELP waterbus_room_join_duration_seconds Time taken to admit a user to a room"
+        );
+        let _ = writeln!(out, "# TYPE waterbus_room_join_duration_seconds histogram");
+        for (bucket, bound) in stats.buckets.iter().zip(BUCKETS) {
+            let _ = writeln!(
+                out,
+                "waterbus_room_join_duration_seconds_bucket{{le=\"{bound}\"}} {bucket}"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "waterbus_room_join_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+            stats.count
+        );
+        let _ = writeln!(
+            out,
+            "waterbus_room_join_duration_seconds_sum {}",
+            stats.sum_seconds
+        );
+        let _ = writeln!(
+            out,
+            "waterbus_room_join_duration_seconds_count {}",
+            stats.count
+        );
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_join_latency() {
+        let metrics = JoinMetrics::new();
+        metrics.record(Duration::from_millis(3));
+        metrics.record(Duration::from_millis(80));
+        metrics.record(Duration::from_secs(7));
+
+        let out = metrics.render();
+
+        assert!(out.contains("waterbus_room_join_duration_seconds_bucket{le=\"0.005\"} 1"));
+        assert!(out.contains("waterbus_room_join_duration_seconds_bucket{le=\"0.1\"} 2"));
+        assert!(out.contains("waterbus_room_join_duration_seconds_bucket{le=\"5\"} 2"));
+        assert!(out.contains("waterbus_room_join_duration_seconds_bucket{le=\"+Inf\"} 3"));
+        assert!(out.contains("waterbus_room_join_duration_seconds_count 3"));
+    }
+}
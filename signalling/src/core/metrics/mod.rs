@@ -0,0 +1,4 @@
+pub mod db_pool_metrics;
+pub mod join_metrics;
+pub mod queue_metrics;
+pub mod socket_metrics;
@@ -0,0 +1,132 @@
+use std::{
+    fmt::{self, Write},
+    sync::{Arc, Mutex},
+};
+
+use dispatcher::infrastructure::event_queue::QueueStats;
+
+/// Depth and overflow of the bounded event queues. A queue that stays near
+/// its capacity means the socket side can't keep up with its producers.
+#[derive(Clone, Default)]
+pub struct QueueMetrics {
+    queues: Arc<Mutex<Vec<Arc<QueueStats>>>>,
+}
+
+impl fmt::Debug for QueueMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueueMetrics").finish_non_exhaustive()
+    }
+}
+
+impl QueueMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&self, stats: Arc<QueueStats>) {
+        self.queues.lock().unwrap().push(stats);
+    }
+
+    /// Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let queues = self.queues.lock().unwrap();
+        let mut out = String::new();
+
+        let _ = writeln!(
+            out,
+            "# HELP waterbus_event_queue_depth Events waiting in the queue"
+        );
+        let _ = writeln!(out, "# TYPE waterbus_event_queue_depth gauge");
+        for stats in queues.iter() {
+            let _ = writeln!(
+                out,
+                "waterbus_event_queue_depth{{queue=\"{}\"}} {}",
+                stats.name,
+                stats.depth()
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP waterbus_event_queue_capacity Events the queue holds before it overflows"
+        );
+        let _ = writeln!(out, "# TYPE waterbus_event_queue_capacity gauge");
+        for stats in queues.iter() {
+            let _ = writeln!(
+                out,
+                "waterbus_event_queue_capacity{{queue=\"{}\"}} {}",
+                stats.name, stats.capacity
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP waterbus_event_queue_dropped_total Events dropped from a full queue"
+        );
+        let _ = writeln!(out, "# TYPE waterbus_event_queue_dropped_total counter");
+        for stats in queues.iter() {
+            for (kind, dropped) in stats.dropped() {
+                let _ = writeln!(
+                    out,
+                    "waterbus_event_queue_dropped_total{{queue=\"{}\",kind=\"{kind}\"}} {dropped}",
+                    stats.name
+                );
+            }
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP waterbus_event_queue_blocked_total Sends that waited for room in a full queue"
+        );
+        let _ = writeln!(out, "# TYPE waterbus_event_queue_blocked_total counter");
+        for stats in queues.iter() {
+            let _ = writeln!(
+                out,
+                "waterbus_event_queue_blocked_total{{queue=\"{}\"}} {}",
+                stats.name,
+                stats.blocked()
+            );
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dispatcher::infrastructure::event_queue::{self, Overflow, QueuedEvent};
+
+    use super::*;
+
+    struct Probe;
+
+    impl QueuedEvent for Probe {
+        fn kind(&self) -> &'static str {
+            "probe"
+        }
+
+        fn overflow(&self) -> Overflow {
+            Overflow::DropOldest
+        }
+    }
+
+    #[tokio::test]
+    async fn test_render_depth_and_drops() {
+        let (sender, _receiver) = event_queue::bounded::<Probe>("probes", 2);
+        for _ in 0..3 {
+            sender.send(Probe).await.unwrap();
+        }
+
+        let metrics = QueueMetrics::new();
+        metrics.register(sender.stats());
+
+        let out = metrics.render();
+
+        assert!(out.contains("waterbus_event_queue_depth{queue=\"probes\"} 2"));
+        assert!(out.contains("waterbus_event_queue_capacity{queue=\"probes\"} 2"));
+        assert!(
+            out.contains("waterbus_event_queue_dropped_total{queue=\"probes\",kind=\"probe\"} 1")
+        );
+        assert!(out.contains("waterbus_event_queue_blocked_total{queue=\"probes\"} 0"));
+    }
+}
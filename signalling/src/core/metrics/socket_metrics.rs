@@ -0,0 +1,191 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write},
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+/// Upper bounds of the latency buckets, in seconds
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Debug, Default)]
+struct HandlerStats {
+    /// Cumulative, like the buckets of a Prometheus histogram
+    buckets: [u64; BUCKETS.len()],
+    count: u64,
+    sum_seconds: f64,
+    errors: u64,
+}
+
+/// Latency and error counts of the socket handlers, by event name.
+///
+/// Kept in memory per signalling instance and scraped from the metrics
+/// endpoint, Prometheus sums them across instances.
+#[derive(Clone, Default)]
+pub struct SocketMetrics {
+    handlers: Arc<Mutex<BTreeMap<&'static str, HandlerStats>>>,
+}
+
+impl fmt::Debug for SocketMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SocketMetrics").finish_non_exhaustive()
+    }
+}
+
+impl SocketMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Times a handler until the returned timer is dropped, so early returns
+    /// are counted too
+    pub fn start(&self, event: &'static str) -> HandlerTimer {
+        HandlerTimer {
+            metrics: self.clone(),
+            event,
+            started_at: Instant::now(),
+            is_failed: false,
+        }
+    }
+
+    fn record(&self, event: &'static str, seconds: f64, is_failed: bool) {
+        let mut handlers = self.handlers.lock().unwrap();
+        let stats = handlers.entry(event).or_default();
+
+        for (bucket, bound) in stats.buckets.iter_mut().zip(BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        stats.count += 1;
+        stats.sum_seconds += seconds;
+        if is_failed {
+            stats.errors += 1;
+        }
+    }
+
+    /// Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let handlers = self.handlers.lock().unwrap();
+        let mut out = String::new();
+
+        let _ = writeln!(
+            out,
+            "# HELP waterbus_socket_handler_duration_seconds Time spent handling a socket event"
+        );
+        let _ = writeln!(
+            out,
+            "# TYPE waterbus_socket_handler_duration_seconds histogram"
+        );
+        for (event, stats) in handlers.iter() {
+            for (bucket, bound) in stats.buckets.iter().zip(BUCKETS) {
+                let _ = writeln!(
+                    out,
+                    "waterbus_socket_handler_duration_seconds_bucket{{event=\"{event}\",le=\"{bound}\"}} {bucket}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "waterbus_socket_handler_duration_seconds_bucket{{event=\"{event}\",le=\"+Inf\"}} {}",
+                stats.count
+            );
+            let _ = writeln!(
+                out,
+                "waterbus_socket_handler_duration_seconds_sum{{event=\"{event}\"}} {}",
+                stats.sum_seconds
+            );
+            let _ = writeln!(
+                out,
+                "waterbus_socket_handler_duration_seconds_count{{event=\"{event}\"}} {}",
+                stats.count
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP waterbus_socket_handler_errors_total Socket events whose handler failed"
+        );
+        let _ = writeln!(out, "# TYPE waterbus_socket_handler_errors_total counter");
+        for (event, stats) in handlers.iter() {
+            let _ = writeln!(
+                out,
+                "waterbus_socket_handler_errors_total{{event=\"{event}\"}} {}",
+                stats.errors
+            );
+        }
+
+        out
+    }
+}
+
+pub struct HandlerTimer {
+    metrics: SocketMetrics,
+    event: &'static str,
+    started_at: Instant,
+    is_failed: bool,
+}
+
+impl HandlerTimer {
+    pub fn fail(&mut self) {
+        self.is_failed = true;
+    }
+}
+
+impl Drop for HandlerTimer {
+    fn drop(&mut self) {
+        // A handler unwinding from a panic failed too
+        self.metrics.record(
+            self.event,
+            self.started_at.elapsed().as_secs_f64(),
+            self.is_failed || std::thread::panicking(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_histogram_and_errors() {
+        let metrics = SocketMetrics::new();
+        metrics.record("room.publish", 0.02, false);
+        metrics.record("room.publish", 3.0, true);
+
+        let out = metrics.render();
+
+        assert!(out.contains(
+            "waterbus_socket_handler_duration_seconds_bucket{event=\"room.publish\",le=\"0.01\"} 0"
+        ));
+        assert!(out.contains(
+            "waterbus_socket_handler_duration_seconds_bucket{event=\"room.publish\",le=\"0.025\"} 1"
+        ));
+        assert!(out.contains(
+            "waterbus_socket_handler_duration_seconds_bucket{event=\"room.publish\",le=\"5\"} 2"
+        ));
+        assert!(
+            out.contains(
+                "waterbus_socket_handler_duration_seconds_count{event=\"room.publish\"} 2"
+            )
+        );
+        assert!(out.contains("waterbus_socket_handler_errors_total{event=\"room.publish\"} 1"));
+    }
+
+    #[test]
+    fn test_timer_records_on_drop() {
+        let metrics = SocketMetrics::new();
+
+        {
+            let mut timer = metrics.start("room.subscribe");
+            timer.fail();
+        }
+
+        let out = metrics.render();
+        assert!(out.contains(
+            "waterbus_socket_handler_duration_seconds_count{event=\"room.subscribe\"} 1"
+        ));
+        assert!(out.contains("waterbus_socket_handler_errors_total{event=\"room.subscribe\"} 1"));
+    }
+}
@@ -1,8 +1,10 @@
+pub mod alerting;
 pub mod api;
 pub mod database;
 pub mod dtos;
 pub mod entities;
 pub mod env;
+pub mod metrics;
 pub mod socket;
 pub mod types;
 pub mod utils;
@@ -0,0 +1,154 @@
+use socketioxide::{adapter::Adapter, extract::SocketRef};
+use tracing::error;
+
+use crate::core::{
+    metrics::socket_metrics::{HandlerTimer, SocketMetrics},
+    types::{
+        enums::ws_event::WsEvent,
+        responses::socket_response::{ResyncResponse, SocketErrorResponse},
+    },
+    utils::{
+        event_budget::EventBudget,
+        panic_guard::{CallIds, HandlerPanic, PanicGuard, PayloadSummary},
+    },
+};
+
+type HandlerGuard = PanicGuard<Box<dyn FnOnce(HandlerPanic) + Send>>;
+
+/// Set on `/` sockets next to their RTC `EventBudget`, so chat events there
+/// are held to the chat rate like on `/chat`
+#[derive(Clone)]
+pub struct ChatEventBudget(pub EventBudget);
+
+/// Which of the socket's budgets an event is taken from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    Rtc,
+    Chat,
+    /// Leaving is never held back, whatever the socket sent before
+    Unlimited,
+}
+
+/// Held by a handler for as long as it runs, it times the handler and guards
+/// it against panics
+pub struct HandlerScope {
+    _guard: HandlerGuard,
+    timer: HandlerTimer,
+}
+
+impl HandlerScope {
+    /// Counts the handler as failed in the metrics
+    pub fn fail(&mut self) {
+        self.timer.fail();
+    }
+}
+
+/// What every handler does before it looks at its payload. `None` once the
+/// socket sends faster than its budget allows, the event is then counted as
+/// failed and the handler returns. `payload` is only called if the handler
+/// panics.
+pub fn begin<A: Adapter>(
+    socket: &SocketRef<A>,
+    metrics: &SocketMetrics,
+    event: WsEvent,
+    budget: Budget,
+    payload: Option<fn() -> PayloadSummary>,
+) -> Option<HandlerScope> {
+    let mut scope = HandlerScope {
+        _guard: _guard_handler(socket, event, payload),
+        timer: metrics.start(event.to_str()),
+    };
+
+    let is_within_budget = match budget {
+        Budget::Rtc => _take_event_budget(socket),
+        Budget::Chat => _take_chat_event_budget(socket),
+        Budget::Unlimited => true,
+    };
+
+    if !is_within_budget {
+        scope.fail();
+        return None;
+    }
+
+    Some(scope)
+}
+
+/// Takes one event from the socket's budget, false once the socket sends faster
+/// than its namespace allows
+fn _take_event_budget<A: Adapter>(socket: &SocketRef<A>) -> bool {
+    socket
+        .extensions
+        .get::<EventBudget>()
+        .is_none_or(|budget| budget.try_take())
+}
+
+/// Like `_take_event_budget`, but from the chat budget on namespaces that
+/// also carry RTC signalling
+fn _take_chat_event_budget<A: Adapter>(socket: &SocketRef<A>) -> bool {
+    match socket.extensions.get::<ChatEventBudget>() {
+        Some(ChatEventBudget(budget)) => budget.try_take(),
+        None => _take_event_budget(socket),
+    }
+}
+
+/// A panic in the handler is logged and reported to the client, and a client
+/// in a call is asked to resync so the SFU rebuilds its peer connections over
+/// whatever the handler left behind. The call is read before the handler runs
+/// and the client is told from a task of its own, nothing blocks while the
+/// handler unwinds.
+fn _guard_handler<A: Adapter>(
+    socket: &SocketRef<A>,
+    event: WsEvent,
+    payload: Option<fn() -> PayloadSummary>,
+) -> HandlerGuard {
+    let socket = socket.clone();
+    let call = socket.extensions.get::<CallIds>();
+
+    PanicGuard::new(
+        event.to_str(),
+        call,
+        payload,
+        Box::new(move |panic: HandlerPanic| {
+            error!(
+                "{} handler panicked on socket {}: {}, call: {:?}, payload: {}",
+                panic.event,
+                socket.id,
+                panic.message,
+                panic.call,
+                panic
+                    .payload
+                    .as_ref()
+                    .map_or_else(|| "none".to_owned(), |payload| payload.to_string())
+            );
+
+            let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+                return;
+            };
+
+            runtime.spawn(async move {
+                let _ = socket
+                    .emit(
+                        WsEvent::SystemError.to_str(),
+                        &SocketErrorResponse {
+                            event: panic.event.to_owned(),
+                            code: "handler-panicked".to_owned(),
+                            message: "The server failed to handle this event".to_owned(),
+                        },
+                    )
+                    .ok();
+
+                if let Some(call) = panic.call {
+                    let _ = socket
+                        .emit(
+                            WsEvent::RoomResync.to_str(),
+                            &ResyncResponse {
+                                room_id: call.room_id,
+                                participant_id: call.participant_id,
+                            },
+                        )
+                        .ok();
+                }
+            });
+        }),
+    )
+}
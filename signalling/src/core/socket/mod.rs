@@ -1,16 +1,26 @@
-use std::{str::FromStr, time::Duration};
+mod handler;
+
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
 use anyhow::anyhow;
-use async_channel::Receiver;
+use chrono::Utc;
 use dispatcher::{
     dispatcher_manager::{DispatcherConfigs, DispatcherManager},
     domain::DispatcherCallback,
+    infrastructure::{
+        cache::cache_manager::ClientMetadata,
+        event_queue::{self, EventReceiver},
+    },
 };
 use salvo::prelude::*;
+use serde::Serialize;
 use socketioxide::{
     ParserConfig, SocketIo,
     adapter::{Adapter, Emitter},
-    extract::{Data, Extension, SocketRef, State},
+    extract::{Data, Extension, SocketRef, State, TryData},
     handler::ConnectHandler,
     socket::Sid,
 };
@@ -20,45 +30,175 @@ use socketioxide_redis::{
 };
 use tower::ServiceBuilder;
 use tower_http::cors::CorsLayer;
-use tracing::{info, warn};
+use tracing::{debug, error, info, warn};
 use waterbus_proto::{
     AddPublisherCandidateRequest, AddSubscriberCandidateRequest, JoinRoomRequest, LeaveRoomRequest,
-    MigratePublisherRequest, PublisherRenegotiationRequest, SetCameraType, SetEnabledRequest,
-    SetScreenSharingRequest, SetSubscriberSdpRequest, SubscribeRequest,
+    MigratePublisherRequest, PublisherRenegotiationRequest, SetBroadcastCutRequest, SetCameraType,
+    SetEgressRequest, SetEnabledRequest, SetQosClassRequest, SetRecordingPausedRequest,
+    SetScreenSharingRequest, SetSpeakerRequest, SetStageModeRequest, SetSubscriberSdpRequest,
+    SetViewportRequest, StopSlateRequest, SubscribeRequest, TrackInfo, UnpublishRequest,
+    ViewportTile,
 };
 
 use crate::{
     core::{
-        dtos::socket::socket_dto::{
-            AnswerSubscribeDto, JoinRoomDto, MigrateConnectionDto, PublisherCandidateDto,
-            PublisherRenegotiationDto, SetCameraTypeDto, SetEnabledDto, SetHandRaisingDto,
-            SetScreenSharingDto, SubscribeDto, SubscriberCandidateDto,
+        alerting::alert_manager::AlertManager,
+        dtos::{
+            common::pagination_dto::PaginationDto,
+            socket::socket_dto::{
+                AnswerSubscribeDto, BotJoinRoomDto, BotMessageDto, ChatRoomDto, ClientMetadataDto,
+                ClientStatsDto, JoinRoomDto, MigrateConnectionDto, PublisherCandidateDto,
+                PublisherRenegotiationDto, ReactionDto, RoomReportDto, SetBroadcastCutDto,
+                SetCameraTypeDto, SetEnabledDto, SetHandRaisingDto, SetQosClassDto,
+                SetRecordingPausedDto, SetRoomTypeDto, SetScreenSharingDto, SetSpeakerDto,
+                SetStageModeDto, SubscribeDto, SubscriberCandidateDto, TrackInfoDto,
+                ViewportUpdateDto,
+            },
         },
-        env::app_env::AppEnv,
+        entities::models::{Bot, LiveSchedule, MembersRoleEnum, Participant, RoomType},
+        env::app_env::{AppEnv, SocketRateLimits},
+        metrics::{queue_metrics::QueueMetrics, socket_metrics::SocketMetrics},
         types::{
             app_channel::AppEvent,
-            enums::ws_event::WsEvent,
-            responses::socket_response::{
-                CameraTypeResponse, EnabledResponse, HandleRaisingResponse, IceCandidate,
-                JoinRoomResponse, NewUserJoinedResponse, ParticipantHasLeftResponse,
-                RenegotiateResponse, ScreenSharingResponse, SubscribeParticipantResponse,
-                SubscribeResponse, SubscriberRenegotiationResponse, SubsriberCandidateResponse,
+            enums::{
+                bot_scope::BotScope, observer_disclosure::ObserverDisclosure,
+                room_event_kind::RoomEventKind, ws_event::WsEvent,
+            },
+            errors::{bot_error::BotError, room_error::RoomError},
+            responses::{
+                room_response::RoomResponse,
+                socket_response::{
+                    BroadcastCutResponse, CameraTypeResponse, CodecMismatchResponse,
+                    EnabledResponse, HandleRaisingResponse, HlsStreamResponse, IceCandidate,
+                    JoinRoomResponse, LiveStartedResponse, NewUserJoinedResponse,
+                    NodeFailoverResponse, ObserveRoomResponse, ObserversResponse,
+                    ParticipantHasLeftResponse, PublishedTrackResponse, PublishedTracksResponse,
+                    QosClassResponse, ReactionResponse, RecordingPausedResponse,
+                    RenegotiateResponse, ReportSubmittedResponse, RoomClosedResponse,
+                    RoomInsightsResponse, RoomTypeResponse, ScreenSharingResponse,
+                    SlotAvailableResponse, SpeakerResponse, StageModeResponse,
+                    SubscribeParticipantResponse, SubscribeResponse,
+                    SubscriberRenegotiationResponse, SubsriberCandidateResponse,
+                    SuggestHlsResponse,
+                },
             },
         },
-        utils::jwt_utils::JwtUtils,
+        utils::{
+            client_stats_store::ClientStatsStore,
+            event_budget::EventBudget,
+            hls_session_store::{HlsSession, HlsSessionStore},
+            join_token_store::JoinTokenStore,
+            jwt_utils::{JwtUtils, ObserverClaims},
+            overflow_queue::OverflowQueue,
+            panic_guard::{self, CallIds, PayloadSummary},
+            room_insights_store::{RoomInsightsStore, network_score, talk_time_balance},
+        },
     },
     features::{
+        bot::{
+            repository::BotRepositoryImpl,
+            service::{BotService, BotServiceImpl},
+        },
+        chat::{
+            outbox::OutboxSignal,
+            repository::ChatRepositoryImpl,
+            service::{ChatService, ChatServiceImpl},
+        },
+        moderation::service::{AbuseReports, ModerationService},
         room::{
-            repository::RoomRepositoryImpl,
-            service::{RoomService, RoomServiceImpl},
+            policy::RoomPolicy,
+            repository::{MediaChange, RoomRepositoryImpl},
+            service::{RoomService, RoomServiceImpl, is_room_full},
         },
+        schedule::service::{LiveSchedules, ScheduleService},
+        timeline::service::{RoomTimelines, TimelineEntry, TimelineService},
         user::repository::UserRepositoryImpl,
     },
 };
 
+use handler::{Budget, ChatEventBudget};
+
+/// Kept for clients from before the split, serves RTC signalling, chat and bots
+const LEGACY_NAMESPACE: &str = "/";
+pub const SOCKET_PATH: &str = "/socket.io";
+/// Publishing, subscribing and the in-call controls, people only
+pub const RTC_NAMESPACE: &str = "/rtc";
+/// Chat delivery for text-only clients and bots, none of the RTC handlers
+pub const CHAT_NAMESPACE: &str = "/chat";
+
+/// Upper bound on the playlists handed to a subscriber falling back to HLS
+const MAX_HLS_STREAMS: i64 = 100;
+
+/// HLS sessions get fresh URLs this long before theirs expire, a player
+/// holding a playlist open should never see a 401
+const HLS_REFRESH_MARGIN_SECS: i64 = 5 * 60;
+
+const HLS_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Participants of a dead node that haven't rejoined by then are gone for good
+const DETACHED_PARTICIPANT_TTL_SECS: i64 = 2 * 60;
+
+const DETACHED_PURGE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often hosts get the room's insights
+const ROOM_INSIGHTS_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Client stats older than this don't count towards the network score, the
+/// client left or stopped reporting
+const ROOM_INSIGHTS_STATS_WINDOW_MS: i64 = 30_000;
+
+/// Matches the participant columns the client metadata is stored in
+const MAX_CLIENT_METADATA_LEN: usize = 64;
+
+/// More tiles than any layout shows at once, larger updates are dropped
+const MAX_VIEWPORT_TILES: usize = 100;
+
+/// Room for a multi-codepoint emoji, reactions aren't messages
+const MAX_REACTION_LEN: usize = 32;
+
+/// QoS classes the SFU knows publishers by, attendees are degraded first
+const QOS_CLASS_ATTENDEE: i32 = 0;
+const QOS_CLASS_PRESENTER: i32 = 1;
+const QOS_CLASS_HOST: i32 = 2;
+
 #[derive(Clone)]
 pub struct UserId(pub String);
 
+/// Set on sockets authenticated with a bot token instead of a user JWT
+#[derive(Clone)]
+pub struct BotIdentity(pub Bot);
+
+/// Set on `/rtc` sockets authenticated with an observer token, these have no `UserId`
+#[derive(Clone)]
+pub struct ObserverIdentity(pub ObserverClaims);
+
+/// Set on `/chat` sockets of users waiting in a full call's line, they give up
+/// their place when the socket goes
+#[derive(Clone)]
+struct OverflowViewer {
+    room_id: String,
+    user_id: i32,
+}
+
+/// Set on `/rtc` sockets asked to rejoin after their SFU node went away, what
+/// the rejoin restores as the participant's row had it
+#[derive(Clone)]
+struct PendingFailover {
+    participant_id: String,
+    is_audio_enabled: bool,
+    is_video_enabled: bool,
+    is_hand_raising: bool,
+    video_codecs: Vec<String>,
+}
+
+/// Video codecs the client last subscribed with, the offers pushed to it after
+/// a failover are made for these
+#[derive(Clone)]
+struct SubscriberCodecs(Vec<String>);
+
+type SocketChatService =
+    ChatServiceImpl<ChatRepositoryImpl, RoomRepositoryImpl, UserRepositoryImpl>;
+
 #[handler(tags("socket.io"))]
 async fn version() -> &'static str {
     "[v3] Waterbus Service written in Rust"
@@ -87,19 +227,35 @@ impl RemoteUserCnt {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn get_socket_router(
     env: &AppEnv,
     jwt_utils: JwtUtils,
     room_service: RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
-    message_receiver: Receiver<AppEvent>,
-) -> Result<Router, Box<dyn std::error::Error>> {
+    schedule_service: LiveSchedules,
+    chat_service: SocketChatService,
+    bot_service: BotServiceImpl<BotRepositoryImpl>,
+    moderation_service: AbuseReports,
+    timeline_service: RoomTimelines,
+    outbox_signal: OutboxSignal,
+    socket_metrics: SocketMetrics,
+    queue_metrics: QueueMetrics,
+    message_receiver: EventReceiver<AppEvent>,
+) -> Result<(Router, DispatcherManager), Box<dyn std::error::Error>> {
+    // Lets the handler guards log what a panic said
+    panic_guard::install_hook();
+
     let client = redis::cluster::ClusterClient::new(env.clone().redis_uris).unwrap();
     let adapter = RedisAdapterCtr::new_with_cluster(&client).await?;
     let conn = client.get_async_connection().await?;
 
     let env_clone = env.clone();
 
-    let (dispacher_sender, dispatcher_receiver) = async_channel::unbounded::<DispatcherCallback>();
+    let (dispacher_sender, dispatcher_receiver) = event_queue::bounded::<DispatcherCallback>(
+        "dispatcher_callbacks",
+        env.event_queues.dispatcher_callbacks,
+    );
+    queue_metrics.register(dispacher_sender.stats());
 
     let configs = DispatcherConfigs {
         redis_uris: env_clone.redis_uris,
@@ -111,11 +267,35 @@ pub async fn get_socket_router(
     };
 
     let dispatcher = DispatcherManager::new(configs).await;
+    let dispatcher_clone = dispatcher.clone();
+
+    let alert_manager = AlertManager::new(env.alerting.clone(), conn.clone());
+    alert_manager.clone().spawn(dispatcher.clone());
+
+    let hls_session_store = HlsSessionStore::new(conn.clone());
+    let join_token_store = JoinTokenStore::new(conn.clone());
+    let insights_store = RoomInsightsStore::new(conn.clone());
+    let client_stats_store = ClientStatsStore::new(conn.clone());
 
     let (layer, io) = SocketIo::builder()
+        .with_state(alert_manager)
+        .with_state(client_stats_store.clone())
+        .with_state(insights_store.clone())
+        .with_state(OverflowQueue::new(conn.clone()))
+        .with_state(hls_session_store.clone())
+        .with_state(join_token_store)
+        .with_state(socket_metrics)
+        .with_state(env.socket_rate_limits.clone())
+        .with_state(env.observer_disclosure)
         .with_state(RemoteUserCnt::new(conn))
         .with_state(jwt_utils.clone())
         .with_state(room_service.clone())
+        .with_state(schedule_service)
+        .with_state(chat_service)
+        .with_state(outbox_signal)
+        .with_state(bot_service)
+        .with_state(moderation_service)
+        .with_state(timeline_service.clone())
         .with_state(dispatcher)
         .with_adapter::<ClusterAdapter<_>>(adapter)
         .with_parser(ParserConfig::msgpack())
@@ -127,82 +307,271 @@ pub async fn get_socket_router(
         .layer(CorsLayer::permissive()) // Enable CORS policy
         .layer(layer);
 
-    io.ns("/", on_connect.with(authenticate_middleware)).await?;
+    io.ns(LEGACY_NAMESPACE, on_connect.with(authenticate_middleware))
+        .await?;
+    io.ns(
+        RTC_NAMESPACE,
+        on_rtc_connect.with(authenticate_rtc_middleware),
+    )
+    .await?;
+    io.ns(
+        CHAT_NAMESPACE,
+        on_chat_connect.with(authenticate_middleware),
+    )
+    .await?;
 
     let layer = layer.compat();
-    let router = Router::new().hoop(layer).path("/socket.io").goal(version);
+    let router = Router::new().hoop(layer).path(SOCKET_PATH).goal(version);
 
     // Listener
     let io_clone = io.clone();
     tokio::spawn(handle_dispatcher_callback(
         io_clone,
         dispatcher_receiver,
+        dispatcher_clone.clone(),
+        room_service.clone(),
+        timeline_service.clone(),
+        hls_session_store.clone(),
+        jwt_utils.clone(),
+    ));
+
+    // Nodes lost while signalling was down are reported to the listener above
+    let dispatcher = dispatcher_clone.clone();
+    tokio::spawn(async move { dispatcher.reconcile_routing().await });
+
+    let io_clone = io.clone();
+    tokio::spawn(handle_detached_purge(
+        io_clone,
+        room_service.clone(),
+        timeline_service.clone(),
+    ));
+
+    let io_clone = io.clone();
+    tokio::spawn(handle_hls_refresh(
+        io_clone,
+        hls_session_store,
         room_service,
+        jwt_utils,
     ));
 
     let io_clone = io.clone();
-    tokio::spawn(handle_message_update(io_clone, message_receiver));
+    tokio::spawn(handle_room_insights(
+        io_clone,
+        insights_store,
+        client_stats_store,
+        dispatcher_clone.clone(),
+    ));
+
+    let io_clone = io.clone();
+    tokio::spawn(handle_message_update(
+        io_clone,
+        message_receiver,
+        timeline_service,
+    ));
 
-    Ok(router)
+    Ok((router, dispatcher_clone))
 }
 
 pub async fn handle_dispatcher_callback(
     io: SocketIo<CustomRedisAdapter<Emitter, ClusterDriver>>,
-    receiver: Receiver<DispatcherCallback>,
+    receiver: EventReceiver<DispatcherCallback>,
+    dispatcher_manager: DispatcherManager,
     room_service: RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    timeline_service: RoomTimelines,
+    hls_session_store: HlsSessionStore,
+    jwt_utils: JwtUtils,
 ) {
-    // Non-blocking check for any new messages on the channel
-    while let Ok(msg) = receiver.recv().await {
+    // Only what can still be delivered later goes to the dead letter queue. A
+    // socket that isn't here left or belongs to another signalling node, and
+    // candidates or renegotiations are stale long before anyone replays them.
+    while let Some(msg) = receiver.recv().await {
         match msg {
             DispatcherCallback::NodeTerminated(node_id) => {
-                let _ = room_service.delete_participants_by_node(&node_id).await;
+                // Keep the participants in their rooms and ask the clients of this
+                // instance to rejoin, the dispatcher routes them to a healthy node
+                if let Err(err) = room_service.detach_participants_by_node(&node_id).await {
+                    warn!(
+                        "Failed to detach participants of node {}: {:?}",
+                        node_id, err
+                    );
+                }
+
+                let clients = match dispatcher_manager.get_clients_by_node(&node_id) {
+                    Ok(clients) => clients,
+                    Err(err) => {
+                        warn!("Failed to fail over node {}: {:?}", node_id, err);
+                        continue;
+                    }
+                };
+
+                info!(
+                    "Node {} terminated, migrating {} clients",
+                    node_id,
+                    clients.len()
+                );
+
+                for (client_id, client) in clients {
+                    let Ok(sid) = Sid::from_str(&client_id) else {
+                        continue;
+                    };
+
+                    // Clients connected to other signalling instances are handled there
+                    let Some(socket) = _get_rtc_socket(&io, sid) else {
+                        continue;
+                    };
+
+                    // What it had switched on is restored from its row
+                    match room_service.get_participant(&client.participant_id).await {
+                        Ok(participant) => _fail_over(
+                            &socket,
+                            &jwt_utils,
+                            &client.room_id,
+                            &participant.participant,
+                        ),
+                        Err(err) => warn!(
+                            "Failed to fail over participant {}: {:?}",
+                            client.participant_id, err
+                        ),
+                    }
+                }
             }
             DispatcherCallback::NewUserJoined(info) => {
+                let callback = DispatcherCallback::NewUserJoined(info.clone());
                 let io = io.clone();
+                let dispatcher = dispatcher_manager.clone();
                 let room_service = room_service.clone();
+                let timeline_service = timeline_service.clone();
+                let hls_session_store = hls_session_store.clone();
+                let jwt_utils = jwt_utils.clone();
                 let room_id = info.room_id;
                 let participant_id = info.participant_id;
                 let client_id = info.client_id;
                 let node_id = info.node_id;
                 let is_migrate = info.is_migrate;
 
-                let participant_id_parsed = match participant_id.parse::<i32>() {
-                    Ok(id) => id,
-                    Err(e) => {
-                        warn!("Failed to parse participant_id as i32: {:?}", e);
-                        return;
-                    }
-                };
-
                 let sid = Sid::from_str(&client_id);
 
                 if let Ok(sid) = sid {
-                    if let Some(socket) = io.get_socket(sid) {
+                    if let Some(socket) = _get_rtc_socket(&io, sid) {
+                        let client = socket
+                            .extensions
+                            .get::<ClientMetadataDto>()
+                            .unwrap_or_default();
+
                         tokio::spawn(async move {
-                            let participant = room_service
-                                .update_participant(participant_id_parsed, &node_id)
+                            let participant = match room_service
+                                .update_participant(&participant_id, &node_id, &client)
+                                .await
+                            {
+                                Ok(participant) => participant,
+                                Err(err) => {
+                                    warn!(
+                                        "Failed to update participant {}: {:?}",
+                                        participant_id, err
+                                    );
+                                    dispatcher.push_dead_letter(
+                                        &callback,
+                                        "Failed to update participant",
+                                    );
+                                    return;
+                                }
+                            };
+
+                            let failover = socket
+                                .extensions
+                                .remove::<PendingFailover>()
+                                .filter(|failover| failover.participant_id == participant_id);
+                            // So is a client back from a dead node, the others resubscribe
+                            let is_migrate = is_migrate || failover.is_some();
+
+                            // A migrating client is still in the session it joined
+                            if !is_migrate {
+                                _journal(
+                                    &timeline_service,
+                                    &room_id,
+                                    TimelineEntry::new(RoomEventKind::ParticipantJoined)
+                                        .participant(&participant_id)
+                                        .user(Some(participant.participant.user_id)),
+                                );
+                            }
+
+                            let _ = socket
+                                .broadcast()
+                                .to(room_id.clone())
+                                .emit(
+                                    WsEvent::RoomNewParticipant.to_str(),
+                                    &NewUserJoinedResponse {
+                                        participant,
+                                        is_migrate,
+                                    },
+                                )
+                                .await
+                                .ok();
+
+                            // Someone new to watch for those on HLS
+                            if !is_migrate {
+                                _push_hls_room(
+                                    &io,
+                                    &hls_session_store,
+                                    &room_service,
+                                    &jwt_utils,
+                                    &room_id,
+                                )
                                 .await;
+                            }
 
-                            if let Ok(participant) = participant {
-                                let _ = socket
-                                    .broadcast()
-                                    .to(room_id)
-                                    .emit(
-                                        WsEvent::RoomNewParticipant.to_str(),
-                                        &NewUserJoinedResponse {
-                                            participant,
-                                            is_migrate,
-                                        },
-                                    )
-                                    .await
-                                    .ok();
+                            if let Some(failover) = failover {
+                                _restore_failover(
+                                    &socket,
+                                    &dispatcher,
+                                    &room_service,
+                                    &room_id,
+                                    failover,
+                                )
+                                .await;
                             }
                         });
                     } else {
-                        warn!("Socket with id {} not found", client_id);
+                        debug!("Socket with id {} not on this node", client_id);
                     }
+                } else {
+                    warn!("Invalid client id {}", client_id);
                 }
             }
+            DispatcherCallback::SubscriberDownlinkStarved(info) => {
+                let Ok(sid) = Sid::from_str(&info.client_id) else {
+                    warn!("Invalid client id {}", info.client_id);
+                    continue;
+                };
+
+                let Some(socket) = _get_rtc_socket(&io, sid) else {
+                    debug!("Socket with id {} not on this node", info.client_id);
+                    continue;
+                };
+
+                let room_service = room_service.clone();
+                let hls_session_store = hls_session_store.clone();
+                let jwt_utils = jwt_utils.clone();
+
+                tokio::spawn(async move {
+                    let Some(suggestion) = _open_hls_fallback(
+                        &socket,
+                        &room_service,
+                        &hls_session_store,
+                        &jwt_utils,
+                        &info.room_id,
+                        &info.participant_id,
+                    )
+                    .await
+                    else {
+                        return;
+                    };
+
+                    let _ = socket
+                        .emit(WsEvent::RoomSuggestHls.to_str(), &suggestion)
+                        .ok();
+                });
+            }
             DispatcherCallback::SubscriberRenegotiate(info) => {
                 let io = io.clone();
                 let client_id = info.client_id;
@@ -213,7 +582,7 @@ pub async fn handle_dispatcher_callback(
 
                 match sid {
                     Ok(sid) => {
-                        if let Some(socket) = io.get_socket(sid) {
+                        if let Some(socket) = _get_rtc_socket(&io, sid) {
                             let _ = socket
                                 .emit(
                                     WsEvent::RoomSubscriberRenegotiation.to_str(),
@@ -221,10 +590,12 @@ pub async fn handle_dispatcher_callback(
                                 )
                                 .ok();
                         } else {
-                            warn!("Socket with id {} not found", client_id);
+                            debug!("Socket with id {} not on this node", client_id);
                         }
                     }
-                    Err(err) => warn!("Failed to parse Sid from str: {:?}", err),
+                    Err(err) => {
+                        warn!("Failed to parse Sid from str: {:?}", err);
+                    }
                 }
             }
             DispatcherCallback::PublisherCandidate(info) => {
@@ -242,15 +613,17 @@ pub async fn handle_dispatcher_callback(
 
                     match sid {
                         Ok(sid) => {
-                            if let Some(socket) = io.get_socket(sid) {
+                            if let Some(socket) = _get_rtc_socket(&io, sid) {
                                 let _ = socket
                                     .emit(WsEvent::RoomPublisherCandidate.to_str(), &candidate)
                                     .ok();
                             } else {
-                                warn!("Socket with id {} not found", client_id);
+                                debug!("Socket with id {} not on this node", client_id);
                             }
                         }
-                        Err(err) => warn!("Failed to parse Sid from str: {:?}", err),
+                        Err(err) => {
+                            warn!("Failed to parse Sid from str: {:?}", err);
+                        }
                     }
                 }
             }
@@ -270,7 +643,7 @@ pub async fn handle_dispatcher_callback(
 
                     match sid {
                         Ok(sid) => {
-                            if let Some(socket) = io.get_socket(sid) {
+                            if let Some(socket) = _get_rtc_socket(&io, sid) {
                                 let _ = socket
                                     .emit(
                                         WsEvent::RoomSubscriberCandidate.to_str(),
@@ -281,10 +654,12 @@ pub async fn handle_dispatcher_callback(
                                     )
                                     .ok();
                             } else {
-                                warn!("Socket with id {} not found", client_id);
+                                debug!("Socket with id {} not on this node", client_id);
                             }
                         }
-                        Err(err) => warn!("Failed to parse Sid from str: {:?}", err),
+                        Err(err) => {
+                            warn!("Failed to parse Sid from str: {:?}", err);
+                        }
                     }
                 }
             }
@@ -292,25 +667,248 @@ pub async fn handle_dispatcher_callback(
     }
 }
 
+/// RTC clients are on `/rtc`, or on `/` when they predate the namespace split
+fn _get_rtc_socket<A: Adapter>(io: &SocketIo<A>, sid: Sid) -> Option<SocketRef<A>> {
+    io.of(RTC_NAMESPACE)
+        .and_then(|rtc| rtc.get_socket(sid))
+        .or_else(|| io.get_socket(sid))
+}
+
+/// Asks a client whose SFU node went away to publish again on `room.migrate`,
+/// with a token for the participant it already is and what it had switched on
+fn _fail_over<A: Adapter>(
+    socket: &SocketRef<A>,
+    jwt_utils: &JwtUtils,
+    room_id: &str,
+    participant: &Participant,
+) {
+    let Some(UserId(user_id)) = socket.extensions.get::<UserId>() else {
+        return;
+    };
+
+    let is_host = socket
+        .extensions
+        .get::<RoomPolicy>()
+        .is_some_and(|policy| policy.is_host());
+
+    let (join_token, _) =
+        jwt_utils.generate_join_token(room_id, &participant.public_id, &user_id, is_host);
+
+    let video_codecs = socket
+        .extensions
+        .get::<SubscriberCodecs>()
+        .map(|codecs| codecs.0)
+        .unwrap_or_default();
+
+    socket.extensions.insert(PendingFailover {
+        participant_id: participant.public_id.clone(),
+        is_audio_enabled: participant.is_audio_enabled,
+        is_video_enabled: participant.is_video_enabled,
+        is_hand_raising: participant.is_hand_raising,
+        video_codecs,
+    });
+
+    let _ = socket
+        .emit(
+            WsEvent::RoomMigrate.to_str(),
+            &NodeFailoverResponse {
+                room_id: room_id.to_owned(),
+                participant_id: participant.public_id.clone(),
+                join_token,
+                is_audio_enabled: participant.is_audio_enabled,
+                is_video_enabled: participant.is_video_enabled,
+                is_hand_raising: participant.is_hand_raising,
+            },
+        )
+        .ok();
+}
+
+/// Once a failed over client published again: its raised hand goes back up
+/// and it gets offers for everyone in the room, its subscriptions went down
+/// with the node
+async fn _restore_failover<A: Adapter>(
+    socket: &SocketRef<A>,
+    dispatcher_manager: &DispatcherManager,
+    room_service: &RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    room_id: &str,
+    failover: PendingFailover,
+) {
+    let client_id = socket.id.to_string();
+
+    if failover.is_hand_raising {
+        let req = SetEnabledRequest {
+            client_id: client_id.clone(),
+            is_enabled: true,
+            is_hardware_muted: None,
+        };
+
+        match dispatcher_manager.set_hand_raising(req).await {
+            Ok(_) => {
+                _remember_media(
+                    room_service,
+                    &failover.participant_id,
+                    MediaChange::HandRaising(true),
+                )
+                .await;
+
+                let _ = socket
+                    .broadcast()
+                    .to(room_id.to_owned())
+                    .emit(
+                        WsEvent::RoomHandRaising.to_str(),
+                        &HandleRaisingResponse {
+                            participant_id: failover.participant_id.clone(),
+                            is_raising: true,
+                        },
+                    )
+                    .await
+                    .ok();
+            }
+            Err(err) => warn!("Failed to restore raised hand: {:?}", err),
+        }
+    }
+
+    let room = match _find_room(room_service, room_id).await {
+        Ok(room) => room,
+        Err(err) => {
+            warn!(
+                "Failed to look up room {} after failover: {:?}",
+                room_id, err
+            );
+            return;
+        }
+    };
+
+    // Those still on their way back are offered when they rejoin
+    let targets = room.participants.into_iter().filter(|participant| {
+        participant.participant.node_id.is_some()
+            && participant.participant.public_id != failover.participant_id
+    });
+
+    for target in targets {
+        let target_id = target.participant.public_id;
+
+        let req = SubscribeRequest {
+            client_id: client_id.clone(),
+            target_id: target_id.clone(),
+            participant_id: failover.participant_id.clone(),
+            room_id: room_id.to_owned(),
+            is_observer: false,
+            video_codecs: failover.video_codecs.clone(),
+        };
+
+        match dispatcher_manager.subscribe(req).await {
+            Ok(res) if res.codec_mismatch.is_empty() => {
+                let _ = socket
+                    .emit(
+                        WsEvent::RoomAnswerSubscriber.to_str(),
+                        &_subscribe_response(target_id, res, target.placeholder_url),
+                    )
+                    .ok();
+            }
+            Ok(res) => warn!(
+                "Skipped offer for {} after failover, {} can't be decoded",
+                target_id, res.codec_mismatch
+            ),
+            Err(err) => warn!("Failed to offer {} after failover: {:?}", target_id, err),
+        }
+    }
+}
+
+/// Keeps what the participant's node acknowledged in its row, a failover
+/// restores it from there
+async fn _remember_media(
+    room_service: &RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    participant_id: &str,
+    change: MediaChange,
+) {
+    if let Err(err) = room_service
+        .update_participant_media(participant_id, change)
+        .await
+    {
+        warn!(
+            "Failed to store media state of {}: {:?}",
+            participant_id, err
+        );
+    }
+}
+
+/// To the chat namespace and to the clients still on `/`
+async fn _emit_chat<A: Adapter, T: Serialize + ?Sized>(
+    io: &SocketIo<A>,
+    room_id: String,
+    event: WsEvent,
+    data: &T,
+) {
+    for namespace in [io.of(CHAT_NAMESPACE), Some(io.broadcast())]
+        .into_iter()
+        .flatten()
+    {
+        let _ = namespace
+            .to(room_id.clone())
+            .emit(event.to_str(), data)
+            .await
+            .ok();
+    }
+}
+
+/// Tells everyone in the room it was closed and hangs up the call. Chat
+/// sockets are shared by every room of the user, they only get told.
+async fn _close_room<A: Adapter>(io: &SocketIo<A>, room_id: String) {
+    let response = RoomClosedResponse {
+        room_id: room_id.clone(),
+    };
+
+    // Chat and the clients still on `/`
+    _emit_chat(io, room_id.clone(), WsEvent::RoomClosed, &response).await;
+
+    if let Some(rtc) = io.of(RTC_NAMESPACE) {
+        let _ = rtc
+            .to(room_id.clone())
+            .emit(WsEvent::RoomClosed.to_str(), &response)
+            .await
+            .ok();
+    }
+
+    for namespace in [io.of(RTC_NAMESPACE), Some(io.broadcast())]
+        .into_iter()
+        .flatten()
+    {
+        if let Err(err) = namespace.to(room_id.clone()).disconnect().await {
+            warn!(
+                "Failed to disconnect sockets of room {}: {:?}",
+                room_id, err
+            );
+        }
+    }
+
+    info!("Room {} closed by an operator", room_id);
+}
+
 pub async fn handle_message_update(
     io: SocketIo<CustomRedisAdapter<Emitter, ClusterDriver>>,
-    receiver: Receiver<AppEvent>,
+    receiver: EventReceiver<AppEvent>,
+    timeline_service: RoomTimelines,
 ) {
     // Non-blocking check for any new messages on the channel
-    while let Ok(msg) = receiver.recv().await {
+    while let Some(msg) = receiver.recv().await {
         match msg {
             AppEvent::SendMessage(msg) => {
                 if let Some(room) = msg.clone().room {
                     let io = io.clone();
                     let msg = msg.clone();
-                    let room_id = room.id.to_string();
+                    let room_id = room.public_id;
+
+                    _journal(
+                        &timeline_service,
+                        &room_id,
+                        TimelineEntry::new(RoomEventKind::Message)
+                            .user(Some(msg.message.created_by_id))
+                            .message(msg.message.id),
+                    );
+
                     tokio::spawn(async move {
-                        let _ = io
-                            .broadcast()
-                            .to(room_id)
-                            .emit(WsEvent::ChatSend.to_str(), &msg)
-                            .await
-                            .ok();
+                        _emit_chat(&io, room_id, WsEvent::ChatSend, &msg).await;
                     });
                 }
             }
@@ -318,14 +916,9 @@ pub async fn handle_message_update(
                 if let Some(room) = msg.clone().room {
                     let io = io.clone();
                     let msg = msg.clone();
-                    let room_id = room.id.to_string();
+                    let room_id = room.public_id;
                     tokio::spawn(async move {
-                        let _ = io
-                            .broadcast()
-                            .to(room_id)
-                            .emit(WsEvent::ChatUpdate.to_str(), &msg)
-                            .await
-                            .ok();
+                        _emit_chat(&io, room_id, WsEvent::ChatUpdate, &msg).await;
                     });
                 }
             }
@@ -333,571 +926,3614 @@ pub async fn handle_message_update(
                 if let Some(room) = msg.clone().room {
                     let io = io.clone();
                     let msg = msg.clone();
-                    let room_id = room.id.to_string();
+                    let room_id = room.public_id;
                     tokio::spawn(async move {
-                        let _ = io
-                            .broadcast()
-                            .to(room_id)
-                            .emit(WsEvent::ChatDelete.to_str(), &msg)
-                            .await
-                            .ok();
+                        _emit_chat(&io, room_id, WsEvent::ChatDelete, &msg).await;
                     });
                 }
             }
+            AppEvent::CloseRoom(room_id) => {
+                let io = io.clone();
+                tokio::spawn(async move {
+                    _close_room(&io, room_id).await;
+                });
+            }
+            AppEvent::RevokeBot(bot_id) => {
+                let io = io.clone();
+                tokio::spawn(async move {
+                    _revoke_bot(&io, bot_id).await;
+                });
+            }
         }
     }
 }
 
-async fn authenticate_middleware<A: Adapter>(
-    s: SocketRef<A>,
-    State(user_cnt): State<RemoteUserCnt>,
-    State(jwt_utils): State<JwtUtils>,
-) -> Result<(), anyhow::Error> {
-    let auth_header = s
-        .req_parts()
-        .headers
-        .get("Authorization")
-        .and_then(|value| value.to_str().ok())
-        .ok_or(anyhow::anyhow!("Missing Authorization header"))?;
-
-    let token = auth_header.trim_start_matches("Bearer ");
+/// Renews the URLs of HLS sessions about to expire. Each node claims its share
+/// from Redis, sessions nobody is connected for anymore are dropped.
+pub async fn handle_hls_refresh(
+    io: SocketIo<CustomRedisAdapter<Emitter, ClusterDriver>>,
+    hls_session_store: HlsSessionStore,
+    room_service: RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    jwt_utils: JwtUtils,
+) {
+    let mut interval = tokio::time::interval(HLS_REFRESH_INTERVAL);
 
-    match jwt_utils.decode_token(token) {
-        Ok(claims) => {
-            let user_id = claims.id;
-            let _ = user_cnt.add_user().await.unwrap_or(0);
-            s.extensions.insert(UserId(user_id.clone()));
-            Ok(())
-        }
-        Err(err) => {
-            warn!("decode token failed: {:?}", err);
-            Err(anyhow!("Invalid token"))
-        }
-    }
-}
+    loop {
+        interval.tick().await;
 
-async fn on_connect<A: Adapter>(socket: SocketRef<A>, user_id: Extension<UserId>) {
-    info!("user {:?} connected", user_id.0.0);
+        let now = Utc::now().timestamp();
+        let sessions = match hls_session_store
+            .claim_expiring(now + HLS_REFRESH_MARGIN_SECS)
+            .await
+        {
+            Ok(sessions) => sessions,
+            Err(err) => {
+                warn!("Failed to claim expiring HLS sessions: {:?}", err);
+                continue;
+            }
+        };
 
-    socket.on(WsEvent::RoomReconnect.to_str(), on_reconnect);
-    socket.on(WsEvent::RoomPublish.to_str(), handle_join_room);
-    socket.on(WsEvent::RoomSubscribe.to_str(), handle_subscribe);
-    socket.on(
-        WsEvent::RoomAnswerSubscriber.to_str(),
-        handle_answer_subscribe,
-    );
-    socket.on(
-        WsEvent::RoomPublisherRenegotiation.to_str(),
-        handle_publisher_renegotiation,
-    );
-    socket.on(
-        WsEvent::RoomPublisherCandidate.to_str(),
-        handle_publisher_candidate,
-    );
-    socket.on(
-        WsEvent::RoomSubscriberCandidate.to_str(),
-        handle_subscriber_candidate,
-    );
-    socket.on(WsEvent::RoomMigrate.to_str(), handle_migrate_connection);
+        for (session, expires_at) in sessions {
+            // An expired session wasn't renewed in time, nobody is playing it anymore
+            let renewed = if expires_at > now && _is_hls_session_watched(&io, &session).await {
+                _push_hls_streams(&io, &room_service, &jwt_utils, &session)
+                    .await
+                    .ok()
+            } else {
+                None
+            };
 
-    socket.on(WsEvent::RoomCameraType.to_str(), handle_set_camera_type);
-    socket.on(WsEvent::RoomVideoEnabled.to_str(), handle_set_video_enabled);
-    socket.on(WsEvent::RoomAudioEnabled.to_str(), handle_set_audio_enabled);
-    socket.on(
-        WsEvent::RoomScreenSharing.to_str(),
-        handle_set_screen_sharing,
-    );
-    socket.on(WsEvent::RoomHandRaising.to_str(), handle_set_hand_raising);
-    socket.on(
-        WsEvent::RoomSubtitleTrack.to_str(),
-        handle_set_subscribe_subtitle,
-    );
-    socket.on(WsEvent::RoomLeave.to_str(), handle_leave_room);
+            let result = match renewed {
+                Some(expires_at) => hls_session_store.renew(&session, expires_at).await,
+                None => {
+                    hls_session_store
+                        .close(&session.room_id, &session.user_id)
+                        .await
+                }
+            };
 
-    socket.on_disconnect(on_disconnect);
+            if let Err(err) = result {
+                warn!(
+                    "Failed to renew HLS session of user {} in room {}: {:?}",
+                    session.user_id, session.room_id, err
+                );
+            }
+        }
+    }
 }
 
-async fn on_disconnect<A: Adapter>(
-    socket: SocketRef<A>,
-    user_cnt: State<RemoteUserCnt>,
-    dispatcher_manager: State<DispatcherManager>,
-    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+/// Drops participants whose node went away and who never rejoined, and tells
+/// their rooms they left. Every signalling node runs it, the delete decides
+/// which node announces a participant.
+pub async fn handle_detached_purge(
+    io: SocketIo<CustomRedisAdapter<Emitter, ClusterDriver>>,
+    room_service: RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    timeline_service: RoomTimelines,
 ) {
-    let _ = _handle_leave_room(socket, dispatcher_manager.0, room_service.0).await;
+    let mut interval = tokio::time::interval(DETACHED_PURGE_INTERVAL);
 
-    let _ = user_cnt.remove_user().await.unwrap_or(0);
-}
+    loop {
+        interval.tick().await;
+
+        let detached_before =
+            Utc::now().naive_utc() - chrono::Duration::seconds(DETACHED_PARTICIPANT_TTL_SECS);
+        let purged = match room_service
+            .purge_detached_participants(detached_before)
+            .await
+        {
+            Ok(purged) => purged,
+            Err(err) => {
+                warn!("Failed to purge detached participants: {:?}", err);
+                continue;
+            }
+        };
+
+        for (room, participant) in purged {
+            info!(
+                "Participant {} didn't rejoin room {} after a failover, removed",
+                participant.public_id, room.public_id
+            );
+
+            let response = ParticipantHasLeftResponse {
+                target_id: participant.public_id.clone(),
+            };
+
+            for namespace in [io.of(RTC_NAMESPACE), Some(io.broadcast())]
+                .into_iter()
+                .flatten()
+            {
+                let _ = namespace
+                    .to(room.public_id.clone())
+                    .emit(WsEvent::RoomParticipantLeft.to_str(), &response)
+                    .await
+                    .ok();
+            }
+
+            _journal(
+                &timeline_service,
+                &room.public_id,
+                TimelineEntry::new(RoomEventKind::ParticipantLeft)
+                    .participant(&participant.public_id)
+                    .user(Some(participant.user_id)),
+            );
+        }
+    }
+}
+
+/// Sends the hosts of each room their insights. Each node claims its share of
+/// the rooms from Redis, rooms whose hosts all left are dropped.
+pub async fn handle_room_insights(
+    io: SocketIo<CustomRedisAdapter<Emitter, ClusterDriver>>,
+    insights_store: RoomInsightsStore,
+    client_stats_store: ClientStatsStore,
+    dispatcher_manager: DispatcherManager,
+) {
+    let mut interval = tokio::time::interval(ROOM_INSIGHTS_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let now = Utc::now().timestamp_millis();
+        let room_ids = match insights_store.claim_due(now).await {
+            Ok(room_ids) => room_ids,
+            Err(err) => {
+                warn!("Failed to claim rooms due for insights: {:?}", err);
+                continue;
+            }
+        };
+
+        for room_id in room_ids {
+            let result = if _has_hosts(&io, &room_id).await {
+                _push_room_insights(
+                    &io,
+                    &insights_store,
+                    &client_stats_store,
+                    &dispatcher_manager,
+                    &room_id,
+                    now,
+                )
+                .await
+            } else {
+                insights_store.forget(&room_id).await
+            };
+
+            if let Err(err) = result {
+                warn!("Failed to send insights of room {}: {:?}", room_id, err);
+            }
+        }
+    }
+}
+
+async fn _push_room_insights(
+    io: &SocketIo<CustomRedisAdapter<Emitter, ClusterDriver>>,
+    insights_store: &RoomInsightsStore,
+    client_stats_store: &ClientStatsStore,
+    dispatcher_manager: &DispatcherManager,
+    room_id: &str,
+    now: i64,
+) -> Result<(), redis::RedisError> {
+    // Scheduled first, a failure below only skips this round
+    insights_store
+        .schedule(room_id, now + ROOM_INSIGHTS_INTERVAL.as_millis() as i64)
+        .await?;
+
+    let stats = dispatcher_manager.get_room_stats(room_id).await;
+
+    let participant_count = stats.participants.len();
+    let participant_trend = insights_store
+        .record_participant_count(room_id, participant_count)
+        .await?;
+
+    let scores: Vec<f64> = client_stats_store
+        .get_latest(room_id, now - ROOM_INSIGHTS_STATS_WINDOW_MS)
+        .await?
+        .iter()
+        .filter_map(|record| network_score(&record.stats))
+        .collect();
+    let average_network_score =
+        (!scores.is_empty()).then(|| scores.iter().sum::<f64>() / scores.len() as f64);
+
+    let talk_times_ms: Vec<u64> = stats
+        .participants
+        .iter()
+        .map(|participant| participant.talk_time_ms)
+        .collect();
+
+    let response = RoomInsightsResponse {
+        room_id: room_id.to_string(),
+        participant_count,
+        participant_trend,
+        average_network_score,
+        talk_time_balance: talk_time_balance(&talk_times_ms),
+        is_recording: stats.is_recording,
+        is_recording_paused: stats.is_recording_paused,
+    };
+
+    for namespace in [io.of(RTC_NAMESPACE), Some(io.broadcast())]
+        .into_iter()
+        .flatten()
+    {
+        let _ = namespace
+            .to(_hosts_room(room_id))
+            .emit(WsEvent::RoomInsights.to_str(), &response)
+            .await
+            .ok();
+    }
+
+    Ok(())
+}
+
+async fn authenticate_middleware<A: Adapter>(
+    s: SocketRef<A>,
+    State(user_cnt): State<RemoteUserCnt>,
+    State(jwt_utils): State<JwtUtils>,
+    State(bot_service): State<BotServiceImpl<BotRepositoryImpl>>,
+) -> Result<(), anyhow::Error> {
+    let auth_header = _authorization_header(&s)?;
+
+    if let Some(token) = auth_header.strip_prefix("Bot ") {
+        return match bot_service.authenticate(token).await {
+            Ok(bot) => {
+                let _ = user_cnt.add_user().await.unwrap_or(0);
+                s.extensions.insert(UserId(bot.user_id.to_string()));
+                s.extensions.insert(BotIdentity(bot));
+                Ok(())
+            }
+            Err(err) => {
+                warn!("bot authentication failed: {:?}", err);
+                Err(anyhow!("Invalid token"))
+            }
+        };
+    }
+
+    _authenticate_user(&s, &user_cnt, &jwt_utils, &auth_header).await
+}
+
+/// Bots never publish or subscribe media, only user and observer tokens are let in
+async fn authenticate_rtc_middleware<A: Adapter>(
+    s: SocketRef<A>,
+    State(user_cnt): State<RemoteUserCnt>,
+    State(jwt_utils): State<JwtUtils>,
+) -> Result<(), anyhow::Error> {
+    let auth_header = _authorization_header(&s)?;
+
+    if auth_header.starts_with("Bot ") {
+        return Err(anyhow!("Bots can't connect to {}", RTC_NAMESPACE));
+    }
+
+    if let Some(token) = auth_header.strip_prefix("Observer ") {
+        return match jwt_utils.decode_observer_token(token) {
+            Ok(claims) => {
+                let _ = user_cnt.add_user().await.unwrap_or(0);
+                s.extensions.insert(ObserverIdentity(claims));
+                Ok(())
+            }
+            Err(err) => {
+                warn!("decode observer token failed: {:?}", err);
+                Err(anyhow!("Invalid token"))
+            }
+        };
+    }
+
+    _authenticate_user(&s, &user_cnt, &jwt_utils, &auth_header).await
+}
+
+fn _authorization_header<A: Adapter>(s: &SocketRef<A>) -> Result<String, anyhow::Error> {
+    s.req_parts()
+        .headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+        .ok_or(anyhow::anyhow!("Missing Authorization header"))
+}
+
+async fn _authenticate_user<A: Adapter>(
+    s: &SocketRef<A>,
+    user_cnt: &RemoteUserCnt,
+    jwt_utils: &JwtUtils,
+    auth_header: &str,
+) -> Result<(), anyhow::Error> {
+    let token = auth_header.trim_start_matches("Bearer ");
+
+    match jwt_utils.decode_token(token) {
+        Ok(claims) => {
+            let user_id = claims.id;
+            let _ = user_cnt.add_user().await.unwrap_or(0);
+            s.extensions.insert(UserId(user_id.clone()));
+            Ok(())
+        }
+        Err(err) => {
+            warn!("decode token failed: {:?}", err);
+            Err(anyhow!("Invalid token"))
+        }
+    }
+}
+
+async fn on_connect<A: Adapter>(
+    socket: SocketRef<A>,
+    user_id: Extension<UserId>,
+    client: TryData<ClientMetadataDto>,
+    rate_limits: State<SocketRateLimits>,
+) {
+    if let Some(BotIdentity(bot)) = socket.extensions.get::<BotIdentity>() {
+        info!("bot {:?} connected as user {:?}", bot.id, user_id.0.0);
+        socket.extensions.insert(EventBudget::new(rate_limits.chat));
+        return on_bot_connect(socket, bot.id);
+    }
+
+    socket
+        .extensions
+        .insert(ChatEventBudget(EventBudget::new(rate_limits.chat)));
+    on_rtc_connect(socket.clone(), client, rate_limits).await;
+
+    socket.on(WsEvent::ChatJoin.to_str(), handle_chat_join);
+    socket.on(WsEvent::ChatLeave.to_str(), handle_chat_leave);
+    socket.on(WsEvent::ChatReaction.to_str(), handle_chat_reaction);
+
+    socket.on_disconnect(on_legacy_disconnect);
+}
+
+async fn on_rtc_connect<A: Adapter>(
+    socket: SocketRef<A>,
+    TryData(client): TryData<ClientMetadataDto>,
+    rate_limits: State<SocketRateLimits>,
+) {
+    if let Some(ObserverIdentity(claims)) = socket.extensions.get::<ObserverIdentity>() {
+        info!(
+            "observer {} ({}) connected to room {}",
+            claims.observer_id, claims.label, claims.room_id
+        );
+        socket.extensions.insert(EventBudget::new(rate_limits.rtc));
+        return on_observer_connect(socket);
+    }
+
+    let Some(UserId(user_id)) = socket.extensions.get::<UserId>() else {
+        return;
+    };
+
+    // A handshake without an `auth` payload, or with one we can't read, still connects
+    let client = _sanitize_client_metadata(client.unwrap_or_default());
+
+    info!("user {:?} connected with {:?}", user_id, client);
+
+    socket.extensions.insert(client);
+    socket.extensions.insert(EventBudget::new(rate_limits.rtc));
+
+    socket.on(WsEvent::RoomReconnect.to_str(), on_reconnect);
+    socket.on(WsEvent::RoomPublish.to_str(), handle_join_room);
+    socket.on(WsEvent::RoomSubscribe.to_str(), handle_subscribe);
+    socket.on(
+        WsEvent::RoomAnswerSubscriber.to_str(),
+        handle_answer_subscribe,
+    );
+    socket.on(
+        WsEvent::RoomPublisherRenegotiation.to_str(),
+        handle_publisher_renegotiation,
+    );
+    socket.on(
+        WsEvent::RoomPublisherCandidate.to_str(),
+        handle_publisher_candidate,
+    );
+    socket.on(
+        WsEvent::RoomSubscriberCandidate.to_str(),
+        handle_subscriber_candidate,
+    );
+    socket.on(WsEvent::RoomMigrate.to_str(), handle_migrate_connection);
+
+    socket.on(WsEvent::RoomViewportUpdate.to_str(), handle_viewport_update);
+    socket.on(WsEvent::RoomCameraType.to_str(), handle_set_camera_type);
+    socket.on(WsEvent::RoomVideoEnabled.to_str(), handle_set_video_enabled);
+    socket.on(WsEvent::RoomAudioEnabled.to_str(), handle_set_audio_enabled);
+    socket.on(
+        WsEvent::RoomScreenSharing.to_str(),
+        handle_set_screen_sharing,
+    );
+    socket.on(WsEvent::RoomHandRaising.to_str(), handle_set_hand_raising);
+    socket.on(
+        WsEvent::RoomSubtitleTrack.to_str(),
+        handle_set_subscribe_subtitle,
+    );
+    socket.on(WsEvent::RoomStageMode.to_str(), handle_set_stage_mode);
+    socket.on(WsEvent::RoomSpeaker.to_str(), handle_set_speaker);
+    socket.on(WsEvent::RoomQosClass.to_str(), handle_set_qos_class);
+    socket.on(
+        WsEvent::RoomRecordingPaused.to_str(),
+        handle_set_recording_paused,
+    );
+    socket.on(WsEvent::RoomBroadcastCut.to_str(), handle_set_broadcast_cut);
+    socket.on(WsEvent::RoomType.to_str(), handle_set_room_type);
+    socket.on(WsEvent::RoomClientStats.to_str(), handle_client_stats);
+    socket.on(WsEvent::RoomReport.to_str(), handle_report_participant);
+    socket.on(WsEvent::RoomLeave.to_str(), handle_leave_room);
+
+    socket.on_disconnect(on_disconnect);
+}
+
+/// Observers watch one room: they subscribe to its participants, never publish,
+/// and never show up in its participant list
+fn on_observer_connect<A: Adapter>(socket: SocketRef<A>) {
+    socket.on(WsEvent::RoomObserve.to_str(), handle_observe_room);
+    socket.on(WsEvent::RoomSubscribe.to_str(), handle_subscribe);
+    socket.on(
+        WsEvent::RoomAnswerSubscriber.to_str(),
+        handle_answer_subscribe,
+    );
+    socket.on(
+        WsEvent::RoomSubscriberCandidate.to_str(),
+        handle_subscriber_candidate,
+    );
+    socket.on(WsEvent::RoomViewportUpdate.to_str(), handle_viewport_update);
+    socket.on(WsEvent::RoomLeave.to_str(), handle_observer_leave);
+
+    socket.on_disconnect(on_observer_disconnect);
+}
+
+/// Chat only, a chat widget joins the rooms it shows and leaves the calls to `/rtc`
+async fn on_chat_connect<A: Adapter>(
+    socket: SocketRef<A>,
+    user_id: Extension<UserId>,
+    rate_limits: State<SocketRateLimits>,
+) {
+    socket.extensions.insert(EventBudget::new(rate_limits.chat));
+
+    if let Some(BotIdentity(bot)) = socket.extensions.get::<BotIdentity>() {
+        info!(
+            "bot {:?} connected to chat as user {:?}",
+            bot.id, user_id.0.0
+        );
+        return on_bot_connect(socket, bot.id);
+    }
+
+    info!("user {:?} connected to chat", user_id.0.0);
+
+    socket.on(WsEvent::ChatJoin.to_str(), handle_chat_join);
+    socket.on(WsEvent::ChatLeave.to_str(), handle_chat_leave);
+    socket.on(WsEvent::ChatReaction.to_str(), handle_chat_reaction);
+
+    socket.on_disconnect(on_chat_disconnect);
+}
+
+/// Bots never publish or subscribe media, they only get the chat and control events
+fn on_bot_connect<A: Adapter>(socket: SocketRef<A>, bot_id: i32) {
+    socket.join(_bot_room(bot_id));
+
+    socket.on(WsEvent::RoomBotJoin.to_str(), handle_bot_join_room);
+    socket.on(WsEvent::RoomBotMessage.to_str(), handle_bot_message);
+    socket.on(
+        WsEvent::RoomRecordingPaused.to_str(),
+        handle_bot_set_recording_paused,
+    );
+    socket.on(WsEvent::RoomLeave.to_str(), handle_bot_leave_room);
+
+    socket.on_disconnect(on_bot_disconnect);
+}
+
+async fn on_disconnect<A: Adapter>(
+    socket: SocketRef<A>,
+    io: SocketIo<A>,
+    user_cnt: State<RemoteUserCnt>,
+    dispatcher_manager: State<DispatcherManager>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    timeline_service: State<RoomTimelines>,
+    overflow_queue: State<OverflowQueue>,
+    hls_session_store: State<HlsSessionStore>,
+    jwt_utils: State<JwtUtils>,
+) {
+    let _ = _handle_leave_room(
+        socket,
+        io,
+        dispatcher_manager.0,
+        room_service.0,
+        timeline_service.0,
+        overflow_queue.0,
+        hls_session_store.0,
+        jwt_utils.0,
+    )
+    .await;
+
+    let _ = user_cnt.remove_user().await.unwrap_or(0);
+}
+
+/// Leaves the call like `/rtc` and gives up the place in line like `/chat`
+#[allow(clippy::too_many_arguments)]
+async fn on_legacy_disconnect<A: Adapter>(
+    socket: SocketRef<A>,
+    io: SocketIo<A>,
+    user_cnt: State<RemoteUserCnt>,
+    dispatcher_manager: State<DispatcherManager>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    timeline_service: State<RoomTimelines>,
+    overflow_queue: State<OverflowQueue>,
+    hls_session_store: State<HlsSessionStore>,
+    jwt_utils: State<JwtUtils>,
+) {
+    _leave_overflow(&socket, &overflow_queue, &hls_session_store).await;
+
+    on_disconnect(
+        socket,
+        io,
+        user_cnt,
+        dispatcher_manager,
+        room_service,
+        timeline_service,
+        overflow_queue,
+        hls_session_store,
+        jwt_utils,
+    )
+    .await;
+}
 
 async fn on_reconnect<A: Adapter>(_: SocketRef<A>) {}
 
-async fn handle_join_room<A: Adapter>(
+async fn on_chat_disconnect<A: Adapter>(
+    socket: SocketRef<A>,
+    user_cnt: State<RemoteUserCnt>,
+    overflow_queue: State<OverflowQueue>,
+    hls_session_store: State<HlsSessionStore>,
+) {
+    _leave_overflow(&socket, &overflow_queue, &hls_session_store).await;
+
+    let _ = user_cnt.remove_user().await.unwrap_or(0);
+}
+
+async fn handle_chat_join<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<ChatRoomDto>,
+    Extension(user_id): Extension<UserId>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    overflow_queue: State<OverflowQueue>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::ChatJoin,
+        Budget::Chat,
+        Some(PayloadSummary::of::<ChatRoomDto>),
+    ) else {
+        return;
+    };
+
+    let is_waiting =
+        match _check_chat_access(&room_service, &overflow_queue, &data.room_id, &user_id.0).await {
+            Ok(is_waiting) => is_waiting,
+            Err(err) => {
+                warn!("Rejected chat join of user {}: {:?}", user_id.0, err);
+                scope.fail();
+                return;
+            }
+        };
+
+    if is_waiting && let Ok(waiting_user_id) = user_id.0.parse() {
+        socket.join(_overflow_room(&data.room_id));
+        socket.join(_hls_session_room(&data.room_id, &user_id.0));
+        socket.extensions.insert(OverflowViewer {
+            room_id: data.room_id.clone(),
+            user_id: waiting_user_id,
+        });
+    }
+
+    socket.join(data.room_id);
+}
+
+async fn handle_chat_leave<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<ChatRoomDto>,
+    overflow_queue: State<OverflowQueue>,
+    hls_session_store: State<HlsSessionStore>,
+) {
+    if socket
+        .extensions
+        .get::<OverflowViewer>()
+        .is_some_and(|viewer| viewer.room_id == data.room_id)
+    {
+        _leave_overflow(&socket, &overflow_queue, &hls_session_store).await;
+        socket.leave(_overflow_room(&data.room_id));
+    }
+
+    socket.leave(data.room_id);
+}
+
+async fn handle_chat_reaction<A: Adapter>(
+    socket: SocketRef<A>,
+    io: SocketIo<A>,
+    Data(data): Data<ReactionDto>,
+    Extension(user_id): Extension<UserId>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    overflow_queue: State<OverflowQueue>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::ChatReaction,
+        Budget::Chat,
+        Some(PayloadSummary::of::<ReactionDto>),
+    ) else {
+        return;
+    };
+
+    if data.emoji.is_empty() || data.emoji.len() > MAX_REACTION_LEN {
+        scope.fail();
+        return;
+    }
+
+    if let Err(err) =
+        _check_chat_access(&room_service, &overflow_queue, &data.room_id, &user_id.0).await
+    {
+        warn!("Rejected reaction of user {}: {:?}", user_id.0, err);
+        scope.fail();
+        return;
+    }
+
+    _emit_chat(
+        &io,
+        data.room_id.clone(),
+        WsEvent::ChatReaction,
+        &ReactionResponse {
+            room_id: data.room_id,
+            emoji: data.emoji,
+        },
+    )
+    .await;
+}
+
+/// Members read the chat, and so does anyone in the call right now or waiting
+/// for a slot in it. `true` for the latter.
+async fn _check_chat_access(
+    room_service: &RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    overflow_queue: &OverflowQueue,
+    room_id: &str,
+    user_id: &str,
+) -> Result<bool, anyhow::Error> {
+    let public_room_id = room_id;
+    let room_id = room_service.resolve_room_id(public_room_id).await?;
+    let user_id = user_id.parse::<i32>()?;
+
+    let room = room_service.get_room_by_id(room_id).await?;
+
+    let is_in_room = room
+        .members
+        .iter()
+        .any(|member| member.member.user_id == user_id)
+        || room
+            .participants
+            .iter()
+            .any(|participant| participant.participant.user_id == user_id);
+    if is_in_room {
+        return Ok(false);
+    }
+
+    if room.room.capacity.is_some() && overflow_queue.contains(public_room_id, user_id).await? {
+        return Ok(true);
+    }
+
+    Err(anyhow!("User {} is not in room {}", user_id, room_id))
+}
+
+async fn _leave_overflow<A: Adapter>(
+    socket: &SocketRef<A>,
+    overflow_queue: &OverflowQueue,
+    hls_session_store: &HlsSessionStore,
+) {
+    let Some(viewer) = socket.extensions.remove::<OverflowViewer>() else {
+        return;
+    };
+
+    if let Err(err) = overflow_queue.remove(&viewer.room_id, viewer.user_id).await {
+        warn!(
+            "Failed to dequeue viewer {} of room {}: {:?}",
+            viewer.user_id, viewer.room_id, err
+        );
+    }
+
+    let user_id = viewer.user_id.to_string();
+    socket.leave(_hls_session_room(&viewer.room_id, &user_id));
+    if let Err(err) = hls_session_store.close(&viewer.room_id, &user_id).await {
+        warn!(
+            "Failed to close HLS session of viewer {} of room {}: {:?}",
+            viewer.user_id, viewer.room_id, err
+        );
+    }
+}
+
+/// The policy of the room the socket is in, if it lets `event` through. A
+/// socket that hasn't joined or observed a room yet has none.
+fn _room_policy<A: Adapter>(socket: &SocketRef<A>, event: WsEvent) -> Option<RoomPolicy> {
+    let policy = socket
+        .extensions
+        .get::<RoomPolicy>()
+        .filter(|policy| policy.allows(event));
+    if policy.is_none() {
+        warn!(
+            "Rejected {} outside of what the room allows",
+            event.to_str()
+        );
+    }
+
+    policy
+}
+
+/// Like `_room_policy`, re-derived from the room record first for events a
+/// switch between conference and live stream changes the answer to. The
+/// record is cached, the socket keeps the refreshed policy.
+async fn _current_room_policy<A: Adapter>(
+    socket: &SocketRef<A>,
+    room_service: &RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    room_id: &str,
+    event: WsEvent,
+) -> Option<RoomPolicy> {
+    if let Some(policy) = socket.extensions.get::<RoomPolicy>() {
+        match _find_room(room_service, room_id).await {
+            Ok(room) => {
+                socket
+                    .extensions
+                    .insert(RoomPolicy::from_room(&room.room, policy.is_host()));
+            }
+            Err(err) => warn!("Failed to refresh policy of room {}: {:?}", room_id, err),
+        }
+    }
+
+    _room_policy(socket, event)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn handle_join_room<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<JoinRoomDto>,
+    Extension(user_id): Extension<UserId>,
+    dispatcher_manager: State<DispatcherManager>,
+    alert_manager: State<AlertManager>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    schedule_service: State<LiveSchedules>,
+    timeline_service: State<RoomTimelines>,
+    observer_disclosure: State<ObserverDisclosure>,
+    jwt_utils: State<JwtUtils>,
+    join_token_store: State<JoinTokenStore>,
+    insights_store: State<RoomInsightsStore>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomPublish,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<JoinRoomDto>),
+    ) else {
+        return;
+    };
+
+    // The REST API already checked membership, password and capacity
+    let claims = match jwt_utils.decode_join_token(&data.join_token, &data.room_id) {
+        Ok(claims) if claims.user_id == user_id.0 => claims,
+        Ok(_) => {
+            warn!("Rejected join with a token of another user");
+            scope.fail();
+            return;
+        }
+        Err(err) => {
+            warn!("Rejected join without a valid join token: {:?}", err);
+            scope.fail();
+            return;
+        }
+    };
+
+    let client_id = socket.id.to_string();
+    let participant_id = &claims.participant_id;
+    let room_id = claims.room_id.clone();
+
+    // Back from a dead node, the client publishes with what it had switched on
+    let (is_audio_enabled, is_video_enabled) = socket
+        .extensions
+        .get::<PendingFailover>()
+        .filter(|failover| failover.participant_id == *participant_id)
+        .map_or((data.is_audio_enabled, data.is_video_enabled), |failover| {
+            (failover.is_audio_enabled, failover.is_video_enabled)
+        });
+
+    // The host joining a scheduled live stream is what takes it live
+    let live_schedule = match _find_startable_schedule(
+        &room_service,
+        &schedule_service,
+        &room_id,
+        &user_id.0,
+    )
+    .await
+    {
+        Ok(schedule) => schedule,
+        Err(err) => {
+            warn!("Failed to look up live schedule: {:?}", err);
+            None
+        }
+    };
+
+    // Without the room type there is no telling whether a non-host may publish
+    let room = match _find_room(&room_service, &room_id).await {
+        Ok(room) => room,
+        Err(err) => {
+            warn!("Rejected join, failed to look up room settings: {:?}", err);
+            scope.fail();
+            return;
+        }
+    };
+
+    let policy = RoomPolicy::from_room(&room.room, claims.is_host);
+    if !policy.allows_publish(data.total_tracks) {
+        warn!(
+            "Rejected publishing of a non-host in live stream {}",
+            room_id
+        );
+        scope.fail();
+        return;
+    }
+
+    // Only redeemed once nothing short of the SFU can turn the join down
+    match join_token_store.redeem(&claims.jti).await {
+        Ok(true) => {}
+        Ok(false) => {
+            warn!("Rejected join with a join token used before");
+            scope.fail();
+            return;
+        }
+        Err(err) => {
+            warn!("Failed to redeem join token: {:?}", err);
+            scope.fail();
+            return;
+        }
+    }
+
+    // Candidates trickle in before the join completes
+    socket.extensions.insert(policy);
+
+    let live_schedule = live_schedule.filter(|_| policy.allows_egress());
+
+    let noise_suppression = !data.is_noise_suppressed && room.room.noise_suppression;
+    let broadcast_delay_ms = room.room.broadcast_delay_secs.max(0) as u32 * 1_000;
+    let qos_class = _qos_class(&room, &user_id.0);
+
+    // Nobody subscribes in a P2P room, the peers get the offer from the room
+    let p2p_offer = policy.is_p2p().then(|| data.sdp.clone());
+
+    let req = JoinRoomRequest {
+        sdp: data.sdp,
+        is_audio_enabled,
+        is_video_enabled,
+        is_e2ee_enabled: data.is_e2ee_enabled,
+        total_tracks: data.total_tracks as i32,
+        client_id,
+        participant_id: participant_id.to_string(),
+        room_id: room_id.clone(),
+        connection_type: policy.connection_type() as i32,
+        tracks: to_proto_tracks(data.tracks),
+        start_egress: live_schedule.is_some() || policy.starts_egress(),
+        noise_suppression,
+        broadcast_delay_ms,
+        qos_class,
+        // Room controls are filled in by the dispatcher, which keeps them
+        ..Default::default()
+    };
+
+    let client = socket
+        .extensions
+        .get::<ClientMetadataDto>()
+        .unwrap_or_default();
+
+    let started_at = Instant::now();
+
+    match dispatcher_manager
+        .join_room(req, client.client_version.as_deref())
+        .await
+    {
+        Ok(res) => {
+            alert_manager.record_join_success(started_at.elapsed());
+
+            _remember_media(
+                &room_service,
+                participant_id,
+                MediaChange::Published {
+                    is_audio_enabled,
+                    is_video_enabled,
+                },
+            )
+            .await;
+
+            socket.join(room_id.clone());
+            socket.extensions.insert(CallIds {
+                room_id: room_id.clone(),
+                participant_id: participant_id.to_string(),
+            });
+
+            if claims.is_host {
+                socket.join(_hosts_room(&room_id));
+                _notify_observers(&socket, &room_id, *observer_disclosure).await;
+
+                if let Err(err) = insights_store
+                    .schedule(&room_id, Utc::now().timestamp_millis())
+                    .await
+                {
+                    warn!("Failed to start insights of room {}: {:?}", room_id, err);
+                }
+            }
+
+            if !res.sdp.is_empty() {
+                let response = JoinRoomResponse {
+                    sdp: res.sdp,
+                    is_recording: res.is_recording,
+                };
+
+                let _ = socket.emit(WsEvent::RoomPublish.to_str(), &response).ok();
+            }
+
+            if let Some(sdp) = p2p_offer {
+                let _ = socket
+                    .broadcast()
+                    .to(room_id.clone())
+                    .emit(
+                        WsEvent::RoomSubscriberRenegotiation.to_str(),
+                        &SubscriberRenegotiationResponse {
+                            target_id: participant_id.to_string(),
+                            sdp,
+                        },
+                    )
+                    .await
+                    .ok();
+            }
+
+            if let Some(schedule) = live_schedule {
+                if let Err(err) = schedule_service.mark_live(schedule.id).await {
+                    warn!("Failed to mark live schedule {}: {:?}", schedule.id, err);
+                }
+
+                if let Err(err) = dispatcher_manager
+                    .stop_slate(StopSlateRequest {
+                        room_id: room_id.clone(),
+                    })
+                    .await
+                {
+                    warn!("Failed to stop slate: {:?}", err);
+                }
+
+                _journal(
+                    &timeline_service,
+                    &room_id,
+                    TimelineEntry::new(RoomEventKind::LiveStarted)
+                        .participant(participant_id)
+                        .user(user_id.0.parse().ok()),
+                );
+
+                let _ = socket
+                    .within(room_id.clone())
+                    .emit(
+                        WsEvent::RoomLiveStarted.to_str(),
+                        &LiveStartedResponse {
+                            room_id,
+                            participant_id: participant_id.to_string(),
+                        },
+                    )
+                    .await
+                    .ok();
+            }
+        }
+        Err(err) => {
+            alert_manager.record_join_failure();
+            scope.fail();
+
+            warn!(
+                "Failed to join room {} from {:?}: {:?}",
+                room_id, client, err
+            )
+        }
+    }
+}
+
+async fn _find_startable_schedule(
+    room_service: &RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    schedule_service: &LiveSchedules,
+    room_id: &str,
+    user_id: &str,
+) -> Result<Option<LiveSchedule>, anyhow::Error> {
+    let room_id = room_service.resolve_room_id(room_id).await?;
+    let user_id = user_id.parse::<i32>()?;
+
+    Ok(schedule_service.find_startable(room_id, user_id).await?)
+}
+
+/// The room a publisher joins, for the settings the SFU applies to it
+async fn _find_room(
+    room_service: &RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    room_id: &str,
+) -> Result<RoomResponse, anyhow::Error> {
+    let room_id = room_service.resolve_room_id(room_id).await?;
+    let room = room_service.get_room_by_id(room_id).await?;
+
+    Ok(room)
+}
+
+/// The QoS class a user publishes with: the host's, a presenter's if the room
+/// settings name their membership, an attendee's otherwise
+fn _qos_class(room: &RoomResponse, user_id: &str) -> i32 {
+    let Ok(user_id) = user_id.parse::<i32>() else {
+        return QOS_CLASS_ATTENDEE;
+    };
+
+    let Some(member) = room
+        .members
+        .iter()
+        .find(|member| member.member.user_id == user_id)
+    else {
+        return QOS_CLASS_ATTENDEE;
+    };
+
+    if member.member.role == MembersRoleEnum::Owner as i16 {
+        QOS_CLASS_HOST
+    } else if room.room.presenter_member_ids.contains(&member.member.id) {
+        QOS_CLASS_PRESENTER
+    } else {
+        QOS_CLASS_ATTENDEE
+    }
+}
+
+async fn handle_subscribe<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<SubscribeDto>,
+    dispatcher_manager: State<DispatcherManager>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    hls_session_store: State<HlsSessionStore>,
+    jwt_utils: State<JwtUtils>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomSubscribe,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<SubscribeDto>),
+    ) else {
+        return;
+    };
+
+    if _room_policy(&socket, WsEvent::RoomSubscribe).is_none() {
+        scope.fail();
+        return;
+    }
+
+    let client_id = socket.id.to_string();
+    let target_id = data.target_id;
+
+    // An observer is held to the room and id its token was issued for, anyone
+    // else to the call its join token got it into
+    let observer = socket.extensions.get::<ObserverIdentity>();
+    let (participant_id, room_id) = match &observer {
+        Some(ObserverIdentity(claims)) => (claims.observer_id.clone(), claims.room_id.clone()),
+        None => match socket.extensions.get::<CallIds>() {
+            Some(call) => (call.participant_id, call.room_id),
+            None => {
+                warn!("Rejected subscribe outside of a call");
+                scope.fail();
+                return;
+            }
+        },
+    };
+
+    socket
+        .extensions
+        .insert(SubscriberCodecs(data.video_codecs.clone()));
+
+    let req = SubscribeRequest {
+        client_id,
+        target_id: target_id.clone(),
+        participant_id: participant_id.clone(),
+        room_id: room_id.clone(),
+        is_observer: observer.is_some(),
+        video_codecs: data.video_codecs,
+    };
+
+    let res = dispatcher_manager.subscribe(req).await;
+    if res.is_err() {
+        scope.fail();
+    }
+
+    if let Ok(res) = res {
+        // Rather than black video, the client is pointed at the HLS rendition
+        if !res.codec_mismatch.is_empty() {
+            scope.fail();
+
+            let fallback = match observer {
+                Some(_) => None,
+                None => {
+                    _open_hls_fallback(
+                        &socket,
+                        &room_service,
+                        &hls_session_store,
+                        &jwt_utils,
+                        &room_id,
+                        &participant_id,
+                    )
+                    .await
+                }
+            };
+
+            let _ = socket
+                .emit(
+                    WsEvent::RoomCodecMismatch.to_str(),
+                    &CodecMismatchResponse {
+                        target_id,
+                        codec: res.codec_mismatch,
+                        fallback,
+                    },
+                )
+                .ok();
+            return;
+        }
+
+        let placeholder_url = room_service
+            .get_participant(&target_id)
+            .await
+            .ok()
+            .and_then(|participant| participant.placeholder_url);
+
+        let _ = socket
+            .emit(
+                WsEvent::RoomAnswerSubscriber.to_str(),
+                &_subscribe_response(target_id, res, placeholder_url),
+            )
+            .ok();
+    }
+}
+
+fn _subscribe_response(
+    target_id: String,
+    res: waterbus_proto::SubscribeResponse,
+    placeholder_url: Option<String>,
+) -> SubscribeParticipantResponse {
+    SubscribeParticipantResponse {
+        subscribe_response: SubscribeResponse {
+            offer: res.offer,
+            camera_type: res.camera_type as u8,
+            video_enabled: res.video_enabled,
+            audio_enabled: res.audio_enabled,
+            is_video_hardware_muted: res.is_video_hardware_muted,
+            is_audio_hardware_muted: res.is_audio_hardware_muted,
+            is_screen_sharing: res.is_screen_sharing,
+            is_hand_raising: res.is_hand_raising,
+            is_e2ee_enabled: res.is_e2ee_enabled,
+            video_codec: res.video_codec,
+            screen_track_id: res.screen_track_id,
+            screen_audio_track_id: res.screen_audio_track_id,
+            tracks: res
+                .tracks
+                .into_iter()
+                .map(|track| PublishedTrackResponse {
+                    track_id: track.track_id,
+                    kind: track.kind,
+                    source: track.source as u8,
+                    label: track.label,
+                })
+                .collect(),
+            placeholder_url,
+        },
+        target_id,
+    }
+}
+
+async fn handle_answer_subscribe<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<AnswerSubscribeDto>,
+    dispatcher_manager: State<DispatcherManager>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomAnswerSubscriber,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<AnswerSubscribeDto>),
+    ) else {
+        return;
+    };
+
+    let Some(policy) = _room_policy(&socket, WsEvent::RoomAnswerSubscriber) else {
+        scope.fail();
+        return;
+    };
+
+    if policy.is_p2p() {
+        // Would hand the observer's answer to everyone in the room
+        if socket.extensions.get::<ObserverIdentity>().is_some() {
+            scope.fail();
+            return;
+        }
+
+        let response = JoinRoomResponse {
+            sdp: data.sdp,
+            is_recording: false,
+        };
+        let _ = socket
+            .broadcast()
+            .to(data.room_id)
+            .emit(WsEvent::RoomPublish.to_str(), &response)
+            .await
+            .ok();
+    } else {
+        let client_id = socket.id.to_string();
+        let target_id = data.target_id;
+        let sdp = data.sdp;
+
+        let req = SetSubscriberSdpRequest {
+            client_id,
+            target_id,
+            sdp,
+        };
+
+        if dispatcher_manager.set_subscribe_sdp(req).await.is_err() {
+            scope.fail();
+        }
+    }
+}
+
+async fn handle_publisher_renegotiation<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<PublisherRenegotiationDto>,
+    dispatcher_manager: State<DispatcherManager>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomPublisherRenegotiation,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<PublisherRenegotiationDto>),
+    ) else {
+        return;
+    };
+
+    // Only what the socket published with is renegotiated and announced
+    let Some(call) = socket.extensions.get::<CallIds>() else {
+        warn!("Rejected publisher renegotiation outside of a call");
+        scope.fail();
+        return;
+    };
+
+    // Attendees of a conference the host turned into a live stream stop publishing
+    let Some(policy) = _current_room_policy(
+        &socket,
+        &room_service,
+        &call.room_id,
+        WsEvent::RoomPublisherRenegotiation,
+    )
+    .await
+    else {
+        scope.fail();
+        return;
+    };
+
+    if policy.is_p2p() {
+        let _ = socket
+            .broadcast()
+            .to(call.room_id)
+            .emit(
+                WsEvent::RoomSubscriberRenegotiation.to_str(),
+                &SubscriberRenegotiationResponse {
+                    target_id: "".to_string(),
+                    sdp: data.sdp,
+                },
+            )
+            .await
+            .ok();
+    } else {
+        let client_id = socket.id.to_string();
+        let sdp = data.sdp;
+
+        let announced = (!data.tracks.is_empty()).then(|| PublishedTracksResponse {
+            participant_id: call.participant_id,
+            tracks: data.tracks.clone(),
+        });
+
+        let req = PublisherRenegotiationRequest {
+            client_id,
+            sdp,
+            tracks: to_proto_tracks(data.tracks),
+        };
+
+        let sdp = dispatcher_manager.publisher_renegotiate(req).await;
+        if sdp.is_err() {
+            scope.fail();
+        }
+
+        if let Ok(sdp) = sdp {
+            let _ = socket
+                .emit(
+                    WsEvent::RoomPublisherRenegotiation.to_str(),
+                    &RenegotiateResponse { sdp: sdp.sdp },
+                )
+                .ok();
+
+            // Lets subscribers label the new tiles before the renegotiated tracks arrive
+            if let Some(announced) = announced {
+                let _ = socket
+                    .broadcast()
+                    .to(call.room_id)
+                    .emit(WsEvent::RoomPublishedTracks.to_str(), &announced)
+                    .await
+                    .ok();
+            }
+        }
+    }
+}
+
+async fn handle_migrate_connection<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<MigrateConnectionDto>,
+    dispatcher_manager: State<DispatcherManager>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomMigrate,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<MigrateConnectionDto>),
+    ) else {
+        return;
+    };
+
+    let Some(policy) = _room_policy(&socket, WsEvent::RoomMigrate) else {
+        scope.fail();
+        return;
+    };
+
+    let client_id = socket.id.to_string();
+    let sdp = data.sdp;
+    let connection_type = policy.connection_type() as i32;
+
+    let req = MigratePublisherRequest {
+        client_id,
+        sdp,
+        connection_type,
+    };
+
+    let sdp = dispatcher_manager.migrate_connection(req).await;
+    if sdp.is_err() {
+        scope.fail();
+    }
+
+    if let Ok(sdp) = sdp
+        && let Some(sdp) = sdp.sdp
+    {
+        let _ = socket
+            .emit(WsEvent::RoomMigrate.to_str(), &RenegotiateResponse { sdp })
+            .ok();
+    }
+}
+
+async fn handle_publisher_candidate<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<PublisherCandidateDto>,
+    dispatcher_manager: State<DispatcherManager>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomPublisherCandidate,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<PublisherCandidateDto>),
+    ) else {
+        return;
+    };
+
+    let Some(policy) = _room_policy(&socket, WsEvent::RoomPublisherCandidate) else {
+        scope.fail();
+        return;
+    };
+
+    let client_id = socket.id.to_string();
+    let candidate = data.candidate;
+
+    let candidate = waterbus_proto::common::IceCandidate {
+        candidate: candidate.candidate,
+        sdp_mid: candidate.sdp_mid,
+        sdp_m_line_index: candidate.sdp_m_line_index.map(|v| v as u32),
+    };
+
+    let req = AddPublisherCandidateRequest {
+        client_id,
+        candidate: Some(candidate.clone()),
+        connection_type: policy.connection_type() as i32,
+    };
+
+    if policy.is_p2p() {
+        let _ = socket
+            .broadcast()
+            .to(data.room_id)
+            .emit(
+                WsEvent::RoomSubscriberCandidate.to_str(),
+                &SubsriberCandidateResponse {
+                    candidate: IceCandidate {
+                        candidate: candidate.candidate,
+                        sdp_mid: candidate.sdp_mid,
+                        sdp_m_line_index: candidate.sdp_m_line_index,
+                    },
+                    target_id: "".to_string(),
+                },
+            )
+            .await
+            .ok();
+    } else {
+        if dispatcher_manager
+            .add_publisher_candidate(req)
+            .await
+            .is_err()
+        {
+            scope.fail();
+        }
+    }
+}
+
+async fn handle_subscriber_candidate<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<SubscriberCandidateDto>,
+    dispatcher_manager: State<DispatcherManager>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomSubscriberCandidate,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<SubscriberCandidateDto>),
+    ) else {
+        return;
+    };
+
+    let Some(policy) = _room_policy(&socket, WsEvent::RoomSubscriberCandidate) else {
+        scope.fail();
+        return;
+    };
+
+    let client_id = socket.id.to_string();
+    let candidate = data.candidate.clone();
+    let target_id = data.target_id;
+
+    let candidate = waterbus_proto::common::IceCandidate {
+        candidate: candidate.candidate,
+        sdp_mid: candidate.sdp_mid,
+        sdp_m_line_index: candidate.sdp_m_line_index.map(|v| v as u32),
+    };
+
+    let req = AddSubscriberCandidateRequest {
+        client_id,
+        target_id,
+        candidate: Some(candidate),
+        connection_type: policy.connection_type() as i32,
+    };
+
+    if policy.is_p2p() {
+        let _ = socket
+            .broadcast()
+            .to(data.room_id)
+            .emit(WsEvent::RoomPublisherCandidate.to_str(), &data.candidate)
+            .await
+            .ok();
+    } else {
+        if dispatcher_manager
+            .add_subscriber_candidate(req)
+            .await
+            .is_err()
+        {
+            scope.fail();
+        }
+    }
+}
+
+async fn handle_set_camera_type<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<SetCameraTypeDto>,
+    dispatcher_manager: State<DispatcherManager>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomCameraType,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<SetCameraTypeDto>),
+    ) else {
+        return;
+    };
+
+    let client_id = socket.id.to_string();
+    let camera_type = data.type_;
+
+    let req = SetCameraType {
+        camera_type,
+        client_id,
+    };
+
+    let resp = dispatcher_manager.set_camera_type(req).await;
+    if resp.is_err() {
+        scope.fail();
+    }
+
+    if let Ok(client) = resp {
+        let _ = socket
+            .broadcast()
+            .to(client.room_id)
+            .emit(
+                WsEvent::RoomCameraType.to_str(),
+                &CameraTypeResponse {
+                    participant_id: client.participant_id,
+                    type_: camera_type,
+                },
+            )
+            .await
+            .ok();
+    }
+}
+
+async fn handle_viewport_update<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<ViewportUpdateDto>,
+    dispatcher_manager: State<DispatcherManager>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomViewportUpdate,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<ViewportUpdateDto>),
+    ) else {
+        return;
+    };
+
+    if _room_policy(&socket, WsEvent::RoomViewportUpdate).is_none() {
+        scope.fail();
+        return;
+    }
+
+    let Some(req) = _viewport_request(socket.id.to_string(), data) else {
+        scope.fail();
+        return;
+    };
+
+    if let Err(err) = dispatcher_manager.set_viewport(req).await {
+        warn!("Failed to update viewport of {}: {:?}", socket.id, err);
+        scope.fail();
+    }
+}
+
+/// None when the client reports more tiles than any layout shows
+fn _viewport_request(client_id: String, data: ViewportUpdateDto) -> Option<SetViewportRequest> {
+    if data.tiles.len() > MAX_VIEWPORT_TILES {
+        return None;
+    }
+
+    Some(SetViewportRequest {
+        client_id,
+        tiles: data
+            .tiles
+            .into_iter()
+            .map(|tile| ViewportTile {
+                participant_id: tile.participant_id,
+                width: tile.width,
+                height: tile.height,
+            })
+            .collect(),
+    })
+}
+
+async fn handle_set_video_enabled<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<SetEnabledDto>,
+    Extension(user_id): Extension<UserId>,
+    dispatcher_manager: State<DispatcherManager>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    timeline_service: State<RoomTimelines>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomVideoEnabled,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<SetEnabledDto>),
+    ) else {
+        return;
+    };
+
+    let client_id = socket.id.to_string();
+    let is_enabled = data.is_enabled;
+    let is_hardware_muted = data.is_hardware_muted;
+
+    let req = SetEnabledRequest {
+        client_id,
+        is_enabled,
+        is_hardware_muted,
+    };
+
+    let resp = dispatcher_manager.set_video_enabled(req).await;
+    if resp.is_err() {
+        scope.fail();
+    }
+
+    if let Ok(client) = resp {
+        _remember_media(
+            &room_service,
+            &client.participant_id,
+            MediaChange::Video(is_enabled),
+        )
+        .await;
+
+        _journal(
+            &timeline_service,
+            &client.room_id,
+            TimelineEntry::new(RoomEventKind::Video)
+                .participant(&client.participant_id)
+                .user(user_id.0.parse().ok())
+                .enabled(is_enabled),
+        );
+
+        let _ = socket
+            .broadcast()
+            .to(client.room_id)
+            .emit(
+                WsEvent::RoomVideoEnabled.to_str(),
+                &EnabledResponse {
+                    participant_id: client.participant_id,
+                    is_enabled,
+                    is_hardware_muted,
+                },
+            )
+            .await
+            .ok();
+    }
+}
+
+async fn handle_set_audio_enabled<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<SetEnabledDto>,
+    Extension(user_id): Extension<UserId>,
+    dispatcher_manager: State<DispatcherManager>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    timeline_service: State<RoomTimelines>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomAudioEnabled,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<SetEnabledDto>),
+    ) else {
+        return;
+    };
+
+    let client_id = socket.id.to_string();
+    let is_enabled = data.is_enabled;
+    let is_hardware_muted = data.is_hardware_muted;
+
+    let req = SetEnabledRequest {
+        client_id,
+        is_enabled,
+        is_hardware_muted,
+    };
+
+    let resp = dispatcher_manager.set_audio_enabled(req).await;
+    if resp.is_err() {
+        scope.fail();
+    }
+
+    if let Ok(client) = resp {
+        _remember_media(
+            &room_service,
+            &client.participant_id,
+            MediaChange::Audio(is_enabled),
+        )
+        .await;
+
+        _journal(
+            &timeline_service,
+            &client.room_id,
+            TimelineEntry::new(RoomEventKind::Audio)
+                .participant(&client.participant_id)
+                .user(user_id.0.parse().ok())
+                .enabled(is_enabled),
+        );
+
+        let _ = socket
+            .broadcast()
+            .to(client.room_id)
+            .emit(
+                WsEvent::RoomAudioEnabled.to_str(),
+                &EnabledResponse {
+                    participant_id: client.participant_id,
+                    is_enabled,
+                    is_hardware_muted,
+                },
+            )
+            .await
+            .ok();
+    }
+}
+
+async fn handle_set_screen_sharing<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<SetScreenSharingDto>,
+    Extension(user_id): Extension<UserId>,
+    dispatcher_manager: State<DispatcherManager>,
+    timeline_service: State<RoomTimelines>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomScreenSharing,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<SetScreenSharingDto>),
+    ) else {
+        return;
+    };
+
+    if _room_policy(&socket, WsEvent::RoomScreenSharing).is_none() {
+        scope.fail();
+        return;
+    }
+
+    let client_id = socket.id.to_string();
+    let is_enabled = data.is_sharing;
+    let screen_track_id = data.screen_track_id;
+    let screen_audio_track_id = data.screen_audio_track_id;
+
+    let req = SetScreenSharingRequest {
+        client_id,
+        is_enabled,
+        screen_track_id: screen_track_id.clone(),
+        screen_audio_track_id: screen_audio_track_id.clone(),
+    };
+
+    let resp = dispatcher_manager.set_screen_sharing(req).await;
+    if resp.is_err() {
+        scope.fail();
+    }
+
+    if let Ok(client) = resp {
+        _journal(
+            &timeline_service,
+            &client.room_id,
+            TimelineEntry::new(RoomEventKind::ScreenShare)
+                .participant(&client.participant_id)
+                .user(user_id.0.parse().ok())
+                .enabled(is_enabled),
+        );
+
+        let _ = socket
+            .broadcast()
+            .to(client.room_id)
+            .emit(
+                WsEvent::RoomScreenSharing.to_str(),
+                &ScreenSharingResponse {
+                    participant_id: client.participant_id,
+                    is_sharing: is_enabled,
+                    screen_track_id,
+                    screen_audio_track_id,
+                },
+            )
+            .await
+            .ok();
+    }
+}
+
+async fn handle_set_hand_raising<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<SetHandRaisingDto>,
+    Extension(user_id): Extension<UserId>,
+    dispatcher_manager: State<DispatcherManager>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    timeline_service: State<RoomTimelines>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomHandRaising,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<SetHandRaisingDto>),
+    ) else {
+        return;
+    };
+
+    let client_id = socket.id.to_string();
+    let is_enabled = data.is_raising;
+
+    let req = SetEnabledRequest {
+        client_id,
+        is_enabled,
+        is_hardware_muted: None,
+    };
+
+    let resp = dispatcher_manager.set_hand_raising(req).await;
+    if resp.is_err() {
+        scope.fail();
+    }
+
+    if let Ok(client) = resp {
+        _remember_media(
+            &room_service,
+            &client.participant_id,
+            MediaChange::HandRaising(is_enabled),
+        )
+        .await;
+
+        _journal(
+            &timeline_service,
+            &client.room_id,
+            TimelineEntry::new(RoomEventKind::HandRaise)
+                .participant(&client.participant_id)
+                .user(user_id.0.parse().ok())
+                .enabled(is_enabled),
+        );
+
+        let _ = socket
+            .broadcast()
+            .to(client.room_id)
+            .emit(
+                WsEvent::RoomHandRaising.to_str(),
+                &HandleRaisingResponse {
+                    participant_id: client.participant_id,
+                    is_raising: is_enabled,
+                },
+            )
+            .await
+            .ok();
+    }
+}
+
+async fn handle_set_subscribe_subtitle<A: Adapter>(
+    _: SocketRef<A>,
+    Data(_data): Data<SetEnabledDto>,
+) {
+}
+
+async fn handle_set_stage_mode<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<SetStageModeDto>,
+    Extension(user_id): Extension<UserId>,
+    dispatcher_manager: State<DispatcherManager>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomStageMode,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<SetStageModeDto>),
+    ) else {
+        return;
+    };
+
+    let room_id = data.room_id;
+    let is_enabled = data.is_enabled;
+
+    if let Err(err) = _check_host(&room_service, &room_id, &user_id.0).await {
+        warn!("Rejected stage mode change: {:?}", err);
+        scope.fail();
+        return;
+    }
+
+    let req = SetStageModeRequest {
+        room_id: room_id.clone(),
+        is_enabled,
+    };
+
+    match dispatcher_manager.set_stage_mode(req).await {
+        Ok(()) => {
+            let _ = socket
+                .within(room_id)
+                .emit(
+                    WsEvent::RoomStageMode.to_str(),
+                    &StageModeResponse { is_enabled },
+                )
+                .await
+                .ok();
+        }
+        Err(err) => {
+            warn!("Failed to set stage mode: {:?}", err);
+            scope.fail();
+        }
+    }
+}
+
+async fn handle_set_qos_class<A: Adapter>(
     socket: SocketRef<A>,
-    Data(data): Data<JoinRoomDto>,
+    Data(data): Data<SetQosClassDto>,
+    Extension(user_id): Extension<UserId>,
     dispatcher_manager: State<DispatcherManager>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    metrics: State<SocketMetrics>,
 ) {
-    let client_id = socket.id.to_string();
-    let participant_id = &data.participant_id;
-    let room_id = data.room_id.clone();
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomQosClass,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<SetQosClassDto>),
+    ) else {
+        return;
+    };
 
-    let req = JoinRoomRequest {
-        sdp: data.sdp,
-        is_audio_enabled: data.is_audio_enabled,
-        is_video_enabled: data.is_video_enabled,
-        is_e2ee_enabled: data.is_e2ee_enabled,
-        total_tracks: data.total_tracks as i32,
-        client_id,
-        participant_id: participant_id.to_string(),
+    let room_id = data.room_id;
+    let participant_id = data.participant_id;
+    let qos_class = data.qos_class.min(QOS_CLASS_HOST as u8);
+
+    if let Err(err) = _check_host(&room_service, &room_id, &user_id.0).await {
+        warn!("Rejected QoS class change: {:?}", err);
+        scope.fail();
+        return;
+    }
+
+    let req = SetQosClassRequest {
         room_id: room_id.clone(),
-        connection_type: data.connection_type as i32,
+        participant_id: participant_id.clone(),
+        qos_class: qos_class as i32,
     };
 
-    match dispatcher_manager.join_room(req).await {
-        Ok(res) => {
-            socket.join(room_id.clone());
+    match dispatcher_manager.set_qos_class(req).await {
+        Ok(()) => {
+            let _ = socket
+                .within(room_id)
+                .emit(
+                    WsEvent::RoomQosClass.to_str(),
+                    &QosClassResponse {
+                        participant_id,
+                        qos_class,
+                    },
+                )
+                .await
+                .ok();
+        }
+        Err(err) => {
+            warn!("Failed to set QoS class: {:?}", err);
+            scope.fail();
+        }
+    }
+}
 
-            if !res.sdp.is_empty() {
-                let response = JoinRoomResponse {
-                    sdp: res.sdp,
-                    is_recording: res.is_recording,
-                };
+async fn handle_set_speaker<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<SetSpeakerDto>,
+    Extension(user_id): Extension<UserId>,
+    dispatcher_manager: State<DispatcherManager>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomSpeaker,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<SetSpeakerDto>),
+    ) else {
+        return;
+    };
 
-                let _ = socket.emit(WsEvent::RoomPublish.to_str(), &response).ok();
+    let room_id = data.room_id;
+    let participant_id = data.participant_id;
+    let is_speaker = data.is_speaker;
+
+    if let Err(err) = _check_host(&room_service, &room_id, &user_id.0).await {
+        warn!("Rejected speaker change: {:?}", err);
+        scope.fail();
+        return;
+    }
+
+    let req = SetSpeakerRequest {
+        room_id: room_id.clone(),
+        participant_id: participant_id.clone(),
+        is_speaker,
+    };
+
+    match dispatcher_manager.set_speaker(req).await {
+        Ok(()) => {
+            let _ = socket
+                .within(room_id)
+                .emit(
+                    WsEvent::RoomSpeaker.to_str(),
+                    &SpeakerResponse {
+                        participant_id,
+                        is_speaker,
+                    },
+                )
+                .await
+                .ok();
+        }
+        Err(err) => {
+            warn!("Failed to set speaker: {:?}", err);
+            scope.fail();
+        }
+    }
+}
+
+async fn handle_set_recording_paused<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<SetRecordingPausedDto>,
+    Extension(user_id): Extension<UserId>,
+    dispatcher_manager: State<DispatcherManager>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    timeline_service: State<RoomTimelines>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomRecordingPaused,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<SetRecordingPausedDto>),
+    ) else {
+        return;
+    };
+
+    let room_id = data.room_id;
+    let is_paused = data.is_paused;
+
+    if let Err(err) = _check_host(&room_service, &room_id, &user_id.0).await {
+        warn!("Rejected recording pause change: {:?}", err);
+        scope.fail();
+        return;
+    }
+
+    let req = SetRecordingPausedRequest {
+        room_id: room_id.clone(),
+        is_paused,
+    };
+
+    match dispatcher_manager.set_recording_paused(req).await {
+        Ok(()) => {
+            _journal(
+                &timeline_service,
+                &room_id,
+                TimelineEntry::new(RoomEventKind::Recording)
+                    .user(user_id.0.parse().ok())
+                    .enabled(!is_paused),
+            );
+
+            let _ = socket
+                .within(room_id)
+                .emit(
+                    WsEvent::RoomRecordingPaused.to_str(),
+                    &RecordingPausedResponse { is_paused },
+                )
+                .await
+                .ok();
+        }
+        Err(err) => {
+            warn!("Failed to set recording paused: {:?}", err);
+            scope.fail();
+        }
+    }
+}
+
+async fn handle_set_broadcast_cut<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<SetBroadcastCutDto>,
+    Extension(user_id): Extension<UserId>,
+    dispatcher_manager: State<DispatcherManager>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomBroadcastCut,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<SetBroadcastCutDto>),
+    ) else {
+        return;
+    };
+
+    let room_id = data.room_id;
+    let is_cut = data.is_cut;
+
+    if let Err(err) = _check_host(&room_service, &room_id, &user_id.0).await {
+        warn!("Rejected broadcast cut: {:?}", err);
+        scope.fail();
+        return;
+    }
+
+    let req = SetBroadcastCutRequest {
+        room_id: room_id.clone(),
+        is_cut,
+    };
+
+    match dispatcher_manager.set_broadcast_cut(req).await {
+        Ok(()) => {
+            if is_cut {
+                info!("Broadcast of room {} cut by {}", room_id, user_id.0);
+            }
+
+            let _ = socket
+                .within(room_id)
+                .emit(
+                    WsEvent::RoomBroadcastCut.to_str(),
+                    &BroadcastCutResponse { is_cut },
+                )
+                .await
+                .ok();
+        }
+        Err(err) => {
+            warn!("Failed to set broadcast cut: {:?}", err);
+            scope.fail();
+        }
+    }
+}
+
+/// Turns a running conference into a live stream or back, the meeting goes on
+/// either way. Going live streams everyone publishing, the hosts, over HLS.
+#[allow(clippy::too_many_arguments)]
+async fn handle_set_room_type<A: Adapter>(
+    socket: SocketRef<A>,
+    io: SocketIo<A>,
+    Data(data): Data<SetRoomTypeDto>,
+    Extension(user_id): Extension<UserId>,
+    dispatcher_manager: State<DispatcherManager>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    timeline_service: State<RoomTimelines>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomType,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<SetRoomTypeDto>),
+    ) else {
+        return;
+    };
+
+    let room_id = data.room_id;
+    let room_type = RoomType::from(data.room_type);
+
+    let room = match _switch_room_type(&room_service, &room_id, &user_id.0, room_type).await {
+        Ok(room) => room,
+        Err(err) => {
+            warn!("Rejected room type switch of {}: {:?}", room_id, err);
+            scope.fail();
+            return;
+        }
+    };
+
+    let is_live = room_type == RoomType::LiveStreaming;
+
+    socket
+        .extensions
+        .insert(RoomPolicy::from_room(&room.room, true));
+
+    // The room record already switched, a node that failed to follow is only logged
+    if let Err(err) = dispatcher_manager
+        .set_egress(SetEgressRequest {
+            room_id: room_id.clone(),
+            is_enabled: is_live,
+        })
+        .await
+    {
+        warn!("Failed to set egress of room {}: {:?}", room_id, err);
+        scope.fail();
+    }
+
+    if is_live && !_unpublish_audience(&dispatcher_manager, &room_service, &room, &room_id).await {
+        scope.fail();
+    }
+    _refresh_room_policies(&io, &room, &room_id).await;
+
+    let kind = if is_live {
+        RoomEventKind::LiveStarted
+    } else {
+        RoomEventKind::LiveEnded
+    };
+    _journal(
+        &timeline_service,
+        &room_id,
+        TimelineEntry::new(kind).user(user_id.0.parse().ok()),
+    );
+
+    let response = RoomTypeResponse {
+        room_id: room_id.clone(),
+        room_type: room.room.type_,
+        streaming_protocol: room.room.streaming_protocol,
+    };
+
+    for namespace in [io.of(RTC_NAMESPACE), Some(io.broadcast())]
+        .into_iter()
+        .flatten()
+    {
+        let _ = namespace
+            .to(room_id.clone())
+            .emit(WsEvent::RoomType.to_str(), &response)
+            .await
+            .ok();
+    }
+}
+
+/// Attendees still publishing when the room turns into a live stream stop, on
+/// whichever node and signalling node they are. False if one couldn't be stopped.
+async fn _unpublish_audience(
+    dispatcher_manager: &DispatcherManager,
+    room_service: &RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    room: &RoomResponse,
+    room_id: &str,
+) -> bool {
+    let clients = match dispatcher_manager.get_clients_by_room(room_id) {
+        Ok(clients) => clients,
+        Err(err) => {
+            warn!("Failed to get publishers of room {}: {:?}", room_id, err);
+            return false;
+        }
+    };
+
+    let mut is_done = true;
+    for (client_id, client) in clients {
+        if _is_host_participant(room, &client.participant_id) {
+            continue;
+        }
+
+        match dispatcher_manager
+            .unpublish(UnpublishRequest { client_id })
+            .await
+        {
+            Ok(()) => {
+                _remember_media(
+                    room_service,
+                    &client.participant_id,
+                    MediaChange::Unpublished,
+                )
+                .await
             }
+            Err(err) => {
+                warn!(
+                    "Failed to unpublish {} in room {}: {:?}",
+                    client.participant_id, room_id, err
+                );
+                is_done = false;
+            }
+        }
+    }
+
+    is_done
+}
+
+/// A participant nobody knows of counts as an attendee
+fn _is_host_participant(room: &RoomResponse, participant_id: &str) -> bool {
+    room.participants
+        .iter()
+        .find(|participant| participant.participant.public_id == participant_id)
+        .is_some_and(|participant| {
+            room.members.iter().any(|member| {
+                member.member.user_id == participant.participant.user_id
+                    && member.member.role == MembersRoleEnum::Owner as i16
+            })
+        })
+}
+
+/// Re-derives the policy of every socket of the room on this signalling node.
+/// Sockets on other nodes re-derive theirs on their next publishing event.
+async fn _refresh_room_policies<A: Adapter>(io: &SocketIo<A>, room: &RoomResponse, room_id: &str) {
+    for namespace in [io.of(RTC_NAMESPACE), Some(io.broadcast())]
+        .into_iter()
+        .flatten()
+    {
+        let Ok(sockets) = namespace.within(room_id.to_owned()).fetch_sockets().await else {
+            continue;
+        };
+
+        for socket in sockets {
+            let Some(socket) = _get_rtc_socket(io, socket.id()) else {
+                continue;
+            };
+
+            if let Some(policy) = socket.extensions.get::<RoomPolicy>() {
+                socket
+                    .extensions
+                    .insert(RoomPolicy::from_room(&room.room, policy.is_host()));
+            }
+        }
+    }
+}
+
+async fn _switch_room_type(
+    room_service: &RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    room_id: &str,
+    user_id: &str,
+    room_type: RoomType,
+) -> Result<RoomResponse, anyhow::Error> {
+    let room_id = room_service.resolve_room_id(room_id).await?;
+    let user_id = user_id.parse::<i32>()?;
+
+    Ok(room_service
+        .switch_room_type(room_id, user_id, room_type)
+        .await?)
+}
+
+async fn handle_client_stats<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<ClientStatsDto>,
+    Extension(user_id): Extension<UserId>,
+    client_stats_store: State<ClientStatsStore>,
+    alert_manager: State<AlertManager>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomClientStats,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<ClientStatsDto>),
+    ) else {
+        return;
+    };
+
+    let session_id = socket.id.to_string();
+
+    if let Some(packet_loss) = data.packet_loss {
+        alert_manager.record_packet_loss(packet_loss);
+    }
+
+    let client = socket
+        .extensions
+        .get::<ClientMetadataDto>()
+        .unwrap_or_default();
+
+    if let Err(err) = client_stats_store
+        .push(&session_id, &user_id.0, client, data)
+        .await
+    {
+        warn!("Failed to store client stats: {:?}", err);
+        scope.fail();
+    }
+}
+
+async fn handle_report_participant<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<RoomReportDto>,
+    Extension(user_id): Extension<UserId>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    moderation_service: State<AbuseReports>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomReport,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<RoomReportDto>),
+    ) else {
+        return;
+    };
+
+    match _report_participant(&room_service, &moderation_service, &user_id.0, data).await {
+        Ok(response) => {
+            let _ = socket.emit(WsEvent::RoomReport.to_str(), &response).ok();
+        }
+        Err(err) => {
+            warn!("Rejected report from user {}: {:?}", user_id.0, err);
+            scope.fail();
+        }
+    }
+}
+
+async fn _report_participant(
+    room_service: &RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    moderation_service: &AbuseReports,
+    user_id: &str,
+    data: RoomReportDto,
+) -> Result<ReportSubmittedResponse, anyhow::Error> {
+    let room_id = room_service.resolve_room_id(&data.room_id).await?;
+    let reporter_id = user_id.parse::<i32>()?;
+    let target_id = data.target_id.clone();
+
+    let report = moderation_service
+        .report_participant(room_id, reporter_id, data.into())
+        .await?;
+
+    Ok(ReportSubmittedResponse {
+        report_id: report.report.id,
+        target_id,
+    })
+}
+
+async fn handle_bot_join_room<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<BotJoinRoomDto>,
+    Extension(BotIdentity(bot)): Extension<BotIdentity>,
+    bot_service: State<BotServiceImpl<BotRepositoryImpl>>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomBotJoin,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<BotJoinRoomDto>),
+    ) else {
+        return;
+    };
+
+    let Some(bot) = _authorize_bot(&socket, &bot_service, &bot, BotScope::ChatRead).await else {
+        scope.fail();
+        return;
+    };
+
+    if let Err(err) = _check_bot_member(&room_service, &data.room_id, &bot).await {
+        warn!("Rejected bot {} join: {:?}", bot.id, err);
+        scope.fail();
+        return;
+    }
+
+    socket.join(data.room_id);
+}
+
+async fn handle_bot_message<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<BotMessageDto>,
+    Extension(BotIdentity(bot)): Extension<BotIdentity>,
+    bot_service: State<BotServiceImpl<BotRepositoryImpl>>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    chat_service: State<SocketChatService>,
+    outbox_signal: State<OutboxSignal>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomBotMessage,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<BotMessageDto>),
+    ) else {
+        return;
+    };
+
+    let Some(bot) = _authorize_bot(&socket, &bot_service, &bot, BotScope::ChatWrite).await else {
+        scope.fail();
+        return;
+    };
+
+    let room_id = match _check_bot_member(&room_service, &data.room_id, &bot).await {
+        Ok(room_id) => room_id,
+        Err(err) => {
+            warn!("Rejected bot {} message: {:?}", bot.id, err);
+            scope.fail();
+            return;
+        }
+    };
+
+    match chat_service
+        .create_message(room_id, bot.user_id, &data.data)
+        .await
+    {
+        Ok(_) => outbox_signal.wake(),
+        Err(err) => {
+            warn!("Failed to create bot message: {:?}", err);
+            scope.fail();
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn handle_bot_set_recording_paused<A: Adapter>(
+    socket: SocketRef<A>,
+    Data(data): Data<SetRecordingPausedDto>,
+    Extension(BotIdentity(bot)): Extension<BotIdentity>,
+    bot_service: State<BotServiceImpl<BotRepositoryImpl>>,
+    dispatcher_manager: State<DispatcherManager>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    timeline_service: State<RoomTimelines>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomRecordingPaused,
+        Budget::Rtc,
+        Some(PayloadSummary::of::<SetRecordingPausedDto>),
+    ) else {
+        return;
+    };
+
+    let Some(bot) = _authorize_bot(&socket, &bot_service, &bot, BotScope::RecordingControl).await
+    else {
+        scope.fail();
+        return;
+    };
+
+    if let Err(err) = _check_bot_member(&room_service, &data.room_id, &bot).await {
+        warn!("Rejected bot {} recording pause change: {:?}", bot.id, err);
+        scope.fail();
+        return;
+    }
+
+    let room_id = data.room_id;
+    let is_paused = data.is_paused;
+
+    let req = SetRecordingPausedRequest {
+        room_id: room_id.clone(),
+        is_paused,
+    };
+
+    match dispatcher_manager.set_recording_paused(req).await {
+        Ok(()) => {
+            _journal(
+                &timeline_service,
+                &room_id,
+                TimelineEntry::new(RoomEventKind::Recording)
+                    .user(Some(bot.user_id))
+                    .enabled(!is_paused),
+            );
+
+            let _ = socket
+                .within(room_id)
+                .emit(
+                    WsEvent::RoomRecordingPaused.to_str(),
+                    &RecordingPausedResponse { is_paused },
+                )
+                .await
+                .ok();
         }
         Err(err) => {
-            warn!("Err: {:?}", err)
+            warn!("Failed to set recording paused: {:?}", err);
+            scope.fail();
         }
     }
 }
 
-async fn handle_subscribe<A: Adapter>(
+async fn handle_bot_leave_room<A: Adapter>(
     socket: SocketRef<A>,
-    Data(data): Data<SubscribeDto>,
-    dispatcher_manager: State<DispatcherManager>,
+    Extension(BotIdentity(bot)): Extension<BotIdentity>,
 ) {
-    let client_id = socket.id.to_string();
-    let target_id = data.target_id;
-    let participant_id = data.participant_id.clone();
-    let room_id = data.room_id.clone();
+    socket.leave_all();
+    socket.join(_bot_room(bot.id));
+}
 
-    let req = SubscribeRequest {
-        client_id,
-        target_id: target_id.clone(),
-        participant_id,
-        room_id,
-    };
+async fn on_bot_disconnect<A: Adapter>(user_cnt: State<RemoteUserCnt>) {
+    let _ = user_cnt.remove_user().await.unwrap_or(0);
+}
 
-    let res = dispatcher_manager.subscribe(req).await;
+/// The bot as it is now, `None` when it lost the scope or was revoked. A
+/// revoked bot's socket is disconnected, deleting the bot already does that
+/// on every node but the event may have raced the revocation.
+async fn _authorize_bot<A: Adapter>(
+    socket: &SocketRef<A>,
+    bot_service: &BotServiceImpl<BotRepositoryImpl>,
+    bot: &Bot,
+    scope: BotScope,
+) -> Option<Bot> {
+    match bot_service.authorize(bot.id, scope).await {
+        Ok(bot) => Some(bot),
+        Err(BotError::InvalidToken) => {
+            warn!("Disconnecting revoked bot {}", bot.id);
+            socket.clone().disconnect().ok();
+            None
+        }
+        Err(err) => {
+            warn!("Rejected bot {} {}: {:?}", bot.id, scope.to_str(), err);
+            None
+        }
+    }
+}
 
-    if let Ok(res) = res {
-        let _ = socket
-            .emit(
-                WsEvent::RoomAnswerSubscriber.to_str(),
-                &SubscribeParticipantResponse {
-                    subscribe_response: SubscribeResponse {
-                        offer: res.offer,
-                        camera_type: res.camera_type as u8,
-                        video_enabled: res.video_enabled,
-                        audio_enabled: res.audio_enabled,
-                        is_screen_sharing: res.is_screen_sharing,
-                        is_hand_raising: res.is_hand_raising,
-                        is_e2ee_enabled: res.is_e2ee_enabled,
-                        video_codec: res.video_codec,
-                        screen_track_id: res.screen_track_id,
-                    },
-                    target_id,
-                },
-            )
-            .ok();
+/// Every socket of one bot, on `/` and `/chat`
+fn _bot_room(bot_id: i32) -> String {
+    format!("bot:{bot_id}")
+}
+
+/// Disconnects a revoked bot on every node
+async fn _revoke_bot<A: Adapter>(io: &SocketIo<A>, bot_id: i32) {
+    for namespace in [io.of(CHAT_NAMESPACE), Some(io.broadcast())]
+        .into_iter()
+        .flatten()
+    {
+        if let Err(err) = namespace.to(_bot_room(bot_id)).disconnect().await {
+            warn!("Failed to disconnect sockets of bot {}: {:?}", bot_id, err);
+        }
     }
+
+    info!("Bot {} revoked", bot_id);
 }
 
-async fn handle_answer_subscribe<A: Adapter>(
-    socket: SocketRef<A>,
-    Data(data): Data<AnswerSubscribeDto>,
-    dispatcher_manager: State<DispatcherManager>,
-) {
-    // P2P handler
-    if data.connection_type == 0 {
-        let response = JoinRoomResponse {
-            sdp: data.sdp,
-            is_recording: false,
-        };
-        let _ = socket
-            .broadcast()
-            .to(data.room_id)
-            .emit(WsEvent::RoomPublish.to_str(), &response)
-            .await
-            .ok();
-    } else {
-        let client_id = socket.id.to_string();
-        let target_id = data.target_id;
-        let sdp = data.sdp;
+/// Bots are added to rooms by the host like any other member
+async fn _check_bot_member(
+    room_service: &RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    room_id: &str,
+    bot: &Bot,
+) -> Result<i32, anyhow::Error> {
+    let room_id = room_service.resolve_room_id(room_id).await?;
 
-        let req = SetSubscriberSdpRequest {
-            client_id,
-            target_id,
-            sdp,
-        };
+    let room = room_service.get_room_by_id(room_id).await?;
 
-        let _ = dispatcher_manager.set_subscribe_sdp(req).await;
+    if !room
+        .members
+        .iter()
+        .any(|member| member.member.user_id == bot.user_id)
+    {
+        return Err(BotError::NotAMember(room_id).into());
     }
+
+    Ok(room_id)
 }
 
-async fn handle_publisher_renegotiation<A: Adapter>(
+async fn _check_host(
+    room_service: &RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    room_id: &str,
+    user_id: &str,
+) -> Result<(), anyhow::Error> {
+    let room_id = room_service.resolve_room_id(room_id).await?;
+    let user_id = user_id.parse::<i32>()?;
+
+    room_service.check_host(room_id, user_id).await?;
+
+    Ok(())
+}
+
+async fn handle_leave_room<A: Adapter>(
     socket: SocketRef<A>,
-    Data(data): Data<PublisherRenegotiationDto>,
+    io: SocketIo<A>,
     dispatcher_manager: State<DispatcherManager>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    timeline_service: State<RoomTimelines>,
+    overflow_queue: State<OverflowQueue>,
+    hls_session_store: State<HlsSessionStore>,
+    jwt_utils: State<JwtUtils>,
+    metrics: State<SocketMetrics>,
 ) {
-    // P2P handler
-    if data.connection_type == 0 {
-        let _ = socket
-            .broadcast()
-            .to(data.room_id)
-            .emit(
-                WsEvent::RoomSubscriberRenegotiation.to_str(),
-                &SubscriberRenegotiationResponse {
-                    target_id: "".to_string(),
-                    sdp: data.sdp,
-                },
-            )
-            .await
-            .ok();
-    } else {
-        let client_id = socket.id.to_string();
-        let sdp = data.sdp;
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomLeave,
+        Budget::Unlimited,
+        None,
+    ) else {
+        return;
+    };
 
-        let req = PublisherRenegotiationRequest { client_id, sdp };
+    if _handle_leave_room(
+        socket,
+        io,
+        dispatcher_manager.0,
+        room_service.0,
+        timeline_service.0,
+        overflow_queue.0,
+        hls_session_store.0,
+        jwt_utils.0,
+    )
+    .await
+    .is_err()
+    {
+        scope.fail();
+    }
+}
 
-        let sdp = dispatcher_manager.publisher_renegotiate(req).await;
+#[allow(clippy::too_many_arguments)]
+async fn _handle_leave_room<A: Adapter>(
+    socket: SocketRef<A>,
+    io: SocketIo<A>,
+    dispatcher_manager: DispatcherManager,
+    room_service: RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    timeline_service: RoomTimelines,
+    overflow_queue: OverflowQueue,
+    hls_session_store: HlsSessionStore,
+    jwt_utils: JwtUtils,
+) -> Result<(), anyhow::Error> {
+    let client_id = socket.id.to_string();
 
-        if let Ok(sdp) = sdp {
-            let _ = socket
-                .emit(
-                    WsEvent::RoomPublisherRenegotiation.to_str(),
-                    &RenegotiateResponse { sdp: sdp.sdp },
-                )
-                .ok();
+    let req = LeaveRoomRequest { client_id };
+
+    let info = dispatcher_manager.leave_room(req).await?;
+
+    let info_clone = info.clone();
+    let room_id = info_clone.room_id.clone();
+    let participant_id = info_clone.participant_id.clone();
+
+    let _ = socket
+        .broadcast()
+        .to(info_clone.room_id)
+        .emit(
+            WsEvent::RoomParticipantLeft.to_str(),
+            &ParticipantHasLeftResponse {
+                target_id: info_clone.participant_id,
+            },
+        )
+        .await
+        .ok();
+
+    socket.leave(room_id.clone());
+    socket.extensions.remove::<RoomPolicy>();
+    socket.extensions.remove::<CallIds>();
+
+    match room_service.delete_participant(&participant_id).await {
+        Ok(()) => {
+            info!("Participant with ID {} deleted", participant_id);
+        }
+        Err(err) => {
+            warn!("Failed to delete participant: {:?}", err);
+        }
+    };
+
+    if let Err(err) = _offer_free_slot(&io, &room_service, &overflow_queue, &room_id).await {
+        warn!(
+            "Failed to offer the free slot of room {}: {:?}",
+            room_id, err
+        );
+    }
+
+    let user_id = socket.extensions.get::<UserId>().map(|user_id| user_id.0);
+
+    // Whether or not the participant was watching over HLS, it's one stream
+    // fewer for everyone who is
+    if let Some(user_id) = &user_id {
+        socket.leave(_hls_session_room(&room_id, user_id));
+        if let Err(err) = hls_session_store.close(&room_id, user_id).await {
+            warn!(
+                "Failed to close HLS session of participant {}: {:?}",
+                participant_id, err
+            );
         }
     }
+    _push_hls_room(&io, &hls_session_store, &room_service, &jwt_utils, &room_id).await;
+
+    // After the participant is gone, so the last one out ends the session
+    let user_id = user_id.and_then(|user_id| user_id.parse().ok());
+    _journal(
+        &timeline_service,
+        &room_id,
+        TimelineEntry::new(RoomEventKind::ParticipantLeft)
+            .participant(&participant_id)
+            .user(user_id),
+    );
+
+    Ok(())
 }
 
-async fn handle_migrate_connection<A: Adapter>(
+async fn handle_observe_room<A: Adapter>(
     socket: SocketRef<A>,
-    Data(data): Data<MigrateConnectionDto>,
-    dispatcher_manager: State<DispatcherManager>,
+    Extension(ObserverIdentity(claims)): Extension<ObserverIdentity>,
+    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
+    observer_disclosure: State<ObserverDisclosure>,
+    metrics: State<SocketMetrics>,
 ) {
-    let client_id = socket.id.to_string();
-    let sdp = data.sdp;
-    let connection_type = data.connection_type as i32;
+    let Some(mut scope) =
+        handler::begin(&socket, &metrics, WsEvent::RoomObserve, Budget::Rtc, None)
+    else {
+        return;
+    };
 
-    let req = MigratePublisherRequest {
-        client_id,
-        sdp,
-        connection_type,
+    let room = match room_service.resolve_room_id(&claims.room_id).await {
+        Ok(room_id) => room_service.get_room_by_id(room_id).await,
+        Err(err) => Err(err),
+    };
+    let room = match room {
+        Ok(room) => room,
+        Err(err) => {
+            warn!(
+                "Observer {} can't watch room {}: {:?}",
+                claims.observer_id, claims.room_id, err
+            );
+            scope.fail();
+            return;
+        }
     };
 
-    let sdp = dispatcher_manager.migrate_connection(req).await;
+    let policy = RoomPolicy::from_room(&room.room, false);
+    if !policy.allows(WsEvent::RoomObserve) {
+        warn!(
+            "Observer {} can't watch P2P room {}",
+            claims.observer_id, claims.room_id
+        );
+        scope.fail();
+        return;
+    }
+    socket.extensions.insert(policy);
 
-    if let Ok(sdp) = sdp
-        && let Some(sdp) = sdp.sdp
+    // Gets the room's broadcasts, so it learns who joins and leaves
+    socket.join(claims.room_id.clone());
+    socket.join(_observers_room(&claims.room_id));
+
+    info!(
+        "Observer {} ({}) is watching room {}",
+        claims.observer_id, claims.label, claims.room_id
+    );
+
+    let _ = socket
+        .emit(
+            WsEvent::RoomObserve.to_str(),
+            &ObserveRoomResponse {
+                room_id: claims.room_id.clone(),
+                participants: room.participants,
+            },
+        )
+        .ok();
+
+    _notify_observers(&socket, &claims.room_id, *observer_disclosure).await;
+}
+
+async fn handle_observer_leave<A: Adapter>(
+    socket: SocketRef<A>,
+    dispatcher_manager: State<DispatcherManager>,
+    observer_disclosure: State<ObserverDisclosure>,
+    metrics: State<SocketMetrics>,
+) {
+    let Some(mut scope) = handler::begin(
+        &socket,
+        &metrics,
+        WsEvent::RoomLeave,
+        Budget::Unlimited,
+        None,
+    ) else {
+        return;
+    };
+
+    if _handle_observer_leave(&socket, &dispatcher_manager, *observer_disclosure)
+        .await
+        .is_err()
     {
-        let _ = socket
-            .emit(WsEvent::RoomMigrate.to_str(), &RenegotiateResponse { sdp })
-            .ok();
+        scope.fail();
     }
 }
 
-async fn handle_publisher_candidate<A: Adapter>(
+async fn on_observer_disconnect<A: Adapter>(
     socket: SocketRef<A>,
-    Data(data): Data<PublisherCandidateDto>,
+    user_cnt: State<RemoteUserCnt>,
     dispatcher_manager: State<DispatcherManager>,
+    observer_disclosure: State<ObserverDisclosure>,
 ) {
-    let client_id = socket.id.to_string();
-    let candidate = data.candidate;
+    let _ = _handle_observer_leave(&socket, &dispatcher_manager, *observer_disclosure).await;
 
-    let candidate = waterbus_proto::common::IceCandidate {
-        candidate: candidate.candidate,
-        sdp_mid: candidate.sdp_mid,
-        sdp_m_line_index: candidate.sdp_m_line_index.map(|v| v as u32),
+    let _ = user_cnt.remove_user().await.unwrap_or(0);
+}
+
+/// Nothing is broadcast, deleted or journaled, the room never saw the observer
+async fn _handle_observer_leave<A: Adapter>(
+    socket: &SocketRef<A>,
+    dispatcher_manager: &DispatcherManager,
+    observer_disclosure: ObserverDisclosure,
+) -> Result<(), anyhow::Error> {
+    let Some(ObserverIdentity(claims)) = socket.extensions.get::<ObserverIdentity>() else {
+        return Ok(());
     };
 
-    let req = AddPublisherCandidateRequest {
-        client_id,
-        candidate: Some(candidate.clone()),
-        connection_type: data.connection_type as i32,
+    // Fails when the observer never subscribed to anyone, the SFU has nothing to close then
+    let result = dispatcher_manager
+        .leave_room(LeaveRoomRequest {
+            client_id: socket.id.to_string(),
+        })
+        .await;
+
+    socket.leave(claims.room_id.clone());
+    socket.leave(_observers_room(&claims.room_id));
+    socket.extensions.remove::<RoomPolicy>();
+
+    _notify_observers(socket, &claims.room_id, observer_disclosure).await;
+
+    result.map(|_| ())
+}
+
+fn _observers_room(room_id: &str) -> String {
+    format!("{room_id}:observers")
+}
+
+/// Sockets of one user watching the room over HLS, on `/rtc` when moved off
+/// WebRTC or on `/chat` while waiting for a slot
+fn _hls_session_room(room_id: &str, user_id: &str) -> String {
+    format!("{room_id}:hls:{user_id}")
+}
+
+fn _hosts_room(room_id: &str) -> String {
+    format!("{room_id}:hosts")
+}
+
+/// Chat sockets of the users waiting for a slot in a full call
+fn _overflow_room(room_id: &str) -> String {
+    format!("{room_id}:overflow")
+}
+
+/// Tells the viewer who waited longest for a slot in the call that one is
+/// free. Joining again is what takes it, so a joiner who wasn't in line may
+/// still get there first.
+async fn _offer_free_slot<A: Adapter>(
+    io: &SocketIo<A>,
+    room_service: &RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    overflow_queue: &OverflowQueue,
+    room_id: &str,
+) -> Result<(), anyhow::Error> {
+    let room = room_service
+        .get_room_by_id(room_service.resolve_room_id(room_id).await?)
+        .await?;
+
+    if room.room.capacity.is_none() {
+        return Ok(());
+    }
+
+    let Some(viewer) = overflow_queue.peek(room_id).await? else {
+        return Ok(());
     };
 
-    if data.connection_type == 0 {
-        let _ = socket
-            .broadcast()
-            .to(data.room_id)
-            .emit(
-                WsEvent::RoomSubscriberCandidate.to_str(),
-                &SubsriberCandidateResponse {
-                    candidate: IceCandidate {
-                        candidate: candidate.candidate,
-                        sdp_mid: candidate.sdp_mid,
-                        sdp_m_line_index: candidate.sdp_m_line_index,
-                    },
-                    target_id: "".to_string(),
-                },
-            )
-            .await
-            .ok();
-    } else {
-        let _ = dispatcher_manager.add_publisher_candidate(req).await;
+    if is_room_full(&room, viewer.user_id) {
+        return Ok(());
     }
+
+    _emit_chat(
+        io,
+        _overflow_room(room_id),
+        WsEvent::RoomSlotAvailable,
+        &SlotAvailableResponse {
+            room_id: room_id.to_string(),
+            viewer_id: viewer.viewer_id,
+        },
+    )
+    .await;
+
+    Ok(())
 }
 
-async fn handle_subscriber_candidate<A: Adapter>(
-    socket: SocketRef<A>,
-    Data(data): Data<SubscriberCandidateDto>,
-    dispatcher_manager: State<DispatcherManager>,
+/// Tells the hosts in the call how many observers are watching, unless the
+/// deployment keeps observers hidden
+async fn _notify_observers<A: Adapter>(
+    socket: &SocketRef<A>,
+    room_id: &str,
+    observer_disclosure: ObserverDisclosure,
 ) {
-    let client_id = socket.id.to_string();
-    let candidate = data.candidate.clone();
-    let target_id = data.target_id;
+    if observer_disclosure == ObserverDisclosure::Hidden {
+        return;
+    }
 
-    let candidate = waterbus_proto::common::IceCandidate {
-        candidate: candidate.candidate,
-        sdp_mid: candidate.sdp_mid,
-        sdp_m_line_index: candidate.sdp_m_line_index.map(|v| v as u32),
+    let count = match socket
+        .within(_observers_room(room_id))
+        .fetch_sockets()
+        .await
+    {
+        Ok(observers) => observers.len(),
+        Err(err) => {
+            warn!("Failed to count observers of room {}: {:?}", room_id, err);
+            return;
+        }
     };
 
-    let req = AddSubscriberCandidateRequest {
-        client_id,
-        target_id,
-        candidate: Some(candidate),
-        connection_type: data.connection_type as i32,
+    let _ = socket
+        .within(_hosts_room(room_id))
+        .emit(
+            WsEvent::RoomObservers.to_str(),
+            &ObserversResponse {
+                room_id: room_id.to_string(),
+                count,
+            },
+        )
+        .await
+        .ok();
+}
+
+/// Off the socket's path, a timeline write never holds up the call
+fn _journal(timeline_service: &RoomTimelines, room_id: &str, entry: TimelineEntry) {
+    let timeline_service = timeline_service.clone();
+    let room_id = room_id.to_string();
+
+    tokio::spawn(async move {
+        let kind = entry.kind;
+        if let Err(err) = timeline_service.record(&room_id, entry).await {
+            warn!(
+                "Failed to journal {} in room {}: {:?}",
+                kind.to_str(),
+                room_id,
+                err
+            );
+        }
+    });
+}
+
+/// Values come straight from the client, blank ones count as not declared
+fn _sanitize_client_metadata(client: ClientMetadataDto) -> ClientMetadataDto {
+    let clamp = |value: Option<String>| {
+        value
+            .map(|value| {
+                value
+                    .trim()
+                    .chars()
+                    .take(MAX_CLIENT_METADATA_LEN)
+                    .collect::<String>()
+            })
+            .filter(|value| !value.is_empty())
     };
 
-    if data.connection_type == 0 {
-        let _ = socket
-            .broadcast()
-            .to(data.room_id)
-            .emit(WsEvent::RoomPublisherCandidate.to_str(), &data.candidate)
-            .await
-            .ok();
-    } else {
-        let _ = dispatcher_manager.add_subscriber_candidate(req).await;
+    ClientMetadataDto {
+        client_version: clamp(client.client_version),
+        platform: clamp(client.platform.map(|platform| platform.to_lowercase())),
+        device_model: clamp(client.device_model),
+        network_type: clamp(client.network_type.map(|network| network.to_lowercase())),
     }
 }
 
-async fn handle_set_camera_type<A: Adapter>(
-    socket: SocketRef<A>,
-    Data(data): Data<SetCameraTypeDto>,
-    dispatcher_manager: State<DispatcherManager>,
-) {
-    let client_id = socket.id.to_string();
-    let camera_type = data.type_;
+fn to_proto_tracks(tracks: Vec<TrackInfoDto>) -> Vec<TrackInfo> {
+    tracks
+        .into_iter()
+        .map(|track| TrackInfo {
+            track_id: track.track_id,
+            source: track.source as i32,
+            label: track.label.unwrap_or_default(),
+            kind: String::new(),
+        })
+        .collect()
+}
 
-    let req = SetCameraType {
-        camera_type,
-        client_id,
+/// Signed HLS playlists of everyone else live in the room
+/// Signed HLS URLs of the room for a participant WebRTC doesn't work out
+/// for, tracked so they're renewed and follow the call as it changes
+async fn _open_hls_fallback<A: Adapter>(
+    socket: &SocketRef<A>,
+    room_service: &RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    hls_session_store: &HlsSessionStore,
+    jwt_utils: &JwtUtils,
+    room_id: &str,
+    participant_id: &str,
+) -> Option<SuggestHlsResponse> {
+    let expires_at = jwt_utils.hls_expires_at();
+    let streams = match _get_hls_streams(room_service, jwt_utils, room_id, participant_id).await {
+        Ok(streams) => streams,
+        Err(err) => {
+            warn!("Failed to build HLS fallback: {:?}", err);
+            return None;
+        }
     };
 
-    let resp = dispatcher_manager.set_camera_type(req).await;
+    if let Some(UserId(user_id)) = socket.extensions.get::<UserId>() {
+        let session = HlsSession {
+            room_id: room_id.to_string(),
+            user_id,
+            viewer_id: participant_id.to_string(),
+        };
+        socket.join(_hls_session_room(&session.room_id, &session.user_id));
 
-    if let Ok(client) = resp {
-        let _ = socket
-            .broadcast()
-            .to(client.room_id)
-            .emit(
-                WsEvent::RoomCameraType.to_str(),
-                &CameraTypeResponse {
-                    participant_id: client.participant_id,
-                    type_: camera_type,
-                },
-            )
-            .await
-            .ok();
+        if let Err(err) = hls_session_store.open(&session, expires_at).await {
+            warn!(
+                "Failed to open HLS session of participant {}: {:?}",
+                participant_id, err
+            );
+        }
     }
-}
 
-async fn handle_set_video_enabled<A: Adapter>(
-    socket: SocketRef<A>,
-    Data(data): Data<SetEnabledDto>,
-    dispatcher_manager: State<DispatcherManager>,
-) {
-    let client_id = socket.id.to_string();
-    let is_enabled = data.is_enabled;
+    Some(SuggestHlsResponse {
+        room_id: room_id.to_string(),
+        streams,
+        expires_at,
+    })
+}
 
-    let req = SetEnabledRequest {
-        client_id,
-        is_enabled,
-    };
+async fn _get_hls_streams(
+    room_service: &RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    jwt_utils: &JwtUtils,
+    room_id: &str,
+    participant_id: &str,
+) -> Result<Vec<HlsStreamResponse>, RoomError> {
+    let public_room_id = room_id;
+    let room_id = room_service.resolve_room_id(public_room_id).await?;
 
-    let resp = dispatcher_manager.set_video_enabled(req).await;
+    let participants = room_service
+        .get_participants(
+            room_id,
+            None,
+            PaginationDto {
+                skip: 0,
+                limit: MAX_HLS_STREAMS,
+            },
+        )
+        .await?;
 
-    if let Ok(client) = resp {
-        let _ = socket
-            .broadcast()
-            .to(client.room_id)
-            .emit(
-                WsEvent::RoomVideoEnabled.to_str(),
-                &EnabledResponse {
-                    participant_id: client.participant_id,
-                    is_enabled,
-                },
-            )
-            .await
-            .ok();
-    }
+    Ok(participants
+        .into_iter()
+        .map(|participant| participant.participant.public_id)
+        .filter(|public_id| public_id != participant_id)
+        .map(|public_id| HlsStreamResponse {
+            url: jwt_utils.sign_hls_url(public_room_id, participant_id, &public_id),
+            participant_id: public_id,
+        })
+        .collect())
 }
 
-async fn handle_set_audio_enabled<A: Adapter>(
-    socket: SocketRef<A>,
-    Data(data): Data<SetEnabledDto>,
-    dispatcher_manager: State<DispatcherManager>,
+/// Sends every HLS session of the room the streams as they are now, after
+/// someone joined or left the call
+async fn _push_hls_room<A: Adapter>(
+    io: &SocketIo<A>,
+    hls_session_store: &HlsSessionStore,
+    room_service: &RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    jwt_utils: &JwtUtils,
+    room_id: &str,
 ) {
-    let client_id = socket.id.to_string();
-    let is_enabled = data.is_enabled;
-
-    let req = SetEnabledRequest {
-        client_id,
-        is_enabled,
+    let sessions = match hls_session_store.sessions(room_id).await {
+        Ok(sessions) => sessions,
+        Err(err) => {
+            warn!("Failed to list HLS sessions of room {}: {:?}", room_id, err);
+            return;
+        }
     };
 
-    let resp = dispatcher_manager.set_audio_enabled(req).await;
+    for session in sessions {
+        let result = match _push_hls_streams(io, room_service, jwt_utils, &session).await {
+            Ok(expires_at) => hls_session_store
+                .renew(&session, expires_at)
+                .await
+                .map_err(anyhow::Error::from),
+            Err(err) => Err(err.into()),
+        };
 
-    if let Ok(client) = resp {
-        let _ = socket
-            .broadcast()
-            .to(client.room_id)
-            .emit(
-                WsEvent::RoomAudioEnabled.to_str(),
-                &EnabledResponse {
-                    participant_id: client.participant_id,
-                    is_enabled,
-                },
-            )
-            .await
-            .ok();
+        if let Err(err) = result {
+            warn!(
+                "Failed to update HLS session of user {} in room {}: {:?}",
+                session.user_id, room_id, err
+            );
+        }
     }
 }
 
-async fn handle_set_screen_sharing<A: Adapter>(
-    socket: SocketRef<A>,
-    Data(data): Data<SetScreenSharingDto>,
-    dispatcher_manager: State<DispatcherManager>,
-) {
-    let client_id = socket.id.to_string();
-    let is_enabled = data.is_sharing;
-    let screen_track_id = data.screen_track_id;
+/// Signs the session a new set of URLs and sends it as `room.hls_streams`,
+/// returns when they expire
+async fn _push_hls_streams<A: Adapter>(
+    io: &SocketIo<A>,
+    room_service: &RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    jwt_utils: &JwtUtils,
+    session: &HlsSession,
+) -> Result<i64, RoomError> {
+    let expires_at = jwt_utils.hls_expires_at();
+    let streams = _get_hls_streams(
+        room_service,
+        jwt_utils,
+        &session.room_id,
+        &session.viewer_id,
+    )
+    .await?;
 
-    let req = SetScreenSharingRequest {
-        client_id,
-        is_enabled,
-        screen_track_id: screen_track_id.clone(),
+    let response = SuggestHlsResponse {
+        room_id: session.room_id.clone(),
+        streams,
+        expires_at,
     };
 
-    let resp = dispatcher_manager.set_screen_sharing(req).await;
-
-    if let Ok(client) = resp {
-        let _ = socket
-            .broadcast()
-            .to(client.room_id)
-            .emit(
-                WsEvent::RoomScreenSharing.to_str(),
-                &ScreenSharingResponse {
-                    participant_id: client.participant_id,
-                    is_sharing: is_enabled,
-                    screen_track_id,
-                },
-            )
+    for namespace in [
+        io.of(RTC_NAMESPACE),
+        io.of(CHAT_NAMESPACE),
+        Some(io.broadcast()),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        let _ = namespace
+            .to(_hls_session_room(&session.room_id, &session.user_id))
+            .emit(WsEvent::RoomHlsStreams.to_str(), &response)
             .await
             .ok();
     }
+
+    Ok(expires_at)
 }
 
-async fn handle_set_hand_raising<A: Adapter>(
-    socket: SocketRef<A>,
-    Data(data): Data<SetHandRaisingDto>,
-    dispatcher_manager: State<DispatcherManager>,
-) {
-    let client_id = socket.id.to_string();
-    let is_enabled = data.is_raising;
+/// Whether any socket of the session is still connected, to any node
+/// Hosts are on `/rtc`, or on `/` when their client predates the split
+async fn _has_hosts<A: Adapter>(io: &SocketIo<A>, room_id: &str) -> bool {
+    for namespace in [io.of(RTC_NAMESPACE), Some(io.broadcast())]
+        .into_iter()
+        .flatten()
+    {
+        if namespace
+            .within(_hosts_room(room_id))
+            .fetch_sockets()
+            .await
+            .is_ok_and(|sockets| !sockets.is_empty())
+        {
+            return true;
+        }
+    }
 
-    let req = SetEnabledRequest {
-        client_id,
-        is_enabled,
-    };
+    false
+}
 
-    let resp = dispatcher_manager.set_hand_raising(req).await;
+async fn _is_hls_session_watched<A: Adapter>(io: &SocketIo<A>, session: &HlsSession) -> bool {
+    let room = _hls_session_room(&session.room_id, &session.user_id);
 
-    if let Ok(client) = resp {
-        let _ = socket
-            .broadcast()
-            .to(client.room_id)
-            .emit(
-                WsEvent::RoomHandRaising.to_str(),
-                &HandleRaisingResponse {
-                    participant_id: client.participant_id,
-                    is_raising: is_enabled,
-                },
-            )
+    for namespace in [
+        io.of(RTC_NAMESPACE),
+        io.of(CHAT_NAMESPACE),
+        Some(io.broadcast()),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        if namespace
+            .within(room.clone())
+            .fetch_sockets()
             .await
-            .ok();
+            .is_ok_and(|sockets| !sockets.is_empty())
+        {
+            return true;
+        }
     }
-}
 
-async fn handle_set_subscribe_subtitle<A: Adapter>(
-    _: SocketRef<A>,
-    Data(_data): Data<SetEnabledDto>,
-) {
+    false
 }
 
-async fn handle_leave_room<A: Adapter>(
-    socket: SocketRef<A>,
-    dispatcher_manager: State<DispatcherManager>,
-    room_service: State<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>,
-) {
-    let _ = _handle_leave_room(socket, dispatcher_manager.0, room_service.0).await;
-}
+#[cfg(test)]
+mod tests {
+    use std::{
+        fmt::Debug,
+        panic::{self, AssertUnwindSafe},
+        sync::{Arc, Mutex},
+    };
 
-async fn _handle_leave_room<A: Adapter>(
-    socket: SocketRef<A>,
-    dispatcher_manager: DispatcherManager,
-    room_service: RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
-) -> Result<(), anyhow::Error> {
-    let client_id = socket.id.to_string();
+    use serde::de::DeserializeOwned;
+    use serde_json::{Number, Value, json};
+    use socketioxide_core::parser::Parse;
+    use socketioxide_parser_msgpack::MsgPackParser;
 
-    let req = LeaveRoomRequest { client_id };
+    use crate::core::{
+        dtos::moderation::report_participant_dto::ReportParticipantDto,
+        utils::panic_guard::{HandlerPanic, PanicGuard},
+    };
 
-    let info = dispatcher_manager.leave_room(req).await?;
+    use super::*;
 
-    let info_clone = info.clone();
-    let room_id = info_clone.room_id.clone();
-    let participant_id = info_clone.participant_id.clone();
+    const ITERATIONS: usize = 2_000;
 
-    let _ = socket
-        .broadcast()
-        .to(info_clone.room_id)
-        .emit(
-            WsEvent::RoomParticipantLeft.to_str(),
-            &ParticipantHasLeftResponse {
-                target_id: info_clone.participant_id,
-            },
-        )
-        .await
-        .ok();
+    /// xorshift64, the same payloads on every run
+    struct Fuzzer(u64);
 
-    socket.leave(room_id);
+    impl Fuzzer {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
 
-    match participant_id.parse::<i32>() {
-        Ok(id) => match room_service.delete_participant(id).await {
-            Ok(()) => {
-                info!("Participant with ID {} deleted", participant_id);
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+
+        fn string(&mut self) -> String {
+            match self.below(5) {
+                0 => String::new(),
+                1 => " \t\n".to_owned(),
+                2 => "é💥\u{0}\u{202e}".repeat(self.below(64) as usize),
+                3 => "a".repeat(self.below(128 * 1024) as usize),
+                _ => format!("{:x}", self.next()),
             }
-            Err(err) => {
-                warn!("Failed to delete participant: {:?}", err);
+        }
+
+        fn value(&mut self, depth: u32) -> Value {
+            match self.below(if depth == 0 { 6 } else { 8 }) {
+                0 => Value::Null,
+                1 => Value::Bool(self.below(2) == 0),
+                2 => json!(self.next() as i64),
+                3 => {
+                    Number::from_f64(f64::from_bits(self.next())).map_or(Value::Null, Value::Number)
+                }
+                4 => json!(self.next()),
+                5 => Value::String(self.string()),
+                6 => Value::Array((0..self.below(4)).map(|_| self.value(depth - 1)).collect()),
+                _ => Value::Object(
+                    (0..self.below(4))
+                        .map(|_| (self.string(), self.value(depth - 1)))
+                        .collect(),
+                ),
             }
-        },
-        Err(e) => {
-            warn!("Failed to parse participant_id as i32: {:?}", e);
         }
-    };
 
-    Ok(())
+        /// Replaces, drops or adds a field somewhere in the payload
+        fn mutate(&mut self, payload: &mut Value) {
+            match payload {
+                Value::Object(fields) if !fields.is_empty() && self.below(4) != 0 => {
+                    let key = fields
+                        .keys()
+                        .nth(self.below(fields.len() as u64) as usize)
+                        .unwrap()
+                        .clone();
+
+                    match self.below(3) {
+                        0 => {
+                            fields.remove(&key);
+                        }
+                        1 => {
+                            fields.insert(key, self.value(3));
+                        }
+                        _ => self.mutate(fields.get_mut(&key).unwrap()),
+                    }
+                }
+                Value::Array(items) if !items.is_empty() && self.below(2) == 0 => {
+                    let index = self.below(items.len() as u64) as usize;
+                    self.mutate(&mut items[index]);
+                }
+                Value::Object(fields) => {
+                    fields.insert(self.string(), self.value(2));
+                }
+                _ => *payload = self.value(3),
+            }
+        }
+    }
+
+    /// Feeds mutations of a well-formed payload through what the handler of
+    /// `event` does with it before any service call: the msgpack round trip
+    /// and decoding `Data` does, then `check`, all under the handler's panic
+    /// guard. The summary that guard would report is taken once up front.
+    fn fuzz<T>(event: WsEvent, sample: Value, check: impl Fn(T))
+    where
+        T: DeserializeOwned + Debug + Send + 'static,
+    {
+        panic_guard::install_hook();
+        PayloadSummary::of::<T>();
+
+        assert!(
+            decode::<T>(event, &sample).is_some(),
+            "sample of {} doesn't decode",
+            event.to_str()
+        );
+
+        let mut fuzzer = Fuzzer(0x9e37_79b9_7f4a_7c15);
+        let panics = Arc::new(Mutex::new(Vec::new()));
+
+        for _ in 0..ITERATIONS {
+            let mut payload = sample.clone();
+            for _ in 0..=fuzzer.below(3) {
+                fuzzer.mutate(&mut payload);
+            }
+
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                let Some(data) = decode::<T>(event, &payload) else {
+                    return;
+                };
+
+                let panics = panics.clone();
+                let _guard = PanicGuard::new(
+                    event.to_str(),
+                    None,
+                    Some(PayloadSummary::of::<T>),
+                    move |panic: HandlerPanic| {
+                        panics.lock().unwrap().push(panic);
+                    },
+                );
+
+                check(data);
+            }));
+
+            assert!(result.is_ok(), "{} panicked on {}", event.to_str(), payload);
+        }
+
+        assert!(panics.lock().unwrap().is_empty());
+    }
+
+    /// Encodes the payload the way a msgpack client sends it and decodes it
+    /// the way the extractors do: `TryData` reads the connect auth, `Data`
+    /// the arguments of an event
+    fn decode<T: DeserializeOwned>(event: WsEvent, payload: &Value) -> Option<T> {
+        let parser = MsgPackParser;
+
+        if event == WsEvent::Connection {
+            let auth = parser.encode_default(payload).ok()?;
+            return parser.decode_default(Some(&auth)).ok();
+        }
+
+        let mut packet = parser.encode_value(payload, Some(event.to_str())).ok()?;
+        parser.decode_value(&mut packet, true).ok()
+    }
+
+    fn candidate() -> Value {
+        json!({
+            "candidate": "candidate:1 1 udp 1 10.0.0.1 5000 typ host",
+            "sdpMid": "0",
+            "sdpMLineIndex": 0
+        })
+    }
+
+    #[test]
+    fn test_malformed_rtc_payloads() {
+        let track = json!({ "trackId": "t1", "source": 0, "label": "Camera" });
+
+        fuzz(
+            WsEvent::Connection,
+            json!({
+                "clientVersion": "2.4.1", "platform": "ios", "deviceModel": "iPhone",
+                "networkType": "wifi"
+            }),
+            |client: ClientMetadataDto| {
+                _sanitize_client_metadata(client);
+            },
+        );
+        fuzz(
+            WsEvent::RoomPublish,
+            json!({
+                "sdp": "v=0", "joinToken": "token", "isVideoEnabled": true, "isAudioEnabled": true,
+                "isE2eeEnabled": false, "totalTracks": 2, "tracks": [track],
+                "isNoiseSuppressed": false
+            }),
+            |_: JoinRoomDto| {},
+        );
+        fuzz(
+            WsEvent::RoomSubscribe,
+            json!({
+                "targetId": "p2", "roomId": "r1", "participantId": "p1", "videoCodecs": ["vp8"]
+            }),
+            |_: SubscribeDto| {},
+        );
+        fuzz(
+            WsEvent::RoomAnswerSubscriber,
+            json!({ "roomId": "r1", "targetId": "p2", "sdp": "v=0" }),
+            |_: AnswerSubscribeDto| {},
+        );
+        fuzz(
+            WsEvent::RoomPublisherRenegotiation,
+            json!({ "sdp": "v=0", "roomId": "r1", "participantId": "p1", "tracks": [track] }),
+            |_: PublisherRenegotiationDto| {},
+        );
+        fuzz(
+            WsEvent::RoomMigrate,
+            json!({ "sdp": "v=0", "roomId": "r1", "participantId": "p1" }),
+            |_: MigrateConnectionDto| {},
+        );
+        fuzz(
+            WsEvent::RoomPublisherCandidate,
+            json!({ "candidate": candidate(), "roomId": "r1" }),
+            |_: PublisherCandidateDto| {},
+        );
+        fuzz(
+            WsEvent::RoomSubscriberCandidate,
+            json!({ "targetId": "p2", "candidate": candidate(), "roomId": "r1" }),
+            |_: SubscriberCandidateDto| {},
+        );
+        fuzz(
+            WsEvent::RoomViewportUpdate,
+            json!({ "tiles": [{ "participantId": "p2", "width": 640, "height": 360 }] }),
+            |data: ViewportUpdateDto| {
+                if let Some(req) = _viewport_request("socket-1".to_owned(), data) {
+                    assert!(req.tiles.len() <= MAX_VIEWPORT_TILES);
+                }
+            },
+        );
+        fuzz(
+            WsEvent::RoomClientStats,
+            json!({
+                "roomId": "r1", "participantId": "p1", "timestamp": 1, "rttMs": 20.0,
+                "jitterMs": 1.0, "packetLoss": 0.01, "availableOutgoingBitrate": 1e6,
+                "tracks": [{
+                    "trackId": "t1", "kind": "video", "direction": "outbound", "bytes": 1,
+                    "packets": 1, "packetsLost": 0, "jitterMs": 1.0, "framesPerSecond": 30.0
+                }]
+            }),
+            |_: ClientStatsDto| {},
+        );
+        fuzz(
+            WsEvent::RoomReport,
+            json!({
+                "roomId": "r1", "targetId": "p2", "reason": "spam", "details": "",
+                "snapshotUrl": null
+            }),
+            |data: RoomReportDto| {
+                ReportParticipantDto::from(data);
+            },
+        );
+    }
+
+    #[test]
+    fn test_malformed_control_payloads() {
+        fuzz(
+            WsEvent::RoomCameraType,
+            json!({ "type": 1 }),
+            |_: SetCameraTypeDto| {},
+        );
+        fuzz(
+            WsEvent::RoomVideoEnabled,
+            json!({ "isEnabled": true, "isHardwareMuted": false }),
+            |_: SetEnabledDto| {},
+        );
+        fuzz(
+            WsEvent::RoomScreenSharing,
+            json!({ "isSharing": true, "screenTrackId": "t3", "screenAudioTrackId": "t4" }),
+            |_: SetScreenSharingDto| {},
+        );
+        fuzz(
+            WsEvent::RoomHandRaising,
+            json!({ "isRaising": true }),
+            |_: SetHandRaisingDto| {},
+        );
+        fuzz(
+            WsEvent::RoomStageMode,
+            json!({ "roomId": "r1", "isEnabled": true }),
+            |_: SetStageModeDto| {},
+        );
+        fuzz(
+            WsEvent::RoomSpeaker,
+            json!({ "roomId": "r1", "participantId": "p2", "isSpeaker": true }),
+            |_: SetSpeakerDto| {},
+        );
+        fuzz(
+            WsEvent::RoomQosClass,
+            json!({ "roomId": "r1", "participantId": "p2", "qosClass": 1 }),
+            |data: SetQosClassDto| {
+                assert!(data.qos_class.min(QOS_CLASS_HOST as u8) <= QOS_CLASS_HOST as u8);
+            },
+        );
+        fuzz(
+            WsEvent::RoomRecordingPaused,
+            json!({ "roomId": "r1", "isPaused": true }),
+            |_: SetRecordingPausedDto| {},
+        );
+        fuzz(
+            WsEvent::RoomBroadcastCut,
+            json!({ "roomId": "r1", "isCut": true }),
+            |_: SetBroadcastCutDto| {},
+        );
+        fuzz(
+            WsEvent::RoomType,
+            json!({ "roomId": "r1", "roomType": 1 }),
+            |data: SetRoomTypeDto| {
+                RoomType::from(data.room_type);
+            },
+        );
+    }
+
+    #[test]
+    fn test_malformed_chat_and_bot_payloads() {
+        fuzz(
+            WsEvent::ChatJoin,
+            json!({ "roomId": "r1" }),
+            |_: ChatRoomDto| {},
+        );
+        fuzz(
+            WsEvent::ChatReaction,
+            json!({ "roomId": "r1", "emoji": "👏" }),
+            |_: ReactionDto| {},
+        );
+        fuzz(
+            WsEvent::RoomBotJoin,
+            json!({ "roomId": "r1" }),
+            |_: BotJoinRoomDto| {},
+        );
+        fuzz(
+            WsEvent::RoomBotMessage,
+            json!({ "roomId": "r1", "data": "{}" }),
+            |_: BotMessageDto| {},
+        );
+    }
+
+    #[test]
+    fn test_guard_reports_handler_panic() {
+        panic_guard::install_hook();
+
+        let panics = Arc::new(Mutex::new(Vec::new()));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let panics = panics.clone();
+            let _guard = PanicGuard::new(
+                WsEvent::RoomCameraType.to_str(),
+                Some(CallIds {
+                    room_id: "room-1".to_owned(),
+                    participant_id: "participant-1".to_owned(),
+                }),
+                Some(PayloadSummary::of::<SetCameraTypeDto>),
+                move |panic: HandlerPanic| panics.lock().unwrap().push(panic),
+            );
+
+            panic!("unknown camera type");
+        }));
+
+        assert!(result.is_err());
+
+        let panics = panics.lock().unwrap();
+        assert_eq!(panics.len(), 1);
+        assert_eq!(panics[0].event, "room.camera_type");
+        assert_eq!(
+            panics[0].call.as_ref().map(|call| call.room_id.as_str()),
+            Some("room-1")
+        );
+        assert!(panics[0].message.starts_with("unknown camera type"));
+        assert_eq!(
+            panics[0]
+                .payload
+                .as_ref()
+                .map(|payload| payload.to_string()),
+            Some("SetCameraTypeDto, keys: [type]".to_owned())
+        );
+    }
 }
@@ -1,4 +1,5 @@
 use async_channel::{Receiver, Sender};
+use dispatcher::infrastructure::event_queue::{Overflow, QueuedEvent};
 
 use super::responses::message_response::MessageResponse;
 
@@ -12,4 +13,28 @@ pub enum AppEvent {
     SendMessage(MessageResponse),
     UpdateMessage(MessageResponse),
     DeleteMessage(MessageResponse),
+    /// Public id of a room an operator closed, its sockets are disconnected
+    CloseRoom(String),
+    /// Id of a bot an operator deleted, its sockets are disconnected
+    RevokeBot(i32),
+}
+
+impl QueuedEvent for AppEvent {
+    fn kind(&self) -> &'static str {
+        match self {
+            AppEvent::SendMessage(_) => "send_message",
+            AppEvent::UpdateMessage(_) => "update_message",
+            AppEvent::DeleteMessage(_) => "delete_message",
+            AppEvent::CloseRoom(_) => "close_room",
+            AppEvent::RevokeBot(_) => "revoke_bot",
+        }
+    }
+
+    /// The outbox marks an entry delivered once it is queued, so this queue
+    /// is where its guarantee ends: dropping the event would lose the message
+    /// for good, and so does a crash before the socket loop emits it. A dropped close would leave the
+    /// room's calls running, a dropped revocation the bot's sockets open.
+    fn overflow(&self) -> Overflow {
+        Overflow::Block
+    }
 }
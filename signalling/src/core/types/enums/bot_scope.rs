@@ -0,0 +1,55 @@
+use std::str::FromStr;
+
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+
+/// What a bot token is allowed to do, stored comma separated on the bot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum BotScope {
+    /// Join rooms the bot is a member of and receive their chat and room events
+    #[serde(rename = "chat:read")]
+    ChatRead,
+    #[serde(rename = "chat:write")]
+    ChatWrite,
+    /// Pause and resume the recording of rooms the bot is a member of
+    #[serde(rename = "recording:control")]
+    RecordingControl,
+}
+
+impl BotScope {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            BotScope::ChatRead => "chat:read",
+            BotScope::ChatWrite => "chat:write",
+            BotScope::RecordingControl => "recording:control",
+        }
+    }
+
+    pub fn join(scopes: &[BotScope]) -> String {
+        scopes
+            .iter()
+            .map(BotScope::to_str)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn parse(scopes: &str) -> Vec<BotScope> {
+        scopes
+            .split(',')
+            .filter_map(|scope| scope.parse().ok())
+            .collect()
+    }
+}
+
+impl FromStr for BotScope {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "chat:read" => Ok(BotScope::ChatRead),
+            "chat:write" => Ok(BotScope::ChatWrite),
+            "recording:control" => Ok(BotScope::RecordingControl),
+            _ => Err(()),
+        }
+    }
+}
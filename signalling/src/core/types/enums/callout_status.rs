@@ -0,0 +1,19 @@
+/// Outcome of a callout attempt as kept in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalloutStatus {
+    /// Accepted by the provider, counts towards the spending cap
+    Initiated,
+    Failed,
+    /// Not dialed because a spending cap or the host's rate limit was reached
+    Rejected,
+}
+
+impl CalloutStatus {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            CalloutStatus::Initiated => "initiated",
+            CalloutStatus::Failed => "failed",
+            CalloutStatus::Rejected => "rejected",
+        }
+    }
+}
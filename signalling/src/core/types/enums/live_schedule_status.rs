@@ -0,0 +1,22 @@
+/// Progress of a scheduled live stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiveScheduleStatus {
+    /// Waiting for the start time
+    Scheduled,
+    /// Start time reached, viewers get the slate until the host joins
+    Slate,
+    /// The host joined and egress is recording the live composition
+    Live,
+    Cancelled,
+}
+
+impl LiveScheduleStatus {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            LiveScheduleStatus::Scheduled => "scheduled",
+            LiveScheduleStatus::Slate => "slate",
+            LiveScheduleStatus::Live => "live",
+            LiveScheduleStatus::Cancelled => "cancelled",
+        }
+    }
+}
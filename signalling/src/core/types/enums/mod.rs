@@ -1 +1,8 @@
+pub mod bot_scope;
+pub mod callout_status;
+pub mod live_schedule_status;
+pub mod observer_disclosure;
+pub mod report_reason;
+pub mod report_status;
+pub mod room_event_kind;
 pub mod ws_event;
@@ -0,0 +1,49 @@
+use std::str::FromStr;
+
+/// Who learns that a room is being observed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObserverDisclosure {
+    /// The hosts in the call are told how many observers are watching
+    Hosts,
+    /// Nobody in the room is told, for deployments whose policy allows it
+    Hidden,
+}
+
+impl ObserverDisclosure {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            ObserverDisclosure::Hosts => "hosts",
+            ObserverDisclosure::Hidden => "hidden",
+        }
+    }
+}
+
+impl FromStr for ObserverDisclosure {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "hosts" => Ok(ObserverDisclosure::Hosts),
+            "hidden" => Ok(ObserverDisclosure::Hidden),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trips_through_str() {
+        for disclosure in [ObserverDisclosure::Hosts, ObserverDisclosure::Hidden] {
+            assert_eq!(disclosure.to_str().parse(), Ok(disclosure));
+        }
+    }
+
+    #[test]
+    fn test_unknown_value_is_rejected() {
+        assert_eq!("Hosts".parse::<ObserverDisclosure>(), Err(()));
+        assert_eq!("".parse::<ObserverDisclosure>(), Err(()));
+    }
+}
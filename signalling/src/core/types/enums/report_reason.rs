@@ -0,0 +1 @@
+pub use waterbus_types::enums::ReportReason;
@@ -0,0 +1,39 @@
+use std::str::FromStr;
+
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+
+/// Where an abuse report is in the moderation queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportStatus {
+    /// Waiting for a moderator
+    Open,
+    /// A moderator acted on it
+    Resolved,
+    /// A moderator looked at it and took no action
+    Dismissed,
+}
+
+impl ReportStatus {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            ReportStatus::Open => "open",
+            ReportStatus::Resolved => "resolved",
+            ReportStatus::Dismissed => "dismissed",
+        }
+    }
+}
+
+impl FromStr for ReportStatus {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "open" => Ok(ReportStatus::Open),
+            "resolved" => Ok(ReportStatus::Resolved),
+            "dismissed" => Ok(ReportStatus::Dismissed),
+            _ => Err(()),
+        }
+    }
+}
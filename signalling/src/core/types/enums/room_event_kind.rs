@@ -0,0 +1,37 @@
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+
+/// What happened in a room session, as kept in its timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RoomEventKind {
+    ParticipantJoined,
+    ParticipantLeft,
+    Video,
+    Audio,
+    ScreenShare,
+    HandRaise,
+    /// `isEnabled` is false while the recording is paused
+    Recording,
+    LiveStarted,
+    /// The host switched the live stream back to a conference
+    LiveEnded,
+    Message,
+}
+
+impl RoomEventKind {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            RoomEventKind::ParticipantJoined => "participant_joined",
+            RoomEventKind::ParticipantLeft => "participant_left",
+            RoomEventKind::Video => "video",
+            RoomEventKind::Audio => "audio",
+            RoomEventKind::ScreenShare => "screen_share",
+            RoomEventKind::HandRaise => "hand_raise",
+            RoomEventKind::Recording => "recording",
+            RoomEventKind::LiveStarted => "live_started",
+            RoomEventKind::LiveEnded => "live_ended",
+            RoomEventKind::Message => "message",
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use super::problem::{
+    Problem, ProblemType, ROOM_NOT_FOUND, UNEXPECTED, register_problems, write_problem,
+};
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+use thiserror::Error;
+
+#[derive(Debug, Error, ToSchema, Serialize, Clone)]
+pub enum AdminError {
+    #[error("Dead letter with ID {0} not found")]
+    DeadLetterNotFound(String),
+    #[error("Invalid rollout: {0}")]
+    InvalidRollout(String),
+    #[error("Room with ID {0} not found")]
+    RoomNotFound(String),
+    #[error("SFU node {0} not found")]
+    NodeNotFound(String),
+    #[error("Invalid observer token request: {0}")]
+    InvalidObserverToken(String),
+    #[error("An unexpected error occurred: {0}")]
+    UnexpectedError(String),
+}
+
+const DEAD_LETTER_NOT_FOUND: ProblemType = ProblemType::new(
+    "dead-letter-not-found",
+    "Dead letter not found",
+    StatusCode::NOT_FOUND,
+);
+const NODE_NOT_FOUND: ProblemType = ProblemType::new(
+    "node-not-found",
+    "SFU node not found",
+    StatusCode::NOT_FOUND,
+);
+const INVALID_ROLLOUT: ProblemType = ProblemType::new(
+    "invalid-rollout",
+    "Invalid rollout",
+    StatusCode::BAD_REQUEST,
+);
+const INVALID_OBSERVER_TOKEN: ProblemType = ProblemType::new(
+    "invalid-observer-token",
+    "Invalid observer token request",
+    StatusCode::BAD_REQUEST,
+);
+
+impl Problem for AdminError {
+    fn problem_types() -> Vec<&'static ProblemType> {
+        vec![
+            &DEAD_LETTER_NOT_FOUND,
+            &ROOM_NOT_FOUND,
+            &NODE_NOT_FOUND,
+            &INVALID_ROLLOUT,
+            &INVALID_OBSERVER_TOKEN,
+            &UNEXPECTED,
+        ]
+    }
+
+    fn problem_type(&self) -> &'static ProblemType {
+        match self {
+            AdminError::DeadLetterNotFound(_) => &DEAD_LETTER_NOT_FOUND,
+            AdminError::RoomNotFound(_) => &ROOM_NOT_FOUND,
+            AdminError::NodeNotFound(_) => &NODE_NOT_FOUND,
+            AdminError::InvalidRollout(_) => &INVALID_ROLLOUT,
+            AdminError::InvalidObserverToken(_) => &INVALID_OBSERVER_TOKEN,
+            AdminError::UnexpectedError(_) => &UNEXPECTED,
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for AdminError {
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        write_problem(&self, req, res);
+    }
+}
+
+impl EndpointOutRegister for AdminError {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        register_problems::<Self>(components, operation);
+    }
+}
@@ -5,7 +5,10 @@ use serde::Serialize;
 use thiserror::Error;
 
 use super::general::GeneralError;
-use super::{BadRequestError, InternalError, NotFoundError};
+use super::problem::{
+    GENERAL_TYPES, INVALID_TOKEN, Problem, ProblemType, UNEXPECTED, USER_EXISTS, USER_NOT_FOUND,
+    register_problems, write_problem,
+};
 
 #[derive(Debug, Error, ToSchema, Serialize, Clone)]
 pub enum AuthError {
@@ -28,38 +31,46 @@ pub enum AuthError {
     General(#[from] GeneralError),
 }
 
+const INVALID_API_KEY: ProblemType = ProblemType::new(
+    "invalid-api-key",
+    "Invalid API key",
+    StatusCode::UNAUTHORIZED,
+);
+
+impl Problem for AuthError {
+    fn problem_types() -> Vec<&'static ProblemType> {
+        let mut types = vec![
+            &INVALID_API_KEY,
+            &INVALID_TOKEN,
+            &USER_EXISTS,
+            &USER_NOT_FOUND,
+            &UNEXPECTED,
+        ];
+        types.extend(GENERAL_TYPES);
+        types
+    }
+
+    fn problem_type(&self) -> &'static ProblemType {
+        match self {
+            AuthError::InvalidAPIKey => &INVALID_API_KEY,
+            AuthError::InvalidToken => &INVALID_TOKEN,
+            AuthError::UserExists(_) => &USER_EXISTS,
+            AuthError::UserNotFound(_) => &USER_NOT_FOUND,
+            AuthError::UnexpectedError(_) => &UNEXPECTED,
+            AuthError::General(error) => error.problem_type(),
+        }
+    }
+}
+
 #[async_trait]
 impl Writer for AuthError {
-    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
-        let status = match self {
-            AuthError::UserNotFound(_) => StatusCode::NOT_FOUND,
-            AuthError::UserExists(_) => StatusCode::BAD_REQUEST,
-            AuthError::InvalidAPIKey | AuthError::InvalidToken => StatusCode::UNAUTHORIZED,
-            AuthError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            AuthError::General(_) => StatusCode::INTERNAL_SERVER_ERROR,
-        };
-
-        res.status_code(status);
-        res.render(Json(serde_json::json!({ "message": self.to_string() })));
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        write_problem(&self, req, res);
     }
 }
 
 impl EndpointOutRegister for AuthError {
     fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
-        operation.responses.insert(
-            StatusCode::NOT_FOUND.as_str(),
-            oapi::Response::new("User not found")
-                .add_content("application/json", NotFoundError::to_schema(components)),
-        );
-        operation.responses.insert(
-            StatusCode::BAD_REQUEST.as_str(),
-            oapi::Response::new("User already exists or bad request")
-                .add_content("application/json", BadRequestError::to_schema(components)),
-        );
-        operation.responses.insert(
-            StatusCode::INTERNAL_SERVER_ERROR.as_str(),
-            oapi::Response::new("Unexpected or general error")
-                .add_content("application/json", InternalError::to_schema(components)),
-        );
+        register_problems::<Self>(components, operation);
     }
 }
@@ -0,0 +1,80 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+use thiserror::Error;
+
+use super::general::GeneralError;
+use super::problem::{
+    GENERAL_TYPES, INVALID_TOKEN, Problem, ProblemType, UNEXPECTED, register_problems,
+    write_problem,
+};
+
+#[derive(Debug, Error, ToSchema, Serialize, Clone)]
+pub enum BotError {
+    #[error("Bot with ID {0} not found")]
+    BotNotFound(i32),
+
+    #[error("Invalid bot token")]
+    InvalidToken,
+
+    #[error("Bot is missing the {0} scope")]
+    MissingScope(String),
+
+    #[error("Bot is not a member of room {0}")]
+    NotAMember(i32),
+
+    #[error("An unexpected error occurred: {0}")]
+    UnexpectedError(String),
+
+    #[error("General error: {0}")]
+    General(#[from] GeneralError),
+}
+
+const BOT_NOT_FOUND: ProblemType =
+    ProblemType::new("bot-not-found", "Bot not found", StatusCode::NOT_FOUND);
+const MISSING_SCOPE: ProblemType =
+    ProblemType::new("missing-scope", "Bot scope missing", StatusCode::FORBIDDEN);
+const BOT_NOT_A_MEMBER: ProblemType = ProblemType::new(
+    "bot-not-a-member",
+    "Bot is not a member of the room",
+    StatusCode::FORBIDDEN,
+);
+
+impl Problem for BotError {
+    fn problem_types() -> Vec<&'static ProblemType> {
+        let mut types = vec![
+            &BOT_NOT_FOUND,
+            &INVALID_TOKEN,
+            &MISSING_SCOPE,
+            &BOT_NOT_A_MEMBER,
+            &UNEXPECTED,
+        ];
+        types.extend(GENERAL_TYPES);
+        types
+    }
+
+    fn problem_type(&self) -> &'static ProblemType {
+        match self {
+            BotError::BotNotFound(_) => &BOT_NOT_FOUND,
+            BotError::InvalidToken => &INVALID_TOKEN,
+            BotError::MissingScope(_) => &MISSING_SCOPE,
+            BotError::NotAMember(_) => &BOT_NOT_A_MEMBER,
+            BotError::UnexpectedError(_) => &UNEXPECTED,
+            BotError::General(error) => error.problem_type(),
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for BotError {
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        write_problem(&self, req, res);
+    }
+}
+
+impl EndpointOutRegister for BotError {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        register_problems::<Self>(components, operation);
+    }
+}
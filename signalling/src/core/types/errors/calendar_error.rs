@@ -0,0 +1,100 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+use thiserror::Error;
+
+use super::general::GeneralError;
+use super::problem::{
+    GENERAL_TYPES, INVALID_TOKEN, Problem, ProblemType, UNEXPECTED, register_problems,
+    write_problem,
+};
+
+#[derive(Debug, Error, ToSchema, Serialize, Clone)]
+pub enum CalendarError {
+    #[error("Calendar sync is not configured")]
+    Disabled,
+
+    #[error("{0} calendars are not supported")]
+    UnknownProvider(String),
+
+    #[error("No {0} calendar is connected")]
+    AccountNotFound(String),
+
+    #[error("The authorization request is invalid or has expired")]
+    InvalidState,
+
+    #[error("The notification token does not match its channel")]
+    InvalidChannelToken,
+
+    #[error("Calendar provider request failed: {0}")]
+    ProviderError(String),
+
+    #[error("An unexpected error occurred: {0}")]
+    UnexpectedError(String),
+
+    #[error("General error: {0}")]
+    General(#[from] GeneralError),
+}
+
+const CALENDAR_DISABLED: ProblemType = ProblemType::new(
+    "calendar-disabled",
+    "Calendar sync is not configured",
+    StatusCode::SERVICE_UNAVAILABLE,
+);
+const UNKNOWN_CALENDAR_PROVIDER: ProblemType = ProblemType::new(
+    "unknown-calendar-provider",
+    "Calendar provider is not supported",
+    StatusCode::NOT_FOUND,
+);
+const CALENDAR_NOT_CONNECTED: ProblemType = ProblemType::new(
+    "calendar-not-connected",
+    "Calendar is not connected",
+    StatusCode::NOT_FOUND,
+);
+const CALENDAR_PROVIDER_ERROR: ProblemType = ProblemType::new(
+    "calendar-provider-error",
+    "Calendar provider request failed",
+    StatusCode::BAD_GATEWAY,
+);
+
+impl Problem for CalendarError {
+    fn problem_types() -> Vec<&'static ProblemType> {
+        let mut types = vec![
+            &CALENDAR_DISABLED,
+            &UNKNOWN_CALENDAR_PROVIDER,
+            &CALENDAR_NOT_CONNECTED,
+            &INVALID_TOKEN,
+            &CALENDAR_PROVIDER_ERROR,
+            &UNEXPECTED,
+        ];
+        types.extend(GENERAL_TYPES);
+        types
+    }
+
+    fn problem_type(&self) -> &'static ProblemType {
+        match self {
+            CalendarError::Disabled => &CALENDAR_DISABLED,
+            CalendarError::UnknownProvider(_) => &UNKNOWN_CALENDAR_PROVIDER,
+            CalendarError::AccountNotFound(_) => &CALENDAR_NOT_CONNECTED,
+            CalendarError::InvalidState => &INVALID_TOKEN,
+            CalendarError::InvalidChannelToken => &INVALID_TOKEN,
+            CalendarError::ProviderError(_) => &CALENDAR_PROVIDER_ERROR,
+            CalendarError::UnexpectedError(_) => &UNEXPECTED,
+            CalendarError::General(error) => error.problem_type(),
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for CalendarError {
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        write_problem(&self, req, res);
+    }
+}
+
+impl EndpointOutRegister for CalendarError {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        register_problems::<Self>(components, operation);
+    }
+}
@@ -0,0 +1,126 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+use thiserror::Error;
+
+use super::general::GeneralError;
+use super::problem::{
+    GENERAL_TYPES, Problem, ProblemType, ROOM_NOT_FOUND, UNEXPECTED, register_problems,
+    write_problem,
+};
+
+#[derive(Debug, Error, ToSchema, Serialize, Clone)]
+pub enum CalloutError {
+    #[error("Phone callouts are not configured")]
+    Disabled,
+
+    #[error("Room with ID {0} not found")]
+    RoomNotFound(String),
+
+    #[error("{0} is not an E.164 phone number")]
+    InvalidPhoneNumber(String),
+
+    #[error("You don't have permissions to call out from this room")]
+    YouDontHavePermissions,
+
+    #[error("Daily callout spending cap reached")]
+    SpendingCapReached,
+
+    #[error("Daily callout spending cap of the room reached")]
+    RoomSpendingCapReached,
+
+    #[error("Too many callouts, try again later")]
+    RateLimited,
+
+    #[error("Phone provider request failed: {0}")]
+    ProviderError(String),
+
+    #[error("An unexpected error occurred: {0}")]
+    UnexpectedError(String),
+
+    #[error("General error: {0}")]
+    General(#[from] GeneralError),
+}
+
+const CALLOUTS_DISABLED: ProblemType = ProblemType::new(
+    "callouts-disabled",
+    "Phone callouts are not configured",
+    StatusCode::SERVICE_UNAVAILABLE,
+);
+const INVALID_PHONE_NUMBER: ProblemType = ProblemType::new(
+    "invalid-phone-number",
+    "Invalid phone number",
+    StatusCode::BAD_REQUEST,
+);
+const CALLOUT_FORBIDDEN: ProblemType = ProblemType::new(
+    "callout-forbidden",
+    "Not allowed to call out from the room",
+    StatusCode::FORBIDDEN,
+);
+const SPENDING_CAP_REACHED: ProblemType = ProblemType::new(
+    "spending-cap-reached",
+    "Daily callout spending cap reached",
+    StatusCode::TOO_MANY_REQUESTS,
+);
+const ROOM_SPENDING_CAP_REACHED: ProblemType = ProblemType::new(
+    "room-spending-cap-reached",
+    "Daily callout spending cap of the room reached",
+    StatusCode::TOO_MANY_REQUESTS,
+);
+const CALLOUT_RATE_LIMITED: ProblemType = ProblemType::new(
+    "callout-rate-limited",
+    "Too many callouts",
+    StatusCode::TOO_MANY_REQUESTS,
+);
+const PHONE_PROVIDER_ERROR: ProblemType = ProblemType::new(
+    "phone-provider-error",
+    "Phone provider request failed",
+    StatusCode::BAD_GATEWAY,
+);
+
+impl Problem for CalloutError {
+    fn problem_types() -> Vec<&'static ProblemType> {
+        let mut types = vec![
+            &CALLOUTS_DISABLED,
+            &ROOM_NOT_FOUND,
+            &INVALID_PHONE_NUMBER,
+            &CALLOUT_FORBIDDEN,
+            &SPENDING_CAP_REACHED,
+            &ROOM_SPENDING_CAP_REACHED,
+            &CALLOUT_RATE_LIMITED,
+            &PHONE_PROVIDER_ERROR,
+            &UNEXPECTED,
+        ];
+        types.extend(GENERAL_TYPES);
+        types
+    }
+
+    fn problem_type(&self) -> &'static ProblemType {
+        match self {
+            CalloutError::Disabled => &CALLOUTS_DISABLED,
+            CalloutError::RoomNotFound(_) => &ROOM_NOT_FOUND,
+            CalloutError::InvalidPhoneNumber(_) => &INVALID_PHONE_NUMBER,
+            CalloutError::YouDontHavePermissions => &CALLOUT_FORBIDDEN,
+            CalloutError::SpendingCapReached => &SPENDING_CAP_REACHED,
+            CalloutError::RoomSpendingCapReached => &ROOM_SPENDING_CAP_REACHED,
+            CalloutError::RateLimited => &CALLOUT_RATE_LIMITED,
+            CalloutError::ProviderError(_) => &PHONE_PROVIDER_ERROR,
+            CalloutError::UnexpectedError(_) => &UNEXPECTED,
+            CalloutError::General(error) => error.problem_type(),
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for CalloutError {
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        write_problem(&self, req, res);
+    }
+}
+
+impl EndpointOutRegister for CalloutError {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        register_problems::<Self>(components, operation);
+    }
+}
@@ -5,14 +5,19 @@ use serde::Serialize;
 use thiserror::Error;
 
 use super::general::GeneralError;
-
-use super::{BadRequestError, InternalError, NotFoundError};
+use super::problem::{
+    GENERAL_TYPES, MEMBER_NOT_FOUND, Problem, ProblemType, ROOM_NOT_FOUND, UNEXPECTED,
+    register_problems, write_problem,
+};
 
 #[derive(Debug, Error, ToSchema, Serialize, Clone)]
 pub enum ChatError {
     #[error("Message with ID {0} not found")]
     MessageNotFound(i32),
 
+    #[error("Message with ID {0} has been deleted")]
+    MessageDeleted(i32),
+
     #[error("Member with ID {0} not found")]
     MemberNotFound(i32),
 
@@ -22,6 +27,9 @@ pub enum ChatError {
     #[error("Conversation with ID {0} not found")]
     ConversationNotFound(i32),
 
+    #[error("Room with ID {0} not found")]
+    RoomNotFound(String),
+
     #[error("An unexpected error occurred in channel: {0}")]
     UnexpectedError(String),
 
@@ -29,39 +37,65 @@ pub enum ChatError {
     General(#[from] GeneralError),
 }
 
+const MESSAGE_NOT_FOUND: ProblemType = ProblemType::new(
+    "message-not-found",
+    "Message not found",
+    StatusCode::NOT_FOUND,
+);
+const MESSAGE_DELETED: ProblemType = ProblemType::new(
+    "message-deleted",
+    "Message has been deleted",
+    StatusCode::GONE,
+);
+const CONVERSATION_NOT_FOUND: ProblemType = ProblemType::new(
+    "conversation-not-found",
+    "Conversation not found",
+    StatusCode::NOT_FOUND,
+);
+const CHAT_FORBIDDEN: ProblemType = ProblemType::new(
+    "chat-forbidden",
+    "Not allowed in this chat",
+    StatusCode::FORBIDDEN,
+);
+
+impl Problem for ChatError {
+    fn problem_types() -> Vec<&'static ProblemType> {
+        let mut types = vec![
+            &MESSAGE_NOT_FOUND,
+            &MESSAGE_DELETED,
+            &MEMBER_NOT_FOUND,
+            &CONVERSATION_NOT_FOUND,
+            &ROOM_NOT_FOUND,
+            &CHAT_FORBIDDEN,
+            &UNEXPECTED,
+        ];
+        types.extend(GENERAL_TYPES);
+        types
+    }
+
+    fn problem_type(&self) -> &'static ProblemType {
+        match self {
+            ChatError::MessageNotFound(_) => &MESSAGE_NOT_FOUND,
+            ChatError::MessageDeleted(_) => &MESSAGE_DELETED,
+            ChatError::MemberNotFound(_) => &MEMBER_NOT_FOUND,
+            ChatError::ConversationNotFound(_) => &CONVERSATION_NOT_FOUND,
+            ChatError::RoomNotFound(_) => &ROOM_NOT_FOUND,
+            ChatError::Forbidden(_) => &CHAT_FORBIDDEN,
+            ChatError::UnexpectedError(_) => &UNEXPECTED,
+            ChatError::General(error) => error.problem_type(),
+        }
+    }
+}
+
 #[async_trait]
 impl Writer for ChatError {
-    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
-        let status = match self {
-            ChatError::MemberNotFound(_)
-            | ChatError::ConversationNotFound(_)
-            | ChatError::MessageNotFound(_) => StatusCode::NOT_FOUND,
-            ChatError::Forbidden(_) => StatusCode::FORBIDDEN,
-            ChatError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            ChatError::General(_) => StatusCode::INTERNAL_SERVER_ERROR,
-        };
-
-        res.status_code(status);
-        res.render(Json(serde_json::json!({ "message": self.to_string() })));
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        write_problem(&self, req, res);
     }
 }
 
 impl EndpointOutRegister for ChatError {
     fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
-        operation.responses.insert(
-            StatusCode::NOT_FOUND.as_str(),
-            oapi::Response::new("Conversation not found")
-                .add_content("application/json", NotFoundError::to_schema(components)),
-        );
-        operation.responses.insert(
-            StatusCode::FORBIDDEN.as_str(),
-            oapi::Response::new("Forbiden:")
-                .add_content("application/json", BadRequestError::to_schema(components)),
-        );
-        operation.responses.insert(
-            StatusCode::INTERNAL_SERVER_ERROR.as_str(),
-            oapi::Response::new("Unexpected or general error")
-                .add_content("application/json", InternalError::to_schema(components)),
-        );
+        register_problems::<Self>(components, operation);
     }
 }
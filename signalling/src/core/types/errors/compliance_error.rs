@@ -0,0 +1,51 @@
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+use thiserror::Error;
+
+use super::general::GeneralError;
+use super::problem::{
+    GENERAL_TYPES, Problem, ProblemType, ROOM_NOT_FOUND, UNEXPECTED, register_problems,
+    write_problem,
+};
+
+#[derive(Debug, Error, ToSchema, Serialize, Clone)]
+pub enum ComplianceError {
+    #[error("Room with ID {0} not found")]
+    RoomNotFound(String),
+
+    #[error("An unexpected error occurred: {0}")]
+    UnexpectedError(String),
+
+    #[error("General error: {0}")]
+    General(#[from] GeneralError),
+}
+
+impl Problem for ComplianceError {
+    fn problem_types() -> Vec<&'static ProblemType> {
+        let mut types = vec![&ROOM_NOT_FOUND, &UNEXPECTED];
+        types.extend(GENERAL_TYPES);
+        types
+    }
+
+    fn problem_type(&self) -> &'static ProblemType {
+        match self {
+            ComplianceError::RoomNotFound(_) => &ROOM_NOT_FOUND,
+            ComplianceError::UnexpectedError(_) => &UNEXPECTED,
+            ComplianceError::General(error) => error.problem_type(),
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for ComplianceError {
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        write_problem(&self, req, res);
+    }
+}
+
+impl EndpointOutRegister for ComplianceError {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        register_problems::<Self>(components, operation);
+    }
+}
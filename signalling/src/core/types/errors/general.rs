@@ -6,4 +6,6 @@ use thiserror::Error;
 pub enum GeneralError {
     #[error("Database connection failed")]
     DbConnectionError,
+    #[error("Database connection pool exhausted")]
+    DbPoolExhausted,
 }
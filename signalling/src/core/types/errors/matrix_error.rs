@@ -0,0 +1,106 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+use thiserror::Error;
+
+use super::general::GeneralError;
+use super::problem::{
+    GENERAL_TYPES, Problem, ProblemType, ROOM_NOT_FOUND, UNEXPECTED, register_problems,
+    write_problem,
+};
+
+#[derive(Debug, Error, ToSchema, Serialize, Clone)]
+pub enum MatrixError {
+    #[error("Room with ID {0} not found")]
+    RoomNotFound(String),
+
+    #[error("Room with ID {0} is not bridged to Matrix")]
+    RoomNotLinked(i32),
+
+    #[error("Room with ID {0} is already bridged to Matrix")]
+    RoomAlreadyLinked(i32),
+
+    #[error("You don't have permissions to bridge this room")]
+    YouDontHavePermissions,
+
+    #[error("Invalid homeserver token")]
+    InvalidToken,
+
+    #[error("Matrix homeserver request failed: {0}")]
+    HomeserverError(String),
+
+    #[error("An unexpected error occurred: {0}")]
+    UnexpectedError(String),
+
+    #[error("General error: {0}")]
+    General(#[from] GeneralError),
+}
+
+const ROOM_NOT_BRIDGED: ProblemType = ProblemType::new(
+    "room-not-bridged",
+    "Room is not bridged to Matrix",
+    StatusCode::NOT_FOUND,
+);
+const ROOM_ALREADY_BRIDGED: ProblemType = ProblemType::new(
+    "room-already-bridged",
+    "Room is already bridged to Matrix",
+    StatusCode::CONFLICT,
+);
+const BRIDGE_FORBIDDEN: ProblemType = ProblemType::new(
+    "bridge-forbidden",
+    "Not allowed to bridge the room",
+    StatusCode::FORBIDDEN,
+);
+const INVALID_HOMESERVER_TOKEN: ProblemType = ProblemType::new(
+    "invalid-homeserver-token",
+    "Invalid homeserver token",
+    StatusCode::FORBIDDEN,
+);
+const HOMESERVER_ERROR: ProblemType = ProblemType::new(
+    "homeserver-error",
+    "Matrix homeserver request failed",
+    StatusCode::BAD_GATEWAY,
+);
+
+impl Problem for MatrixError {
+    fn problem_types() -> Vec<&'static ProblemType> {
+        let mut types = vec![
+            &ROOM_NOT_FOUND,
+            &ROOM_NOT_BRIDGED,
+            &ROOM_ALREADY_BRIDGED,
+            &BRIDGE_FORBIDDEN,
+            &INVALID_HOMESERVER_TOKEN,
+            &HOMESERVER_ERROR,
+            &UNEXPECTED,
+        ];
+        types.extend(GENERAL_TYPES);
+        types
+    }
+
+    fn problem_type(&self) -> &'static ProblemType {
+        match self {
+            MatrixError::RoomNotFound(_) => &ROOM_NOT_FOUND,
+            MatrixError::RoomNotLinked(_) => &ROOM_NOT_BRIDGED,
+            MatrixError::RoomAlreadyLinked(_) => &ROOM_ALREADY_BRIDGED,
+            MatrixError::YouDontHavePermissions => &BRIDGE_FORBIDDEN,
+            MatrixError::InvalidToken => &INVALID_HOMESERVER_TOKEN,
+            MatrixError::HomeserverError(_) => &HOMESERVER_ERROR,
+            MatrixError::UnexpectedError(_) => &UNEXPECTED,
+            MatrixError::General(error) => error.problem_type(),
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for MatrixError {
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        write_problem(&self, req, res);
+    }
+}
+
+impl EndpointOutRegister for MatrixError {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        register_problems::<Self>(components, operation);
+    }
+}
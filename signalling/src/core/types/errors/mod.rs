@@ -1,27 +1,18 @@
-use salvo::oapi::ToSchema;
-use serde::Serialize;
-
+pub mod admin_error;
 pub mod auth_error;
+pub mod bot_error;
+pub mod calendar_error;
+pub mod callout_error;
 pub mod ccu_error;
 pub mod chat_error;
+pub mod compliance_error;
 pub mod general;
+pub mod matrix_error;
+pub mod moderation_error;
+pub mod org_error;
+pub mod problem;
 pub mod room_error;
+pub mod schedule_error;
+pub mod search_error;
+pub mod timeline_error;
 pub mod user_error;
-
-#[derive(Debug, ToSchema, Serialize)]
-#[salvo(schema(example = json!({"message": ""})))]
-struct NotFoundError {
-    message: String,
-}
-
-#[derive(Debug, ToSchema, Serialize)]
-#[salvo(schema(example = json!({"message": ""})))]
-struct BadRequestError {
-    message: String,
-}
-
-#[derive(Debug, ToSchema, Serialize)]
-#[salvo(schema(example = json!({"message": ""})))]
-struct InternalError {
-    message: String,
-}
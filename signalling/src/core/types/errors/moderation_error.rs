@@ -0,0 +1,110 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+use thiserror::Error;
+
+use super::general::GeneralError;
+use super::problem::{
+    GENERAL_TYPES, Problem, ProblemType, ROOM_NOT_FOUND, UNEXPECTED, register_problems,
+    write_problem,
+};
+
+#[derive(Debug, Error, ToSchema, Serialize, Clone)]
+pub enum ModerationError {
+    #[error("Room with ID {0} not found")]
+    RoomNotFound(String),
+
+    #[error("Participant {0} is not in this room")]
+    TargetNotFound(String),
+
+    #[error("Report with ID {0} not found")]
+    ReportNotFound(i32),
+
+    #[error("You can't report yourself")]
+    CannotReportSelf,
+
+    #[error("Invalid report: {0}")]
+    InvalidReport(String),
+
+    #[error("Report {0} was already closed")]
+    ReportAlreadyClosed(i32),
+
+    #[error("You are not in this room")]
+    NotInRoom,
+
+    #[error("An unexpected error occurred: {0}")]
+    UnexpectedError(String),
+
+    #[error("General error: {0}")]
+    General(#[from] GeneralError),
+}
+
+const REPORT_TARGET_NOT_FOUND: ProblemType = ProblemType::new(
+    "report-target-not-found",
+    "Reported participant is not in the room",
+    StatusCode::NOT_FOUND,
+);
+const REPORT_NOT_FOUND: ProblemType = ProblemType::new(
+    "report-not-found",
+    "Report not found",
+    StatusCode::NOT_FOUND,
+);
+const CANNOT_REPORT_SELF: ProblemType = ProblemType::new(
+    "cannot-report-self",
+    "Cannot report yourself",
+    StatusCode::BAD_REQUEST,
+);
+const INVALID_REPORT: ProblemType =
+    ProblemType::new("invalid-report", "Invalid report", StatusCode::BAD_REQUEST);
+const REPORT_ALREADY_CLOSED: ProblemType = ProblemType::new(
+    "report-already-closed",
+    "Report already closed",
+    StatusCode::CONFLICT,
+);
+const NOT_IN_ROOM: ProblemType =
+    ProblemType::new("not-in-room", "Not in the room", StatusCode::FORBIDDEN);
+
+impl Problem for ModerationError {
+    fn problem_types() -> Vec<&'static ProblemType> {
+        let mut types = vec![
+            &ROOM_NOT_FOUND,
+            &REPORT_TARGET_NOT_FOUND,
+            &REPORT_NOT_FOUND,
+            &CANNOT_REPORT_SELF,
+            &INVALID_REPORT,
+            &REPORT_ALREADY_CLOSED,
+            &NOT_IN_ROOM,
+            &UNEXPECTED,
+        ];
+        types.extend(GENERAL_TYPES);
+        types
+    }
+
+    fn problem_type(&self) -> &'static ProblemType {
+        match self {
+            ModerationError::RoomNotFound(_) => &ROOM_NOT_FOUND,
+            ModerationError::TargetNotFound(_) => &REPORT_TARGET_NOT_FOUND,
+            ModerationError::ReportNotFound(_) => &REPORT_NOT_FOUND,
+            ModerationError::CannotReportSelf => &CANNOT_REPORT_SELF,
+            ModerationError::InvalidReport(_) => &INVALID_REPORT,
+            ModerationError::ReportAlreadyClosed(_) => &REPORT_ALREADY_CLOSED,
+            ModerationError::NotInRoom => &NOT_IN_ROOM,
+            ModerationError::UnexpectedError(_) => &UNEXPECTED,
+            ModerationError::General(error) => error.problem_type(),
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for ModerationError {
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        write_problem(&self, req, res);
+    }
+}
+
+impl EndpointOutRegister for ModerationError {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        register_problems::<Self>(components, operation);
+    }
+}
@@ -0,0 +1,86 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+use thiserror::Error;
+
+use super::general::GeneralError;
+use super::problem::{
+    GENERAL_TYPES, Problem, ProblemType, ROOM_NOT_FOUND, UNEXPECTED, register_problems,
+    write_problem,
+};
+
+#[derive(Debug, Error, ToSchema, Serialize, Clone)]
+pub enum OrgError {
+    #[error("Organization with ID {0} not found")]
+    OrgNotFound(String),
+
+    #[error("Room with ID {0} not found")]
+    RoomNotFound(String),
+
+    #[error("Invalid branding: {0}")]
+    InvalidBranding(String),
+
+    #[error("Invalid client features: {0}")]
+    InvalidClientFeatures(String),
+
+    #[error("An unexpected error occurred: {0}")]
+    UnexpectedError(String),
+
+    #[error("General error: {0}")]
+    General(#[from] GeneralError),
+}
+
+const ORG_NOT_FOUND: ProblemType = ProblemType::new(
+    "org-not-found",
+    "Organization not found",
+    StatusCode::NOT_FOUND,
+);
+const INVALID_BRANDING: ProblemType = ProblemType::new(
+    "invalid-branding",
+    "Invalid branding",
+    StatusCode::BAD_REQUEST,
+);
+const INVALID_CLIENT_FEATURES: ProblemType = ProblemType::new(
+    "invalid-client-features",
+    "Invalid client features",
+    StatusCode::BAD_REQUEST,
+);
+
+impl Problem for OrgError {
+    fn problem_types() -> Vec<&'static ProblemType> {
+        let mut types = vec![
+            &ORG_NOT_FOUND,
+            &ROOM_NOT_FOUND,
+            &INVALID_BRANDING,
+            &INVALID_CLIENT_FEATURES,
+            &UNEXPECTED,
+        ];
+        types.extend(GENERAL_TYPES);
+        types
+    }
+
+    fn problem_type(&self) -> &'static ProblemType {
+        match self {
+            OrgError::OrgNotFound(_) => &ORG_NOT_FOUND,
+            OrgError::RoomNotFound(_) => &ROOM_NOT_FOUND,
+            OrgError::InvalidBranding(_) => &INVALID_BRANDING,
+            OrgError::InvalidClientFeatures(_) => &INVALID_CLIENT_FEATURES,
+            OrgError::UnexpectedError(_) => &UNEXPECTED,
+            OrgError::General(error) => error.problem_type(),
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for OrgError {
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        write_problem(&self, req, res);
+    }
+}
+
+impl EndpointOutRegister for OrgError {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        register_problems::<Self>(components, operation);
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use nanoid::nanoid;
+use salvo::http::StatusCode;
+use salvo::oapi::{self, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+use tracing::warn;
+
+use super::general::GeneralError;
+
+/// Problem types name the problem rather than link to a page, clients match
+/// on them so they never change once released
+const PROBLEM_TYPE_PREFIX: &str = "urn:waterbus:problem:";
+
+const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// One kind of error the REST API answers with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProblemType {
+    pub slug: &'static str,
+    pub title: &'static str,
+    pub status: StatusCode,
+}
+
+impl ProblemType {
+    pub const fn new(slug: &'static str, title: &'static str, status: StatusCode) -> Self {
+        Self {
+            slug,
+            title,
+            status,
+        }
+    }
+
+    pub fn uri(&self) -> String {
+        format!("{PROBLEM_TYPE_PREFIX}{}", self.slug)
+    }
+}
+
+pub const UNEXPECTED: ProblemType = ProblemType::new(
+    "unexpected",
+    "Unexpected error",
+    StatusCode::INTERNAL_SERVER_ERROR,
+);
+pub const DATABASE_UNAVAILABLE: ProblemType = ProblemType::new(
+    "database-unavailable",
+    "Database unavailable",
+    StatusCode::SERVICE_UNAVAILABLE,
+);
+pub const DATABASE_BUSY: ProblemType = ProblemType::new(
+    "database-busy",
+    "Database connection pool exhausted",
+    StatusCode::SERVICE_UNAVAILABLE,
+);
+pub const ROOM_NOT_FOUND: ProblemType =
+    ProblemType::new("room-not-found", "Room not found", StatusCode::NOT_FOUND);
+pub const USER_NOT_FOUND: ProblemType =
+    ProblemType::new("user-not-found", "User not found", StatusCode::NOT_FOUND);
+pub const MEMBER_NOT_FOUND: ProblemType = ProblemType::new(
+    "member-not-found",
+    "Member not found",
+    StatusCode::NOT_FOUND,
+);
+pub const USER_EXISTS: ProblemType =
+    ProblemType::new("user-exists", "User already exists", StatusCode::CONFLICT);
+pub const INVALID_TOKEN: ProblemType =
+    ProblemType::new("invalid-token", "Invalid token", StatusCode::UNAUTHORIZED);
+
+/// What every error wrapping a `GeneralError` may also answer with
+pub const GENERAL_TYPES: [&ProblemType; 2] = [&DATABASE_UNAVAILABLE, &DATABASE_BUSY];
+
+impl GeneralError {
+    pub fn problem_type(&self) -> &'static ProblemType {
+        match self {
+            GeneralError::DbConnectionError => &DATABASE_UNAVAILABLE,
+            GeneralError::DbPoolExhausted => &DATABASE_BUSY,
+        }
+    }
+}
+
+/// An error rendered as RFC 7807 problem details
+pub trait Problem: Display {
+    /// Every type the error may answer with, listed in the OpenAPI document
+    fn problem_types() -> Vec<&'static ProblemType>;
+
+    fn problem_type(&self) -> &'static ProblemType;
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[salvo(schema(example = json!({
+    "type": "urn:waterbus:problem:room-not-found",
+    "title": "Room not found",
+    "status": 404,
+    "detail": "Room with ID 01JCQ8Z4V9M6T2W5X7Y3B1N0KD not found",
+    "instance": "/busapi/v3/rooms/01JCQ8Z4V9M6T2W5X7Y3B1N0KD",
+    "traceId": "4bf92f3577b34da6a3ce929d0e0e4736",
+    "message": "Room with ID 01JCQ8Z4V9M6T2W5X7Y3B1N0KD not found"
+})))]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    pub instance: String,
+    /// Quote it when reporting the error, it's on the server's log line too
+    pub trace_id: String,
+    /// Same as `detail`, for clients from before problem details
+    pub message: String,
+}
+
+/// Renders the error as problem details. Server errors are logged with the
+/// trace id the client gets.
+pub fn write_problem<E: Problem>(error: &E, req: &Request, res: &mut Response) {
+    let problem_type = error.problem_type();
+    let trace_id = trace_id(req);
+    let detail = error.to_string();
+
+    if problem_type.status.is_server_error() {
+        warn!(
+            trace_id = %trace_id,
+            "{} {} failed: {}",
+            req.method(),
+            req.uri().path(),
+            detail
+        );
+    }
+
+    let problem = ProblemDetails {
+        type_: problem_type.uri(),
+        title: problem_type.title.to_string(),
+        status: problem_type.status.as_u16(),
+        detail: detail.clone(),
+        instance: req.uri().path().to_string(),
+        trace_id,
+        message: detail,
+    };
+
+    res.status_code(problem_type.status);
+    let _ = res.add_header("Content-Type", PROBLEM_CONTENT_TYPE, true);
+    let _ = res.add_header("X-Request-Id", &problem.trace_id, true);
+    let _ = res.write_body(serde_json::to_vec(&problem).unwrap_or_default());
+}
+
+/// Documents the error's problem types, one response per status
+pub fn register_problems<E: Problem>(
+    components: &mut oapi::Components,
+    operation: &mut oapi::Operation,
+) {
+    let mut by_status: BTreeMap<u16, Vec<&ProblemType>> = BTreeMap::new();
+    for problem_type in E::problem_types() {
+        by_status
+            .entry(problem_type.status.as_u16())
+            .or_default()
+            .push(problem_type);
+    }
+
+    for (status, problem_types) in by_status {
+        let description = problem_types
+            .iter()
+            .map(|problem_type| format!("{} (`{}`)", problem_type.title, problem_type.uri()))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        operation.responses.insert(
+            status.to_string(),
+            oapi::Response::new(description)
+                .add_content(PROBLEM_CONTENT_TYPE, ProblemDetails::to_schema(components)),
+        );
+    }
+}
+
+/// The caller's own trace when it sent one, W3C `traceparent` first, so the
+/// error can be found from their side as well
+fn trace_id(req: &Request) -> String {
+    req.header::<String>("traceparent")
+        .and_then(|traceparent| traceparent.split('-').nth(1).map(str::to_owned))
+        .filter(|trace_id| trace_id.len() == 32)
+        .or_else(|| req.header::<String>("x-request-id"))
+        .unwrap_or_else(|| nanoid!())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_problem_type_uri() {
+        assert_eq!(ROOM_NOT_FOUND.uri(), "urn:waterbus:problem:room-not-found");
+        assert_eq!(GeneralError::DbPoolExhausted.problem_type(), &DATABASE_BUSY);
+    }
+}
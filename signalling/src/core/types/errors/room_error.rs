@@ -1,5 +1,8 @@
 use super::general::GeneralError;
-use super::{BadRequestError, InternalError, NotFoundError};
+use super::problem::{
+    GENERAL_TYPES, MEMBER_NOT_FOUND, Problem, ProblemType, ROOM_NOT_FOUND, UNEXPECTED,
+    USER_NOT_FOUND, register_problems, write_problem,
+};
 use salvo::http::StatusCode;
 use salvo::oapi::{self, EndpointOutRegister, ToSchema};
 use salvo::prelude::*;
@@ -12,64 +15,127 @@ pub enum RoomError {
     RoomNotFound(i32),
     #[error("Room with Code {0} not found")]
     RoomCodeNotFound(String),
+    #[error("Room with ID {0} not found")]
+    PublicIdNotFound(String),
+    #[error("User with ID {0} not found")]
+    UserNotFound(String),
+    #[error("Member not found")]
+    MemberNotFound,
+    #[error("Participant with ID {0} not found")]
+    ParticipantNotFound(String),
     #[error("Room with ID {0} is already exists")]
     RoomExists(i32),
+    #[error("User with ID {0} is already a member")]
+    AlreadyMember(i32),
     #[error("Owner can not leave the room")]
     OwnerCannotLeaveRoom,
     #[error("Only the host has permission")]
     YouDontHavePermissions,
     #[error("Password is not correct")]
     PasswordIncorrect,
+    #[error("Room is full")]
+    RoomFull,
+    #[error("Room type can't be switched")]
+    RoomTypeFixed,
+    #[error("Skip and limit can't be negative")]
+    InvalidPagination,
     #[error("An unexpected error occurred in channel: {0}")]
     UnexpectedError(String),
     #[error("General error: {0}")]
     General(#[from] GeneralError),
 }
 
+const PARTICIPANT_NOT_FOUND: ProblemType = ProblemType::new(
+    "participant-not-found",
+    "Participant not found",
+    StatusCode::NOT_FOUND,
+);
+const ROOM_EXISTS: ProblemType =
+    ProblemType::new("room-exists", "Room already exists", StatusCode::CONFLICT);
+const ALREADY_MEMBER: ProblemType = ProblemType::new(
+    "already-member",
+    "Already a member of the room",
+    StatusCode::CONFLICT,
+);
+const OWNER_CANNOT_LEAVE: ProblemType = ProblemType::new(
+    "owner-cannot-leave",
+    "Owner can not leave the room",
+    StatusCode::CONFLICT,
+);
+const NOT_ROOM_HOST: ProblemType = ProblemType::new(
+    "not-room-host",
+    "Only the host has permission",
+    StatusCode::FORBIDDEN,
+);
+const PASSWORD_INCORRECT: ProblemType = ProblemType::new(
+    "password-incorrect",
+    "Password is not correct",
+    StatusCode::UNAUTHORIZED,
+);
+const ROOM_FULL: ProblemType = ProblemType::new("room-full", "Room is full", StatusCode::CONFLICT);
+const ROOM_TYPE_FIXED: ProblemType = ProblemType::new(
+    "room-type-fixed",
+    "Room type can't be switched",
+    StatusCode::CONFLICT,
+);
+const INVALID_PAGINATION: ProblemType = ProblemType::new(
+    "invalid-pagination",
+    "Skip and limit can't be negative",
+    StatusCode::BAD_REQUEST,
+);
+
+impl Problem for RoomError {
+    fn problem_types() -> Vec<&'static ProblemType> {
+        let mut types = vec![
+            &ROOM_NOT_FOUND,
+            &USER_NOT_FOUND,
+            &MEMBER_NOT_FOUND,
+            &PARTICIPANT_NOT_FOUND,
+            &ROOM_EXISTS,
+            &ALREADY_MEMBER,
+            &OWNER_CANNOT_LEAVE,
+            &NOT_ROOM_HOST,
+            &PASSWORD_INCORRECT,
+            &ROOM_FULL,
+            &ROOM_TYPE_FIXED,
+            &INVALID_PAGINATION,
+            &UNEXPECTED,
+        ];
+        types.extend(GENERAL_TYPES);
+        types
+    }
+
+    fn problem_type(&self) -> &'static ProblemType {
+        match self {
+            RoomError::RoomNotFound(_)
+            | RoomError::RoomCodeNotFound(_)
+            | RoomError::PublicIdNotFound(_) => &ROOM_NOT_FOUND,
+            RoomError::UserNotFound(_) => &USER_NOT_FOUND,
+            RoomError::MemberNotFound => &MEMBER_NOT_FOUND,
+            RoomError::ParticipantNotFound(_) => &PARTICIPANT_NOT_FOUND,
+            RoomError::RoomExists(_) => &ROOM_EXISTS,
+            RoomError::AlreadyMember(_) => &ALREADY_MEMBER,
+            RoomError::OwnerCannotLeaveRoom => &OWNER_CANNOT_LEAVE,
+            RoomError::YouDontHavePermissions => &NOT_ROOM_HOST,
+            RoomError::PasswordIncorrect => &PASSWORD_INCORRECT,
+            RoomError::RoomFull => &ROOM_FULL,
+            RoomError::RoomTypeFixed => &ROOM_TYPE_FIXED,
+            RoomError::InvalidPagination => &INVALID_PAGINATION,
+            RoomError::UnexpectedError(_) => &UNEXPECTED,
+            RoomError::General(error) => error.problem_type(),
+        }
+    }
+}
+
 #[async_trait]
 impl Writer for RoomError {
-    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
-        let status = match self {
-            RoomError::RoomNotFound(_) | RoomError::RoomCodeNotFound(_) => StatusCode::NOT_FOUND,
-            RoomError::RoomExists(_) => StatusCode::BAD_REQUEST,
-            RoomError::YouDontHavePermissions | RoomError::OwnerCannotLeaveRoom => {
-                StatusCode::FORBIDDEN
-            }
-            RoomError::PasswordIncorrect => StatusCode::UNAUTHORIZED,
-            RoomError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            RoomError::General(_) => StatusCode::INTERNAL_SERVER_ERROR,
-        };
-        res.status_code(status);
-        res.render(Json(serde_json::json!({ "message": self.to_string() })));
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        write_problem(&self, req, res);
     }
 }
 
 impl EndpointOutRegister for RoomError {
     fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
-        operation.responses.insert(
-            StatusCode::NOT_FOUND.as_str(),
-            oapi::Response::new("Room not found")
-                .add_content("application/json", NotFoundError::to_schema(components)),
-        );
-        operation.responses.insert(
-            StatusCode::BAD_REQUEST.as_str(),
-            oapi::Response::new("Room already exists or bad request")
-                .add_content("application/json", BadRequestError::to_schema(components)),
-        );
-        operation.responses.insert(
-            StatusCode::FORBIDDEN.as_str(),
-            oapi::Response::new("Insufficient permissions or forbidden action")
-                .add_content("application/json", BadRequestError::to_schema(components)),
-        );
-        operation.responses.insert(
-            StatusCode::UNAUTHORIZED.as_str(),
-            oapi::Response::new("Incorrect password or unauthorized")
-                .add_content("application/json", BadRequestError::to_schema(components)),
-        );
-        operation.responses.insert(
-            StatusCode::INTERNAL_SERVER_ERROR.as_str(),
-            oapi::Response::new("Unexpected or general error")
-                .add_content("application/json", InternalError::to_schema(components)),
-        );
+        register_problems::<Self>(components, operation);
     }
 }
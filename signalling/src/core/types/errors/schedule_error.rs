@@ -0,0 +1,106 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+use thiserror::Error;
+
+use super::general::GeneralError;
+use super::problem::{
+    GENERAL_TYPES, Problem, ProblemType, ROOM_NOT_FOUND, UNEXPECTED, register_problems,
+    write_problem,
+};
+
+#[derive(Debug, Error, ToSchema, Serialize, Clone)]
+pub enum ScheduleError {
+    #[error("Room with ID {0} not found")]
+    RoomNotFound(String),
+
+    #[error("Room {0} has no live stream scheduled")]
+    ScheduleNotFound(String),
+
+    #[error("The start time must be in the future")]
+    StartInPast,
+
+    #[error("{0} is not an http(s) URL")]
+    InvalidSlateUrl(String),
+
+    #[error("The live stream has already started")]
+    AlreadyLive,
+
+    #[error("You don't have permissions to schedule this room")]
+    YouDontHavePermissions,
+
+    #[error("An unexpected error occurred: {0}")]
+    UnexpectedError(String),
+
+    #[error("General error: {0}")]
+    General(#[from] GeneralError),
+}
+
+const SCHEDULE_NOT_FOUND: ProblemType = ProblemType::new(
+    "schedule-not-found",
+    "No live stream scheduled",
+    StatusCode::NOT_FOUND,
+);
+const START_IN_PAST: ProblemType = ProblemType::new(
+    "start-in-past",
+    "Start time is in the past",
+    StatusCode::BAD_REQUEST,
+);
+const INVALID_SLATE_URL: ProblemType = ProblemType::new(
+    "invalid-slate-url",
+    "Invalid slate URL",
+    StatusCode::BAD_REQUEST,
+);
+const ALREADY_LIVE: ProblemType = ProblemType::new(
+    "already-live",
+    "Live stream already started",
+    StatusCode::CONFLICT,
+);
+const SCHEDULE_FORBIDDEN: ProblemType = ProblemType::new(
+    "schedule-forbidden",
+    "Not allowed to schedule the room",
+    StatusCode::FORBIDDEN,
+);
+
+impl Problem for ScheduleError {
+    fn problem_types() -> Vec<&'static ProblemType> {
+        let mut types = vec![
+            &ROOM_NOT_FOUND,
+            &SCHEDULE_NOT_FOUND,
+            &START_IN_PAST,
+            &INVALID_SLATE_URL,
+            &ALREADY_LIVE,
+            &SCHEDULE_FORBIDDEN,
+            &UNEXPECTED,
+        ];
+        types.extend(GENERAL_TYPES);
+        types
+    }
+
+    fn problem_type(&self) -> &'static ProblemType {
+        match self {
+            ScheduleError::RoomNotFound(_) => &ROOM_NOT_FOUND,
+            ScheduleError::ScheduleNotFound(_) => &SCHEDULE_NOT_FOUND,
+            ScheduleError::StartInPast => &START_IN_PAST,
+            ScheduleError::InvalidSlateUrl(_) => &INVALID_SLATE_URL,
+            ScheduleError::AlreadyLive => &ALREADY_LIVE,
+            ScheduleError::YouDontHavePermissions => &SCHEDULE_FORBIDDEN,
+            ScheduleError::UnexpectedError(_) => &UNEXPECTED,
+            ScheduleError::General(error) => error.problem_type(),
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for ScheduleError {
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        write_problem(&self, req, res);
+    }
+}
+
+impl EndpointOutRegister for ScheduleError {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        register_problems::<Self>(components, operation);
+    }
+}
@@ -0,0 +1,77 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+use thiserror::Error;
+
+use super::general::GeneralError;
+use super::problem::{
+    GENERAL_TYPES, Problem, ProblemType, UNEXPECTED, register_problems, write_problem,
+};
+
+#[derive(Debug, Error, ToSchema, Serialize, Clone)]
+pub enum SearchError {
+    #[error("The search query must not be empty")]
+    EmptyQuery,
+
+    #[error("Typesense is not configured")]
+    IndexNotConfigured,
+
+    #[error("A search index rebuild is already in progress")]
+    ReindexInProgress,
+
+    #[error("An unexpected error occurred: {0}")]
+    UnexpectedError(String),
+
+    #[error("General error: {0}")]
+    General(#[from] GeneralError),
+}
+
+const EMPTY_QUERY: ProblemType =
+    ProblemType::new("empty-query", "Empty search query", StatusCode::BAD_REQUEST);
+const SEARCH_NOT_CONFIGURED: ProblemType = ProblemType::new(
+    "search-not-configured",
+    "Search is not configured",
+    StatusCode::SERVICE_UNAVAILABLE,
+);
+const REINDEX_IN_PROGRESS: ProblemType = ProblemType::new(
+    "reindex-in-progress",
+    "Search index rebuild in progress",
+    StatusCode::CONFLICT,
+);
+
+impl Problem for SearchError {
+    fn problem_types() -> Vec<&'static ProblemType> {
+        let mut types = vec![
+            &EMPTY_QUERY,
+            &SEARCH_NOT_CONFIGURED,
+            &REINDEX_IN_PROGRESS,
+            &UNEXPECTED,
+        ];
+        types.extend(GENERAL_TYPES);
+        types
+    }
+
+    fn problem_type(&self) -> &'static ProblemType {
+        match self {
+            SearchError::EmptyQuery => &EMPTY_QUERY,
+            SearchError::IndexNotConfigured => &SEARCH_NOT_CONFIGURED,
+            SearchError::ReindexInProgress => &REINDEX_IN_PROGRESS,
+            SearchError::UnexpectedError(_) => &UNEXPECTED,
+            SearchError::General(error) => error.problem_type(),
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for SearchError {
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        write_problem(&self, req, res);
+    }
+}
+
+impl EndpointOutRegister for SearchError {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        register_problems::<Self>(components, operation);
+    }
+}
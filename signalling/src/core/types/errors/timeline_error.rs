@@ -0,0 +1,76 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+use thiserror::Error;
+
+use super::general::GeneralError;
+use super::problem::{
+    GENERAL_TYPES, Problem, ProblemType, ROOM_NOT_FOUND, UNEXPECTED, register_problems,
+    write_problem,
+};
+
+#[derive(Debug, Error, ToSchema, Serialize, Clone)]
+pub enum TimelineError {
+    #[error("Room with ID {0} not found")]
+    RoomNotFound(String),
+
+    #[error("Session with ID {0} not found")]
+    SessionNotFound(String),
+
+    #[error("You are not a member of this room")]
+    NotAMember,
+
+    #[error("An unexpected error occurred: {0}")]
+    UnexpectedError(String),
+
+    #[error("General error: {0}")]
+    General(#[from] GeneralError),
+}
+
+const SESSION_NOT_FOUND: ProblemType = ProblemType::new(
+    "session-not-found",
+    "Session not found",
+    StatusCode::NOT_FOUND,
+);
+const NOT_A_MEMBER: ProblemType = ProblemType::new(
+    "not-a-member",
+    "Not a member of the room",
+    StatusCode::FORBIDDEN,
+);
+
+impl Problem for TimelineError {
+    fn problem_types() -> Vec<&'static ProblemType> {
+        let mut types = vec![
+            &ROOM_NOT_FOUND,
+            &SESSION_NOT_FOUND,
+            &NOT_A_MEMBER,
+            &UNEXPECTED,
+        ];
+        types.extend(GENERAL_TYPES);
+        types
+    }
+
+    fn problem_type(&self) -> &'static ProblemType {
+        match self {
+            TimelineError::RoomNotFound(_) => &ROOM_NOT_FOUND,
+            TimelineError::SessionNotFound(_) => &SESSION_NOT_FOUND,
+            TimelineError::NotAMember => &NOT_A_MEMBER,
+            TimelineError::UnexpectedError(_) => &UNEXPECTED,
+            TimelineError::General(error) => error.problem_type(),
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for TimelineError {
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        write_problem(&self, req, res);
+    }
+}
+
+impl EndpointOutRegister for TimelineError {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        register_problems::<Self>(components, operation);
+    }
+}
@@ -5,7 +5,10 @@ use serde::Serialize;
 use thiserror::Error;
 
 use super::general::GeneralError;
-use super::{BadRequestError, InternalError, NotFoundError};
+use super::problem::{
+    GENERAL_TYPES, Problem, ProblemType, UNEXPECTED, USER_EXISTS, USER_NOT_FOUND,
+    register_problems, write_problem,
+};
 
 #[derive(Debug, Error, Serialize, ToSchema, Clone)]
 pub enum UserError {
@@ -15,9 +18,18 @@ pub enum UserError {
     #[error("User with username {0} not found")]
     UserNameNotFound(String),
 
+    #[error("User with ID {0} not found")]
+    PublicIdNotFound(String),
+
     #[error("User with ID {0} is already exists")]
     UserExists(i32),
 
+    #[error("Invalid locale {0}")]
+    InvalidLocale(String),
+
+    #[error("Unknown timezone {0}")]
+    InvalidTimezone(String),
+
     #[error("An unexpected error occurred in channel: {0}")]
     UnexpectedError(String),
 
@@ -25,37 +37,50 @@ pub enum UserError {
     General(#[from] GeneralError),
 }
 
+const INVALID_LOCALE: ProblemType =
+    ProblemType::new("invalid-locale", "Invalid locale", StatusCode::BAD_REQUEST);
+const INVALID_TIMEZONE: ProblemType = ProblemType::new(
+    "invalid-timezone",
+    "Unknown timezone",
+    StatusCode::BAD_REQUEST,
+);
+
+impl Problem for UserError {
+    fn problem_types() -> Vec<&'static ProblemType> {
+        let mut types = vec![
+            &USER_NOT_FOUND,
+            &USER_EXISTS,
+            &INVALID_LOCALE,
+            &INVALID_TIMEZONE,
+            &UNEXPECTED,
+        ];
+        types.extend(GENERAL_TYPES);
+        types
+    }
+
+    fn problem_type(&self) -> &'static ProblemType {
+        match self {
+            UserError::UserNotFound(_)
+            | UserError::UserNameNotFound(_)
+            | UserError::PublicIdNotFound(_) => &USER_NOT_FOUND,
+            UserError::UserExists(_) => &USER_EXISTS,
+            UserError::InvalidLocale(_) => &INVALID_LOCALE,
+            UserError::InvalidTimezone(_) => &INVALID_TIMEZONE,
+            UserError::UnexpectedError(_) => &UNEXPECTED,
+            UserError::General(error) => error.problem_type(),
+        }
+    }
+}
+
 #[async_trait]
 impl Writer for UserError {
-    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
-        let status = match self {
-            UserError::UserNotFound(_) | UserError::UserNameNotFound(_) => StatusCode::NOT_FOUND,
-            UserError::UserExists(_) => StatusCode::BAD_REQUEST,
-            UserError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            UserError::General(_) => StatusCode::INTERNAL_SERVER_ERROR,
-        };
-
-        res.status_code(status);
-        res.render(Json(serde_json::json!({ "message": self.to_string() })));
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        write_problem(&self, req, res);
     }
 }
 
 impl EndpointOutRegister for UserError {
     fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
-        operation.responses.insert(
-            StatusCode::NOT_FOUND.as_str(),
-            oapi::Response::new("User not found")
-                .add_content("application/json", NotFoundError::to_schema(components)),
-        );
-        operation.responses.insert(
-            StatusCode::BAD_REQUEST.as_str(),
-            oapi::Response::new("User already exists or bad request")
-                .add_content("application/json", BadRequestError::to_schema(components)),
-        );
-        operation.responses.insert(
-            StatusCode::INTERNAL_SERVER_ERROR.as_str(),
-            oapi::Response::new("Unexpected or general error")
-                .add_content("application/json", InternalError::to_schema(components)),
-        );
+        register_problems::<Self>(components, operation);
     }
 }
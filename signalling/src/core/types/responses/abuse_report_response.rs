@@ -0,0 +1,67 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+
+use crate::core::entities::models::{AbuseReport, Room, User};
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AbuseReportResponse {
+    #[serde(flatten)]
+    pub report: AbuseReport,
+    pub room: Room,
+    pub reporter: User,
+    pub target: User,
+}
+
+#[async_trait]
+impl Writer for AbuseReportResponse {
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        if req.method() == Method::POST {
+            res.status_code(StatusCode::CREATED);
+            res.render(Json(self));
+        } else {
+            res.status_code(StatusCode::OK);
+            res.render(Json(self));
+        }
+    }
+}
+
+impl EndpointOutRegister for AbuseReportResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK").add_content(
+                "application/json",
+                AbuseReportResponse::to_schema(components),
+            ),
+        );
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListAbuseReportResponse {
+    pub reports: Vec<AbuseReportResponse>,
+}
+
+#[async_trait]
+impl Writer for ListAbuseReportResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for ListAbuseReportResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK").add_content(
+                "application/json",
+                ListAbuseReportResponse::to_schema(components),
+            ),
+        );
+    }
+}
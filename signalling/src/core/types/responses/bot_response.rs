@@ -0,0 +1,58 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+
+use crate::core::entities::models::Bot;
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BotResponse {
+    #[serde(flatten)]
+    pub bot: Bot,
+    /// Only returned when the bot is registered, it can't be recovered later
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+#[async_trait]
+impl Writer for BotResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for BotResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK")
+                .add_content("application/json", BotResponse::to_schema(components)),
+        );
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListBotResponse {
+    pub bots: Vec<Bot>,
+}
+
+#[async_trait]
+impl Writer for ListBotResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for ListBotResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK")
+                .add_content("application/json", ListBotResponse::to_schema(components)),
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use salvo::http::{Method, StatusCode};
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::core::entities::models::Organization;
+
+/// What clients theme the waiting room and the call with
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BrandingResponse {
+    pub org_id: String,
+    pub name: String,
+    pub logo_url: Option<String>,
+    pub primary_color: Option<String>,
+    pub accent_color: Option<String>,
+    pub background_url: Option<String>,
+}
+
+impl From<Organization> for BrandingResponse {
+    fn from(org: Organization) -> Self {
+        Self {
+            org_id: org.public_id,
+            name: org.name,
+            logo_url: org.logo_url,
+            primary_color: org.primary_color,
+            accent_color: org.accent_color,
+            background_url: org.background_url,
+        }
+    }
+}
+
+/// Branding rarely changes and is fetched before every join
+const BRANDING_CACHE_CONTROL: &str = "public, max-age=300";
+
+#[async_trait]
+impl Writer for BrandingResponse {
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        if req.method() == Method::POST {
+            res.status_code(StatusCode::CREATED);
+        } else {
+            res.status_code(StatusCode::OK);
+        }
+
+        if req.method() == Method::GET {
+            let _ = res.add_header("Cache-Control", BRANDING_CACHE_CONTROL, true);
+        }
+
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for BrandingResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK")
+                .add_content("application/json", BrandingResponse::to_schema(components)),
+        );
+        operation.responses.insert(
+            StatusCode::CREATED.as_str(),
+            oapi::Response::new("Created")
+                .add_content("application/json", BrandingResponse::to_schema(components)),
+        );
+    }
+}
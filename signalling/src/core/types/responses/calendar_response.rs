@@ -0,0 +1,86 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+
+use crate::core::entities::models::CalendarAccount;
+
+/// Where to send the user to grant access to their calendar
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarConnectResponse {
+    pub authorize_url: String,
+}
+
+#[async_trait]
+impl Writer for CalendarConnectResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for CalendarConnectResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK").add_content(
+                "application/json",
+                CalendarConnectResponse::to_schema(components),
+            ),
+        );
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarAccountResponse {
+    #[serde(flatten)]
+    pub account: CalendarAccount,
+}
+
+#[async_trait]
+impl Writer for CalendarAccountResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for CalendarAccountResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK").add_content(
+                "application/json",
+                CalendarAccountResponse::to_schema(components),
+            ),
+        );
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListCalendarAccountResponse {
+    pub accounts: Vec<CalendarAccount>,
+}
+
+#[async_trait]
+impl Writer for ListCalendarAccountResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for ListCalendarAccountResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK").add_content(
+                "application/json",
+                ListCalendarAccountResponse::to_schema(components),
+            ),
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+
+use crate::core::entities::models::Callout;
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CalloutResponse {
+    #[serde(flatten)]
+    pub callout: Callout,
+}
+
+#[async_trait]
+impl Writer for CalloutResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for CalloutResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK")
+                .add_content("application/json", CalloutResponse::to_schema(components)),
+        );
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListCalloutResponse {
+    pub callouts: Vec<Callout>,
+}
+
+#[async_trait]
+impl Writer for ListCalloutResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for ListCalloutResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK").add_content(
+                "application/json",
+                ListCalloutResponse::to_schema(components),
+            ),
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use chrono::NaiveDateTime;
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+
+/// What clients show as "messages are retained for N days"
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatRetentionResponse {
+    /// `None` when messages are kept forever
+    pub retention_days: Option<i32>,
+    /// Whether the room overrides the deployment default
+    pub is_room_policy: bool,
+}
+
+#[async_trait]
+impl Writer for ChatRetentionResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for ChatRetentionResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK").add_content(
+                "application/json",
+                ChatRetentionResponse::to_schema(components),
+            ),
+        );
+    }
+}
+
+/// Progress of the retention purge across signalling nodes
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatRetentionProgressResponse {
+    pub default_retention_days: Option<i32>,
+    pub is_running: bool,
+    pub last_started_at: Option<NaiveDateTime>,
+    pub last_finished_at: Option<NaiveDateTime>,
+    pub last_run_deleted: i64,
+    pub last_run_batches: i64,
+    pub total_deleted: i64,
+}
+
+#[async_trait]
+impl Writer for ChatRetentionProgressResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for ChatRetentionProgressResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK").add_content(
+                "application/json",
+                ChatRetentionProgressResponse::to_schema(components),
+            ),
+        );
+    }
+}
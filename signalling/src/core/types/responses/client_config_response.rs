@@ -0,0 +1,100 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// An `RTCIceServer` as the browser takes it
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IceServerResponse {
+    pub urls: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential: Option<String>,
+}
+
+/// Caps for the encodings a client publishes, in kbps
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MaxBitratesResponse {
+    pub video: u32,
+    pub screen: u32,
+    pub audio: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SocketEndpointsResponse {
+    /// `null` when the socket is served by the API's host
+    pub url: Option<String>,
+    pub path: String,
+    pub rtc_namespace: String,
+    pub chat_namespace: String,
+}
+
+/// Settings clients used to ship with, served so they can change without a
+/// client release
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientConfigResponse {
+    /// Feature flags that are on, anything not listed is off
+    pub features: Vec<String>,
+    pub ice_servers: Vec<IceServerResponse>,
+    pub max_bitrates: MaxBitratesResponse,
+    /// Most preferred first
+    pub video_codecs: Vec<String>,
+    pub socket: SocketEndpointsResponse,
+}
+
+/// Clients fetch it on every launch, a minute is soon enough for a change
+const CLIENT_CONFIG_CACHE_CONTROL: &str = "private, max-age=60";
+
+#[async_trait]
+impl Writer for ClientConfigResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        let _ = res.add_header("Cache-Control", CLIENT_CONFIG_CACHE_CONTROL, true);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for ClientConfigResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK").add_content(
+                "application/json",
+                ClientConfigResponse::to_schema(components),
+            ),
+        );
+    }
+}
+
+/// Feature flags an organization turns on for its clients
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientFeaturesResponse {
+    pub org_id: String,
+    pub features: Vec<String>,
+}
+
+#[async_trait]
+impl Writer for ClientFeaturesResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for ClientFeaturesResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK").add_content(
+                "application/json",
+                ClientFeaturesResponse::to_schema(components),
+            ),
+        );
+    }
+}
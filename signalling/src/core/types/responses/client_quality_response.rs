@@ -0,0 +1,66 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+
+use crate::core::dtos::socket::socket_dto::ClientMetadataDto;
+
+/// Averages over the stats snapshots pushed by clients
+#[derive(Debug, Default, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientQualityStats {
+    pub snapshots: i64,
+    pub avg_rtt_ms: Option<f64>,
+    pub avg_jitter_ms: Option<f64>,
+    pub avg_packet_loss: Option<f64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ParticipantQualityResponse {
+    pub participant_id: String,
+    pub user_id: String,
+    pub client: ClientMetadataDto,
+    pub last_received_at: i64,
+    #[serde(flatten)]
+    pub stats: ClientQualityStats,
+}
+
+/// Participants grouped by the app version they connected with, so a
+/// regression shows up against the versions around it
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientVersionQualityResponse {
+    pub client_version: Option<String>,
+    pub participants: i64,
+    #[serde(flatten)]
+    pub stats: ClientQualityStats,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientQualityResponse {
+    pub room_id: String,
+    pub versions: Vec<ClientVersionQualityResponse>,
+    pub participants: Vec<ParticipantQualityResponse>,
+}
+
+#[async_trait]
+impl Writer for ClientQualityResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for ClientQualityResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK").add_content(
+                "application/json",
+                ClientQualityResponse::to_schema(components),
+            ),
+        );
+    }
+}
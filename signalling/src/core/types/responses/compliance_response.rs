@@ -0,0 +1,122 @@
+use chrono::NaiveDateTime;
+use salvo::http::StatusCode;
+use salvo::http::header::CONTENT_DISPOSITION;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LegalHoldResponse {
+    pub room_id: String,
+    /// `None` once the hold is lifted
+    pub legal_hold_at: Option<NaiveDateTime>,
+}
+
+/// Everything kept about a room, for legal discovery. Soft-deleted messages are included.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ComplianceExportResponse {
+    pub room_id: String,
+    pub title: String,
+    pub created_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub legal_hold_at: Option<NaiveDateTime>,
+    pub generated_at: NaiveDateTime,
+    pub messages: Vec<ComplianceMessage>,
+    pub events: Vec<ComplianceEvent>,
+    pub recordings: Vec<ComplianceRecording>,
+    /// SFU nodes whose recordings couldn't be listed, the export is incomplete when not empty
+    pub unreachable_nodes: Vec<String>,
+    pub integrity: ComplianceIntegrity,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ComplianceMessage {
+    pub id: i32,
+    /// Public id of the author
+    pub author_id: String,
+    pub data: String,
+    #[serde(rename = "type")]
+    pub type_: i16,
+    pub status: i16,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
+}
+
+/// One entry of the room's history, e.g. `member_joined` or `message_deleted`
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ComplianceEvent {
+    pub at: NaiveDateTime,
+    pub kind: String,
+    /// Public id of the user the event is about
+    pub actor_id: Option<String>,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ComplianceRecording {
+    pub node_id: String,
+    pub participant_id: String,
+    /// SHA-256 of `manifest.json` as stored on the node
+    pub sha256: String,
+    pub manifest: Value,
+}
+
+/// SHA-256 hex digests of the compact JSON of each section. `bundle` hashes the
+/// section digests joined by newlines in field order, so one value pins the whole export.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ComplianceIntegrity {
+    pub algorithm: String,
+    pub messages: String,
+    pub events: String,
+    pub recordings: String,
+    pub bundle: String,
+}
+
+#[async_trait]
+impl Writer for LegalHoldResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for LegalHoldResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK")
+                .add_content("application/json", LegalHoldResponse::to_schema(components)),
+        );
+    }
+}
+
+#[async_trait]
+impl Writer for ComplianceExportResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        let disposition = format!("attachment; filename=\"compliance-{}.json\"", self.room_id);
+        res.add_header(CONTENT_DISPOSITION, disposition, true).ok();
+
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for ComplianceExportResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK").add_content(
+                "application/json",
+                ComplianceExportResponse::to_schema(components),
+            ),
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HlsPlaybackStats {
+    /// Sessions that reported a startup time
+    pub startups: i64,
+    pub avg_startup_ms: Option<i64>,
+    pub rebuffer_count: i64,
+    pub rebuffer_ms: i64,
+    pub watched_ms: i64,
+    /// Share of watch time spent rebuffering
+    pub rebuffer_ratio: f64,
+    /// Beacons per selected rendition
+    pub renditions: HashMap<String, i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HlsSessionTelemetryResponse {
+    pub session_id: String,
+    pub viewer_id: Option<String>,
+    pub participant_id: Option<String>,
+    pub last_rendition: Option<String>,
+    #[serde(flatten)]
+    pub stats: HlsPlaybackStats,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HlsTelemetryResponse {
+    pub room_id: String,
+    #[serde(flatten)]
+    pub stats: HlsPlaybackStats,
+    pub sessions: Vec<HlsSessionTelemetryResponse>,
+}
+
+#[async_trait]
+impl Writer for HlsTelemetryResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for HlsTelemetryResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK").add_content(
+                "application/json",
+                HlsTelemetryResponse::to_schema(components),
+            ),
+        );
+    }
+}
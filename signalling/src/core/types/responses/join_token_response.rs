@@ -0,0 +1,34 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JoinTokenResponse {
+    /// Sent as `joinToken` with the socket `room.publish` event
+    pub token: String,
+    pub room_id: String,
+    /// What the SFU and the other participants know the user by in this call
+    pub participant_id: String,
+    /// Unix timestamp
+    pub expires_at: i64,
+}
+
+#[async_trait]
+impl Writer for JoinTokenResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::CREATED);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for JoinTokenResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::CREATED.as_str(),
+            oapi::Response::new("Created")
+                .add_content("application/json", JoinTokenResponse::to_schema(components)),
+        );
+    }
+}
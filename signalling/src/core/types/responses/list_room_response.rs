@@ -3,7 +3,7 @@ use salvo::oapi::{self, EndpointOutRegister, ToSchema};
 use salvo::prelude::*;
 use serde::Serialize;
 
-use super::room_response::RoomResponse;
+use super::room_response::{RoomResponse, SlimRoomResponse, is_slim_response};
 
 #[derive(Debug, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -13,9 +13,16 @@ pub struct ListRoomResponse {
 
 #[async_trait]
 impl Writer for ListRoomResponse {
-    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
         res.status_code(StatusCode::OK);
-        res.render(Json(self));
+
+        if is_slim_response(req) {
+            let rooms: Vec<SlimRoomResponse> =
+                self.rooms.into_iter().map(SlimRoomResponse::from).collect();
+            res.render(Json(serde_json::json!({ "rooms": rooms })));
+        } else {
+            res.render(Json(self));
+        }
     }
 }
 
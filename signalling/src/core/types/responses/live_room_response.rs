@@ -0,0 +1,51 @@
+use dispatcher::domain::fleet::LiveRoomView;
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveRoomResponse {
+    pub room_id: String,
+    /// Clients in the call across every node
+    pub clients: usize,
+    pub node_ids: Vec<String>,
+}
+
+/// Rooms with a call going on, busiest first
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListLiveRoomResponse {
+    pub rooms: Vec<LiveRoomResponse>,
+}
+
+impl From<LiveRoomView> for LiveRoomResponse {
+    fn from(view: LiveRoomView) -> Self {
+        LiveRoomResponse {
+            room_id: view.room_id,
+            clients: view.clients,
+            node_ids: view.node_ids,
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for ListLiveRoomResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for ListLiveRoomResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK").add_content(
+                "application/json",
+                ListLiveRoomResponse::to_schema(components),
+            ),
+        );
+    }
+}
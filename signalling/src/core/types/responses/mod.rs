@@ -1,10 +1,37 @@
+pub mod abuse_report_response;
 pub mod auth_response;
+pub mod bot_response;
+pub mod branding_response;
+pub mod calendar_response;
+pub mod callout_response;
+pub mod chat_retention_response;
 pub mod check_username_response;
+pub mod client_config_response;
+pub mod client_quality_response;
+pub mod compliance_response;
+pub mod dead_letter_response;
 pub mod failed_response;
+pub mod hls_telemetry_response;
+pub mod join_token_response;
+pub mod list_member_response;
 pub mod list_message_response;
+pub mod list_participant_response;
 pub mod list_room_response;
+pub mod live_room_response;
+pub mod live_schedule_response;
+pub mod matrix_room_response;
 pub mod message_response;
+pub mod node_response;
+pub mod observer_token_response;
+pub mod overflow_response;
 pub mod presigned_url_response;
+pub mod rollout_response;
 pub mod room_response;
+pub mod room_stats_response;
+pub mod room_timeline_response;
+pub mod search_reindex_response;
+pub mod search_response;
+pub mod sfu_diagnostics_response;
 pub mod socket_response;
 pub mod user_response;
+pub mod viewer_count_response;
@@ -0,0 +1,78 @@
+use dispatcher::domain::fleet::NodeView;
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeResponse {
+    pub node_id: String,
+    pub addr: String,
+    /// `stable` or `canary`
+    pub cohort: String,
+    pub tags: Vec<String>,
+    /// Usage in percent
+    pub cpu: f32,
+    pub ram: f32,
+    pub clients: usize,
+    /// Takes no new rooms while drained, calls already on it carry on
+    pub is_drained: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListNodeResponse {
+    pub nodes: Vec<NodeResponse>,
+}
+
+impl From<NodeView> for NodeResponse {
+    fn from(view: NodeView) -> Self {
+        NodeResponse {
+            node_id: view.node_id,
+            addr: view.addr,
+            cohort: view.cohort.as_str().to_owned(),
+            tags: view.tags,
+            cpu: view.cpu,
+            ram: view.ram,
+            clients: view.clients,
+            is_drained: view.is_drained,
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for NodeResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for NodeResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK")
+                .add_content("application/json", NodeResponse::to_schema(components)),
+        );
+    }
+}
+
+#[async_trait]
+impl Writer for ListNodeResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for ListNodeResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK")
+                .add_content("application/json", ListNodeResponse::to_schema(components)),
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use salvo::http::{Method, StatusCode};
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ObserverTokenResponse {
+    /// Sent as `Authorization: Observer <token>` when connecting to `/rtc`
+    pub token: String,
+    pub observer_id: String,
+    pub room_id: String,
+    /// Unix timestamp
+    pub expires_at: i64,
+}
+
+#[async_trait]
+impl Writer for ObserverTokenResponse {
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        if req.method() == Method::POST {
+            res.status_code(StatusCode::CREATED);
+        } else {
+            res.status_code(StatusCode::OK);
+        }
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for ObserverTokenResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::CREATED.as_str(),
+            oapi::Response::new("Created").add_content(
+                "application/json",
+                ObserverTokenResponse::to_schema(components),
+            ),
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+
+use super::socket_response::HlsStreamResponse;
+
+/// What a viewer of a full call may do besides watching
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum OverflowInteraction {
+    Chat,
+    Reactions,
+}
+
+/// Handed to a joiner the call has no WebRTC slot for. They watch the
+/// participants over HLS and wait in line, `room.slot_available` on the chat
+/// namespace tells them when to join again.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OverflowResponse {
+    pub viewer_id: String,
+    /// Place in line for the next free slot, from 1
+    pub position: i64,
+    pub streams: Vec<HlsStreamResponse>,
+    /// When the stream URLs stop working, as a unix timestamp. Fresh ones come
+    /// as `room.hls_streams` on the chat namespace before then.
+    pub expires_at: i64,
+    pub interactions: Vec<OverflowInteraction>,
+}
@@ -0,0 +1,41 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CanaryNodeResponse {
+    pub node_id: String,
+    /// Idle CPU in percent
+    pub cpu_free: f32,
+}
+
+/// Canary rollout settings. Changes apply to rooms opened afterwards, rooms
+/// already running stay on the nodes they started on.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RolloutResponse {
+    pub percentage: u8,
+    pub client_versions: Vec<String>,
+    /// Canary rooms go to stable nodes while this is empty
+    pub canary_nodes: Vec<CanaryNodeResponse>,
+}
+
+#[async_trait]
+impl Writer for RolloutResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for RolloutResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK")
+                .add_content("application/json", RolloutResponse::to_schema(components)),
+        );
+    }
+}
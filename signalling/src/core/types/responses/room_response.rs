@@ -1,13 +1,19 @@
 use salvo::http::{Method, StatusCode};
 use salvo::oapi::{self, EndpointOutRegister, ToSchema};
 use salvo::prelude::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use crate::core::entities::models::{Member, Participant, Room, User};
+use crate::core::{
+    entities::models::{Member, Participant, Room, User},
+    utils::avatar_utils::placeholder_url,
+};
 
-use super::message_response::MessageResponse;
+use super::{
+    branding_response::BrandingResponse, message_response::MessageResponse,
+    overflow_response::OverflowResponse,
+};
 
-#[derive(Debug, Serialize, ToSchema, Clone)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct RoomResponse {
     #[serde(flatten)]
@@ -15,9 +21,15 @@ pub struct RoomResponse {
     pub members: Vec<MemberResponse>,
     pub participants: Vec<ParticipantResponse>,
     pub latest_message: Option<MessageResponse>,
+    /// Set on join for rooms of an organization
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branding: Option<BrandingResponse>,
+    /// Set on join when the call was full and the user was admitted as a viewer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overflow: Option<OverflowResponse>,
 }
 
-#[derive(Debug, Serialize, ToSchema, Clone)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct MemberResponse {
     #[serde(flatten)]
@@ -25,12 +37,81 @@ pub struct MemberResponse {
     pub user: Option<User>,
 }
 
-#[derive(Debug, Serialize, Clone, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ParticipantResponse {
     #[serde(flatten)]
     pub participant: Participant,
     pub user: Option<User>,
+    /// Tile to render while the participant's video is off
+    #[serde(default)]
+    pub placeholder_url: Option<String>,
+}
+
+impl ParticipantResponse {
+    pub fn new(participant: Participant, user: Option<User>) -> Self {
+        let placeholder_url = user.as_ref().map(placeholder_url);
+
+        Self {
+            participant,
+            user,
+            placeholder_url,
+        }
+    }
+}
+
+/// Clients that send `X-Response-Version: 2` get `SlimRoomResponse` payloads,
+/// full member and participant lists are served by the paginated endpoints
+pub const RESPONSE_VERSION_HEADER: &str = "X-Response-Version";
+
+const SLIM_RESPONSE_VERSION: u8 = 2;
+
+/// Number of members and participants embedded in a slim room payload
+const SLIM_PAGE_SIZE: usize = 20;
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SlimRoomResponse {
+    #[serde(flatten)]
+    pub room: Room,
+    pub members: Vec<MemberResponse>,
+    pub participants: Vec<ParticipantResponse>,
+    pub total_members: usize,
+    pub total_participants: usize,
+    pub latest_message: Option<MessageResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branding: Option<BrandingResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overflow: Option<OverflowResponse>,
+}
+
+impl From<RoomResponse> for SlimRoomResponse {
+    fn from(response: RoomResponse) -> Self {
+        let total_members = response.members.len();
+        let total_participants = response.participants.len();
+
+        let mut members = response.members;
+        members.truncate(SLIM_PAGE_SIZE);
+
+        let mut participants = response.participants;
+        participants.truncate(SLIM_PAGE_SIZE);
+
+        Self {
+            room: response.room,
+            members,
+            participants,
+            total_members,
+            total_participants,
+            latest_message: response.latest_message,
+            branding: response.branding,
+            overflow: response.overflow,
+        }
+    }
+}
+
+pub fn is_slim_response(req: &Request) -> bool {
+    req.header::<u8>(RESPONSE_VERSION_HEADER)
+        .is_some_and(|version| version >= SLIM_RESPONSE_VERSION)
 }
 
 #[async_trait]
@@ -38,9 +119,13 @@ impl Writer for RoomResponse {
     async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
         if req.method() == Method::POST {
             res.status_code(StatusCode::CREATED);
-            res.render(Json(self));
         } else {
             res.status_code(StatusCode::OK);
+        }
+
+        if is_slim_response(req) {
+            res.render(Json(SlimRoomResponse::from(self)));
+        } else {
             res.render(Json(self));
         }
     }
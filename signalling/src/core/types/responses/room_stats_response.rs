@@ -0,0 +1,98 @@
+use dispatcher::domain::room_stats::{NodeShare, ParticipantPlacement, RoomStatsView};
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ParticipantPlacementResponse {
+    pub participant_id: String,
+    /// More than one while the participant migrates between nodes
+    pub node_ids: Vec<String>,
+    pub tracks: u64,
+    pub subscribers: u64,
+    pub egress_bps: u64,
+    pub talk_time_ms: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeShareResponse {
+    pub node_id: String,
+    pub publishers: u64,
+    pub subscribers: u64,
+    pub egress_bps: u64,
+}
+
+/// A room across every SFU node holding part of it
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomStatsResponse {
+    pub room_id: String,
+    pub participants: Vec<ParticipantPlacementResponse>,
+    pub nodes: Vec<NodeShareResponse>,
+    pub subscribers: u64,
+    pub egress_bps: u64,
+    pub is_recording: bool,
+    pub is_recording_paused: bool,
+    /// Their share of the room is missing from the totals
+    pub unreachable_nodes: Vec<String>,
+}
+
+impl From<ParticipantPlacement> for ParticipantPlacementResponse {
+    fn from(placement: ParticipantPlacement) -> Self {
+        Self {
+            participant_id: placement.participant_id,
+            node_ids: placement.node_ids,
+            tracks: placement.tracks,
+            subscribers: placement.subscribers,
+            egress_bps: placement.egress_bps,
+            talk_time_ms: placement.talk_time_ms,
+        }
+    }
+}
+
+impl From<NodeShare> for NodeShareResponse {
+    fn from(share: NodeShare) -> Self {
+        Self {
+            node_id: share.node_id,
+            publishers: share.publishers,
+            subscribers: share.subscribers,
+            egress_bps: share.egress_bps,
+        }
+    }
+}
+
+impl From<RoomStatsView> for RoomStatsResponse {
+    fn from(view: RoomStatsView) -> Self {
+        Self {
+            room_id: view.room_id,
+            participants: view.participants.into_iter().map(Into::into).collect(),
+            nodes: view.nodes.into_iter().map(Into::into).collect(),
+            subscribers: view.subscribers,
+            egress_bps: view.egress_bps,
+            is_recording: view.is_recording,
+            is_recording_paused: view.is_recording_paused,
+            unreachable_nodes: view.unreachable_nodes,
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for RoomStatsResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for RoomStatsResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK")
+                .add_content("application/json", RoomStatsResponse::to_schema(components)),
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+
+use crate::core::entities::models::{RoomEvent, RoomSession, User};
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineEventResponse {
+    #[serde(flatten)]
+    pub event: RoomEvent,
+    /// Who acted, missing for events nobody in particular caused
+    pub user: Option<User>,
+}
+
+/// Everything that happened in one session, oldest first
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomTimelineResponse {
+    pub session: RoomSession,
+    pub events: Vec<TimelineEventResponse>,
+}
+
+#[async_trait]
+impl Writer for RoomTimelineResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for RoomTimelineResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK").add_content(
+                "application/json",
+                RoomTimelineResponse::to_schema(components),
+            ),
+        );
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListRoomSessionResponse {
+    pub sessions: Vec<RoomSession>,
+}
+
+#[async_trait]
+impl Writer for ListRoomSessionResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for ListRoomSessionResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK").add_content(
+                "application/json",
+                ListRoomSessionResponse::to_schema(components),
+            ),
+        );
+    }
+}
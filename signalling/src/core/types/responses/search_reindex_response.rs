@@ -0,0 +1,56 @@
+use chrono::NaiveDateTime;
+use salvo::http::{Method, StatusCode};
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchReindexCollection {
+    pub name: String,
+    pub indexed: i64,
+    pub total: i64,
+}
+
+/// Progress of the latest search index rebuild across signalling nodes
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchReindexResponse {
+    pub is_running: bool,
+    pub started_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+    pub is_failed: bool,
+    pub collections: Vec<SearchReindexCollection>,
+}
+
+#[async_trait]
+impl Writer for SearchReindexResponse {
+    async fn write(self, req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        // Starting a rebuild only queues it, the work happens in the background
+        if req.method() == Method::POST {
+            res.status_code(StatusCode::ACCEPTED);
+        } else {
+            res.status_code(StatusCode::OK);
+        }
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for SearchReindexResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK").add_content(
+                "application/json",
+                SearchReindexResponse::to_schema(components),
+            ),
+        );
+        operation.responses.insert(
+            StatusCode::ACCEPTED.as_str(),
+            oapi::Response::new("Accepted").add_content(
+                "application/json",
+                SearchReindexResponse::to_schema(components),
+            ),
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectCountsResponse {
+    pub rooms: u64,
+    pub publishers: u64,
+    pub subscribers: u64,
+    pub tracks: u64,
+    pub forward_tracks: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeDiagnosticsResponse {
+    pub node_id: String,
+    pub clients: u64,
+    /// Walked from the node's rooms
+    pub reachable: ObjectCountsResponse,
+    /// Still allocated, above `reachable` when objects outlived their room
+    pub live: ObjectCountsResponse,
+    pub str0m_subscribers: u64,
+    pub forwarding_channels: u64,
+    pub tokio_tasks: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SfuDiagnosticsResponse {
+    pub nodes: Vec<NodeDiagnosticsResponse>,
+    pub unreachable_nodes: Vec<String>,
+}
+
+#[async_trait]
+impl Writer for SfuDiagnosticsResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for SfuDiagnosticsResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK").add_content(
+                "application/json",
+                SfuDiagnosticsResponse::to_schema(components),
+            ),
+        );
+    }
+}
@@ -0,0 +1,37 @@
+use salvo::http::StatusCode;
+use salvo::oapi::{self, EndpointOutRegister, ToSchema};
+use salvo::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewerCountResponse {
+    pub room_id: String,
+    /// True while the stream has at least one publisher connected
+    pub is_live: bool,
+    pub webrtc_viewers: i64,
+    /// Estimated from recent playlist requests, players poll a live playlist every few seconds
+    pub hls_viewers: i64,
+    pub total_viewers: i64,
+    pub updated_at: i64,
+}
+
+#[async_trait]
+impl Writer for ViewerCountResponse {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        res.status_code(StatusCode::OK);
+        res.render(Json(self));
+    }
+}
+
+impl EndpointOutRegister for ViewerCountResponse {
+    fn register(components: &mut oapi::Components, operation: &mut oapi::Operation) {
+        operation.responses.insert(
+            StatusCode::OK.as_str(),
+            oapi::Response::new("OK").add_content(
+                "application/json",
+                ViewerCountResponse::to_schema(components),
+            ),
+        );
+    }
+}
@@ -23,3 +23,24 @@ pub fn api_key_middleware() -> impl Handler {
     }
     middleware
 }
+
+/// Guards operator endpoints, requests are rejected while `ADMIN_SECRET_KEY` is unset
+pub fn admin_key_middleware() -> impl Handler {
+    #[handler]
+    async fn middleware(req: &mut Request, depot: &mut Depot, res: &mut Response) {
+        let admin_key_header = req
+            .headers()
+            .get("X-Admin-Key")
+            .and_then(|h| h.to_str().ok());
+        let app_env = depot.obtain::<AppEnv>().unwrap();
+
+        match admin_key_header {
+            Some(key) if !app_env.admin_api_key.is_empty() && key == app_env.admin_api_key => {}
+            _ => {
+                res.status_code(StatusCode::UNAUTHORIZED);
+                return res.render(Json(AuthError::InvalidAPIKey));
+            }
+        }
+    }
+    middleware
+}
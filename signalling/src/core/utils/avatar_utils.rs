@@ -0,0 +1,48 @@
+use crate::core::entities::models::User;
+
+/// Tile backgrounds, picked by user id so a user looks the same on every client
+const PALETTE: [&str; 8] = [
+    "#1E88E5", "#43A047", "#E53935", "#8E24AA", "#FB8C00", "#00897B", "#3949AB", "#6D4C41",
+];
+
+/// Where clients fetch the tile shown while a participant's video is off.
+/// The version changes whenever the user is updated, so renames bust caches.
+pub fn placeholder_url(user: &User) -> String {
+    format!(
+        "/busapi/v3/avatars/{}/placeholder.svg?v={}",
+        user.public_id,
+        user.updated_at.and_utc().timestamp()
+    )
+}
+
+pub fn render_placeholder(user: &User) -> String {
+    let name = user.full_name.as_deref().unwrap_or(&user.user_name);
+    let color = PALETTE[user.id.unsigned_abs() as usize % PALETTE.len()];
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="256" height="256" viewBox="0 0 256 256"><rect width="256" height="256" fill="{}"/><text x="50%" y="50%" dy=".35em" fill="#FFFFFF" font-family="Helvetica, Arial, sans-serif" font-size="104" font-weight="600" text-anchor="middle">{}</text></svg>"##,
+        color,
+        escape_xml(&initials(name))
+    )
+}
+
+/// First letter of the first and last word, `?` for blank names
+fn initials(name: &str) -> String {
+    let words = name.split_whitespace().collect::<Vec<_>>();
+
+    let letters = match words.as_slice() {
+        [] => return "?".to_string(),
+        [word] => word.chars().take(1).collect::<String>(),
+        [first, .., last] => first.chars().take(1).chain(last.chars().take(1)).collect(),
+    };
+
+    letters.to_uppercase()
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,365 @@
+use std::{collections::BTreeMap, fmt};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use socketioxide_redis::drivers::redis::redis_client::{
+    self as redis, AsyncCommands, cluster_async::ClusterConnection,
+};
+
+use crate::core::{
+    dtos::socket::socket_dto::{ClientMetadataDto, ClientStatsDto},
+    types::responses::client_quality_response::{
+        ClientQualityResponse, ClientQualityStats, ClientVersionQualityResponse,
+        ParticipantQualityResponse,
+    },
+};
+
+/// Snapshots kept per participant, about ten minutes at the usual 5s push interval
+const MAX_SNAPSHOTS: isize = 120;
+
+const SNAPSHOT_TTL_SECS: i64 = 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientStatsRecord {
+    pub session_id: String,
+    pub user_id: String,
+    pub received_at: i64,
+    /// Missing on snapshots stored before clients declared it
+    #[serde(default)]
+    pub client: ClientMetadataDto,
+    #[serde(flatten)]
+    pub stats: ClientStatsDto,
+}
+
+/// Running sums behind the averages of `ClientQualityStats`
+#[derive(Debug, Default)]
+struct QualityTotals {
+    snapshots: i64,
+    rtt: (f64, i64),
+    jitter: (f64, i64),
+    packet_loss: (f64, i64),
+}
+
+impl QualityTotals {
+    fn add(&mut self, stats: &ClientStatsDto) {
+        self.snapshots += 1;
+
+        for (total, value) in [
+            (&mut self.rtt, stats.rtt_ms),
+            (&mut self.jitter, stats.jitter_ms),
+            (&mut self.packet_loss, stats.packet_loss),
+        ] {
+            if let Some(value) = value {
+                total.0 += value;
+                total.1 += 1;
+            }
+        }
+    }
+
+    fn merge(&mut self, other: &QualityTotals) {
+        self.snapshots += other.snapshots;
+
+        for (total, other) in [
+            (&mut self.rtt, other.rtt),
+            (&mut self.jitter, other.jitter),
+            (&mut self.packet_loss, other.packet_loss),
+        ] {
+            total.0 += other.0;
+            total.1 += other.1;
+        }
+    }
+
+    fn to_stats(&self) -> ClientQualityStats {
+        let avg = |(sum, count): (f64, i64)| (count > 0).then(|| sum / count as f64);
+
+        ClientQualityStats {
+            snapshots: self.snapshots,
+            avg_rtt_ms: avg(self.rtt),
+            avg_jitter_ms: avg(self.jitter),
+            avg_packet_loss: avg(self.packet_loss),
+        }
+    }
+}
+
+/// Stores the getStats snapshots pushed by clients in Redis, keyed by room and
+/// participant, so they can be lined up with the server-side view of a session
+#[derive(Clone)]
+pub struct ClientStatsStore {
+    conn: ClusterConnection,
+}
+
+impl fmt::Debug for ClientStatsStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientStatsStore").finish_non_exhaustive()
+    }
+}
+
+impl ClientStatsStore {
+    pub fn new(conn: ClusterConnection) -> Self {
+        Self { conn }
+    }
+
+    pub async fn push(
+        &self,
+        session_id: &str,
+        user_id: &str,
+        client: ClientMetadataDto,
+        stats: ClientStatsDto,
+    ) -> Result<(), redis::RedisError> {
+        let key = Self::key(&stats.room_id, &stats.participant_id);
+        let participants_key = Self::participants_key(&stats.room_id);
+        let participant_id = stats.participant_id.clone();
+
+        let record = ClientStatsRecord {
+            session_id: session_id.to_owned(),
+            user_id: user_id.to_owned(),
+            received_at: Utc::now().timestamp_millis(),
+            client,
+            stats,
+        };
+
+        let value = serde_json::to_string(&record).map_err(|e| {
+            redis::RedisError::from((
+                redis::ErrorKind::TypeError,
+                "serialization error",
+                e.to_string(),
+            ))
+        })?;
+
+        let mut conn = self.conn.clone();
+        let _: () = conn.lpush(&key, value).await?;
+        let _: () = conn.ltrim(&key, 0, MAX_SNAPSHOTS - 1).await?;
+        let _: () = conn.expire(&key, SNAPSHOT_TTL_SECS).await?;
+        let _: () = conn.sadd(&participants_key, participant_id).await?;
+        let _: () = conn.expire(&participants_key, SNAPSHOT_TTL_SECS).await?;
+
+        Ok(())
+    }
+
+    /// Return the latest snapshots of a participant, newest first
+    pub async fn get_recent(
+        &self,
+        room_id: &str,
+        participant_id: &str,
+        count: isize,
+    ) -> Result<Vec<ClientStatsRecord>, redis::RedisError> {
+        let mut conn = self.conn.clone();
+        let values: Vec<String> = conn
+            .lrange(Self::key(room_id, participant_id), 0, count - 1)
+            .await?;
+
+        Ok(values
+            .iter()
+            .filter_map(|value| serde_json::from_str(value).ok())
+            .collect())
+    }
+
+    /// The newest snapshot of every participant of the room that reported
+    /// since `since`, in milliseconds
+    pub async fn get_latest(
+        &self,
+        room_id: &str,
+        since: i64,
+    ) -> Result<Vec<ClientStatsRecord>, redis::RedisError> {
+        let mut conn = self.conn.clone();
+        let participant_ids: Vec<String> = conn.smembers(Self::participants_key(room_id)).await?;
+
+        let mut latest = Vec::with_capacity(participant_ids.len());
+        for participant_id in participant_ids {
+            let records = self.get_recent(room_id, &participant_id, 1).await?;
+            latest.extend(
+                records
+                    .into_iter()
+                    .filter(|record| record.received_at >= since),
+            );
+        }
+
+        Ok(latest)
+    }
+
+    /// Call quality of the room's participants over their retained snapshots,
+    /// optionally only those on one app version
+    pub async fn get_room_quality(
+        &self,
+        room_id: &str,
+        client_version: Option<&str>,
+    ) -> Result<ClientQualityResponse, redis::RedisError> {
+        let mut conn = self.conn.clone();
+
+        let mut participant_ids: Vec<String> =
+            conn.smembers(Self::participants_key(room_id)).await?;
+        participant_ids.sort();
+
+        let mut versions: BTreeMap<Option<String>, (i64, QualityTotals)> = BTreeMap::new();
+        let mut participants = Vec::with_capacity(participant_ids.len());
+
+        for participant_id in participant_ids {
+            let records = self
+                .get_recent(room_id, &participant_id, MAX_SNAPSHOTS)
+                .await?;
+
+            // The newest snapshot carries what the client declared last
+            let Some(latest) = records.first() else {
+                continue;
+            };
+            if client_version.is_some() && latest.client.client_version.as_deref() != client_version
+            {
+                continue;
+            }
+
+            let mut totals = QualityTotals::default();
+            for record in &records {
+                totals.add(&record.stats);
+            }
+
+            let version = versions
+                .entry(latest.client.client_version.clone())
+                .or_default();
+            version.0 += 1;
+            version.1.merge(&totals);
+
+            participants.push(ParticipantQualityResponse {
+                participant_id,
+                user_id: latest.user_id.clone(),
+                client: latest.client.clone(),
+                last_received_at: latest.received_at,
+                stats: totals.to_stats(),
+            });
+        }
+
+        Ok(ClientQualityResponse {
+            room_id: room_id.to_owned(),
+            versions: versions
+                .into_iter()
+                .map(
+                    |(client_version, (participants, totals))| ClientVersionQualityResponse {
+                        client_version,
+                        participants,
+                        stats: totals.to_stats(),
+                    },
+                )
+                .collect(),
+            participants,
+        })
+    }
+
+    fn key(room_id: &str, participant_id: &str) -> String {
+        format!("client_stats:{room_id}:{participant_id}")
+    }
+
+    fn participants_key(room_id: &str) -> String {
+        format!("client_stats:{room_id}:participants")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_record_keeps_stats_at_top_level() {
+        let stats: ClientStatsDto = serde_json::from_value(json!({
+            "roomId": "room",
+            "participantId": "p1",
+            "timestamp": 1_000,
+            "rttMs": 120.0,
+            "jitterMs": null,
+            "packetLoss": 0.02,
+            "availableOutgoingBitrate": null,
+        }))
+        .unwrap();
+        assert!(stats.tracks.is_empty());
+
+        let record = ClientStatsRecord {
+            session_id: "sid".to_string(),
+            user_id: "7".to_string(),
+            received_at: 2_000,
+            client: ClientMetadataDto::default(),
+            stats,
+        };
+
+        let value = serde_json::to_value(&record).unwrap();
+        assert_eq!(value["sessionId"], "sid");
+        assert_eq!(value["roomId"], "room");
+        assert_eq!(value["participantId"], "p1");
+        assert_eq!(value["rttMs"], 120.0);
+
+        let stored: ClientStatsRecord = serde_json::from_value(value).unwrap();
+        assert_eq!(stored.received_at, 2_000);
+        assert_eq!(stored.stats.participant_id, "p1");
+        assert_eq!(stored.stats.packet_loss, Some(0.02));
+    }
+
+    fn stats(rtt_ms: Option<f64>, packet_loss: Option<f64>) -> ClientStatsDto {
+        serde_json::from_value(json!({
+            "roomId": "room",
+            "participantId": "p1",
+            "timestamp": 1_000,
+            "rttMs": rtt_ms,
+            "jitterMs": null,
+            "packetLoss": packet_loss,
+            "availableOutgoingBitrate": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_record_without_client_metadata() {
+        let record: ClientStatsRecord = serde_json::from_value(json!({
+            "sessionId": "sid",
+            "userId": "7",
+            "receivedAt": 2_000,
+            "roomId": "room",
+            "participantId": "p1",
+            "timestamp": 1_000,
+        }))
+        .unwrap();
+
+        assert_eq!(record.client, ClientMetadataDto::default());
+        assert_eq!(record.stats.participant_id, "p1");
+    }
+
+    #[test]
+    fn test_averages_skip_missing_values() {
+        let mut totals = QualityTotals::default();
+        totals.add(&stats(Some(100.0), None));
+        totals.add(&stats(Some(200.0), Some(0.1)));
+        totals.add(&stats(None, None));
+
+        let stats = totals.to_stats();
+        assert_eq!(stats.snapshots, 3);
+        assert_eq!(stats.avg_rtt_ms, Some(150.0));
+        assert_eq!(stats.avg_jitter_ms, None);
+        assert_eq!(stats.avg_packet_loss, Some(0.1));
+    }
+
+    #[test]
+    fn test_merged_totals_weigh_every_snapshot() {
+        let mut first = QualityTotals::default();
+        first.add(&stats(Some(100.0), None));
+
+        let mut second = QualityTotals::default();
+        second.add(&stats(Some(400.0), None));
+        second.add(&stats(Some(400.0), None));
+
+        let mut version = QualityTotals::default();
+        version.merge(&first);
+        version.merge(&second);
+
+        let stats = version.to_stats();
+        assert_eq!(stats.snapshots, 3);
+        assert_eq!(stats.avg_rtt_ms, Some(300.0));
+    }
+
+    #[test]
+    fn test_keys_are_scoped_to_the_room() {
+        assert_eq!(ClientStatsStore::key("room", "p1"), "client_stats:room:p1");
+        assert_eq!(
+            ClientStatsStore::participants_key("room"),
+            "client_stats:room:participants"
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use crate::core::env::app_env::EventRate;
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Token bucket of the events one socket may send. Kept in the socket's
+/// extensions, the clones handed out by them share the bucket.
+#[derive(Debug, Clone)]
+pub struct EventBudget {
+    rate: EventRate,
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+impl EventBudget {
+    pub fn new(rate: EventRate) -> Self {
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: rate.burst as f64,
+                refilled_at: Instant::now(),
+            })),
+            rate,
+        }
+    }
+
+    /// Takes one event out of the budget, false when it is used up
+    pub fn try_take(&self) -> bool {
+        self.try_take_at(Instant::now())
+    }
+
+    fn try_take_at(&self, now: Instant) -> bool {
+        let mut bucket = self.bucket.lock().unwrap();
+
+        let elapsed = now.saturating_duration_since(bucket.refilled_at);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * self.rate.per_second)
+            .min(self.rate.burst as f64);
+        bucket.refilled_at = now;
+
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+
+        bucket.tokens -= 1.0;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_burst_then_refill() {
+        let budget = EventBudget::new(EventRate {
+            per_second: 2.0,
+            burst: 3,
+        });
+        let start = Instant::now();
+
+        assert!(budget.try_take_at(start));
+        assert!(budget.try_take_at(start));
+        assert!(budget.try_take_at(start));
+        assert!(!budget.try_take_at(start));
+
+        // Half a second buys one event back at two a second
+        assert!(budget.try_take_at(start + Duration::from_millis(500)));
+        assert!(!budget.try_take_at(start + Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn test_refill_stops_at_burst() {
+        let budget = EventBudget::new(EventRate {
+            per_second: 100.0,
+            burst: 2,
+        });
+        let later = Instant::now() + Duration::from_secs(60);
+
+        assert!(budget.try_take_at(later));
+        assert!(budget.try_take_at(later));
+        assert!(!budget.try_take_at(later));
+    }
+
+    #[test]
+    fn test_clones_share_the_bucket() {
+        let budget = EventBudget::new(EventRate {
+            per_second: 0.0,
+            burst: 1,
+        });
+        let now = Instant::now();
+
+        assert!(budget.clone().try_take_at(now));
+        assert!(!budget.try_take_at(now));
+    }
+}
@@ -0,0 +1,354 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use salvo::{http::StatusCode, prelude::*};
+use tracing::warn;
+
+use crate::core::env::app_env::HlsEdgeConfigs;
+
+/// Viewers blocking on the same playlist share one origin fetch this often
+const PLAYLIST_TTL: Duration = Duration::from_millis(250);
+/// Playlists of streams nobody asked for in this long are dropped
+const PLAYLIST_RETAIN: Duration = Duration::from_secs(30);
+const BLOCKING_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The URL of a blocking reload names the segment it waits for, its answer
+/// never changes
+const BLOCKING_PLAYLIST_CACHE_CONTROL: &str = "public, max-age=6";
+const PLAYLIST_CACHE_CONTROL: &str = "public, max-age=1";
+const SEGMENT_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// Serves live HLS out of the origin bucket the SFU nodes upload to, so a
+/// signalling node in the viewer's region can stand in for the SFU's.
+/// Playlists honour LL-HLS blocking reloads, segments are fetched from the
+/// origin once and served from memory after that.
+#[derive(Clone)]
+pub struct HlsEdge {
+    client: reqwest::Client,
+    origin_url: String,
+    playlists: Arc<Mutex<HashMap<String, CachedPlaylist>>>,
+    segments: Arc<Mutex<SegmentCache>>,
+}
+
+struct CachedPlaylist {
+    fetched_at: Instant,
+    body: Bytes,
+}
+
+impl HlsEdge {
+    pub fn new(configs: HlsEdgeConfigs) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            origin_url: configs.origin_url,
+            playlists: Arc::new(Mutex::new(HashMap::new())),
+            segments: Arc::new(Mutex::new(SegmentCache::new(configs.cache_bytes))),
+        }
+    }
+
+    /// Mounted under `hls/{token}/{*path}`, behind `JwtUtils::hls_middleware`
+    pub fn handler(&self) -> impl Handler {
+        #[handler]
+        async fn serve(req: &mut Request, depot: &mut Depot, res: &mut Response) {
+            let hls_edge = depot.obtain::<HlsEdge>().unwrap().clone();
+
+            let path = req.param::<String>("path").unwrap_or_default();
+            if path.is_empty() || path.split('/').any(|part| part.is_empty() || part == "..") {
+                res.status_code(StatusCode::NOT_FOUND);
+                return;
+            }
+
+            if path.ends_with(".m3u8") {
+                let msn = blocking_msn(
+                    req.query::<String>("_HLS_msn").as_deref(),
+                    req.query::<String>("_HLS_part").as_deref(),
+                );
+
+                match msn {
+                    Ok(msn) => hls_edge._serve_playlist(&path, msn, res).await,
+                    Err(()) => {
+                        res.status_code(StatusCode::BAD_REQUEST);
+                    }
+                }
+            } else {
+                hls_edge._serve_segment(&path, res).await;
+            }
+        }
+        serve
+    }
+
+    async fn _serve_playlist(&self, path: &str, msn: Option<u64>, res: &mut Response) {
+        let started_at = Instant::now();
+
+        loop {
+            let playlist = match self._fetch_playlist(path).await {
+                Ok(Some(playlist)) => playlist,
+                Ok(None) => {
+                    res.status_code(StatusCode::NOT_FOUND);
+                    return;
+                }
+                Err(err) => {
+                    warn!("Failed to fetch playlist {} from origin: {:?}", path, err);
+                    res.status_code(StatusCode::BAD_GATEWAY);
+                    return;
+                }
+            };
+
+            let Some(msn) = msn else {
+                return _render(res, path, playlist, PLAYLIST_CACHE_CONTROL);
+            };
+
+            let position = playlist_position(&playlist);
+            match position.last_msn {
+                Some(last_msn) if last_msn >= msn => {
+                    return _render(res, path, playlist, BLOCKING_PLAYLIST_CACHE_CONTROL);
+                }
+                // The spec has the server refuse to wait on more than two segments
+                Some(last_msn) if msn > last_msn + 2 => {
+                    res.status_code(StatusCode::BAD_REQUEST);
+                    return;
+                }
+                _ => {}
+            }
+
+            // Three target durations, after that the segment isn't coming
+            if started_at.elapsed() >= Duration::from_secs(3 * position.target_duration.max(1)) {
+                res.status_code(StatusCode::SERVICE_UNAVAILABLE);
+                return;
+            }
+
+            tokio::time::sleep(BLOCKING_POLL_INTERVAL).await;
+        }
+    }
+
+    async fn _serve_segment(&self, path: &str, res: &mut Response) {
+        let cached = self.segments.lock().unwrap().get(path);
+        if let Some(segment) = cached {
+            return _render(res, path, segment, SEGMENT_CACHE_CONTROL);
+        }
+
+        match self._fetch(path).await {
+            Ok(Some(segment)) => {
+                self.segments
+                    .lock()
+                    .unwrap()
+                    .insert(path.to_string(), segment.clone());
+
+                _render(res, path, segment, SEGMENT_CACHE_CONTROL);
+            }
+            Ok(None) => {
+                res.status_code(StatusCode::NOT_FOUND);
+            }
+            Err(err) => {
+                warn!("Failed to fetch segment {} from origin: {:?}", path, err);
+                res.status_code(StatusCode::BAD_GATEWAY);
+            }
+        }
+    }
+
+    async fn _fetch_playlist(&self, path: &str) -> Result<Option<Bytes>, reqwest::Error> {
+        {
+            let playlists = self.playlists.lock().unwrap();
+            if let Some(playlist) = playlists.get(path)
+                && playlist.fetched_at.elapsed() < PLAYLIST_TTL
+            {
+                return Ok(Some(playlist.body.clone()));
+            }
+        }
+
+        let Some(body) = self._fetch(path).await? else {
+            return Ok(None);
+        };
+
+        let mut playlists = self.playlists.lock().unwrap();
+        playlists.retain(|_, playlist| playlist.fetched_at.elapsed() < PLAYLIST_RETAIN);
+        playlists.insert(
+            path.to_string(),
+            CachedPlaylist {
+                fetched_at: Instant::now(),
+                body: body.clone(),
+            },
+        );
+
+        Ok(Some(body))
+    }
+
+    /// `None` when the origin doesn't have the object (yet)
+    async fn _fetch(&self, path: &str) -> Result<Option<Bytes>, reqwest::Error> {
+        let response = self
+            .client
+            .get(format!("{}/{path}", self.origin_url))
+            .send()
+            .await?;
+
+        if matches!(
+            response.status(),
+            reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::FORBIDDEN
+        ) {
+            return Ok(None);
+        }
+
+        Ok(Some(response.error_for_status()?.bytes().await?))
+    }
+}
+
+fn _render(res: &mut Response, path: &str, body: Bytes, cache_control: &'static str) {
+    let content_type = if path.ends_with(".m3u8") {
+        "application/vnd.apple.mpegurl"
+    } else if path.ends_with(".cmfa") {
+        "audio/mp4"
+    } else {
+        "video/mp4"
+    };
+
+    let _ = res.add_header("Content-Type", content_type, true);
+    let _ = res.add_header("Cache-Control", cache_control, true);
+    let _ = res.write_body(body);
+}
+
+/// Where a media playlist ends, as far as a blocking reload cares
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlaylistPosition {
+    pub target_duration: u64,
+    /// Media sequence number of the last segment, `None` while there are none
+    pub last_msn: Option<u64>,
+}
+
+pub fn playlist_position(playlist: &[u8]) -> PlaylistPosition {
+    let playlist = String::from_utf8_lossy(playlist);
+
+    let mut target_duration = 1;
+    let mut media_sequence = 0;
+    let mut segments = 0;
+
+    for line in playlist.lines().map(str::trim) {
+        if let Some(value) = line.strip_prefix("#EXT-X-TARGETDURATION:") {
+            target_duration = value.parse().unwrap_or(target_duration);
+        } else if let Some(value) = line.strip_prefix("#EXT-X-MEDIA-SEQUENCE:") {
+            media_sequence = value.parse().unwrap_or(media_sequence);
+        } else if !line.is_empty() && !line.starts_with('#') {
+            segments += 1;
+        }
+    }
+
+    PlaylistPosition {
+        target_duration,
+        last_msn: (segments > 0).then(|| media_sequence + segments - 1),
+    }
+}
+
+/// The segment a playlist request waits for, `Err` for a request the spec
+/// has us reject. `_HLS_part` alone is one of those, and since the playlists
+/// carry whole segments only, a part is there once its segment is.
+pub fn blocking_msn(msn: Option<&str>, part: Option<&str>) -> Result<Option<u64>, ()> {
+    let msn = msn
+        .map(|msn| msn.parse::<u64>().map_err(|_| ()))
+        .transpose()?;
+
+    if let Some(part) = part {
+        if msn.is_none() {
+            return Err(());
+        }
+        part.parse::<u64>().map_err(|_| ())?;
+    }
+
+    Ok(msn)
+}
+
+/// Segments by path, the oldest go first once the cache is over its size
+struct SegmentCache {
+    max_bytes: usize,
+    bytes: usize,
+    entries: HashMap<String, Bytes>,
+    order: VecDeque<String>,
+}
+
+impl SegmentCache {
+    fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            bytes: 0,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn get(&self, path: &str) -> Option<Bytes> {
+        self.entries.get(path).cloned()
+    }
+
+    fn insert(&mut self, path: String, segment: Bytes) {
+        if segment.len() > self.max_bytes || self.entries.contains_key(&path) {
+            return;
+        }
+
+        self.bytes += segment.len();
+        self.entries.insert(path.clone(), segment);
+        self.order.push_back(path);
+
+        while self.bytes > self.max_bytes {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            if let Some(evicted) = self.entries.remove(&oldest) {
+                self.bytes -= evicted.len();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_playlist_position() {
+        let playlist = b"#EXTM3U\n\
+            #EXT-X-VERSION:7\n\
+            #EXT-X-TARGETDURATION:2\n\
+            #EXT-X-MEDIA-SEQUENCE:12\n\
+            #EXT-X-MAP:URI=\"init.cmfi\"\n\
+            #EXTINF:1.9,\n\
+            segment_12.cmfv\n\
+            #EXTINF:2,\n\
+            segment_13.cmfv\n";
+
+        assert_eq!(
+            playlist_position(playlist),
+            PlaylistPosition {
+                target_duration: 2,
+                last_msn: Some(13),
+            }
+        );
+        assert_eq!(playlist_position(b"#EXTM3U\n").last_msn, None);
+    }
+
+    #[test]
+    fn test_blocking_msn() {
+        assert_eq!(blocking_msn(None, None), Ok(None));
+        assert_eq!(blocking_msn(Some("14"), None), Ok(Some(14)));
+        assert_eq!(blocking_msn(Some("14"), Some("2")), Ok(Some(14)));
+        assert!(blocking_msn(None, Some("2")).is_err());
+        assert!(blocking_msn(Some("next"), None).is_err());
+    }
+
+    #[test]
+    fn test_segment_cache_evicts_oldest() {
+        let mut cache = SegmentCache::new(10);
+
+        cache.insert("a".to_string(), Bytes::from_static(b"12345"));
+        cache.insert("b".to_string(), Bytes::from_static(b"12345"));
+        cache.insert("c".to_string(), Bytes::from_static(b"123"));
+
+        assert!(cache.get("a").is_none());
+        assert!(cache.get("b").is_some());
+        assert!(cache.get("c").is_some());
+
+        // Bigger than the whole cache, served but never kept
+        cache.insert("d".to_string(), Bytes::from_static(b"12345678901"));
+        assert!(cache.get("d").is_none());
+    }
+}
@@ -0,0 +1,233 @@
+use std::fmt;
+
+use socketioxide_redis::drivers::redis::redis_client::{
+    self as redis, AsyncCommands, cluster_async::ClusterConnection,
+};
+
+use super::jwt_utils::HLS_TOKEN_DURATION;
+
+/// Every room's sessions by when their URLs expire, so renewals don't scan rooms
+const EXPIRY_KEY: &str = "hls_session_expiry";
+
+/// Sessions up for renewal taken by one node per pass, the rest wait for the next
+const CLAIM_BATCH_SIZE: isize = 100;
+
+/// Someone watching a room over HLS, with the signed URLs handed to them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HlsSession {
+    pub room_id: String,
+    pub user_id: String,
+    /// What the URLs were signed for, the participant id of a subscriber
+    /// moved off WebRTC or the viewer id of a joiner of a full call
+    pub viewer_id: String,
+}
+
+/// The HLS sessions handed out by the signalling nodes, so any of them can
+/// renew the signed URLs before they expire, send new ones when the call
+/// changes and turn away viewers removed from the room. Shared through Redis.
+#[derive(Clone)]
+pub struct HlsSessionStore {
+    conn: ClusterConnection,
+}
+
+impl fmt::Debug for HlsSessionStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HlsSessionStore").finish_non_exhaustive()
+    }
+}
+
+impl HlsSessionStore {
+    pub fn new(conn: ClusterConnection) -> Self {
+        Self { conn }
+    }
+
+    /// Opening a session again replaces it, and lifts a revocation of the
+    /// same viewer id since the user was let back in
+    pub async fn open(
+        &self,
+        session: &HlsSession,
+        expires_at: i64,
+    ) -> Result<(), redis::RedisError> {
+        let sessions_key = Self::sessions_key(&session.room_id);
+
+        let mut conn = self.conn.clone();
+        let _: () = conn
+            .hset(&sessions_key, &session.user_id, &session.viewer_id)
+            .await?;
+        let _: () = conn
+            .expire(&sessions_key, HLS_TOKEN_DURATION.whole_seconds())
+            .await?;
+        let _: () = conn
+            .srem(Self::revoked_key(&session.room_id), &session.viewer_id)
+            .await?;
+
+        self.renew(session, expires_at).await
+    }
+
+    /// Records the expiry of the URLs just sent to the session
+    pub async fn renew(
+        &self,
+        session: &HlsSession,
+        expires_at: i64,
+    ) -> Result<(), redis::RedisError> {
+        let sessions_key = Self::sessions_key(&session.room_id);
+
+        let mut conn = self.conn.clone();
+        let _: () = conn
+            .zadd(
+                EXPIRY_KEY,
+                Self::expiry_member(&session.room_id, &session.user_id),
+                expires_at,
+            )
+            .await?;
+        let _: () = conn
+            .expire(&sessions_key, HLS_TOKEN_DURATION.whole_seconds())
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn sessions(&self, room_id: &str) -> Result<Vec<HlsSession>, redis::RedisError> {
+        let mut conn = self.conn.clone();
+        let sessions: Vec<(String, String)> = conn.hgetall(Self::sessions_key(room_id)).await?;
+
+        Ok(sessions
+            .into_iter()
+            .map(|(user_id, viewer_id)| HlsSession {
+                room_id: room_id.to_string(),
+                user_id,
+                viewer_id,
+            })
+            .collect())
+    }
+
+    /// Sessions whose URLs expire before `before`, with their expiry. Each is
+    /// handed to one node only, which renews it once new URLs are sent.
+    pub async fn claim_expiring(
+        &self,
+        before: i64,
+    ) -> Result<Vec<(HlsSession, i64)>, redis::RedisError> {
+        let mut conn = self.conn.clone();
+        let due: Vec<(String, i64)> = conn
+            .zrangebyscore_limit_withscores(EXPIRY_KEY, "-inf", before, 0, CLAIM_BATCH_SIZE)
+            .await?;
+
+        let mut claimed = Vec::with_capacity(due.len());
+        for (member, expires_at) in due {
+            // Another node got there first
+            let removed: i64 = conn.zrem(EXPIRY_KEY, &member).await?;
+            if removed == 0 {
+                continue;
+            }
+
+            let Some((room_id, user_id)) = Self::parse_expiry_member(&member) else {
+                continue;
+            };
+
+            let viewer_id: Option<String> = conn.hget(Self::sessions_key(room_id), user_id).await?;
+            if let Some(viewer_id) = viewer_id {
+                claimed.push((
+                    HlsSession {
+                        room_id: room_id.to_string(),
+                        user_id: user_id.to_string(),
+                        viewer_id,
+                    },
+                    expires_at,
+                ));
+            }
+        }
+
+        Ok(claimed)
+    }
+
+    /// The URLs already handed out keep working until they expire
+    pub async fn close(&self, room_id: &str, user_id: &str) -> Result<(), redis::RedisError> {
+        let mut conn = self.conn.clone();
+        let _: () = conn.hdel(Self::sessions_key(room_id), user_id).await?;
+        let _: () = conn
+            .zrem(EXPIRY_KEY, Self::expiry_member(room_id, user_id))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Closes the session and turns away its URLs for as long as they'd have
+    /// worked. `None` when the user had no session in the room.
+    pub async fn revoke(
+        &self,
+        room_id: &str,
+        user_id: &str,
+    ) -> Result<Option<HlsSession>, redis::RedisError> {
+        let mut conn = self.conn.clone();
+        let viewer_id: Option<String> = conn.hget(Self::sessions_key(room_id), user_id).await?;
+
+        let Some(viewer_id) = viewer_id else {
+            return Ok(None);
+        };
+
+        let revoked_key = Self::revoked_key(room_id);
+        let _: () = conn.sadd(&revoked_key, &viewer_id).await?;
+        let _: () = conn
+            .expire(&revoked_key, HLS_TOKEN_DURATION.whole_seconds())
+            .await?;
+
+        self.close(room_id, user_id).await?;
+
+        Ok(Some(HlsSession {
+            room_id: room_id.to_string(),
+            user_id: user_id.to_string(),
+            viewer_id,
+        }))
+    }
+
+    pub async fn is_revoked(
+        &self,
+        room_id: &str,
+        viewer_id: &str,
+    ) -> Result<bool, redis::RedisError> {
+        let mut conn = self.conn.clone();
+        conn.sismember(Self::revoked_key(room_id), viewer_id).await
+    }
+
+    fn sessions_key(room_id: &str) -> String {
+        format!("hls_sessions:{room_id}")
+    }
+
+    fn revoked_key(room_id: &str) -> String {
+        format!("hls_revoked:{room_id}")
+    }
+
+    fn expiry_member(room_id: &str, user_id: &str) -> String {
+        format!("{room_id}/{user_id}")
+    }
+
+    fn parse_expiry_member(member: &str) -> Option<(&str, &str)> {
+        member.split_once('/')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expiry_member_round_trip() {
+        let member = HlsSessionStore::expiry_member("R0000000000000000000000001", "7");
+
+        assert_eq!(
+            HlsSessionStore::parse_expiry_member(&member),
+            Some(("R0000000000000000000000001", "7"))
+        );
+    }
+
+    #[test]
+    fn test_malformed_expiry_member_is_skipped() {
+        assert_eq!(HlsSessionStore::parse_expiry_member("room"), None);
+    }
+
+    #[test]
+    fn test_keys_are_scoped_to_the_room() {
+        assert_eq!(HlsSessionStore::sessions_key("room"), "hls_sessions:room");
+        assert_eq!(HlsSessionStore::revoked_key("room"), "hls_revoked:room");
+    }
+}
@@ -0,0 +1,255 @@
+use std::{collections::HashMap, fmt};
+
+use socketioxide_redis::drivers::redis::redis_client::{
+    self as redis, AsyncCommands, cluster_async::ClusterConnection,
+};
+
+use crate::core::{
+    dtos::analytics::hls_telemetry_dto::HlsTelemetryDto,
+    types::responses::hls_telemetry_response::{
+        HlsPlaybackStats, HlsSessionTelemetryResponse, HlsTelemetryResponse,
+    },
+};
+
+const TELEMETRY_TTL_SECS: i64 = 24 * 60 * 60;
+
+/// Sessions listed in a room summary, the room totals still cover all of them
+const MAX_SESSIONS: usize = 200;
+
+const MAX_LABEL_LEN: usize = 64;
+
+const RENDITION_PREFIX: &str = "rendition:";
+
+/// Aggregates the playback beacons of HLS players in Redis, per room and per
+/// playback session, next to what `ClientStatsStore` keeps for WebRTC clients
+#[derive(Clone)]
+pub struct HlsTelemetryStore {
+    conn: ClusterConnection,
+}
+
+impl fmt::Debug for HlsTelemetryStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HlsTelemetryStore").finish_non_exhaustive()
+    }
+}
+
+impl HlsTelemetryStore {
+    pub fn new(conn: ClusterConnection) -> Self {
+        Self { conn }
+    }
+
+    pub async fn record(
+        &self,
+        room_id: &str,
+        viewer_id: &str,
+        participant_id: &str,
+        beacon: HlsTelemetryDto,
+    ) -> Result<(), redis::RedisError> {
+        let session_id = Self::clamp(&beacon.session_id);
+        let room_key = Self::room_key(room_id);
+        let session_key = Self::session_key(room_id, &session_id);
+        let sessions_key = Self::sessions_key(room_id);
+
+        let counters = Self::counters(&beacon);
+
+        let mut conn = self.conn.clone();
+
+        for key in [&room_key, &session_key] {
+            for (field, delta) in &counters {
+                if *delta != 0 {
+                    let _: () = conn.hincr(key, field, *delta).await?;
+                }
+            }
+            let _: () = conn.expire(key, TELEMETRY_TTL_SECS).await?;
+        }
+
+        let mut session_fields = vec![
+            ("viewer_id", viewer_id.to_owned()),
+            ("participant_id", participant_id.to_owned()),
+        ];
+        if let Some(rendition) = &beacon.rendition {
+            session_fields.push(("last_rendition", Self::clamp(rendition)));
+        }
+        let _: () = conn.hset_multiple(&session_key, &session_fields).await?;
+
+        let _: () = conn.sadd(&sessions_key, &session_id).await?;
+        let _: () = conn.expire(&sessions_key, TELEMETRY_TTL_SECS).await?;
+
+        Ok(())
+    }
+
+    pub async fn get_room_summary(
+        &self,
+        room_id: &str,
+    ) -> Result<HlsTelemetryResponse, redis::RedisError> {
+        let mut conn = self.conn.clone();
+
+        let room: HashMap<String, String> = conn.hgetall(Self::room_key(room_id)).await?;
+        let mut session_ids: Vec<String> = conn.smembers(Self::sessions_key(room_id)).await?;
+        session_ids.sort();
+        session_ids.truncate(MAX_SESSIONS);
+
+        let mut sessions = Vec::with_capacity(session_ids.len());
+        for session_id in session_ids {
+            let mut fields: HashMap<String, String> = conn
+                .hgetall(Self::session_key(room_id, &session_id))
+                .await?;
+
+            // The session hash expired before the set did
+            if fields.is_empty() {
+                continue;
+            }
+
+            sessions.push(HlsSessionTelemetryResponse {
+                session_id,
+                viewer_id: fields.remove("viewer_id"),
+                participant_id: fields.remove("participant_id"),
+                last_rendition: fields.remove("last_rendition"),
+                stats: Self::to_stats(&fields),
+            });
+        }
+
+        Ok(HlsTelemetryResponse {
+            room_id: room_id.to_owned(),
+            stats: Self::to_stats(&room),
+            sessions,
+        })
+    }
+
+    /// Hash increments for one beacon, the same for the room and the session
+    fn counters(beacon: &HlsTelemetryDto) -> Vec<(String, i64)> {
+        let mut counters: Vec<(String, i64)> = vec![
+            ("rebuffer_count".into(), beacon.rebuffer_count as i64),
+            ("rebuffer_ms".into(), beacon.rebuffer_ms as i64),
+            ("watched_ms".into(), beacon.watched_ms as i64),
+        ];
+        if let Some(startup_ms) = beacon.startup_ms {
+            counters.push(("startups".into(), 1));
+            counters.push(("startup_ms".into(), startup_ms as i64));
+        }
+        if let Some(rendition) = &beacon.rendition {
+            counters.push((format!("{RENDITION_PREFIX}{}", Self::clamp(rendition)), 1));
+        }
+
+        counters
+    }
+
+    fn to_stats(fields: &HashMap<String, String>) -> HlsPlaybackStats {
+        let get = |field: &str| {
+            fields
+                .get(field)
+                .and_then(|value| value.parse::<i64>().ok())
+                .unwrap_or(0)
+        };
+
+        let startups = get("startups");
+        let rebuffer_ms = get("rebuffer_ms");
+        let watched_ms = get("watched_ms");
+
+        HlsPlaybackStats {
+            startups,
+            avg_startup_ms: (startups > 0).then(|| get("startup_ms") / startups),
+            rebuffer_count: get("rebuffer_count"),
+            rebuffer_ms,
+            watched_ms,
+            rebuffer_ratio: if watched_ms + rebuffer_ms > 0 {
+                rebuffer_ms as f64 / (watched_ms + rebuffer_ms) as f64
+            } else {
+                0.0
+            },
+            renditions: fields
+                .iter()
+                .filter_map(|(field, value)| {
+                    let rendition = field.strip_prefix(RENDITION_PREFIX)?;
+                    Some((rendition.to_owned(), value.parse().ok()?))
+                })
+                .collect(),
+        }
+    }
+
+    /// Session ids and rendition names come straight from players
+    fn clamp(value: &str) -> String {
+        value.chars().take(MAX_LABEL_LEN).collect()
+    }
+
+    fn room_key(room_id: &str) -> String {
+        format!("hls_telemetry:{room_id}")
+    }
+
+    fn sessions_key(room_id: &str) -> String {
+        format!("hls_telemetry:{room_id}:sessions")
+    }
+
+    fn session_key(room_id: &str, session_id: &str) -> String {
+        format!("hls_telemetry:{room_id}:session:{session_id}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn beacon(startup_ms: Option<u64>, rendition: Option<&str>) -> HlsTelemetryDto {
+        HlsTelemetryDto {
+            session_id: "session".to_string(),
+            startup_ms,
+            rebuffer_count: 1,
+            rebuffer_ms: 500,
+            watched_ms: 9_500,
+            rendition: rendition.map(str::to_string),
+        }
+    }
+
+    /// What the counters of the beacons add up to in a hash
+    fn fold(beacons: &[HlsTelemetryDto]) -> HashMap<String, String> {
+        let mut totals: HashMap<String, i64> = HashMap::new();
+        for beacon in beacons {
+            for (field, delta) in HlsTelemetryStore::counters(beacon) {
+                *totals.entry(field).or_default() += delta;
+            }
+        }
+
+        totals
+            .into_iter()
+            .map(|(field, total)| (field, total.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_startup_is_counted_once_per_session() {
+        let stats = HlsTelemetryStore::to_stats(&fold(&[
+            beacon(Some(1_800), Some("720p")),
+            beacon(None, Some("720p")),
+            beacon(None, Some("480p")),
+        ]));
+
+        assert_eq!(stats.startups, 1);
+        assert_eq!(stats.avg_startup_ms, Some(1_800));
+        assert_eq!(stats.rebuffer_count, 3);
+        assert_eq!(stats.rebuffer_ms, 1_500);
+        assert_eq!(stats.watched_ms, 28_500);
+        assert_eq!(stats.rebuffer_ratio, 0.05);
+        assert_eq!(stats.renditions["720p"], 2);
+        assert_eq!(stats.renditions["480p"], 1);
+    }
+
+    #[test]
+    fn test_empty_hash_has_no_averages() {
+        let stats = HlsTelemetryStore::to_stats(&HashMap::new());
+
+        assert_eq!(stats.startups, 0);
+        assert_eq!(stats.avg_startup_ms, None);
+        assert_eq!(stats.rebuffer_ratio, 0.0);
+        assert!(stats.renditions.is_empty());
+    }
+
+    #[test]
+    fn test_rendition_names_are_clamped() {
+        let rendition = "x".repeat(MAX_LABEL_LEN * 2);
+
+        let counters = HlsTelemetryStore::counters(&beacon(None, Some(&rendition)));
+
+        let (field, _) = counters.last().unwrap();
+        assert_eq!(field.len(), RENDITION_PREFIX.len() + MAX_LABEL_LEN);
+    }
+}
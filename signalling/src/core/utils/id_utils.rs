@@ -24,6 +24,59 @@ pub fn generate_room_code() -> String {
     )
 }
 
+/// No underscores, the SFU splits its subscriber keys on them
+pub fn generate_observer_id() -> String {
+    let suffix: String = rng()
+        .sample_iter(Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect();
+
+    format!("observer-{suffix}")
+}
+
+/// Names an overflow viewer in their HLS URLs, same alphabet as observers
+pub fn generate_viewer_id() -> String {
+    let suffix: String = rng()
+        .sample_iter(Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect();
+
+    format!("viewer-{suffix}")
+}
+
+/// Tells apart tokens otherwise minted with the same claims
+pub fn generate_token_id() -> String {
+    nanoid!()
+}
+
+/// Handed to a calendar provider, which sends it back with every push
+pub fn generate_channel_token() -> String {
+    nanoid!(32)
+}
+
 pub fn generate_username() -> String {
     nanoid!(12)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observer_id_has_no_underscores() {
+        for _ in 0..100 {
+            let observer_id = generate_observer_id();
+
+            assert!(observer_id.starts_with("observer-"));
+            assert_eq!(observer_id.len(), "observer-".len() + 16);
+            assert!(!observer_id.contains('_'));
+        }
+    }
+
+    #[test]
+    fn test_observer_ids_are_told_apart() {
+        assert_ne!(generate_observer_id(), generate_observer_id());
+    }
+}
@@ -0,0 +1,45 @@
+use std::fmt;
+
+use socketioxide_redis::drivers::redis::redis_client::{
+    self as redis, cluster_async::ClusterConnection,
+};
+
+use super::jwt_utils::JOIN_TOKEN_DURATION;
+
+/// Outlives the token by the leeway its expiry is checked with
+const REDEEMED_TTL_SECS: i64 = JOIN_TOKEN_DURATION.whole_seconds() + 60;
+
+/// Join tokens already published with, so one can't be replayed by whoever
+/// got hold of it. Shared through Redis, the publish may land on any node.
+#[derive(Clone)]
+pub struct JoinTokenStore {
+    conn: ClusterConnection,
+}
+
+impl fmt::Debug for JoinTokenStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JoinTokenStore").finish_non_exhaustive()
+    }
+}
+
+impl JoinTokenStore {
+    pub fn new(conn: ClusterConnection) -> Self {
+        Self { conn }
+    }
+
+    /// Uses up the token, `false` when it was used before
+    pub async fn redeem(&self, jti: &str) -> Result<bool, redis::RedisError> {
+        let mut conn = self.conn.clone();
+
+        let reply: Option<String> = redis::cmd("SET")
+            .arg(format!("join_token:{jti}"))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(REDEEMED_TTL_SECS)
+            .query_async(&mut conn)
+            .await?;
+
+        Ok(reply.is_some())
+    }
+}
@@ -1,3 +1,4 @@
+use jsonwebtoken::errors::ErrorKind;
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
 use salvo::Handler;
 use salvo::prelude::*;
@@ -6,6 +7,9 @@ use time::OffsetDateTime;
 
 use crate::core::env::app_env::AppEnv;
 use crate::core::types::errors::auth_error::AuthError;
+use crate::core::utils::hls_session_store::HlsSessionStore;
+use crate::core::utils::id_utils::generate_token_id;
+use crate::core::utils::viewer_count_store::ViewerCountStore;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JwtClaims {
@@ -13,6 +17,57 @@ pub struct JwtClaims {
     pub exp: i64,
 }
 
+/// Grants playback of one participant's HLS stream
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HlsClaims {
+    pub participant_id: String,
+    pub room_id: String,
+    /// Participant the stream was signed for, counted once however many streams it plays
+    pub viewer_id: String,
+    pub exp: i64,
+}
+
+pub const HLS_TOKEN_DURATION: time::Duration = time::Duration::hours(1);
+
+/// Grants subscribe-only access to one room, without joining it as a participant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObserverClaims {
+    pub room_id: String,
+    /// Stands in for a participant id on the SFU
+    pub observer_id: String,
+    /// Who is watching and why, e.g. "QA review"
+    pub label: String,
+    pub exp: i64,
+}
+
+/// Lets a participant the REST API admitted publish in the room, so the socket
+/// doesn't take the room and participant ids on the client's word
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JoinClaims {
+    /// Public id of the room
+    pub room_id: String,
+    pub participant_id: String,
+    pub user_id: String,
+    pub is_host: bool,
+    /// Redeemed on publish, a token only gets one
+    pub jti: String,
+    pub exp: i64,
+}
+
+/// Only has to last from the REST call to the socket join right after it
+pub const JOIN_TOKEN_DURATION: time::Duration = time::Duration::minutes(2);
+
+/// OAuth `state` of a calendar connect, ties the provider's redirect back to the user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarStateClaims {
+    pub user_id: String,
+    pub provider: String,
+    pub exp: i64,
+}
+
+/// Long enough to sign in to the provider and grant access
+pub const CALENDAR_STATE_DURATION: time::Duration = time::Duration::minutes(10);
+
 #[derive(Debug, Clone)]
 pub struct JwtUtils {
     secret_key: String,
@@ -86,6 +141,209 @@ impl JwtUtils {
         Ok(token_data.claims)
     }
 
+    /// The token goes in the path rather than the query, so the segment URIs the
+    /// player resolves relative to the manifest carry it too
+    pub fn sign_hls_url(&self, room_id: &str, viewer_id: &str, participant_id: &str) -> String {
+        let claims = HlsClaims {
+            participant_id: participant_id.to_owned(),
+            room_id: room_id.to_owned(),
+            viewer_id: viewer_id.to_owned(),
+            exp: self.hls_expires_at(),
+        };
+
+        let token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(self.secret_key.as_bytes()),
+        )
+        .expect("Failed to generate hls token");
+
+        format!("/hls/{token}/{participant_id}/manifest.m3u8")
+    }
+
+    /// When HLS URLs signed now stop working, as a unix timestamp
+    pub fn hls_expires_at(&self) -> i64 {
+        (OffsetDateTime::now_utc() + HLS_TOKEN_DURATION).unix_timestamp()
+    }
+
+    pub fn decode_hls_token(&self, token: &str) -> Result<HlsClaims, jsonwebtoken::errors::Error> {
+        let token_data = decode::<HlsClaims>(
+            token,
+            &DecodingKey::from_secret(self.secret_key.as_bytes()),
+            &Validation::default(),
+        )?;
+        Ok(token_data.claims)
+    }
+
+    /// Returns the token along with its expiry, as a unix timestamp
+    pub fn generate_observer_token(
+        &self,
+        room_id: &str,
+        observer_id: &str,
+        label: &str,
+        duration: time::Duration,
+    ) -> (String, i64) {
+        let exp = (OffsetDateTime::now_utc() + duration).unix_timestamp();
+
+        let claims = ObserverClaims {
+            room_id: room_id.to_owned(),
+            observer_id: observer_id.to_owned(),
+            label: label.to_owned(),
+            exp,
+        };
+
+        let token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(self.secret_key.as_bytes()),
+        )
+        .expect("Failed to generate observer token");
+
+        (token, exp)
+    }
+
+    pub fn decode_observer_token(
+        &self,
+        token: &str,
+    ) -> Result<ObserverClaims, jsonwebtoken::errors::Error> {
+        let token_data = decode::<ObserverClaims>(
+            token,
+            &DecodingKey::from_secret(self.secret_key.as_bytes()),
+            &Validation::default(),
+        )?;
+        Ok(token_data.claims)
+    }
+
+    /// Returns the token along with its expiry, as a unix timestamp
+    pub fn generate_join_token(
+        &self,
+        room_id: &str,
+        participant_id: &str,
+        user_id: &str,
+        is_host: bool,
+    ) -> (String, i64) {
+        let exp = (OffsetDateTime::now_utc() + JOIN_TOKEN_DURATION).unix_timestamp();
+
+        let claims = JoinClaims {
+            room_id: room_id.to_owned(),
+            participant_id: participant_id.to_owned(),
+            user_id: user_id.to_owned(),
+            is_host,
+            jti: generate_token_id(),
+            exp,
+        };
+
+        let token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(self.secret_key.as_bytes()),
+        )
+        .expect("Failed to generate join token");
+
+        (token, exp)
+    }
+
+    /// Turns away tokens minted for another room than the one published in
+    pub fn decode_join_token(
+        &self,
+        token: &str,
+        room_id: &str,
+    ) -> Result<JoinClaims, jsonwebtoken::errors::Error> {
+        let token_data = decode::<JoinClaims>(
+            token,
+            &DecodingKey::from_secret(self.secret_key.as_bytes()),
+            &Validation::default(),
+        )?;
+
+        if token_data.claims.room_id != room_id {
+            return Err(ErrorKind::InvalidToken.into());
+        }
+
+        Ok(token_data.claims)
+    }
+
+    pub fn generate_calendar_state(&self, user_id: &str, provider: &str) -> String {
+        let exp = OffsetDateTime::now_utc() + CALENDAR_STATE_DURATION;
+
+        let claims = CalendarStateClaims {
+            user_id: user_id.to_owned(),
+            provider: provider.to_owned(),
+            exp: exp.unix_timestamp(),
+        };
+
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(self.secret_key.as_bytes()),
+        )
+        .expect("Failed to generate calendar state")
+    }
+
+    /// Turns away states issued for another provider than the one redirecting back
+    pub fn decode_calendar_state(
+        &self,
+        state: &str,
+        provider: &str,
+    ) -> Result<CalendarStateClaims, jsonwebtoken::errors::Error> {
+        let token_data = decode::<CalendarStateClaims>(
+            state,
+            &DecodingKey::from_secret(self.secret_key.as_bytes()),
+            &Validation::default(),
+        )?;
+
+        if token_data.claims.provider != provider {
+            return Err(ErrorKind::InvalidToken.into());
+        }
+
+        Ok(token_data.claims)
+    }
+
+    pub fn hls_middleware(&self) -> impl Handler {
+        #[handler]
+        async fn middleware(req: &mut Request, depot: &mut Depot, res: &mut Response) {
+            let token = req.param::<String>("token").unwrap_or_default();
+
+            let jwt_utils = depot.obtain::<JwtUtils>().unwrap();
+
+            let path = req.uri().path();
+            let claims = jwt_utils.decode_hls_token(&token).ok().filter(|claims| {
+                path.starts_with(&format!("/hls/{token}/{}/", claims.participant_id))
+            });
+
+            let Some(claims) = claims else {
+                res.status_code(StatusCode::UNAUTHORIZED);
+                return res.render(Json(AuthError::InvalidToken));
+            };
+
+            // Checked on playlists only, a removed viewer's player stops at the
+            // next refetch without a Redis round trip per segment
+            if path.ends_with(".m3u8")
+                && let Ok(hls_session_store) = depot.obtain::<HlsSessionStore>()
+                && hls_session_store
+                    .is_revoked(&claims.room_id, &claims.viewer_id)
+                    .await
+                    .unwrap_or(false)
+            {
+                res.status_code(StatusCode::FORBIDDEN);
+                return res.render(Json(AuthError::InvalidToken));
+            }
+
+            // Live playlists are refetched every few seconds, segments are not worth counting
+            if path.ends_with(".m3u8")
+                && let Ok(viewer_count_store) = depot.obtain::<ViewerCountStore>()
+            {
+                let viewer_count_store = viewer_count_store.clone();
+
+                tokio::spawn(async move {
+                    let _ = viewer_count_store
+                        .record_hls_request(&claims.room_id, &claims.viewer_id)
+                        .await;
+                });
+            }
+        }
+        middleware
+    }
+
     pub fn auth_middleware(&self) -> impl Handler {
         #[handler]
         async fn middleware(req: &mut Request, depot: &mut Depot, res: &mut Response) {
@@ -144,3 +402,165 @@ impl JwtUtils {
         middleware
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jwt_utils() -> JwtUtils {
+        JwtUtils {
+            secret_key: "secret".to_string(),
+            refresh_secret_key: "refresh_secret".to_string(),
+            token_duration: time::Duration::hours(1),
+            refresh_token_duration: time::Duration::hours(2),
+        }
+    }
+
+    #[test]
+    fn test_join_token_round_trip() {
+        let jwt_utils = jwt_utils();
+
+        let (token, expires_at) = jwt_utils.generate_join_token("room", "participant", "7", true);
+        let claims = jwt_utils.decode_join_token(&token, "room").unwrap();
+
+        assert_eq!(claims.room_id, "room");
+        assert_eq!(claims.participant_id, "participant");
+        assert_eq!(claims.user_id, "7");
+        assert!(claims.is_host);
+        assert_eq!(claims.exp, expires_at);
+    }
+
+    #[test]
+    fn test_join_tokens_are_told_apart() {
+        let jwt_utils = jwt_utils();
+
+        let (first, _) = jwt_utils.generate_join_token("room", "participant", "7", false);
+        let (second, _) = jwt_utils.generate_join_token("room", "participant", "7", false);
+
+        let first = jwt_utils.decode_join_token(&first, "room").unwrap();
+        let second = jwt_utils.decode_join_token(&second, "room").unwrap();
+        assert_ne!(first.jti, second.jti);
+    }
+
+    #[test]
+    fn test_expired_join_token_is_rejected() {
+        let jwt_utils = jwt_utils();
+
+        let claims = JoinClaims {
+            room_id: "room".to_string(),
+            participant_id: "participant".to_string(),
+            user_id: "7".to_string(),
+            is_host: false,
+            jti: generate_token_id(),
+            exp: (OffsetDateTime::now_utc() - time::Duration::minutes(5)).unix_timestamp(),
+        };
+        let token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(jwt_utils.secret_key.as_bytes()),
+        )
+        .unwrap();
+
+        let err = jwt_utils.decode_join_token(&token, "room").unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ExpiredSignature);
+    }
+
+    #[test]
+    fn test_join_token_of_another_room_is_rejected() {
+        let jwt_utils = jwt_utils();
+
+        let (token, _) = jwt_utils.generate_join_token("room", "participant", "7", false);
+
+        let err = jwt_utils.decode_join_token(&token, "other").unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidToken);
+    }
+
+    #[test]
+    fn test_join_token_signed_with_another_secret_is_rejected() {
+        let mut other = jwt_utils();
+        other.secret_key = "other_secret".to_string();
+
+        let (token, _) = other.generate_join_token("room", "participant", "7", false);
+
+        let err = jwt_utils().decode_join_token(&token, "room").unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidSignature);
+    }
+
+    #[test]
+    fn test_observer_token_round_trip() {
+        let jwt_utils = jwt_utils();
+
+        let (token, expires_at) = jwt_utils.generate_observer_token(
+            "room",
+            "observer-1",
+            "QA review",
+            time::Duration::minutes(30),
+        );
+        let claims = jwt_utils.decode_observer_token(&token).unwrap();
+
+        assert_eq!(claims.room_id, "room");
+        assert_eq!(claims.observer_id, "observer-1");
+        assert_eq!(claims.label, "QA review");
+        assert_eq!(claims.exp, expires_at);
+    }
+
+    #[test]
+    fn test_join_token_is_not_an_observer_token() {
+        let jwt_utils = jwt_utils();
+
+        let (token, _) = jwt_utils.generate_join_token("room", "participant", "7", true);
+
+        assert!(jwt_utils.decode_observer_token(&token).is_err());
+    }
+
+    #[test]
+    fn test_calendar_state_round_trip() {
+        let jwt_utils = jwt_utils();
+
+        let state = jwt_utils.generate_calendar_state("7", "google");
+        let claims = jwt_utils.decode_calendar_state(&state, "google").unwrap();
+
+        assert_eq!(claims.user_id, "7");
+        assert_eq!(claims.provider, "google");
+    }
+
+    #[test]
+    fn test_calendar_state_of_another_provider_is_rejected() {
+        let jwt_utils = jwt_utils();
+
+        let state = jwt_utils.generate_calendar_state("7", "google");
+
+        let err = jwt_utils
+            .decode_calendar_state(&state, "outlook")
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidToken);
+    }
+
+    #[test]
+    fn test_access_token_is_not_a_calendar_state() {
+        let jwt_utils = jwt_utils();
+
+        let token = jwt_utils.generate_token("7");
+
+        assert!(jwt_utils.decode_calendar_state(&token, "google").is_err());
+    }
+
+    #[test]
+    fn test_signed_hls_url_carries_the_token_in_the_path() {
+        let jwt_utils = jwt_utils();
+        let before = jwt_utils.hls_expires_at();
+
+        let url = jwt_utils.sign_hls_url("room", "viewer", "publisher");
+
+        let token = url
+            .strip_prefix("/hls/")
+            .and_then(|rest| rest.strip_suffix("/publisher/manifest.m3u8"))
+            .unwrap();
+        let claims = jwt_utils.decode_hls_token(token).unwrap();
+        assert_eq!(claims.room_id, "room");
+        assert_eq!(claims.viewer_id, "viewer");
+        assert_eq!(claims.participant_id, "publisher");
+        assert!(claims.exp >= before);
+        assert!(claims.exp <= jwt_utils.hls_expires_at());
+    }
+}
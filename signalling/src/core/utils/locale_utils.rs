@@ -0,0 +1,51 @@
+use chrono::{NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+
+use crate::core::entities::models::User;
+
+/// Regions that write dates month first with a 12-hour clock
+const MONTH_FIRST_REGIONS: [&str; 4] = ["US", "PH", "FM", "MH"];
+
+/// Loose BCP 47 check: a 2-3 letter language followed by 1-8 character subtags
+pub fn is_valid_locale(locale: &str) -> bool {
+    if locale.len() > 35 {
+        return false;
+    }
+
+    let mut subtags = locale.split('-');
+
+    let language_ok = subtags.next().is_some_and(|language| {
+        (2..=3).contains(&language.len()) && language.chars().all(|c| c.is_ascii_alphabetic())
+    });
+
+    language_ok
+        && subtags.all(|subtag| {
+            (1..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+}
+
+pub fn parse_timezone(timezone: &str) -> Option<Tz> {
+    timezone.parse::<Tz>().ok()
+}
+
+/// Render a UTC timestamp in the user's own timezone and date order, for text
+/// that is sent to them (system messages, reminders, notification emails)
+pub fn format_for_user(at: NaiveDateTime, user: &User) -> String {
+    format_local_time(at, &user.locale, &user.timezone)
+}
+
+pub fn format_local_time(at: NaiveDateTime, locale: &str, timezone: &str) -> String {
+    let tz = parse_timezone(timezone).unwrap_or(Tz::UTC);
+    let local = tz.from_utc_datetime(&at);
+
+    let month_first = locale
+        .split('-')
+        .skip(1)
+        .any(|subtag| MONTH_FIRST_REGIONS.contains(&subtag.to_ascii_uppercase().as_str()));
+
+    if month_first {
+        local.format("%b %-d, %Y %-I:%M %p %Z").to_string()
+    } else {
+        local.format("%Y-%m-%d %H:%M %Z").to_string()
+    }
+}
@@ -1,8 +1,23 @@
 pub mod api_key_utils;
+pub mod avatar_utils;
 pub mod aws_utils;
 pub mod bcrypt_utils;
+pub mod client_stats_store;
+pub mod event_budget;
+pub mod hls_edge;
+pub mod hls_session_store;
+pub mod hls_telemetry_store;
 pub mod id_utils;
+pub mod join_token_store;
 pub mod jwt_utils;
+pub mod locale_utils;
+pub mod overflow_queue;
+pub mod panic_guard;
+pub mod retention_progress_store;
+pub mod room_insights_store;
+pub mod room_state_cache;
+pub mod search_reindex_store;
+pub mod viewer_count_store;
 
 #[macro_use]
 pub mod try_from_i16;
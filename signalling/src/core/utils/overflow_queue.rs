@@ -0,0 +1,114 @@
+use std::fmt;
+
+use chrono::Utc;
+use socketioxide_redis::drivers::redis::redis_client::{
+    self as redis, AsyncCommands, cluster_async::ClusterConnection,
+};
+
+use super::id_utils::generate_viewer_id;
+
+/// A line nobody joined or left in this long belongs to a call that's over
+const QUEUE_TTL_SECS: i64 = 6 * 60 * 60;
+
+/// A user waiting for a WebRTC slot of a full call, watching over HLS meanwhile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueuedViewer {
+    pub user_id: i32,
+    pub viewer_id: String,
+    /// From 1, the next free slot is offered to the viewer at 1
+    pub position: i64,
+}
+
+/// Joiners of full calls in the order they arrived, so freed slots go to
+/// whoever waited longest. Shared by the signalling nodes through Redis.
+#[derive(Clone)]
+pub struct OverflowQueue {
+    conn: ClusterConnection,
+}
+
+impl fmt::Debug for OverflowQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OverflowQueue").finish_non_exhaustive()
+    }
+}
+
+impl OverflowQueue {
+    pub fn new(conn: ClusterConnection) -> Self {
+        Self { conn }
+    }
+
+    /// A user joining again keeps their place and viewer id
+    pub async fn enqueue(
+        &self,
+        room_id: &str,
+        user_id: i32,
+    ) -> Result<QueuedViewer, redis::RedisError> {
+        let queue_key = Self::queue_key(room_id);
+        let viewers_key = Self::viewers_key(room_id);
+
+        let mut conn = self.conn.clone();
+        let _: () = redis::cmd("ZADD")
+            .arg(&queue_key)
+            .arg("NX")
+            .arg(Utc::now().timestamp_millis())
+            .arg(user_id)
+            .query_async(&mut conn)
+            .await?;
+        let _: bool = conn
+            .hset_nx(&viewers_key, user_id, generate_viewer_id())
+            .await?;
+        let _: () = conn.expire(&queue_key, QUEUE_TTL_SECS).await?;
+        let _: () = conn.expire(&viewers_key, QUEUE_TTL_SECS).await?;
+
+        let viewer_id: String = conn.hget(&viewers_key, user_id).await?;
+        let rank: Option<i64> = conn.zrank(&queue_key, user_id).await?;
+
+        Ok(QueuedViewer {
+            user_id,
+            viewer_id,
+            position: rank.unwrap_or(0) + 1,
+        })
+    }
+
+    pub async fn contains(&self, room_id: &str, user_id: i32) -> Result<bool, redis::RedisError> {
+        let mut conn = self.conn.clone();
+        let score: Option<i64> = conn.zscore(Self::queue_key(room_id), user_id).await?;
+
+        Ok(score.is_some())
+    }
+
+    /// The viewer who waited longest, `None` when nobody is waiting
+    pub async fn peek(&self, room_id: &str) -> Result<Option<QueuedViewer>, redis::RedisError> {
+        let mut conn = self.conn.clone();
+        let user_ids: Vec<i32> = conn.zrange(Self::queue_key(room_id), 0, 0).await?;
+
+        let Some(user_id) = user_ids.first().copied() else {
+            return Ok(None);
+        };
+
+        let viewer_id: Option<String> = conn.hget(Self::viewers_key(room_id), user_id).await?;
+
+        Ok(viewer_id.map(|viewer_id| QueuedViewer {
+            user_id,
+            viewer_id,
+            position: 1,
+        }))
+    }
+
+    /// Once the viewer got a slot or gave up waiting
+    pub async fn remove(&self, room_id: &str, user_id: i32) -> Result<(), redis::RedisError> {
+        let mut conn = self.conn.clone();
+        let _: () = conn.zrem(Self::queue_key(room_id), user_id).await?;
+        let _: () = conn.hdel(Self::viewers_key(room_id), user_id).await?;
+
+        Ok(())
+    }
+
+    fn queue_key(room_id: &str) -> String {
+        format!("overflow_queue:{room_id}")
+    }
+
+    fn viewers_key(room_id: &str) -> String {
+        format!("overflow_viewers:{room_id}")
+    }
+}
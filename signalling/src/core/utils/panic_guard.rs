@@ -0,0 +1,336 @@
+use std::{
+    cell::RefCell,
+    fmt,
+    panic::{self, PanicHookInfo},
+    sync::Once,
+};
+
+use serde::{
+    Deserializer,
+    de::{self, DeserializeOwned, Visitor},
+    forward_to_deserialize_any,
+};
+
+static HOOK: Once = Once::new();
+
+const MAX_SUMMARY_KEYS: usize = 16;
+const MAX_SUMMARY_KEY_LEN: usize = 32;
+
+thread_local! {
+    /// Message of the panic unwinding this thread, for the guards it passes
+    static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Keeps the message of every panic for the guards it unwinds through. The
+/// previous hook still runs, panics are printed as before.
+pub fn install_hook() {
+    HOOK.call_once(|| {
+        let previous = panic::take_hook();
+
+        panic::set_hook(Box::new(move |info| {
+            LAST_PANIC.with(|last| *last.borrow_mut() = Some(_panic_message(info)));
+            previous(info);
+        }));
+    });
+}
+
+fn _panic_message(info: &PanicHookInfo<'_>) -> String {
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_owned());
+
+    match info.location() {
+        Some(location) => format!("{message} at {location}"),
+        None => message,
+    }
+}
+
+/// The call a socket is in, read when its handler starts so a panic report
+/// needs nothing but what the guard already holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallIds {
+    pub room_id: String,
+    pub participant_id: String,
+}
+
+/// The shape of what a handler was given, for its panic report. Only built
+/// once a handler panics, from the type it decodes its payload into rather
+/// than the payload itself, so the handlers that don't panic pay nothing and
+/// what a hostile client sent can't reach the log. Keys are capped in number
+/// and length all the same.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PayloadSummary {
+    /// Name of the struct the payload decodes into, or of its type when it
+    /// isn't a struct
+    pub name: &'static str,
+    /// Fields of that struct as they are named on the wire
+    pub keys: Vec<String>,
+    /// Fields past the cap, counted but not kept
+    pub more_keys: usize,
+}
+
+impl PayloadSummary {
+    /// Taken by handlers as `PayloadSummary::of::<Dto>`, a plain function
+    /// their guard calls only while unwinding
+    pub fn of<T: DeserializeOwned>() -> Self {
+        let mut fields = StructFields::default();
+        let _ = T::deserialize(&mut fields);
+
+        let Some((name, fields)) = fields.0 else {
+            return Self {
+                name: std::any::type_name::<T>(),
+                ..Self::default()
+            };
+        };
+
+        Self {
+            name,
+            keys: fields
+                .iter()
+                .take(MAX_SUMMARY_KEYS)
+                .map(|key| _truncate(key, MAX_SUMMARY_KEY_LEN))
+                .collect(),
+            more_keys: fields.len().saturating_sub(MAX_SUMMARY_KEYS),
+        }
+    }
+}
+
+impl fmt::Display for PayloadSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, keys: [{}", self.name, self.keys.join(", "))?;
+        if self.more_keys > 0 {
+            write!(f, ", +{} more", self.more_keys)?;
+        }
+        write!(f, "]")
+    }
+}
+
+fn _truncate(key: &str, max_len: usize) -> String {
+    match key.char_indices().nth(max_len) {
+        Some((end, _)) => format!("{}…", &key[..end]),
+        None => key.to_owned(),
+    }
+}
+
+/// A deserializer with no input. Derived `Deserialize` impls of structs hand
+/// it their name and fields before asking for any value, everything else
+/// fails right away.
+#[derive(Default)]
+struct StructFields(Option<(&'static str, &'static [&'static str])>);
+
+impl<'de> Deserializer<'de> for &mut StructFields {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("no input"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0 = Some((name, fields));
+        Err(de::Error::custom("no input"))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HandlerPanic {
+    pub event: &'static str,
+    pub message: String,
+    pub call: Option<CallIds>,
+    pub payload: Option<PayloadSummary>,
+}
+
+/// Held by a socket handler for as long as it runs. When the handler panics
+/// the guard is dropped while unwinding and hands the panic to `on_panic`,
+/// which runs during the unwind and must not block.
+///
+/// Handlers run in their own task, so a panic never takes the connection
+/// down, but without the guard the client waits on a reply that never comes.
+pub struct PanicGuard<F: FnOnce(HandlerPanic)> {
+    event: &'static str,
+    call: Option<CallIds>,
+    payload: Option<fn() -> PayloadSummary>,
+    on_panic: Option<F>,
+}
+
+impl<F: FnOnce(HandlerPanic)> PanicGuard<F> {
+    pub fn new(
+        event: &'static str,
+        call: Option<CallIds>,
+        payload: Option<fn() -> PayloadSummary>,
+        on_panic: F,
+    ) -> Self {
+        Self {
+            event,
+            call,
+            payload,
+            on_panic: Some(on_panic),
+        }
+    }
+}
+
+impl<F: FnOnce(HandlerPanic)> fmt::Debug for PanicGuard<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PanicGuard")
+            .field("event", &self.event)
+            .field("call", &self.call)
+            .field("payload", &self.payload.map(|summary| summary()))
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: FnOnce(HandlerPanic)> Drop for PanicGuard<F> {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            return;
+        }
+
+        let Some(on_panic) = self.on_panic.take() else {
+            return;
+        };
+
+        on_panic(HandlerPanic {
+            event: self.event,
+            message: LAST_PANIC
+                .with(|last| last.borrow_mut().take())
+                .unwrap_or_else(|| "unknown panic".to_owned()),
+            call: self.call.take(),
+            payload: self.payload.map(|summary| summary()),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        panic::AssertUnwindSafe,
+        sync::{Arc, LazyLock, Mutex},
+    };
+
+    use serde::{Deserialize, de::IgnoredAny};
+
+    use crate::core::dtos::socket::socket_dto::AnswerSubscribeDto;
+
+    use super::*;
+
+    const ANSWER_KEYS: [&str; 4] = ["roomId", "targetId", "sdp", "connectionType"];
+
+    fn guarded(call: Option<CallIds>, body: impl FnOnce()) -> Option<HandlerPanic> {
+        install_hook();
+
+        let reported = Arc::new(Mutex::new(None));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let reported = reported.clone();
+            let _guard = PanicGuard::new(
+                "room.answer_subscriber",
+                call,
+                Some(PayloadSummary::of::<AnswerSubscribeDto>),
+                move |panic: HandlerPanic| {
+                    *reported.lock().unwrap() = Some(panic);
+                },
+            );
+
+            body();
+        }));
+
+        assert_eq!(result.is_err(), reported.lock().unwrap().is_some());
+        reported.lock().unwrap().take()
+    }
+
+    fn call() -> CallIds {
+        CallIds {
+            room_id: "room-1".to_owned(),
+            participant_id: "participant-1".to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_reports_panic_with_call() {
+        let panic = guarded(Some(call()), || panic!("unexpected track")).unwrap();
+
+        assert_eq!(panic.event, "room.answer_subscriber");
+        assert_eq!(panic.call, Some(call()));
+        assert!(panic.message.starts_with("unexpected track at "));
+        assert_eq!(
+            panic.payload.map(|payload| payload.keys),
+            Some(ANSWER_KEYS.map(str::to_owned).to_vec())
+        );
+    }
+
+    #[test]
+    fn test_reports_panic_outside_call() {
+        let panic = guarded(None, || panic!("no call")).unwrap();
+
+        assert!(panic.call.is_none());
+        assert!(panic.message.starts_with("no call at "));
+    }
+
+    #[test]
+    fn test_silent_without_panic() {
+        assert!(guarded(Some(call()), || {}).is_none());
+    }
+
+    #[test]
+    fn test_summary_names_fields_on_the_wire() {
+        let summary = PayloadSummary::of::<AnswerSubscribeDto>();
+
+        assert_eq!(summary.name, "AnswerSubscribeDto");
+        assert_eq!(summary.keys, ANSWER_KEYS);
+        assert_eq!(summary.more_keys, 0);
+        assert_eq!(
+            summary.to_string(),
+            "AnswerSubscribeDto, keys: [roomId, targetId, sdp, connectionType]"
+        );
+    }
+
+    static WIDE_FIELDS: LazyLock<Vec<&'static str>> = LazyLock::new(|| {
+        (0..1_000)
+            .map(|index| &*format!("{index:04}{}", "k".repeat(1_000)).leak())
+            .collect()
+    });
+
+    struct WideDto;
+
+    impl<'de> Deserialize<'de> for WideDto {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_struct("WideDto", WIDE_FIELDS.as_slice(), IgnoredAny)?;
+            Ok(WideDto)
+        }
+    }
+
+    #[test]
+    fn test_summary_is_bounded() {
+        let summary = PayloadSummary::of::<WideDto>();
+
+        assert_eq!(summary.keys.len(), MAX_SUMMARY_KEYS);
+        assert_eq!(summary.more_keys, 1_000 - MAX_SUMMARY_KEYS);
+        assert!(
+            summary
+                .keys
+                .iter()
+                .all(|key| key.chars().count() <= MAX_SUMMARY_KEY_LEN + 1)
+        );
+        assert!(summary.to_string().len() < 1_024);
+    }
+
+    #[test]
+    fn test_summary_of_non_struct() {
+        let summary = PayloadSummary::of::<String>();
+
+        assert_eq!(summary.name, "alloc::string::String");
+        assert!(summary.keys.is_empty());
+    }
+}
@@ -0,0 +1,122 @@
+use std::{collections::HashMap, fmt};
+
+use chrono::{DateTime, NaiveDateTime};
+use socketioxide_redis::drivers::redis::redis_client::{
+    self as redis, AsyncCommands, cluster_async::ClusterConnection,
+};
+
+use crate::core::types::responses::chat_retention_response::ChatRetentionProgressResponse;
+
+const PROGRESS_KEY: &str = "chat_retention:progress";
+const RUNNING_KEY: &str = "chat_retention:running";
+
+/// A node that died mid-run stops holding the run after this
+const RUNNING_TTL_SECS: i64 = 6 * 60 * 60;
+
+/// Progress of the chat retention purge, kept in Redis so any signalling node
+/// can report on a run another node is doing
+#[derive(Clone)]
+pub struct RetentionProgressStore {
+    conn: ClusterConnection,
+}
+
+impl fmt::Debug for RetentionProgressStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetentionProgressStore")
+            .finish_non_exhaustive()
+    }
+}
+
+impl RetentionProgressStore {
+    pub fn new(conn: ClusterConnection) -> Self {
+        Self { conn }
+    }
+
+    /// Claims the run for this node, `false` while another node's run is in progress
+    pub async fn start_run(&self, now: NaiveDateTime) -> Result<bool, redis::RedisError> {
+        let mut conn = self.conn.clone();
+
+        let started_at = now.and_utc().timestamp_millis();
+        let reply: Option<String> = redis::cmd("SET")
+            .arg(RUNNING_KEY)
+            .arg(started_at)
+            .arg("NX")
+            .arg("EX")
+            .arg(RUNNING_TTL_SECS)
+            .query_async(&mut conn)
+            .await?;
+        let claimed = reply.is_some();
+
+        if claimed {
+            let _: () = conn
+                .hset_multiple(
+                    PROGRESS_KEY,
+                    &[
+                        ("last_started_at", started_at),
+                        ("last_run_deleted", 0),
+                        ("last_run_batches", 0),
+                    ],
+                )
+                .await?;
+        }
+
+        Ok(claimed)
+    }
+
+    pub async fn record_batch(&self, deleted: usize) -> Result<(), redis::RedisError> {
+        let mut conn = self.conn.clone();
+
+        let _: () = conn
+            .hincr(PROGRESS_KEY, "last_run_deleted", deleted as i64)
+            .await?;
+        let _: () = conn.hincr(PROGRESS_KEY, "last_run_batches", 1).await?;
+        let _: () = conn
+            .hincr(PROGRESS_KEY, "total_deleted", deleted as i64)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn finish_run(&self, now: NaiveDateTime) -> Result<(), redis::RedisError> {
+        let mut conn = self.conn.clone();
+
+        let _: () = conn
+            .hset(
+                PROGRESS_KEY,
+                "last_finished_at",
+                now.and_utc().timestamp_millis(),
+            )
+            .await?;
+        let _: () = conn.del(RUNNING_KEY).await?;
+
+        Ok(())
+    }
+
+    pub async fn get_progress(
+        &self,
+        default_retention_days: Option<i32>,
+    ) -> Result<ChatRetentionProgressResponse, redis::RedisError> {
+        let mut conn = self.conn.clone();
+
+        let fields: HashMap<String, i64> = conn.hgetall(PROGRESS_KEY).await?;
+        let is_running: bool = conn.exists(RUNNING_KEY).await?;
+
+        let get = |field: &str| fields.get(field).copied().unwrap_or(0);
+        let get_time = |field: &str| {
+            fields
+                .get(field)
+                .and_then(|millis| DateTime::from_timestamp_millis(*millis))
+                .map(|time| time.naive_utc())
+        };
+
+        Ok(ChatRetentionProgressResponse {
+            default_retention_days,
+            is_running,
+            last_started_at: get_time("last_started_at"),
+            last_finished_at: get_time("last_finished_at"),
+            last_run_deleted: get("last_run_deleted"),
+            last_run_batches: get("last_run_batches"),
+            total_deleted: get("total_deleted"),
+        })
+    }
+}
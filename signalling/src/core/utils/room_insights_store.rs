@@ -0,0 +1,192 @@
+use std::fmt;
+
+use socketioxide_redis::drivers::redis::redis_client::{
+    self as redis, AsyncCommands, cluster_async::ClusterConnection,
+};
+
+use crate::core::dtos::socket::socket_dto::ClientStatsDto;
+
+/// Every room with a host in the call by when its next insights are due
+const DUE_KEY: &str = "room_insights_due";
+
+/// Rooms taken by one node per pass, the rest wait for the next
+const CLAIM_BATCH_SIZE: isize = 100;
+
+/// Participant counts kept per room, a minute of them at the usual interval
+const TREND_SAMPLES: isize = 5;
+
+const TREND_TTL_SECS: i64 = 5 * 60;
+
+/// The rooms whose hosts get insights and the participant counts their trend
+/// is taken from. Shared through Redis, any signalling node can send a room's.
+#[derive(Clone)]
+pub struct RoomInsightsStore {
+    conn: ClusterConnection,
+}
+
+impl fmt::Debug for RoomInsightsStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RoomInsightsStore").finish_non_exhaustive()
+    }
+}
+
+impl RoomInsightsStore {
+    pub fn new(conn: ClusterConnection) -> Self {
+        Self { conn }
+    }
+
+    /// Insights of the room go out on the first pass after `at`
+    pub async fn schedule(&self, room_id: &str, at: i64) -> Result<(), redis::RedisError> {
+        let mut conn = self.conn.clone();
+        let _: () = conn.zadd(DUE_KEY, room_id, at).await?;
+
+        Ok(())
+    }
+
+    /// Rooms due by `now`. Each is handed to one node only, which schedules
+    /// it again if its hosts are still there.
+    pub async fn claim_due(&self, now: i64) -> Result<Vec<String>, redis::RedisError> {
+        let mut conn = self.conn.clone();
+        let due: Vec<String> = conn
+            .zrangebyscore_limit(DUE_KEY, "-inf", now, 0, CLAIM_BATCH_SIZE)
+            .await?;
+
+        let mut claimed = Vec::with_capacity(due.len());
+        for room_id in due {
+            // Another node got there first
+            let removed: i64 = conn.zrem(DUE_KEY, &room_id).await?;
+            if removed > 0 {
+                claimed.push(room_id);
+            }
+        }
+
+        Ok(claimed)
+    }
+
+    /// Adds a sample and returns the change since the oldest one kept
+    pub async fn record_participant_count(
+        &self,
+        room_id: &str,
+        count: usize,
+    ) -> Result<i64, redis::RedisError> {
+        let key = Self::trend_key(room_id);
+
+        let mut conn = self.conn.clone();
+        let _: () = conn.lpush(&key, count).await?;
+        let _: () = conn.ltrim(&key, 0, TREND_SAMPLES - 1).await?;
+        let _: () = conn.expire(&key, TREND_TTL_SECS).await?;
+        let oldest: Option<i64> = conn.lindex(&key, -1).await?;
+
+        Ok(count as i64 - oldest.unwrap_or(count as i64))
+    }
+
+    /// The hosts left, a host coming back starts a new trend
+    pub async fn forget(&self, room_id: &str) -> Result<(), redis::RedisError> {
+        let mut conn = self.conn.clone();
+        let _: () = conn.del(Self::trend_key(room_id)).await?;
+
+        Ok(())
+    }
+
+    fn trend_key(room_id: &str) -> String {
+        format!("room_insights:{room_id}:participants")
+    }
+}
+
+/// From 0 to 100, how well a client's connection carries a call. Loss hurts
+/// the most, then round trip time, then jitter. None without any of them.
+pub fn network_score(stats: &ClientStatsDto) -> Option<f64> {
+    if stats.rtt_ms.is_none() && stats.packet_loss.is_none() && stats.jitter_ms.is_none() {
+        return None;
+    }
+
+    let penalty = |value: Option<f64>, good: f64, bad: f64, weight: f64| {
+        value.map_or(0.0, |value| {
+            ((value - good) / (bad - good)).clamp(0.0, 1.0) * weight
+        })
+    };
+
+    let score = 100.0
+        - penalty(stats.packet_loss, 0.0, 0.1, 50.0)
+        - penalty(stats.rtt_ms, 100.0, 500.0, 30.0)
+        - penalty(stats.jitter_ms, 10.0, 100.0, 20.0);
+
+    Some(score)
+}
+
+/// How evenly the talking is spread, the entropy of everyone's share of the
+/// talk time over its maximum. From 0, one person talks, to 1, everyone
+/// talks as much. None with fewer than two participants or before anyone spoke.
+pub fn talk_time_balance(talk_times_ms: &[u64]) -> Option<f64> {
+    if talk_times_ms.len() < 2 {
+        return None;
+    }
+
+    let total: u64 = talk_times_ms.iter().sum();
+    if talk_times_ms.iter().filter(|ms| **ms > 0).count() < 2 {
+        return (total > 0).then_some(0.0);
+    }
+
+    let entropy: f64 = talk_times_ms
+        .iter()
+        .filter(|ms| **ms > 0)
+        .map(|ms| {
+            let share = *ms as f64 / total as f64;
+            -share * share.ln()
+        })
+        .sum();
+
+    Some(entropy / (talk_times_ms.len() as f64).ln())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(
+        rtt_ms: Option<f64>,
+        packet_loss: Option<f64>,
+        jitter_ms: Option<f64>,
+    ) -> ClientStatsDto {
+        ClientStatsDto {
+            room_id: "room".to_string(),
+            participant_id: "participant".to_string(),
+            timestamp: 0,
+            rtt_ms,
+            jitter_ms,
+            packet_loss,
+            available_outgoing_bitrate: None,
+            tracks: vec![],
+        }
+    }
+
+    #[test]
+    fn test_network_score() {
+        assert_eq!(network_score(&stats(None, None, None)), None);
+        assert_eq!(
+            network_score(&stats(Some(40.0), Some(0.0), Some(5.0))),
+            Some(100.0)
+        );
+        assert_eq!(
+            network_score(&stats(Some(900.0), Some(0.5), Some(300.0))),
+            Some(0.0)
+        );
+        // Half way to a bad round trip time, nothing else known
+        assert_eq!(network_score(&stats(Some(300.0), None, None)), Some(85.0));
+    }
+
+    #[test]
+    fn test_talk_time_balance() {
+        assert_eq!(talk_time_balance(&[]), None);
+        assert_eq!(talk_time_balance(&[60_000]), None);
+        // Nobody has spoken yet
+        assert_eq!(talk_time_balance(&[0, 0, 0]), None);
+        assert_eq!(talk_time_balance(&[60_000, 0, 0]), Some(0.0));
+
+        let even = talk_time_balance(&[30_000, 30_000, 30_000]).unwrap();
+        assert!((even - 1.0).abs() < 1e-9);
+
+        let lopsided = talk_time_balance(&[80_000, 10_000, 10_000]).unwrap();
+        assert!(lopsided > 0.0 && lopsided < 0.7);
+    }
+}
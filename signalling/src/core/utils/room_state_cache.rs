@@ -0,0 +1,251 @@
+use std::{fmt, sync::Arc};
+
+use salvo::async_trait;
+use serde::{Deserialize, Serialize};
+use socketioxide_redis::drivers::redis::redis_client::{
+    self as redis, AsyncCommands, cluster_async::ClusterConnection,
+};
+use tracing::warn;
+
+use crate::core::types::responses::room_response::RoomResponse;
+
+/// A backstop for writes that bypass the room service, which invalidates
+/// the rooms it changes itself
+const ROOM_STATE_TTL_SECS: u64 = 30;
+
+/// `RoomResponse` skips the password, participant nodes and internal ids when
+/// serialized. The nodes and ids are stored next to it so a cached room can be
+/// used for joins, the password never leaves Postgres.
+#[derive(Serialize, Deserialize)]
+struct CachedRoomState {
+    room: RoomResponse,
+    node_ids: Vec<Option<String>>,
+    ids: CachedIds,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedIds {
+    room_id: i32,
+    /// `(user_id, user)` per member
+    members: Vec<(i32, Option<i32>)>,
+    /// `(participant_id, user_id, user)` per participant
+    participants: Vec<(i32, i32, Option<i32>)>,
+    /// `(created_by_id, created_by)` of the latest message
+    latest_message: Option<(i32, Option<i32>)>,
+}
+
+impl CachedIds {
+    fn from_room(room: &RoomResponse) -> Self {
+        Self {
+            room_id: room.room.id,
+            members: room
+                .members
+                .iter()
+                .map(|m| (m.member.user_id, m.user.as_ref().map(|u| u.id)))
+                .collect(),
+            participants: room
+                .participants
+                .iter()
+                .map(|p| {
+                    (
+                        p.participant.id,
+                        p.participant.user_id,
+                        p.user.as_ref().map(|u| u.id),
+                    )
+                })
+                .collect(),
+            latest_message: room
+                .latest_message
+                .as_ref()
+                .map(|m| (m.message.created_by_id, m.created_by.as_ref().map(|u| u.id))),
+        }
+    }
+
+    fn restore(self, room: &mut RoomResponse) {
+        let room_id = self.room_id;
+        room.room.id = room_id;
+
+        for (member, (user_id, user)) in room.members.iter_mut().zip(self.members) {
+            member.member.user_id = user_id;
+            member.member.room_id = room_id;
+            if let (Some(u), Some(id)) = (member.user.as_mut(), user) {
+                u.id = id;
+            }
+        }
+
+        for (participant, (id, user_id, user)) in
+            room.participants.iter_mut().zip(self.participants)
+        {
+            participant.participant.id = id;
+            participant.participant.user_id = user_id;
+            participant.participant.room_id = room_id;
+            if let (Some(u), Some(id)) = (participant.user.as_mut(), user) {
+                u.id = id;
+            }
+        }
+
+        if let (Some(message), Some((created_by_id, created_by))) =
+            (room.latest_message.as_mut(), self.latest_message)
+        {
+            message.message.created_by_id = created_by_id;
+            message.message.room_id = room_id;
+            if let (Some(u), Some(id)) = (message.created_by.as_mut(), created_by) {
+                u.id = id;
+            }
+            if let Some(r) = message.room.as_mut() {
+                r.id = room_id;
+            }
+        }
+    }
+}
+
+/// Where the cached rooms are kept, Redis outside of tests
+#[async_trait]
+trait RoomStateStore: Send + Sync {
+    async fn get(&self, key: &str) -> redis::RedisResult<Option<String>>;
+
+    async fn set_ex(&self, key: &str, value: String, ttl_secs: u64) -> redis::RedisResult<()>;
+
+    async fn del(&self, key: &str) -> redis::RedisResult<()>;
+}
+
+struct RedisRoomStateStore {
+    conn: ClusterConnection,
+}
+
+#[async_trait]
+impl RoomStateStore for RedisRoomStateStore {
+    async fn get(&self, key: &str) -> redis::RedisResult<Option<String>> {
+        let mut conn = self.conn.clone();
+        conn.get(key).await
+    }
+
+    async fn set_ex(&self, key: &str, value: String, ttl_secs: u64) -> redis::RedisResult<()> {
+        let mut conn = self.conn.clone();
+        conn.set_ex(key, value, ttl_secs).await
+    }
+
+    async fn del(&self, key: &str) -> redis::RedisResult<()> {
+        let mut conn = self.conn.clone();
+        conn.del(key).await
+    }
+}
+
+/// Redis-backed cache of `RoomResponse` so room joins don't have to load the room,
+/// its members and participants from Postgres every time.
+///
+/// Rooms read from it have no password, anything checking or writing one back
+/// goes to the repository.
+#[derive(Clone)]
+pub struct RoomStateCache {
+    store: Arc<dyn RoomStateStore>,
+}
+
+impl fmt::Debug for RoomStateCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RoomStateCache").finish_non_exhaustive()
+    }
+}
+
+impl RoomStateCache {
+    pub fn new(conn: ClusterConnection) -> Self {
+        Self {
+            store: Arc::new(RedisRoomStateStore { conn }),
+        }
+    }
+
+    pub async fn get(&self, room_id: i32) -> Option<RoomResponse> {
+        let value = match self.store.get(&Self::key(room_id)).await {
+            Ok(value) => value,
+            Err(err) => {
+                warn!("Failed to read room state {}: {:?}", room_id, err);
+                return None;
+            }
+        };
+
+        let state: CachedRoomState = serde_json::from_str(&value?).ok()?;
+
+        let mut room = state.room;
+        for (participant, node_id) in room.participants.iter_mut().zip(state.node_ids) {
+            participant.participant.node_id = node_id;
+        }
+        state.ids.restore(&mut room);
+
+        Some(room)
+    }
+
+    pub async fn set(&self, room: &RoomResponse) {
+        let state = CachedRoomState {
+            room: room.clone(),
+            node_ids: room
+                .participants
+                .iter()
+                .map(|p| p.participant.node_id.clone())
+                .collect(),
+            ids: CachedIds::from_room(room),
+        };
+
+        let Ok(value) = serde_json::to_string(&state) else {
+            return;
+        };
+
+        let result = self
+            .store
+            .set_ex(&Self::key(room.room.id), value, ROOM_STATE_TTL_SECS)
+            .await;
+
+        if let Err(err) = result {
+            warn!("Failed to cache room state {}: {:?}", room.room.id, err);
+        }
+    }
+
+    pub async fn invalidate(&self, room_id: i32) {
+        let result = self.store.del(&Self::key(room_id)).await;
+
+        if let Err(err) = result {
+            warn!("Failed to invalidate room state {}: {:?}", room_id, err);
+        }
+    }
+
+    fn key(room_id: i32) -> String {
+        format!("room_state:{room_id}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Mutex};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct MemoryRoomStateStore {
+        values: Mutex<HashMap<String, String>>,
+    }
+
+    #[async_trait]
+    impl RoomStateStore for MemoryRoomStateStore {
+        async fn get(&self, key: &str) -> redis::RedisResult<Option<String>> {
+            Ok(self.values.lock().unwrap().get(key).cloned())
+        }
+
+        async fn set_ex(&self, key: &str, value: String, _ttl_secs: u64) -> redis::RedisResult<()> {
+            self.values.lock().unwrap().insert(key.to_owned(), value);
+            Ok(())
+        }
+
+        async fn del(&self, key: &str) -> redis::RedisResult<()> {
+            self.values.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+
+    impl RoomStateCache {
+        /// Kept in memory, for the tests of the services using it
+        pub(crate) fn in_memory() -> Self {
+            Self {
+                store: Arc::new(MemoryRoomStateStore::default()),
+            }
+        }
+    }
+}
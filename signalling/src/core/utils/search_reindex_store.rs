@@ -0,0 +1,209 @@
+use std::{collections::HashMap, fmt};
+
+use chrono::{DateTime, NaiveDateTime};
+use socketioxide_redis::drivers::redis::redis_client::{
+    self as redis, AsyncCommands, cluster_async::ClusterConnection,
+};
+
+use crate::core::types::responses::search_reindex_response::{
+    SearchReindexCollection, SearchReindexResponse,
+};
+
+const PROGRESS_KEY: &str = "search_reindex:progress";
+const RUNNING_KEY: &str = "search_reindex:running";
+
+/// Refreshed after every chunk, so only a node that died mid-run lets it lapse
+const RUNNING_TTL_SECS: i64 = 10 * 60;
+
+/// Progress of the search index rebuild, kept in Redis so any signalling node
+/// can report on a rebuild another node is doing
+#[derive(Clone)]
+pub struct SearchReindexStore {
+    conn: ClusterConnection,
+}
+
+impl fmt::Debug for SearchReindexStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SearchReindexStore").finish_non_exhaustive()
+    }
+}
+
+impl SearchReindexStore {
+    pub fn new(conn: ClusterConnection) -> Self {
+        Self { conn }
+    }
+
+    /// Claims the rebuild for this node, `false` while another one is in progress
+    pub async fn start_run(&self, now: NaiveDateTime) -> Result<bool, redis::RedisError> {
+        let mut conn = self.conn.clone();
+
+        let started_at = now.and_utc().timestamp_millis();
+        let reply: Option<String> = redis::cmd("SET")
+            .arg(RUNNING_KEY)
+            .arg(started_at)
+            .arg("NX")
+            .arg("EX")
+            .arg(RUNNING_TTL_SECS)
+            .query_async(&mut conn)
+            .await?;
+        let claimed = reply.is_some();
+
+        if claimed {
+            // The previous run's counts and outcome don't carry over
+            let _: () = conn.del(PROGRESS_KEY).await?;
+            let _: () = conn.hset(PROGRESS_KEY, "started_at", started_at).await?;
+        }
+
+        Ok(claimed)
+    }
+
+    pub async fn set_total(&self, collection: &str, total: i64) -> Result<(), redis::RedisError> {
+        let mut conn = self.conn.clone();
+
+        let _: () = conn
+            .hset(PROGRESS_KEY, format!("{collection}_total"), total)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn record_chunk(
+        &self,
+        collection: &str,
+        indexed: usize,
+    ) -> Result<(), redis::RedisError> {
+        let mut conn = self.conn.clone();
+
+        let _: () = conn
+            .hincr(
+                PROGRESS_KEY,
+                format!("{collection}_indexed"),
+                indexed as i64,
+            )
+            .await?;
+        let _: () = conn.expire(RUNNING_KEY, RUNNING_TTL_SECS).await?;
+
+        Ok(())
+    }
+
+    pub async fn finish_run(
+        &self,
+        now: NaiveDateTime,
+        is_failed: bool,
+    ) -> Result<(), redis::RedisError> {
+        let mut conn = self.conn.clone();
+
+        let _: () = conn
+            .hset_multiple(
+                PROGRESS_KEY,
+                &[
+                    ("finished_at", now.and_utc().timestamp_millis()),
+                    ("failed", is_failed as i64),
+                ],
+            )
+            .await?;
+        let _: () = conn.del(RUNNING_KEY).await?;
+
+        Ok(())
+    }
+
+    pub async fn get_progress(
+        &self,
+        collections: &[&str],
+    ) -> Result<SearchReindexResponse, redis::RedisError> {
+        let mut conn = self.conn.clone();
+
+        let fields: HashMap<String, i64> = conn.hgetall(PROGRESS_KEY).await?;
+        let is_running: bool = conn.exists(RUNNING_KEY).await?;
+
+        Ok(Self::to_progress(&fields, is_running, collections))
+    }
+
+    fn to_progress(
+        fields: &HashMap<String, i64>,
+        is_running: bool,
+        collections: &[&str],
+    ) -> SearchReindexResponse {
+        let get = |field: &str| fields.get(field).copied().unwrap_or(0);
+        let get_time = |field: &str| {
+            fields
+                .get(field)
+                .and_then(|millis| DateTime::from_timestamp_millis(*millis))
+                .map(|time| time.naive_utc())
+        };
+
+        SearchReindexResponse {
+            is_running,
+            started_at: get_time("started_at"),
+            finished_at: get_time("finished_at"),
+            is_failed: get("failed") == 1,
+            collections: collections
+                .iter()
+                .map(|collection| SearchReindexCollection {
+                    name: collection.to_string(),
+                    indexed: get(&format!("{collection}_indexed")),
+                    total: get(&format!("{collection}_total")),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_before_any_rebuild() {
+        let progress = SearchReindexStore::to_progress(&HashMap::new(), false, &["users"]);
+
+        assert!(!progress.is_running);
+        assert!(!progress.is_failed);
+        assert!(progress.started_at.is_none());
+        assert!(progress.finished_at.is_none());
+        assert_eq!(progress.collections[0].name, "users");
+        assert_eq!(progress.collections[0].indexed, 0);
+        assert_eq!(progress.collections[0].total, 0);
+    }
+
+    #[test]
+    fn test_progress_of_a_running_rebuild() {
+        let fields = HashMap::from([
+            ("started_at".to_string(), 1_700_000_000_000),
+            ("users_total".to_string(), 1_200),
+            ("users_indexed".to_string(), 1_000),
+            ("messages_total".to_string(), 5_000),
+        ]);
+
+        let progress = SearchReindexStore::to_progress(&fields, true, &["users", "messages"]);
+
+        assert!(progress.is_running);
+        assert_eq!(
+            progress.started_at,
+            DateTime::from_timestamp_millis(1_700_000_000_000).map(|time| time.naive_utc())
+        );
+        assert!(progress.finished_at.is_none());
+
+        let counts: Vec<(i64, i64)> = progress
+            .collections
+            .iter()
+            .map(|collection| (collection.indexed, collection.total))
+            .collect();
+        assert_eq!(counts, [(1_000, 1_200), (0, 5_000)]);
+    }
+
+    #[test]
+    fn test_progress_of_a_failed_rebuild() {
+        let fields = HashMap::from([
+            ("started_at".to_string(), 1_700_000_000_000),
+            ("finished_at".to_string(), 1_700_000_060_000),
+            ("failed".to_string(), 1),
+        ]);
+
+        let progress = SearchReindexStore::to_progress(&fields, false, &[]);
+
+        assert!(progress.is_failed);
+        assert!(progress.finished_at.is_some());
+        assert!(progress.collections.is_empty());
+    }
+}
@@ -0,0 +1,101 @@
+use std::fmt;
+
+use chrono::Utc;
+use socketioxide_redis::drivers::redis::redis_client::{
+    self as redis, AsyncCommands, cluster_async::ClusterConnection,
+};
+use tracing::warn;
+
+use crate::core::types::responses::viewer_count_response::ViewerCountResponse;
+
+/// A player that hasn't refreshed the playlist for this long has stopped watching
+const HLS_VIEWER_WINDOW_MS: i64 = 30_000;
+
+/// Counts are served from here for a few seconds, landing pages may poll them a lot
+const VIEWER_COUNT_TTL_SECS: u64 = 5;
+
+/// Tracks HLS viewers per room from their playlist requests and caches the
+/// combined viewer counts served to landing pages
+#[derive(Clone)]
+pub struct ViewerCountStore {
+    conn: ClusterConnection,
+}
+
+impl fmt::Debug for ViewerCountStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ViewerCountStore").finish_non_exhaustive()
+    }
+}
+
+impl ViewerCountStore {
+    pub fn new(conn: ClusterConnection) -> Self {
+        Self { conn }
+    }
+
+    pub async fn record_hls_request(
+        &self,
+        room_id: &str,
+        viewer_id: &str,
+    ) -> Result<(), redis::RedisError> {
+        let key = Self::hls_key(room_id);
+
+        let mut conn = self.conn.clone();
+        let _: () = conn
+            .zadd(&key, viewer_id, Utc::now().timestamp_millis())
+            .await?;
+        let _: () = conn.expire(&key, HLS_VIEWER_WINDOW_MS / 1000).await?;
+
+        Ok(())
+    }
+
+    pub async fn hls_viewers(&self, room_id: &str) -> Result<i64, redis::RedisError> {
+        let key = Self::hls_key(room_id);
+        let since = Utc::now().timestamp_millis() - HLS_VIEWER_WINDOW_MS;
+
+        let mut conn = self.conn.clone();
+        let _: () = conn.zrembyscore(&key, "-inf", since).await?;
+
+        conn.zcard(&key).await
+    }
+
+    pub async fn get_cached(&self, room_id: &str) -> Option<ViewerCountResponse> {
+        let mut conn = self.conn.clone();
+
+        let value: Option<String> = match conn.get(Self::count_key(room_id)).await {
+            Ok(value) => value,
+            Err(err) => {
+                warn!("Failed to read viewer count {}: {:?}", room_id, err);
+                return None;
+            }
+        };
+
+        serde_json::from_str(&value?).ok()
+    }
+
+    pub async fn set_cached(&self, count: &ViewerCountResponse) {
+        let Ok(value) = serde_json::to_string(count) else {
+            return;
+        };
+
+        let mut conn = self.conn.clone();
+        let result: Result<(), redis::RedisError> = conn
+            .set_ex(
+                Self::count_key(&count.room_id),
+                value,
+                VIEWER_COUNT_TTL_SECS,
+            )
+            .await;
+
+        if let Err(err) = result {
+            warn!("Failed to cache viewer count {}: {:?}", count.room_id, err);
+        }
+    }
+
+    fn hls_key(room_id: &str) -> String {
+        format!("hls_viewers:{room_id}")
+    }
+
+    fn count_key(room_id: &str) -> String {
+        format!("viewer_count:{room_id}")
+    }
+}
@@ -0,0 +1,858 @@
+use dispatcher::{
+    dispatcher_manager::DispatcherManager,
+    domain::rollout::RolloutConfig,
+    infrastructure::{cache::dead_letter_queue::DeadLetter, event_queue::EventSender},
+};
+use salvo::{
+    oapi::extract::{JsonBody, PathParam, QueryParam},
+    prelude::*,
+};
+use tracing::info;
+use waterbus_proto::{GetRecordingManifestsRequest, ObjectCounts};
+
+use crate::{
+    core::{
+        database::soft_delete::DeletedScope,
+        dtos::{
+            bot::register_bot_dto::RegisterBotDto,
+            common::pagination_dto::PaginationDto,
+            moderation::resolve_report_dto::ResolveReportDto,
+            org::{
+                assign_org_dto::AssignOrgDto, create_org_dto::CreateOrgDto,
+                update_branding_dto::UpdateBrandingDto,
+                update_client_features_dto::UpdateClientFeaturesDto,
+            },
+            rollout::update_rollout_dto::UpdateRolloutDto,
+            room::{
+                create_join_token_dto::CreateJoinTokenDto,
+                create_observer_token_dto::CreateObserverTokenDto,
+            },
+        },
+        entities::models::{MembersRoleEnum, User},
+        env::app_env::AppEnv,
+        types::{
+            app_channel::AppEvent,
+            enums::report_status::ReportStatus,
+            errors::{
+                admin_error::AdminError, bot_error::BotError, compliance_error::ComplianceError,
+                moderation_error::ModerationError, org_error::OrgError, room_error::RoomError,
+                search_error::SearchError, user_error::UserError,
+            },
+            responses::{
+                abuse_report_response::{AbuseReportResponse, ListAbuseReportResponse},
+                bot_response::{BotResponse, ListBotResponse},
+                branding_response::BrandingResponse,
+                chat_retention_response::ChatRetentionProgressResponse,
+                client_config_response::ClientFeaturesResponse,
+                compliance_response::{ComplianceExportResponse, LegalHoldResponse},
+                dead_letter_response::{DeadLetterResponse, ListDeadLetterResponse},
+                join_token_response::JoinTokenResponse,
+                live_room_response::ListLiveRoomResponse,
+                node_response::{ListNodeResponse, NodeResponse},
+                observer_token_response::ObserverTokenResponse,
+                rollout_response::{CanaryNodeResponse, RolloutResponse},
+                room_response::RoomResponse,
+                room_stats_response::RoomStatsResponse,
+                search_reindex_response::SearchReindexResponse,
+                sfu_diagnostics_response::{
+                    NodeDiagnosticsResponse, ObjectCountsResponse, SfuDiagnosticsResponse,
+                },
+            },
+        },
+        utils::{
+            api_key_utils::admin_key_middleware, id_utils::generate_observer_id,
+            jwt_utils::JwtUtils, retention_progress_store::RetentionProgressStore,
+            search_reindex_store::SearchReindexStore,
+        },
+    },
+    features::{
+        bot::{
+            repository::BotRepositoryImpl,
+            service::{BotService, BotServiceImpl},
+        },
+        compliance::{
+            repository::ComplianceRepositoryImpl,
+            service::{ComplianceService, ComplianceServiceImpl, to_recording},
+        },
+        moderation::service::{AbuseReports, ModerationService},
+        org::{
+            repository::OrgRepositoryImpl,
+            service::{OrgService, OrgServiceImpl},
+        },
+        room::{
+            repository::RoomRepositoryImpl,
+            service::{RoomService, RoomServiceImpl},
+        },
+        search::{
+            collection::SearchCollection, reindex::SearchReindexer,
+            repository::SearchRepositoryImpl,
+        },
+        user::{
+            repository::UserRepositoryImpl,
+            service::{UserService, UserServiceImpl},
+        },
+    },
+};
+
+const DEFAULT_DEAD_LETTER_LIMIT: usize = 50;
+
+const DEFAULT_OBSERVER_TOKEN_SECONDS: i64 = 3_600;
+const MAX_OBSERVER_TOKEN_SECONDS: i64 = 86_400;
+const MAX_OBSERVER_LABEL_LEN: usize = 100;
+
+pub fn get_admin_router() -> Router {
+    let dead_letter_router = Router::with_path("dead-letters")
+        .get(get_dead_letters)
+        .push(Router::with_path("/{id}/replay").post(replay_dead_letter));
+
+    let bot_router = Router::with_path("bots")
+        .get(get_bots)
+        .post(register_bot)
+        .push(Router::with_path("/{id}").delete(delete_bot));
+
+    let org_router = Router::with_path("orgs")
+        .post(create_org)
+        .push(Router::with_path("/{id}/branding").put(update_branding))
+        .push(Router::with_path("/{id}/client-features").put(update_client_features));
+
+    let node_router = Router::with_path("nodes").get(get_nodes).push(
+        Router::with_path("/{id}/drain")
+            .put(drain_node)
+            .delete(undrain_node),
+    );
+
+    Router::with_hoop(admin_key_middleware())
+        .path("admin")
+        .push(dead_letter_router)
+        .push(node_router)
+        .push(bot_router)
+        .push(org_router)
+        .push(Router::with_path("users/{id}").get(get_user))
+        .push(Router::with_path("rooms").get(get_live_rooms))
+        .push(
+            Router::with_path("rooms/{id}")
+                .get(get_room)
+                .push(Router::with_path("close").post(close_room))
+                .push(Router::with_path("join-tokens").post(create_join_token))
+                .push(
+                    Router::with_path("legal-hold")
+                        .put(place_legal_hold)
+                        .delete(lift_legal_hold),
+                )
+                .push(Router::with_path("compliance-export").get(get_compliance_export))
+                .push(Router::with_path("observer-tokens").post(create_observer_token))
+                .push(Router::with_path("org").put(assign_room_org))
+                .push(Router::with_path("sfu-stats").get(get_room_sfu_stats)),
+        )
+        .push(
+            Router::with_path("reports")
+                .get(get_reports)
+                .push(Router::with_path("{id}").put(resolve_report)),
+        )
+        .push(Router::with_path("chat-retention").get(get_chat_retention))
+        .push(
+            Router::with_path("rollout")
+                .get(get_rollout)
+                .put(update_rollout),
+        )
+        .push(Router::with_path("sfu/diagnostics").get(get_sfu_diagnostics))
+        .push(
+            Router::with_path("search/reindex")
+                .get(get_search_reindex)
+                .post(start_search_reindex),
+        )
+}
+
+/// Moderation queue of abuse reports, oldest first. Defaults to the open ones,
+/// pass `status` to look at resolved or dismissed reports instead.
+#[endpoint(tags("admin"), status_codes(200, 400, 401, 500))]
+async fn get_reports(
+    _res: &mut Response,
+    status: QueryParam<String, false>,
+    pagination_dto: PaginationDto,
+    depot: &mut Depot,
+) -> Result<ListAbuseReportResponse, ModerationError> {
+    let moderation_service = depot.obtain::<AbuseReports>().unwrap();
+
+    let status = match status.into_inner().as_deref() {
+        None => ReportStatus::Open,
+        Some(status) => status
+            .parse()
+            .map_err(|_| ModerationError::InvalidReport(format!("unknown status {status}")))?,
+    };
+
+    let reports = moderation_service
+        .get_reports(status, pagination_dto)
+        .await?;
+
+    Ok(ListAbuseReportResponse { reports })
+}
+
+/// Closes an open report as `resolved` or `dismissed`.
+#[endpoint(tags("admin"), status_codes(200, 400, 401, 404, 409, 500))]
+async fn resolve_report(
+    _res: &mut Response,
+    id: PathParam<i32>,
+    data: JsonBody<ResolveReportDto>,
+    depot: &mut Depot,
+) -> Result<AbuseReportResponse, ModerationError> {
+    let moderation_service = depot.obtain::<AbuseReports>().unwrap();
+
+    moderation_service
+        .resolve_report(id.into_inner(), data.into_inner())
+        .await
+}
+
+/// Canary rollout settings and the nodes currently taking canary rooms.
+#[endpoint(tags("admin"), status_codes(200, 401, 500))]
+async fn get_rollout(
+    _res: &mut Response,
+    depot: &mut Depot,
+) -> Result<RolloutResponse, AdminError> {
+    let dispatcher_manager = depot.obtain::<DispatcherManager>().unwrap();
+
+    let config = dispatcher_manager
+        .get_rollout()
+        .map_err(|e| AdminError::UnexpectedError(e.to_string()))?;
+
+    Ok(to_rollout_response(dispatcher_manager, config).await)
+}
+
+/// Changes which new rooms go to canary nodes. Set `percentage` to 0 and
+/// clear `clientVersions` to stop sending rooms there.
+#[endpoint(tags("admin"), status_codes(200, 400, 401, 500))]
+async fn update_rollout(
+    _res: &mut Response,
+    data: JsonBody<UpdateRolloutDto>,
+    depot: &mut Depot,
+) -> Result<RolloutResponse, AdminError> {
+    let dispatcher_manager = depot.obtain::<DispatcherManager>().unwrap();
+
+    let data = data.into_inner();
+    let config = RolloutConfig {
+        percentage: data.percentage,
+        client_versions: data.client_versions,
+    };
+    config.validate().map_err(AdminError::InvalidRollout)?;
+
+    dispatcher_manager
+        .set_rollout(&config)
+        .map_err(|e| AdminError::UnexpectedError(e.to_string()))?;
+
+    Ok(to_rollout_response(dispatcher_manager, config).await)
+}
+
+async fn to_rollout_response(
+    dispatcher_manager: &DispatcherManager,
+    config: RolloutConfig,
+) -> RolloutResponse {
+    let canary_nodes = dispatcher_manager
+        .get_canary_nodes()
+        .await
+        .into_iter()
+        .map(|(node_id, cpu_free)| CanaryNodeResponse { node_id, cpu_free })
+        .collect();
+
+    RolloutResponse {
+        percentage: config.percentage,
+        client_versions: config.client_versions,
+        canary_nodes,
+    }
+}
+
+/// SFU nodes of this group with their load, cohort and whether they take
+/// new rooms.
+#[endpoint(tags("admin"), status_codes(200, 401, 500))]
+async fn get_nodes(_res: &mut Response, depot: &mut Depot) -> Result<ListNodeResponse, AdminError> {
+    let dispatcher_manager = depot.obtain::<DispatcherManager>().unwrap();
+
+    let nodes = dispatcher_manager
+        .get_nodes()
+        .await
+        .map_err(|e| AdminError::UnexpectedError(e.to_string()))?;
+
+    Ok(ListNodeResponse {
+        nodes: nodes.into_iter().map(NodeResponse::from).collect(),
+    })
+}
+
+/// Takes the node out of rotation ahead of maintenance: new rooms and
+/// publishers go elsewhere, calls already on it carry on. Wait for `clients`
+/// to reach 0 before stopping it.
+#[endpoint(tags("admin"), status_codes(200, 401, 404, 500))]
+async fn drain_node(
+    _res: &mut Response,
+    id: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<NodeResponse, AdminError> {
+    set_node_drained(depot, id.into_inner(), true).await
+}
+
+/// Puts a drained node back in rotation.
+#[endpoint(tags("admin"), status_codes(200, 401, 404, 500))]
+async fn undrain_node(
+    _res: &mut Response,
+    id: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<NodeResponse, AdminError> {
+    set_node_drained(depot, id.into_inner(), false).await
+}
+
+async fn set_node_drained(
+    depot: &Depot,
+    node_id: String,
+    is_drained: bool,
+) -> Result<NodeResponse, AdminError> {
+    let dispatcher_manager = depot.obtain::<DispatcherManager>().unwrap();
+
+    let node = dispatcher_manager
+        .set_node_drained(&node_id, is_drained)
+        .await
+        .map_err(|e| AdminError::UnexpectedError(e.to_string()))?
+        .ok_or(AdminError::NodeNotFound(node_id))?;
+
+    Ok(node.into())
+}
+
+/// Rooms with a call going on right now and the nodes carrying them.
+#[endpoint(tags("admin"), status_codes(200, 401, 500))]
+async fn get_live_rooms(
+    _res: &mut Response,
+    depot: &mut Depot,
+) -> Result<ListLiveRoomResponse, AdminError> {
+    let dispatcher_manager = depot.obtain::<DispatcherManager>().unwrap();
+
+    let rooms = dispatcher_manager
+        .get_live_rooms()
+        .map_err(|e| AdminError::UnexpectedError(e.to_string()))?;
+
+    Ok(ListLiveRoomResponse {
+        rooms: rooms.into_iter().map(Into::into).collect(),
+    })
+}
+
+/// Ends the room for everyone: it is deactivated, participants are told with
+/// `room.closed` and their call sockets are disconnected on every node.
+#[endpoint(tags("admin"), status_codes(200, 401, 404, 500))]
+async fn close_room(
+    _res: &mut Response,
+    id: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<RoomResponse, RoomError> {
+    let room_service = depot
+        .obtain::<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>()
+        .unwrap();
+    let app_events = depot.obtain::<EventSender<AppEvent>>().unwrap();
+
+    let room_id = room_service.resolve_room_id(&id.into_inner()).await?;
+
+    let room = room_service.close_room(room_id).await?;
+
+    app_events
+        .send(AppEvent::CloseRoom(room.room.public_id.clone()))
+        .await
+        .map_err(|e| RoomError::UnexpectedError(e.to_string()))?;
+
+    info!("Closed room {}", room.room.public_id);
+
+    Ok(room)
+}
+
+/// Admits the user to the call and returns the token for the socket
+/// `room.publish` event, like `POST rooms/{id}/join-token` on the user's
+/// behalf. Load tests and support use it to put someone in a call.
+#[endpoint(tags("admin"), status_codes(201, 400, 401, 403, 404, 409, 500))]
+async fn create_join_token(
+    _res: &mut Response,
+    id: PathParam<String>,
+    data: JsonBody<CreateJoinTokenDto>,
+    depot: &mut Depot,
+) -> Result<JoinTokenResponse, RoomError> {
+    let room_service = depot
+        .obtain::<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>()
+        .unwrap();
+    let user_service = depot
+        .obtain::<UserServiceImpl<UserRepositoryImpl>>()
+        .unwrap();
+    let jwt_utils = depot.obtain::<JwtUtils>().unwrap();
+
+    let data = data.into_inner();
+
+    let room_id = room_service.resolve_room_id(&id.into_inner()).await?;
+    let user_id = user_service
+        .get_user_by_public_id(&data.user_id, DeletedScope::Exclude)
+        .await
+        .map_err(|_| RoomError::UserNotFound(data.user_id.clone()))?
+        .id;
+
+    let (room, participant) = room_service
+        .join_room(user_id, room_id, data.password.as_deref())
+        .await?;
+
+    let participant_id = participant.participant.public_id;
+
+    let is_host = room.members.iter().any(|member| {
+        member.member.user_id == user_id && member.member.role == MembersRoleEnum::Owner as i16
+    });
+
+    let (token, expires_at) = jwt_utils.generate_join_token(
+        &room.room.public_id,
+        &participant_id,
+        &user_id.to_string(),
+        is_host,
+    );
+
+    info!(
+        "Issued join token for user {} in room {}",
+        data.user_id, room.room.public_id
+    );
+
+    Ok(JoinTokenResponse {
+        token,
+        room_id: room.room.public_id,
+        participant_id,
+        expires_at,
+    })
+}
+
+/// Live object and task counts of every SFU node. Run a soak test, close its
+/// rooms and compare: an idle node should be back to zero objects.
+#[endpoint(tags("admin"), status_codes(200, 401, 500))]
+async fn get_sfu_diagnostics(
+    _res: &mut Response,
+    depot: &mut Depot,
+) -> Result<SfuDiagnosticsResponse, AdminError> {
+    let dispatcher_manager = depot.obtain::<DispatcherManager>().unwrap();
+
+    let (diagnostics, unreachable_nodes) = dispatcher_manager.get_node_diagnostics().await;
+
+    let nodes = diagnostics
+        .into_iter()
+        .map(|(node_id, diagnostics)| NodeDiagnosticsResponse {
+            node_id,
+            clients: diagnostics.clients,
+            reachable: to_counts_response(diagnostics.reachable),
+            live: to_counts_response(diagnostics.live),
+            str0m_subscribers: diagnostics.str0m_subscribers,
+            forwarding_channels: diagnostics.forwarding_channels,
+            tokio_tasks: diagnostics.tokio_tasks,
+        })
+        .collect();
+
+    Ok(SfuDiagnosticsResponse {
+        nodes,
+        unreachable_nodes,
+    })
+}
+
+/// The room as one call across the SFU nodes holding it: who publishes on
+/// which node, subscribers and bitrate. Rooms migrating between nodes show up
+/// on both.
+#[endpoint(tags("admin"), status_codes(200, 401, 500))]
+async fn get_room_sfu_stats(
+    _res: &mut Response,
+    id: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<RoomStatsResponse, AdminError> {
+    let dispatcher_manager = depot.obtain::<DispatcherManager>().unwrap();
+
+    let view = dispatcher_manager.get_room_stats(&id.into_inner()).await;
+
+    Ok(view.into())
+}
+
+fn to_counts_response(counts: Option<ObjectCounts>) -> ObjectCountsResponse {
+    counts
+        .map(|counts| ObjectCountsResponse {
+            rooms: counts.rooms,
+            publishers: counts.publishers,
+            subscribers: counts.subscribers,
+            tracks: counts.tracks,
+            forward_tracks: counts.forward_tracks,
+        })
+        .unwrap_or_default()
+}
+
+/// Starts rebuilding the search index in the background. Search keeps
+/// answering from the current index until the rebuild swaps it out.
+#[endpoint(tags("admin"), status_codes(202, 401, 409, 500))]
+async fn start_search_reindex(
+    _res: &mut Response,
+    depot: &mut Depot,
+) -> Result<SearchReindexResponse, SearchError> {
+    let search_reindexer = depot
+        .obtain::<SearchReindexer<SearchRepositoryImpl>>()
+        .map_err(|_| SearchError::IndexNotConfigured)?;
+
+    search_reindexer.start().await?;
+
+    get_reindex_progress(depot).await
+}
+
+/// Progress of the latest search index rebuild.
+#[endpoint(tags("admin"), status_codes(200, 401, 500))]
+async fn get_search_reindex(
+    _res: &mut Response,
+    depot: &mut Depot,
+) -> Result<SearchReindexResponse, SearchError> {
+    get_reindex_progress(depot).await
+}
+
+async fn get_reindex_progress(depot: &Depot) -> Result<SearchReindexResponse, SearchError> {
+    let reindex_store = depot.obtain::<SearchReindexStore>().unwrap();
+    let collections = SearchCollection::ALL.map(SearchCollection::alias);
+
+    reindex_store
+        .get_progress(&collections)
+        .await
+        .map_err(|e| SearchError::UnexpectedError(e.to_string()))
+}
+
+/// Progress of the chat retention purge and the deployment's default period.
+#[endpoint(tags("admin"), status_codes(200, 401, 500))]
+async fn get_chat_retention(
+    _res: &mut Response,
+    depot: &mut Depot,
+) -> Result<ChatRetentionProgressResponse, AdminError> {
+    let progress_store = depot.obtain::<RetentionProgressStore>().unwrap();
+    let env = depot.obtain::<AppEnv>().unwrap();
+
+    progress_store
+        .get_progress(env.chat_retention.default_days)
+        .await
+        .map_err(|e| AdminError::UnexpectedError(e.to_string()))
+}
+
+/// Lists dispatcher callbacks that could not be delivered, newest first.
+#[endpoint(tags("admin"), status_codes(200, 401, 500))]
+async fn get_dead_letters(
+    _res: &mut Response,
+    limit: QueryParam<usize, false>,
+    depot: &mut Depot,
+) -> Result<ListDeadLetterResponse, AdminError> {
+    let dispatcher_manager = depot.obtain::<DispatcherManager>().unwrap();
+
+    let limit = limit.into_inner().unwrap_or(DEFAULT_DEAD_LETTER_LIMIT);
+
+    let dead_letters = dispatcher_manager
+        .get_dead_letters(limit)
+        .map_err(|e| AdminError::UnexpectedError(e.to_string()))?;
+
+    Ok(ListDeadLetterResponse {
+        dead_letters: dead_letters.into_iter().map(to_response).collect(),
+    })
+}
+
+/// Removes a dead letter from the queue and delivers its callback again.
+#[endpoint(tags("admin"), status_codes(200, 401, 404, 500))]
+async fn replay_dead_letter(
+    _res: &mut Response,
+    id: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<DeadLetterResponse, AdminError> {
+    let dispatcher_manager = depot.obtain::<DispatcherManager>().unwrap();
+
+    let id = id.into_inner();
+
+    let dead_letter = dispatcher_manager
+        .replay_dead_letter(&id)
+        .await
+        .map_err(|e| AdminError::UnexpectedError(e.to_string()))?
+        .ok_or(AdminError::DeadLetterNotFound(id))?;
+
+    Ok(to_response(dead_letter))
+}
+
+/// Lists the registered bots.
+#[endpoint(tags("admin"), status_codes(200, 401, 500))]
+async fn get_bots(_res: &mut Response, depot: &mut Depot) -> Result<ListBotResponse, BotError> {
+    let bot_service = depot.obtain::<BotServiceImpl<BotRepositoryImpl>>().unwrap();
+
+    let bots = bot_service.get_bots().await?;
+
+    Ok(ListBotResponse { bots })
+}
+
+/// Registers a bot. The returned token is shown once, store it right away.
+#[endpoint(tags("admin"), status_codes(200, 400, 401, 500))]
+async fn register_bot(
+    _res: &mut Response,
+    data: JsonBody<RegisterBotDto>,
+    depot: &mut Depot,
+) -> Result<BotResponse, BotError> {
+    let bot_service = depot.obtain::<BotServiceImpl<BotRepositoryImpl>>().unwrap();
+
+    bot_service.register_bot(data.into_inner()).await
+}
+
+/// Revokes a bot: its token stops working and its open sockets are
+/// disconnected on every node.
+#[endpoint(tags("admin"), status_codes(200, 401, 404, 500))]
+async fn delete_bot(
+    _res: &mut Response,
+    id: PathParam<i32>,
+    depot: &mut Depot,
+) -> Result<BotResponse, BotError> {
+    let bot_service = depot.obtain::<BotServiceImpl<BotRepositoryImpl>>().unwrap();
+    let app_events = depot.obtain::<EventSender<AppEvent>>().unwrap();
+
+    let bot = bot_service.delete_bot(id.into_inner()).await?;
+
+    app_events
+        .send(AppEvent::RevokeBot(bot.id))
+        .await
+        .map_err(|e| BotError::UnexpectedError(e.to_string()))?;
+
+    info!("Revoked bot {}", bot.id);
+
+    Ok(BotResponse { bot, token: None })
+}
+
+/// Looks up a user by public id, pass `include_deleted` to see soft-deleted accounts.
+#[endpoint(tags("admin"), status_codes(200, 401, 404, 500))]
+async fn get_user(
+    _res: &mut Response,
+    id: PathParam<String>,
+    include_deleted: QueryParam<bool, false>,
+    depot: &mut Depot,
+) -> Result<User, UserError> {
+    let user_service = depot
+        .obtain::<UserServiceImpl<UserRepositoryImpl>>()
+        .unwrap();
+
+    let scope = DeletedScope::from_include_deleted(include_deleted.into_inner().unwrap_or(false));
+
+    user_service
+        .get_user_by_public_id(&id.into_inner(), scope)
+        .await
+}
+
+/// Looks up a room by public id, pass `include_deleted` to see soft-deleted rooms.
+#[endpoint(tags("admin"), status_codes(200, 401, 404, 500))]
+async fn get_room(
+    _res: &mut Response,
+    id: PathParam<String>,
+    include_deleted: QueryParam<bool, false>,
+    depot: &mut Depot,
+) -> Result<RoomResponse, RoomError> {
+    let room_service = depot
+        .obtain::<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>()
+        .unwrap();
+
+    let scope = DeletedScope::from_include_deleted(include_deleted.into_inner().unwrap_or(false));
+
+    room_service
+        .get_room_by_public_id(&id.into_inner(), scope)
+        .await
+}
+
+/// Creates an organization along with its branding, rooms are added to it
+/// with `PUT admin/rooms/{id}/org`.
+#[endpoint(tags("admin"), status_codes(201, 400, 401, 500))]
+async fn create_org(
+    _res: &mut Response,
+    data: JsonBody<CreateOrgDto>,
+    depot: &mut Depot,
+) -> Result<BrandingResponse, OrgError> {
+    let org_service = depot.obtain::<OrgServiceImpl<OrgRepositoryImpl>>().unwrap();
+
+    org_service.create_org(data.into_inner()).await
+}
+
+/// Replaces the organization's branding, fields left out are cleared. Clients
+/// cache branding for a few minutes, joins pick it up right away.
+#[endpoint(tags("admin"), status_codes(200, 400, 401, 404, 500))]
+async fn update_branding(
+    _res: &mut Response,
+    id: PathParam<String>,
+    data: JsonBody<UpdateBrandingDto>,
+    depot: &mut Depot,
+) -> Result<BrandingResponse, OrgError> {
+    let org_service = depot.obtain::<OrgServiceImpl<OrgRepositoryImpl>>().unwrap();
+
+    org_service
+        .update_branding(&id.into_inner(), data.into_inner())
+        .await
+}
+
+/// Replaces the feature flags the organization's clients get on top of the
+/// server's, served by `GET client-config?org_id=`.
+#[endpoint(tags("admin"), status_codes(200, 400, 401, 404, 500))]
+async fn update_client_features(
+    _res: &mut Response,
+    id: PathParam<String>,
+    data: JsonBody<UpdateClientFeaturesDto>,
+    depot: &mut Depot,
+) -> Result<ClientFeaturesResponse, OrgError> {
+    let org_service = depot.obtain::<OrgServiceImpl<OrgRepositoryImpl>>().unwrap();
+
+    org_service
+        .update_client_features(&id.into_inner(), data.into_inner())
+        .await
+}
+
+/// Moves the room into an organization, its participants get the
+/// organization's branding from their next join. `null` moves it out again.
+#[endpoint(tags("admin"), status_codes(200, 401, 404, 500))]
+async fn assign_room_org(
+    _res: &mut Response,
+    id: PathParam<String>,
+    data: JsonBody<AssignOrgDto>,
+    depot: &mut Depot,
+) -> Result<RoomResponse, OrgError> {
+    let room_service = depot
+        .obtain::<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>()
+        .unwrap();
+    let org_service = depot.obtain::<OrgServiceImpl<OrgRepositoryImpl>>().unwrap();
+
+    let id = id.into_inner();
+    let mut room = room_service
+        .get_room_by_public_id(&id, DeletedScope::Exclude)
+        .await
+        .map_err(|_| OrgError::RoomNotFound(id.clone()))?;
+
+    org_service
+        .assign_room(room.room.id, data.into_inner().org_id.as_deref())
+        .await?;
+
+    room.branding = org_service.get_room_branding(room.room.id).await?;
+
+    Ok(room)
+}
+
+/// Issues a token that lets QA or compliance watch the room without joining
+/// it. Observers subscribe to the participants over `/rtc` but never publish
+/// and never show up in the participant list, hosts are told they are there
+/// unless `OBSERVER_DISCLOSURE` is `hidden`.
+#[endpoint(tags("admin"), status_codes(201, 400, 401, 404, 500))]
+async fn create_observer_token(
+    _res: &mut Response,
+    id: PathParam<String>,
+    data: JsonBody<CreateObserverTokenDto>,
+    depot: &mut Depot,
+) -> Result<ObserverTokenResponse, AdminError> {
+    let room_service = depot
+        .obtain::<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>()
+        .unwrap();
+    let jwt_utils = depot.obtain::<JwtUtils>().unwrap();
+
+    let room_id = id.into_inner();
+    let data = data.into_inner();
+
+    room_service
+        .resolve_room_id(&room_id)
+        .await
+        .map_err(|_| AdminError::RoomNotFound(room_id.clone()))?;
+
+    let label = data.label.trim();
+    if label.is_empty() || label.chars().count() > MAX_OBSERVER_LABEL_LEN {
+        return Err(AdminError::InvalidObserverToken(format!(
+            "label must be 1 to {MAX_OBSERVER_LABEL_LEN} characters"
+        )));
+    }
+
+    let expires_in_seconds = data
+        .expires_in_seconds
+        .unwrap_or(DEFAULT_OBSERVER_TOKEN_SECONDS);
+    if !(1..=MAX_OBSERVER_TOKEN_SECONDS).contains(&expires_in_seconds) {
+        return Err(AdminError::InvalidObserverToken(format!(
+            "expiresInSeconds must be 1 to {MAX_OBSERVER_TOKEN_SECONDS}"
+        )));
+    }
+
+    let observer_id = generate_observer_id();
+    let (token, expires_at) = jwt_utils.generate_observer_token(
+        &room_id,
+        &observer_id,
+        label,
+        time::Duration::seconds(expires_in_seconds),
+    );
+
+    info!(
+        "Issued observer token {} ({}) for room {}",
+        observer_id, label, room_id
+    );
+
+    Ok(ObserverTokenResponse {
+        token,
+        observer_id,
+        room_id,
+        expires_at,
+    })
+}
+
+/// Places the room under legal hold: its messages are skipped by the retention
+/// purge until the hold is lifted. Members aren't told.
+#[endpoint(tags("admin"), status_codes(200, 401, 404, 500))]
+async fn place_legal_hold(
+    _res: &mut Response,
+    id: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<LegalHoldResponse, ComplianceError> {
+    let compliance_service = depot
+        .obtain::<ComplianceServiceImpl<ComplianceRepositoryImpl>>()
+        .unwrap();
+
+    compliance_service
+        .set_legal_hold(&id.into_inner(), true)
+        .await
+}
+
+/// Lifts the legal hold, the room's retention applies again from the next purge.
+#[endpoint(tags("admin"), status_codes(200, 401, 404, 500))]
+async fn lift_legal_hold(
+    _res: &mut Response,
+    id: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<LegalHoldResponse, ComplianceError> {
+    let compliance_service = depot
+        .obtain::<ComplianceServiceImpl<ComplianceRepositoryImpl>>()
+        .unwrap();
+
+    compliance_service
+        .set_legal_hold(&id.into_inner(), false)
+        .await
+}
+
+/// Downloads everything kept about a room: messages including deleted ones,
+/// its event history and the manifests of its recordings, with SHA-256 digests
+/// to verify the bundle later. Check `unreachableNodes` before relying on it.
+#[endpoint(tags("admin"), status_codes(200, 401, 404, 500))]
+async fn get_compliance_export(
+    _res: &mut Response,
+    id: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<ComplianceExportResponse, ComplianceError> {
+    let compliance_service = depot
+        .obtain::<ComplianceServiceImpl<ComplianceRepositoryImpl>>()
+        .unwrap();
+    let dispatcher_manager = depot.obtain::<DispatcherManager>().unwrap();
+
+    let room_id = id.into_inner();
+
+    let (manifests, unreachable_nodes) = dispatcher_manager
+        .get_recording_manifests(GetRecordingManifestsRequest {
+            room_id: room_id.clone(),
+        })
+        .await;
+
+    let recordings = manifests
+        .into_iter()
+        .map(|(node_id, manifest)| {
+            to_recording(node_id, manifest.participant_id, &manifest.manifest_json)
+        })
+        .collect();
+
+    compliance_service
+        .export_room(&room_id, recordings, unreachable_nodes)
+        .await
+}
+
+fn to_response(dead_letter: DeadLetter) -> DeadLetterResponse {
+    DeadLetterResponse {
+        id: dead_letter.id,
+        kind: dead_letter.kind,
+        client_id: dead_letter.client_id,
+        reason: dead_letter.reason,
+    }
+}
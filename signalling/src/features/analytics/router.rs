@@ -0,0 +1,139 @@
+use dispatcher::dispatcher_manager::DispatcherManager;
+use salvo::{
+    oapi::extract::{PathParam, QueryParam},
+    prelude::*,
+    rate_limiter::{BasicQuota, FixedGuard, MokaStore, RateLimiter, RemoteIpIssuer},
+};
+use serde_json::json;
+use tracing::warn;
+
+use crate::core::{
+    dtos::analytics::hls_telemetry_dto::HlsTelemetryDto,
+    types::{
+        errors::{admin_error::AdminError, auth_error::AuthError},
+        responses::{
+            client_quality_response::ClientQualityResponse,
+            hls_telemetry_response::HlsTelemetryResponse, room_stats_response::RoomStatsResponse,
+        },
+    },
+    utils::{
+        api_key_utils::admin_key_middleware, client_stats_store::ClientStatsStore,
+        hls_telemetry_store::HlsTelemetryStore, jwt_utils::JwtUtils,
+    },
+};
+
+/// Players can't attach our API key to a beacon, the signed HLS token in the
+/// path authenticates it instead
+pub fn get_hls_beacon_router(jwt_utils: JwtUtils) -> Router {
+    Router::with_path("hls/{token}/telemetry")
+        .hoop(affix_state::inject(jwt_utils))
+        .hoop(RateLimiter::new(
+            FixedGuard::new(),
+            MokaStore::new(),
+            RemoteIpIssuer,
+            BasicQuota::per_second(5),
+        ))
+        .post(post_hls_telemetry)
+}
+
+pub fn get_analytics_router() -> Router {
+    Router::with_hoop(admin_key_middleware())
+        .path("analytics")
+        .push(Router::with_path("rooms/{room_id}/hls").get(get_hls_telemetry))
+        .push(Router::with_path("rooms/{room_id}/quality").get(get_client_quality))
+        .push(Router::with_path("rooms/{room_id}/sfu").get(get_room_sfu_stats))
+}
+
+/// `navigator.sendBeacon` posts strings as `text/plain`, so the body is parsed
+/// as JSON whatever the content type says
+#[handler]
+async fn post_hls_telemetry(req: &mut Request, depot: &mut Depot, res: &mut Response) {
+    let token = req.param::<String>("token").unwrap_or_default();
+
+    let jwt_utils = depot.obtain::<JwtUtils>().unwrap();
+    let telemetry_store = depot.obtain::<HlsTelemetryStore>().unwrap();
+
+    let Ok(claims) = jwt_utils.decode_hls_token(&token) else {
+        res.status_code(StatusCode::UNAUTHORIZED);
+        return res.render(Json(AuthError::InvalidToken));
+    };
+
+    let beacon = match req
+        .payload()
+        .await
+        .map(|body| serde_json::from_slice::<HlsTelemetryDto>(body))
+    {
+        Ok(Ok(beacon)) => beacon,
+        _ => {
+            res.status_code(StatusCode::BAD_REQUEST);
+            return res.render(Json(json!({ "message": "Invalid telemetry beacon" })));
+        }
+    };
+
+    if let Err(err) = telemetry_store
+        .record(
+            &claims.room_id,
+            &claims.viewer_id,
+            &claims.participant_id,
+            beacon,
+        )
+        .await
+    {
+        warn!("Failed to record HLS telemetry: {:?}", err);
+        res.status_code(StatusCode::INTERNAL_SERVER_ERROR);
+        return;
+    }
+
+    res.status_code(StatusCode::NO_CONTENT);
+}
+
+/// Startup time, rebuffering and rendition choice of the HLS viewers of a room, per session.
+#[endpoint(tags("analytics"), status_codes(200, 401, 500))]
+async fn get_hls_telemetry(
+    _res: &mut Response,
+    room_id: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<HlsTelemetryResponse, AdminError> {
+    let telemetry_store = depot.obtain::<HlsTelemetryStore>().unwrap();
+
+    telemetry_store
+        .get_room_summary(&room_id.into_inner())
+        .await
+        .map_err(|e| AdminError::UnexpectedError(e.to_string()))
+}
+
+/// Call quality reported by the WebRTC clients of a room, per participant and
+/// per app version. `client_version` narrows it down to a single version.
+#[endpoint(tags("analytics"), status_codes(200, 401, 500))]
+async fn get_client_quality(
+    _res: &mut Response,
+    room_id: PathParam<String>,
+    client_version: QueryParam<String, false>,
+    depot: &mut Depot,
+) -> Result<ClientQualityResponse, AdminError> {
+    let client_stats_store = depot.obtain::<ClientStatsStore>().unwrap();
+
+    let client_version = client_version.into_inner();
+
+    client_stats_store
+        .get_room_quality(&room_id.into_inner(), client_version.as_deref())
+        .await
+        .map_err(|e| AdminError::UnexpectedError(e.to_string()))
+}
+
+/// Participants, bitrate and node placement of a room, merged from every SFU
+/// node holding part of it
+#[endpoint(tags("analytics"), status_codes(200, 401, 500))]
+async fn get_room_sfu_stats(
+    _res: &mut Response,
+    room_id: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<RoomStatsResponse, AdminError> {
+    let dispatcher_manager = depot.obtain::<DispatcherManager>().unwrap();
+
+    let view = dispatcher_manager
+        .get_room_stats(&room_id.into_inner())
+        .await;
+
+    Ok(view.into())
+}
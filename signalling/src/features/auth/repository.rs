@@ -6,7 +6,11 @@ use diesel::{
 use salvo::async_trait;
 
 use crate::core::{
-    database::schema::users,
+    database::{
+        db::checkout,
+        schema::users,
+        soft_delete::{DeletedScope, users_in_scope},
+    },
     entities::models::{NewUser, User},
     types::errors::{auth_error::AuthError, general::GeneralError},
 };
@@ -33,7 +37,7 @@ impl AuthRepositoryImpl {
     }
 
     fn get_conn(&self) -> Result<PooledConnection<ConnectionManager<PgConnection>>, GeneralError> {
-        self.pool.get().map_err(|_| GeneralError::DbConnectionError)
+        checkout(&self.pool)
     }
 }
 
@@ -60,6 +64,7 @@ impl AuthRepository for AuthRepositoryImpl {
 
         let user = users::table
             .filter(users::id.eq(id))
+            .filter(users_in_scope(DeletedScope::Exclude))
             .first::<User>(&mut conn);
 
         match user {
@@ -73,6 +78,7 @@ impl AuthRepository for AuthRepositoryImpl {
 
         let user = users::table
             .filter(users::external_id.eq(external_id))
+            .filter(users_in_scope(DeletedScope::Exclude))
             .first::<User>(&mut conn);
 
         match user {
@@ -86,6 +92,7 @@ impl AuthRepository for AuthRepositoryImpl {
 
         let user = users::table
             .filter(users::user_name.eq(username))
+            .filter(users_in_scope(DeletedScope::Exclude))
             .first::<User>(&mut conn);
 
         match user {
@@ -143,6 +143,9 @@ mod tests {
             updated_at: now,
             deleted_at: None,
             last_seen_at: None,
+            public_id: format!("U{id:025}"),
+            locale: "en-US".to_string(),
+            timezone: "UTC".to_string(),
         }
     }
 
@@ -155,14 +158,25 @@ mod tests {
 
     // Helper to create a dummy AppEnv for JwtUtils
     fn dummy_app_env() -> crate::core::env::app_env::AppEnv {
-        use crate::core::env::app_env::{AppEnv, DbUri, GrpcConfigs, JwtConfig, UdpPortRange};
+        use crate::core::env::app_env::{
+            AlertingConfigs, AppEnv, ChatRetentionConfigs, ClientConfigs, DbPoolConfigs, DbUri,
+            EventQueueConfigs, EventRate, GrpcConfigs, JwtConfig, SocketRateLimits, UdpPortRange,
+        };
+        use crate::core::types::enums::observer_disclosure::ObserverDisclosure;
         AppEnv {
             group_id: "test-group".to_string(),
             etcd_addr: "localhost:2379".to_string(),
             public_ip: "127.0.0.1".to_string(),
             app_port: 1234,
             client_api_key: "dummy".to_string(),
+            admin_api_key: "dummy".to_string(),
             db_uri: DbUri("dummy_db_uri".to_string()),
+            db_pool: DbPoolConfigs {
+                max_size: 10,
+                min_idle: None,
+                checkout_timeout_ms: 5_000,
+                statement_timeout_ms: 0,
+            },
             redis_uris: vec!["redis://localhost:6379".to_string()],
             jwt: JwtConfig {
                 jwt_token: "secret".to_string(),
@@ -180,6 +194,52 @@ mod tests {
                 dispatcher_host: "localhost".to_string(),
                 dispatcher_port: 2,
             },
+            alerting: AlertingConfigs {
+                slack_webhook_url: None,
+                pagerduty_routing_key: None,
+                cooldown_seconds: 900,
+                max_join_failure_rate: 0.05,
+                max_p95_join_latency_ms: 3000,
+                max_node_cpu: 85.0,
+                max_packet_loss: 0.05,
+            },
+            matrix: None,
+            callout: None,
+            calendar: None,
+            chat_retention: ChatRetentionConfigs {
+                default_days: None,
+                batch_size: 1000,
+                interval_seconds: 3600,
+            },
+            typesense: None,
+            moderation_webhook_url: None,
+            socket_rate_limits: SocketRateLimits {
+                rtc: EventRate {
+                    per_second: 50.0,
+                    burst: 200,
+                },
+                chat: EventRate {
+                    per_second: 5.0,
+                    burst: 20,
+                },
+            },
+            event_queues: EventQueueConfigs {
+                app_events: 10_000,
+                dispatcher_callbacks: 10_000,
+            },
+            observer_disclosure: ObserverDisclosure::Hosts,
+            hls_edge: None,
+            client: ClientConfigs {
+                ice_servers: vec!["stun:stun.l.google.com:19302".to_string()],
+                turn_username: None,
+                turn_credential: None,
+                max_video_bitrate_kbps: 1_500,
+                max_screen_bitrate_kbps: 2_500,
+                max_audio_bitrate_kbps: 64,
+                video_codecs: vec!["vp8".to_string(), "h264".to_string()],
+                features: vec![],
+                socket_url: None,
+            },
             tls_enabled: false,
         }
     }
@@ -0,0 +1,130 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::{
+    Connection, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper,
+    insert_into,
+    r2d2::{ConnectionManager, Pool, PooledConnection},
+    update,
+};
+use nanoid::nanoid;
+use salvo::async_trait;
+
+use crate::core::{
+    database::{
+        db::checkout,
+        schema::{bots, users},
+    },
+    entities::models::{Bot, NewBot, NewUser, User},
+    types::errors::{bot_error::BotError, general::GeneralError},
+    utils::id_utils::generate_username,
+};
+
+#[async_trait]
+pub trait BotRepository: Send + Sync {
+    /// Create the bot together with the user it posts and joins rooms as
+    async fn create_bot(
+        &self,
+        name: &str,
+        token_hash: &str,
+        scopes: &str,
+        created_at: NaiveDateTime,
+    ) -> Result<Bot, BotError>;
+
+    async fn get_bot_by_id(&self, bot_id: i32) -> Result<Bot, BotError>;
+
+    async fn find_all(&self) -> Result<Vec<Bot>, BotError>;
+
+    async fn delete_bot(&self, bot_id: i32) -> Result<Bot, BotError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct BotRepositoryImpl {
+    pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl BotRepositoryImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Self {
+        Self { pool }
+    }
+
+    fn get_conn(&self) -> Result<PooledConnection<ConnectionManager<PgConnection>>, GeneralError> {
+        checkout(&self.pool)
+    }
+}
+
+#[async_trait]
+impl BotRepository for BotRepositoryImpl {
+    async fn create_bot(
+        &self,
+        name: &str,
+        token_hash: &str,
+        scopes: &str,
+        created_at: NaiveDateTime,
+    ) -> Result<Bot, BotError> {
+        let mut conn = self.get_conn()?;
+
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let new_user = NewUser {
+                full_name: Some(name),
+                user_name: &generate_username(),
+                bio: None,
+                external_id: &format!("bot:{}", nanoid!(16)),
+                avatar: None,
+                created_at,
+                updated_at: created_at,
+            };
+
+            let user = insert_into(users::table)
+                .values(&new_user)
+                .returning(User::as_select())
+                .get_result(conn)?;
+
+            let new_bot = NewBot {
+                user_id: &user.id,
+                name,
+                token_hash,
+                scopes,
+                created_at,
+            };
+
+            insert_into(bots::table)
+                .values(&new_bot)
+                .returning(Bot::as_select())
+                .get_result(conn)
+        })
+        .map_err(|err| BotError::UnexpectedError(err.to_string()))
+    }
+
+    async fn get_bot_by_id(&self, bot_id: i32) -> Result<Bot, BotError> {
+        let mut conn = self.get_conn()?;
+
+        bots::table
+            .filter(bots::id.eq(bot_id))
+            .filter(bots::deleted_at.is_null())
+            .select(Bot::as_select())
+            .first::<Bot>(&mut conn)
+            .map_err(|_| BotError::BotNotFound(bot_id))
+    }
+
+    async fn find_all(&self) -> Result<Vec<Bot>, BotError> {
+        let mut conn = self.get_conn()?;
+
+        bots::table
+            .filter(bots::deleted_at.is_null())
+            .order(bots::id.asc())
+            .select(Bot::as_select())
+            .load::<Bot>(&mut conn)
+            .map_err(|err| BotError::UnexpectedError(err.to_string()))
+    }
+
+    async fn delete_bot(&self, bot_id: i32) -> Result<Bot, BotError> {
+        let mut conn = self.get_conn()?;
+
+        update(bots::table)
+            .filter(bots::id.eq(bot_id))
+            .filter(bots::deleted_at.is_null())
+            .set(bots::deleted_at.eq(Utc::now().naive_utc()))
+            .returning(Bot::as_select())
+            .get_result(&mut conn)
+            .map_err(|_| BotError::BotNotFound(bot_id))
+    }
+}
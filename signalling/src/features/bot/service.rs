@@ -0,0 +1,284 @@
+use chrono::Utc;
+use nanoid::nanoid;
+use salvo::async_trait;
+
+use crate::core::{
+    dtos::bot::register_bot_dto::RegisterBotDto,
+    entities::models::Bot,
+    types::{
+        enums::bot_scope::BotScope, errors::bot_error::BotError,
+        responses::bot_response::BotResponse,
+    },
+    utils::bcrypt_utils::{hash_password, verify_password},
+};
+
+use super::repository::BotRepository;
+
+#[async_trait]
+pub trait BotService: Send + Sync {
+    /// Register a bot, the token is only ever returned here
+    async fn register_bot(&self, data: RegisterBotDto) -> Result<BotResponse, BotError>;
+
+    async fn get_bots(&self) -> Result<Vec<Bot>, BotError>;
+
+    async fn delete_bot(&self, bot_id: i32) -> Result<Bot, BotError>;
+
+    /// Resolve a `<bot id>.<secret>` token to its bot
+    async fn authenticate(&self, token: &str) -> Result<Bot, BotError>;
+
+    fn check_scope(&self, bot: &Bot, scope: BotScope) -> Result<(), BotError>;
+
+    /// Reload the bot before a privileged action, a socket keeps the bot it
+    /// connected with and would outlive a revocation otherwise
+    async fn authorize(&self, bot_id: i32, scope: BotScope) -> Result<Bot, BotError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct BotServiceImpl<B: BotRepository> {
+    bot_repository: B,
+}
+
+impl<B: BotRepository> BotServiceImpl<B> {
+    pub fn new(bot_repository: B) -> Self {
+        Self { bot_repository }
+    }
+}
+
+#[async_trait]
+impl<B: BotRepository> BotService for BotServiceImpl<B> {
+    async fn register_bot(&self, data: RegisterBotDto) -> Result<BotResponse, BotError> {
+        let secret = nanoid!(32);
+
+        let bot = self
+            .bot_repository
+            .create_bot(
+                &data.name,
+                &hash_password(&secret),
+                &BotScope::join(&data.scopes),
+                Utc::now().naive_utc(),
+            )
+            .await?;
+
+        let token = format!("{}.{}", bot.id, secret);
+
+        Ok(BotResponse {
+            bot,
+            token: Some(token),
+        })
+    }
+
+    async fn get_bots(&self) -> Result<Vec<Bot>, BotError> {
+        self.bot_repository.find_all().await
+    }
+
+    async fn delete_bot(&self, bot_id: i32) -> Result<Bot, BotError> {
+        self.bot_repository.delete_bot(bot_id).await
+    }
+
+    async fn authenticate(&self, token: &str) -> Result<Bot, BotError> {
+        let (bot_id, secret) = token
+            .split_once('.')
+            .and_then(|(id, secret)| Some((id.parse::<i32>().ok()?, secret)))
+            .ok_or(BotError::InvalidToken)?;
+
+        let bot = self
+            .bot_repository
+            .get_bot_by_id(bot_id)
+            .await
+            .map_err(|_| BotError::InvalidToken)?;
+
+        if !verify_password(secret, &bot.token_hash) {
+            return Err(BotError::InvalidToken);
+        }
+
+        Ok(bot)
+    }
+
+    fn check_scope(&self, bot: &Bot, scope: BotScope) -> Result<(), BotError> {
+        if BotScope::parse(&bot.scopes).contains(&scope) {
+            Ok(())
+        } else {
+            Err(BotError::MissingScope(scope.to_str().to_string()))
+        }
+    }
+
+    async fn authorize(&self, bot_id: i32, scope: BotScope) -> Result<Bot, BotError> {
+        let bot = self
+            .bot_repository
+            .get_bot_by_id(bot_id)
+            .await
+            .map_err(|err| match err {
+                BotError::BotNotFound(_) => BotError::InvalidToken,
+                err => err,
+            })?;
+
+        self.check_scope(&bot, scope)?;
+
+        Ok(bot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct MockBotRepository {
+        bots: Arc<Mutex<Vec<Bot>>>,
+    }
+
+    #[async_trait]
+    impl BotRepository for MockBotRepository {
+        async fn create_bot(
+            &self,
+            name: &str,
+            token_hash: &str,
+            scopes: &str,
+            created_at: NaiveDateTime,
+        ) -> Result<Bot, BotError> {
+            let mut bots = self.bots.lock().unwrap();
+            let id = bots.len() as i32 + 1;
+            let bot = Bot {
+                id,
+                user_id: id + 100,
+                name: name.to_string(),
+                token_hash: token_hash.to_string(),
+                scopes: scopes.to_string(),
+                created_at,
+                deleted_at: None,
+            };
+            bots.push(bot.clone());
+            Ok(bot)
+        }
+
+        async fn get_bot_by_id(&self, bot_id: i32) -> Result<Bot, BotError> {
+            self.bots
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|b| b.id == bot_id && b.deleted_at.is_none())
+                .cloned()
+                .ok_or(BotError::BotNotFound(bot_id))
+        }
+
+        async fn find_all(&self) -> Result<Vec<Bot>, BotError> {
+            Ok(self.bots.lock().unwrap().clone())
+        }
+
+        async fn delete_bot(&self, bot_id: i32) -> Result<Bot, BotError> {
+            let mut bots = self.bots.lock().unwrap();
+            let bot = bots
+                .iter_mut()
+                .find(|b| b.id == bot_id)
+                .ok_or(BotError::BotNotFound(bot_id))?;
+            bot.deleted_at = Some(Utc::now().naive_utc());
+            Ok(bot.clone())
+        }
+    }
+
+    fn register_dto(scopes: Vec<BotScope>) -> RegisterBotDto {
+        RegisterBotDto {
+            name: "Standup bot".to_string(),
+            scopes,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_register_and_authenticate() {
+        let service = BotServiceImpl::new(MockBotRepository::default());
+
+        let registered = service
+            .register_bot(register_dto(vec![BotScope::ChatRead, BotScope::ChatWrite]))
+            .await
+            .unwrap();
+        let token = registered.token.unwrap();
+
+        let bot = service.authenticate(&token).await.unwrap();
+        assert_eq!(bot.id, registered.bot.id);
+        assert_eq!(bot.scopes, "chat:read,chat:write");
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_rejects_wrong_secret() {
+        let service = BotServiceImpl::new(MockBotRepository::default());
+
+        let registered = service
+            .register_bot(register_dto(vec![BotScope::ChatRead]))
+            .await
+            .unwrap();
+
+        let result = service
+            .authenticate(&format!("{}.not-the-secret", registered.bot.id))
+            .await;
+        assert!(matches!(result, Err(BotError::InvalidToken)));
+
+        let result = service.authenticate("garbage").await;
+        assert!(matches!(result, Err(BotError::InvalidToken)));
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_rejects_deleted_bot() {
+        let service = BotServiceImpl::new(MockBotRepository::default());
+
+        let registered = service
+            .register_bot(register_dto(vec![BotScope::ChatRead]))
+            .await
+            .unwrap();
+        service.delete_bot(registered.bot.id).await.unwrap();
+
+        let result = service.authenticate(&registered.token.unwrap()).await;
+        assert!(matches!(result, Err(BotError::InvalidToken)));
+    }
+
+    #[tokio::test]
+    async fn test_check_scope() {
+        let service = BotServiceImpl::new(MockBotRepository::default());
+
+        let registered = service
+            .register_bot(register_dto(vec![BotScope::ChatRead]))
+            .await
+            .unwrap();
+
+        assert!(
+            service
+                .check_scope(&registered.bot, BotScope::ChatRead)
+                .is_ok()
+        );
+        assert!(matches!(
+            service.check_scope(&registered.bot, BotScope::RecordingControl),
+            Err(BotError::MissingScope(scope)) if scope == "recording:control"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_authorize_rejects_revoked_bot() {
+        let service = BotServiceImpl::new(MockBotRepository::default());
+
+        let registered = service
+            .register_bot(register_dto(vec![BotScope::ChatWrite]))
+            .await
+            .unwrap();
+
+        // What an open socket holds from when it connected
+        let connected = service
+            .authenticate(&registered.token.unwrap())
+            .await
+            .unwrap();
+
+        assert!(
+            service
+                .authorize(connected.id, BotScope::ChatWrite)
+                .await
+                .is_ok()
+        );
+
+        service.delete_bot(connected.id).await.unwrap();
+
+        assert!(matches!(
+            service.authorize(connected.id, BotScope::ChatWrite).await,
+            Err(BotError::InvalidToken)
+        ));
+    }
+}
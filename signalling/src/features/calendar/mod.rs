@@ -0,0 +1,5 @@
+pub mod provider;
+pub mod renewer;
+pub mod repository;
+pub mod router;
+pub mod service;
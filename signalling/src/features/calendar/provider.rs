@@ -0,0 +1,715 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use reqwest::{StatusCode, Url};
+use salvo::{async_trait, http::HeaderMap};
+use serde_json::{Value, json};
+
+use crate::core::{
+    env::app_env::{CalendarConfigs, OAuthClientConfigs},
+    types::errors::calendar_error::CalendarError,
+};
+
+/// Google caps push channels at a week, Graph caps event subscriptions just under three days
+const GOOGLE_WATCH_TTL: Duration = Duration::days(7);
+const OUTLOOK_WATCH_TTL: Duration = Duration::minutes(4_200);
+
+const GOOGLE_EVENTS_URL: &str = "https://www.googleapis.com/calendar/v3/calendars/primary/events";
+const OUTLOOK_EVENTS_URL: &str = "https://graph.microsoft.com/v1.0/me/events";
+const OUTLOOK_SCOPE: &str = "offline_access Calendars.ReadWrite";
+
+#[derive(Debug, Clone)]
+pub struct CalendarTokens {
+    pub access_token: String,
+    /// Only sent on the first grant by some providers, keep the old one otherwise
+    pub refresh_token: Option<String>,
+    pub expires_at: NaiveDateTime,
+}
+
+/// What a scheduled live stream looks like on the calendar
+#[derive(Debug, Clone)]
+pub struct CalendarEventInput {
+    pub title: String,
+    pub join_url: String,
+    pub start_at: NaiveDateTime,
+    pub end_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct CalendarWatch {
+    pub channel_id: String,
+    pub expires_at: NaiveDateTime,
+}
+
+/// A change pushed to the webhook, for the channel it was registered with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalendarNotification {
+    pub channel_id: String,
+    pub token: String,
+}
+
+/// A calendar service hosts connect over OAuth. Events are written to the
+/// account's primary calendar, changes to them are pushed back to our webhook.
+#[async_trait]
+pub trait CalendarProvider: Send + Sync + fmt::Debug {
+    fn name(&self) -> &'static str;
+
+    fn authorize_url(&self, state: &str) -> String;
+
+    async fn exchange_code(&self, code: &str) -> Result<CalendarTokens, CalendarError>;
+
+    async fn refresh(&self, refresh_token: &str) -> Result<CalendarTokens, CalendarError>;
+
+    /// Creates the event, or updates it when `event_id` is set, returns the event id
+    async fn upsert_event(
+        &self,
+        access_token: &str,
+        event_id: Option<&str>,
+        event: &CalendarEventInput,
+    ) -> Result<String, CalendarError>;
+
+    /// Events already gone count as deleted
+    async fn delete_event(&self, access_token: &str, event_id: &str) -> Result<(), CalendarError>;
+
+    /// When the event starts, `None` once it was cancelled or deleted
+    async fn get_event_start(
+        &self,
+        access_token: &str,
+        event_id: &str,
+    ) -> Result<Option<NaiveDateTime>, CalendarError>;
+
+    /// Registers a push channel for changes to the primary calendar.
+    /// `channel_id` is a suggestion, providers that assign their own return it.
+    async fn watch(
+        &self,
+        access_token: &str,
+        channel_id: &str,
+        token: &str,
+    ) -> Result<CalendarWatch, CalendarError>;
+
+    /// Channels a webhook request notifies about, empty for handshakes
+    fn parse_notifications(&self, headers: &HeaderMap, body: &[u8]) -> Vec<CalendarNotification>;
+}
+
+/// The providers a deployment has OAuth clients for, by name
+#[derive(Debug, Clone, Default)]
+pub struct CalendarProviders(HashMap<&'static str, Arc<dyn CalendarProvider>>);
+
+impl CalendarProviders {
+    pub fn get(&self, name: &str) -> Result<Arc<dyn CalendarProvider>, CalendarError> {
+        self.0
+            .get(name)
+            .cloned()
+            .ok_or_else(|| CalendarError::UnknownProvider(name.to_string()))
+    }
+}
+
+pub fn build_providers(configs: &CalendarConfigs) -> CalendarProviders {
+    let mut providers: HashMap<&'static str, Arc<dyn CalendarProvider>> = HashMap::new();
+
+    if let Some(client) = &configs.google {
+        let provider = GoogleCalendarProvider::new(client.clone(), &configs.public_url);
+        providers.insert(provider.name(), Arc::new(provider));
+    }
+
+    if let Some(client) = &configs.outlook {
+        let provider = OutlookCalendarProvider::new(client.clone(), &configs.public_url);
+        providers.insert(provider.name(), Arc::new(provider));
+    }
+
+    CalendarProviders(providers)
+}
+
+fn redirect_uri(public_url: &str, provider: &str) -> String {
+    format!("{public_url}/busapi/v3/calendar/{provider}/callback")
+}
+
+fn webhook_url(public_url: &str, provider: &str) -> String {
+    format!("{public_url}/busapi/v3/calendar/webhooks/{provider}")
+}
+
+fn description(event: &CalendarEventInput) -> String {
+    format!("Join the live stream on Waterbus: {}", event.join_url)
+}
+
+pub struct GoogleCalendarProvider {
+    http: reqwest::Client,
+    client: OAuthClientConfigs,
+    redirect_uri: String,
+    webhook_url: String,
+}
+
+impl fmt::Debug for GoogleCalendarProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GoogleCalendarProvider")
+            .field("client_id", &self.client.client_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl GoogleCalendarProvider {
+    pub fn new(client: OAuthClientConfigs, public_url: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            client,
+            redirect_uri: redirect_uri(public_url, "google"),
+            webhook_url: webhook_url(public_url, "google"),
+        }
+    }
+
+    fn event_body(event: &CalendarEventInput) -> Value {
+        json!({
+            "summary": event.title,
+            "description": description(event),
+            "location": event.join_url,
+            "start": { "dateTime": event.start_at.and_utc().to_rfc3339(), "timeZone": "UTC" },
+            "end": { "dateTime": event.end_at.and_utc().to_rfc3339(), "timeZone": "UTC" },
+        })
+    }
+
+    async fn token_request(&self, form: &[(&str, &str)]) -> Result<CalendarTokens, CalendarError> {
+        let response = self
+            .http
+            .post("https://oauth2.googleapis.com/token")
+            .form(form)
+            .send()
+            .await
+            .map_err(|e| CalendarError::ProviderError(e.to_string()))?;
+
+        parse_tokens(parse_response(response).await?)
+    }
+}
+
+#[async_trait]
+impl CalendarProvider for GoogleCalendarProvider {
+    fn name(&self) -> &'static str {
+        "google"
+    }
+
+    fn authorize_url(&self, state: &str) -> String {
+        // Offline access with a forced consent is what gets a refresh token every time
+        Url::parse_with_params(
+            "https://accounts.google.com/o/oauth2/v2/auth",
+            &[
+                ("client_id", self.client.client_id.as_str()),
+                ("redirect_uri", self.redirect_uri.as_str()),
+                ("response_type", "code"),
+                ("scope", "https://www.googleapis.com/auth/calendar.events"),
+                ("access_type", "offline"),
+                ("prompt", "consent"),
+                ("state", state),
+            ],
+        )
+        .unwrap()
+        .to_string()
+    }
+
+    async fn exchange_code(&self, code: &str) -> Result<CalendarTokens, CalendarError> {
+        self.token_request(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("client_id", &self.client.client_id),
+            ("client_secret", &self.client.client_secret),
+            ("redirect_uri", &self.redirect_uri),
+        ])
+        .await
+    }
+
+    async fn refresh(&self, refresh_token: &str) -> Result<CalendarTokens, CalendarError> {
+        self.token_request(&[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+            ("client_id", &self.client.client_id),
+            ("client_secret", &self.client.client_secret),
+        ])
+        .await
+    }
+
+    async fn upsert_event(
+        &self,
+        access_token: &str,
+        event_id: Option<&str>,
+        event: &CalendarEventInput,
+    ) -> Result<String, CalendarError> {
+        let request = match event_id {
+            Some(event_id) => self
+                .http
+                .patch(format!("{}/{}", GOOGLE_EVENTS_URL, event_id)),
+            None => self.http.post(GOOGLE_EVENTS_URL),
+        };
+
+        let response = request
+            .bearer_auth(access_token)
+            .json(&Self::event_body(event))
+            .send()
+            .await
+            .map_err(|e| CalendarError::ProviderError(e.to_string()))?;
+
+        let value = parse_response(response).await?;
+
+        value["id"]
+            .as_str()
+            .map(str::to_owned)
+            .ok_or_else(|| CalendarError::ProviderError("Missing event id".into()))
+    }
+
+    async fn delete_event(&self, access_token: &str, event_id: &str) -> Result<(), CalendarError> {
+        let response = self
+            .http
+            .delete(format!("{}/{}", GOOGLE_EVENTS_URL, event_id))
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .map_err(|e| CalendarError::ProviderError(e.to_string()))?;
+
+        if is_gone(response.status()) {
+            return Ok(());
+        }
+
+        parse_response(response).await.map(|_| ())
+    }
+
+    async fn get_event_start(
+        &self,
+        access_token: &str,
+        event_id: &str,
+    ) -> Result<Option<NaiveDateTime>, CalendarError> {
+        let response = self
+            .http
+            .get(format!("{}/{}", GOOGLE_EVENTS_URL, event_id))
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .map_err(|e| CalendarError::ProviderError(e.to_string()))?;
+
+        if is_gone(response.status()) {
+            return Ok(None);
+        }
+
+        let value = parse_response(response).await?;
+
+        if value["status"] == "cancelled" {
+            return Ok(None);
+        }
+
+        value["start"]["dateTime"]
+            .as_str()
+            .and_then(|start| DateTime::parse_from_rfc3339(start).ok())
+            .map(|start| Some(start.naive_utc()))
+            .ok_or_else(|| CalendarError::ProviderError("Missing event start".into()))
+    }
+
+    async fn watch(
+        &self,
+        access_token: &str,
+        channel_id: &str,
+        token: &str,
+    ) -> Result<CalendarWatch, CalendarError> {
+        let body = json!({
+            "id": channel_id,
+            "type": "web_hook",
+            "address": self.webhook_url,
+            "token": token,
+            "params": { "ttl": GOOGLE_WATCH_TTL.num_seconds().to_string() },
+        });
+
+        let response = self
+            .http
+            .post(format!("{}/watch", GOOGLE_EVENTS_URL))
+            .bearer_auth(access_token)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| CalendarError::ProviderError(e.to_string()))?;
+
+        let value = parse_response(response).await?;
+
+        // Expiration comes back as a string of unix milliseconds
+        let expires_at = value["expiration"]
+            .as_str()
+            .and_then(|ms| ms.parse::<i64>().ok())
+            .and_then(DateTime::from_timestamp_millis)
+            .map(|at| at.naive_utc())
+            .unwrap_or_else(|| (Utc::now() + GOOGLE_WATCH_TTL).naive_utc());
+
+        Ok(CalendarWatch {
+            channel_id: channel_id.to_string(),
+            expires_at,
+        })
+    }
+
+    fn parse_notifications(&self, headers: &HeaderMap, _body: &[u8]) -> Vec<CalendarNotification> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|h| h.to_str().ok())
+                .map(str::to_owned)
+        };
+
+        // Google confirms a new channel with a `sync` message, nothing changed yet
+        if header("X-Goog-Resource-State").as_deref() == Some("sync") {
+            return vec![];
+        }
+
+        match (header("X-Goog-Channel-ID"), header("X-Goog-Channel-Token")) {
+            (Some(channel_id), Some(token)) => vec![CalendarNotification { channel_id, token }],
+            _ => vec![],
+        }
+    }
+}
+
+pub struct OutlookCalendarProvider {
+    http: reqwest::Client,
+    client: OAuthClientConfigs,
+    redirect_uri: String,
+    webhook_url: String,
+}
+
+impl fmt::Debug for OutlookCalendarProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OutlookCalendarProvider")
+            .field("client_id", &self.client.client_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl OutlookCalendarProvider {
+    pub fn new(client: OAuthClientConfigs, public_url: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            client,
+            redirect_uri: redirect_uri(public_url, "outlook"),
+            webhook_url: webhook_url(public_url, "outlook"),
+        }
+    }
+
+    fn event_body(event: &CalendarEventInput) -> Value {
+        json!({
+            "subject": event.title,
+            "body": { "contentType": "text", "content": description(event) },
+            "location": { "displayName": event.join_url },
+            "start": { "dateTime": graph_date_time(event.start_at), "timeZone": "UTC" },
+            "end": { "dateTime": graph_date_time(event.end_at), "timeZone": "UTC" },
+        })
+    }
+
+    async fn token_request(&self, form: &[(&str, &str)]) -> Result<CalendarTokens, CalendarError> {
+        let response = self
+            .http
+            .post("https://login.microsoftonline.com/common/oauth2/v2.0/token")
+            .form(form)
+            .send()
+            .await
+            .map_err(|e| CalendarError::ProviderError(e.to_string()))?;
+
+        parse_tokens(parse_response(response).await?)
+    }
+}
+
+#[async_trait]
+impl CalendarProvider for OutlookCalendarProvider {
+    fn name(&self) -> &'static str {
+        "outlook"
+    }
+
+    fn authorize_url(&self, state: &str) -> String {
+        Url::parse_with_params(
+            "https://login.microsoftonline.com/common/oauth2/v2.0/authorize",
+            &[
+                ("client_id", self.client.client_id.as_str()),
+                ("redirect_uri", self.redirect_uri.as_str()),
+                ("response_type", "code"),
+                ("response_mode", "query"),
+                ("scope", OUTLOOK_SCOPE),
+                ("state", state),
+            ],
+        )
+        .unwrap()
+        .to_string()
+    }
+
+    async fn exchange_code(&self, code: &str) -> Result<CalendarTokens, CalendarError> {
+        self.token_request(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("client_id", &self.client.client_id),
+            ("client_secret", &self.client.client_secret),
+            ("redirect_uri", &self.redirect_uri),
+            ("scope", OUTLOOK_SCOPE),
+        ])
+        .await
+    }
+
+    async fn refresh(&self, refresh_token: &str) -> Result<CalendarTokens, CalendarError> {
+        self.token_request(&[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+            ("client_id", &self.client.client_id),
+            ("client_secret", &self.client.client_secret),
+            ("scope", OUTLOOK_SCOPE),
+        ])
+        .await
+    }
+
+    async fn upsert_event(
+        &self,
+        access_token: &str,
+        event_id: Option<&str>,
+        event: &CalendarEventInput,
+    ) -> Result<String, CalendarError> {
+        let request = match event_id {
+            Some(event_id) => self
+                .http
+                .patch(format!("{}/{}", OUTLOOK_EVENTS_URL, event_id)),
+            None => self.http.post(OUTLOOK_EVENTS_URL),
+        };
+
+        let response = request
+            .bearer_auth(access_token)
+            .json(&Self::event_body(event))
+            .send()
+            .await
+            .map_err(|e| CalendarError::ProviderError(e.to_string()))?;
+
+        let value = parse_response(response).await?;
+
+        value["id"]
+            .as_str()
+            .map(str::to_owned)
+            .ok_or_else(|| CalendarError::ProviderError("Missing event id".into()))
+    }
+
+    async fn delete_event(&self, access_token: &str, event_id: &str) -> Result<(), CalendarError> {
+        let response = self
+            .http
+            .delete(format!("{}/{}", OUTLOOK_EVENTS_URL, event_id))
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .map_err(|e| CalendarError::ProviderError(e.to_string()))?;
+
+        if is_gone(response.status()) {
+            return Ok(());
+        }
+
+        // Deletes answer 204 without a body
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(CalendarError::ProviderError(format!("{status}: {body}")));
+        }
+
+        Ok(())
+    }
+
+    async fn get_event_start(
+        &self,
+        access_token: &str,
+        event_id: &str,
+    ) -> Result<Option<NaiveDateTime>, CalendarError> {
+        let response = self
+            .http
+            .get(format!("{}/{}", OUTLOOK_EVENTS_URL, event_id))
+            .bearer_auth(access_token)
+            .header("Prefer", "outlook.timezone=\"UTC\"")
+            .send()
+            .await
+            .map_err(|e| CalendarError::ProviderError(e.to_string()))?;
+
+        if is_gone(response.status()) {
+            return Ok(None);
+        }
+
+        let value = parse_response(response).await?;
+
+        if value["isCancelled"] == true {
+            return Ok(None);
+        }
+
+        value["start"]["dateTime"]
+            .as_str()
+            .and_then(parse_graph_date_time)
+            .map(Some)
+            .ok_or_else(|| CalendarError::ProviderError("Missing event start".into()))
+    }
+
+    async fn watch(
+        &self,
+        access_token: &str,
+        _channel_id: &str,
+        token: &str,
+    ) -> Result<CalendarWatch, CalendarError> {
+        let expires_at = (Utc::now() + OUTLOOK_WATCH_TTL).naive_utc();
+        let body = json!({
+            "changeType": "updated,deleted",
+            "notificationUrl": self.webhook_url,
+            "resource": "me/events",
+            "expirationDateTime": expires_at.and_utc().to_rfc3339(),
+            "clientState": token,
+        });
+
+        let response = self
+            .http
+            .post("https://graph.microsoft.com/v1.0/subscriptions")
+            .bearer_auth(access_token)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| CalendarError::ProviderError(e.to_string()))?;
+
+        let value = parse_response(response).await?;
+
+        // Graph names the subscription itself, its id is what notifications carry
+        let channel_id = value["id"]
+            .as_str()
+            .map(str::to_owned)
+            .ok_or_else(|| CalendarError::ProviderError("Missing subscription id".into()))?;
+
+        Ok(CalendarWatch {
+            channel_id,
+            expires_at,
+        })
+    }
+
+    fn parse_notifications(&self, _headers: &HeaderMap, body: &[u8]) -> Vec<CalendarNotification> {
+        let Ok(value) = serde_json::from_slice::<Value>(body) else {
+            return vec![];
+        };
+
+        value["value"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|notification| {
+                Some(CalendarNotification {
+                    channel_id: notification["subscriptionId"].as_str()?.to_owned(),
+                    token: notification["clientState"].as_str()?.to_owned(),
+                })
+            })
+            .collect()
+    }
+}
+
+async fn parse_response(response: reqwest::Response) -> Result<Value, CalendarError> {
+    let status = response.status();
+    let value = response.json::<Value>().await.unwrap_or(Value::Null);
+
+    if !status.is_success() {
+        return Err(CalendarError::ProviderError(format!("{status}: {value}")));
+    }
+
+    Ok(value)
+}
+
+fn parse_tokens(value: Value) -> Result<CalendarTokens, CalendarError> {
+    let access_token = value["access_token"]
+        .as_str()
+        .map(str::to_owned)
+        .ok_or_else(|| CalendarError::ProviderError("Missing access token".into()))?;
+    let expires_in = value["expires_in"].as_i64().unwrap_or(3_600);
+
+    Ok(CalendarTokens {
+        access_token,
+        refresh_token: value["refresh_token"].as_str().map(str::to_owned),
+        expires_at: (Utc::now() + Duration::seconds(expires_in)).naive_utc(),
+    })
+}
+
+fn is_gone(status: StatusCode) -> bool {
+    status == StatusCode::NOT_FOUND || status == StatusCode::GONE
+}
+
+/// Graph takes local times next to a `timeZone`, an offset suffix is rejected
+fn graph_date_time(at: NaiveDateTime) -> String {
+    at.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+/// Graph answers with seven fractional digits, e.g. `2025-09-01T18:00:00.0000000`
+fn parse_graph_date_time(at: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(at, "%Y-%m-%dT%H:%M:%S%.f").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use salvo::http::HeaderValue;
+
+    fn client() -> OAuthClientConfigs {
+        OAuthClientConfigs {
+            client_id: "client".to_string(),
+            client_secret: "secret".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_authorize_url_carries_the_state_and_redirect() {
+        let provider = GoogleCalendarProvider::new(client(), "https://api.example.com");
+
+        let url = Url::parse(&provider.authorize_url("signed-state")).unwrap();
+        let params: HashMap<_, _> = url.query_pairs().into_owned().collect();
+
+        assert_eq!(params["state"], "signed-state");
+        assert_eq!(
+            params["redirect_uri"],
+            "https://api.example.com/busapi/v3/calendar/google/callback"
+        );
+        assert_eq!(params["access_type"], "offline");
+    }
+
+    #[test]
+    fn test_google_sync_message_is_not_a_change() {
+        let provider = GoogleCalendarProvider::new(client(), "https://api.example.com");
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Goog-Channel-ID", HeaderValue::from_static("channel"));
+        headers.insert("X-Goog-Channel-Token", HeaderValue::from_static("token"));
+        headers.insert("X-Goog-Resource-State", HeaderValue::from_static("sync"));
+
+        assert!(provider.parse_notifications(&headers, b"").is_empty());
+
+        headers.insert("X-Goog-Resource-State", HeaderValue::from_static("exists"));
+        assert_eq!(
+            provider.parse_notifications(&headers, b""),
+            vec![CalendarNotification {
+                channel_id: "channel".to_string(),
+                token: "token".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_outlook_notifications_are_read_from_the_body() {
+        let provider = OutlookCalendarProvider::new(client(), "https://api.example.com");
+        let body = json!({
+            "value": [
+                { "subscriptionId": "sub-1", "clientState": "token", "changeType": "updated" },
+                { "subscriptionId": "sub-2", "changeType": "deleted" },
+            ]
+        });
+
+        assert_eq!(
+            provider.parse_notifications(&HeaderMap::new(), body.to_string().as_bytes()),
+            vec![CalendarNotification {
+                channel_id: "sub-1".to_string(),
+                token: "token".to_string(),
+            }]
+        );
+        assert!(
+            provider
+                .parse_notifications(&HeaderMap::new(), b"not json")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_graph_date_time_round_trip() {
+        let at = DateTime::from_timestamp(1_756_749_600, 0)
+            .unwrap()
+            .naive_utc();
+
+        assert_eq!(graph_date_time(at), "2025-09-01T18:00:00");
+        assert_eq!(
+            parse_graph_date_time("2025-09-01T18:00:00.0000000"),
+            Some(at)
+        );
+    }
+}
@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+use tracing::warn;
+
+use super::service::CalendarService;
+
+/// Channels are renewed a day ahead of expiring, hourly checks leave plenty of slack
+const POLL_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Keeps the push channels of connected calendars alive, without them moved
+/// or deleted events go unnoticed.
+///
+/// Every signalling node runs one, claiming an account is a single conditional
+/// update so each channel is renewed once.
+pub struct CalendarWatchRenewer<C: CalendarService> {
+    calendar_service: C,
+}
+
+impl<C: CalendarService + 'static> CalendarWatchRenewer<C> {
+    pub fn new(calendar_service: C) -> Self {
+        Self { calendar_service }
+    }
+
+    pub fn spawn(self) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(POLL_INTERVAL).await;
+
+                if let Err(err) = self.calendar_service.renew_watches().await {
+                    warn!("Failed to renew calendar channels: {:?}", err);
+                }
+            }
+        });
+    }
+}
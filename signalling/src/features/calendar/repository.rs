@@ -0,0 +1,332 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::{
+    BoolExpressionMethods, ExpressionMethods, OptionalExtension, PgConnection, QueryDsl,
+    RunQueryDsl, SelectableHelper,
+    dsl::{delete, insert_into, update},
+    r2d2::{ConnectionManager, Pool, PooledConnection},
+    upsert::excluded,
+};
+use salvo::async_trait;
+
+use crate::core::{
+    database::{
+        db::checkout,
+        schema::{calendar_accounts, calendar_events, live_schedules},
+    },
+    entities::models::{
+        CalendarAccount, CalendarEvent, LiveSchedule, NewCalendarAccount, NewCalendarEvent,
+    },
+    types::{
+        enums::live_schedule_status::LiveScheduleStatus,
+        errors::{calendar_error::CalendarError, general::GeneralError},
+    },
+};
+
+#[async_trait]
+pub trait CalendarRepository: Send + Sync {
+    /// A user has at most one account per provider, connecting again replaces its tokens
+    async fn upsert_account(
+        &self,
+        account: NewCalendarAccount<'_>,
+    ) -> Result<CalendarAccount, CalendarError>;
+
+    async fn find_accounts_by_user_id(
+        &self,
+        user_id: i32,
+    ) -> Result<Vec<CalendarAccount>, CalendarError>;
+
+    async fn find_account(
+        &self,
+        user_id: i32,
+        provider: &str,
+    ) -> Result<Option<CalendarAccount>, CalendarError>;
+
+    async fn find_account_by_channel_id(
+        &self,
+        channel_id: &str,
+    ) -> Result<Option<CalendarAccount>, CalendarError>;
+
+    async fn delete_account(&self, account_id: i32) -> Result<CalendarAccount, CalendarError>;
+
+    async fn update_tokens(
+        &self,
+        account_id: i32,
+        access_token: &str,
+        refresh_token: &str,
+        expires_at: NaiveDateTime,
+    ) -> Result<CalendarAccount, CalendarError>;
+
+    async fn update_watch(
+        &self,
+        account_id: i32,
+        channel_id: &str,
+        expires_at: NaiveDateTime,
+    ) -> Result<CalendarAccount, CalendarError>;
+
+    /// Accounts whose push channel is missing or expires before `horizon` and
+    /// nobody touched since `idle_since`. Touching them is the claim, so only
+    /// one signalling node renews each.
+    async fn claim_expiring_watches(
+        &self,
+        horizon: NaiveDateTime,
+        idle_since: NaiveDateTime,
+    ) -> Result<Vec<CalendarAccount>, CalendarError>;
+
+    async fn find_event(
+        &self,
+        schedule_id: i32,
+        account_id: i32,
+    ) -> Result<Option<CalendarEvent>, CalendarError>;
+
+    async fn upsert_event(
+        &self,
+        event: NewCalendarEvent<'_>,
+    ) -> Result<CalendarEvent, CalendarError>;
+
+    async fn find_events_by_schedule_id(
+        &self,
+        schedule_id: i32,
+    ) -> Result<Vec<(CalendarEvent, CalendarAccount)>, CalendarError>;
+
+    /// Events of the account whose schedule still waits for its start time
+    async fn find_pending_events(
+        &self,
+        account_id: i32,
+    ) -> Result<Vec<(CalendarEvent, LiveSchedule)>, CalendarError>;
+
+    async fn delete_event(&self, event_id: i32) -> Result<(), CalendarError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct CalendarRepositoryImpl {
+    pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl CalendarRepositoryImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Self {
+        Self { pool }
+    }
+
+    fn get_conn(&self) -> Result<PooledConnection<ConnectionManager<PgConnection>>, GeneralError> {
+        checkout(&self.pool)
+    }
+}
+
+#[async_trait]
+impl CalendarRepository for CalendarRepositoryImpl {
+    async fn upsert_account(
+        &self,
+        account: NewCalendarAccount<'_>,
+    ) -> Result<CalendarAccount, CalendarError> {
+        let mut conn = self.get_conn()?;
+
+        insert_into(calendar_accounts::table)
+            .values(&account)
+            .on_conflict((calendar_accounts::user_id, calendar_accounts::provider))
+            .do_update()
+            .set((
+                calendar_accounts::access_token.eq(excluded(calendar_accounts::access_token)),
+                calendar_accounts::refresh_token.eq(excluded(calendar_accounts::refresh_token)),
+                calendar_accounts::expires_at.eq(excluded(calendar_accounts::expires_at)),
+                calendar_accounts::channel_token.eq(excluded(calendar_accounts::channel_token)),
+                calendar_accounts::updated_at.eq(excluded(calendar_accounts::updated_at)),
+            ))
+            .returning(CalendarAccount::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| CalendarError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_accounts_by_user_id(
+        &self,
+        user_id: i32,
+    ) -> Result<Vec<CalendarAccount>, CalendarError> {
+        let mut conn = self.get_conn()?;
+
+        calendar_accounts::table
+            .filter(calendar_accounts::user_id.eq(user_id))
+            .order(calendar_accounts::id.asc())
+            .select(CalendarAccount::as_select())
+            .load::<CalendarAccount>(&mut conn)
+            .map_err(|err| CalendarError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_account(
+        &self,
+        user_id: i32,
+        provider: &str,
+    ) -> Result<Option<CalendarAccount>, CalendarError> {
+        let mut conn = self.get_conn()?;
+
+        calendar_accounts::table
+            .filter(calendar_accounts::user_id.eq(user_id))
+            .filter(calendar_accounts::provider.eq(provider))
+            .select(CalendarAccount::as_select())
+            .first::<CalendarAccount>(&mut conn)
+            .optional()
+            .map_err(|err| CalendarError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_account_by_channel_id(
+        &self,
+        channel_id: &str,
+    ) -> Result<Option<CalendarAccount>, CalendarError> {
+        let mut conn = self.get_conn()?;
+
+        calendar_accounts::table
+            .filter(calendar_accounts::channel_id.eq(channel_id))
+            .select(CalendarAccount::as_select())
+            .first::<CalendarAccount>(&mut conn)
+            .optional()
+            .map_err(|err| CalendarError::UnexpectedError(err.to_string()))
+    }
+
+    async fn delete_account(&self, account_id: i32) -> Result<CalendarAccount, CalendarError> {
+        let mut conn = self.get_conn()?;
+
+        delete(calendar_accounts::table)
+            .filter(calendar_accounts::id.eq(account_id))
+            .returning(CalendarAccount::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| CalendarError::UnexpectedError(err.to_string()))
+    }
+
+    async fn update_tokens(
+        &self,
+        account_id: i32,
+        access_token: &str,
+        refresh_token: &str,
+        expires_at: NaiveDateTime,
+    ) -> Result<CalendarAccount, CalendarError> {
+        let mut conn = self.get_conn()?;
+
+        update(calendar_accounts::table)
+            .filter(calendar_accounts::id.eq(account_id))
+            .set((
+                calendar_accounts::access_token.eq(access_token),
+                calendar_accounts::refresh_token.eq(refresh_token),
+                calendar_accounts::expires_at.eq(expires_at),
+                calendar_accounts::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .returning(CalendarAccount::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| CalendarError::UnexpectedError(err.to_string()))
+    }
+
+    async fn update_watch(
+        &self,
+        account_id: i32,
+        channel_id: &str,
+        expires_at: NaiveDateTime,
+    ) -> Result<CalendarAccount, CalendarError> {
+        let mut conn = self.get_conn()?;
+
+        update(calendar_accounts::table)
+            .filter(calendar_accounts::id.eq(account_id))
+            .set((
+                calendar_accounts::channel_id.eq(channel_id),
+                calendar_accounts::channel_expires_at.eq(expires_at),
+                calendar_accounts::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .returning(CalendarAccount::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| CalendarError::UnexpectedError(err.to_string()))
+    }
+
+    async fn claim_expiring_watches(
+        &self,
+        horizon: NaiveDateTime,
+        idle_since: NaiveDateTime,
+    ) -> Result<Vec<CalendarAccount>, CalendarError> {
+        let mut conn = self.get_conn()?;
+
+        update(calendar_accounts::table)
+            .filter(
+                calendar_accounts::channel_expires_at
+                    .is_null()
+                    .or(calendar_accounts::channel_expires_at.lt(horizon)),
+            )
+            .filter(calendar_accounts::updated_at.lt(idle_since))
+            .set(calendar_accounts::updated_at.eq(Utc::now().naive_utc()))
+            .returning(CalendarAccount::as_select())
+            .get_results(&mut conn)
+            .map_err(|err| CalendarError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_event(
+        &self,
+        schedule_id: i32,
+        account_id: i32,
+    ) -> Result<Option<CalendarEvent>, CalendarError> {
+        let mut conn = self.get_conn()?;
+
+        calendar_events::table
+            .filter(calendar_events::live_schedule_id.eq(schedule_id))
+            .filter(calendar_events::calendar_account_id.eq(account_id))
+            .select(CalendarEvent::as_select())
+            .first::<CalendarEvent>(&mut conn)
+            .optional()
+            .map_err(|err| CalendarError::UnexpectedError(err.to_string()))
+    }
+
+    async fn upsert_event(
+        &self,
+        event: NewCalendarEvent<'_>,
+    ) -> Result<CalendarEvent, CalendarError> {
+        let mut conn = self.get_conn()?;
+
+        insert_into(calendar_events::table)
+            .values(&event)
+            .on_conflict((
+                calendar_events::live_schedule_id,
+                calendar_events::calendar_account_id,
+            ))
+            .do_update()
+            .set((
+                calendar_events::provider_event_id.eq(excluded(calendar_events::provider_event_id)),
+                calendar_events::updated_at.eq(excluded(calendar_events::updated_at)),
+            ))
+            .returning(CalendarEvent::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| CalendarError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_events_by_schedule_id(
+        &self,
+        schedule_id: i32,
+    ) -> Result<Vec<(CalendarEvent, CalendarAccount)>, CalendarError> {
+        let mut conn = self.get_conn()?;
+
+        calendar_events::table
+            .inner_join(calendar_accounts::table)
+            .filter(calendar_events::live_schedule_id.eq(schedule_id))
+            .select((CalendarEvent::as_select(), CalendarAccount::as_select()))
+            .load::<(CalendarEvent, CalendarAccount)>(&mut conn)
+            .map_err(|err| CalendarError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_pending_events(
+        &self,
+        account_id: i32,
+    ) -> Result<Vec<(CalendarEvent, LiveSchedule)>, CalendarError> {
+        let mut conn = self.get_conn()?;
+
+        calendar_events::table
+            .inner_join(live_schedules::table)
+            .filter(calendar_events::calendar_account_id.eq(account_id))
+            .filter(live_schedules::status.eq(LiveScheduleStatus::Scheduled.to_str()))
+            .select((CalendarEvent::as_select(), LiveSchedule::as_select()))
+            .load::<(CalendarEvent, LiveSchedule)>(&mut conn)
+            .map_err(|err| CalendarError::UnexpectedError(err.to_string()))
+    }
+
+    async fn delete_event(&self, event_id: i32) -> Result<(), CalendarError> {
+        let mut conn = self.get_conn()?;
+
+        delete(calendar_events::table)
+            .filter(calendar_events::id.eq(event_id))
+            .execute(&mut conn)
+            .map_err(|err| CalendarError::UnexpectedError(err.to_string()))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+use salvo::{
+    oapi::extract::{PathParam, QueryParam},
+    prelude::*,
+};
+use tracing::warn;
+
+use crate::core::{
+    types::{
+        errors::calendar_error::CalendarError,
+        responses::calendar_response::{
+            CalendarAccountResponse, CalendarConnectResponse, ListCalendarAccountResponse,
+        },
+    },
+    utils::jwt_utils::JwtUtils,
+};
+
+use super::{
+    provider::CalendarProviders,
+    service::{CalendarService, CalendarSync},
+};
+
+pub fn get_calendar_router(jwt_utils: JwtUtils) -> Router {
+    Router::with_hoop(jwt_utils.auth_middleware())
+        .path("calendar")
+        .push(Router::with_path("accounts").get(get_accounts))
+        .push(Router::with_path("{provider}/connect").get(connect_calendar))
+        .push(Router::with_path("{provider}").delete(disconnect_calendar))
+}
+
+/// OAuth redirects and provider webhooks, mounted next to the client API since
+/// neither the browser coming back from the provider nor the provider itself
+/// can send our API key
+pub fn get_calendar_provider_router() -> Router {
+    Router::with_path("calendar")
+        .push(Router::with_path("{provider}/callback").get(oauth_callback))
+        .push(Router::with_path("webhooks/{provider}").post(post_notifications))
+}
+
+/// Starts connecting a calendar, the client opens the returned URL in a browser.
+/// Scheduled live streams of the user are put on it from then on.
+#[endpoint(tags("calendar"), status_codes(200, 401, 404, 503, 500))]
+async fn connect_calendar(
+    _res: &mut Response,
+    provider: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<CalendarConnectResponse, CalendarError> {
+    let calendar_service = depot
+        .obtain::<CalendarSync>()
+        .map_err(|_| CalendarError::Disabled)?;
+    let jwt_utils = depot.obtain::<JwtUtils>().unwrap();
+    let user_id = depot.get::<String>("user_id").unwrap();
+    let provider = provider.into_inner();
+
+    let state = jwt_utils.generate_calendar_state(user_id, &provider);
+    let authorize_url = calendar_service.authorize_url(&provider, &state)?;
+
+    Ok(CalendarConnectResponse { authorize_url })
+}
+
+/// Calendars the user connected.
+#[endpoint(tags("calendar"), status_codes(200, 401, 503, 500))]
+async fn get_accounts(
+    _res: &mut Response,
+    depot: &mut Depot,
+) -> Result<ListCalendarAccountResponse, CalendarError> {
+    let calendar_service = depot
+        .obtain::<CalendarSync>()
+        .map_err(|_| CalendarError::Disabled)?;
+    let user_id = depot.get::<String>("user_id").unwrap();
+
+    let accounts = calendar_service
+        .get_accounts(user_id.parse().unwrap())
+        .await?;
+
+    Ok(ListCalendarAccountResponse { accounts })
+}
+
+/// Stops syncing with a calendar, events already on it are left in place.
+#[endpoint(tags("calendar"), status_codes(200, 401, 404, 503, 500))]
+async fn disconnect_calendar(
+    _res: &mut Response,
+    provider: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<CalendarAccountResponse, CalendarError> {
+    let calendar_service = depot
+        .obtain::<CalendarSync>()
+        .map_err(|_| CalendarError::Disabled)?;
+    let user_id = depot.get::<String>("user_id").unwrap();
+
+    let account = calendar_service
+        .disconnect(user_id.parse().unwrap(), &provider.into_inner())
+        .await?;
+
+    Ok(CalendarAccountResponse { account })
+}
+
+/// Where the provider sends the browser back to once access was granted, the
+/// signed `state` tells whose calendar it is
+#[handler]
+async fn oauth_callback(
+    provider: PathParam<String>,
+    code: QueryParam<String, false>,
+    state: QueryParam<String, false>,
+    depot: &mut Depot,
+) -> Result<&'static str, CalendarError> {
+    let calendar_service = depot
+        .obtain::<CalendarSync>()
+        .map_err(|_| CalendarError::Disabled)?;
+    let jwt_utils = depot.obtain::<JwtUtils>().unwrap();
+    let provider = provider.into_inner();
+
+    // Denied grants come back with an `error` instead of a code
+    let (Some(code), Some(state)) = (code.into_inner(), state.into_inner()) else {
+        return Err(CalendarError::InvalidState);
+    };
+
+    let claims = jwt_utils
+        .decode_calendar_state(&state, &provider)
+        .map_err(|_| CalendarError::InvalidState)?;
+
+    calendar_service
+        .connect(claims.user_id.parse().unwrap(), &provider, &code)
+        .await?;
+
+    Ok("Calendar connected, you can close this window.")
+}
+
+/// Providers only wait a few seconds for an answer, so changes are followed in
+/// the background once the notification is acknowledged
+#[handler]
+async fn post_notifications(req: &mut Request, depot: &mut Depot, res: &mut Response) {
+    // Graph checks a new subscription's URL by having the token echoed back
+    if let Some(validation_token) = req.query::<String>("validationToken") {
+        return res.render(Text::Plain(validation_token));
+    }
+
+    let provider = req.param::<String>("provider").unwrap_or_default();
+    let (Ok(providers), Ok(calendar_service)) = (
+        depot.obtain::<CalendarProviders>(),
+        depot.obtain::<CalendarSync>(),
+    ) else {
+        res.status_code(StatusCode::SERVICE_UNAVAILABLE);
+        return;
+    };
+    let Ok(calendar_provider) = providers.get(&provider) else {
+        res.status_code(StatusCode::NOT_FOUND);
+        return;
+    };
+
+    let headers = req.headers().clone();
+    let notifications = match req.payload().await {
+        Ok(body) => calendar_provider.parse_notifications(&headers, body),
+        Err(_) => {
+            res.status_code(StatusCode::BAD_REQUEST);
+            return;
+        }
+    };
+
+    let calendar_service = calendar_service.clone();
+    tokio::spawn(async move {
+        for notification in notifications {
+            if let Err(err) = calendar_service
+                .handle_notification(&provider, notification)
+                .await
+            {
+                warn!("Failed to follow {} calendar change: {:?}", provider, err);
+            }
+        }
+    });
+
+    res.status_code(StatusCode::ACCEPTED);
+}
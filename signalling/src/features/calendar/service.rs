@@ -0,0 +1,443 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use salvo::async_trait;
+use tracing::{info, warn};
+
+use crate::{
+    core::{
+        entities::models::{
+            CalendarAccount, LiveSchedule, NewCalendarAccount, NewCalendarEvent, Room,
+        },
+        env::app_env::CalendarConfigs,
+        types::errors::calendar_error::CalendarError,
+        utils::id_utils::{generate_channel_token, generate_token_id},
+    },
+    features::{
+        room::{
+            repository::RoomRepositoryImpl,
+            service::{RoomService, RoomServiceImpl},
+        },
+        schedule::service::{LiveSchedules, ScheduleService},
+        user::repository::UserRepositoryImpl,
+    },
+};
+
+use super::{
+    provider::{CalendarEventInput, CalendarNotification, CalendarProviders},
+    repository::{CalendarRepository, CalendarRepositoryImpl},
+};
+
+/// Live streams don't have an end time, the event blocks out an hour
+const EVENT_DURATION: Duration = Duration::hours(1);
+
+/// Access tokens this close to expiring are refreshed before use
+const TOKEN_REFRESH_MARGIN: Duration = Duration::seconds(60);
+
+/// Calendar sync as wired up against Postgres, injected into the depot when enabled
+pub type CalendarSync = CalendarServiceImpl<
+    CalendarRepositoryImpl,
+    LiveSchedules,
+    RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+>;
+
+#[async_trait]
+pub trait CalendarService: Send + Sync {
+    /// Where to send the user to grant access to their calendar
+    fn authorize_url(&self, provider: &str, state: &str) -> Result<String, CalendarError>;
+
+    /// Finishes the OAuth grant and starts listening for event changes
+    async fn connect(
+        &self,
+        user_id: i32,
+        provider: &str,
+        code: &str,
+    ) -> Result<CalendarAccount, CalendarError>;
+
+    async fn get_accounts(&self, user_id: i32) -> Result<Vec<CalendarAccount>, CalendarError>;
+
+    /// Forgets the account, events already on the calendar stay there
+    async fn disconnect(
+        &self,
+        user_id: i32,
+        provider: &str,
+    ) -> Result<CalendarAccount, CalendarError>;
+
+    /// Puts the schedule on every calendar its host connected, or updates the
+    /// events already there. A failing calendar doesn't hold up the others.
+    async fn sync_schedule(&self, schedule: &LiveSchedule) -> Result<(), CalendarError>;
+
+    /// Takes a cancelled schedule off the calendars it was put on
+    async fn remove_schedule(&self, schedule_id: i32) -> Result<(), CalendarError>;
+
+    /// Follows the host's edits to the events of schedules that haven't started:
+    /// a moved event moves the schedule, a deleted one cancels it
+    async fn handle_notification(
+        &self,
+        provider: &str,
+        notification: CalendarNotification,
+    ) -> Result<(), CalendarError>;
+
+    /// Replaces push channels about to expire, or that were never set up
+    async fn renew_watches(&self) -> Result<(), CalendarError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct CalendarServiceImpl<C: CalendarRepository, S: ScheduleService, R: RoomService> {
+    calendar_repository: C,
+    schedule_service: S,
+    room_service: R,
+    providers: CalendarProviders,
+    join_url: String,
+}
+
+impl<C: CalendarRepository, S: ScheduleService, R: RoomService> CalendarServiceImpl<C, S, R> {
+    pub fn new(
+        calendar_repository: C,
+        schedule_service: S,
+        room_service: R,
+        providers: CalendarProviders,
+        configs: &CalendarConfigs,
+    ) -> Self {
+        Self {
+            calendar_repository,
+            schedule_service,
+            room_service,
+            providers,
+            join_url: configs.join_url.clone(),
+        }
+    }
+
+    async fn _access_token(&self, account: &CalendarAccount) -> Result<String, CalendarError> {
+        if !needs_refresh(account.expires_at, Utc::now().naive_utc()) {
+            return Ok(account.access_token.clone());
+        }
+
+        let tokens = self
+            .providers
+            .get(&account.provider)?
+            .refresh(&account.refresh_token)
+            .await?;
+        let refresh_token = tokens
+            .refresh_token
+            .as_deref()
+            .unwrap_or(&account.refresh_token);
+
+        self.calendar_repository
+            .update_tokens(
+                account.id,
+                &tokens.access_token,
+                refresh_token,
+                tokens.expires_at,
+            )
+            .await?;
+
+        Ok(tokens.access_token)
+    }
+
+    async fn _watch(&self, account: &CalendarAccount) -> Result<(), CalendarError> {
+        let provider = self.providers.get(&account.provider)?;
+        let access_token = self._access_token(account).await?;
+
+        let watch = provider
+            .watch(&access_token, &generate_token_id(), &account.channel_token)
+            .await?;
+
+        self.calendar_repository
+            .update_watch(account.id, &watch.channel_id, watch.expires_at)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn _sync_event(
+        &self,
+        account: &CalendarAccount,
+        schedule_id: i32,
+        event: &CalendarEventInput,
+    ) -> Result<(), CalendarError> {
+        let provider = self.providers.get(&account.provider)?;
+        let access_token = self._access_token(account).await?;
+        let existing = self
+            .calendar_repository
+            .find_event(schedule_id, account.id)
+            .await?;
+
+        let provider_event_id = provider
+            .upsert_event(
+                &access_token,
+                existing.as_ref().map(|e| e.provider_event_id.as_str()),
+                event,
+            )
+            .await?;
+
+        let now = Utc::now().naive_utc();
+        self.calendar_repository
+            .upsert_event(NewCalendarEvent {
+                live_schedule_id: &schedule_id,
+                calendar_account_id: &account.id,
+                provider_event_id: &provider_event_id,
+                created_at: now,
+                updated_at: now,
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn _follow_event(
+        &self,
+        account: &CalendarAccount,
+        access_token: &str,
+        provider_event_id: &str,
+        schedule: &LiveSchedule,
+    ) -> Result<(), CalendarError> {
+        let start_at = self
+            .providers
+            .get(&account.provider)?
+            .get_event_start(access_token, provider_event_id)
+            .await?;
+
+        let result = match start_at {
+            None => self.schedule_service.cancel_pending(schedule.id).await,
+            Some(start_at) if start_at != schedule.start_at => {
+                self.schedule_service
+                    .move_start(schedule.id, start_at)
+                    .await
+            }
+            Some(_) => return Ok(()),
+        };
+
+        match result {
+            Ok(Some(updated)) => info!(
+                "Live schedule {} follows its calendar event: {} at {}",
+                updated.id, updated.status, updated.start_at
+            ),
+            Ok(None) => {}
+            Err(err) => warn!(
+                "Calendar change to live schedule {} not applied: {:?}",
+                schedule.id, err
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<C: CalendarRepository, S: ScheduleService, R: RoomService> CalendarService
+    for CalendarServiceImpl<C, S, R>
+{
+    fn authorize_url(&self, provider: &str, state: &str) -> Result<String, CalendarError> {
+        Ok(self.providers.get(provider)?.authorize_url(state))
+    }
+
+    async fn connect(
+        &self,
+        user_id: i32,
+        provider: &str,
+        code: &str,
+    ) -> Result<CalendarAccount, CalendarError> {
+        let calendar_provider = self.providers.get(provider)?;
+        let tokens = calendar_provider.exchange_code(code).await?;
+
+        // Events are synced long after the grant, an access token alone won't last
+        let refresh_token = tokens
+            .refresh_token
+            .ok_or_else(|| CalendarError::ProviderError("No refresh token was granted".into()))?;
+
+        let now = Utc::now().naive_utc();
+        let account = self
+            .calendar_repository
+            .upsert_account(NewCalendarAccount {
+                user_id: &user_id,
+                provider: calendar_provider.name(),
+                access_token: &tokens.access_token,
+                refresh_token: &refresh_token,
+                expires_at: tokens.expires_at,
+                channel_token: &generate_channel_token(),
+                created_at: now,
+                updated_at: now,
+            })
+            .await?;
+
+        // The renewer retries channels that couldn't be set up
+        if let Err(err) = self._watch(&account).await {
+            warn!(
+                "Failed to watch {} calendar of user {}: {:?}",
+                provider, user_id, err
+            );
+        }
+
+        Ok(account)
+    }
+
+    async fn get_accounts(&self, user_id: i32) -> Result<Vec<CalendarAccount>, CalendarError> {
+        self.calendar_repository
+            .find_accounts_by_user_id(user_id)
+            .await
+    }
+
+    async fn disconnect(
+        &self,
+        user_id: i32,
+        provider: &str,
+    ) -> Result<CalendarAccount, CalendarError> {
+        let account = self
+            .calendar_repository
+            .find_account(user_id, provider)
+            .await?
+            .ok_or_else(|| CalendarError::AccountNotFound(provider.to_string()))?;
+
+        self.calendar_repository.delete_account(account.id).await
+    }
+
+    async fn sync_schedule(&self, schedule: &LiveSchedule) -> Result<(), CalendarError> {
+        let accounts = self
+            .calendar_repository
+            .find_accounts_by_user_id(schedule.created_by_id)
+            .await?;
+
+        if accounts.is_empty() {
+            return Ok(());
+        }
+
+        let room = self
+            .room_service
+            .get_room_by_id(schedule.room_id)
+            .await
+            .map_err(|err| CalendarError::UnexpectedError(err.to_string()))?;
+        let event = build_event(&room.room, schedule, &self.join_url);
+
+        for account in accounts {
+            if let Err(err) = self._sync_event(&account, schedule.id, &event).await {
+                warn!(
+                    "Failed to put live schedule {} on {} calendar: {:?}",
+                    schedule.id, account.provider, err
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn remove_schedule(&self, schedule_id: i32) -> Result<(), CalendarError> {
+        let events = self
+            .calendar_repository
+            .find_events_by_schedule_id(schedule_id)
+            .await?;
+
+        for (event, account) in events {
+            let deleted: Result<(), CalendarError> = async {
+                let access_token = self._access_token(&account).await?;
+
+                self.providers
+                    .get(&account.provider)?
+                    .delete_event(&access_token, &event.provider_event_id)
+                    .await
+            }
+            .await;
+
+            match deleted {
+                Ok(()) => self.calendar_repository.delete_event(event.id).await?,
+                Err(err) => warn!(
+                    "Failed to take live schedule {} off {} calendar: {:?}",
+                    schedule_id, account.provider, err
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_notification(
+        &self,
+        provider: &str,
+        notification: CalendarNotification,
+    ) -> Result<(), CalendarError> {
+        // Channels replaced by a renewal keep firing until they expire
+        let Some(account) = self
+            .calendar_repository
+            .find_account_by_channel_id(&notification.channel_id)
+            .await?
+            .filter(|account| account.provider == provider)
+        else {
+            return Ok(());
+        };
+
+        if account.channel_token != notification.token {
+            return Err(CalendarError::InvalidChannelToken);
+        }
+
+        // Notifications don't say which event changed, so every pending one is checked
+        let events = self
+            .calendar_repository
+            .find_pending_events(account.id)
+            .await?;
+
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        let access_token = self._access_token(&account).await?;
+
+        for (event, schedule) in events {
+            if let Err(err) = self
+                ._follow_event(&account, &access_token, &event.provider_event_id, &schedule)
+                .await
+            {
+                warn!(
+                    "Failed to check calendar event of live schedule {}: {:?}",
+                    schedule.id, err
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn renew_watches(&self) -> Result<(), CalendarError> {
+        let now = Utc::now().naive_utc();
+        let accounts = self
+            .calendar_repository
+            .claim_expiring_watches(now + Duration::days(1), now - Duration::minutes(10))
+            .await?;
+
+        for account in accounts {
+            if let Err(err) = self._watch(&account).await {
+                warn!(
+                    "Failed to renew {} calendar channel of user {}: {:?}",
+                    account.provider, account.user_id, err
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn needs_refresh(expires_at: NaiveDateTime, now: NaiveDateTime) -> bool {
+    expires_at <= now + TOKEN_REFRESH_MARGIN
+}
+
+fn build_event(room: &Room, schedule: &LiveSchedule, join_url: &str) -> CalendarEventInput {
+    CalendarEventInput {
+        title: room.title.clone(),
+        join_url: format!("{}/{}", join_url, room.code),
+        start_at: schedule.start_at,
+        end_at: schedule.start_at + EVENT_DURATION,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    #[test]
+    fn test_tokens_are_refreshed_ahead_of_expiry() {
+        let now = DateTime::from_timestamp(1_000, 0).unwrap().naive_utc();
+
+        assert!(needs_refresh(now - Duration::seconds(1), now));
+        assert!(needs_refresh(now + Duration::seconds(30), now));
+        assert!(!needs_refresh(now + Duration::minutes(5), now));
+    }
+}
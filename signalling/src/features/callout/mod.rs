@@ -0,0 +1,4 @@
+pub mod provider;
+pub mod repository;
+pub mod router;
+pub mod service;
@@ -0,0 +1,185 @@
+use std::{fmt, sync::Arc};
+
+use chrono::Utc;
+use jsonwebtoken::{Algorithm, EncodingKey, Header, encode};
+use nanoid::nanoid;
+use salvo::async_trait;
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::core::{env::app_env::CalloutConfigs, types::errors::callout_error::CalloutError};
+
+/// A telephony provider able to place an outbound call that reads a message out.
+/// Connecting the callee into the room's audio needs a SIP bridge, which Waterbus
+/// doesn't have yet, so calls are notifications for now.
+#[async_trait]
+pub trait CalloutProvider: Send + Sync + fmt::Debug {
+    fn name(&self) -> &'static str;
+
+    /// Dial `to` and speak `message`, returns the provider's call id
+    async fn dial(&self, to: &str, message: &str) -> Result<String, CalloutError>;
+}
+
+/// Build the provider selected by `CALLOUT_PROVIDER`
+pub fn build_provider(configs: &CalloutConfigs) -> Arc<dyn CalloutProvider> {
+    match configs.provider.as_str() {
+        "twilio" => Arc::new(TwilioProvider::new(configs.clone())),
+        "vonage" => Arc::new(VonageProvider::new(configs.clone())),
+        other => panic!("Unknown CALLOUT_PROVIDER {other}, expected twilio or vonage"),
+    }
+}
+
+pub struct TwilioProvider {
+    http: reqwest::Client,
+    configs: CalloutConfigs,
+}
+
+impl fmt::Debug for TwilioProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TwilioProvider")
+            .field("account_id", &self.configs.account_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TwilioProvider {
+    pub fn new(configs: CalloutConfigs) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            configs,
+        }
+    }
+}
+
+#[async_trait]
+impl CalloutProvider for TwilioProvider {
+    fn name(&self) -> &'static str {
+        "twilio"
+    }
+
+    async fn dial(&self, to: &str, message: &str) -> Result<String, CalloutError> {
+        let url = format!(
+            "https://api.twilio.com/2010-04-01/Accounts/{}/Calls.json",
+            self.configs.account_id
+        );
+        let twiml = format!("<Response><Say>{}</Say></Response>", escape_xml(message));
+
+        let response = self
+            .http
+            .post(url)
+            .basic_auth(&self.configs.account_id, Some(&self.configs.secret))
+            .form(&[
+                ("To", to),
+                ("From", self.configs.from_number.as_str()),
+                ("Twiml", twiml.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| CalloutError::ProviderError(e.to_string()))?;
+
+        let value = parse_response(response).await?;
+
+        value["sid"]
+            .as_str()
+            .map(str::to_owned)
+            .ok_or_else(|| CalloutError::ProviderError("Missing call sid".into()))
+    }
+}
+
+pub struct VonageProvider {
+    http: reqwest::Client,
+    configs: CalloutConfigs,
+}
+
+impl fmt::Debug for VonageProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VonageProvider")
+            .field("application_id", &self.configs.account_id)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Serialize)]
+struct VonageClaims<'a> {
+    application_id: &'a str,
+    iat: i64,
+    exp: i64,
+    jti: String,
+}
+
+impl VonageProvider {
+    pub fn new(configs: CalloutConfigs) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            configs,
+        }
+    }
+
+    /// Vonage authenticates applications with a short lived JWT signed by their private key
+    fn application_token(&self) -> Result<String, CalloutError> {
+        let now = Utc::now().timestamp();
+        let claims = VonageClaims {
+            application_id: &self.configs.account_id,
+            iat: now,
+            exp: now + 60,
+            jti: nanoid!(),
+        };
+
+        let key = EncodingKey::from_rsa_pem(self.configs.secret.as_bytes())
+            .map_err(|e| CalloutError::UnexpectedError(e.to_string()))?;
+
+        encode(&Header::new(Algorithm::RS256), &claims, &key)
+            .map_err(|e| CalloutError::UnexpectedError(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl CalloutProvider for VonageProvider {
+    fn name(&self) -> &'static str {
+        "vonage"
+    }
+
+    async fn dial(&self, to: &str, message: &str) -> Result<String, CalloutError> {
+        // Vonage expects numbers without the leading plus
+        let body = json!({
+            "to": [{ "type": "phone", "number": to.trim_start_matches('+') }],
+            "from": { "type": "phone", "number": self.configs.from_number.trim_start_matches('+') },
+            "ncco": [{ "action": "talk", "text": message }],
+        });
+
+        let response = self
+            .http
+            .post("https://api.nexmo.com/v1/calls")
+            .bearer_auth(self.application_token()?)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| CalloutError::ProviderError(e.to_string()))?;
+
+        let value = parse_response(response).await?;
+
+        value["uuid"]
+            .as_str()
+            .map(str::to_owned)
+            .ok_or_else(|| CalloutError::ProviderError("Missing call uuid".into()))
+    }
+}
+
+async fn parse_response(response: reqwest::Response) -> Result<Value, CalloutError> {
+    let status = response.status();
+    let value = response.json::<Value>().await.unwrap_or(Value::Null);
+
+    if !status.is_success() {
+        return Err(CalloutError::ProviderError(format!("{status}: {value}")));
+    }
+
+    Ok(value)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
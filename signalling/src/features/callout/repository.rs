@@ -0,0 +1,117 @@
+use chrono::NaiveDateTime;
+use diesel::{
+    ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper,
+    dsl::{insert_into, sum},
+    r2d2::{ConnectionManager, Pool, PooledConnection},
+};
+use salvo::async_trait;
+
+use crate::core::{
+    database::{db::checkout, schema::callouts},
+    entities::models::{Callout, NewCallout},
+    types::{
+        enums::callout_status::CalloutStatus,
+        errors::{callout_error::CalloutError, general::GeneralError},
+    },
+};
+
+#[async_trait]
+pub trait CalloutRepository: Send + Sync {
+    async fn create_callout(&self, callout: NewCallout<'_>) -> Result<Callout, CalloutError>;
+
+    async fn find_by_room_id(&self, room_id: i32) -> Result<Vec<Callout>, CalloutError>;
+
+    /// Cost of the calls placed since `since`, of one room or all of them.
+    /// Failed and rejected attempts are free.
+    async fn get_spent_cents(
+        &self,
+        since: NaiveDateTime,
+        room_id: Option<i32>,
+    ) -> Result<i64, CalloutError>;
+
+    /// Calls the host had dialed since `since`, rejected attempts don't count
+    async fn count_dialed_by_host(
+        &self,
+        host_id: i32,
+        since: NaiveDateTime,
+    ) -> Result<i64, CalloutError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct CalloutRepositoryImpl {
+    pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl CalloutRepositoryImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Self {
+        Self { pool }
+    }
+
+    fn get_conn(&self) -> Result<PooledConnection<ConnectionManager<PgConnection>>, GeneralError> {
+        checkout(&self.pool)
+    }
+}
+
+#[async_trait]
+impl CalloutRepository for CalloutRepositoryImpl {
+    async fn create_callout(&self, callout: NewCallout<'_>) -> Result<Callout, CalloutError> {
+        let mut conn = self.get_conn()?;
+
+        insert_into(callouts::table)
+            .values(&callout)
+            .returning(Callout::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| CalloutError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_by_room_id(&self, room_id: i32) -> Result<Vec<Callout>, CalloutError> {
+        let mut conn = self.get_conn()?;
+
+        callouts::table
+            .filter(callouts::room_id.eq(room_id))
+            .order(callouts::created_at.desc())
+            .select(Callout::as_select())
+            .load::<Callout>(&mut conn)
+            .map_err(|err| CalloutError::UnexpectedError(err.to_string()))
+    }
+
+    async fn get_spent_cents(
+        &self,
+        since: NaiveDateTime,
+        room_id: Option<i32>,
+    ) -> Result<i64, CalloutError> {
+        let mut conn = self.get_conn()?;
+
+        let mut statement = callouts::table
+            .filter(callouts::created_at.ge(since))
+            .filter(callouts::status.eq(CalloutStatus::Initiated.to_str()))
+            .select(sum(callouts::cost_cents))
+            .into_boxed();
+
+        if let Some(room_id) = room_id {
+            statement = statement.filter(callouts::room_id.eq(room_id));
+        }
+
+        let spent: Option<i64> = statement
+            .first(&mut conn)
+            .map_err(|err| CalloutError::UnexpectedError(err.to_string()))?;
+
+        Ok(spent.unwrap_or(0))
+    }
+
+    async fn count_dialed_by_host(
+        &self,
+        host_id: i32,
+        since: NaiveDateTime,
+    ) -> Result<i64, CalloutError> {
+        let mut conn = self.get_conn()?;
+
+        callouts::table
+            .filter(callouts::requested_by_id.eq(host_id))
+            .filter(callouts::created_at.ge(since))
+            .filter(callouts::status.ne(CalloutStatus::Rejected.to_str()))
+            .count()
+            .get_result(&mut conn)
+            .map_err(|err| CalloutError::UnexpectedError(err.to_string()))
+    }
+}
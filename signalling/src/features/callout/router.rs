@@ -0,0 +1,90 @@
+use salvo::{
+    oapi::extract::{JsonBody, PathParam},
+    prelude::*,
+};
+
+use crate::{
+    core::{
+        dtos::callout::create_callout_dto::CreateCalloutDto,
+        types::{
+            errors::callout_error::CalloutError,
+            responses::callout_response::{CalloutResponse, ListCalloutResponse},
+        },
+        utils::jwt_utils::JwtUtils,
+    },
+    features::{
+        room::{
+            repository::RoomRepositoryImpl,
+            service::{RoomService, RoomServiceImpl},
+        },
+        user::repository::UserRepositoryImpl,
+    },
+};
+
+use super::service::{CalloutService, PhoneCallout};
+
+pub fn get_callout_router(jwt_utils: JwtUtils) -> Router {
+    Router::with_hoop(jwt_utils.auth_middleware())
+        .path("rooms/{room_id}/callouts")
+        .post(create_callout)
+        .get(get_callouts)
+}
+
+/// Phones a number and invites the callee to the room. Only the host can call out,
+/// within an hourly limit per host and a daily spending cap per room.
+#[endpoint(
+    tags("callout"),
+    status_codes(200, 400, 401, 403, 404, 429, 502, 503, 500)
+)]
+async fn create_callout(
+    _res: &mut Response,
+    room_id: PathParam<String>,
+    data: JsonBody<CreateCalloutDto>,
+    depot: &mut Depot,
+) -> Result<CalloutResponse, CalloutError> {
+    let callout_service = depot
+        .obtain::<PhoneCallout>()
+        .map_err(|_| CalloutError::Disabled)?;
+    let user_id = depot.get::<String>("user_id").unwrap();
+    let room_id = resolve_room_id(depot, &room_id.into_inner()).await?;
+
+    let callout = callout_service
+        .call(
+            room_id,
+            user_id.parse().unwrap(),
+            &data.into_inner().phone_number,
+        )
+        .await?;
+
+    Ok(CalloutResponse { callout })
+}
+
+/// Lists the callouts placed from a room, including failed and rejected ones.
+#[endpoint(tags("callout"), status_codes(200, 401, 403, 503, 500))]
+async fn get_callouts(
+    _res: &mut Response,
+    room_id: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<ListCalloutResponse, CalloutError> {
+    let callout_service = depot
+        .obtain::<PhoneCallout>()
+        .map_err(|_| CalloutError::Disabled)?;
+    let user_id = depot.get::<String>("user_id").unwrap();
+    let room_id = resolve_room_id(depot, &room_id.into_inner()).await?;
+
+    let callouts = callout_service
+        .get_callouts(room_id, user_id.parse().unwrap())
+        .await?;
+
+    Ok(ListCalloutResponse { callouts })
+}
+
+/// Rooms are addressed by their public id, the services work with internal keys
+async fn resolve_room_id(depot: &Depot, room_id: &str) -> Result<i32, CalloutError> {
+    depot
+        .obtain::<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>()
+        .unwrap()
+        .resolve_room_id(room_id)
+        .await
+        .map_err(|_| CalloutError::RoomNotFound(room_id.to_string()))
+}
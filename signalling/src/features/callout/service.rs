@@ -0,0 +1,373 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use salvo::async_trait;
+use tracing::{info, warn};
+
+use crate::{
+    core::{
+        entities::models::{Callout, NewCallout},
+        env::app_env::CalloutConfigs,
+        types::{enums::callout_status::CalloutStatus, errors::callout_error::CalloutError},
+    },
+    features::{
+        room::{
+            repository::RoomRepositoryImpl,
+            service::{RoomService, RoomServiceImpl},
+        },
+        user::repository::UserRepositoryImpl,
+    },
+};
+
+use super::{
+    provider::CalloutProvider,
+    repository::{CalloutRepository, CalloutRepositoryImpl},
+};
+
+/// Callouts as wired up against Postgres, injected into the depot when enabled
+pub type PhoneCallout = CalloutServiceImpl<
+    CalloutRepositoryImpl,
+    RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+>;
+
+#[async_trait]
+pub trait CalloutService: Send + Sync {
+    /// Phone a number on behalf of the room's host, every attempt is audited
+    async fn call(
+        &self,
+        room_id: i32,
+        host_id: i32,
+        phone_number: &str,
+    ) -> Result<Callout, CalloutError>;
+
+    async fn get_callouts(&self, room_id: i32, host_id: i32) -> Result<Vec<Callout>, CalloutError>;
+}
+
+/// What has been spent and dialed so far, read before every call
+#[derive(Debug, Clone, Copy, Default)]
+struct CalloutUsage {
+    /// Today, across the deployment
+    spent_cents: i64,
+    /// Today, by the room calling out
+    room_spent_cents: i64,
+    /// Over the last hour, by the host calling out
+    host_calls: i64,
+}
+
+/// Keeps a single host or room from using up the budget of everyone else
+#[derive(Debug, Clone, Copy)]
+struct CalloutLimits {
+    cost_per_call_cents: i64,
+    daily_spend_cap_cents: i64,
+    room_daily_spend_cap_cents: i64,
+    host_calls_per_hour: i64,
+}
+
+impl CalloutLimits {
+    fn from_configs(configs: &CalloutConfigs) -> Self {
+        Self {
+            cost_per_call_cents: configs.cost_per_call_cents,
+            daily_spend_cap_cents: configs.daily_spend_cap_cents,
+            room_daily_spend_cap_cents: configs.room_daily_spend_cap_cents,
+            host_calls_per_hour: configs.host_calls_per_hour,
+        }
+    }
+
+    /// Whether one more call fits
+    fn check(&self, usage: CalloutUsage) -> Result<(), CalloutError> {
+        if usage.host_calls >= self.host_calls_per_hour {
+            return Err(CalloutError::RateLimited);
+        }
+
+        if usage.room_spent_cents + self.cost_per_call_cents > self.room_daily_spend_cap_cents {
+            return Err(CalloutError::RoomSpendingCapReached);
+        }
+
+        if usage.spent_cents + self.cost_per_call_cents > self.daily_spend_cap_cents {
+            return Err(CalloutError::SpendingCapReached);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CalloutServiceImpl<C: CalloutRepository, R: RoomService> {
+    callout_repository: C,
+    room_service: R,
+    provider: Arc<dyn CalloutProvider>,
+    limits: CalloutLimits,
+}
+
+impl<C: CalloutRepository, R: RoomService> CalloutServiceImpl<C, R> {
+    pub fn new(
+        callout_repository: C,
+        room_service: R,
+        provider: Arc<dyn CalloutProvider>,
+        configs: &CalloutConfigs,
+    ) -> Self {
+        Self {
+            callout_repository,
+            room_service,
+            provider,
+            limits: CalloutLimits::from_configs(configs),
+        }
+    }
+
+    async fn _check_host(&self, room_id: i32, host_id: i32) -> Result<(), CalloutError> {
+        self.room_service
+            .check_host(room_id, host_id)
+            .await
+            .map_err(|_| CalloutError::YouDontHavePermissions)
+    }
+
+    async fn _usage(&self, room_id: i32, host_id: i32) -> Result<CalloutUsage, CalloutError> {
+        let now = Utc::now().naive_utc();
+        let today = now.date().and_hms_opt(0, 0, 0).unwrap();
+
+        Ok(CalloutUsage {
+            spent_cents: self.callout_repository.get_spent_cents(today, None).await?,
+            room_spent_cents: self
+                .callout_repository
+                .get_spent_cents(today, Some(room_id))
+                .await?,
+            host_calls: self
+                .callout_repository
+                .count_dialed_by_host(host_id, now - Duration::hours(1))
+                .await?,
+        })
+    }
+
+    async fn _audit(
+        &self,
+        room_id: i32,
+        host_id: i32,
+        phone_number: &str,
+        status: CalloutStatus,
+        provider_call_id: Option<&str>,
+        error: Option<&str>,
+    ) -> Result<Callout, CalloutError> {
+        info!(
+            "Callout from room {} by user {} to {}: {}",
+            room_id,
+            host_id,
+            mask_phone_number(phone_number),
+            status.to_str()
+        );
+
+        let cost_cents = match status {
+            CalloutStatus::Initiated => self.limits.cost_per_call_cents as i32,
+            _ => 0,
+        };
+
+        self.callout_repository
+            .create_callout(NewCallout {
+                room_id: &room_id,
+                requested_by_id: &host_id,
+                phone_number,
+                provider: self.provider.name(),
+                provider_call_id,
+                status: status.to_str(),
+                cost_cents,
+                error,
+                created_at: Utc::now().naive_utc(),
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<C: CalloutRepository, R: RoomService> CalloutService for CalloutServiceImpl<C, R> {
+    async fn call(
+        &self,
+        room_id: i32,
+        host_id: i32,
+        phone_number: &str,
+    ) -> Result<Callout, CalloutError> {
+        self._check_host(room_id, host_id).await?;
+
+        if !is_e164(phone_number) {
+            return Err(CalloutError::InvalidPhoneNumber(phone_number.to_string()));
+        }
+
+        let room = self
+            .room_service
+            .get_room_by_id(room_id)
+            .await
+            .map_err(|_| CalloutError::RoomNotFound(room_id.to_string()))?;
+
+        // Caps are per UTC day, concurrent calls may overshoot them by a call or two
+        let usage = self._usage(room_id, host_id).await?;
+
+        if let Err(err) = self.limits.check(usage) {
+            self._audit(
+                room_id,
+                host_id,
+                phone_number,
+                CalloutStatus::Rejected,
+                None,
+                Some(&err.to_string()),
+            )
+            .await?;
+
+            return Err(err);
+        }
+
+        let message = format!(
+            "You have been invited to join {} on Waterbus. The room code is {}.",
+            room.room.title,
+            spell_out(&room.room.code)
+        );
+
+        match self.provider.dial(phone_number, &message).await {
+            Ok(call_id) => {
+                self._audit(
+                    room_id,
+                    host_id,
+                    phone_number,
+                    CalloutStatus::Initiated,
+                    Some(&call_id),
+                    None,
+                )
+                .await
+            }
+            Err(err) => {
+                warn!("Failed to place callout: {:?}", err);
+
+                let reason = err.to_string().chars().take(255).collect::<String>();
+                self._audit(
+                    room_id,
+                    host_id,
+                    phone_number,
+                    CalloutStatus::Failed,
+                    None,
+                    Some(&reason),
+                )
+                .await?;
+
+                Err(err)
+            }
+        }
+    }
+
+    async fn get_callouts(&self, room_id: i32, host_id: i32) -> Result<Vec<Callout>, CalloutError> {
+        self._check_host(room_id, host_id).await?;
+
+        self.callout_repository.find_by_room_id(room_id).await
+    }
+}
+
+/// `+` followed by up to 15 digits, the first one not zero
+fn is_e164(phone_number: &str) -> bool {
+    let Some(digits) = phone_number.strip_prefix('+') else {
+        return false;
+    };
+
+    (7..=15).contains(&digits.len())
+        && digits.chars().all(|c| c.is_ascii_digit())
+        && !digits.starts_with('0')
+}
+
+/// Text to speech reads `abc-defg-hij` as words, spacing it out reads each letter
+fn spell_out(code: &str) -> String {
+    code.split('-')
+        .map(|group| {
+            group
+                .chars()
+                .map(String::from)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Keep full numbers out of the logs, the audit table has them
+fn mask_phone_number(phone_number: &str) -> String {
+    let masked = phone_number.len().saturating_sub(4);
+    format!("{}{}", "*".repeat(masked), &phone_number[masked..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_e164() {
+        assert!(is_e164("+14155550123"));
+        assert!(is_e164("+84901234567"));
+        assert!(!is_e164("14155550123"));
+        assert!(!is_e164("+0415555012"));
+        assert!(!is_e164("+1415-555-0123"));
+        assert!(!is_e164("+123"));
+        assert!(!is_e164("+1234567890123456"));
+    }
+
+    #[test]
+    fn test_spell_out_room_code() {
+        assert_eq!(spell_out("abc-defg-hij"), "a b c, d e f g, h i j");
+    }
+
+    fn limits() -> CalloutLimits {
+        CalloutLimits {
+            cost_per_call_cents: 5,
+            daily_spend_cap_cents: 1_000,
+            room_daily_spend_cap_cents: 200,
+            host_calls_per_hour: 10,
+        }
+    }
+
+    #[test]
+    fn test_limits_allow_call_within_budget() {
+        assert!(limits().check(CalloutUsage::default()).is_ok());
+        assert!(
+            limits()
+                .check(CalloutUsage {
+                    spent_cents: 995,
+                    room_spent_cents: 195,
+                    host_calls: 9,
+                })
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_limits_cap_each_room() {
+        // Plenty left for the deployment, none for this room
+        let result = limits().check(CalloutUsage {
+            spent_cents: 200,
+            room_spent_cents: 200,
+            host_calls: 0,
+        });
+
+        assert!(matches!(result, Err(CalloutError::RoomSpendingCapReached)));
+    }
+
+    #[test]
+    fn test_limits_rate_limit_host() {
+        let result = limits().check(CalloutUsage {
+            spent_cents: 0,
+            room_spent_cents: 0,
+            host_calls: 10,
+        });
+
+        assert!(matches!(result, Err(CalloutError::RateLimited)));
+    }
+
+    #[test]
+    fn test_limits_keep_deployment_cap() {
+        let result = limits().check(CalloutUsage {
+            spent_cents: 1_000,
+            room_spent_cents: 0,
+            host_calls: 0,
+        });
+
+        assert!(matches!(result, Err(CalloutError::SpendingCapReached)));
+    }
+
+    #[test]
+    fn test_mask_phone_number() {
+        assert_eq!(mask_phone_number("+14155550123"), "********0123");
+        assert_eq!(mask_phone_number("+1"), "+1");
+    }
+}
@@ -1,3 +1,5 @@
+pub mod outbox;
 pub mod repository;
+pub mod retention;
 pub mod router;
 pub mod service;
@@ -0,0 +1,400 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use dispatcher::infrastructure::event_queue::{
+    self, EventSender, Overflow, QueueStats, QueuedEvent,
+};
+use tokio::sync::Notify;
+use tracing::warn;
+
+use crate::{
+    core::{
+        entities::models::OutboxEventEnum,
+        types::{app_channel::AppEvent, responses::message_response::MessageResponse},
+    },
+    features::matrix::service::{MatrixBridge, MatrixService},
+};
+
+use super::repository::ChatRepository;
+
+const BATCH_SIZE: i64 = 100;
+/// How long a claimed batch is reserved, a relay that dies mid-batch blocks delivery for at most this long
+const LEASE: Duration = Duration::from_secs(30);
+/// Catches writes made on other signalling nodes, local writes wake the relay right away
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const DELIVERED_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+/// Messages waiting on the homeserver, past this the oldest are dropped
+const MATRIX_QUEUE_CAPACITY: usize = 1_000;
+
+/// Wakes the relay after a message write instead of waiting for the next poll
+#[derive(Debug, Clone, Default)]
+pub struct OutboxSignal(Arc<Notify>);
+
+impl OutboxSignal {
+    pub fn wake(&self) {
+        self.0.notify_one();
+    }
+}
+
+/// A created message on its way to Matrix
+struct MatrixRelay(MessageResponse);
+
+impl QueuedEvent for MatrixRelay {
+    fn kind(&self) -> &'static str {
+        "matrix_relay"
+    }
+
+    /// Matrix is best effort, a homeserver that can't keep up loses the
+    /// oldest messages rather than holding up the outbox
+    fn overflow(&self) -> Overflow {
+        Overflow::DropOldest
+    }
+}
+
+/// Relays created messages to Matrix on a task of their own, so a slow or
+/// hanging homeserver never holds up chat delivery
+#[derive(Clone)]
+pub struct MatrixRelays {
+    sender: EventSender<MatrixRelay>,
+}
+
+impl MatrixRelays {
+    pub fn spawn(matrix_bridge: MatrixBridge) -> Self {
+        let (sender, receiver) =
+            event_queue::bounded::<MatrixRelay>("matrix_relays", MATRIX_QUEUE_CAPACITY);
+
+        tokio::spawn(async move {
+            while let Some(MatrixRelay(message)) = receiver.recv().await {
+                if let Err(err) = matrix_bridge.relay_message(&message).await {
+                    warn!("Failed to relay message to Matrix: {:?}", err);
+                }
+            }
+        });
+
+        Self { sender }
+    }
+
+    pub fn stats(&self) -> Arc<QueueStats> {
+        self.sender.stats()
+    }
+}
+
+/// Drains the message outbox into the socket broadcast and the Matrix bridge.
+///
+/// Entries are written in the same transaction as the message and marked
+/// delivered once they are queued for the socket loop of this node. A crash
+/// before that means the entry is delivered again (at-least-once), clients
+/// dedupe by message id. Past the queue there is no retry: events still in it
+/// when the node dies are lost, and clients catch up from the message history
+/// when they reconnect.
+pub struct OutboxRelay<C: ChatRepository> {
+    chat_repository: C,
+    sender: EventSender<AppEvent>,
+    signal: OutboxSignal,
+    matrix_relays: Option<MatrixRelays>,
+}
+
+impl<C: ChatRepository + 'static> OutboxRelay<C> {
+    pub fn new(chat_repository: C, sender: EventSender<AppEvent>, signal: OutboxSignal) -> Self {
+        Self {
+            chat_repository,
+            sender,
+            signal,
+            matrix_relays: None,
+        }
+    }
+
+    pub fn with_matrix_relays(mut self, matrix_relays: MatrixRelays) -> Self {
+        self.matrix_relays = Some(matrix_relays);
+        self
+    }
+
+    pub fn spawn(self) {
+        tokio::spawn(async move {
+            let mut last_pruned = Instant::now();
+
+            loop {
+                tokio::select! {
+                    _ = self.signal.0.notified() => {}
+                    _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                }
+
+                self.drain().await;
+
+                if last_pruned.elapsed() >= PRUNE_INTERVAL {
+                    last_pruned = Instant::now();
+                    self.prune().await;
+                }
+            }
+        });
+    }
+
+    async fn drain(&self) {
+        loop {
+            let batch = match self.chat_repository.claim_outbox(BATCH_SIZE, LEASE).await {
+                Ok(batch) => batch,
+                Err(err) => {
+                    warn!("Failed to claim message outbox: {:?}", err);
+                    return;
+                }
+            };
+
+            let claimed = batch.len();
+            let mut delivered = Vec::with_capacity(claimed);
+
+            for (entry, message) in batch {
+                if !self.deliver(entry.event.into(), message).await {
+                    break;
+                }
+                delivered.push(entry.id);
+            }
+
+            let complete = delivered.len() == claimed;
+
+            if !delivered.is_empty()
+                && let Err(err) = self.chat_repository.mark_outbox_delivered(delivered).await
+            {
+                warn!("Failed to mark outbox entries delivered: {:?}", err);
+                return;
+            }
+
+            if !complete || (claimed as i64) < BATCH_SIZE {
+                return;
+            }
+        }
+    }
+
+    /// Returns false once the socket side has gone away, the rest stays pending
+    async fn deliver(&self, event: OutboxEventEnum, message: MessageResponse) -> bool {
+        // Never waits, a full Matrix queue drops its oldest message instead
+        if event == OutboxEventEnum::Created
+            && let Some(matrix_relays) = &self.matrix_relays
+            && matrix_relays
+                .sender
+                .send(MatrixRelay(message.clone()))
+                .await
+                .is_err()
+        {
+            warn!(
+                "Matrix relay is gone, message {} not bridged",
+                message.message.id
+            );
+        }
+
+        let event = match event {
+            OutboxEventEnum::Created => AppEvent::SendMessage(message),
+            OutboxEventEnum::Updated => AppEvent::UpdateMessage(message),
+            OutboxEventEnum::Deleted => AppEvent::DeleteMessage(message),
+        };
+
+        self.sender.send(event).await.is_ok()
+    }
+
+    async fn prune(&self) {
+        let delivered_before = Utc::now().naive_utc() - DELIVERED_RETENTION;
+
+        if let Err(err) = self.chat_repository.prune_outbox(delivered_before).await {
+            warn!("Failed to prune message outbox: {:?}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use chrono::{DateTime, NaiveDateTime};
+    use dispatcher::infrastructure::event_queue::EventReceiver;
+    use salvo::async_trait;
+
+    use super::*;
+    use crate::core::{
+        entities::models::{
+            Message, MessagesStatusEnum, MessagesTypeEnum, NewMessage, OutboxEntry,
+        },
+        types::errors::chat_error::ChatError,
+    };
+
+    /// Keeps the undelivered entries, oldest first
+    struct MockOutboxRepository {
+        pending: Mutex<Vec<(OutboxEntry, MessageResponse)>>,
+    }
+
+    impl MockOutboxRepository {
+        fn with_entries(events: &[OutboxEventEnum]) -> Self {
+            let now = DateTime::from_timestamp(0, 0).unwrap().naive_utc();
+
+            let pending = events
+                .iter()
+                .zip(1..)
+                .map(|(event, id)| {
+                    let entry = OutboxEntry {
+                        id,
+                        message_id: id,
+                        event: *event as i16,
+                        created_at: now,
+                        locked_until: None,
+                        delivered_at: None,
+                    };
+                    let message = MessageResponse {
+                        message: Message {
+                            id,
+                            data: format!("message {id}"),
+                            created_at: now,
+                            updated_at: now,
+                            deleted_at: None,
+                            created_by_id: 1,
+                            room_id: 1,
+                            type_: MessagesTypeEnum::Default as i16,
+                            status: MessagesStatusEnum::Active as i16,
+                        },
+                        created_by: None,
+                        room: None,
+                    };
+                    (entry, message)
+                })
+                .collect();
+
+            Self {
+                pending: Mutex::new(pending),
+            }
+        }
+
+        fn pending_ids(&self) -> Vec<i32> {
+            let pending = self.pending.lock().unwrap();
+            pending.iter().map(|(entry, _)| entry.id).collect()
+        }
+    }
+
+    #[async_trait]
+    impl ChatRepository for MockOutboxRepository {
+        async fn get_messages_by_room(
+            &self,
+            _room_id: i32,
+            _deleted_at: NaiveDateTime,
+            _skip: i64,
+            _limit: i64,
+        ) -> Result<Vec<MessageResponse>, ChatError> {
+            unimplemented!()
+        }
+        async fn get_message_by_id(&self, _message_id: i32) -> Result<MessageResponse, ChatError> {
+            unimplemented!()
+        }
+        async fn create_message(&self, _message: NewMessage<'_>) -> Result<Message, ChatError> {
+            unimplemented!()
+        }
+        async fn update_message(&self, _message: Message) -> Result<Message, ChatError> {
+            unimplemented!()
+        }
+        async fn delete_message_by_id(&self, _message_id: i32) -> Result<Message, ChatError> {
+            unimplemented!()
+        }
+        async fn claim_outbox(
+            &self,
+            limit: i64,
+            _lease: Duration,
+        ) -> Result<Vec<(OutboxEntry, MessageResponse)>, ChatError> {
+            let pending = self.pending.lock().unwrap();
+            Ok(pending.iter().take(limit as usize).cloned().collect())
+        }
+        async fn mark_outbox_delivered(&self, entry_ids: Vec<i32>) -> Result<(), ChatError> {
+            let mut pending = self.pending.lock().unwrap();
+            pending.retain(|(entry, _)| !entry_ids.contains(&entry.id));
+            Ok(())
+        }
+        async fn prune_outbox(&self, _delivered_before: NaiveDateTime) -> Result<usize, ChatError> {
+            Ok(0)
+        }
+        async fn purge_expired_messages(
+            &self,
+            _default_days: Option<i32>,
+            _now: NaiveDateTime,
+            _limit: i64,
+        ) -> Result<usize, ChatError> {
+            unimplemented!()
+        }
+    }
+
+    fn relay(
+        chat_repository: MockOutboxRepository,
+        capacity: usize,
+    ) -> (OutboxRelay<MockOutboxRepository>, EventReceiver<AppEvent>) {
+        let (sender, receiver) = event_queue::bounded("app_events", capacity);
+        let relay = OutboxRelay::new(chat_repository, sender, OutboxSignal::default());
+        (relay, receiver)
+    }
+
+    #[tokio::test]
+    async fn test_drain_delivers_entries_in_order() {
+        let chat_repository = MockOutboxRepository::with_entries(&[
+            OutboxEventEnum::Created,
+            OutboxEventEnum::Updated,
+            OutboxEventEnum::Deleted,
+        ]);
+        let (relay, receiver) = relay(chat_repository, 8);
+
+        relay.drain().await;
+
+        assert!(relay.chat_repository.pending_ids().is_empty());
+        assert!(matches!(
+            receiver.recv().await,
+            Some(AppEvent::SendMessage(message)) if message.message.id == 1
+        ));
+        assert!(matches!(
+            receiver.recv().await,
+            Some(AppEvent::UpdateMessage(message)) if message.message.id == 2
+        ));
+        assert!(matches!(
+            receiver.recv().await,
+            Some(AppEvent::DeleteMessage(message)) if message.message.id == 3
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_drain_claims_batches_until_empty() {
+        let events = vec![OutboxEventEnum::Created; BATCH_SIZE as usize + 20];
+        let chat_repository = MockOutboxRepository::with_entries(&events);
+        let (relay, _receiver) = relay(chat_repository, events.len());
+
+        relay.drain().await;
+
+        assert!(relay.chat_repository.pending_ids().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stalled_matrix_relay_does_not_hold_up_delivery() {
+        let chat_repository = MockOutboxRepository::with_entries(&[OutboxEventEnum::Created; 3]);
+        let (relay, receiver) = relay(chat_repository, 8);
+
+        // Nobody drains it, like a homeserver that never answers
+        let (sender, _matrix_receiver) = event_queue::bounded::<MatrixRelay>("matrix_relays", 1);
+        let relay = relay.with_matrix_relays(MatrixRelays { sender });
+
+        tokio::time::timeout(Duration::from_secs(1), relay.drain())
+            .await
+            .unwrap();
+
+        assert!(relay.chat_repository.pending_ids().is_empty());
+        for id in 1..=3 {
+            assert!(matches!(
+                receiver.recv().await,
+                Some(AppEvent::SendMessage(message)) if message.message.id == id
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_entries_stay_pending_once_the_socket_side_is_gone() {
+        let chat_repository = MockOutboxRepository::with_entries(&[OutboxEventEnum::Created; 2]);
+        let (relay, receiver) = relay(chat_repository, 8);
+        drop(receiver);
+
+        relay.drain().await;
+
+        assert_eq!(relay.chat_repository.pending_ids(), [1, 2]);
+    }
+}
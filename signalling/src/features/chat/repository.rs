@@ -1,13 +1,26 @@
+use std::{collections::HashMap, time::Duration};
+
+use chrono::{NaiveDateTime, Utc};
 use diesel::{
-    ExpressionMethods, JoinOnDsl, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper,
-    dsl::{insert_into, update},
+    Connection, ExpressionMethods, JoinOnDsl, PgConnection, QueryDsl, QueryResult, RunQueryDsl,
+    SelectableHelper,
+    dsl::{delete, insert_into, update},
     r2d2::{ConnectionManager, Pool, PooledConnection},
+    sql_query,
+    sql_types::{BigInt, Bool, Integer, Nullable, Timestamp},
 };
 use salvo::async_trait;
 
 use crate::core::{
-    database::schema::{messages, rooms, users},
-    entities::models::{Message, MessagesStatusEnum, NewMessage, Room, User},
+    database::{
+        db::checkout,
+        schema::{message_outbox, messages, rooms, users},
+        soft_delete::{DeletedScope, messages_in_scope},
+    },
+    entities::models::{
+        Message, MessagesStatusEnum, NewMessage, NewOutboxEntry, OutboxEntry, OutboxEventEnum,
+        Room, User,
+    },
     types::{
         errors::{chat_error::ChatError, general::GeneralError},
         responses::message_response::MessageResponse,
@@ -31,6 +44,46 @@ pub trait ChatRepository: Send + Sync {
     async fn update_message(&self, message: Message) -> Result<Message, ChatError>;
 
     async fn delete_message_by_id(&self, message_id: i32) -> Result<Message, ChatError>;
+
+    /// Lease the oldest undelivered outbox entries, in order. Returns nothing
+    /// while another relay holds an unexpired lease, so delivery stays ordered.
+    async fn claim_outbox(
+        &self,
+        limit: i64,
+        lease: Duration,
+    ) -> Result<Vec<(OutboxEntry, MessageResponse)>, ChatError>;
+
+    async fn mark_outbox_delivered(&self, entry_ids: Vec<i32>) -> Result<(), ChatError>;
+
+    async fn prune_outbox(&self, delivered_before: NaiveDateTime) -> Result<usize, ChatError>;
+
+    /// Hard-deletes up to `limit` messages older than their room's retention,
+    /// `default_days` applying to rooms without their own. Rooms under legal hold are skipped.
+    async fn purge_expired_messages(
+        &self,
+        default_days: Option<i32>,
+        now: NaiveDateTime,
+        limit: i64,
+    ) -> Result<usize, ChatError>;
+}
+
+diesel::define_sql_function!(fn pg_try_advisory_xact_lock(key: BigInt) -> Bool);
+
+/// Arbitrary, only has to be unique among the advisory locks we take
+const OUTBOX_LOCK_KEY: i64 = 0x6f7574626f78;
+
+fn record_outbox(
+    conn: &mut PgConnection,
+    message_id: i32,
+    event: OutboxEventEnum,
+) -> QueryResult<usize> {
+    insert_into(message_outbox::table)
+        .values(&NewOutboxEntry {
+            message_id,
+            event: event.into(),
+            created_at: Utc::now().naive_utc(),
+        })
+        .execute(conn)
 }
 
 #[derive(Debug, Clone)]
@@ -44,7 +97,7 @@ impl ChatRepositoryImpl {
     }
 
     fn get_conn(&self) -> Result<PooledConnection<ConnectionManager<PgConnection>>, GeneralError> {
-        self.pool.get().map_err(|_| GeneralError::DbConnectionError)
+        checkout(&self.pool)
     }
 }
 
@@ -62,6 +115,7 @@ impl ChatRepository for ChatRepositoryImpl {
         let result = messages::table
             .filter(messages::room_id.eq(room_id))
             .filter(messages::created_at.gt(deleted_at))
+            .filter(messages_in_scope(DeletedScope::Exclude))
             .left_join(rooms::table.on(messages::room_id.eq(rooms::id)))
             .left_join(users::table.on(messages::created_by_id.eq(users::id)))
             .select((
@@ -92,6 +146,7 @@ impl ChatRepository for ChatRepositoryImpl {
 
         let result = messages::table
             .filter(messages::id.eq(message_id))
+            .filter(messages_in_scope(DeletedScope::Exclude))
             .left_join(rooms::table.on(messages::room_id.eq(rooms::id)))
             .left_join(users::table.on(messages::created_by_id.eq(users::id)))
             .select((
@@ -117,10 +172,16 @@ impl ChatRepository for ChatRepositoryImpl {
     async fn create_message(&self, message: NewMessage<'_>) -> Result<Message, ChatError> {
         let mut conn = self.get_conn()?;
 
-        let new_message = insert_into(messages::table)
-            .values(&message)
-            .returning(Message::as_select())
-            .get_result(&mut conn);
+        let new_message = conn.transaction(|conn| {
+            let new_message = insert_into(messages::table)
+                .values(&message)
+                .returning(Message::as_select())
+                .get_result::<Message>(conn)?;
+
+            record_outbox(conn, new_message.id, OutboxEventEnum::Created)?;
+
+            Ok::<_, diesel::result::Error>(new_message)
+        });
 
         match new_message {
             Ok(message) => Ok(message),
@@ -133,11 +194,20 @@ impl ChatRepository for ChatRepositoryImpl {
     async fn update_message(&self, message: Message) -> Result<Message, ChatError> {
         let mut conn = self.get_conn()?;
 
-        let updated_message = update(messages::table)
-            .filter(messages::id.eq(message.id))
-            .set(messages::data.eq(message.data))
-            .returning(Message::as_select())
-            .get_result(&mut conn);
+        let updated_message = conn.transaction(|conn| {
+            let updated_message = update(messages::table)
+                .filter(messages::id.eq(message.id))
+                .set((
+                    messages::data.eq(message.data),
+                    messages::updated_at.eq(message.updated_at),
+                ))
+                .returning(Message::as_select())
+                .get_result::<Message>(conn)?;
+
+            record_outbox(conn, updated_message.id, OutboxEventEnum::Updated)?;
+
+            Ok::<_, diesel::result::Error>(updated_message)
+        });
 
         match updated_message {
             Ok(message) => Ok(message),
@@ -152,11 +222,20 @@ impl ChatRepository for ChatRepositoryImpl {
 
         let message_status: i16 = MessagesStatusEnum::Inactive.into();
 
-        let updated_message = update(messages::table)
-            .filter(messages::id.eq(message_id))
-            .set(messages::status.eq(message_status))
-            .returning(Message::as_select())
-            .get_result(&mut conn);
+        let updated_message = conn.transaction(|conn| {
+            let updated_message = update(messages::table)
+                .filter(messages::id.eq(message_id))
+                .set((
+                    messages::status.eq(message_status),
+                    messages::updated_at.eq(Utc::now().naive_utc()),
+                ))
+                .returning(Message::as_select())
+                .get_result::<Message>(conn)?;
+
+            record_outbox(conn, updated_message.id, OutboxEventEnum::Deleted)?;
+
+            Ok::<_, diesel::result::Error>(updated_message)
+        });
 
         match updated_message {
             Ok(message) => Ok(message),
@@ -165,4 +244,136 @@ impl ChatRepository for ChatRepositoryImpl {
             )),
         }
     }
+
+    async fn claim_outbox(
+        &self,
+        limit: i64,
+        lease: Duration,
+    ) -> Result<Vec<(OutboxEntry, MessageResponse)>, ChatError> {
+        let mut conn = self.get_conn()?;
+
+        let now = Utc::now().naive_utc();
+        let locked_until = now + lease;
+
+        let claimed = conn.transaction(|conn| {
+            let locked = diesel::select(pg_try_advisory_xact_lock(OUTBOX_LOCK_KEY))
+                .get_result::<bool>(conn)?;
+            if !locked {
+                return Ok(Vec::new());
+            }
+
+            // Taking the rows after someone else's batch would deliver them out of order
+            let in_flight = message_outbox::table
+                .filter(message_outbox::delivered_at.is_null())
+                .filter(message_outbox::locked_until.gt(now))
+                .count()
+                .get_result::<i64>(conn)?;
+            if in_flight > 0 {
+                return Ok(Vec::new());
+            }
+
+            let entries = message_outbox::table
+                .filter(message_outbox::delivered_at.is_null())
+                .order(message_outbox::id.asc())
+                .limit(limit)
+                .select(OutboxEntry::as_select())
+                .load::<OutboxEntry>(conn)?;
+            if entries.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let entry_ids: Vec<i32> = entries.iter().map(|entry| entry.id).collect();
+            update(message_outbox::table)
+                .filter(message_outbox::id.eq_any(&entry_ids))
+                .set(message_outbox::locked_until.eq(locked_until))
+                .execute(conn)?;
+
+            // Entries carry the message as it is now, not as it was when the event was recorded
+            let message_ids: Vec<i32> = entries.iter().map(|entry| entry.message_id).collect();
+            let messages = messages::table
+                .filter(messages::id.eq_any(&message_ids))
+                .left_join(rooms::table.on(messages::room_id.eq(rooms::id)))
+                .left_join(users::table.on(messages::created_by_id.eq(users::id)))
+                .select((
+                    Message::as_select(),
+                    Option::<Room>::as_select(),
+                    Option::<User>::as_select(),
+                ))
+                .load::<(Message, Option<Room>, Option<User>)>(conn)?
+                .into_iter()
+                .map(|(message, room, user)| {
+                    (
+                        message.id,
+                        MessageResponse {
+                            message,
+                            created_by: user,
+                            room,
+                        },
+                    )
+                })
+                .collect::<HashMap<_, _>>();
+
+            let claimed = entries
+                .into_iter()
+                .filter_map(|entry| {
+                    let message = messages.get(&entry.message_id).cloned()?;
+                    Some((entry, message))
+                })
+                .collect::<Vec<_>>();
+
+            Ok::<_, diesel::result::Error>(claimed)
+        });
+
+        claimed.map_err(|_| ChatError::UnexpectedError("Failed to claim outbox".to_string()))
+    }
+
+    async fn mark_outbox_delivered(&self, entry_ids: Vec<i32>) -> Result<(), ChatError> {
+        let mut conn = self.get_conn()?;
+
+        update(message_outbox::table)
+            .filter(message_outbox::id.eq_any(entry_ids))
+            .set((
+                message_outbox::delivered_at.eq(Utc::now().naive_utc()),
+                message_outbox::locked_until.eq(None::<NaiveDateTime>),
+            ))
+            .execute(&mut conn)
+            .map_err(|_| ChatError::UnexpectedError("Failed to update outbox".to_string()))?;
+
+        Ok(())
+    }
+
+    async fn prune_outbox(&self, delivered_before: NaiveDateTime) -> Result<usize, ChatError> {
+        let mut conn = self.get_conn()?;
+
+        delete(message_outbox::table)
+            .filter(message_outbox::delivered_at.lt(delivered_before))
+            .execute(&mut conn)
+            .map_err(|_| ChatError::UnexpectedError("Failed to prune outbox".to_string()))
+    }
+
+    async fn purge_expired_messages(
+        &self,
+        default_days: Option<i32>,
+        now: NaiveDateTime,
+        limit: i64,
+    ) -> Result<usize, ChatError> {
+        let mut conn = self.get_conn()?;
+
+        // A NULL period on both sides compares as NULL, so those rooms keep everything.
+        // Rooms under legal hold keep everything too, whatever their period.
+        sql_query(
+            "DELETE FROM messages WHERE id IN ( \
+                SELECT messages.id FROM messages \
+                JOIN rooms ON rooms.id = messages.room_id \
+                WHERE rooms.legal_hold_at IS NULL \
+                AND messages.created_at < $1 - make_interval(days => COALESCE(rooms.message_retention_days, $2)) \
+                LIMIT $3 \
+            )",
+        )
+        .bind::<Timestamp, _>(now)
+        .bind::<Nullable<Integer>, _>(default_days)
+        .bind::<BigInt, _>(limit)
+        .execute(&mut conn)
+        .map_err(|err| ChatError::UnexpectedError(err.to_string()))
+    }
 }
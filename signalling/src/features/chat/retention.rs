@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use tracing::{info, warn};
+
+use crate::core::{
+    env::app_env::ChatRetentionConfigs, utils::retention_progress_store::RetentionProgressStore,
+};
+
+use super::repository::ChatRepository;
+
+/// Breather between batches so a large backlog doesn't hog the database
+const BATCH_PAUSE: Duration = Duration::from_millis(200);
+
+/// Hard-deletes chat messages past their room's retention period.
+///
+/// Runs on every signalling node, a run is claimed in Redis so only one node
+/// purges at a time. Deleted messages are not announced to
+/// clients, they fall out of history the next time it is fetched.
+pub struct RetentionPurger<C: ChatRepository> {
+    chat_repository: C,
+    progress_store: RetentionProgressStore,
+    configs: ChatRetentionConfigs,
+}
+
+impl<C: ChatRepository + 'static> RetentionPurger<C> {
+    pub fn new(
+        chat_repository: C,
+        progress_store: RetentionProgressStore,
+        configs: ChatRetentionConfigs,
+    ) -> Self {
+        Self {
+            chat_repository,
+            progress_store,
+            configs,
+        }
+    }
+
+    pub fn spawn(self) {
+        let interval = Duration::from_secs(self.configs.interval_seconds.max(60) as u64);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+
+                self.purge().await;
+            }
+        });
+    }
+
+    async fn purge(&self) {
+        let batch_size = self.configs.batch_size.max(1);
+        let started_at = Utc::now().naive_utc();
+
+        match self.progress_store.start_run(started_at).await {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(err) => {
+                warn!("Failed to claim the retention run: {:?}", err);
+                return;
+            }
+        }
+
+        let mut total = 0;
+
+        loop {
+            // Rooms can have their own period even without a deployment default
+            let deleted = match self
+                .chat_repository
+                .purge_expired_messages(self.configs.default_days, started_at, batch_size)
+                .await
+            {
+                Ok(deleted) => deleted,
+                Err(err) => {
+                    warn!("Failed to purge expired messages: {:?}", err);
+                    break;
+                }
+            };
+
+            if deleted > 0 {
+                total += deleted;
+
+                if let Err(err) = self.progress_store.record_batch(deleted).await {
+                    warn!("Failed to record retention progress: {:?}", err);
+                }
+            }
+
+            if (deleted as i64) < batch_size {
+                break;
+            }
+
+            tokio::time::sleep(BATCH_PAUSE).await;
+        }
+
+        if total > 0 {
+            info!("Chat retention purged {} messages", total);
+        }
+
+        if let Err(err) = self.progress_store.finish_run(Utc::now().naive_utc()).await {
+            warn!("Failed to record retention progress: {:?}", err);
+        }
+    }
+}
@@ -1,4 +1,3 @@
-use async_channel::Sender;
 use salvo::{
     oapi::extract::{JsonBody, PathParam},
     prelude::*,
@@ -8,9 +7,9 @@ use crate::{
     core::{
         dtos::{chat::send_message_dto::SendMessageDto, common::pagination_dto::PaginationDto},
         types::{
-            app_channel::AppEvent,
             errors::chat_error::ChatError,
             responses::{
+                chat_retention_response::ChatRetentionResponse,
                 list_message_response::ListMessageResponse, message_response::MessageResponse,
                 room_response::RoomResponse,
             },
@@ -18,12 +17,19 @@ use crate::{
         utils::jwt_utils::JwtUtils,
     },
     features::{
-        chat::repository::ChatRepositoryImpl, room::repository::RoomRepositoryImpl,
+        chat::repository::ChatRepositoryImpl,
+        room::{
+            repository::RoomRepositoryImpl,
+            service::{RoomService, RoomServiceImpl},
+        },
         user::repository::UserRepositoryImpl,
     },
 };
 
-use super::service::{ChatService, ChatServiceImpl};
+use super::{
+    outbox::OutboxSignal,
+    service::{ChatService, ChatServiceImpl},
+};
 
 pub fn get_chat_router(jwt_utils: JwtUtils) -> Router {
     Router::with_hoop(jwt_utils.auth_middleware())
@@ -39,13 +45,30 @@ pub fn get_chat_router(jwt_utils: JwtUtils) -> Router {
                 .delete(delete_message),
         )
         .push(Router::with_path("conversations/{room_id}").delete(delete_conversation))
+        .push(Router::with_path("/{room_id}/retention").get(get_retention))
+}
+
+/// How many days the room's messages are kept, for "messages are retained for N days" notices
+#[endpoint(tags("chats"), status_codes(200, 404, 500))]
+async fn get_retention(
+    _res: &mut Response,
+    room_id: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<ChatRetentionResponse, ChatError> {
+    let chat_service = depot
+        .obtain::<ChatServiceImpl<ChatRepositoryImpl, RoomRepositoryImpl, UserRepositoryImpl>>()
+        .unwrap();
+
+    let room_id = resolve_room_id(depot, &room_id.0).await?;
+
+    chat_service.get_retention(room_id).await
 }
 
 /// Get messages by room
 #[endpoint(tags("chats"), status_codes(200, 400, 500))]
 async fn get_messages_by_room(
     _res: &mut Response,
-    room_id: PathParam<i32>,
+    room_id: PathParam<String>,
     pagination_dto: PaginationDto,
     depot: &mut Depot,
 ) -> Result<ListMessageResponse, ChatError> {
@@ -55,7 +78,7 @@ async fn get_messages_by_room(
     let user_id = depot.get::<String>("user_id").unwrap();
 
     let pagination_dto = pagination_dto.clone();
-    let room_id = room_id.0;
+    let room_id = resolve_room_id(depot, &room_id.0).await?;
 
     let messages = chat_service
         .get_messages_by_room(
@@ -73,25 +96,23 @@ async fn get_messages_by_room(
 #[endpoint(tags("chats"), status_codes(201, 400, 403, 404, 500))]
 async fn create_message(
     _res: &mut Response,
-    room_id: PathParam<i32>,
+    room_id: PathParam<String>,
     data: JsonBody<SendMessageDto>,
     depot: &mut Depot,
 ) -> Result<MessageResponse, ChatError> {
     let chat_service = depot
         .obtain::<ChatServiceImpl<ChatRepositoryImpl, RoomRepositoryImpl, UserRepositoryImpl>>()
         .unwrap();
-    let app_channel_tx = depot.obtain::<Sender<AppEvent>>().unwrap();
+    let outbox_signal = depot.obtain::<OutboxSignal>().unwrap();
     let user_id = depot.get::<String>("user_id").unwrap();
     let data = data.0.data;
-    let room_id = room_id.into_inner();
+    let room_id = resolve_room_id(depot, &room_id.into_inner()).await?;
 
     let message = chat_service
         .create_message(room_id, user_id.parse().unwrap(), &data)
         .await?;
 
-    let _ = app_channel_tx
-        .send(AppEvent::SendMessage(message.clone()))
-        .await;
+    outbox_signal.wake();
 
     Ok(message)
 }
@@ -107,7 +128,7 @@ async fn update_message(
     let chat_service = depot
         .obtain::<ChatServiceImpl<ChatRepositoryImpl, RoomRepositoryImpl, UserRepositoryImpl>>()
         .unwrap();
-    let app_channel_tx = depot.obtain::<Sender<AppEvent>>().unwrap();
+    let outbox_signal = depot.obtain::<OutboxSignal>().unwrap();
     let user_id = depot.get::<String>("user_id").unwrap();
     let data = data.0.data;
     let message_id = message_id.into_inner();
@@ -116,9 +137,7 @@ async fn update_message(
         .update_message(message_id, user_id.parse().unwrap(), &data)
         .await?;
 
-    let _ = app_channel_tx
-        .send(AppEvent::UpdateMessage(message.clone()))
-        .await;
+    outbox_signal.wake();
 
     Ok(message)
 }
@@ -133,7 +152,7 @@ async fn delete_message(
     let chat_service = depot
         .obtain::<ChatServiceImpl<ChatRepositoryImpl, RoomRepositoryImpl, UserRepositoryImpl>>()
         .unwrap();
-    let app_channel_tx = depot.obtain::<Sender<AppEvent>>().unwrap();
+    let outbox_signal = depot.obtain::<OutboxSignal>().unwrap();
     let user_id = depot.get::<String>("user_id").unwrap();
     let message_id = message_id.into_inner();
 
@@ -141,9 +160,7 @@ async fn delete_message(
         .delete_message_by_id(message_id, user_id.parse().unwrap())
         .await?;
 
-    let _ = app_channel_tx
-        .send(AppEvent::DeleteMessage(message.clone()))
-        .await;
+    outbox_signal.wake();
 
     Ok(message)
 }
@@ -152,14 +169,14 @@ async fn delete_message(
 #[endpoint(tags("chats"), status_codes(200, 400, 403, 404, 500))]
 async fn delete_conversation(
     _res: &mut Response,
-    room_id: PathParam<i32>,
+    room_id: PathParam<String>,
     depot: &mut Depot,
 ) -> Result<RoomResponse, ChatError> {
     let chat_service = depot
         .obtain::<ChatServiceImpl<ChatRepositoryImpl, RoomRepositoryImpl, UserRepositoryImpl>>()
         .unwrap();
     let user_id = depot.get::<String>("user_id").unwrap();
-    let room_id = room_id.into_inner();
+    let room_id = resolve_room_id(depot, &room_id.into_inner()).await?;
 
     let room = chat_service
         .delete_conversation(room_id, user_id.parse().unwrap())
@@ -170,5 +187,17 @@ async fn delete_conversation(
         members: vec![],
         participants: vec![],
         latest_message: None,
+        branding: None,
+        overflow: None,
     })
 }
+
+/// Rooms are addressed by their public id, the services work with internal keys
+async fn resolve_room_id(depot: &Depot, room_id: &str) -> Result<i32, ChatError> {
+    depot
+        .obtain::<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>()
+        .unwrap()
+        .resolve_room_id(room_id)
+        .await
+        .map_err(|_| ChatError::RoomNotFound(room_id.to_string()))
+}
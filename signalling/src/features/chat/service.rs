@@ -3,8 +3,14 @@ use salvo::async_trait;
 
 use crate::{
     core::{
+        database::soft_delete::DeletedScope,
         entities::models::{MessagesStatusEnum, MessagesTypeEnum, NewMessage, Room},
-        types::{errors::chat_error::ChatError, responses::message_response::MessageResponse},
+        types::{
+            errors::chat_error::ChatError,
+            responses::{
+                chat_retention_response::ChatRetentionResponse, message_response::MessageResponse,
+            },
+        },
     },
     features::{room::repository::RoomRepository, user::repository::UserRepository},
 };
@@ -53,6 +59,9 @@ pub trait ChatService: Send + Sync {
         now: NaiveDateTime,
         latest_mesage_id: Option<i32>,
     );
+
+    /// How long the room's messages are kept before the retention purge deletes them
+    async fn get_retention(&self, room_id: i32) -> Result<ChatRetentionResponse, ChatError>;
 }
 
 #[derive(Debug, Clone)]
@@ -60,6 +69,7 @@ pub struct ChatServiceImpl<C: ChatRepository, R: RoomRepository, U: UserReposito
     chat_repository: C,
     room_repository: R,
     user_repository: U,
+    default_retention_days: Option<i32>,
 }
 
 impl<C: ChatRepository, R: RoomRepository, U: UserRepository> ChatServiceImpl<C, R, U> {
@@ -68,8 +78,14 @@ impl<C: ChatRepository, R: RoomRepository, U: UserRepository> ChatServiceImpl<C,
             chat_repository,
             room_repository,
             user_repository,
+            default_retention_days: None,
         }
     }
+
+    pub fn with_retention_days(mut self, default_retention_days: Option<i32>) -> Self {
+        self.default_retention_days = default_retention_days;
+        self
+    }
 }
 
 #[async_trait]
@@ -88,7 +104,7 @@ impl<
     ) -> Result<Vec<MessageResponse>, ChatError> {
         let room = self
             .room_repository
-            .get_room_by_id(room_id)
+            .get_room_by_id(room_id, DeletedScope::Exclude)
             .await
             .map_err(|_| ChatError::ConversationNotFound(room_id))?;
 
@@ -138,7 +154,7 @@ impl<
 
         let room = self
             .room_repository
-            .get_room_by_id(room_id)
+            .get_room_by_id(room_id, DeletedScope::Exclude)
             .await
             .map_err(|_| ChatError::ConversationNotFound(room_id))?;
 
@@ -176,9 +192,7 @@ impl<
         let room = message_response.clone().room.unwrap();
 
         if message_response.message.status == MessagesStatusEnum::Inactive as i16 {
-            return Err(ChatError::UnexpectedError(
-                "Message has been deleted".to_string(),
-            ));
+            return Err(ChatError::MessageDeleted(message_id));
         }
 
         if message_response.message.created_by_id != user_id {
@@ -210,9 +224,7 @@ impl<
         let mut message_response = self.chat_repository.get_message_by_id(message_id).await?;
 
         if message_response.message.status == MessagesStatusEnum::Inactive as i16 {
-            return Err(ChatError::UnexpectedError(
-                "Message has been deleted".to_string(),
-            ));
+            return Err(ChatError::MessageDeleted(message_id));
         }
 
         if message_response.message.created_by_id != user_id {
@@ -221,11 +233,10 @@ impl<
             ));
         }
 
-        let mut message = message_response.message;
-
-        message.status = MessagesStatusEnum::Inactive.into();
-
-        let message = self.chat_repository.update_message(message).await?;
+        let message = self
+            .chat_repository
+            .delete_message_by_id(message_response.message.id)
+            .await?;
 
         message_response.message = message;
 
@@ -239,7 +250,7 @@ impl<
     ) -> Result<Room, ChatError> {
         let room = self
             .room_repository
-            .get_room_by_id(conversation_id)
+            .get_room_by_id(conversation_id, DeletedScope::Exclude)
             .await
             .map_err(|_| ChatError::ConversationNotFound(conversation_id))?;
 
@@ -283,10 +294,27 @@ impl<
 
         let _ = self.room_repository.update_room(room).await;
     }
+
+    async fn get_retention(&self, room_id: i32) -> Result<ChatRetentionResponse, ChatError> {
+        let room = self
+            .room_repository
+            .get_room_by_id(room_id, DeletedScope::Exclude)
+            .await
+            .map_err(|_| ChatError::ConversationNotFound(room_id))?;
+
+        let room_days = room.room.message_retention_days;
+
+        Ok(ChatRetentionResponse {
+            retention_days: room_days.or(self.default_retention_days),
+            is_room_policy: room_days.is_some(),
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use super::*;
     use crate::core::entities::models::*;
     use crate::core::types::errors::chat_error::ChatError;
@@ -296,6 +324,7 @@ mod tests {
     use crate::core::types::responses::room_response::{
         MemberResponse, ParticipantResponse, RoomResponse,
     };
+    use crate::features::room::repository::MediaChange;
     use chrono::DateTime;
 
     // --- Sample Data Helpers ---
@@ -312,6 +341,9 @@ mod tests {
             updated_at: now,
             deleted_at: None,
             last_seen_at: None,
+            public_id: "U0000000000000000000000001".to_string(),
+            locale: "en-US".to_string(),
+            timezone: "UTC".to_string(),
         }
     }
 
@@ -330,6 +362,14 @@ mod tests {
             deleted_at: None,
             latest_message_id: None,
             type_: 0,
+            public_id: "R0000000000000000000000001".to_string(),
+            message_retention_days: None,
+            legal_hold_at: None,
+            noise_suppression: false,
+            broadcast_delay_secs: 0,
+            capacity: None,
+            presenter_member_ids: vec![],
+            streaming_protocol: 0,
         }
     }
 
@@ -378,6 +418,8 @@ mod tests {
             }],
             participants: vec![],
             latest_message: None,
+            branding: None,
+            overflow: None,
         }
     }
 
@@ -438,6 +480,27 @@ mod tests {
                 .clone()
                 .ok_or(ChatError::UnexpectedError("fail delete".to_string()))
         }
+        async fn claim_outbox(
+            &self,
+            _limit: i64,
+            _lease: Duration,
+        ) -> Result<Vec<(OutboxEntry, MessageResponse)>, ChatError> {
+            Ok(vec![])
+        }
+        async fn mark_outbox_delivered(&self, _entry_ids: Vec<i32>) -> Result<(), ChatError> {
+            Ok(())
+        }
+        async fn prune_outbox(&self, _delivered_before: NaiveDateTime) -> Result<usize, ChatError> {
+            Ok(0)
+        }
+        async fn purge_expired_messages(
+            &self,
+            _default_days: Option<i32>,
+            _now: NaiveDateTime,
+            _limit: i64,
+        ) -> Result<usize, ChatError> {
+            Ok(0)
+        }
     }
 
     #[derive(Clone)]
@@ -462,7 +525,11 @@ mod tests {
         async fn exists_code(&self, _room_code: &str) -> Result<bool, RoomError> {
             unimplemented!()
         }
-        async fn get_room_by_id(&self, _room_id: i32) -> Result<RoomResponse, RoomError> {
+        async fn get_room_by_id(
+            &self,
+            _room_id: i32,
+            _scope: DeletedScope,
+        ) -> Result<RoomResponse, RoomError> {
             if let Some(ref err) = self.fail {
                 return Err(RoomError::UnexpectedError(format!("{err:?}")));
             }
@@ -471,6 +538,16 @@ mod tests {
         async fn get_room_by_code(&self, _room_code: &str) -> Result<RoomResponse, RoomError> {
             unimplemented!()
         }
+        async fn get_room_password(&self, _room_id: i32) -> Result<Option<String>, RoomError> {
+            unimplemented!()
+        }
+        async fn get_room_id_by_public_id(
+            &self,
+            _public_id: &str,
+            _scope: DeletedScope,
+        ) -> Result<i32, RoomError> {
+            unimplemented!()
+        }
         async fn create_room(&self, _room: NewRoom<'_>) -> Result<RoomResponse, RoomError> {
             unimplemented!()
         }
@@ -493,6 +570,15 @@ mod tests {
         async fn get_member_by_id(&self, _member_id: i32) -> Result<MemberResponse, RoomError> {
             unimplemented!()
         }
+        async fn find_members(
+            &self,
+            _room_id: i32,
+            _query: Option<String>,
+            _skip: i64,
+            _limit: i64,
+        ) -> Result<Vec<MemberResponse>, RoomError> {
+            unimplemented!()
+        }
         async fn create_member(&self, _member: NewMember<'_>) -> Result<MemberResponse, RoomError> {
             unimplemented!()
         }
@@ -513,6 +599,21 @@ mod tests {
         ) -> Result<ParticipantResponse, RoomError> {
             unimplemented!()
         }
+        async fn get_participant_by_public_id(
+            &self,
+            _public_id: &str,
+        ) -> Result<ParticipantResponse, RoomError> {
+            unimplemented!()
+        }
+        async fn find_participants(
+            &self,
+            _room_id: i32,
+            _query: Option<String>,
+            _skip: i64,
+            _limit: i64,
+        ) -> Result<Vec<ParticipantResponse>, RoomError> {
+            unimplemented!()
+        }
         async fn create_participant(
             &self,
             _participant: NewParticipant<'_>,
@@ -528,7 +629,23 @@ mod tests {
         async fn delete_participant_by_id(&self, _participant_id: i32) -> Result<(), RoomError> {
             unimplemented!()
         }
-        async fn delete_participants_by_node(&self, _node_id: &str) -> Result<(), RoomError> {
+        async fn delete_participants_by_node(&self, _node_id: &str) -> Result<Vec<i32>, RoomError> {
+            unimplemented!()
+        }
+        async fn detach_participants_by_node(&self, _node_id: &str) -> Result<Vec<i32>, RoomError> {
+            unimplemented!()
+        }
+        async fn update_participant_media(
+            &self,
+            _participant_id: &str,
+            _change: MediaChange,
+        ) -> Result<Participant, RoomError> {
+            unimplemented!()
+        }
+        async fn purge_detached_participants(
+            &self,
+            _detached_before: NaiveDateTime,
+        ) -> Result<Vec<(Room, Participant)>, RoomError> {
             unimplemented!()
         }
     }
@@ -547,6 +664,13 @@ mod tests {
             }
             self.user.clone().ok_or(UserError::UserNotFound(_user_id))
         }
+        async fn get_user_by_public_id(
+            &self,
+            _public_id: &str,
+            _scope: DeletedScope,
+        ) -> Result<User, UserError> {
+            unimplemented!()
+        }
         async fn update_user(&self, _user: User) -> Result<User, UserError> {
             unimplemented!()
         }
@@ -825,4 +949,60 @@ mod tests {
         let result = service.delete_conversation(1, 1).await;
         assert!(matches!(result, Err(ChatError::ConversationNotFound(1))));
     }
+
+    #[tokio::test]
+    async fn test_get_retention_falls_back_to_default() {
+        let chat_repo = MockChatRepository {
+            messages: None,
+            message: None,
+            new_message: None,
+            updated_message: None,
+            delete_message: None,
+            fail: None,
+        };
+        let room_repo = MockRoomRepository {
+            room: Some(sample_room_response(1, 1)),
+            updated_member: None,
+            updated_room: None,
+            fail: None,
+        };
+        let user_repo = MockUserRepository {
+            user: Some(sample_user()),
+            fail: None,
+        };
+        let service =
+            ChatServiceImpl::new(chat_repo, room_repo, user_repo).with_retention_days(Some(90));
+        let retention = service.get_retention(1).await.unwrap();
+        assert_eq!(retention.retention_days, Some(90));
+        assert!(!retention.is_room_policy);
+    }
+
+    #[tokio::test]
+    async fn test_get_retention_room_policy_wins() {
+        let chat_repo = MockChatRepository {
+            messages: None,
+            message: None,
+            new_message: None,
+            updated_message: None,
+            delete_message: None,
+            fail: None,
+        };
+        let mut room_resp = sample_room_response(1, 1);
+        room_resp.room.message_retention_days = Some(7);
+        let room_repo = MockRoomRepository {
+            room: Some(room_resp),
+            updated_member: None,
+            updated_room: None,
+            fail: None,
+        };
+        let user_repo = MockUserRepository {
+            user: Some(sample_user()),
+            fail: None,
+        };
+        let service =
+            ChatServiceImpl::new(chat_repo, room_repo, user_repo).with_retention_days(Some(90));
+        let retention = service.get_retention(1).await.unwrap();
+        assert_eq!(retention.retention_days, Some(7));
+        assert!(retention.is_room_policy);
+    }
 }
@@ -0,0 +1,30 @@
+use salvo::{oapi::extract::QueryParam, prelude::*};
+
+use crate::core::{
+    types::{errors::org_error::OrgError, responses::client_config_response::ClientConfigResponse},
+    utils::jwt_utils::JwtUtils,
+};
+
+use super::service::{ClientConfigService, ServedClientConfigs};
+
+pub fn get_client_config_router(jwt_utils: JwtUtils) -> Router {
+    Router::with_hoop(jwt_utils.auth_middleware())
+        .path("client-config")
+        .get(get_client_config)
+}
+
+/// Feature flags, ICE servers, bitrate caps, codec preferences and socket
+/// endpoints for this deployment. Pass the organization the user signed in
+/// with as `org_id` to get its flags too.
+#[endpoint(tags("client-config"), status_codes(200, 401, 404, 500))]
+async fn get_client_config(
+    _res: &mut Response,
+    org_id: QueryParam<String, false>,
+    depot: &mut Depot,
+) -> Result<ClientConfigResponse, OrgError> {
+    let client_config_service = depot.obtain::<ServedClientConfigs>().unwrap();
+
+    client_config_service
+        .get_client_config(org_id.into_inner().as_deref())
+        .await
+}
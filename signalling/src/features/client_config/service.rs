@@ -0,0 +1,186 @@
+use salvo::async_trait;
+
+use crate::{
+    core::{
+        env::app_env::ClientConfigs,
+        socket::{CHAT_NAMESPACE, RTC_NAMESPACE, SOCKET_PATH},
+        types::{
+            errors::org_error::OrgError,
+            responses::client_config_response::{
+                ClientConfigResponse, IceServerResponse, MaxBitratesResponse,
+                SocketEndpointsResponse,
+            },
+        },
+    },
+    features::org::{
+        repository::OrgRepositoryImpl,
+        service::{OrgService, OrgServiceImpl},
+    },
+};
+
+/// Client configs as wired up against Postgres
+pub type ServedClientConfigs = ClientConfigServiceImpl<OrgServiceImpl<OrgRepositoryImpl>>;
+
+#[async_trait]
+pub trait ClientConfigService: Send + Sync {
+    /// With `org_id` the organization's flags are on as well
+    async fn get_client_config(
+        &self,
+        org_id: Option<&str>,
+    ) -> Result<ClientConfigResponse, OrgError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientConfigServiceImpl<O: OrgService> {
+    org_service: O,
+    configs: ClientConfigs,
+    features: Vec<String>,
+}
+
+impl<O: OrgService> ClientConfigServiceImpl<O> {
+    pub fn new(org_service: O, configs: ClientConfigs) -> Self {
+        let features = merge_features(&configs.features, &[]);
+
+        Self {
+            org_service,
+            configs,
+            features,
+        }
+    }
+
+    /// Turns on flags for what this deployment runs with, like the callout
+    /// provider, on top of the configured ones
+    pub fn with_features<'a>(mut self, features: impl IntoIterator<Item = &'a str>) -> Self {
+        let features = features.into_iter().map(str::to_string).collect::<Vec<_>>();
+        self.features = merge_features(&self.features, &features);
+        self
+    }
+}
+
+#[async_trait]
+impl<O: OrgService> ClientConfigService for ClientConfigServiceImpl<O> {
+    async fn get_client_config(
+        &self,
+        org_id: Option<&str>,
+    ) -> Result<ClientConfigResponse, OrgError> {
+        let features = match org_id {
+            Some(org_id) => {
+                let org_features = self.org_service.get_client_features(org_id).await?;
+                merge_features(&self.features, &org_features)
+            }
+            None => self.features.clone(),
+        };
+
+        Ok(ClientConfigResponse {
+            features,
+            ice_servers: ice_servers(&self.configs),
+            max_bitrates: MaxBitratesResponse {
+                video: self.configs.max_video_bitrate_kbps,
+                screen: self.configs.max_screen_bitrate_kbps,
+                audio: self.configs.max_audio_bitrate_kbps,
+            },
+            video_codecs: self
+                .configs
+                .video_codecs
+                .iter()
+                .map(|codec| codec.to_lowercase())
+                .collect(),
+            socket: SocketEndpointsResponse {
+                url: self.configs.socket_url.clone(),
+                path: SOCKET_PATH.to_string(),
+                rtc_namespace: RTC_NAMESPACE.to_string(),
+                chat_namespace: CHAT_NAMESPACE.to_string(),
+            },
+        })
+    }
+}
+
+/// Lowercased, sorted and once each, like organizations store theirs
+pub fn merge_features(server: &[String], org: &[String]) -> Vec<String> {
+    let mut features = server
+        .iter()
+        .chain(org)
+        .map(|feature| feature.trim().to_lowercase())
+        .filter(|feature| !feature.is_empty())
+        .collect::<Vec<_>>();
+    features.sort();
+    features.dedup();
+
+    features
+}
+
+/// STUN servers go out as one entry and TURN servers as another, only the
+/// latter carry the credentials
+pub fn ice_servers(configs: &ClientConfigs) -> Vec<IceServerResponse> {
+    let (turn, stun): (Vec<String>, Vec<String>) = configs
+        .ice_servers
+        .iter()
+        .cloned()
+        .partition(|url| url.starts_with("turn:") || url.starts_with("turns:"));
+
+    let mut ice_servers = Vec::new();
+
+    if !stun.is_empty() {
+        ice_servers.push(IceServerResponse {
+            urls: stun,
+            username: None,
+            credential: None,
+        });
+    }
+
+    if !turn.is_empty() {
+        ice_servers.push(IceServerResponse {
+            urls: turn,
+            username: configs.turn_username.clone(),
+            credential: configs.turn_credential.clone(),
+        });
+    }
+
+    ice_servers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_configs() -> ClientConfigs {
+        ClientConfigs {
+            ice_servers: vec![
+                "stun:stun.waterbus.tech:3478".to_string(),
+                "turn:turn.waterbus.tech:3478?transport=udp".to_string(),
+                "turns:turn.waterbus.tech:5349".to_string(),
+            ],
+            turn_username: Some("waterbus".to_string()),
+            turn_credential: Some("secret".to_string()),
+            max_video_bitrate_kbps: 1_500,
+            max_screen_bitrate_kbps: 2_500,
+            max_audio_bitrate_kbps: 64,
+            video_codecs: vec!["VP8".to_string(), "h264".to_string()],
+            features: vec!["e2ee".to_string()],
+            socket_url: None,
+        }
+    }
+
+    #[test]
+    fn test_ice_servers_keep_credentials_to_turn() {
+        let ice_servers = ice_servers(&sample_configs());
+
+        assert_eq!(ice_servers.len(), 2);
+        assert_eq!(ice_servers[0].urls, vec!["stun:stun.waterbus.tech:3478"]);
+        assert_eq!(ice_servers[0].credential, None);
+        assert_eq!(ice_servers[1].urls.len(), 2);
+        assert_eq!(ice_servers[1].username.as_deref(), Some("waterbus"));
+    }
+
+    #[test]
+    fn test_merge_features() {
+        let server = vec!["e2ee".to_string(), "Search".to_string()];
+        let org = vec!["whiteboard".to_string(), "search".to_string()];
+
+        assert_eq!(
+            merge_features(&server, &org),
+            vec!["e2ee", "search", "whiteboard"]
+        );
+        assert_eq!(merge_features(&[], &[]), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,137 @@
+use chrono::NaiveDateTime;
+use diesel::{
+    ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper,
+    dsl::update,
+    r2d2::{ConnectionManager, Pool, PooledConnection},
+};
+use salvo::async_trait;
+
+use crate::core::{
+    database::{
+        db::checkout,
+        schema::{callouts, live_schedules, members, messages, rooms, users},
+    },
+    entities::models::{Callout, LiveSchedule, Member, Message, Room},
+    types::errors::{compliance_error::ComplianceError, general::GeneralError},
+};
+
+/// Rows are paired with the public id of the user they belong to
+#[async_trait]
+pub trait ComplianceRepository: Send + Sync {
+    /// Soft-deleted rooms are found too, a hold can outlive the room
+    async fn find_room(&self, public_id: &str) -> Result<Room, ComplianceError>;
+
+    async fn set_legal_hold(
+        &self,
+        room_id: i32,
+        legal_hold_at: Option<NaiveDateTime>,
+    ) -> Result<Room, ComplianceError>;
+
+    /// Every message of the room whatever its status, oldest first
+    async fn find_messages(&self, room_id: i32) -> Result<Vec<(Message, String)>, ComplianceError>;
+
+    async fn find_members(&self, room_id: i32) -> Result<Vec<(Member, String)>, ComplianceError>;
+
+    async fn find_callouts(&self, room_id: i32) -> Result<Vec<(Callout, String)>, ComplianceError>;
+
+    async fn find_live_schedule(
+        &self,
+        room_id: i32,
+    ) -> Result<Option<(LiveSchedule, String)>, ComplianceError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct ComplianceRepositoryImpl {
+    pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl ComplianceRepositoryImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Self {
+        Self { pool }
+    }
+
+    fn get_conn(&self) -> Result<PooledConnection<ConnectionManager<PgConnection>>, GeneralError> {
+        checkout(&self.pool)
+    }
+}
+
+#[async_trait]
+impl ComplianceRepository for ComplianceRepositoryImpl {
+    async fn find_room(&self, public_id: &str) -> Result<Room, ComplianceError> {
+        let mut conn = self.get_conn()?;
+
+        rooms::table
+            .filter(rooms::public_id.eq(public_id))
+            .select(Room::as_select())
+            .first(&mut conn)
+            .optional()
+            .map_err(|err| ComplianceError::UnexpectedError(err.to_string()))?
+            .ok_or_else(|| ComplianceError::RoomNotFound(public_id.to_string()))
+    }
+
+    async fn set_legal_hold(
+        &self,
+        room_id: i32,
+        legal_hold_at: Option<NaiveDateTime>,
+    ) -> Result<Room, ComplianceError> {
+        let mut conn = self.get_conn()?;
+
+        update(rooms::table)
+            .filter(rooms::id.eq(room_id))
+            .set(rooms::legal_hold_at.eq(legal_hold_at))
+            .returning(Room::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| ComplianceError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_messages(&self, room_id: i32) -> Result<Vec<(Message, String)>, ComplianceError> {
+        let mut conn = self.get_conn()?;
+
+        messages::table
+            .inner_join(users::table)
+            .filter(messages::room_id.eq(room_id))
+            .order(messages::id.asc())
+            .select((Message::as_select(), users::public_id))
+            .load(&mut conn)
+            .map_err(|err| ComplianceError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_members(&self, room_id: i32) -> Result<Vec<(Member, String)>, ComplianceError> {
+        let mut conn = self.get_conn()?;
+
+        members::table
+            .inner_join(users::table)
+            .filter(members::room_id.eq(room_id))
+            .order(members::id.asc())
+            .select((Member::as_select(), users::public_id))
+            .load(&mut conn)
+            .map_err(|err| ComplianceError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_callouts(&self, room_id: i32) -> Result<Vec<(Callout, String)>, ComplianceError> {
+        let mut conn = self.get_conn()?;
+
+        callouts::table
+            .inner_join(users::table)
+            .filter(callouts::room_id.eq(room_id))
+            .order(callouts::id.asc())
+            .select((Callout::as_select(), users::public_id))
+            .load(&mut conn)
+            .map_err(|err| ComplianceError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_live_schedule(
+        &self,
+        room_id: i32,
+    ) -> Result<Option<(LiveSchedule, String)>, ComplianceError> {
+        let mut conn = self.get_conn()?;
+
+        live_schedules::table
+            .inner_join(users::table)
+            .filter(live_schedules::room_id.eq(room_id))
+            .select((LiveSchedule::as_select(), users::public_id))
+            .first(&mut conn)
+            .optional()
+            .map_err(|err| ComplianceError::UnexpectedError(err.to_string()))
+    }
+}
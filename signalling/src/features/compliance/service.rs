@@ -0,0 +1,398 @@
+use chrono::{NaiveDateTime, Utc};
+use salvo::async_trait;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::core::{
+    entities::models::{Callout, LiveSchedule, Member, Message, MessagesStatusEnum, Room},
+    types::{
+        errors::compliance_error::ComplianceError,
+        responses::compliance_response::{
+            ComplianceEvent, ComplianceExportResponse, ComplianceIntegrity, ComplianceMessage,
+            ComplianceRecording, LegalHoldResponse,
+        },
+    },
+};
+
+use super::repository::ComplianceRepository;
+
+#[async_trait]
+pub trait ComplianceService: Send + Sync {
+    /// Placing a hold twice keeps the time it was first placed
+    async fn set_legal_hold(
+        &self,
+        public_id: &str,
+        is_held: bool,
+    ) -> Result<LegalHoldResponse, ComplianceError>;
+
+    /// Recordings live on the SFU nodes, the caller collects them beforehand
+    async fn export_room(
+        &self,
+        public_id: &str,
+        recordings: Vec<ComplianceRecording>,
+        unreachable_nodes: Vec<String>,
+    ) -> Result<ComplianceExportResponse, ComplianceError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct ComplianceServiceImpl<C: ComplianceRepository> {
+    compliance_repository: C,
+}
+
+impl<C: ComplianceRepository> ComplianceServiceImpl<C> {
+    pub fn new(compliance_repository: C) -> Self {
+        Self {
+            compliance_repository,
+        }
+    }
+}
+
+#[async_trait]
+impl<C: ComplianceRepository> ComplianceService for ComplianceServiceImpl<C> {
+    async fn set_legal_hold(
+        &self,
+        public_id: &str,
+        is_held: bool,
+    ) -> Result<LegalHoldResponse, ComplianceError> {
+        let room = self.compliance_repository.find_room(public_id).await?;
+
+        let legal_hold_at = match (is_held, room.legal_hold_at) {
+            (true, Some(held_at)) => Some(held_at),
+            (true, None) => Some(Utc::now().naive_utc()),
+            (false, _) => None,
+        };
+
+        let room = self
+            .compliance_repository
+            .set_legal_hold(room.id, legal_hold_at)
+            .await?;
+
+        Ok(LegalHoldResponse {
+            room_id: room.public_id,
+            legal_hold_at: room.legal_hold_at,
+        })
+    }
+
+    async fn export_room(
+        &self,
+        public_id: &str,
+        recordings: Vec<ComplianceRecording>,
+        unreachable_nodes: Vec<String>,
+    ) -> Result<ComplianceExportResponse, ComplianceError> {
+        let room = self.compliance_repository.find_room(public_id).await?;
+
+        let messages = self.compliance_repository.find_messages(room.id).await?;
+        let members = self.compliance_repository.find_members(room.id).await?;
+        let callouts = self.compliance_repository.find_callouts(room.id).await?;
+        let schedule = self
+            .compliance_repository
+            .find_live_schedule(room.id)
+            .await?;
+
+        let events = build_events(&room, &members, &messages, &callouts, schedule.as_ref());
+
+        let messages: Vec<ComplianceMessage> = messages
+            .into_iter()
+            .map(|(message, author_id)| ComplianceMessage {
+                id: message.id,
+                author_id,
+                data: message.data,
+                type_: message.type_,
+                status: message.status,
+                created_at: message.created_at,
+                updated_at: message.updated_at,
+                deleted_at: message.deleted_at,
+            })
+            .collect();
+
+        let integrity = build_integrity(&messages, &events, &recordings)
+            .map_err(|err| ComplianceError::UnexpectedError(err.to_string()))?;
+
+        Ok(ComplianceExportResponse {
+            room_id: room.public_id,
+            title: room.title,
+            created_at: room.created_at,
+            deleted_at: room.deleted_at,
+            legal_hold_at: room.legal_hold_at,
+            generated_at: Utc::now().naive_utc(),
+            messages,
+            events,
+            recordings,
+            unreachable_nodes,
+            integrity,
+        })
+    }
+}
+
+/// Manifests that aren't valid JSON are kept as the raw string, the digest
+/// covers the stored bytes either way
+pub fn to_recording(
+    node_id: String,
+    participant_id: String,
+    manifest_json: &str,
+) -> ComplianceRecording {
+    let manifest = serde_json::from_str(manifest_json)
+        .unwrap_or_else(|_| serde_json::Value::String(manifest_json.to_string()));
+
+    ComplianceRecording {
+        node_id,
+        participant_id,
+        sha256: sha256_hex(manifest_json.as_bytes()),
+        manifest,
+    }
+}
+
+/// The room's history in time order, pieced together from the rows it left behind
+fn build_events(
+    room: &Room,
+    members: &[(Member, String)],
+    messages: &[(Message, String)],
+    callouts: &[(Callout, String)],
+    schedule: Option<&(LiveSchedule, String)>,
+) -> Vec<ComplianceEvent> {
+    let event =
+        |at: NaiveDateTime, kind: &str, actor_id: Option<&String>, detail: Option<String>| {
+            ComplianceEvent {
+                at,
+                kind: kind.to_string(),
+                actor_id: actor_id.cloned(),
+                detail,
+            }
+        };
+
+    let mut events = vec![event(room.created_at, "room_created", None, None)];
+
+    for (member, user_id) in members {
+        events.push(event(
+            member.created_at,
+            "member_joined",
+            Some(user_id),
+            None,
+        ));
+
+        if let Some(deleted_at) = member.deleted_at {
+            events.push(event(deleted_at, "member_left", Some(user_id), None));
+        }
+    }
+
+    for (message, user_id) in messages {
+        let detail = Some(format!("message {}", message.id));
+
+        events.push(event(
+            message.created_at,
+            "message_sent",
+            Some(user_id),
+            detail.clone(),
+        ));
+
+        if message.status == MessagesStatusEnum::Inactive as i16 {
+            let deleted_at = message.deleted_at.unwrap_or(message.updated_at);
+            events.push(event(deleted_at, "message_deleted", Some(user_id), detail));
+        } else if message.updated_at > message.created_at {
+            events.push(event(
+                message.updated_at,
+                "message_edited",
+                Some(user_id),
+                detail,
+            ));
+        }
+    }
+
+    for (callout, user_id) in callouts {
+        events.push(event(
+            callout.created_at,
+            "callout_requested",
+            Some(user_id),
+            Some(format!(
+                "{} via {}, {}",
+                callout.phone_number, callout.provider, callout.status
+            )),
+        ));
+    }
+
+    if let Some((schedule, user_id)) = schedule {
+        events.push(event(
+            schedule.created_at,
+            "live_scheduled",
+            Some(user_id),
+            Some(format!(
+                "start at {}, {}",
+                schedule.start_at, schedule.status
+            )),
+        ));
+    }
+
+    if let Some(legal_hold_at) = room.legal_hold_at {
+        events.push(event(legal_hold_at, "legal_hold_placed", None, None));
+    }
+
+    if let Some(deleted_at) = room.deleted_at {
+        events.push(event(deleted_at, "room_deleted", None, None));
+    }
+
+    // Stable, so events at the same instant keep the order they were added in
+    events.sort_by_key(|event| event.at);
+
+    events
+}
+
+fn build_integrity(
+    messages: &[ComplianceMessage],
+    events: &[ComplianceEvent],
+    recordings: &[ComplianceRecording],
+) -> Result<ComplianceIntegrity, serde_json::Error> {
+    let messages = section_hash(messages)?;
+    let events = section_hash(events)?;
+    let recordings = section_hash(recordings)?;
+    let bundle = sha256_hex(format!("{messages}\n{events}\n{recordings}").as_bytes());
+
+    Ok(ComplianceIntegrity {
+        algorithm: "SHA-256".to_string(),
+        messages,
+        events,
+        recordings,
+        bundle,
+    })
+}
+
+fn section_hash<T: Serialize>(section: &[T]) -> Result<String, serde_json::Error> {
+    Ok(sha256_hex(&serde_json::to_vec(section)?))
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn at(seconds: i64) -> NaiveDateTime {
+        DateTime::from_timestamp(seconds, 0).unwrap().naive_utc()
+    }
+
+    fn sample_room() -> Room {
+        Room {
+            id: 1,
+            title: "Room".to_string(),
+            password: None,
+            avatar: None,
+            status: 0,
+            latest_message_created_at: None,
+            code: "roomcode".to_string(),
+            created_at: at(0),
+            updated_at: at(0),
+            deleted_at: None,
+            latest_message_id: None,
+            type_: 0,
+            public_id: "R0000000000000000000000001".to_string(),
+            message_retention_days: None,
+            legal_hold_at: Some(at(50)),
+            noise_suppression: false,
+            broadcast_delay_secs: 0,
+            capacity: None,
+            presenter_member_ids: vec![],
+            streaming_protocol: 0,
+        }
+    }
+
+    fn sample_message(id: i32, created_at: i64, updated_at: i64, status: i16) -> Message {
+        Message {
+            id,
+            data: format!("message {id}"),
+            created_at: at(created_at),
+            updated_at: at(updated_at),
+            deleted_at: None,
+            created_by_id: 1,
+            room_id: 1,
+            type_: 0,
+            status,
+        }
+    }
+
+    fn sample_member(created_at: i64, deleted_at: Option<i64>) -> Member {
+        Member {
+            id: 1,
+            role: 0,
+            created_at: at(created_at),
+            deleted_at: deleted_at.map(at),
+            soft_deleted_at: None,
+            user_id: 1,
+            room_id: 1,
+        }
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_events_are_in_time_order() {
+        let user_id = "U1".to_string();
+        let members = vec![(sample_member(10, Some(90)), user_id.clone())];
+        let messages = vec![
+            (sample_message(1, 20, 30, 0), user_id.clone()),
+            (sample_message(2, 40, 60, 1), user_id.clone()),
+            (sample_message(3, 70, 70, 0), user_id.clone()),
+        ];
+
+        let events = build_events(&sample_room(), &members, &messages, &[], None);
+        let kinds: Vec<&str> = events.iter().map(|event| event.kind.as_str()).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                "room_created",
+                "member_joined",
+                "message_sent",
+                "message_edited",
+                "message_sent",
+                "legal_hold_placed",
+                "message_deleted",
+                "message_sent",
+                "member_left",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bundle_hash_covers_every_section() {
+        let recordings = vec![to_recording(
+            "node-1".to_string(),
+            "P1".to_string(),
+            r#"{"files":[]}"#,
+        )];
+        let original = build_integrity(&[], &[], &recordings).unwrap();
+
+        let tampered = vec![to_recording(
+            "node-1".to_string(),
+            "P1".to_string(),
+            r#"{"files":[{}]}"#,
+        )];
+        let changed = build_integrity(&[], &[], &tampered).unwrap();
+
+        assert_eq!(original.messages, changed.messages);
+        assert_ne!(original.recordings, changed.recordings);
+        assert_ne!(original.bundle, changed.bundle);
+        assert_eq!(
+            original.bundle,
+            build_integrity(&[], &[], &recordings).unwrap().bundle
+        );
+    }
+
+    #[test]
+    fn test_invalid_manifest_is_kept_raw() {
+        let recording = to_recording("node-1".to_string(), "P1".to_string(), "not json");
+
+        assert_eq!(
+            recording.manifest,
+            serde_json::Value::String("not json".to_string())
+        );
+        assert_eq!(recording.sha256, sha256_hex(b"not json"));
+    }
+}
@@ -0,0 +1,299 @@
+use std::{fmt, time::Duration};
+
+use reqwest::{Method, StatusCode, Url};
+use serde_json::{Value, json};
+use socketioxide_redis::drivers::redis::redis_client::{
+    self as redis, AsyncCommands, cluster_async::ClusterConnection,
+};
+
+use crate::core::{env::app_env::MatrixConfigs, types::errors::matrix_error::MatrixError};
+
+/// Homeservers retry a transaction until it is acknowledged, ids are kept for a day
+const TRANSACTION_TTL_SECS: u64 = 24 * 60 * 60;
+
+const PUPPETS_KEY: &str = "matrix_puppets";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Talks to the homeserver with the application service token, as the bridge bot
+/// or as the puppet of a Waterbus user. Puppet registration and room membership
+/// are remembered in Redis so they are only set up once.
+#[derive(Clone)]
+pub struct MatrixClient {
+    http: reqwest::Client,
+    configs: MatrixConfigs,
+    conn: ClusterConnection,
+}
+
+impl fmt::Debug for MatrixClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MatrixClient")
+            .field("homeserver_url", &self.configs.homeserver_url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MatrixClient {
+    pub fn new(configs: MatrixConfigs, conn: ClusterConnection) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .connect_timeout(CONNECT_TIMEOUT)
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            configs,
+            conn,
+        }
+    }
+
+    pub fn is_valid_hs_token(&self, token: &str) -> bool {
+        token == self.configs.hs_token
+    }
+
+    /// Whether the MXID belongs to the bridge itself, events it sent are not relayed back
+    pub fn is_bridged_user(&self, matrix_user_id: &str) -> bool {
+        let Some((localpart, server_name)) = matrix_user_id
+            .strip_prefix('@')
+            .and_then(|id| id.split_once(':'))
+        else {
+            return false;
+        };
+
+        server_name == self.configs.server_name
+            && (localpart == self.configs.bot_localpart
+                || localpart.starts_with(&self.configs.puppet_prefix))
+    }
+
+    pub fn puppet_user_id(&self, user_id: i32) -> String {
+        format!(
+            "@{}{}:{}",
+            self.configs.puppet_prefix, user_id, self.configs.server_name
+        )
+    }
+
+    /// Claim a homeserver transaction, false if it was already processed
+    pub async fn claim_transaction(&self, txn_id: &str) -> Result<bool, MatrixError> {
+        let mut conn = self.conn.clone();
+
+        let reply: Option<String> = redis::cmd("SET")
+            .arg(format!("matrix_txn:{txn_id}"))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(TRANSACTION_TTL_SECS)
+            .query_async(&mut conn)
+            .await
+            .map_err(Self::redis_error)?;
+
+        Ok(reply.is_some())
+    }
+
+    /// Register the puppet of a Waterbus user and make sure it is in the Matrix room
+    pub async fn ensure_puppet(
+        &self,
+        user_id: i32,
+        display_name: &str,
+        matrix_room_id: &str,
+    ) -> Result<String, MatrixError> {
+        let puppet = self.puppet_user_id(user_id);
+        let mut conn = self.conn.clone();
+
+        let is_registered: bool = conn
+            .sismember(PUPPETS_KEY, &puppet)
+            .await
+            .map_err(Self::redis_error)?;
+
+        if !is_registered {
+            self.register_puppet(user_id, &puppet, display_name).await?;
+
+            let _: () = conn
+                .sadd(PUPPETS_KEY, &puppet)
+                .await
+                .map_err(Self::redis_error)?;
+        }
+
+        let room_key = Self::room_puppets_key(matrix_room_id);
+        let is_joined: bool = conn
+            .sismember(&room_key, &puppet)
+            .await
+            .map_err(Self::redis_error)?;
+
+        if !is_joined {
+            // Rooms are usually invite only, the bot invites the puppet first.
+            // The invite fails harmlessly when the puppet is already in the room.
+            let _ = self
+                .request(
+                    Method::POST,
+                    &["rooms", matrix_room_id, "invite"],
+                    None,
+                    Some(json!({ "user_id": puppet })),
+                )
+                .await;
+
+            self.join_room(matrix_room_id, Some(&puppet)).await?;
+
+            let _: () = conn
+                .sadd(&room_key, &puppet)
+                .await
+                .map_err(Self::redis_error)?;
+        }
+
+        Ok(puppet)
+    }
+
+    /// Join a room as the bridge bot, or as a puppet when `user_id` is given
+    pub async fn join_room(
+        &self,
+        matrix_room_id: &str,
+        user_id: Option<&str>,
+    ) -> Result<(), MatrixError> {
+        self.request(
+            Method::POST,
+            &["join", matrix_room_id],
+            user_id,
+            Some(json!({})),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn leave_room(
+        &self,
+        matrix_room_id: &str,
+        user_id: Option<&str>,
+    ) -> Result<(), MatrixError> {
+        self.request(
+            Method::POST,
+            &["rooms", matrix_room_id, "leave"],
+            user_id,
+            Some(json!({})),
+        )
+        .await?;
+
+        if let Some(user_id) = user_id {
+            let mut conn = self.conn.clone();
+            let _: () = conn
+                .srem(Self::room_puppets_key(matrix_room_id), user_id)
+                .await
+                .map_err(Self::redis_error)?;
+        }
+
+        Ok(())
+    }
+
+    /// Send a plain text message, `txn_id` makes retries idempotent on the homeserver
+    pub async fn send_text(
+        &self,
+        matrix_room_id: &str,
+        user_id: &str,
+        txn_id: &str,
+        body: &str,
+    ) -> Result<(), MatrixError> {
+        self.request(
+            Method::PUT,
+            &["rooms", matrix_room_id, "send", "m.room.message", txn_id],
+            Some(user_id),
+            Some(json!({ "msgtype": "m.text", "body": body })),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn register_puppet(
+        &self,
+        user_id: i32,
+        puppet: &str,
+        display_name: &str,
+    ) -> Result<(), MatrixError> {
+        let localpart = format!("{}{}", self.configs.puppet_prefix, user_id);
+
+        let result = self
+            .request(
+                Method::POST,
+                &["register"],
+                None,
+                Some(json!({
+                    "type": "m.login.application_service",
+                    "username": localpart,
+                })),
+            )
+            .await;
+
+        match result {
+            Ok(_) => {}
+            Err(MatrixError::HomeserverError(err)) if err.contains("M_USER_IN_USE") => {}
+            Err(err) => return Err(err),
+        }
+
+        self.request(
+            Method::PUT,
+            &["profile", puppet, "displayname"],
+            Some(puppet),
+            Some(json!({ "displayname": display_name })),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn request(
+        &self,
+        method: Method,
+        segments: &[&str],
+        user_id: Option<&str>,
+        body: Option<Value>,
+    ) -> Result<Value, MatrixError> {
+        let url = self.client_url(segments, user_id)?;
+
+        let mut request = self
+            .http
+            .request(method, url)
+            .bearer_auth(&self.configs.as_token);
+
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| MatrixError::HomeserverError(e.to_string()))?;
+
+        let status = response.status();
+        let value = response.json::<Value>().await.unwrap_or(Value::Null);
+
+        if status != StatusCode::OK {
+            return Err(MatrixError::HomeserverError(format!("{status}: {value}")));
+        }
+
+        Ok(value)
+    }
+
+    fn client_url(&self, segments: &[&str], user_id: Option<&str>) -> Result<Url, MatrixError> {
+        let mut url = Url::parse(&self.configs.homeserver_url)
+            .map_err(|e| MatrixError::UnexpectedError(e.to_string()))?;
+
+        url.path_segments_mut()
+            .map_err(|_| MatrixError::UnexpectedError("Invalid homeserver url".into()))?
+            .pop_if_empty()
+            .extend(["_matrix", "client", "v3"])
+            .extend(segments);
+
+        if let Some(user_id) = user_id {
+            url.query_pairs_mut().append_pair("user_id", user_id);
+        }
+
+        Ok(url)
+    }
+
+    fn room_puppets_key(matrix_room_id: &str) -> String {
+        format!("matrix_room_puppets:{matrix_room_id}")
+    }
+
+    fn redis_error(err: redis::RedisError) -> MatrixError {
+        MatrixError::UnexpectedError(err.to_string())
+    }
+}
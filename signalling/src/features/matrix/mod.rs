@@ -0,0 +1,4 @@
+pub mod client;
+pub mod repository;
+pub mod router;
+pub mod service;
@@ -0,0 +1,142 @@
+use chrono::Utc;
+use diesel::{
+    ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper,
+    delete,
+    dsl::insert_into,
+    r2d2::{ConnectionManager, Pool, PooledConnection},
+};
+use salvo::async_trait;
+
+use crate::core::{
+    database::{
+        db::checkout,
+        schema::{matrix_rooms, users},
+        soft_delete::{DeletedScope, users_in_scope},
+    },
+    entities::models::{MatrixRoom, NewMatrixRoom, NewUser, User},
+    types::errors::{general::GeneralError, matrix_error::MatrixError},
+    utils::id_utils::generate_username,
+};
+
+#[async_trait]
+pub trait MatrixRepository: Send + Sync {
+    async fn find_by_room_id(&self, room_id: i32) -> Result<Option<MatrixRoom>, MatrixError>;
+
+    async fn find_by_matrix_room_id(
+        &self,
+        matrix_room_id: &str,
+    ) -> Result<Option<MatrixRoom>, MatrixError>;
+
+    async fn create_link(&self, link: NewMatrixRoom<'_>) -> Result<MatrixRoom, MatrixError>;
+
+    async fn delete_link(&self, room_id: i32) -> Result<(), MatrixError>;
+
+    /// Matrix users are stored as regular users keyed by their MXID
+    async fn get_or_create_user(
+        &self,
+        matrix_user_id: &str,
+        display_name: &str,
+    ) -> Result<User, MatrixError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct MatrixRepositoryImpl {
+    pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl MatrixRepositoryImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Self {
+        Self { pool }
+    }
+
+    fn get_conn(&self) -> Result<PooledConnection<ConnectionManager<PgConnection>>, GeneralError> {
+        checkout(&self.pool)
+    }
+}
+
+#[async_trait]
+impl MatrixRepository for MatrixRepositoryImpl {
+    async fn find_by_room_id(&self, room_id: i32) -> Result<Option<MatrixRoom>, MatrixError> {
+        let mut conn = self.get_conn()?;
+
+        matrix_rooms::table
+            .filter(matrix_rooms::room_id.eq(room_id))
+            .select(MatrixRoom::as_select())
+            .first::<MatrixRoom>(&mut conn)
+            .optional()
+            .map_err(|err| MatrixError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_by_matrix_room_id(
+        &self,
+        matrix_room_id: &str,
+    ) -> Result<Option<MatrixRoom>, MatrixError> {
+        let mut conn = self.get_conn()?;
+
+        matrix_rooms::table
+            .filter(matrix_rooms::matrix_room_id.eq(matrix_room_id))
+            .select(MatrixRoom::as_select())
+            .first::<MatrixRoom>(&mut conn)
+            .optional()
+            .map_err(|err| MatrixError::UnexpectedError(err.to_string()))
+    }
+
+    async fn create_link(&self, link: NewMatrixRoom<'_>) -> Result<MatrixRoom, MatrixError> {
+        let mut conn = self.get_conn()?;
+
+        insert_into(matrix_rooms::table)
+            .values(&link)
+            .returning(MatrixRoom::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| MatrixError::UnexpectedError(err.to_string()))
+    }
+
+    async fn delete_link(&self, room_id: i32) -> Result<(), MatrixError> {
+        let mut conn = self.get_conn()?;
+
+        delete(matrix_rooms::table)
+            .filter(matrix_rooms::room_id.eq(room_id))
+            .execute(&mut conn)
+            .map_err(|_| MatrixError::UnexpectedError("Failed to delete Matrix link".into()))?;
+
+        Ok(())
+    }
+
+    async fn get_or_create_user(
+        &self,
+        matrix_user_id: &str,
+        display_name: &str,
+    ) -> Result<User, MatrixError> {
+        let mut conn = self.get_conn()?;
+
+        let user = users::table
+            .filter(users::external_id.eq(matrix_user_id))
+            .filter(users_in_scope(DeletedScope::Exclude))
+            .select(User::as_select())
+            .first::<User>(&mut conn)
+            .optional()
+            .map_err(|err| MatrixError::UnexpectedError(err.to_string()))?;
+
+        if let Some(user) = user {
+            return Ok(user);
+        }
+
+        let now = Utc::now().naive_utc();
+
+        let new_user = NewUser {
+            full_name: Some(display_name),
+            user_name: &generate_username(),
+            bio: None,
+            external_id: matrix_user_id,
+            avatar: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        insert_into(users::table)
+            .values(&new_user)
+            .returning(User::as_select())
+            .get_result(&mut conn)
+            .map_err(|_| MatrixError::UnexpectedError("Cannot insert Matrix user to DB".into()))
+    }
+}
@@ -0,0 +1,171 @@
+use salvo::{
+    oapi::extract::{JsonBody, PathParam},
+    prelude::*,
+};
+use serde_json::json;
+use tracing::warn;
+
+use crate::{
+    core::{
+        dtos::matrix::{
+            link_matrix_room_dto::LinkMatrixRoomDto, matrix_transaction_dto::MatrixTransactionDto,
+        },
+        types::{
+            errors::matrix_error::MatrixError, responses::matrix_room_response::MatrixRoomResponse,
+        },
+        utils::jwt_utils::JwtUtils,
+    },
+    features::{
+        chat::outbox::OutboxSignal,
+        room::{
+            repository::RoomRepositoryImpl,
+            service::{RoomService, RoomServiceImpl},
+        },
+        user::repository::UserRepositoryImpl,
+    },
+};
+
+use super::{
+    client::MatrixClient,
+    service::{MatrixBridge, MatrixService},
+};
+
+pub fn get_matrix_router(jwt_utils: JwtUtils) -> Router {
+    Router::with_hoop(jwt_utils.auth_middleware())
+        .path("matrix/rooms/{room_id}")
+        .post(link_room)
+        .delete(unlink_room)
+}
+
+/// Application service API the homeserver pushes events to. Mounted next to the
+/// client API since the homeserver can't send our API key.
+pub fn get_matrix_appservice_router() -> Router {
+    Router::with_hoop(hs_token_middleware())
+        .path("_matrix/app/v1")
+        .push(Router::with_path("transactions/{txn_id}").put(put_transaction))
+}
+
+/// Bridges a room's chat with a Matrix room. Only the host can bridge a room.
+#[endpoint(tags("matrix"), status_codes(200, 400, 401, 403, 502, 500))]
+async fn link_room(
+    _res: &mut Response,
+    room_id: PathParam<String>,
+    data: JsonBody<LinkMatrixRoomDto>,
+    depot: &mut Depot,
+) -> Result<MatrixRoomResponse, MatrixError> {
+    let matrix_service = depot
+        .obtain::<MatrixBridge>()
+        .map_err(|_| MatrixError::UnexpectedError("Matrix bridge is disabled".into()))?;
+    let user_id = depot.get::<String>("user_id").unwrap();
+    let room_id = resolve_room_id(depot, &room_id.into_inner()).await?;
+
+    let matrix_room = matrix_service
+        .link_room(
+            room_id,
+            user_id.parse().unwrap(),
+            &data.into_inner().matrix_room_id,
+        )
+        .await?;
+
+    Ok(MatrixRoomResponse { matrix_room })
+}
+
+/// Stops bridging a room's chat with Matrix.
+#[endpoint(tags("matrix"), status_codes(200, 401, 403, 404, 500))]
+async fn unlink_room(
+    _res: &mut Response,
+    room_id: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<MatrixRoomResponse, MatrixError> {
+    let matrix_service = depot
+        .obtain::<MatrixBridge>()
+        .map_err(|_| MatrixError::UnexpectedError("Matrix bridge is disabled".into()))?;
+    let user_id = depot.get::<String>("user_id").unwrap();
+    let room_id = resolve_room_id(depot, &room_id.into_inner()).await?;
+
+    let matrix_room = matrix_service
+        .unlink_room(room_id, user_id.parse().unwrap())
+        .await?;
+
+    Ok(MatrixRoomResponse { matrix_room })
+}
+
+#[handler]
+async fn put_transaction(req: &mut Request, depot: &mut Depot, res: &mut Response) {
+    let txn_id = req.param::<String>("txn_id").unwrap_or_default();
+
+    let transaction = match req.parse_json::<MatrixTransactionDto>().await {
+        Ok(transaction) => transaction,
+        Err(err) => {
+            res.status_code(StatusCode::BAD_REQUEST);
+            return res.render(Json(
+                json!({ "errcode": "M_NOT_JSON", "error": err.to_string() }),
+            ));
+        }
+    };
+
+    let client = depot.obtain::<MatrixClient>().unwrap();
+
+    match client.claim_transaction(&txn_id).await {
+        Ok(true) => {}
+        Ok(false) => return res.render(Json(json!({}))),
+        Err(err) => {
+            // Not acknowledging makes the homeserver retry the transaction later
+            warn!("Failed to claim Matrix transaction {}: {:?}", txn_id, err);
+            res.status_code(StatusCode::SERVICE_UNAVAILABLE);
+            return res.render(Json(
+                json!({ "errcode": "M_UNKNOWN", "error": err.to_string() }),
+            ));
+        }
+    }
+
+    let matrix_service = depot.obtain::<MatrixBridge>().unwrap();
+    let outbox_signal = depot.obtain::<OutboxSignal>().unwrap();
+
+    for event in transaction.events {
+        if let Err(err) = matrix_service.handle_event(event).await {
+            warn!("Failed to bridge Matrix event: {:?}", err);
+        }
+    }
+
+    outbox_signal.wake();
+
+    res.render(Json(json!({})));
+}
+
+fn hs_token_middleware() -> impl Handler {
+    #[handler]
+    async fn middleware(req: &mut Request, depot: &mut Depot, res: &mut Response) {
+        // Older homeservers send the token as a query parameter
+        let token = req
+            .headers()
+            .get("Authorization")
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .map(str::to_owned)
+            .or_else(|| req.query::<String>("access_token"));
+
+        let is_valid = match (depot.obtain::<MatrixClient>(), token) {
+            (Ok(client), Some(token)) => client.is_valid_hs_token(&token),
+            _ => false,
+        };
+
+        if !is_valid {
+            res.status_code(StatusCode::FORBIDDEN);
+            return res.render(Json(
+                json!({ "errcode": "M_FORBIDDEN", "error": MatrixError::InvalidToken.to_string() }),
+            ));
+        }
+    }
+    middleware
+}
+
+/// Rooms are addressed by their public id, the services work with internal keys
+async fn resolve_room_id(depot: &Depot, room_id: &str) -> Result<i32, MatrixError> {
+    depot
+        .obtain::<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>()
+        .unwrap()
+        .resolve_room_id(room_id)
+        .await
+        .map_err(|_| MatrixError::RoomNotFound(room_id.to_string()))
+}
@@ -0,0 +1,285 @@
+use chrono::Utc;
+use salvo::async_trait;
+use tracing::warn;
+
+use crate::{
+    core::{
+        dtos::matrix::matrix_transaction_dto::MatrixEventDto,
+        entities::models::{MatrixRoom, NewMatrixRoom},
+        types::{errors::matrix_error::MatrixError, responses::message_response::MessageResponse},
+    },
+    features::{
+        chat::{
+            repository::ChatRepositoryImpl,
+            service::{ChatService, ChatServiceImpl},
+        },
+        room::{
+            repository::RoomRepositoryImpl,
+            service::{RoomService, RoomServiceImpl},
+        },
+        user::repository::UserRepositoryImpl,
+    },
+};
+
+use super::{
+    client::MatrixClient,
+    repository::{MatrixRepository, MatrixRepositoryImpl},
+};
+
+/// The bridge as wired up against Postgres, injected into the depot when enabled
+pub type MatrixBridge = MatrixServiceImpl<
+    MatrixRepositoryImpl,
+    ChatServiceImpl<ChatRepositoryImpl, RoomRepositoryImpl, UserRepositoryImpl>,
+    RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+>;
+
+#[async_trait]
+pub trait MatrixService: Send + Sync {
+    async fn link_room(
+        &self,
+        room_id: i32,
+        host_id: i32,
+        matrix_room_id: &str,
+    ) -> Result<MatrixRoom, MatrixError>;
+
+    async fn unlink_room(&self, room_id: i32, host_id: i32) -> Result<MatrixRoom, MatrixError>;
+
+    /// Apply an event pushed by the homeserver, returns the chat message it created
+    async fn handle_event(
+        &self,
+        event: MatrixEventDto,
+    ) -> Result<Option<MessageResponse>, MatrixError>;
+
+    /// Post a Waterbus chat message to the bridged Matrix room, if any
+    async fn relay_message(&self, message: &MessageResponse) -> Result<(), MatrixError>;
+
+    /// Remove the puppet of a user who left a Waterbus room from the bridged Matrix room
+    async fn relay_leave(&self, room_id: i32, user_id: i32) -> Result<(), MatrixError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct MatrixServiceImpl<M: MatrixRepository, C: ChatService, R: RoomService> {
+    matrix_repository: M,
+    chat_service: C,
+    room_service: R,
+    client: MatrixClient,
+}
+
+impl<M: MatrixRepository, C: ChatService, R: RoomService> MatrixServiceImpl<M, C, R> {
+    pub fn new(
+        matrix_repository: M,
+        chat_service: C,
+        room_service: R,
+        client: MatrixClient,
+    ) -> Self {
+        Self {
+            matrix_repository,
+            chat_service,
+            room_service,
+            client,
+        }
+    }
+
+    async fn _check_host(&self, room_id: i32, host_id: i32) -> Result<(), MatrixError> {
+        self.room_service
+            .check_host(room_id, host_id)
+            .await
+            .map_err(|_| MatrixError::YouDontHavePermissions)
+    }
+}
+
+#[async_trait]
+impl<M: MatrixRepository, C: ChatService, R: RoomService> MatrixService
+    for MatrixServiceImpl<M, C, R>
+{
+    async fn link_room(
+        &self,
+        room_id: i32,
+        host_id: i32,
+        matrix_room_id: &str,
+    ) -> Result<MatrixRoom, MatrixError> {
+        self._check_host(room_id, host_id).await?;
+
+        if self
+            .matrix_repository
+            .find_by_room_id(room_id)
+            .await?
+            .is_some()
+        {
+            return Err(MatrixError::RoomAlreadyLinked(room_id));
+        }
+
+        // The bot has to be in the room to receive its events
+        self.client.join_room(matrix_room_id, None).await?;
+
+        let link = NewMatrixRoom {
+            room_id: &room_id,
+            matrix_room_id,
+            created_at: Utc::now().naive_utc(),
+        };
+
+        self.matrix_repository.create_link(link).await
+    }
+
+    async fn unlink_room(&self, room_id: i32, host_id: i32) -> Result<MatrixRoom, MatrixError> {
+        self._check_host(room_id, host_id).await?;
+
+        let link = self
+            .matrix_repository
+            .find_by_room_id(room_id)
+            .await?
+            .ok_or(MatrixError::RoomNotLinked(room_id))?;
+
+        if let Err(err) = self.client.leave_room(&link.matrix_room_id, None).await {
+            warn!(
+                "Failed to leave Matrix room {}: {:?}",
+                link.matrix_room_id, err
+            );
+        }
+
+        self.matrix_repository.delete_link(room_id).await?;
+
+        Ok(link)
+    }
+
+    async fn handle_event(
+        &self,
+        event: MatrixEventDto,
+    ) -> Result<Option<MessageResponse>, MatrixError> {
+        if self.client.is_bridged_user(&event.sender) {
+            return Ok(None);
+        }
+
+        let Some(link) = self
+            .matrix_repository
+            .find_by_matrix_room_id(&event.room_id)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        match event.event_type.as_str() {
+            "m.room.message" => {
+                let msgtype = event.content["msgtype"].as_str().unwrap_or_default();
+                let Some(body) = event.content["body"].as_str() else {
+                    return Ok(None);
+                };
+
+                // Only text is bridged, media would need to be rehosted
+                let data = match msgtype {
+                    "m.text" | "m.notice" => body.to_string(),
+                    "m.emote" => format!("* {body}"),
+                    _ => return Ok(None),
+                };
+
+                let user = self
+                    .matrix_repository
+                    .get_or_create_user(&event.sender, localpart(&event.sender))
+                    .await?;
+
+                self.room_service
+                    .ensure_member(link.room_id, user.id)
+                    .await
+                    .map_err(|e| MatrixError::UnexpectedError(e.to_string()))?;
+
+                let message = self
+                    .chat_service
+                    .create_message(link.room_id, user.id, &data)
+                    .await
+                    .map_err(|e| MatrixError::UnexpectedError(e.to_string()))?;
+
+                Ok(Some(message))
+            }
+            "m.room.member" => {
+                // Kicks and bans are sent by the moderator, the state key is the target
+                let target = event.state_key.as_deref().unwrap_or(&event.sender);
+
+                if self.client.is_bridged_user(target) {
+                    return Ok(None);
+                }
+
+                let display_name = event.content["displayname"]
+                    .as_str()
+                    .unwrap_or_else(|| localpart(target));
+
+                let user = self
+                    .matrix_repository
+                    .get_or_create_user(target, display_name)
+                    .await?;
+
+                match event.content["membership"].as_str() {
+                    Some("join") => {
+                        self.room_service
+                            .ensure_member(link.room_id, user.id)
+                            .await
+                            .map_err(|e| MatrixError::UnexpectedError(e.to_string()))?;
+                    }
+                    Some("leave") | Some("ban") => {
+                        // Fails when the user never became a member, nothing to undo then
+                        let _ = self.room_service.leave_room(link.room_id, user.id).await;
+                    }
+                    _ => {}
+                }
+
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn relay_message(&self, message: &MessageResponse) -> Result<(), MatrixError> {
+        let Some(link) = self
+            .matrix_repository
+            .find_by_room_id(message.message.room_id)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        let Some(user) = &message.created_by else {
+            return Ok(());
+        };
+
+        // Messages imported from Matrix are already there
+        if user.external_id.starts_with('@') {
+            return Ok(());
+        }
+
+        let display_name = user.full_name.as_deref().unwrap_or(&user.user_name);
+
+        let puppet = self
+            .client
+            .ensure_puppet(user.id, display_name, &link.matrix_room_id)
+            .await?;
+
+        self.client
+            .send_text(
+                &link.matrix_room_id,
+                &puppet,
+                &format!("waterbus-{}", message.message.id),
+                &message.message.data,
+            )
+            .await
+    }
+
+    async fn relay_leave(&self, room_id: i32, user_id: i32) -> Result<(), MatrixError> {
+        let Some(link) = self.matrix_repository.find_by_room_id(room_id).await? else {
+            return Ok(());
+        };
+
+        let puppet = self.client.puppet_user_id(user_id);
+
+        self.client
+            .leave_room(&link.matrix_room_id, Some(&puppet))
+            .await
+    }
+}
+
+/// `@alice:example.org` -> `alice`
+fn localpart(matrix_user_id: &str) -> &str {
+    matrix_user_id
+        .trim_start_matches('@')
+        .split(':')
+        .next()
+        .unwrap_or(matrix_user_id)
+}
@@ -1,4 +1,17 @@
+pub mod admin;
+pub mod analytics;
 pub mod auth;
+pub mod bot;
+pub mod calendar;
+pub mod callout;
 pub mod chat;
+pub mod client_config;
+pub mod compliance;
+pub mod matrix;
+pub mod moderation;
+pub mod org;
 pub mod room;
+pub mod schedule;
+pub mod search;
+pub mod timeline;
 pub mod user;
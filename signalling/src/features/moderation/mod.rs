@@ -0,0 +1,4 @@
+pub mod notifier;
+pub mod repository;
+pub mod router;
+pub mod service;
@@ -0,0 +1,36 @@
+use serde_json::json;
+
+use crate::core::types::responses::abuse_report_response::AbuseReportResponse;
+
+/// Posts new abuse reports to the moderators' webhook. Email or chat delivery
+/// is left to whatever receives it.
+#[derive(Debug, Clone)]
+pub struct ReportNotifier {
+    client: reqwest::Client,
+    webhook_url: String,
+}
+
+impl ReportNotifier {
+    pub fn new(webhook_url: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            webhook_url,
+        }
+    }
+
+    pub async fn notify(&self, report: &AbuseReportResponse) -> Result<(), reqwest::Error> {
+        let body = json!({
+            "event": "abuse_report.created",
+            "report": report,
+        });
+
+        self.client
+            .post(&self.webhook_url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
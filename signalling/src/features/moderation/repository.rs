@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use diesel::{
+    ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper,
+    dsl::{insert_into, update},
+    r2d2::{ConnectionManager, Pool, PooledConnection},
+};
+use salvo::async_trait;
+
+use crate::core::{
+    database::{
+        db::checkout,
+        schema::{abuse_reports, rooms, users},
+    },
+    entities::models::{AbuseReport, NewAbuseReport, Room, User},
+    types::{
+        enums::report_status::ReportStatus,
+        errors::{general::GeneralError, moderation_error::ModerationError},
+        responses::abuse_report_response::AbuseReportResponse,
+    },
+};
+
+#[async_trait]
+pub trait ModerationRepository: Send + Sync {
+    async fn create_report(
+        &self,
+        report: NewAbuseReport<'_>,
+    ) -> Result<AbuseReport, ModerationError>;
+
+    async fn find_open_report(
+        &self,
+        room_id: i32,
+        reporter_id: i32,
+        target_id: i32,
+    ) -> Result<Option<AbuseReport>, ModerationError>;
+
+    async fn find_report(
+        &self,
+        report_id: i32,
+    ) -> Result<Option<AbuseReportResponse>, ModerationError>;
+
+    /// Oldest first, the queue is worked through in the order it came in
+    async fn find_reports(
+        &self,
+        status: ReportStatus,
+        skip: i64,
+        limit: i64,
+    ) -> Result<Vec<AbuseReportResponse>, ModerationError>;
+
+    /// `None` when the report isn't open anymore, so two moderators can't
+    /// close the same report
+    async fn close_report(
+        &self,
+        report_id: i32,
+        status: ReportStatus,
+        note: Option<&str>,
+        now: NaiveDateTime,
+    ) -> Result<Option<AbuseReport>, ModerationError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct ModerationRepositoryImpl {
+    pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl ModerationRepositoryImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Self {
+        Self { pool }
+    }
+
+    fn get_conn(&self) -> Result<PooledConnection<ConnectionManager<PgConnection>>, GeneralError> {
+        checkout(&self.pool)
+    }
+
+    /// Reporter and target both point at users, they are loaded in one go
+    fn _with_users(
+        conn: &mut PgConnection,
+        rows: Vec<(AbuseReport, Room)>,
+    ) -> Result<Vec<AbuseReportResponse>, ModerationError> {
+        let user_ids: Vec<i32> = rows
+            .iter()
+            .flat_map(|(report, _)| [report.reporter_id, report.target_id])
+            .collect();
+
+        let users: HashMap<i32, User> = users::table
+            .filter(users::id.eq_any(user_ids))
+            .select(User::as_select())
+            .load::<User>(conn)
+            .map_err(|err| ModerationError::UnexpectedError(err.to_string()))?
+            .into_iter()
+            .map(|user| (user.id, user))
+            .collect();
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(report, room)| {
+                let reporter = users.get(&report.reporter_id)?.clone();
+                let target = users.get(&report.target_id)?.clone();
+
+                Some(AbuseReportResponse {
+                    report,
+                    room,
+                    reporter,
+                    target,
+                })
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl ModerationRepository for ModerationRepositoryImpl {
+    async fn create_report(
+        &self,
+        report: NewAbuseReport<'_>,
+    ) -> Result<AbuseReport, ModerationError> {
+        let mut conn = self.get_conn()?;
+
+        insert_into(abuse_reports::table)
+            .values(&report)
+            .returning(AbuseReport::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| ModerationError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_open_report(
+        &self,
+        room_id: i32,
+        reporter_id: i32,
+        target_id: i32,
+    ) -> Result<Option<AbuseReport>, ModerationError> {
+        let mut conn = self.get_conn()?;
+
+        abuse_reports::table
+            .filter(abuse_reports::room_id.eq(room_id))
+            .filter(abuse_reports::reporter_id.eq(reporter_id))
+            .filter(abuse_reports::target_id.eq(target_id))
+            .filter(abuse_reports::status.eq(ReportStatus::Open.to_str()))
+            .select(AbuseReport::as_select())
+            .first::<AbuseReport>(&mut conn)
+            .optional()
+            .map_err(|err| ModerationError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_report(
+        &self,
+        report_id: i32,
+    ) -> Result<Option<AbuseReportResponse>, ModerationError> {
+        let mut conn = self.get_conn()?;
+
+        let row = abuse_reports::table
+            .inner_join(rooms::table)
+            .filter(abuse_reports::id.eq(report_id))
+            .select((AbuseReport::as_select(), Room::as_select()))
+            .first::<(AbuseReport, Room)>(&mut conn)
+            .optional()
+            .map_err(|err| ModerationError::UnexpectedError(err.to_string()))?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        Ok(Self::_with_users(&mut conn, vec![row])?.pop())
+    }
+
+    async fn find_reports(
+        &self,
+        status: ReportStatus,
+        skip: i64,
+        limit: i64,
+    ) -> Result<Vec<AbuseReportResponse>, ModerationError> {
+        let mut conn = self.get_conn()?;
+
+        let rows = abuse_reports::table
+            .inner_join(rooms::table)
+            .filter(abuse_reports::status.eq(status.to_str()))
+            .select((AbuseReport::as_select(), Room::as_select()))
+            .order((abuse_reports::created_at.asc(), abuse_reports::id.asc()))
+            .offset(skip)
+            .limit(limit)
+            .load::<(AbuseReport, Room)>(&mut conn)
+            .map_err(|err| ModerationError::UnexpectedError(err.to_string()))?;
+
+        Self::_with_users(&mut conn, rows)
+    }
+
+    async fn close_report(
+        &self,
+        report_id: i32,
+        status: ReportStatus,
+        note: Option<&str>,
+        now: NaiveDateTime,
+    ) -> Result<Option<AbuseReport>, ModerationError> {
+        let mut conn = self.get_conn()?;
+
+        update(abuse_reports::table)
+            .filter(abuse_reports::id.eq(report_id))
+            .filter(abuse_reports::status.eq(ReportStatus::Open.to_str()))
+            .set((
+                abuse_reports::status.eq(status.to_str()),
+                abuse_reports::resolution_note.eq(note),
+                abuse_reports::resolved_at.eq(now),
+            ))
+            .returning(AbuseReport::as_select())
+            .get_result(&mut conn)
+            .optional()
+            .map_err(|err| ModerationError::UnexpectedError(err.to_string()))
+    }
+}
@@ -0,0 +1,57 @@
+use salvo::{
+    oapi::extract::{JsonBody, PathParam},
+    prelude::*,
+};
+
+use crate::{
+    core::{
+        dtos::moderation::report_participant_dto::ReportParticipantDto,
+        types::{
+            errors::moderation_error::ModerationError,
+            responses::abuse_report_response::AbuseReportResponse,
+        },
+        utils::jwt_utils::JwtUtils,
+    },
+    features::{
+        room::{
+            repository::RoomRepositoryImpl,
+            service::{RoomService, RoomServiceImpl},
+        },
+        user::repository::UserRepositoryImpl,
+    },
+};
+
+use super::service::{AbuseReports, ModerationService};
+
+pub fn get_moderation_router(jwt_utils: JwtUtils) -> Router {
+    Router::with_hoop(jwt_utils.auth_middleware())
+        .path("rooms/{room_id}/reports")
+        .post(report_participant)
+}
+
+/// Reports a participant of the room to the moderators. Same as the
+/// `room.report` socket event, for clients that report from outside the call.
+#[endpoint(tags("moderation"), status_codes(201, 400, 401, 403, 404, 500))]
+async fn report_participant(
+    _res: &mut Response,
+    room_id: PathParam<String>,
+    data: JsonBody<ReportParticipantDto>,
+    depot: &mut Depot,
+) -> Result<AbuseReportResponse, ModerationError> {
+    let moderation_service = depot.obtain::<AbuseReports>().unwrap();
+    let user_id = depot.get::<String>("user_id").unwrap();
+    let room_id = resolve_room_id(depot, &room_id.into_inner()).await?;
+
+    moderation_service
+        .report_participant(room_id, user_id.parse().unwrap(), data.into_inner())
+        .await
+}
+
+async fn resolve_room_id(depot: &Depot, room_id: &str) -> Result<i32, ModerationError> {
+    depot
+        .obtain::<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>()
+        .unwrap()
+        .resolve_room_id(room_id)
+        .await
+        .map_err(|_| ModerationError::RoomNotFound(room_id.to_string()))
+}
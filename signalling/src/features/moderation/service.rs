@@ -0,0 +1,302 @@
+use chrono::Utc;
+use salvo::async_trait;
+use tracing::warn;
+
+use crate::{
+    core::{
+        dtos::{
+            common::pagination_dto::PaginationDto,
+            moderation::{
+                report_participant_dto::ReportParticipantDto, resolve_report_dto::ResolveReportDto,
+            },
+        },
+        entities::models::NewAbuseReport,
+        types::{
+            enums::report_status::ReportStatus, errors::moderation_error::ModerationError,
+            responses::abuse_report_response::AbuseReportResponse,
+        },
+    },
+    features::{
+        room::{
+            repository::RoomRepositoryImpl,
+            service::{RoomService, RoomServiceImpl},
+        },
+        user::repository::UserRepositoryImpl,
+    },
+};
+
+use super::{
+    notifier::ReportNotifier,
+    repository::{ModerationRepository, ModerationRepositoryImpl},
+};
+
+/// Matches the report columns
+const MAX_DETAILS_LEN: usize = 1000;
+const MAX_SNAPSHOT_URL_LEN: usize = 2048;
+
+/// Abuse reports as wired up against Postgres
+pub type AbuseReports = ModerationServiceImpl<
+    ModerationRepositoryImpl,
+    RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+>;
+
+#[async_trait]
+pub trait ModerationService: Send + Sync {
+    /// Reports a participant of a room the reporter is in. Reporting the same
+    /// participant again while the first report is open returns that report.
+    async fn report_participant(
+        &self,
+        room_id: i32,
+        reporter_id: i32,
+        data: ReportParticipantDto,
+    ) -> Result<AbuseReportResponse, ModerationError>;
+
+    async fn get_reports(
+        &self,
+        status: ReportStatus,
+        pagination_dto: PaginationDto,
+    ) -> Result<Vec<AbuseReportResponse>, ModerationError>;
+
+    async fn resolve_report(
+        &self,
+        report_id: i32,
+        data: ResolveReportDto,
+    ) -> Result<AbuseReportResponse, ModerationError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct ModerationServiceImpl<M: ModerationRepository, R: RoomService> {
+    moderation_repository: M,
+    room_service: R,
+    notifier: Option<ReportNotifier>,
+}
+
+impl<M: ModerationRepository, R: RoomService> ModerationServiceImpl<M, R> {
+    pub fn new(moderation_repository: M, room_service: R) -> Self {
+        Self {
+            moderation_repository,
+            room_service,
+            notifier: None,
+        }
+    }
+
+    pub fn with_notifier(mut self, notifier: ReportNotifier) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    async fn _get_report(&self, report_id: i32) -> Result<AbuseReportResponse, ModerationError> {
+        self.moderation_repository
+            .find_report(report_id)
+            .await?
+            .ok_or(ModerationError::ReportNotFound(report_id))
+    }
+
+    /// In the background, a slow webhook shouldn't hold up the reporter
+    fn _notify(&self, report: &AbuseReportResponse) {
+        let Some(notifier) = self.notifier.clone() else {
+            return;
+        };
+
+        let report = report.clone();
+        tokio::spawn(async move {
+            if let Err(err) = notifier.notify(&report).await {
+                warn!(
+                    "Failed to notify moderators of report {}: {:?}",
+                    report.report.id, err
+                );
+            }
+        });
+    }
+}
+
+#[async_trait]
+impl<M: ModerationRepository, R: RoomService> ModerationService for ModerationServiceImpl<M, R> {
+    async fn report_participant(
+        &self,
+        room_id: i32,
+        reporter_id: i32,
+        data: ReportParticipantDto,
+    ) -> Result<AbuseReportResponse, ModerationError> {
+        let room = self
+            .room_service
+            .get_room_by_id(room_id)
+            .await
+            .map_err(|_| ModerationError::RoomNotFound(room_id.to_string()))?;
+
+        let is_in_room = room
+            .members
+            .iter()
+            .any(|member| member.member.user_id == reporter_id)
+            || room
+                .participants
+                .iter()
+                .any(|participant| participant.participant.user_id == reporter_id);
+        if !is_in_room {
+            return Err(ModerationError::NotInRoom);
+        }
+
+        let target_id = room
+            .participants
+            .iter()
+            .find(|participant| participant.participant.public_id == data.target_id)
+            .map(|participant| participant.participant.user_id)
+            .ok_or_else(|| ModerationError::TargetNotFound(data.target_id.clone()))?;
+
+        if target_id == reporter_id {
+            return Err(ModerationError::CannotReportSelf);
+        }
+
+        let details = normalize_text(data.details, MAX_DETAILS_LEN, "details")?;
+        let snapshot_url = normalize_snapshot_url(data.snapshot_url)?;
+
+        if let Some(existing) = self
+            .moderation_repository
+            .find_open_report(room_id, reporter_id, target_id)
+            .await?
+        {
+            return self._get_report(existing.id).await;
+        }
+
+        let report = self
+            .moderation_repository
+            .create_report(NewAbuseReport {
+                room_id: &room_id,
+                reporter_id: &reporter_id,
+                target_id: &target_id,
+                reason: data.reason.to_str(),
+                details: details.as_deref(),
+                snapshot_url: snapshot_url.as_deref(),
+                status: ReportStatus::Open.to_str(),
+                created_at: Utc::now().naive_utc(),
+            })
+            .await?;
+
+        let report = self._get_report(report.id).await?;
+
+        self._notify(&report);
+
+        Ok(report)
+    }
+
+    async fn get_reports(
+        &self,
+        status: ReportStatus,
+        pagination_dto: PaginationDto,
+    ) -> Result<Vec<AbuseReportResponse>, ModerationError> {
+        self.moderation_repository
+            .find_reports(status, pagination_dto.skip, pagination_dto.limit)
+            .await
+    }
+
+    async fn resolve_report(
+        &self,
+        report_id: i32,
+        data: ResolveReportDto,
+    ) -> Result<AbuseReportResponse, ModerationError> {
+        if data.status == ReportStatus::Open {
+            return Err(ModerationError::InvalidReport(
+                "a report can only be resolved or dismissed".to_string(),
+            ));
+        }
+
+        let note = normalize_text(data.note, MAX_DETAILS_LEN, "note")?;
+
+        let closed = self
+            .moderation_repository
+            .close_report(
+                report_id,
+                data.status,
+                note.as_deref(),
+                Utc::now().naive_utc(),
+            )
+            .await?;
+
+        if closed.is_none() {
+            // Tells a missing report apart from one another moderator closed
+            self._get_report(report_id).await?;
+            return Err(ModerationError::ReportAlreadyClosed(report_id));
+        }
+
+        self._get_report(report_id).await
+    }
+}
+
+/// Blank text counts as not given
+fn normalize_text(
+    value: Option<String>,
+    max_len: usize,
+    field: &str,
+) -> Result<Option<String>, ModerationError> {
+    let Some(value) = value.map(|value| value.trim().to_string()) else {
+        return Ok(None);
+    };
+
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    if value.chars().count() > max_len {
+        return Err(ModerationError::InvalidReport(format!(
+            "{field} is longer than {max_len} characters"
+        )));
+    }
+
+    Ok(Some(value))
+}
+
+/// Moderators open the snapshot from the queue, only plain web links are kept
+fn normalize_snapshot_url(value: Option<String>) -> Result<Option<String>, ModerationError> {
+    let Some(url) = normalize_text(value, MAX_SNAPSHOT_URL_LEN, "snapshotUrl")? else {
+        return Ok(None);
+    };
+
+    let lowercase = url.to_lowercase();
+    if !(lowercase.starts_with("https://") || lowercase.starts_with("http://"))
+        || url.contains(char::is_whitespace)
+    {
+        return Err(ModerationError::InvalidReport(format!(
+            "{url} is not an http(s) URL"
+        )));
+    }
+
+    Ok(Some(url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blank_text_is_dropped() {
+        assert_eq!(normalize_text(None, 10, "details").unwrap(), None);
+        assert_eq!(
+            normalize_text(Some("   ".to_string()), 10, "details").unwrap(),
+            None
+        );
+        assert_eq!(
+            normalize_text(Some(" spam ".to_string()), 10, "details").unwrap(),
+            Some("spam".to_string())
+        );
+    }
+
+    #[test]
+    fn test_long_text_is_rejected() {
+        let result = normalize_text(Some("a".repeat(11)), 10, "details");
+
+        assert!(matches!(result, Err(ModerationError::InvalidReport(_))));
+    }
+
+    #[test]
+    fn test_snapshot_url_must_be_http() {
+        assert_eq!(
+            normalize_snapshot_url(Some("https://cdn.example.com/frame.jpg".to_string())).unwrap(),
+            Some("https://cdn.example.com/frame.jpg".to_string())
+        );
+        assert!(normalize_snapshot_url(Some("file:///etc/passwd".to_string())).is_err());
+        assert!(
+            normalize_snapshot_url(Some("https://cdn.example.com/a b.jpg".to_string())).is_err()
+        );
+        assert_eq!(normalize_snapshot_url(Some("".to_string())).unwrap(), None);
+    }
+}
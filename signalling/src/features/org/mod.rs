@@ -0,0 +1,3 @@
+pub mod repository;
+pub mod router;
+pub mod service;
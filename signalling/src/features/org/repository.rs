@@ -0,0 +1,136 @@
+use chrono::Utc;
+use diesel::{
+    ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper,
+    dsl::{insert_into, update},
+    r2d2::{ConnectionManager, Pool, PooledConnection},
+};
+use salvo::async_trait;
+
+use crate::core::{
+    database::{
+        db::checkout,
+        schema::{organizations, rooms},
+    },
+    entities::models::{NewOrganization, Organization, OrganizationBranding},
+    types::errors::{general::GeneralError, org_error::OrgError},
+};
+
+#[async_trait]
+pub trait OrgRepository: Send + Sync {
+    async fn create_org(&self, org: NewOrganization<'_>) -> Result<Organization, OrgError>;
+
+    async fn find_by_public_id(&self, public_id: &str) -> Result<Option<Organization>, OrgError>;
+
+    /// The organization the room belongs to, `None` for rooms outside any
+    async fn find_by_room(&self, room_id: i32) -> Result<Option<Organization>, OrgError>;
+
+    async fn update_branding(
+        &self,
+        org_id: i32,
+        branding: OrganizationBranding<'_>,
+    ) -> Result<Organization, OrgError>;
+
+    async fn update_client_features(
+        &self,
+        org_id: i32,
+        features: Vec<String>,
+    ) -> Result<Organization, OrgError>;
+
+    async fn set_room_org(&self, room_id: i32, org_id: Option<i32>) -> Result<(), OrgError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct OrgRepositoryImpl {
+    pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl OrgRepositoryImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Self {
+        Self { pool }
+    }
+
+    fn get_conn(&self) -> Result<PooledConnection<ConnectionManager<PgConnection>>, GeneralError> {
+        checkout(&self.pool)
+    }
+}
+
+#[async_trait]
+impl OrgRepository for OrgRepositoryImpl {
+    async fn create_org(&self, org: NewOrganization<'_>) -> Result<Organization, OrgError> {
+        let mut conn = self.get_conn()?;
+
+        insert_into(organizations::table)
+            .values(&org)
+            .returning(Organization::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| OrgError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_by_public_id(&self, public_id: &str) -> Result<Option<Organization>, OrgError> {
+        let mut conn = self.get_conn()?;
+
+        organizations::table
+            .filter(organizations::public_id.eq(public_id))
+            .select(Organization::as_select())
+            .first::<Organization>(&mut conn)
+            .optional()
+            .map_err(|err| OrgError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_by_room(&self, room_id: i32) -> Result<Option<Organization>, OrgError> {
+        let mut conn = self.get_conn()?;
+
+        rooms::table
+            .inner_join(organizations::table)
+            .filter(rooms::id.eq(room_id))
+            .select(Organization::as_select())
+            .first::<Organization>(&mut conn)
+            .optional()
+            .map_err(|err| OrgError::UnexpectedError(err.to_string()))
+    }
+
+    async fn update_branding(
+        &self,
+        org_id: i32,
+        branding: OrganizationBranding<'_>,
+    ) -> Result<Organization, OrgError> {
+        let mut conn = self.get_conn()?;
+
+        update(organizations::table)
+            .filter(organizations::id.eq(org_id))
+            .set(&branding)
+            .returning(Organization::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| OrgError::UnexpectedError(err.to_string()))
+    }
+
+    async fn update_client_features(
+        &self,
+        org_id: i32,
+        features: Vec<String>,
+    ) -> Result<Organization, OrgError> {
+        let mut conn = self.get_conn()?;
+
+        update(organizations::table)
+            .filter(organizations::id.eq(org_id))
+            .set((
+                organizations::client_features.eq(features),
+                organizations::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .returning(Organization::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| OrgError::UnexpectedError(err.to_string()))
+    }
+
+    async fn set_room_org(&self, room_id: i32, org_id: Option<i32>) -> Result<(), OrgError> {
+        let mut conn = self.get_conn()?;
+
+        update(rooms::table)
+            .filter(rooms::id.eq(room_id))
+            .set(rooms::org_id.eq(org_id))
+            .execute(&mut conn)
+            .map_err(|err| OrgError::UnexpectedError(err.to_string()))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,28 @@
+use salvo::{oapi::extract::PathParam, prelude::*};
+
+use crate::core::types::{
+    errors::org_error::OrgError, responses::branding_response::BrandingResponse,
+};
+
+use super::{
+    repository::OrgRepositoryImpl,
+    service::{OrgService, OrgServiceImpl},
+};
+
+/// Only behind the API key, clients theme the pre-join screen before anybody
+/// signs in
+pub fn get_org_router() -> Router {
+    Router::with_path("orgs/{org_id}/branding").get(get_branding)
+}
+
+/// Logo, colors and waiting room background of an organization
+#[endpoint(tags("org"), status_codes(200, 404, 500))]
+async fn get_branding(
+    _res: &mut Response,
+    org_id: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<BrandingResponse, OrgError> {
+    let org_service = depot.obtain::<OrgServiceImpl<OrgRepositoryImpl>>().unwrap();
+
+    org_service.get_branding(&org_id.into_inner()).await
+}
@@ -0,0 +1,451 @@
+use chrono::Utc;
+use salvo::async_trait;
+
+use crate::core::{
+    dtos::org::{
+        create_org_dto::CreateOrgDto, update_branding_dto::UpdateBrandingDto,
+        update_client_features_dto::UpdateClientFeaturesDto,
+    },
+    entities::models::{NewOrganization, OrganizationBranding},
+    types::{
+        errors::org_error::OrgError,
+        responses::{
+            branding_response::BrandingResponse, client_config_response::ClientFeaturesResponse,
+        },
+    },
+};
+
+/// Keeps a typo'd bulk update from bloating every client config response
+const MAX_CLIENT_FEATURES: usize = 64;
+
+const MAX_CLIENT_FEATURE_LEN: usize = 64;
+
+use super::repository::OrgRepository;
+
+#[async_trait]
+pub trait OrgService: Send + Sync {
+    async fn create_org(&self, data: CreateOrgDto) -> Result<BrandingResponse, OrgError>;
+
+    async fn update_branding(
+        &self,
+        org_id: &str,
+        data: UpdateBrandingDto,
+    ) -> Result<BrandingResponse, OrgError>;
+
+    async fn get_branding(&self, org_id: &str) -> Result<BrandingResponse, OrgError>;
+
+    /// Branding of the room's organization, `None` for rooms outside any
+    async fn get_room_branding(&self, room_id: i32) -> Result<Option<BrandingResponse>, OrgError>;
+
+    /// Moves the room into the organization, or out of its current one with `None`
+    async fn assign_room(&self, room_id: i32, org_id: Option<&str>) -> Result<(), OrgError>;
+
+    async fn update_client_features(
+        &self,
+        org_id: &str,
+        data: UpdateClientFeaturesDto,
+    ) -> Result<ClientFeaturesResponse, OrgError>;
+
+    async fn get_client_features(&self, org_id: &str) -> Result<Vec<String>, OrgError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct OrgServiceImpl<O: OrgRepository> {
+    org_repository: O,
+}
+
+impl<O: OrgRepository> OrgServiceImpl<O> {
+    pub fn new(org_repository: O) -> Self {
+        Self { org_repository }
+    }
+
+    async fn _find_org_id(&self, org_id: &str) -> Result<i32, OrgError> {
+        self.org_repository
+            .find_by_public_id(org_id)
+            .await?
+            .map(|org| org.id)
+            .ok_or_else(|| OrgError::OrgNotFound(org_id.to_string()))
+    }
+}
+
+#[async_trait]
+impl<O: OrgRepository> OrgService for OrgServiceImpl<O> {
+    async fn create_org(&self, data: CreateOrgDto) -> Result<BrandingResponse, OrgError> {
+        let name = data.name.trim();
+        if name.is_empty() {
+            return Err(OrgError::InvalidBranding("name is required".to_string()));
+        }
+        validate_branding(&data.branding)?;
+
+        let now = Utc::now().naive_utc();
+        let branding = &data.branding;
+
+        let org = self
+            .org_repository
+            .create_org(NewOrganization {
+                name,
+                logo_url: branding.logo_url.as_deref(),
+                primary_color: branding.primary_color.as_deref(),
+                accent_color: branding.accent_color.as_deref(),
+                background_url: branding.background_url.as_deref(),
+                created_at: now,
+                updated_at: now,
+            })
+            .await?;
+
+        Ok(org.into())
+    }
+
+    async fn update_branding(
+        &self,
+        org_id: &str,
+        data: UpdateBrandingDto,
+    ) -> Result<BrandingResponse, OrgError> {
+        validate_branding(&data)?;
+
+        let id = self._find_org_id(org_id).await?;
+
+        let org = self
+            .org_repository
+            .update_branding(
+                id,
+                OrganizationBranding {
+                    logo_url: data.logo_url.as_deref(),
+                    primary_color: data.primary_color.as_deref(),
+                    accent_color: data.accent_color.as_deref(),
+                    background_url: data.background_url.as_deref(),
+                    updated_at: Utc::now().naive_utc(),
+                },
+            )
+            .await?;
+
+        Ok(org.into())
+    }
+
+    async fn get_branding(&self, org_id: &str) -> Result<BrandingResponse, OrgError> {
+        self.org_repository
+            .find_by_public_id(org_id)
+            .await?
+            .map(BrandingResponse::from)
+            .ok_or_else(|| OrgError::OrgNotFound(org_id.to_string()))
+    }
+
+    async fn get_room_branding(&self, room_id: i32) -> Result<Option<BrandingResponse>, OrgError> {
+        Ok(self
+            .org_repository
+            .find_by_room(room_id)
+            .await?
+            .map(BrandingResponse::from))
+    }
+
+    async fn assign_room(&self, room_id: i32, org_id: Option<&str>) -> Result<(), OrgError> {
+        let org_id = match org_id {
+            Some(org_id) => Some(self._find_org_id(org_id).await?),
+            None => None,
+        };
+
+        self.org_repository.set_room_org(room_id, org_id).await
+    }
+
+    async fn update_client_features(
+        &self,
+        org_id: &str,
+        data: UpdateClientFeaturesDto,
+    ) -> Result<ClientFeaturesResponse, OrgError> {
+        let features = normalize_client_features(data.features)?;
+        let id = self._find_org_id(org_id).await?;
+
+        let org = self
+            .org_repository
+            .update_client_features(id, features)
+            .await?;
+
+        Ok(ClientFeaturesResponse {
+            org_id: org.public_id,
+            features: org.client_features,
+        })
+    }
+
+    async fn get_client_features(&self, org_id: &str) -> Result<Vec<String>, OrgError> {
+        self.org_repository
+            .find_by_public_id(org_id)
+            .await?
+            .map(|org| org.client_features)
+            .ok_or_else(|| OrgError::OrgNotFound(org_id.to_string()))
+    }
+}
+
+/// Flags are matched by name on the clients, so they're stored lowercased,
+/// sorted and once each
+pub fn normalize_client_features(features: Vec<String>) -> Result<Vec<String>, OrgError> {
+    let mut features = features
+        .into_iter()
+        .map(|feature| feature.trim().to_lowercase())
+        .filter(|feature| !feature.is_empty())
+        .collect::<Vec<_>>();
+    features.sort();
+    features.dedup();
+
+    if features.len() > MAX_CLIENT_FEATURES {
+        return Err(OrgError::InvalidClientFeatures(format!(
+            "at most {MAX_CLIENT_FEATURES} features"
+        )));
+    }
+
+    if let Some(feature) = features.iter().find(|feature| {
+        feature.len() > MAX_CLIENT_FEATURE_LEN
+            || !feature
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+    }) {
+        return Err(OrgError::InvalidClientFeatures(format!(
+            "{feature} is not a valid feature name"
+        )));
+    }
+
+    Ok(features)
+}
+
+/// `#RRGGBB`, what every client can parse
+pub fn is_brand_color(color: &str) -> bool {
+    color
+        .strip_prefix('#')
+        .is_some_and(|hex| hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Colors are handed to clients as is, URLs are loaded by browsers and the
+/// HLS slate, so neither is stored unless it's well formed
+pub fn validate_branding(branding: &UpdateBrandingDto) -> Result<(), OrgError> {
+    for (field, color) in [
+        ("primaryColor", &branding.primary_color),
+        ("accentColor", &branding.accent_color),
+    ] {
+        if let Some(color) = color
+            && !is_brand_color(color)
+        {
+            return Err(OrgError::InvalidBranding(format!(
+                "{field} must look like #RRGGBB"
+            )));
+        }
+    }
+
+    for (field, url) in [
+        ("logoUrl", &branding.logo_url),
+        ("backgroundUrl", &branding.background_url),
+    ] {
+        if let Some(url) = url
+            && !url.starts_with("https://")
+        {
+            return Err(OrgError::InvalidBranding(format!(
+                "{field} must be an https URL"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::entities::models::Organization;
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    #[derive(Clone, Default)]
+    struct MockOrgRepository {
+        orgs: Arc<Mutex<Vec<Organization>>>,
+        room_orgs: Arc<Mutex<HashMap<i32, i32>>>,
+    }
+
+    #[async_trait]
+    impl OrgRepository for MockOrgRepository {
+        async fn create_org(&self, org: NewOrganization<'_>) -> Result<Organization, OrgError> {
+            let mut orgs = self.orgs.lock().unwrap();
+            let id = orgs.len() as i32 + 1;
+            let org = Organization {
+                id,
+                public_id: format!("org-{id}"),
+                name: org.name.to_string(),
+                logo_url: org.logo_url.map(str::to_string),
+                primary_color: org.primary_color.map(str::to_string),
+                accent_color: org.accent_color.map(str::to_string),
+                background_url: org.background_url.map(str::to_string),
+                created_at: org.created_at,
+                updated_at: org.updated_at,
+                client_features: vec![],
+            };
+            orgs.push(org.clone());
+            Ok(org)
+        }
+
+        async fn find_by_public_id(
+            &self,
+            public_id: &str,
+        ) -> Result<Option<Organization>, OrgError> {
+            Ok(self
+                .orgs
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|org| org.public_id == public_id)
+                .cloned())
+        }
+
+        async fn find_by_room(&self, room_id: i32) -> Result<Option<Organization>, OrgError> {
+            let Some(org_id) = self.room_orgs.lock().unwrap().get(&room_id).copied() else {
+                return Ok(None);
+            };
+
+            Ok(self
+                .orgs
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|org| org.id == org_id)
+                .cloned())
+        }
+
+        async fn update_branding(
+            &self,
+            org_id: i32,
+            branding: OrganizationBranding<'_>,
+        ) -> Result<Organization, OrgError> {
+            let mut orgs = self.orgs.lock().unwrap();
+            let org = orgs.iter_mut().find(|org| org.id == org_id).unwrap();
+            org.logo_url = branding.logo_url.map(str::to_string);
+            org.primary_color = branding.primary_color.map(str::to_string);
+            org.accent_color = branding.accent_color.map(str::to_string);
+            org.background_url = branding.background_url.map(str::to_string);
+            org.updated_at = branding.updated_at;
+            Ok(org.clone())
+        }
+
+        async fn update_client_features(
+            &self,
+            org_id: i32,
+            features: Vec<String>,
+        ) -> Result<Organization, OrgError> {
+            let mut orgs = self.orgs.lock().unwrap();
+            let org = orgs.iter_mut().find(|org| org.id == org_id).unwrap();
+            org.client_features = features;
+            Ok(org.clone())
+        }
+
+        async fn set_room_org(&self, room_id: i32, org_id: Option<i32>) -> Result<(), OrgError> {
+            let mut room_orgs = self.room_orgs.lock().unwrap();
+            match org_id {
+                Some(org_id) => room_orgs.insert(room_id, org_id),
+                None => room_orgs.remove(&room_id),
+            };
+            Ok(())
+        }
+    }
+
+    fn sample_branding() -> UpdateBrandingDto {
+        UpdateBrandingDto {
+            logo_url: Some("https://cdn.acme.com/logo.png".to_string()),
+            primary_color: Some("#1A73E8".to_string()),
+            accent_color: None,
+            background_url: None,
+        }
+    }
+
+    #[test]
+    fn test_is_brand_color() {
+        assert!(is_brand_color("#1a73e8"));
+        assert!(is_brand_color("#FBBC04"));
+        assert!(!is_brand_color("1A73E8"));
+        assert!(!is_brand_color("#1A73E"));
+        assert!(!is_brand_color("#1A73E8FF"));
+        assert!(!is_brand_color("#GGGGGG"));
+    }
+
+    #[test]
+    fn test_validate_branding_rejects_plain_http() {
+        let mut branding = sample_branding();
+        assert!(validate_branding(&branding).is_ok());
+
+        branding.background_url = Some("http://cdn.acme.com/lobby.jpg".to_string());
+        assert!(matches!(
+            validate_branding(&branding),
+            Err(OrgError::InvalidBranding(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_room_branding_follows_its_org() {
+        let service = OrgServiceImpl::new(MockOrgRepository::default());
+
+        let org = service
+            .create_org(CreateOrgDto {
+                name: "Acme".to_string(),
+                branding: sample_branding(),
+            })
+            .await
+            .unwrap();
+
+        assert!(service.get_room_branding(7).await.unwrap().is_none());
+
+        service.assign_room(7, Some(&org.org_id)).await.unwrap();
+        let branding = service.get_room_branding(7).await.unwrap().unwrap();
+        assert_eq!(branding.primary_color.as_deref(), Some("#1A73E8"));
+
+        service.assign_room(7, None).await.unwrap();
+        assert!(service.get_room_branding(7).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_update_branding_replaces_every_field() {
+        let service = OrgServiceImpl::new(MockOrgRepository::default());
+
+        let org = service
+            .create_org(CreateOrgDto {
+                name: "Acme".to_string(),
+                branding: sample_branding(),
+            })
+            .await
+            .unwrap();
+
+        let branding = service
+            .update_branding(
+                &org.org_id,
+                UpdateBrandingDto {
+                    accent_color: Some("#FBBC04".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(branding.logo_url, None);
+        assert_eq!(branding.primary_color, None);
+        assert_eq!(branding.accent_color.as_deref(), Some("#FBBC04"));
+
+        assert!(matches!(
+            service
+                .update_branding("missing", UpdateBrandingDto::default())
+                .await,
+            Err(OrgError::OrgNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_normalize_client_features() {
+        let features = normalize_client_features(vec![
+            "Whiteboard".to_string(),
+            " live_captions ".to_string(),
+            "whiteboard".to_string(),
+            "".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(features, vec!["live_captions", "whiteboard"]);
+
+        assert!(matches!(
+            normalize_client_features(vec!["live captions".to_string()]),
+            Err(OrgError::InvalidClientFeatures(_))
+        ));
+    }
+}
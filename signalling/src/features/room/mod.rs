@@ -1,3 +1,4 @@
+pub mod policy;
 pub mod repository;
 pub mod router;
 pub mod service;
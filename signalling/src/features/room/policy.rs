@@ -0,0 +1,153 @@
+use crate::core::{
+    entities::models::{Room, RoomType, StreamingProtocol},
+    types::enums::ws_event::WsEvent,
+};
+
+/// Connection types clients declare, the policy picks one for them
+const CONNECTION_TYPE_P2P: u8 = 0;
+const CONNECTION_TYPE_SFU: u8 = 1;
+
+/// What the socket handlers let through in a room, from its type. Set on a
+/// socket when it joins or starts observing the room, and refreshed where a
+/// switch between conference and live stream matters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoomPolicy {
+    room_type: RoomType,
+    streaming_protocol: StreamingProtocol,
+    is_host: bool,
+}
+
+impl RoomPolicy {
+    pub fn new(room_type: RoomType, is_host: bool) -> Self {
+        Self {
+            room_type,
+            streaming_protocol: StreamingProtocol::SFU,
+            is_host,
+        }
+    }
+
+    pub fn from_room(room: &Room, is_host: bool) -> Self {
+        Self {
+            streaming_protocol: StreamingProtocol::from(room.streaming_protocol),
+            ..Self::new(RoomType::from(room.type_), is_host)
+        }
+    }
+
+    pub fn room_type(&self) -> RoomType {
+        self.room_type
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.is_host
+    }
+
+    /// Peers of a P2P room connect to each other, signalling relays their
+    /// offers, answers and candidates instead of handing them to the SFU
+    pub fn is_p2p(&self) -> bool {
+        self.room_type == RoomType::P2P
+    }
+
+    /// What the SFU is told the publisher connects with
+    pub fn connection_type(&self) -> u8 {
+        if self.is_p2p() {
+            CONNECTION_TYPE_P2P
+        } else {
+            CONNECTION_TYPE_SFU
+        }
+    }
+
+    /// Only hosts publish to a live stream, the audience joins without tracks
+    pub fn allows_publish(&self, total_tracks: u8) -> bool {
+        total_tracks == 0 || self.room_type != RoomType::LiveStreaming || self.is_host
+    }
+
+    /// The SFU never gets a P2P room's media to record or stream
+    pub fn allows_egress(&self) -> bool {
+        !self.is_p2p()
+    }
+
+    /// Hosts of a live stream switched to mid-session go out over HLS as they
+    /// join, without waiting on a schedule
+    pub fn starts_egress(&self) -> bool {
+        self.is_host
+            && self.room_type == RoomType::LiveStreaming
+            && self.streaming_protocol != StreamingProtocol::SFU
+    }
+
+    /// Whether the socket may send `event` in the room
+    pub fn allows(&self, event: WsEvent) -> bool {
+        match event {
+            // Nothing of a P2P room goes through the SFU, nor out over HLS
+            WsEvent::RoomSubscribe
+            | WsEvent::RoomViewportUpdate
+            | WsEvent::RoomMigrate
+            | WsEvent::RoomObserve => !self.is_p2p(),
+            WsEvent::RoomPublisherRenegotiation | WsEvent::RoomScreenSharing => {
+                self.room_type != RoomType::LiveStreaming || self.is_host
+            }
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_p2p_room_skips_sfu_and_hls() {
+        let policy = RoomPolicy::new(RoomType::P2P, false);
+
+        assert!(policy.is_p2p());
+        assert_eq!(policy.connection_type(), CONNECTION_TYPE_P2P);
+        assert!(!policy.allows(WsEvent::RoomSubscribe));
+        assert!(!policy.allows(WsEvent::RoomObserve));
+        assert!(!policy.allows(WsEvent::RoomMigrate));
+        assert!(!policy.allows_egress());
+        assert!(policy.allows(WsEvent::RoomAnswerSubscriber));
+        assert!(policy.allows(WsEvent::RoomPublisherCandidate));
+        assert!(policy.allows_publish(2));
+    }
+
+    #[test]
+    fn test_live_stream_publishing_is_for_hosts() {
+        let audience = RoomPolicy::new(RoomType::LiveStreaming, false);
+        let host = RoomPolicy::new(RoomType::LiveStreaming, true);
+
+        assert_eq!(audience.connection_type(), CONNECTION_TYPE_SFU);
+        assert!(audience.allows(WsEvent::RoomSubscribe));
+        assert!(audience.allows_publish(0));
+        assert!(!audience.allows_publish(2));
+        assert!(!audience.allows(WsEvent::RoomPublisherRenegotiation));
+        assert!(!audience.allows(WsEvent::RoomScreenSharing));
+
+        assert!(host.allows_publish(2));
+        assert!(host.allows(WsEvent::RoomPublisherRenegotiation));
+        assert!(!host.starts_egress());
+    }
+
+    #[test]
+    fn test_switched_live_stream_starts_egress() {
+        let host = RoomPolicy {
+            streaming_protocol: StreamingProtocol::HLS,
+            ..RoomPolicy::new(RoomType::LiveStreaming, true)
+        };
+        let audience = RoomPolicy {
+            streaming_protocol: StreamingProtocol::HLS,
+            ..RoomPolicy::new(RoomType::LiveStreaming, false)
+        };
+
+        assert!(host.starts_egress());
+        assert!(!audience.starts_egress());
+    }
+
+    #[test]
+    fn test_conference_allows_everyone() {
+        let policy = RoomPolicy::new(RoomType::Conferencing, false);
+
+        assert_eq!(policy.connection_type(), CONNECTION_TYPE_SFU);
+        assert!(policy.allows_publish(2));
+        assert!(policy.allows(WsEvent::RoomSubscribe));
+        assert!(policy.allows(WsEvent::RoomScreenSharing));
+    }
+}
@@ -1,6 +1,7 @@
 use diesel::{
-    BelongingToDsl, Connection, ExpressionMethods, GroupedBy, JoinOnDsl, NullableExpressionMethods,
-    PgConnection, QueryDsl, RunQueryDsl, SelectableHelper,
+    BelongingToDsl, BoolExpressionMethods, Connection, ExpressionMethods, GroupedBy, JoinOnDsl,
+    NullableExpressionMethods, OptionalExtension, PgConnection, PgTextExpressionMethods, QueryDsl,
+    RunQueryDsl, SelectableHelper,
     dsl::delete,
     insert_into,
     r2d2::{ConnectionManager, Pool, PooledConnection},
@@ -9,12 +10,17 @@ use diesel::{
 use salvo::async_trait;
 use tracing::warn;
 
-use chrono::NaiveDateTime;
+use chrono::{NaiveDateTime, Utc};
 
 use crate::core::{
-    database::schema::{members, messages, participants, rooms, users},
+    database::{
+        db::checkout,
+        schema::{members, messages, participants, rooms, users},
+        soft_delete::{DeletedScope, rooms_in_scope, users_in_scope},
+    },
     entities::models::{
-        Member, MembersRoleEnum, Message, NewRoom, Participant, Room, RoomStatusEnum, User,
+        Member, MembersRoleEnum, Message, NewRoom, Participant, ParticipantsStatusEnum, Room,
+        RoomStatusEnum, User,
     },
     types::{
         errors::{general::GeneralError, room_error::RoomError},
@@ -28,6 +34,7 @@ use crate::core::{
     entities::models::{NewMember, NewParticipant},
     types::responses::room_response::MemberResponse,
 };
+use crate::features::search::repository::like_pattern;
 
 #[async_trait]
 pub trait RoomRepository: Send + Sync {
@@ -41,10 +48,23 @@ pub trait RoomRepository: Send + Sync {
 
     async fn exists_code(&self, room_code: &str) -> Result<bool, RoomError>;
 
-    async fn get_room_by_id(&self, room_id: i32) -> Result<RoomResponse, RoomError>;
+    async fn get_room_by_id(
+        &self,
+        room_id: i32,
+        scope: DeletedScope,
+    ) -> Result<RoomResponse, RoomError>;
 
     async fn get_room_by_code(&self, room_code: &str) -> Result<RoomResponse, RoomError>;
 
+    /// The password hash of a room, `get_room_by_id` answers from the cache without it
+    async fn get_room_password(&self, room_id: i32) -> Result<Option<String>, RoomError>;
+
+    async fn get_room_id_by_public_id(
+        &self,
+        public_id: &str,
+        scope: DeletedScope,
+    ) -> Result<i32, RoomError>;
+
     async fn create_room(&self, room: NewRoom<'_>) -> Result<RoomResponse, RoomError>;
 
     async fn create_room_with_member(
@@ -58,6 +78,14 @@ pub trait RoomRepository: Send + Sync {
 
     async fn get_member_by_id(&self, member_id: i32) -> Result<MemberResponse, RoomError>;
 
+    async fn find_members(
+        &self,
+        room_id: i32,
+        query: Option<String>,
+        skip: i64,
+        limit: i64,
+    ) -> Result<Vec<MemberResponse>, RoomError>;
+
     async fn create_member(&self, member: NewMember<'_>) -> Result<MemberResponse, RoomError>;
 
     async fn update_member(&self, member: Member) -> Result<MemberResponse, RoomError>;
@@ -69,6 +97,19 @@ pub trait RoomRepository: Send + Sync {
         participant_id: i32,
     ) -> Result<ParticipantResponse, RoomError>;
 
+    async fn get_participant_by_public_id(
+        &self,
+        public_id: &str,
+    ) -> Result<ParticipantResponse, RoomError>;
+
+    async fn find_participants(
+        &self,
+        room_id: i32,
+        query: Option<String>,
+        skip: i64,
+        limit: i64,
+    ) -> Result<Vec<ParticipantResponse>, RoomError>;
+
     async fn create_participant(
         &self,
         participant: NewParticipant<'_>,
@@ -81,7 +122,39 @@ pub trait RoomRepository: Send + Sync {
 
     async fn delete_participant_by_id(&self, participant_id: i32) -> Result<(), RoomError>;
 
-    async fn delete_participants_by_node(&self, node_id: &str) -> Result<(), RoomError>;
+    /// Returns the rooms the participants were in
+    async fn delete_participants_by_node(&self, node_id: &str) -> Result<Vec<i32>, RoomError>;
+
+    /// Returns the rooms the participants were in
+    async fn detach_participants_by_node(&self, node_id: &str) -> Result<Vec<i32>, RoomError>;
+
+    async fn update_participant_media(
+        &self,
+        participant_id: &str,
+        change: MediaChange,
+    ) -> Result<Participant, RoomError>;
+
+    /// Deletes participants detached before `detached_before` that never
+    /// rejoined, with the rooms they were in
+    async fn purge_detached_participants(
+        &self,
+        detached_before: NaiveDateTime,
+    ) -> Result<Vec<(Room, Participant)>, RoomError>;
+}
+
+/// A switch a participant's node acknowledged, kept on the participant so a
+/// failover can restore it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaChange {
+    /// A fresh publish, a raised hand doesn't carry over
+    Published {
+        is_audio_enabled: bool,
+        is_video_enabled: bool,
+    },
+    Unpublished,
+    Audio(bool),
+    Video(bool),
+    HandRaising(bool),
 }
 
 #[derive(Debug, Clone)]
@@ -95,7 +168,7 @@ impl RoomRepositoryImpl {
     }
 
     fn get_conn(&self) -> Result<PooledConnection<ConnectionManager<PgConnection>>, GeneralError> {
-        self.pool.get().map_err(|_| GeneralError::DbConnectionError)
+        checkout(&self.pool)
     }
 }
 
@@ -118,6 +191,7 @@ impl RoomRepository for RoomRepositoryImpl {
             .inner_join(members::table.on(rooms::id.eq(members::room_id)))
             .inner_join(users::table.on(members::user_id.eq(users::id)))
             .filter(rooms::status.eq(room_status))
+            .filter(rooms_in_scope(DeletedScope::Exclude))
             .filter(users::id.eq(user_id))
             .left_join(messages::table.on(rooms::latest_message_id.eq(messages::id.nullable())))
             .left_join(
@@ -139,6 +213,9 @@ impl RoomRepository for RoomRepositoryImpl {
                         users::updated_at,
                         users::deleted_at,
                         users::last_seen_at,
+                        users::public_id,
+                        users::locale,
+                        users::timezone,
                     ))
                     .nullable(),
             ))
@@ -190,7 +267,7 @@ impl RoomRepository for RoomRepositoryImpl {
 
                 let participants = participants
                     .into_iter()
-                    .map(|(participant, user)| ParticipantResponse { participant, user })
+                    .map(|(participant, user)| ParticipantResponse::new(participant, user))
                     .collect();
 
                 let latest_message = latest_message.map(|message| MessageResponse {
@@ -204,6 +281,8 @@ impl RoomRepository for RoomRepositoryImpl {
                     members,
                     participants,
                     latest_message,
+                    branding: None,
+                    overflow: None,
                 }
             })
             .collect::<Vec<_>>();
@@ -214,6 +293,8 @@ impl RoomRepository for RoomRepositoryImpl {
     async fn exists_code(&self, room_code: &str) -> Result<bool, RoomError> {
         let mut conn = self.get_conn()?;
 
+        // Codes stay reserved after a room is deleted, the column is unique
+
         use self::rooms::dsl::*;
 
         match rooms
@@ -229,11 +310,16 @@ impl RoomRepository for RoomRepositoryImpl {
         }
     }
 
-    async fn get_room_by_id(&self, room_id: i32) -> Result<RoomResponse, RoomError> {
+    async fn get_room_by_id(
+        &self,
+        room_id: i32,
+        scope: DeletedScope,
+    ) -> Result<RoomResponse, RoomError> {
         let mut conn = self.get_conn()?;
 
         let rooms = rooms::table
             .filter(rooms::id.eq(room_id))
+            .filter(rooms_in_scope(scope))
             .select(Room::as_select())
             .load::<Room>(&mut conn)
             .map_err(|_| RoomError::RoomNotFound(room_id))?;
@@ -259,7 +345,7 @@ impl RoomRepository for RoomRepositoryImpl {
         let participant_responses: Vec<ParticipantResponse> = participant_grouped
             .into_iter()
             .flatten()
-            .map(|(participant, user)| ParticipantResponse { participant, user })
+            .map(|(participant, user)| ParticipantResponse::new(participant, user))
             .collect();
 
         let member_responses: Vec<MemberResponse> = member_grouped
@@ -278,6 +364,8 @@ impl RoomRepository for RoomRepositoryImpl {
             members: member_responses,
             participants: participant_responses,
             latest_message: None,
+            branding: None,
+            overflow: None,
         };
 
         Ok(response)
@@ -288,6 +376,7 @@ impl RoomRepository for RoomRepositoryImpl {
 
         let rooms = rooms::table
             .filter(rooms::code.eq(room_code))
+            .filter(rooms_in_scope(DeletedScope::Exclude))
             .select(Room::as_select())
             .load::<Room>(&mut conn)
             .map_err(|_| RoomError::RoomCodeNotFound(room_code.to_string()))?;
@@ -313,7 +402,7 @@ impl RoomRepository for RoomRepositoryImpl {
         let participant_responses: Vec<ParticipantResponse> = participant_grouped
             .into_iter()
             .flatten()
-            .map(|(participant, user)| ParticipantResponse { participant, user })
+            .map(|(participant, user)| ParticipantResponse::new(participant, user))
             .collect();
 
         let member_responses: Vec<MemberResponse> = member_grouped
@@ -332,11 +421,39 @@ impl RoomRepository for RoomRepositoryImpl {
             members: member_responses,
             participants: participant_responses,
             latest_message: None,
+            branding: None,
+            overflow: None,
         };
 
         Ok(response)
     }
 
+    async fn get_room_password(&self, room_id: i32) -> Result<Option<String>, RoomError> {
+        let mut conn = self.get_conn()?;
+
+        rooms::table
+            .filter(rooms::id.eq(room_id))
+            .filter(rooms_in_scope(DeletedScope::Exclude))
+            .select(rooms::password)
+            .first::<Option<String>>(&mut conn)
+            .map_err(|_| RoomError::RoomNotFound(room_id))
+    }
+
+    async fn get_room_id_by_public_id(
+        &self,
+        public_id: &str,
+        scope: DeletedScope,
+    ) -> Result<i32, RoomError> {
+        let mut conn = self.get_conn()?;
+
+        rooms::table
+            .filter(rooms::public_id.eq(public_id))
+            .filter(rooms_in_scope(scope))
+            .select(rooms::id)
+            .first::<i32>(&mut conn)
+            .map_err(|_| RoomError::PublicIdNotFound(public_id.to_string()))
+    }
+
     async fn create_room(&self, room: NewRoom<'_>) -> Result<RoomResponse, RoomError> {
         let mut conn = self.get_conn()?;
 
@@ -351,6 +468,8 @@ impl RoomRepository for RoomRepositoryImpl {
             members: Vec::new(),
             participants: Vec::new(),
             latest_message: None,
+            branding: None,
+            overflow: None,
         };
 
         Ok(room_response)
@@ -390,6 +509,8 @@ impl RoomRepository for RoomRepositoryImpl {
                 }],
                 participants: vec![],
                 latest_message: None,
+                branding: None,
+                overflow: None,
             };
 
             Ok(response)
@@ -409,12 +530,21 @@ impl RoomRepository for RoomRepositoryImpl {
                 rooms::latest_message_created_at.eq(room.latest_message_created_at),
                 rooms::latest_message_id.eq(room.latest_message_id),
                 rooms::status.eq(room.status),
+                rooms::message_retention_days.eq(room.message_retention_days),
+                rooms::noise_suppression.eq(room.noise_suppression),
+                rooms::broadcast_delay_secs.eq(room.broadcast_delay_secs),
+                rooms::capacity.eq(room.capacity),
+                rooms::presenter_member_ids.eq(room.presenter_member_ids),
+                rooms::type_.eq(room.type_),
+                rooms::streaming_protocol.eq(room.streaming_protocol),
             ))
             .returning(Room::as_select())
             .get_result(&mut conn)
             .map_err(|err| RoomError::UnexpectedError(err.to_string()))?;
 
-        let room_response = self.get_room_by_id(updated_room.id).await?;
+        let room_response = self
+            .get_room_by_id(updated_room.id, DeletedScope::Exclude)
+            .await?;
 
         Ok(room_response)
     }
@@ -427,18 +557,56 @@ impl RoomRepository for RoomRepositoryImpl {
             .left_join(users::table.on(members::user_id.nullable().eq(users::id.nullable())))
             .select((Member::as_select(), Option::<User>::as_select()))
             .load::<(Member, Option<User>)>(&mut conn)
-            .map_err(|_| RoomError::UnexpectedError("Member not found".to_string()))?;
+            .map_err(|_| RoomError::MemberNotFound)?;
 
         if result.is_empty() {
-            return Err(RoomError::UnexpectedError("Member not found".to_string()));
+            return Err(RoomError::MemberNotFound);
         }
 
         match result.into_iter().next() {
             Some((member, user)) => Ok(MemberResponse { member, user }),
-            None => Err(RoomError::UnexpectedError("Member not found".to_string())),
+            None => Err(RoomError::MemberNotFound),
         }
     }
 
+    async fn find_members(
+        &self,
+        room_id: i32,
+        query: Option<String>,
+        skip: i64,
+        limit: i64,
+    ) -> Result<Vec<MemberResponse>, RoomError> {
+        let mut conn = self.get_conn()?;
+
+        let mut statement = members::table
+            .inner_join(users::table.on(users::id.eq(members::user_id)))
+            .filter(members::room_id.eq(room_id))
+            .filter(users_in_scope(DeletedScope::Exclude))
+            .select((Member::as_select(), Option::<User>::as_select()))
+            .into_boxed();
+
+        if let Some(query) = query {
+            let pattern = like_pattern(&query);
+            statement = statement.filter(
+                users::user_name
+                    .ilike(pattern.clone())
+                    .or(users::full_name.ilike(pattern)),
+            );
+        }
+
+        let members = statement
+            .order(members::id.asc())
+            .offset(skip)
+            .limit(limit)
+            .load::<(Member, Option<User>)>(&mut conn)
+            .map_err(|_| RoomError::UnexpectedError("Failed to find members".into()))?;
+
+        Ok(members
+            .into_iter()
+            .map(|(member, user)| MemberResponse { member, user })
+            .collect())
+    }
+
     async fn create_member(&self, member: NewMember<'_>) -> Result<MemberResponse, RoomError> {
         let mut conn = self.get_conn()?;
         let new_member = insert_into(members::table)
@@ -485,20 +653,71 @@ impl RoomRepository for RoomRepositoryImpl {
             .left_join(users::table.on(participants::user_id.nullable().eq(users::id.nullable())))
             .select((Participant::as_select(), Option::<User>::as_select()))
             .load::<(Participant, Option<User>)>(&mut conn)
-            .map_err(|_| RoomError::UnexpectedError("Participant not found".to_string()))?;
+            .map_err(|_| RoomError::ParticipantNotFound(participant_id.to_string()))?;
 
         if result.is_empty() {
-            return Err(RoomError::UnexpectedError(
-                "Participant not found".to_string(),
-            ));
+            return Err(RoomError::ParticipantNotFound(participant_id.to_string()));
         }
 
         match result.into_iter().next() {
-            Some((participant, user)) => Ok(ParticipantResponse { participant, user }),
-            None => Err(RoomError::UnexpectedError(
-                "Participant not found".to_string(),
-            )),
+            Some((participant, user)) => Ok(ParticipantResponse::new(participant, user)),
+            None => Err(RoomError::ParticipantNotFound(participant_id.to_string())),
+        }
+    }
+
+    async fn get_participant_by_public_id(
+        &self,
+        public_id: &str,
+    ) -> Result<ParticipantResponse, RoomError> {
+        let mut conn = self.get_conn()?;
+
+        let (participant, user) = participants::table
+            .filter(participants::public_id.eq(public_id))
+            .left_join(users::table.on(participants::user_id.nullable().eq(users::id.nullable())))
+            .select((Participant::as_select(), Option::<User>::as_select()))
+            .first::<(Participant, Option<User>)>(&mut conn)
+            .map_err(|_| RoomError::ParticipantNotFound(public_id.to_string()))?;
+
+        Ok(ParticipantResponse::new(participant, user))
+    }
+
+    async fn find_participants(
+        &self,
+        room_id: i32,
+        query: Option<String>,
+        skip: i64,
+        limit: i64,
+    ) -> Result<Vec<ParticipantResponse>, RoomError> {
+        let mut conn = self.get_conn()?;
+
+        let mut statement = participants::table
+            .inner_join(users::table.on(users::id.eq(participants::user_id)))
+            .filter(participants::room_id.eq(room_id))
+            .filter(participants::node_id.is_not_null())
+            .filter(users_in_scope(DeletedScope::Exclude))
+            .select((Participant::as_select(), Option::<User>::as_select()))
+            .into_boxed();
+
+        if let Some(query) = query {
+            let pattern = like_pattern(&query);
+            statement = statement.filter(
+                users::user_name
+                    .ilike(pattern.clone())
+                    .or(users::full_name.ilike(pattern)),
+            );
         }
+
+        let participants = statement
+            .order(participants::id.asc())
+            .offset(skip)
+            .limit(limit)
+            .load::<(Participant, Option<User>)>(&mut conn)
+            .map_err(|_| RoomError::UnexpectedError("Failed to find participants".into()))?;
+
+        Ok(participants
+            .into_iter()
+            .map(|(participant, user)| ParticipantResponse::new(participant, user))
+            .collect())
     }
 
     async fn create_participant(
@@ -527,6 +746,11 @@ impl RoomRepository for RoomRepositoryImpl {
             .set((
                 participants::status.eq(participant.status),
                 participants::node_id.eq(participant.node_id),
+                participants::client_version.eq(participant.client_version),
+                participants::client_platform.eq(participant.client_platform),
+                participants::device_model.eq(participant.device_model),
+                participants::network_type.eq(participant.network_type),
+                participants::detached_at.eq(participant.detached_at),
             ))
             .returning(Participant::as_select())
             .get_result(&mut conn)
@@ -547,9 +771,7 @@ impl RoomRepository for RoomRepositoryImpl {
             })?;
 
         if deleted_rows == 0 {
-            return Err(RoomError::UnexpectedError(
-                "No participant found to delete".to_string(),
-            ));
+            return Err(RoomError::ParticipantNotFound(participant_id.to_string()));
         }
 
         let participant = self.get_participant_by_id(participant_id).await;
@@ -561,12 +783,13 @@ impl RoomRepository for RoomRepositoryImpl {
         Ok(())
     }
 
-    async fn delete_participants_by_node(&self, node_id: &str) -> Result<(), RoomError> {
+    async fn delete_participants_by_node(&self, node_id: &str) -> Result<Vec<i32>, RoomError> {
         let mut conn = self.get_conn()?;
 
-        let deleted_rows = delete(participants::table)
+        let mut room_ids = delete(participants::table)
             .filter(participants::node_id.eq(node_id))
-            .execute(&mut conn)
+            .returning(participants::room_id)
+            .get_results::<i32>(&mut conn)
             .map_err(|err| {
                 warn!(
                     "Failed to delete participants for node {}: {:?}",
@@ -575,10 +798,126 @@ impl RoomRepository for RoomRepositoryImpl {
                 RoomError::UnexpectedError("Failed to delete participants by node".into())
             })?;
 
-        if deleted_rows == 0 {
+        if room_ids.is_empty() {
             warn!("No participants found for node_id: {}", node_id);
         }
 
-        Ok(())
+        room_ids.sort_unstable();
+        room_ids.dedup();
+
+        Ok(room_ids)
+    }
+
+    async fn detach_participants_by_node(&self, node_id: &str) -> Result<Vec<i32>, RoomError> {
+        let mut conn = self.get_conn()?;
+
+        let mut room_ids = update(participants::table)
+            .filter(participants::node_id.eq(node_id))
+            .set((
+                participants::node_id.eq(None::<String>),
+                participants::status.eq(ParticipantsStatusEnum::Inactive as i16),
+                participants::detached_at.eq(Utc::now().naive_utc()),
+            ))
+            .returning(participants::room_id)
+            .get_results::<i32>(&mut conn)
+            .map_err(|err| {
+                warn!(
+                    "Failed to detach participants from node {}: {:?}",
+                    node_id, err
+                );
+                RoomError::UnexpectedError("Failed to detach participants by node".into())
+            })?;
+
+        room_ids.sort_unstable();
+        room_ids.dedup();
+
+        Ok(room_ids)
+    }
+
+    async fn update_participant_media(
+        &self,
+        participant_id: &str,
+        change: MediaChange,
+    ) -> Result<Participant, RoomError> {
+        let mut conn = self.get_conn()?;
+
+        let target = update(participants::table).filter(participants::public_id.eq(participant_id));
+
+        let result = match change {
+            MediaChange::Published {
+                is_audio_enabled,
+                is_video_enabled,
+            } => target
+                .set((
+                    participants::is_audio_enabled.eq(is_audio_enabled),
+                    participants::is_video_enabled.eq(is_video_enabled),
+                    participants::is_hand_raising.eq(false),
+                ))
+                .returning(Participant::as_select())
+                .get_result(&mut conn),
+            MediaChange::Unpublished => target
+                .set((
+                    participants::is_audio_enabled.eq(false),
+                    participants::is_video_enabled.eq(false),
+                ))
+                .returning(Participant::as_select())
+                .get_result(&mut conn),
+            MediaChange::Audio(is_enabled) => target
+                .set(participants::is_audio_enabled.eq(is_enabled))
+                .returning(Participant::as_select())
+                .get_result(&mut conn),
+            MediaChange::Video(is_enabled) => target
+                .set(participants::is_video_enabled.eq(is_enabled))
+                .returning(Participant::as_select())
+                .get_result(&mut conn),
+            MediaChange::HandRaising(is_raising) => target
+                .set(participants::is_hand_raising.eq(is_raising))
+                .returning(Participant::as_select())
+                .get_result(&mut conn),
+        };
+
+        result
+            .optional()
+            .map_err(|err| RoomError::UnexpectedError(err.to_string()))?
+            .ok_or_else(|| RoomError::ParticipantNotFound(participant_id.to_string()))
+    }
+
+    async fn purge_detached_participants(
+        &self,
+        detached_before: NaiveDateTime,
+    ) -> Result<Vec<(Room, Participant)>, RoomError> {
+        let mut conn = self.get_conn()?;
+
+        let purged = delete(participants::table)
+            .filter(participants::node_id.is_null())
+            .filter(participants::status.eq(ParticipantsStatusEnum::Inactive as i16))
+            .filter(participants::detached_at.lt(detached_before))
+            .returning(Participant::as_select())
+            .get_results::<Participant>(&mut conn)
+            .map_err(|err| {
+                warn!("Failed to purge detached participants: {:?}", err);
+                RoomError::UnexpectedError("Failed to purge detached participants".into())
+            })?;
+
+        if purged.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let room_ids: Vec<i32> = purged.iter().map(|p| p.room_id).collect();
+        let rooms = rooms::table
+            .filter(rooms::id.eq_any(room_ids))
+            .select(Room::as_select())
+            .load::<Room>(&mut conn)
+            .map_err(|err| RoomError::UnexpectedError(err.to_string()))?;
+
+        Ok(purged
+            .into_iter()
+            .filter_map(|participant| {
+                rooms
+                    .iter()
+                    .find(|room| room.id == participant.room_id)
+                    .map(|room| (room.clone(), participant))
+            })
+            .collect())
     }
 }
@@ -1,7 +1,11 @@
+use std::time::Instant;
+
 use salvo::{
-    oapi::extract::{JsonBody, PathParam},
+    oapi::extract::{JsonBody, PathParam, QueryParam},
     prelude::*,
+    rate_limiter::{BasicQuota, FixedGuard, MokaStore, RateLimiter, RemoteIpIssuer},
 };
+use tracing::warn;
 
 use crate::{
     core::{
@@ -12,14 +16,37 @@ use crate::{
                 join_room_dto::JoinRoomDto, update_room_dto::UpdateRoomDto,
             },
         },
-        entities::models::RoomStatusEnum,
+        entities::models::{MembersRoleEnum, ParticipantsStatusEnum, RoomStatusEnum},
+        metrics::join_metrics::JoinMetrics,
         types::{
             errors::room_error::RoomError,
-            responses::{list_room_response::ListRoomResponse, room_response::RoomResponse},
+            responses::{
+                join_token_response::JoinTokenResponse,
+                list_member_response::ListMemberResponse,
+                list_participant_response::ListParticipantResponse,
+                list_room_response::ListRoomResponse,
+                overflow_response::{OverflowInteraction, OverflowResponse},
+                room_response::RoomResponse,
+                socket_response::HlsStreamResponse,
+                viewer_count_response::ViewerCountResponse,
+            },
+        },
+        utils::{
+            hls_session_store::{HlsSession, HlsSessionStore},
+            jwt_utils::JwtUtils,
+            overflow_queue::OverflowQueue,
+            viewer_count_store::ViewerCountStore,
         },
-        utils::jwt_utils::JwtUtils,
     },
-    features::{room::repository::RoomRepositoryImpl, user::repository::UserRepositoryImpl},
+    features::{
+        matrix::service::{MatrixBridge, MatrixService},
+        org::{
+            repository::OrgRepositoryImpl,
+            service::{OrgService, OrgServiceImpl},
+        },
+        room::repository::RoomRepositoryImpl,
+        user::repository::UserRepositoryImpl,
+    },
 };
 
 use super::service::{RoomService, RoomServiceImpl};
@@ -27,10 +54,17 @@ use super::service::{RoomService, RoomServiceImpl};
 pub fn get_room_router(jwt_utils: JwtUtils) -> Router {
     let member_router = Router::with_path("/{room_id}/members")
         .post(add_member)
-        .delete(delete_member);
+        .delete(delete_member)
+        .push(Router::with_hoop(listing_rate_limiter()).get(get_members));
+
+    let participant_router = Router::with_path("/{room_id}/participants")
+        .hoop(listing_rate_limiter())
+        .get(get_participants);
 
     let join_router = Router::with_path("/{room_id}/join").post(join_room);
 
+    let join_token_router = Router::with_path("/{room_id}/join-token").post(create_join_token);
+
     let deactivate_router = Router::with_path("/{room_id}/deactivate").post(deactivate_room);
 
     Router::with_hoop(jwt_utils.auth_middleware())
@@ -45,10 +79,19 @@ pub fn get_room_router(jwt_utils: JwtUtils) -> Router {
                 .delete(leave_room),
         )
         .push(member_router)
+        .push(participant_router)
         .push(join_router)
+        .push(join_token_router)
         .push(deactivate_router)
 }
 
+/// Served without a user token so landing pages can show it to visitors
+pub fn get_room_viewers_router() -> Router {
+    Router::with_path("rooms/{room_id}/viewers")
+        .hoop(listing_rate_limiter())
+        .get(get_viewer_count)
+}
+
 /// Retrieves room details using a unique room code.
 #[endpoint(tags("room"), status_codes(200, 400, 401, 403, 404, 500))]
 async fn get_room_by_code(
@@ -71,18 +114,20 @@ async fn get_room_by_code(
 #[endpoint(tags("room"), status_codes(200, 400, 401, 403, 404, 500))]
 async fn leave_room(
     _res: &mut Response,
-    room_id: PathParam<i32>,
+    room_id: PathParam<String>,
     depot: &mut Depot,
 ) -> Result<RoomResponse, RoomError> {
     let room_service = depot
         .obtain::<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>()
         .unwrap();
     let user_id = depot.get::<String>("user_id").unwrap();
-    let room_id = room_id.into_inner();
+    let room_id = room_service.resolve_room_id(&room_id.into_inner()).await?;
 
-    let room = room_service
-        .leave_room(room_id, user_id.parse().unwrap())
-        .await?;
+    let user_id = user_id.parse().unwrap();
+
+    let room = room_service.leave_room(room_id, user_id).await?;
+
+    relay_matrix_leave(depot, room_id, user_id);
 
     Ok(room)
 }
@@ -157,7 +202,7 @@ async fn create_room(
 #[endpoint(tags("room"), status_codes(200, 400, 401, 403, 404, 500))]
 async fn update_room(
     _res: &mut Response,
-    room_id: PathParam<i32>,
+    room_id: PathParam<String>,
     data: JsonBody<UpdateRoomDto>,
     depot: &mut Depot,
 ) -> Result<RoomResponse, RoomError> {
@@ -167,7 +212,7 @@ async fn update_room(
     let user_id = depot.get::<String>("user_id").unwrap();
 
     let update_room_dto = data.0;
-    let room_id = room_id.into_inner();
+    let room_id = room_service.resolve_room_id(&room_id.into_inner()).await?;
 
     let room = room_service
         .update_room(update_room_dto, room_id, user_id.parse().unwrap())
@@ -180,7 +225,7 @@ async fn update_room(
 #[endpoint(tags("room"), status_codes(200, 400, 401, 403, 404, 500))]
 async fn add_member(
     _res: &mut Response,
-    room_id: PathParam<i32>,
+    room_id: PathParam<String>,
     data: JsonBody<AddMemberDto>,
     depot: &mut Depot,
 ) -> Result<RoomResponse, RoomError> {
@@ -189,8 +234,10 @@ async fn add_member(
         .unwrap();
     let host_id = depot.get::<String>("user_id").unwrap();
 
-    let user_id = data.into_inner().user_id;
-    let room_id = room_id.into_inner();
+    let room_id = room_service.resolve_room_id(&room_id.into_inner()).await?;
+    let user_id = room_service
+        .resolve_user_id(&data.into_inner().user_id)
+        .await?;
 
     let room = room_service
         .add_member(room_id, host_id.parse().unwrap(), user_id)
@@ -203,7 +250,7 @@ async fn add_member(
 #[endpoint(tags("room"), status_codes(200, 400, 401, 403, 404, 500))]
 async fn delete_member(
     _res: &mut Response,
-    room_id: PathParam<i32>,
+    room_id: PathParam<String>,
     data: JsonBody<AddMemberDto>,
     depot: &mut Depot,
 ) -> Result<RoomResponse, RoomError> {
@@ -212,21 +259,82 @@ async fn delete_member(
         .unwrap();
     let host_id = depot.get::<String>("user_id").unwrap();
 
-    let user_id = data.into_inner().user_id;
-    let room_id = room_id.into_inner();
+    let room_id = room_service.resolve_room_id(&room_id.into_inner()).await?;
+    let user_id = room_service
+        .resolve_user_id(&data.into_inner().user_id)
+        .await?;
 
     let room = room_service
         .remove_member(room_id, host_id.parse().unwrap(), user_id)
         .await?;
 
+    // Whatever they were watching over HLS stops at the next playlist refetch
+    let hls_session_store = depot.obtain::<HlsSessionStore>().unwrap();
+    if let Err(err) = hls_session_store
+        .revoke(&room.room.public_id, &user_id.to_string())
+        .await
+    {
+        warn!(
+            "Failed to revoke HLS session of user {} in room {}: {:?}",
+            user_id, room_id, err
+        );
+    }
+
+    relay_matrix_leave(depot, room_id, user_id);
+
     Ok(room)
 }
 
-/// Joins a room that will be requires a password (for Guess) and not if you're a member
-#[endpoint(tags("room"), status_codes(200, 400, 401, 403, 404, 500))]
+/// Lists members of a room page by page, optionally filtered by name.
+#[endpoint(tags("room"), status_codes(200, 400, 401, 403, 404, 429, 500))]
+async fn get_members(
+    _res: &mut Response,
+    room_id: PathParam<String>,
+    query: QueryParam<String, false>,
+    pagination_dto: PaginationDto,
+    depot: &mut Depot,
+) -> Result<ListMemberResponse, RoomError> {
+    let room_service = depot
+        .obtain::<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>()
+        .unwrap();
+
+    let room_id = room_service.resolve_room_id(&room_id.into_inner()).await?;
+
+    let members = room_service
+        .get_members(room_id, query.into_inner(), pagination_dto)
+        .await?;
+
+    Ok(ListMemberResponse { members })
+}
+
+/// Lists participants currently in a room page by page, optionally filtered by name.
+#[endpoint(tags("room"), status_codes(200, 400, 401, 403, 404, 429, 500))]
+async fn get_participants(
+    _res: &mut Response,
+    room_id: PathParam<String>,
+    query: QueryParam<String, false>,
+    pagination_dto: PaginationDto,
+    depot: &mut Depot,
+) -> Result<ListParticipantResponse, RoomError> {
+    let room_service = depot
+        .obtain::<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>()
+        .unwrap();
+
+    let room_id = room_service.resolve_room_id(&room_id.into_inner()).await?;
+
+    let participants = room_service
+        .get_participants(room_id, query.into_inner(), pagination_dto)
+        .await?;
+
+    Ok(ListParticipantResponse { participants })
+}
+
+/// Joins a room that will be requires a password (for Guess) and not if you're a member.
+/// When the call is at capacity the user is admitted as an HLS viewer instead, see `overflow`.
+#[endpoint(tags("room"), status_codes(200, 400, 401, 403, 404, 409, 500))]
 async fn join_room(
     _res: &mut Response,
-    room_id: PathParam<i32>,
+    room_id: PathParam<String>,
     data: JsonBody<JoinRoomDto>,
     depot: &mut Depot,
 ) -> Result<RoomResponse, RoomError> {
@@ -235,14 +343,196 @@ async fn join_room(
         .unwrap();
     let user_id = depot.get::<String>("user_id").unwrap();
 
-    let room_id = room_id.into_inner();
+    let room_id = room_service.resolve_room_id(&room_id.into_inner()).await?;
 
     let password = data.into_inner().password;
 
-    let room = room_service
-        .join_room(user_id.parse().unwrap(), room_id, password.as_deref())
+    let started_at = Instant::now();
+
+    let user_id = user_id.parse().unwrap();
+    let overflow_queue = depot.obtain::<OverflowQueue>().unwrap();
+    let hls_session_store = depot.obtain::<HlsSessionStore>().unwrap();
+
+    let mut room = match room_service
+        .join_room(user_id, room_id, password.as_deref())
+        .await
+    {
+        Ok((room, _)) => {
+            _leave_overflow(overflow_queue, hls_session_store, &room, user_id).await;
+            room
+        }
+        Err(RoomError::RoomFull) => {
+            let jwt_utils = depot.obtain::<JwtUtils>().unwrap();
+            _admit_as_viewer(
+                room_service,
+                jwt_utils,
+                overflow_queue,
+                hls_session_store,
+                room_id,
+                user_id,
+            )
+            .await?
+        }
+        Err(err) => return Err(err),
+    };
+
+    // Clients fall back to their own theme, not worth failing the join over
+    let org_service = depot.obtain::<OrgServiceImpl<OrgRepositoryImpl>>().unwrap();
+    match org_service.get_room_branding(room_id).await {
+        Ok(branding) => room.branding = branding,
+        Err(err) => warn!("Failed to load branding of room {}: {:?}", room_id, err),
+    }
+
+    let join_metrics = depot.obtain::<JoinMetrics>().unwrap();
+    join_metrics.record(started_at.elapsed());
+
+    Ok(room)
+}
+
+/// Admits the user to the call like `join`, and hands back the token the
+/// socket `room.publish` event has to present. The membership, password and
+/// capacity checks happen here, the socket takes the room and participant
+/// from the token. A full call answers 409, `join` lines the user up to watch.
+#[endpoint(tags("room"), status_codes(201, 400, 401, 403, 404, 409, 500))]
+async fn create_join_token(
+    _res: &mut Response,
+    room_id: PathParam<String>,
+    data: JsonBody<JoinRoomDto>,
+    depot: &mut Depot,
+) -> Result<JoinTokenResponse, RoomError> {
+    let room_service = depot
+        .obtain::<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>()
+        .unwrap();
+    let jwt_utils = depot.obtain::<JwtUtils>().unwrap();
+    let overflow_queue = depot.obtain::<OverflowQueue>().unwrap();
+    let hls_session_store = depot.obtain::<HlsSessionStore>().unwrap();
+    let user_id = depot.get::<String>("user_id").unwrap();
+
+    let room_id = room_service.resolve_room_id(&room_id.into_inner()).await?;
+    let user_id: i32 = user_id.parse().unwrap();
+
+    let password = data.into_inner().password;
+
+    let (room, participant) = room_service
+        .join_room(user_id, room_id, password.as_deref())
         .await?;
 
+    _leave_overflow(overflow_queue, hls_session_store, &room, user_id).await;
+
+    let participant_id = participant.participant.public_id;
+
+    let is_host = room.members.iter().any(|member| {
+        member.member.user_id == user_id && member.member.role == MembersRoleEnum::Owner as i16
+    });
+
+    let (token, expires_at) = jwt_utils.generate_join_token(
+        &room.room.public_id,
+        &participant_id,
+        &user_id.to_string(),
+        is_host,
+    );
+
+    Ok(JoinTokenResponse {
+        token,
+        room_id: room.room.public_id,
+        participant_id,
+        expires_at,
+    })
+}
+
+/// A viewer taking a freed slot leaves the line, and stops watching
+async fn _leave_overflow(
+    overflow_queue: &OverflowQueue,
+    hls_session_store: &HlsSessionStore,
+    room: &RoomResponse,
+    user_id: i32,
+) {
+    if room.room.capacity.is_none() {
+        return;
+    }
+
+    let public_room_id = &room.room.public_id;
+
+    if let Err(err) = overflow_queue.remove(public_room_id, user_id).await {
+        warn!(
+            "Failed to dequeue viewer {} of room {}: {:?}",
+            user_id, public_room_id, err
+        );
+    }
+    if let Err(err) = hls_session_store
+        .close(public_room_id, &user_id.to_string())
+        .await
+    {
+        warn!(
+            "Failed to close HLS session of user {} in room {}: {:?}",
+            user_id, public_room_id, err
+        );
+    }
+}
+
+/// Lines the user up for the next free slot and hands them the participants'
+/// HLS streams to watch meanwhile
+async fn _admit_as_viewer(
+    room_service: &RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+    jwt_utils: &JwtUtils,
+    overflow_queue: &OverflowQueue,
+    hls_session_store: &HlsSessionStore,
+    room_id: i32,
+    user_id: i32,
+) -> Result<RoomResponse, RoomError> {
+    let mut room = room_service.get_room_by_id(room_id).await?;
+    let public_room_id = room.room.public_id.clone();
+
+    let viewer = overflow_queue
+        .enqueue(&public_room_id, user_id)
+        .await
+        .map_err(|err| {
+            warn!(
+                "Failed to queue viewer {} of room {}: {:?}",
+                user_id, room_id, err
+            );
+            RoomError::RoomFull
+        })?;
+
+    let streams = room
+        .participants
+        .iter()
+        .filter(|p| {
+            p.participant.node_id.is_some()
+                && p.participant.status == ParticipantsStatusEnum::Active as i16
+        })
+        .map(|p| HlsStreamResponse {
+            url: jwt_utils.sign_hls_url(
+                &public_room_id,
+                &viewer.viewer_id,
+                &p.participant.public_id,
+            ),
+            participant_id: p.participant.public_id.clone(),
+        })
+        .collect();
+
+    // Not worth turning the viewer away over, the URLs work until they expire
+    let expires_at = jwt_utils.hls_expires_at();
+    let session = HlsSession {
+        room_id: public_room_id,
+        user_id: user_id.to_string(),
+        viewer_id: viewer.viewer_id.clone(),
+    };
+    if let Err(err) = hls_session_store.open(&session, expires_at).await {
+        warn!(
+            "Failed to open HLS session of viewer {} in room {}: {:?}",
+            user_id, room_id, err
+        );
+    }
+
+    room.overflow = Some(OverflowResponse {
+        viewer_id: viewer.viewer_id,
+        position: viewer.position,
+        streams,
+        expires_at,
+        interactions: vec![OverflowInteraction::Chat, OverflowInteraction::Reactions],
+    });
+
     Ok(room)
 }
 
@@ -250,7 +540,7 @@ async fn join_room(
 #[endpoint(tags("room"), status_codes(200, 400, 401, 403, 404, 500))]
 async fn deactivate_room(
     _res: &mut Response,
-    room_id: PathParam<i32>,
+    room_id: PathParam<String>,
     depot: &mut Depot,
 ) -> Result<RoomResponse, RoomError> {
     let room_service = depot
@@ -258,7 +548,7 @@ async fn deactivate_room(
         .unwrap();
     let user_id = depot.get::<String>("user_id").unwrap();
 
-    let room_id = room_id.into_inner();
+    let room_id = room_service.resolve_room_id(&room_id.into_inner()).await?;
 
     let room = room_service
         .deactivate_room(room_id, user_id.parse().unwrap())
@@ -266,3 +556,60 @@ async fn deactivate_room(
 
     Ok(room)
 }
+
+/// Returns how many people are watching a room, over WebRTC and HLS combined.
+#[endpoint(tags("room"), status_codes(200, 404, 429, 500))]
+async fn get_viewer_count(
+    _res: &mut Response,
+    room_id: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<ViewerCountResponse, RoomError> {
+    let room_service = depot
+        .obtain::<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>()
+        .unwrap();
+    let viewer_count_store = depot.obtain::<ViewerCountStore>().unwrap();
+
+    let room_id = room_id.into_inner();
+
+    if let Some(count) = viewer_count_store.get_cached(&room_id).await {
+        return Ok(count);
+    }
+
+    let hls_viewers = viewer_count_store
+        .hls_viewers(&room_id)
+        .await
+        .unwrap_or_else(|err| {
+            warn!("Failed to count HLS viewers of {}: {:?}", room_id, err);
+            0
+        });
+
+    let count = room_service.get_viewer_count(&room_id, hls_viewers).await?;
+
+    viewer_count_store.set_cached(&count).await;
+
+    Ok(count)
+}
+
+/// Take the user's puppet out of the bridged Matrix room, if the room is bridged
+fn relay_matrix_leave(depot: &Depot, room_id: i32, user_id: i32) {
+    if let Ok(matrix_service) = depot.obtain::<MatrixBridge>() {
+        let matrix_service = matrix_service.clone();
+
+        tokio::spawn(async move {
+            if let Err(err) = matrix_service.relay_leave(room_id, user_id).await {
+                warn!("Failed to relay leave to Matrix: {:?}", err);
+            }
+        });
+    }
+}
+
+/// Listing large rooms is expensive, so these endpoints get a tighter quota
+/// than the global limiter
+fn listing_rate_limiter() -> impl Handler {
+    RateLimiter::new(
+        FixedGuard::new(),
+        MokaStore::new(),
+        RemoteIpIssuer,
+        BasicQuota::per_second(20),
+    )
+}
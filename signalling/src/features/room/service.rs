@@ -1,19 +1,28 @@
+use crate::core::database::soft_delete::DeletedScope;
 use crate::core::dtos::common::pagination_dto::PaginationDto;
 use crate::core::dtos::room::create_room_dto::CreateRoomDto;
 use crate::core::dtos::room::update_room_dto::UpdateRoomDto;
+use crate::core::dtos::socket::socket_dto::ClientMetadataDto;
 use crate::core::entities::models::{
-    MembersRoleEnum, NewMember, NewParticipant, NewRoom, ParticipantsStatusEnum, RoomStatusEnum,
-    RoomType,
+    MembersRoleEnum, NewMember, NewParticipant, NewRoom, Participant, ParticipantsStatusEnum, Room,
+    RoomStatusEnum, RoomType, StreamingProtocol,
 };
 use crate::core::types::errors::room_error::RoomError;
-use crate::core::types::responses::room_response::{ParticipantResponse, RoomResponse};
+use crate::core::types::responses::room_response::{
+    MemberResponse, ParticipantResponse, RoomResponse,
+};
+use crate::core::types::responses::viewer_count_response::ViewerCountResponse;
 use crate::core::utils::bcrypt_utils::{hash_password, verify_password};
 use crate::core::utils::id_utils::generate_room_code;
-use crate::features::room::repository::RoomRepository;
+use crate::core::utils::room_state_cache::RoomStateCache;
+use crate::features::room::repository::{MediaChange, RoomRepository};
 use crate::features::user::repository::UserRepository;
-use chrono::Utc;
+use chrono::{NaiveDateTime, Utc};
 use salvo::async_trait;
 
+/// Matches the SFU's cap, a minute of media held back per publisher
+pub const MAX_BROADCAST_DELAY_SECS: i32 = 60;
+
 #[async_trait]
 pub trait RoomService {
     async fn create_room(
@@ -40,14 +49,42 @@ pub trait RoomService {
 
     async fn get_room_by_code(&self, room_code: &str) -> Result<RoomResponse, RoomError>;
 
+    /// Map the room id clients see to the internal key
+    async fn resolve_room_id(&self, public_id: &str) -> Result<i32, RoomError>;
+
+    /// Uncached lookup for admin tooling, which may need to see deleted rooms
+    async fn get_room_by_public_id(
+        &self,
+        public_id: &str,
+        scope: DeletedScope,
+    ) -> Result<RoomResponse, RoomError>;
+
+    /// Map the user id clients see to the internal key
+    async fn resolve_user_id(&self, public_id: &str) -> Result<i32, RoomError>;
+
+    async fn get_members(
+        &self,
+        room_id: i32,
+        query: Option<String>,
+        pagination_dto: PaginationDto,
+    ) -> Result<Vec<MemberResponse>, RoomError>;
+
+    async fn get_participants(
+        &self,
+        room_id: i32,
+        query: Option<String>,
+        pagination_dto: PaginationDto,
+    ) -> Result<Vec<ParticipantResponse>, RoomError>;
+
     async fn leave_room(&self, room_id: i32, user_id: i32) -> Result<RoomResponse, RoomError>;
 
+    /// Returns the room along with the participant the user joined as
     async fn join_room(
         &self,
         user_id: i32,
         room_id: i32,
         password: Option<&str>,
-    ) -> Result<RoomResponse, RoomError>;
+    ) -> Result<(RoomResponse, ParticipantResponse), RoomError>;
 
     async fn add_member(
         &self,
@@ -63,18 +100,68 @@ pub trait RoomService {
         user_id: i32,
     ) -> Result<RoomResponse, RoomError>;
 
+    /// Add a user as attendee without a host or password check, for trusted
+    /// integrations like the Matrix bridge. Existing members are left untouched.
+    async fn ensure_member(&self, room_id: i32, user_id: i32) -> Result<RoomResponse, RoomError>;
+
     async fn deactivate_room(&self, room_id: i32, user_id: i32) -> Result<RoomResponse, RoomError>;
 
+    /// Deactivates the room on an operator's behalf, no host needed
+    async fn close_room(&self, room_id: i32) -> Result<RoomResponse, RoomError>;
+
+    /// Host only, turns a running conference into a live stream over HLS or
+    /// back. P2P rooms keep their type, their peers aren't on the SFU.
+    async fn switch_room_type(
+        &self,
+        room_id: i32,
+        user_id: i32,
+        room_type: RoomType,
+    ) -> Result<RoomResponse, RoomError>;
+
+    /// Participants are looked up by the id clients and the SFU know them by
+    async fn get_participant(&self, participant_id: &str)
+    -> Result<ParticipantResponse, RoomError>;
+
+    /// Records the node the participant's media landed on and what the client
+    /// declared about itself when it connected
     async fn update_participant(
         &self,
-        participant_id: i32,
+        participant_id: &str,
         node_id: &str,
+        client: &ClientMetadataDto,
     ) -> Result<ParticipantResponse, RoomError>;
 
-    async fn delete_participant(&self, participant_id: i32) -> Result<(), RoomError>;
+    async fn delete_participant(&self, participant_id: &str) -> Result<(), RoomError>;
 
     async fn delete_participants_by_node(&self, node_id: &str) -> Result<(), RoomError>;
 
+    /// Keep participants of a dead node in the room while they rejoin on another node
+    async fn detach_participants_by_node(&self, node_id: &str) -> Result<(), RoomError>;
+
+    /// Keeps what the participant switched on, for a failover to restore
+    async fn update_participant_media(
+        &self,
+        participant_id: &str,
+        change: MediaChange,
+    ) -> Result<(), RoomError>;
+
+    /// Drops participants of a dead node that didn't rejoin in time, returns
+    /// them with their rooms so the others can be told they left
+    async fn purge_detached_participants(
+        &self,
+        detached_before: NaiveDateTime,
+    ) -> Result<Vec<(Room, Participant)>, RoomError>;
+
+    async fn check_host(&self, room_id: i32, user_id: i32) -> Result<(), RoomError>;
+
+    /// Combine the participants connected over WebRTC with the HLS viewers
+    /// counted elsewhere, the HLS side isn't known to the database
+    async fn get_viewer_count(
+        &self,
+        public_id: &str,
+        hls_viewers: i64,
+    ) -> Result<ViewerCountResponse, RoomError>;
+
     async fn generate_unique_room_code(&self, max_attempts: usize) -> Result<String, RoomError>;
 }
 
@@ -82,6 +169,7 @@ pub trait RoomService {
 pub struct RoomServiceImpl<R: RoomRepository, U: UserRepository> {
     room_repository: R,
     user_repository: U,
+    room_state_cache: Option<RoomStateCache>,
 }
 
 impl<R: RoomRepository, U: UserRepository> RoomServiceImpl<R, U> {
@@ -89,6 +177,44 @@ impl<R: RoomRepository, U: UserRepository> RoomServiceImpl<R, U> {
         Self {
             room_repository,
             user_repository,
+            room_state_cache: None,
+        }
+    }
+
+    pub fn with_cache(mut self, room_state_cache: RoomStateCache) -> Self {
+        self.room_state_cache = Some(room_state_cache);
+        self
+    }
+
+    async fn _get_room(&self, room_id: i32) -> Result<RoomResponse, RoomError> {
+        if let Some(cache) = &self.room_state_cache
+            && let Some(room) = cache.get(room_id).await
+        {
+            return Ok(room);
+        }
+
+        let room = self
+            .room_repository
+            .get_room_by_id(room_id, DeletedScope::Exclude)
+            .await?;
+
+        if let Some(cache) = &self.room_state_cache {
+            cache.set(&room).await;
+        }
+
+        Ok(room)
+    }
+
+    /// Rooms written back have to come from Postgres, cached ones have no password
+    async fn _get_room_for_update(&self, room_id: i32) -> Result<RoomResponse, RoomError> {
+        self.room_repository
+            .get_room_by_id(room_id, DeletedScope::Exclude)
+            .await
+    }
+
+    async fn _invalidate_room(&self, room_id: i32) {
+        if let Some(cache) = &self.room_state_cache {
+            cache.invalidate(room_id).await;
         }
     }
 }
@@ -106,7 +232,7 @@ impl<R: RoomRepository + Send + Sync, U: UserRepository + Send + Sync> RoomServi
             .user_repository
             .get_user_by_id(user_id)
             .await
-            .map_err(|_| RoomError::UnexpectedError("User not found".into()))?;
+            .map_err(|_| RoomError::UserNotFound(user_id.to_string()))?;
 
         let (password_hashed, code) = tokio::try_join!(
             {
@@ -135,7 +261,9 @@ impl<R: RoomRepository + Send + Sync, U: UserRepository + Send + Sync> RoomServi
             created_at: now,
             updated_at: now,
             latest_message_created_at: now,
-            type_: RoomType::Conferencing.into(),
+            type_: data.room_type.into(),
+            streaming_protocol: data.streaming_protocol.into(),
+            capacity: data.capacity.filter(|capacity| *capacity > 0),
         };
 
         self.room_repository
@@ -150,7 +278,10 @@ impl<R: RoomRepository + Send + Sync, U: UserRepository + Send + Sync> RoomServi
         user_id: i32,
     ) -> Result<RoomResponse, RoomError> {
         let update_room_dto = data.clone();
-        let room = self.room_repository.get_room_by_id(room_id).await?;
+        let room = self
+            .room_repository
+            .get_room_by_id(room_id, DeletedScope::Exclude)
+            .await?;
 
         // Check whether user_id is host or not
         let is_host = room.members.iter().any(|member| {
@@ -162,6 +293,7 @@ impl<R: RoomRepository + Send + Sync, U: UserRepository + Send + Sync> RoomServi
         }
 
         // Update new room metadata
+        let members = room.members;
         let mut room = room.room;
 
         if let Some(title) = update_room_dto.title {
@@ -177,8 +309,38 @@ impl<R: RoomRepository + Send + Sync, U: UserRepository + Send + Sync> RoomServi
             room.avatar = Some(avatar);
         }
 
+        if let Some(days) = update_room_dto.message_retention_days {
+            room.message_retention_days = (days > 0).then_some(days);
+        }
+
+        if let Some(capacity) = update_room_dto.capacity {
+            room.capacity = (capacity > 0).then_some(capacity);
+        }
+
+        if let Some(noise_suppression) = update_room_dto.noise_suppression {
+            room.noise_suppression = noise_suppression;
+        }
+
+        if let Some(secs) = update_room_dto.broadcast_delay_secs {
+            room.broadcast_delay_secs = secs.clamp(0, MAX_BROADCAST_DELAY_SECS);
+        }
+
+        if let Some(mut member_ids) = update_room_dto.presenter_member_ids {
+            let is_member =
+                |member_id: &i32| members.iter().any(|member| member.member.id == *member_id);
+            if !member_ids.iter().all(is_member) {
+                return Err(RoomError::MemberNotFound);
+            }
+
+            member_ids.sort_unstable();
+            member_ids.dedup();
+            room.presenter_member_ids = member_ids;
+        }
+
         let updated_room = self.room_repository.update_room(room).await?;
 
+        self._invalidate_room(room_id).await;
+
         Ok(updated_room)
     }
 
@@ -205,7 +367,7 @@ impl<R: RoomRepository + Send + Sync, U: UserRepository + Send + Sync> RoomServi
     }
 
     async fn get_room_by_id(&self, room_id: i32) -> Result<RoomResponse, RoomError> {
-        let room = self.room_repository.get_room_by_id(room_id).await?;
+        let room = self._get_room(room_id).await?;
 
         Ok(room)
     }
@@ -216,23 +378,100 @@ impl<R: RoomRepository + Send + Sync, U: UserRepository + Send + Sync> RoomServi
         Ok(room)
     }
 
+    async fn resolve_room_id(&self, public_id: &str) -> Result<i32, RoomError> {
+        self.room_repository
+            .get_room_id_by_public_id(public_id, DeletedScope::Exclude)
+            .await
+    }
+
+    async fn get_room_by_public_id(
+        &self,
+        public_id: &str,
+        scope: DeletedScope,
+    ) -> Result<RoomResponse, RoomError> {
+        let room_id = self
+            .room_repository
+            .get_room_id_by_public_id(public_id, scope)
+            .await?;
+
+        self.room_repository.get_room_by_id(room_id, scope).await
+    }
+
+    async fn resolve_user_id(&self, public_id: &str) -> Result<i32, RoomError> {
+        let user = self
+            .user_repository
+            .get_user_by_public_id(public_id, DeletedScope::Exclude)
+            .await
+            .map_err(|_| RoomError::UserNotFound(public_id.to_string()))?;
+
+        Ok(user.id)
+    }
+
+    async fn get_members(
+        &self,
+        room_id: i32,
+        query: Option<String>,
+        pagination_dto: PaginationDto,
+    ) -> Result<Vec<MemberResponse>, RoomError> {
+        if !pagination_dto.is_valid() {
+            return Err(RoomError::InvalidPagination);
+        }
+
+        let members = self
+            .room_repository
+            .find_members(
+                room_id,
+                query,
+                pagination_dto.skip,
+                pagination_dto.page_limit(),
+            )
+            .await?;
+
+        Ok(members)
+    }
+
+    async fn get_participants(
+        &self,
+        room_id: i32,
+        query: Option<String>,
+        pagination_dto: PaginationDto,
+    ) -> Result<Vec<ParticipantResponse>, RoomError> {
+        if !pagination_dto.is_valid() {
+            return Err(RoomError::InvalidPagination);
+        }
+
+        let participants = self
+            .room_repository
+            .find_participants(
+                room_id,
+                query,
+                pagination_dto.skip,
+                pagination_dto.page_limit(),
+            )
+            .await?;
+
+        Ok(participants)
+    }
+
     async fn leave_room(&self, room_id: i32, user_id: i32) -> Result<RoomResponse, RoomError> {
-        let mut room = self.room_repository.get_room_by_id(room_id).await?;
+        let mut room = self._get_room(room_id).await?;
 
         let index_of_member = room
             .members
             .iter()
             .position(|member| member.member.user_id == user_id)
-            .ok_or_else(|| RoomError::UnexpectedError("Member not found".into()))?;
+            .ok_or(RoomError::MemberNotFound)?;
 
         let member = room.members[index_of_member].member.clone();
 
         if member.role == MembersRoleEnum::Owner as i16 {
-            return Err(RoomError::UnexpectedError("Host not allowed to leave the room. You can archive chats if the room no longer active.".into()));
+            return Err(RoomError::OwnerCannotLeaveRoom);
         }
 
         self.room_repository.delete_member_by_id(member.id).await?;
 
+        self._invalidate_room(room_id).await;
+
         room.members
             .retain(|member| member.member.user_id != user_id);
 
@@ -244,14 +483,14 @@ impl<R: RoomRepository + Send + Sync, U: UserRepository + Send + Sync> RoomServi
         user_id: i32,
         room_id: i32,
         password: Option<&str>,
-    ) -> Result<RoomResponse, RoomError> {
+    ) -> Result<(RoomResponse, ParticipantResponse), RoomError> {
         let _ = self
             .user_repository
             .get_user_by_id(user_id)
             .await
-            .map_err(|_| RoomError::UnexpectedError("User not found".into()))?;
+            .map_err(|_| RoomError::UserNotFound(user_id.to_string()))?;
 
-        let mut room = self.room_repository.get_room_by_id(room_id).await?;
+        let mut room = self._get_room(room_id).await?;
 
         let is_member = room
             .members
@@ -259,7 +498,8 @@ impl<R: RoomRepository + Send + Sync, U: UserRepository + Send + Sync> RoomServi
             .any(|member| member.member.user_id == user_id);
 
         if !is_member {
-            let is_password_correct = match room.room.password.as_ref() {
+            let password_hashed = self.room_repository.get_room_password(room_id).await?;
+            let is_password_correct = match password_hashed.as_ref() {
                 Some(hash_password) => match password {
                     Some(pw) => verify_password(pw, hash_password),
                     None => false,
@@ -272,21 +512,45 @@ impl<R: RoomRepository + Send + Sync, U: UserRepository + Send + Sync> RoomServi
             }
         }
 
-        let now = Utc::now().naive_utc();
-        let participant = NewParticipant {
-            user_id: Some(user_id),
-            room_id: &room.room.id,
-            status: ParticipantsStatusEnum::Active.into(),
-            created_at: now,
+        if is_room_full(&room, user_id) {
+            return Err(RoomError::RoomFull);
+        }
+
+        // Someone whose node went away comes back as the participant they were
+        let detached = room.participants.iter().find(|p| {
+            p.participant.user_id == user_id
+                && p.participant.node_id.is_none()
+                && p.participant.status == ParticipantsStatusEnum::Inactive as i16
+        });
+
+        let participant = match detached {
+            Some(detached) => {
+                let mut participant = detached.participant.clone();
+                participant.status = ParticipantsStatusEnum::Active.into();
+                participant.detached_at = None;
+
+                self.room_repository.update_participant(participant).await?
+            }
+            None => {
+                let now = Utc::now().naive_utc();
+                let participant = NewParticipant {
+                    user_id: Some(user_id),
+                    room_id: &room.room.id,
+                    status: ParticipantsStatusEnum::Active.into(),
+                    created_at: now,
+                };
+
+                self.room_repository.create_participant(participant).await?
+            }
         };
 
-        let participant = self.room_repository.create_participant(participant).await?;
+        self._invalidate_room(room_id).await;
 
         room.participants
             .retain(|p| p.participant.node_id.is_some());
-        room.participants.push(participant);
+        room.participants.push(participant.clone());
 
-        Ok(room)
+        Ok((room, participant))
     }
 
     async fn add_member(
@@ -295,7 +559,7 @@ impl<R: RoomRepository + Send + Sync, U: UserRepository + Send + Sync> RoomServi
         host_id: i32,
         user_id: i32,
     ) -> Result<RoomResponse, RoomError> {
-        let mut room = self.room_repository.get_room_by_id(room_id).await?;
+        let mut room = self._get_room(room_id).await?;
 
         let is_member = room
             .members
@@ -303,9 +567,7 @@ impl<R: RoomRepository + Send + Sync, U: UserRepository + Send + Sync> RoomServi
             .any(|member| member.member.user_id == user_id);
 
         if is_member {
-            return Err(RoomError::UnexpectedError(
-                "User already in the room".to_string(),
-            ));
+            return Err(RoomError::AlreadyMember(user_id));
         }
 
         let is_host = room.members.iter().any(|member| {
@@ -320,7 +582,7 @@ impl<R: RoomRepository + Send + Sync, U: UserRepository + Send + Sync> RoomServi
             .user_repository
             .get_user_by_id(user_id)
             .await
-            .map_err(|_| RoomError::UnexpectedError("User not found".to_string()));
+            .map_err(|_| RoomError::UserNotFound(user_id.to_string()));
 
         let now = Utc::now().naive_utc();
 
@@ -333,6 +595,8 @@ impl<R: RoomRepository + Send + Sync, U: UserRepository + Send + Sync> RoomServi
 
         let new_member = self.room_repository.create_member(new_member).await?;
 
+        self._invalidate_room(room_id).await;
+
         room.members.push(new_member);
 
         Ok(room)
@@ -344,13 +608,13 @@ impl<R: RoomRepository + Send + Sync, U: UserRepository + Send + Sync> RoomServi
         host_id: i32,
         user_id: i32,
     ) -> Result<RoomResponse, RoomError> {
-        let mut room = self.room_repository.get_room_by_id(room_id).await?;
+        let mut room = self._get_room(room_id).await?;
 
         let index_of_member = room
             .members
             .iter()
             .position(|member| member.member.user_id == user_id)
-            .ok_or_else(|| RoomError::UnexpectedError("Member not found".into()))?;
+            .ok_or(RoomError::MemberNotFound)?;
 
         let is_host = room.members.iter().any(|member| {
             member.member.user_id == host_id && member.member.role == MembersRoleEnum::Owner as i16
@@ -364,20 +628,52 @@ impl<R: RoomRepository + Send + Sync, U: UserRepository + Send + Sync> RoomServi
 
         self.room_repository.delete_member_by_id(member_id).await?;
 
+        self._invalidate_room(room_id).await;
+
         room.members
             .retain(|member| member.member.user_id != user_id);
 
         Ok(room)
     }
 
+    async fn ensure_member(&self, room_id: i32, user_id: i32) -> Result<RoomResponse, RoomError> {
+        let mut room = self._get_room(room_id).await?;
+
+        let is_member = room
+            .members
+            .iter()
+            .any(|member| member.member.user_id == user_id);
+
+        if is_member {
+            return Ok(room);
+        }
+
+        let now = Utc::now().naive_utc();
+
+        let new_member = NewMember {
+            user_id: Some(user_id),
+            room_id: &room.room.id,
+            created_at: now,
+            role: MembersRoleEnum::Attendee.into(),
+        };
+
+        let new_member = self.room_repository.create_member(new_member).await?;
+
+        self._invalidate_room(room_id).await;
+
+        room.members.push(new_member);
+
+        Ok(room)
+    }
+
     async fn deactivate_room(&self, room_id: i32, user_id: i32) -> Result<RoomResponse, RoomError> {
-        let room = self.room_repository.get_room_by_id(room_id).await?;
+        let room = self._get_room_for_update(room_id).await?;
 
         let index_of_member = room
             .members
             .iter()
             .position(|member| member.member.user_id == user_id)
-            .ok_or_else(|| RoomError::UnexpectedError("Member not found".into()))?;
+            .ok_or(RoomError::MemberNotFound)?;
 
         let member = room.members[index_of_member].member.clone();
 
@@ -391,46 +687,212 @@ impl<R: RoomRepository + Send + Sync, U: UserRepository + Send + Sync> RoomServi
 
         let room = self.room_repository.update_room(room).await?;
 
+        self._invalidate_room(room_id).await;
+
+        Ok(room)
+    }
+
+    async fn close_room(&self, room_id: i32) -> Result<RoomResponse, RoomError> {
+        let mut room = self._get_room_for_update(room_id).await?.room;
+
+        room.status = RoomStatusEnum::Inactive as i16;
+
+        let room = self.room_repository.update_room(room).await?;
+
+        self._invalidate_room(room_id).await;
+
+        Ok(room)
+    }
+
+    async fn switch_room_type(
+        &self,
+        room_id: i32,
+        user_id: i32,
+        room_type: RoomType,
+    ) -> Result<RoomResponse, RoomError> {
+        let room = self._get_room_for_update(room_id).await?;
+
+        let is_host = room.members.iter().any(|member| {
+            member.member.user_id == user_id && member.member.role == MembersRoleEnum::Owner as i16
+        });
+
+        if !is_host {
+            return Err(RoomError::YouDontHavePermissions);
+        }
+
+        if room_type == RoomType::P2P || RoomType::from(room.room.type_) == RoomType::P2P {
+            return Err(RoomError::RoomTypeFixed);
+        }
+
+        let mut room = room.room;
+
+        room.type_ = room_type.into();
+        room.streaming_protocol = match room_type {
+            RoomType::LiveStreaming => StreamingProtocol::HLS,
+            _ => StreamingProtocol::SFU,
+        }
+        .into();
+
+        let room = self.room_repository.update_room(room).await?;
+
+        self._invalidate_room(room_id).await;
+
         Ok(room)
     }
 
+    async fn get_participant(
+        &self,
+        participant_id: &str,
+    ) -> Result<ParticipantResponse, RoomError> {
+        self.room_repository
+            .get_participant_by_public_id(participant_id)
+            .await
+    }
+
     async fn update_participant(
         &self,
-        participant_id: i32,
+        participant_id: &str,
         node_id: &str,
+        client: &ClientMetadataDto,
     ) -> Result<ParticipantResponse, RoomError> {
         let participant = self
             .room_repository
-            .get_participant_by_id(participant_id)
+            .get_participant_by_public_id(participant_id)
             .await?;
 
         let mut participant = participant.participant;
 
         participant.node_id = Some(node_id.to_string());
+        participant.status = ParticipantsStatusEnum::Active.into();
+        participant.detached_at = None;
+        participant.client_version = client.client_version.clone();
+        participant.client_platform = client.platform.clone();
+        participant.device_model = client.device_model.clone();
+        participant.network_type = client.network_type.clone();
 
         let participant = self.room_repository.update_participant(participant).await?;
 
+        self._invalidate_room(participant.participant.room_id).await;
+
         Ok(participant)
     }
 
-    async fn delete_participant(&self, participant_id: i32) -> Result<(), RoomError> {
+    async fn delete_participant(&self, participant_id: &str) -> Result<(), RoomError> {
+        let participant = self
+            .room_repository
+            .get_participant_by_public_id(participant_id)
+            .await?
+            .participant;
+
         let _ = self
             .room_repository
-            .delete_participant_by_id(participant_id)
+            .delete_participant_by_id(participant.id)
             .await?;
 
+        self._invalidate_room(participant.room_id).await;
+
         Ok(())
     }
 
     async fn delete_participants_by_node(&self, node_id: &str) -> Result<(), RoomError> {
-        let _ = self
+        let room_ids = self
             .room_repository
             .delete_participants_by_node(node_id)
             .await?;
 
+        for room_id in room_ids {
+            self._invalidate_room(room_id).await;
+        }
+
+        Ok(())
+    }
+
+    async fn detach_participants_by_node(&self, node_id: &str) -> Result<(), RoomError> {
+        let room_ids = self
+            .room_repository
+            .detach_participants_by_node(node_id)
+            .await?;
+
+        for room_id in room_ids {
+            self._invalidate_room(room_id).await;
+        }
+
+        Ok(())
+    }
+
+    async fn update_participant_media(
+        &self,
+        participant_id: &str,
+        change: MediaChange,
+    ) -> Result<(), RoomError> {
+        let participant = self
+            .room_repository
+            .update_participant_media(participant_id, change)
+            .await?;
+
+        self._invalidate_room(participant.room_id).await;
+
+        Ok(())
+    }
+
+    async fn purge_detached_participants(
+        &self,
+        detached_before: NaiveDateTime,
+    ) -> Result<Vec<(Room, Participant)>, RoomError> {
+        let purged = self
+            .room_repository
+            .purge_detached_participants(detached_before)
+            .await?;
+
+        let mut room_ids: Vec<i32> = purged.iter().map(|(room, _)| room.id).collect();
+        room_ids.sort_unstable();
+        room_ids.dedup();
+
+        for room_id in room_ids {
+            self._invalidate_room(room_id).await;
+        }
+
+        Ok(purged)
+    }
+
+    async fn check_host(&self, room_id: i32, user_id: i32) -> Result<(), RoomError> {
+        let room = self._get_room(room_id).await?;
+
+        let is_host = room.members.iter().any(|member| {
+            member.member.user_id == user_id && member.member.role == MembersRoleEnum::Owner as i16
+        });
+
+        if !is_host {
+            return Err(RoomError::YouDontHavePermissions);
+        }
+
         Ok(())
     }
 
+    async fn get_viewer_count(
+        &self,
+        public_id: &str,
+        hls_viewers: i64,
+    ) -> Result<ViewerCountResponse, RoomError> {
+        let room_id = self.resolve_room_id(public_id).await?;
+        let room = self._get_room(room_id).await?;
+
+        let webrtc_viewers = room
+            .participants
+            .iter()
+            .filter(|p| p.participant.status == ParticipantsStatusEnum::Active as i16)
+            .count() as i64;
+
+        Ok(ViewerCountResponse {
+            room_id: public_id.to_owned(),
+            is_live: room.room.status == RoomStatusEnum::Active as i16 && webrtc_viewers > 0,
+            webrtc_viewers,
+            hls_viewers,
+            total_viewers: webrtc_viewers + hls_viewers,
+            updated_at: Utc::now().timestamp_millis(),
+        })
+    }
+
     async fn generate_unique_room_code(&self, max_attempts: usize) -> Result<String, RoomError> {
         for _ in 0..max_attempts {
             let code = generate_room_code();
@@ -451,9 +913,42 @@ impl<R: RoomRepository + Send + Sync, U: UserRepository + Send + Sync> RoomServi
     }
 }
 
+/// Whether the call has no WebRTC slot left for the user. Only conference
+/// rooms have a capacity, the host always gets in and a user rejoining doesn't
+/// count against their own slot.
+pub fn is_room_full(room: &RoomResponse, user_id: i32) -> bool {
+    let Some(capacity) = room.room.capacity else {
+        return false;
+    };
+
+    if room.room.type_ != RoomType::Conferencing as i16 {
+        return false;
+    }
+
+    let is_host = room.members.iter().any(|member| {
+        member.member.user_id == user_id && member.member.role == MembersRoleEnum::Owner as i16
+    });
+    if is_host {
+        return false;
+    }
+
+    let connected = room
+        .participants
+        .iter()
+        .filter(|p| {
+            p.participant.node_id.is_some()
+                && p.participant.status == ParticipantsStatusEnum::Active as i16
+                && p.participant.user_id != user_id
+        })
+        .count();
+
+    connected >= capacity.max(0) as usize
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::dtos::common::pagination_dto::MAX_PAGE_SIZE;
     use crate::core::dtos::room::create_room_dto::CreateRoomDto;
     use crate::core::dtos::room::update_room_dto::UpdateRoomDto;
     use crate::core::entities::models::{
@@ -481,6 +976,9 @@ mod tests {
             updated_at: now,
             deleted_at: None,
             last_seen_at: None,
+            public_id: format!("U{id:025}"),
+            locale: "en-US".to_string(),
+            timezone: "UTC".to_string(),
         }
     }
 
@@ -512,6 +1010,15 @@ mod tests {
             room_id,
             status: ParticipantsStatusEnum::Active as i16,
             node_id,
+            public_id: format!("P{id:025}"),
+            client_version: None,
+            client_platform: None,
+            device_model: None,
+            network_type: None,
+            is_audio_enabled: false,
+            is_video_enabled: false,
+            is_hand_raising: false,
+            detached_at: None,
         }
     }
 
@@ -546,20 +1053,30 @@ mod tests {
                 deleted_at: None,
                 latest_message_id: Some(1),
                 type_: RoomType::Conferencing as i16,
+                public_id: format!("R{id:025}"),
+                message_retention_days: None,
+                legal_hold_at: None,
+                noise_suppression: false,
+                broadcast_delay_secs: 0,
+                capacity: None,
+                presenter_member_ids: vec![],
+                streaming_protocol: 0,
             },
             members: vec![MemberResponse {
                 member: sample_member(1, owner_id, id, MembersRoleEnum::Owner as i16),
                 user: Some(sample_user(owner_id)),
             }],
-            participants: vec![ParticipantResponse {
-                participant: sample_participant(1, owner_id, id, Some("node1".to_string())),
-                user: Some(sample_user(owner_id)),
-            }],
+            participants: vec![ParticipantResponse::new(
+                sample_participant(1, owner_id, id, Some("node1".to_string())),
+                Some(sample_user(owner_id)),
+            )],
             latest_message: Some(MessageResponse {
                 message: sample_message(1, owner_id, id),
                 created_by: Some(sample_user(owner_id)),
                 room: None,
             }),
+            branding: None,
+            overflow: None,
         }
     }
 
@@ -581,6 +1098,10 @@ mod tests {
             room_type: None,
             streaming_protocol: None,
             capacity: None,
+            message_retention_days: None,
+            noise_suppression: None,
+            broadcast_delay_secs: None,
+            presenter_member_ids: None,
         }
     }
 
@@ -606,11 +1127,15 @@ mod tests {
             let rooms = self.rooms.lock().unwrap();
             Ok(rooms.iter().any(|r| r.room.code == code))
         }
-        async fn get_room_by_id(&self, room_id: i32) -> Result<RoomResponse, RoomError> {
+        async fn get_room_by_id(
+            &self,
+            room_id: i32,
+            scope: DeletedScope,
+        ) -> Result<RoomResponse, RoomError> {
             let rooms = self.rooms.lock().unwrap();
             rooms
                 .iter()
-                .find(|r| r.room.id == room_id)
+                .find(|r| r.room.id == room_id && scope.allows(r.room.deleted_at))
                 .cloned()
                 .ok_or(RoomError::UnexpectedError("not found".into()))
         }
@@ -622,8 +1147,28 @@ mod tests {
                 .cloned()
                 .ok_or(RoomError::UnexpectedError("not found".into()))
         }
-        async fn create_room(&self, room: NewRoom<'_>) -> Result<RoomResponse, RoomError> {
-            let mut response = sample_room(1, 1);
+        async fn get_room_password(&self, room_id: i32) -> Result<Option<String>, RoomError> {
+            let rooms = self.rooms.lock().unwrap();
+            rooms
+                .iter()
+                .find(|r| r.room.id == room_id)
+                .map(|r| r.room.password.clone())
+                .ok_or(RoomError::RoomNotFound(room_id))
+        }
+        async fn get_room_id_by_public_id(
+            &self,
+            public_id: &str,
+            scope: DeletedScope,
+        ) -> Result<i32, RoomError> {
+            let rooms = self.rooms.lock().unwrap();
+            rooms
+                .iter()
+                .find(|r| r.room.public_id == public_id && scope.allows(r.room.deleted_at))
+                .map(|r| r.room.id)
+                .ok_or(RoomError::PublicIdNotFound(public_id.to_string()))
+        }
+        async fn create_room(&self, room: NewRoom<'_>) -> Result<RoomResponse, RoomError> {
+            let mut response = sample_room(1, 1);
             response.room.title = room.title.to_string();
             Ok(response)
         }
@@ -665,6 +1210,30 @@ mod tests {
             }
             Err(RoomError::UnexpectedError("not found".into()))
         }
+        async fn find_members(
+            &self,
+            room_id: i32,
+            query: Option<String>,
+            skip: i64,
+            limit: i64,
+        ) -> Result<Vec<MemberResponse>, RoomError> {
+            if self.fail {
+                return Err(RoomError::UnexpectedError("fail".into()));
+            }
+            let rooms = self.rooms.lock().unwrap();
+            Ok(rooms
+                .iter()
+                .filter(|r| r.room.id == room_id)
+                .flat_map(|r| r.members.clone())
+                .filter(|m| match (&query, &m.user) {
+                    (Some(query), Some(user)) => user.user_name.contains(query.as_str()),
+                    (Some(_), None) => false,
+                    (None, _) => true,
+                })
+                .skip(skip as usize)
+                .take(limit as usize)
+                .collect())
+        }
         async fn create_member(&self, _member: NewMember<'_>) -> Result<MemberResponse, RoomError> {
             Ok(MemberResponse {
                 member: sample_member(2, 2, 1, MembersRoleEnum::Attendee as i16),
@@ -684,35 +1253,154 @@ mod tests {
         async fn get_participant_by_id(&self, _id: i32) -> Result<ParticipantResponse, RoomError> {
             let participant = sample_participant(1, 1, 1, Some("node1".to_string()));
             let participant_clone = participant.clone();
-            Ok(ParticipantResponse {
+            Ok(ParticipantResponse::new(
                 participant,
-                user: Some(sample_user(participant_clone.user_id)),
-            })
+                Some(sample_user(participant_clone.user_id)),
+            ))
+        }
+        async fn get_participant_by_public_id(
+            &self,
+            public_id: &str,
+        ) -> Result<ParticipantResponse, RoomError> {
+            let rooms = self.rooms.lock().unwrap();
+            rooms
+                .iter()
+                .flat_map(|r| r.participants.iter())
+                .find(|p| p.participant.public_id == public_id)
+                .cloned()
+                .ok_or(RoomError::UnexpectedError("not found".into()))
+        }
+        async fn find_participants(
+            &self,
+            room_id: i32,
+            _query: Option<String>,
+            skip: i64,
+            limit: i64,
+        ) -> Result<Vec<ParticipantResponse>, RoomError> {
+            if self.fail {
+                return Err(RoomError::UnexpectedError("fail".into()));
+            }
+            let rooms = self.rooms.lock().unwrap();
+            Ok(rooms
+                .iter()
+                .filter(|r| r.room.id == room_id)
+                .flat_map(|r| r.participants.clone())
+                .filter(|p| p.participant.node_id.is_some())
+                .skip(skip as usize)
+                .take(limit as usize)
+                .collect())
         }
         async fn create_participant(
             &self,
-            _participant: NewParticipant<'_>,
+            participant: NewParticipant<'_>,
         ) -> Result<ParticipantResponse, RoomError> {
-            Ok(ParticipantResponse {
-                participant: sample_participant(1, 1, 1, Some("node1".to_string())),
-                user: Some(sample_user(1)),
-            })
+            let user_id = participant.user_id.unwrap();
+            Ok(ParticipantResponse::new(
+                sample_participant(100, user_id, *participant.room_id, None),
+                Some(sample_user(user_id)),
+            ))
         }
         async fn update_participant(
             &self,
             participant: Participant,
         ) -> Result<ParticipantResponse, RoomError> {
             let participant_clone = participant.clone();
-            Ok(ParticipantResponse {
+            Ok(ParticipantResponse::new(
                 participant,
-                user: Some(sample_user(participant_clone.user_id)),
-            })
+                Some(sample_user(participant_clone.user_id)),
+            ))
         }
         async fn delete_participant_by_id(&self, _id: i32) -> Result<(), RoomError> {
             Ok(())
         }
-        async fn delete_participants_by_node(&self, _node_id: &str) -> Result<(), RoomError> {
-            Ok(())
+        async fn delete_participants_by_node(&self, node_id: &str) -> Result<Vec<i32>, RoomError> {
+            let mut rooms = self.rooms.lock().unwrap();
+            let mut room_ids = vec![];
+            for room in rooms.iter_mut() {
+                let count = room.participants.len();
+                room.participants
+                    .retain(|p| p.participant.node_id.as_deref() != Some(node_id));
+                if room.participants.len() != count {
+                    room_ids.push(room.room.id);
+                }
+            }
+            Ok(room_ids)
+        }
+        async fn detach_participants_by_node(&self, node_id: &str) -> Result<Vec<i32>, RoomError> {
+            if self.fail {
+                return Err(RoomError::UnexpectedError("fail".into()));
+            }
+            let mut rooms = self.rooms.lock().unwrap();
+            let mut room_ids = vec![];
+            for room in rooms.iter_mut() {
+                for p in room.participants.iter_mut() {
+                    if p.participant.node_id.as_deref() == Some(node_id) {
+                        p.participant.node_id = None;
+                        p.participant.status = ParticipantsStatusEnum::Inactive as i16;
+                        p.participant.detached_at = Some(Utc::now().naive_utc());
+                        room_ids.push(room.room.id);
+                    }
+                }
+            }
+            room_ids.dedup();
+            Ok(room_ids)
+        }
+        async fn update_participant_media(
+            &self,
+            participant_id: &str,
+            change: MediaChange,
+        ) -> Result<Participant, RoomError> {
+            let mut rooms = self.rooms.lock().unwrap();
+            let participant = rooms
+                .iter_mut()
+                .flat_map(|room| room.participants.iter_mut())
+                .find(|p| p.participant.public_id == participant_id)
+                .ok_or_else(|| RoomError::ParticipantNotFound(participant_id.to_string()))?;
+            let participant = &mut participant.participant;
+            match change {
+                MediaChange::Published {
+                    is_audio_enabled,
+                    is_video_enabled,
+                } => {
+                    participant.is_audio_enabled = is_audio_enabled;
+                    participant.is_video_enabled = is_video_enabled;
+                    participant.is_hand_raising = false;
+                }
+                MediaChange::Unpublished => {
+                    participant.is_audio_enabled = false;
+                    participant.is_video_enabled = false;
+                }
+                MediaChange::Audio(is_enabled) => participant.is_audio_enabled = is_enabled,
+                MediaChange::Video(is_enabled) => participant.is_video_enabled = is_enabled,
+                MediaChange::HandRaising(is_raising) => participant.is_hand_raising = is_raising,
+            }
+            Ok(participant.clone())
+        }
+        async fn purge_detached_participants(
+            &self,
+            detached_before: NaiveDateTime,
+        ) -> Result<Vec<(Room, Participant)>, RoomError> {
+            if self.fail {
+                return Err(RoomError::UnexpectedError("fail".into()));
+            }
+            let mut rooms = self.rooms.lock().unwrap();
+            let mut purged = vec![];
+            for room in rooms.iter_mut() {
+                let (expired, kept) = room.participants.drain(..).partition(|p| {
+                    p.participant.node_id.is_none()
+                        && p.participant.status == ParticipantsStatusEnum::Inactive as i16
+                        && p.participant
+                            .detached_at
+                            .is_some_and(|at| at < detached_before)
+                });
+                room.participants = kept;
+                purged.extend(
+                    expired
+                        .into_iter()
+                        .map(|p: ParticipantResponse| (room.room.clone(), p.participant)),
+                );
+            }
+            Ok(purged)
         }
     }
 
@@ -740,6 +1428,22 @@ mod tests {
                     .ok_or(crate::core::types::errors::user_error::UserError::UserNotFound(id))
             }
         }
+        async fn get_user_by_public_id(
+            &self,
+            public_id: &str,
+            scope: DeletedScope,
+        ) -> Result<User, crate::core::types::errors::user_error::UserError> {
+            let users = self.users.lock().unwrap();
+            users
+                .iter()
+                .find(|u| u.public_id == public_id && scope.allows(u.deleted_at))
+                .cloned()
+                .ok_or(
+                    crate::core::types::errors::user_error::UserError::PublicIdNotFound(
+                        public_id.to_string(),
+                    ),
+                )
+        }
         async fn update_user(
             &self,
             user: User,
@@ -828,6 +1532,112 @@ mod tests {
         assert_eq!(updated.room.title, "Updated Room");
     }
 
+    #[tokio::test]
+    async fn test_update_room_message_retention() {
+        let mut room = sample_room(1, 1);
+        room.room.message_retention_days = Some(30);
+        let rooms = Arc::new(Mutex::new(vec![room]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+
+        let mut dto = sample_update_room_dto();
+        dto.message_retention_days = Some(7);
+        let updated = service.update_room(dto, 1, 1).await.unwrap();
+        assert_eq!(updated.room.message_retention_days, Some(7));
+
+        // Zero goes back to the deployment default
+        let mut dto = sample_update_room_dto();
+        dto.message_retention_days = Some(0);
+        let updated = service.update_room(dto, 1, 1).await.unwrap();
+        assert_eq!(updated.room.message_retention_days, None);
+    }
+
+    #[tokio::test]
+    async fn test_update_room_noise_suppression() {
+        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+
+        let mut dto = sample_update_room_dto();
+        dto.noise_suppression = Some(true);
+        let updated = service.update_room(dto, 1, 1).await.unwrap();
+        assert!(updated.room.noise_suppression);
+
+        // Left out, the setting stays as it is
+        let updated = service
+            .update_room(sample_update_room_dto(), 1, 1)
+            .await
+            .unwrap();
+        assert!(updated.room.noise_suppression);
+    }
+
+    #[tokio::test]
+    async fn test_update_room_broadcast_delay_is_capped() {
+        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+
+        let mut dto = sample_update_room_dto();
+        dto.broadcast_delay_secs = Some(30);
+        let updated = service.update_room(dto, 1, 1).await.unwrap();
+        assert_eq!(updated.room.broadcast_delay_secs, 30);
+
+        let mut dto = sample_update_room_dto();
+        dto.broadcast_delay_secs = Some(3_600);
+        let updated = service.update_room(dto, 1, 1).await.unwrap();
+        assert_eq!(updated.room.broadcast_delay_secs, MAX_BROADCAST_DELAY_SECS);
+    }
+
+    #[tokio::test]
+    async fn test_update_room_presenters() {
+        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+
+        let mut dto = sample_update_room_dto();
+        dto.presenter_member_ids = Some(vec![1, 1]);
+        let updated = service.update_room(dto, 1, 1).await.unwrap();
+        assert_eq!(updated.room.presenter_member_ids, vec![1]);
+
+        // Only members of the room can present
+        let mut dto = sample_update_room_dto();
+        dto.presenter_member_ids = Some(vec![99]);
+        let result = service.update_room(dto, 1, 1).await;
+        assert!(matches!(result, Err(RoomError::MemberNotFound)));
+    }
+
     #[tokio::test]
     async fn test_update_room_not_host() {
         let room = sample_room(1, 1);
@@ -906,6 +1716,26 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_get_room_by_id_participant_placeholder() {
+        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        let room = service.get_room_by_id(1).await.unwrap();
+        assert_eq!(
+            room.participants[0].placeholder_url.as_deref(),
+            Some("/busapi/v3/avatars/U0000000000000000000000001/placeholder.svg?v=0")
+        );
+    }
+
     #[tokio::test]
     async fn test_leave_room_success() {
         let mut room = sample_room(1, 1);
@@ -965,8 +1795,93 @@ mod tests {
             fail: false,
         };
         let service = RoomServiceImpl::new(room_repo, user_repo);
+        let (room, joined) = service.join_room(2, 1, None).await.unwrap();
+
+        assert_eq!(joined.participant.user_id, 2);
+        assert_eq!(joined.participant.public_id, format!("P{:025}", 100));
+        assert!(
+            room.participants
+                .iter()
+                .any(|p| p.participant.public_id == joined.participant.public_id)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_join_room_reattaches_participant_of_dead_node() {
+        let mut room = sample_room(1, 1);
+        let mut detached = sample_participant(7, 2, 1, None);
+        detached.status = ParticipantsStatusEnum::Inactive as i16;
+        room.participants
+            .push(ParticipantResponse::new(detached, Some(sample_user(2))));
+        let rooms = Arc::new(Mutex::new(vec![room]));
+        let users = Arc::new(Mutex::new(vec![sample_user(2)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        let (result, joined) = service.join_room(2, 1, None).await.unwrap();
+
+        assert_eq!(joined.participant.public_id, format!("P{:025}", 7));
+        assert_eq!(
+            joined.participant.status,
+            ParticipantsStatusEnum::Active as i16
+        );
+        assert_eq!(
+            result
+                .participants
+                .iter()
+                .filter(|p| p.participant.user_id == 2)
+                .count(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_join_room_full() {
+        let mut room = sample_room(1, 1);
+        room.room.capacity = Some(1);
+        let rooms = Arc::new(Mutex::new(vec![room]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1), sample_user(2)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
         let result = service.join_room(2, 1, None).await;
-        assert!(result.is_ok());
+        assert!(matches!(result, Err(RoomError::RoomFull)));
+    }
+
+    #[test]
+    fn test_is_room_full() {
+        let mut room = sample_room(1, 1);
+        room.participants.push(ParticipantResponse::new(
+            sample_participant(2, 2, 1, Some("node1".to_string())),
+            Some(sample_user(2)),
+        ));
+        // Joined but never reached a node
+        room.participants.push(ParticipantResponse::new(
+            sample_participant(3, 3, 1, None),
+            Some(sample_user(3)),
+        ));
+        assert!(!is_room_full(&room, 4));
+
+        room.room.capacity = Some(2);
+        assert!(is_room_full(&room, 4));
+        // The host and a user taking their own slot back still get in
+        assert!(!is_room_full(&room, 1));
+        assert!(!is_room_full(&room, 2));
+
+        room.room.type_ = RoomType::LiveStreaming as i16;
+        assert!(!is_room_full(&room, 4));
     }
 
     #[tokio::test]
@@ -1005,6 +1920,43 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_ensure_member_adds_attendee() {
+        let room = sample_room(1, 1);
+        let rooms = Arc::new(Mutex::new(vec![room.clone()]));
+        let users = Arc::new(Mutex::new(vec![sample_user(2)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        let result = service.ensure_member(1, 2).await.unwrap();
+        assert_eq!(result.members.len(), 2);
+        assert!(result.members.iter().any(|m| m.member.user_id == 2));
+    }
+
+    #[tokio::test]
+    async fn test_ensure_member_existing_member() {
+        let room = sample_room(1, 1);
+        let rooms = Arc::new(Mutex::new(vec![room.clone()]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        let result = service.ensure_member(1, 1).await.unwrap();
+        assert_eq!(result.members.len(), 1);
+    }
+
     #[tokio::test]
     async fn test_remove_member_success() {
         let mut room = sample_room(1, 1);
@@ -1072,6 +2024,25 @@ mod tests {
         assert_eq!(result.unwrap().room.status, RoomStatusEnum::Inactive as i16);
     }
 
+    #[tokio::test]
+    async fn test_close_room_without_host() {
+        let room = sample_room(1, 1);
+        let rooms = Arc::new(Mutex::new(vec![room.clone()]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        let result = service.close_room(1).await;
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().room.status, RoomStatusEnum::Inactive as i16);
+    }
+
     #[tokio::test]
     async fn test_deactivate_room_not_owner() {
         let mut room = sample_room(1, 1);
@@ -1099,8 +2070,9 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_update_participant_success() {
-        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
+    async fn test_switch_room_type_to_live_stream_and_back() {
+        let room = sample_room(1, 1);
+        let rooms = Arc::new(Mutex::new(vec![room.clone()]));
         let users = Arc::new(Mutex::new(vec![sample_user(1)]));
         let room_repo = MockRoomRepository {
             rooms: rooms.clone(),
@@ -1111,18 +2083,37 @@ mod tests {
             fail: false,
         };
         let service = RoomServiceImpl::new(room_repo, user_repo);
-        let result = service.update_participant(1, "node1").await;
-        assert!(result.is_ok());
+
+        let live = service
+            .switch_room_type(1, 1, RoomType::LiveStreaming)
+            .await
+            .unwrap();
+        assert_eq!(live.room.type_, RoomType::LiveStreaming as i16);
+        assert_eq!(live.room.streaming_protocol, StreamingProtocol::HLS as i16);
+
+        let conference = service
+            .switch_room_type(1, 1, RoomType::Conferencing)
+            .await
+            .unwrap();
+        assert_eq!(conference.room.type_, RoomType::Conferencing as i16);
         assert_eq!(
-            result.unwrap().participant.node_id,
-            Some("node1".to_string())
+            conference.room.streaming_protocol,
+            StreamingProtocol::SFU as i16
         );
     }
 
     #[tokio::test]
-    async fn test_delete_participant_success() {
-        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
-        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+    async fn test_switch_room_type_rejects_attendees_and_p2p() {
+        let mut room = sample_room(1, 1);
+        room.members.push(MemberResponse {
+            member: sample_member(2, 2, 1, MembersRoleEnum::Attendee as i16),
+            user: Some(sample_user(2)),
+        });
+        let mut p2p_room = sample_room(2, 1);
+        p2p_room.room.type_ = RoomType::P2P as i16;
+
+        let rooms = Arc::new(Mutex::new(vec![room, p2p_room]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1), sample_user(2)]));
         let room_repo = MockRoomRepository {
             rooms: rooms.clone(),
             fail: false,
@@ -1132,15 +2123,188 @@ mod tests {
             fail: false,
         };
         let service = RoomServiceImpl::new(room_repo, user_repo);
-        let result = service.delete_participant(1).await;
-        assert!(result.is_ok());
-    }
 
-    #[tokio::test]
-    async fn test_delete_participants_by_node_success() {
-        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
-        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
-        let room_repo = MockRoomRepository {
+        let result = service
+            .switch_room_type(1, 2, RoomType::LiveStreaming)
+            .await;
+        assert!(matches!(result, Err(RoomError::YouDontHavePermissions)));
+
+        let result = service.switch_room_type(1, 1, RoomType::P2P).await;
+        assert!(matches!(result, Err(RoomError::RoomTypeFixed)));
+
+        let result = service
+            .switch_room_type(2, 1, RoomType::LiveStreaming)
+            .await;
+        assert!(matches!(result, Err(RoomError::RoomTypeFixed)));
+    }
+
+    #[tokio::test]
+    async fn test_update_participant_success() {
+        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        let client = ClientMetadataDto {
+            client_version: Some("2.4.1".to_string()),
+            platform: Some("ios".to_string()),
+            ..Default::default()
+        };
+        let result = service
+            .update_participant("P0000000000000000000000001", "node1", &client)
+            .await;
+        assert!(result.is_ok());
+        let participant = result.unwrap().participant;
+        assert_eq!(participant.node_id, Some("node1".to_string()));
+        assert_eq!(participant.client_version, Some("2.4.1".to_string()));
+        assert_eq!(participant.client_platform, Some("ios".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_delete_participant_success() {
+        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        let result = service
+            .delete_participant("P0000000000000000000000001")
+            .await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_delete_participant_unknown_public_id() {
+        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        // Sequential ids are no longer accepted
+        let result = service.delete_participant("1").await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_room_id() {
+        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1), sample_room(2, 1)]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        assert_eq!(
+            service
+                .resolve_room_id("R0000000000000000000000002")
+                .await
+                .unwrap(),
+            2
+        );
+        assert!(matches!(
+            service.resolve_room_id("2").await,
+            Err(RoomError::PublicIdNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_deleted_room_only_visible_with_include_deleted() {
+        let mut deleted = sample_room(3, 1);
+        deleted.room.deleted_at = Some(Utc::now().naive_utc());
+        let rooms = Arc::new(Mutex::new(vec![deleted]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        assert!(
+            service
+                .resolve_room_id("R0000000000000000000000003")
+                .await
+                .is_err()
+        );
+        assert!(
+            service
+                .get_room_by_public_id("R0000000000000000000000003", DeletedScope::Exclude)
+                .await
+                .is_err()
+        );
+        let room = service
+            .get_room_by_public_id("R0000000000000000000000003", DeletedScope::Include)
+            .await
+            .unwrap();
+        assert_eq!(room.room.id, 3);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_user_id() {
+        let rooms = Arc::new(Mutex::new(vec![]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1), sample_user(7)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        assert_eq!(
+            service
+                .resolve_user_id("U0000000000000000000000007")
+                .await
+                .unwrap(),
+            7
+        );
+        assert!(service.resolve_user_id("7").await.is_err());
+    }
+
+    #[test]
+    fn test_room_response_hides_internal_ids() {
+        let json = serde_json::to_value(sample_room(3, 5)).unwrap();
+        assert_eq!(json["id"], "R0000000000000000000000003");
+        assert_eq!(
+            json["members"][0]["user"]["id"],
+            "U0000000000000000000000005"
+        );
+        assert!(json["members"][0].get("userId").is_none());
+        assert_eq!(json["participants"][0]["id"], "P0000000000000000000000001");
+        assert!(json["participants"][0].get("roomId").is_none());
+        assert!(json["latestMessage"].get("createdById").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_delete_participants_by_node_success() {
+        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+        let room_repo = MockRoomRepository {
             rooms: rooms.clone(),
             fail: false,
         };
@@ -1153,6 +2317,403 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_detach_participants_by_node_success() {
+        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        let result = service.detach_participants_by_node("node1").await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_detach_participants_by_node_failure() {
+        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: true,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        let result = service.detach_participants_by_node("node1").await;
+        assert!(result.is_err());
+    }
+
+    fn cached_service(
+        rooms: Arc<Mutex<Vec<RoomResponse>>>,
+        users: Vec<User>,
+    ) -> RoomServiceImpl<MockRoomRepository, MockUserRepository> {
+        let room_repo = MockRoomRepository { rooms, fail: false };
+        let user_repo = MockUserRepository {
+            users: Arc::new(Mutex::new(users)),
+            fail: false,
+        };
+        RoomServiceImpl::new(room_repo, user_repo).with_cache(RoomStateCache::in_memory())
+    }
+
+    #[tokio::test]
+    async fn test_room_state_cache_hit_and_miss() {
+        let cache = RoomStateCache::in_memory();
+        let mut room = sample_room(5, 1);
+        room.room.password = Some("hashed".to_string());
+
+        cache.set(&room).await;
+
+        let cached = cache.get(5).await.unwrap();
+        assert_eq!(cached.room.id, 5);
+        assert_eq!(cached.members[0].member.user_id, 1);
+        assert_eq!(cached.participants[0].participant.id, 1);
+        assert_eq!(
+            cached.participants[0].participant.node_id.as_deref(),
+            Some("node1")
+        );
+        assert!(cached.room.password.is_none());
+
+        assert!(cache.get(6).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_room_by_id_served_from_cache() {
+        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
+        let service = cached_service(rooms.clone(), vec![sample_user(1)]);
+
+        assert_eq!(service.get_room_by_id(1).await.unwrap().room.title, "Room1");
+
+        rooms.lock().unwrap()[0].room.title = "Renamed".to_string();
+        assert_eq!(service.get_room_by_id(1).await.unwrap().room.title, "Room1");
+    }
+
+    #[tokio::test]
+    async fn test_detach_participants_by_node_invalidates_cache() {
+        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
+        let service = cached_service(rooms, vec![sample_user(1)]);
+
+        let room = service.get_room_by_id(1).await.unwrap();
+        assert!(room.participants[0].participant.node_id.is_some());
+
+        service.detach_participants_by_node("node1").await.unwrap();
+
+        let room = service.get_room_by_id(1).await.unwrap();
+        assert!(room.participants[0].participant.node_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_purge_detached_participants() {
+        let now = Utc::now().naive_utc();
+        let mut room = sample_room(1, 1);
+        for (id, detached_at) in [
+            (7, Some(now - chrono::Duration::minutes(10))),
+            (8, Some(now)),
+        ] {
+            let mut detached = sample_participant(id, id, 1, None);
+            detached.status = ParticipantsStatusEnum::Inactive as i16;
+            detached.detached_at = detached_at;
+            room.participants
+                .push(ParticipantResponse::new(detached, Some(sample_user(id))));
+        }
+        let rooms = Arc::new(Mutex::new(vec![room]));
+        let service = cached_service(rooms, vec![sample_user(1)]);
+
+        assert_eq!(
+            service.get_room_by_id(1).await.unwrap().participants.len(),
+            3
+        );
+
+        let purged = service
+            .purge_detached_participants(now - chrono::Duration::minutes(2))
+            .await
+            .unwrap();
+
+        assert_eq!(purged.len(), 1);
+        assert_eq!(purged[0].0.public_id, format!("R{:025}", 1));
+        assert_eq!(purged[0].1.public_id, format!("P{:025}", 7));
+
+        // The one still within its grace period and the active one stay
+        let room = service.get_room_by_id(1).await.unwrap();
+        let ids: Vec<i32> = room.participants.iter().map(|p| p.participant.id).collect();
+        assert_eq!(ids, vec![1, 8]);
+    }
+
+    #[tokio::test]
+    async fn test_update_participant_media_invalidates_cache() {
+        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
+        let service = cached_service(rooms.clone(), vec![sample_user(1)]);
+        let public_id = format!("P{:025}", 1);
+
+        service
+            .update_participant_media(
+                &public_id,
+                MediaChange::Published {
+                    is_audio_enabled: true,
+                    is_video_enabled: false,
+                },
+            )
+            .await
+            .unwrap();
+        service
+            .update_participant_media(&public_id, MediaChange::HandRaising(true))
+            .await
+            .unwrap();
+
+        let participant = rooms.lock().unwrap()[0].participants[0].participant.clone();
+        assert!(participant.is_audio_enabled);
+        assert!(!participant.is_video_enabled);
+        assert!(participant.is_hand_raising);
+
+        let result = service
+            .update_participant_media("missing", MediaChange::Audio(true))
+            .await;
+        assert!(matches!(result, Err(RoomError::ParticipantNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_delete_participants_by_node_invalidates_cache() {
+        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
+        let service = cached_service(rooms, vec![sample_user(1)]);
+
+        assert_eq!(
+            service.get_room_by_id(1).await.unwrap().participants.len(),
+            1
+        );
+
+        service.delete_participants_by_node("node1").await.unwrap();
+
+        assert!(
+            service
+                .get_room_by_id(1)
+                .await
+                .unwrap()
+                .participants
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_join_room_checks_password_of_cached_room() {
+        let mut room = sample_room(1, 1);
+        room.room.password = Some(hash_password("secret"));
+        let rooms = Arc::new(Mutex::new(vec![room]));
+        let service = cached_service(rooms, vec![sample_user(2)]);
+
+        // Warms the cache, the room read from it has no password
+        service.get_room_by_id(1).await.unwrap();
+
+        assert!(matches!(
+            service.join_room(2, 1, Some("wrong")).await,
+            Err(RoomError::PasswordIncorrect)
+        ));
+        assert!(service.join_room(2, 1, Some("secret")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_get_members_with_query() {
+        let mut room = sample_room(1, 1);
+        room.members.push(MemberResponse {
+            member: sample_member(2, 2, 1, MembersRoleEnum::Attendee as i16),
+            user: Some(sample_user(2)),
+        });
+        let rooms = Arc::new(Mutex::new(vec![room]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1), sample_user(2)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        let pagination = PaginationDto { skip: 0, limit: 10 };
+        let result = service
+            .get_members(1, Some("user2".to_string()), pagination)
+            .await;
+        assert!(result.is_ok());
+        let members = result.unwrap();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].member.user_id, 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_members_failure() {
+        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: true,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        let pagination = PaginationDto { skip: 0, limit: 10 };
+        let result = service.get_members(1, None, pagination).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_members_page_is_bounded() {
+        let mut room = sample_room(1, 1);
+        for id in 2..=MAX_PAGE_SIZE as i32 + 50 {
+            room.members.push(MemberResponse {
+                member: sample_member(id, id, 1, MembersRoleEnum::Attendee as i16),
+                user: Some(sample_user(id)),
+            });
+        }
+        let room_repo = MockRoomRepository {
+            rooms: Arc::new(Mutex::new(vec![room])),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: Arc::new(Mutex::new(vec![sample_user(1)])),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+
+        let members = service
+            .get_members(
+                1,
+                None,
+                PaginationDto {
+                    skip: 0,
+                    limit: 1_000_000,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(members.len(), MAX_PAGE_SIZE as usize);
+
+        for pagination in [
+            PaginationDto {
+                skip: -1,
+                limit: 10,
+            },
+            PaginationDto { skip: 0, limit: -1 },
+        ] {
+            assert!(matches!(
+                service.get_members(1, None, pagination.clone()).await,
+                Err(RoomError::InvalidPagination)
+            ));
+            assert!(matches!(
+                service.get_participants(1, None, pagination).await,
+                Err(RoomError::InvalidPagination)
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_participants_success() {
+        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        let pagination = PaginationDto { skip: 0, limit: 10 };
+        let result = service.get_participants(1, None, pagination).await;
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_check_host_success() {
+        let rooms = Arc::new(Mutex::new(vec![sample_room(1, 1)]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        let result = service.check_host(1, 1).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_check_host_not_owner() {
+        let mut room = sample_room(1, 1);
+        room.members.push(MemberResponse {
+            member: sample_member(2, 2, 1, MembersRoleEnum::Attendee as i16),
+            user: Some(sample_user(2)),
+        });
+        let rooms = Arc::new(Mutex::new(vec![room]));
+        let users = Arc::new(Mutex::new(vec![sample_user(2)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        let result = service.check_host(1, 2).await;
+        assert!(matches!(result, Err(RoomError::YouDontHavePermissions)));
+    }
+
+    #[tokio::test]
+    async fn test_get_viewer_count_combines_webrtc_and_hls() {
+        let mut room = sample_room(1, 1);
+        let mut left = sample_participant(2, 2, 1, None);
+        left.status = ParticipantsStatusEnum::Inactive as i16;
+        room.participants
+            .push(ParticipantResponse::new(left, Some(sample_user(2))));
+        let rooms = Arc::new(Mutex::new(vec![room]));
+        let users = Arc::new(Mutex::new(vec![sample_user(1)]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        let public_id = format!("R{:025}", 1);
+        let result = service.get_viewer_count(&public_id, 4).await.unwrap();
+        assert_eq!(result.webrtc_viewers, 1);
+        assert_eq!(result.hls_viewers, 4);
+        assert_eq!(result.total_viewers, 5);
+        assert!(result.is_live);
+    }
+
+    #[tokio::test]
+    async fn test_get_viewer_count_room_not_found() {
+        let rooms = Arc::new(Mutex::new(vec![]));
+        let users = Arc::new(Mutex::new(vec![]));
+        let room_repo = MockRoomRepository {
+            rooms: rooms.clone(),
+            fail: false,
+        };
+        let user_repo = MockUserRepository {
+            users: users.clone(),
+            fail: false,
+        };
+        let service = RoomServiceImpl::new(room_repo, user_repo);
+        let result = service.get_viewer_count("missing", 0).await;
+        assert!(matches!(result, Err(RoomError::PublicIdNotFound(_))));
+    }
+
     #[tokio::test]
     async fn test_generate_unique_room_code_success() {
         let rooms = Arc::new(Mutex::new(vec![]));
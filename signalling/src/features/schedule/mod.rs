@@ -0,0 +1,4 @@
+pub mod repository;
+pub mod router;
+pub mod scheduler;
+pub mod service;
@@ -0,0 +1,175 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::{
+    ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper,
+    dsl::{insert_into, update},
+    r2d2::{ConnectionManager, Pool, PooledConnection},
+    upsert::excluded,
+};
+use salvo::async_trait;
+
+use crate::core::{
+    database::{db::checkout, schema::live_schedules},
+    entities::models::{LiveSchedule, NewLiveSchedule},
+    types::{
+        enums::live_schedule_status::LiveScheduleStatus,
+        errors::{general::GeneralError, schedule_error::ScheduleError},
+    },
+};
+
+#[async_trait]
+pub trait ScheduleRepository: Send + Sync {
+    /// A room has at most one schedule, scheduling again replaces it
+    async fn upsert_schedule(
+        &self,
+        schedule: NewLiveSchedule<'_>,
+    ) -> Result<LiveSchedule, ScheduleError>;
+
+    async fn find_by_room_id(&self, room_id: i32) -> Result<Option<LiveSchedule>, ScheduleError>;
+
+    async fn update_status(
+        &self,
+        schedule_id: i32,
+        status: LiveScheduleStatus,
+    ) -> Result<LiveSchedule, ScheduleError>;
+
+    /// Moves the start of a schedule still waiting for it, `None` once the
+    /// slate is up or the schedule was cancelled
+    async fn move_scheduled(
+        &self,
+        schedule_id: i32,
+        start_at: NaiveDateTime,
+    ) -> Result<Option<LiveSchedule>, ScheduleError>;
+
+    /// Cancels a schedule still waiting for its start time, `None` otherwise
+    async fn cancel_scheduled(
+        &self,
+        schedule_id: i32,
+    ) -> Result<Option<LiveSchedule>, ScheduleError>;
+
+    /// Moves the schedules whose start time has passed to `Slate` and returns
+    /// them. The update is the claim, so only one signalling node gets each.
+    async fn claim_due(&self, now: NaiveDateTime) -> Result<Vec<LiveSchedule>, ScheduleError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct ScheduleRepositoryImpl {
+    pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl ScheduleRepositoryImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Self {
+        Self { pool }
+    }
+
+    fn get_conn(&self) -> Result<PooledConnection<ConnectionManager<PgConnection>>, GeneralError> {
+        checkout(&self.pool)
+    }
+}
+
+#[async_trait]
+impl ScheduleRepository for ScheduleRepositoryImpl {
+    async fn upsert_schedule(
+        &self,
+        schedule: NewLiveSchedule<'_>,
+    ) -> Result<LiveSchedule, ScheduleError> {
+        let mut conn = self.get_conn()?;
+
+        insert_into(live_schedules::table)
+            .values(&schedule)
+            .on_conflict(live_schedules::room_id)
+            .do_update()
+            .set((
+                live_schedules::created_by_id.eq(excluded(live_schedules::created_by_id)),
+                live_schedules::start_at.eq(excluded(live_schedules::start_at)),
+                live_schedules::slate_url.eq(excluded(live_schedules::slate_url)),
+                live_schedules::status.eq(excluded(live_schedules::status)),
+                live_schedules::updated_at.eq(excluded(live_schedules::updated_at)),
+            ))
+            .returning(LiveSchedule::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| ScheduleError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_by_room_id(&self, room_id: i32) -> Result<Option<LiveSchedule>, ScheduleError> {
+        let mut conn = self.get_conn()?;
+
+        live_schedules::table
+            .filter(live_schedules::room_id.eq(room_id))
+            .select(LiveSchedule::as_select())
+            .first::<LiveSchedule>(&mut conn)
+            .optional()
+            .map_err(|err| ScheduleError::UnexpectedError(err.to_string()))
+    }
+
+    async fn update_status(
+        &self,
+        schedule_id: i32,
+        status: LiveScheduleStatus,
+    ) -> Result<LiveSchedule, ScheduleError> {
+        let mut conn = self.get_conn()?;
+
+        update(live_schedules::table)
+            .filter(live_schedules::id.eq(schedule_id))
+            .set((
+                live_schedules::status.eq(status.to_str()),
+                live_schedules::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .returning(LiveSchedule::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| ScheduleError::UnexpectedError(err.to_string()))
+    }
+
+    async fn move_scheduled(
+        &self,
+        schedule_id: i32,
+        start_at: NaiveDateTime,
+    ) -> Result<Option<LiveSchedule>, ScheduleError> {
+        let mut conn = self.get_conn()?;
+
+        update(live_schedules::table)
+            .filter(live_schedules::id.eq(schedule_id))
+            .filter(live_schedules::status.eq(LiveScheduleStatus::Scheduled.to_str()))
+            .set((
+                live_schedules::start_at.eq(start_at),
+                live_schedules::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .returning(LiveSchedule::as_select())
+            .get_result(&mut conn)
+            .optional()
+            .map_err(|err| ScheduleError::UnexpectedError(err.to_string()))
+    }
+
+    async fn cancel_scheduled(
+        &self,
+        schedule_id: i32,
+    ) -> Result<Option<LiveSchedule>, ScheduleError> {
+        let mut conn = self.get_conn()?;
+
+        update(live_schedules::table)
+            .filter(live_schedules::id.eq(schedule_id))
+            .filter(live_schedules::status.eq(LiveScheduleStatus::Scheduled.to_str()))
+            .set((
+                live_schedules::status.eq(LiveScheduleStatus::Cancelled.to_str()),
+                live_schedules::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .returning(LiveSchedule::as_select())
+            .get_result(&mut conn)
+            .optional()
+            .map_err(|err| ScheduleError::UnexpectedError(err.to_string()))
+    }
+
+    async fn claim_due(&self, now: NaiveDateTime) -> Result<Vec<LiveSchedule>, ScheduleError> {
+        let mut conn = self.get_conn()?;
+
+        update(live_schedules::table)
+            .filter(live_schedules::status.eq(LiveScheduleStatus::Scheduled.to_str()))
+            .filter(live_schedules::start_at.le(now))
+            .set((
+                live_schedules::status.eq(LiveScheduleStatus::Slate.to_str()),
+                live_schedules::updated_at.eq(now),
+            ))
+            .returning(LiveSchedule::as_select())
+            .get_results(&mut conn)
+            .map_err(|err| ScheduleError::UnexpectedError(err.to_string()))
+    }
+}
@@ -0,0 +1,144 @@
+use dispatcher::dispatcher_manager::DispatcherManager;
+use salvo::{
+    oapi::extract::{JsonBody, PathParam},
+    prelude::*,
+};
+use tracing::warn;
+use waterbus_proto::StopSlateRequest;
+
+use crate::{
+    core::{
+        dtos::schedule::schedule_live_dto::ScheduleLiveDto,
+        entities::models::LiveSchedule,
+        types::{
+            enums::live_schedule_status::LiveScheduleStatus, errors::schedule_error::ScheduleError,
+            responses::live_schedule_response::LiveScheduleResponse,
+        },
+        utils::jwt_utils::JwtUtils,
+    },
+    features::{
+        calendar::service::{CalendarService, CalendarSync},
+        room::{
+            repository::RoomRepositoryImpl,
+            service::{RoomService, RoomServiceImpl},
+        },
+        user::repository::UserRepositoryImpl,
+    },
+};
+
+use super::service::{LiveSchedules, ScheduleService};
+
+pub fn get_schedule_router(jwt_utils: JwtUtils) -> Router {
+    Router::with_hoop(jwt_utils.auth_middleware())
+        .path("rooms/{room_id}/schedule")
+        .post(schedule_live)
+        .get(get_schedule)
+        .delete(cancel_schedule)
+}
+
+/// Schedules the room's live stream. From the start time viewers get the slate,
+/// recording starts when the host joins. Only the host can schedule.
+#[endpoint(tags("schedule"), status_codes(200, 400, 401, 403, 404, 409, 500))]
+async fn schedule_live(
+    _res: &mut Response,
+    room_id: PathParam<String>,
+    data: JsonBody<ScheduleLiveDto>,
+    depot: &mut Depot,
+) -> Result<LiveScheduleResponse, ScheduleError> {
+    let schedule_service = depot.obtain::<LiveSchedules>().unwrap();
+    let user_id = depot.get::<String>("user_id").unwrap();
+    let room_id = resolve_room_id(depot, &room_id.into_inner()).await?;
+
+    let schedule = schedule_service
+        .schedule_live(room_id, user_id.parse().unwrap(), data.into_inner())
+        .await?;
+
+    sync_calendars(depot, &schedule);
+
+    Ok(LiveScheduleResponse { schedule })
+}
+
+/// Start time and progress of the room's live stream, for countdowns.
+#[endpoint(tags("schedule"), status_codes(200, 401, 404, 500))]
+async fn get_schedule(
+    _res: &mut Response,
+    room_id: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<LiveScheduleResponse, ScheduleError> {
+    let schedule_service = depot.obtain::<LiveSchedules>().unwrap();
+    let room_id = resolve_room_id(depot, &room_id.into_inner()).await?;
+
+    let schedule = schedule_service.get_schedule(room_id).await?;
+
+    Ok(LiveScheduleResponse { schedule })
+}
+
+/// Cancels a live stream that hasn't started, taking its slate down.
+#[endpoint(tags("schedule"), status_codes(200, 401, 403, 404, 409, 500))]
+async fn cancel_schedule(
+    _res: &mut Response,
+    room_id: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<LiveScheduleResponse, ScheduleError> {
+    let schedule_service = depot.obtain::<LiveSchedules>().unwrap();
+    let dispatcher_manager = depot.obtain::<DispatcherManager>().unwrap();
+    let user_id = depot.get::<String>("user_id").unwrap();
+    let public_room_id = room_id.into_inner();
+    let room_id = resolve_room_id(depot, &public_room_id).await?;
+
+    let schedule = schedule_service
+        .cancel_schedule(room_id, user_id.parse().unwrap())
+        .await?;
+
+    remove_from_calendars(depot, schedule.id);
+
+    if schedule.status == LiveScheduleStatus::Slate.to_str()
+        && let Err(err) = dispatcher_manager
+            .stop_slate(StopSlateRequest {
+                room_id: public_room_id,
+            })
+            .await
+    {
+        warn!("Failed to stop slate: {:?}", err);
+    }
+
+    let schedule = schedule_service.get_schedule(room_id).await?;
+
+    Ok(LiveScheduleResponse { schedule })
+}
+
+/// Put the schedule on the host's connected calendars, if calendar sync is on
+fn sync_calendars(depot: &Depot, schedule: &LiveSchedule) {
+    if let Ok(calendar_service) = depot.obtain::<CalendarSync>() {
+        let calendar_service = calendar_service.clone();
+        let schedule = schedule.clone();
+
+        tokio::spawn(async move {
+            if let Err(err) = calendar_service.sync_schedule(&schedule).await {
+                warn!("Failed to sync live schedule to calendars: {:?}", err);
+            }
+        });
+    }
+}
+
+/// Take a cancelled schedule off the calendars it was put on, if calendar sync is on
+fn remove_from_calendars(depot: &Depot, schedule_id: i32) {
+    if let Ok(calendar_service) = depot.obtain::<CalendarSync>() {
+        let calendar_service = calendar_service.clone();
+
+        tokio::spawn(async move {
+            if let Err(err) = calendar_service.remove_schedule(schedule_id).await {
+                warn!("Failed to remove live schedule from calendars: {:?}", err);
+            }
+        });
+    }
+}
+
+async fn resolve_room_id(depot: &Depot, room_id: &str) -> Result<i32, ScheduleError> {
+    depot
+        .obtain::<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>()
+        .unwrap()
+        .resolve_room_id(room_id)
+        .await
+        .map_err(|_| ScheduleError::RoomNotFound(room_id.to_string()))
+}
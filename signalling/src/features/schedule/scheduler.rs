@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use dispatcher::dispatcher_manager::DispatcherManager;
+use tracing::{info, warn};
+use waterbus_proto::StartSlateRequest;
+
+use crate::features::room::service::RoomService;
+
+use super::service::ScheduleService;
+
+/// Start times are minute-grained in practice, a few seconds late is fine
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Puts the slate up for live streams whose start time has passed.
+///
+/// Every signalling node runs one, claiming a schedule is a single conditional
+/// update so each slate is started once. The host joining the room takes it
+/// from there, see `handle_join_room`.
+pub struct SlateScheduler<S: ScheduleService, R: RoomService> {
+    schedule_service: S,
+    room_service: R,
+    dispatcher_manager: DispatcherManager,
+}
+
+impl<S: ScheduleService + 'static, R: RoomService + 'static> SlateScheduler<S, R> {
+    pub fn new(
+        schedule_service: S,
+        room_service: R,
+        dispatcher_manager: DispatcherManager,
+    ) -> Self {
+        Self {
+            schedule_service,
+            room_service,
+            dispatcher_manager,
+        }
+    }
+
+    pub fn spawn(self) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(POLL_INTERVAL).await;
+
+                self.start_due().await;
+            }
+        });
+    }
+
+    async fn start_due(&self) {
+        let schedules = match self.schedule_service.claim_due().await {
+            Ok(schedules) => schedules,
+            Err(err) => {
+                warn!("Failed to claim due live schedules: {:?}", err);
+                return;
+            }
+        };
+
+        for schedule in schedules {
+            let public_room_id = match self.room_service.get_room_by_id(schedule.room_id).await {
+                Ok(room) => room.room.public_id,
+                Err(err) => {
+                    warn!("Room of live schedule {} is gone: {:?}", schedule.id, err);
+                    continue;
+                }
+            };
+
+            let req = StartSlateRequest {
+                room_id: public_room_id.clone(),
+                slate_uri: schedule.slate_url.clone().unwrap_or_default(),
+            };
+
+            match self.dispatcher_manager.start_slate(req).await {
+                Ok(()) => info!("Slate started for room {}", public_room_id),
+                Err(err) => {
+                    warn!(
+                        "Failed to start slate for room {}: {:?}",
+                        public_room_id, err
+                    );
+
+                    if let Err(err) = self.schedule_service.release(schedule.id).await {
+                        warn!("Failed to release live schedule {}: {:?}", schedule.id, err);
+                    }
+                }
+            }
+        }
+    }
+}
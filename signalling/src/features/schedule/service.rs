@@ -0,0 +1,298 @@
+use chrono::{NaiveDateTime, Utc};
+use salvo::async_trait;
+
+use crate::{
+    core::{
+        dtos::schedule::schedule_live_dto::ScheduleLiveDto,
+        entities::models::{LiveSchedule, NewLiveSchedule},
+        types::{
+            enums::live_schedule_status::LiveScheduleStatus, errors::schedule_error::ScheduleError,
+        },
+    },
+    features::{
+        room::{
+            repository::RoomRepositoryImpl,
+            service::{RoomService, RoomServiceImpl},
+        },
+        user::repository::UserRepositoryImpl,
+    },
+};
+
+use super::repository::{ScheduleRepository, ScheduleRepositoryImpl};
+
+/// Live schedules as wired up against Postgres
+pub type LiveSchedules = ScheduleServiceImpl<
+    ScheduleRepositoryImpl,
+    RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+>;
+
+#[async_trait]
+pub trait ScheduleService: Send + Sync {
+    /// Schedules the room's live stream, replacing a schedule that hasn't gone live yet
+    async fn schedule_live(
+        &self,
+        room_id: i32,
+        host_id: i32,
+        data: ScheduleLiveDto,
+    ) -> Result<LiveSchedule, ScheduleError>;
+
+    async fn get_schedule(&self, room_id: i32) -> Result<LiveSchedule, ScheduleError>;
+
+    /// Returns the schedule as it was before cancelling, a `slate` one still has
+    /// its slate running
+    async fn cancel_schedule(
+        &self,
+        room_id: i32,
+        host_id: i32,
+    ) -> Result<LiveSchedule, ScheduleError>;
+
+    /// Follows a calendar event that was moved, only while the schedule waits
+    /// for its start. `None` once the slate is up, the host runs it from there.
+    async fn move_start(
+        &self,
+        schedule_id: i32,
+        start_at: NaiveDateTime,
+    ) -> Result<Option<LiveSchedule>, ScheduleError>;
+
+    /// Follows a calendar event that was deleted, same rules as `move_start`
+    async fn cancel_pending(&self, schedule_id: i32)
+    -> Result<Option<LiveSchedule>, ScheduleError>;
+
+    async fn claim_due(&self) -> Result<Vec<LiveSchedule>, ScheduleError>;
+
+    /// Puts a claimed schedule back so the next poll retries its slate
+    async fn release(&self, schedule_id: i32) -> Result<LiveSchedule, ScheduleError>;
+
+    /// The schedule a joining user would start, when they host a room that
+    /// is scheduled but not live yet
+    async fn find_startable(
+        &self,
+        room_id: i32,
+        user_id: i32,
+    ) -> Result<Option<LiveSchedule>, ScheduleError>;
+
+    async fn mark_live(&self, schedule_id: i32) -> Result<LiveSchedule, ScheduleError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct ScheduleServiceImpl<S: ScheduleRepository, R: RoomService> {
+    schedule_repository: S,
+    room_service: R,
+}
+
+impl<S: ScheduleRepository, R: RoomService> ScheduleServiceImpl<S, R> {
+    pub fn new(schedule_repository: S, room_service: R) -> Self {
+        Self {
+            schedule_repository,
+            room_service,
+        }
+    }
+
+    async fn _check_host(&self, room_id: i32, host_id: i32) -> Result<(), ScheduleError> {
+        self.room_service
+            .check_host(room_id, host_id)
+            .await
+            .map_err(|_| ScheduleError::YouDontHavePermissions)
+    }
+}
+
+#[async_trait]
+impl<S: ScheduleRepository, R: RoomService> ScheduleService for ScheduleServiceImpl<S, R> {
+    async fn schedule_live(
+        &self,
+        room_id: i32,
+        host_id: i32,
+        data: ScheduleLiveDto,
+    ) -> Result<LiveSchedule, ScheduleError> {
+        self._check_host(room_id, host_id).await?;
+
+        let now = Utc::now().naive_utc();
+
+        if data.start_at <= now {
+            return Err(ScheduleError::StartInPast);
+        }
+
+        let slate_url = data.slate_url.filter(|url| !url.is_empty());
+        if let Some(url) = &slate_url
+            && !is_http_url(url)
+        {
+            return Err(ScheduleError::InvalidSlateUrl(url.clone()));
+        }
+
+        if let Some(existing) = self.schedule_repository.find_by_room_id(room_id).await?
+            && !is_reschedulable(&existing)
+        {
+            return Err(ScheduleError::AlreadyLive);
+        }
+
+        self.schedule_repository
+            .upsert_schedule(NewLiveSchedule {
+                room_id: &room_id,
+                created_by_id: &host_id,
+                start_at: data.start_at,
+                slate_url: slate_url.as_deref(),
+                status: LiveScheduleStatus::Scheduled.to_str(),
+                created_at: now,
+                updated_at: now,
+            })
+            .await
+    }
+
+    async fn get_schedule(&self, room_id: i32) -> Result<LiveSchedule, ScheduleError> {
+        self.schedule_repository
+            .find_by_room_id(room_id)
+            .await?
+            .ok_or_else(|| ScheduleError::ScheduleNotFound(room_id.to_string()))
+    }
+
+    async fn cancel_schedule(
+        &self,
+        room_id: i32,
+        host_id: i32,
+    ) -> Result<LiveSchedule, ScheduleError> {
+        self._check_host(room_id, host_id).await?;
+
+        let schedule = self.get_schedule(room_id).await?;
+
+        if schedule.status == LiveScheduleStatus::Live.to_str() {
+            return Err(ScheduleError::AlreadyLive);
+        }
+
+        self.schedule_repository
+            .update_status(schedule.id, LiveScheduleStatus::Cancelled)
+            .await?;
+
+        Ok(schedule)
+    }
+
+    async fn move_start(
+        &self,
+        schedule_id: i32,
+        start_at: NaiveDateTime,
+    ) -> Result<Option<LiveSchedule>, ScheduleError> {
+        if start_at <= Utc::now().naive_utc() {
+            return Err(ScheduleError::StartInPast);
+        }
+
+        self.schedule_repository
+            .move_scheduled(schedule_id, start_at)
+            .await
+    }
+
+    async fn cancel_pending(
+        &self,
+        schedule_id: i32,
+    ) -> Result<Option<LiveSchedule>, ScheduleError> {
+        self.schedule_repository.cancel_scheduled(schedule_id).await
+    }
+
+    async fn claim_due(&self) -> Result<Vec<LiveSchedule>, ScheduleError> {
+        self.schedule_repository
+            .claim_due(Utc::now().naive_utc())
+            .await
+    }
+
+    async fn release(&self, schedule_id: i32) -> Result<LiveSchedule, ScheduleError> {
+        self.schedule_repository
+            .update_status(schedule_id, LiveScheduleStatus::Scheduled)
+            .await
+    }
+
+    async fn find_startable(
+        &self,
+        room_id: i32,
+        user_id: i32,
+    ) -> Result<Option<LiveSchedule>, ScheduleError> {
+        let Some(schedule) = self.schedule_repository.find_by_room_id(room_id).await? else {
+            return Ok(None);
+        };
+
+        if !is_startable(&schedule) || self._check_host(room_id, user_id).await.is_err() {
+            return Ok(None);
+        }
+
+        Ok(Some(schedule))
+    }
+
+    async fn mark_live(&self, schedule_id: i32) -> Result<LiveSchedule, ScheduleError> {
+        self.schedule_repository
+            .update_status(schedule_id, LiveScheduleStatus::Live)
+            .await
+    }
+}
+
+/// The slate is fetched by the SFU's decoder, local files and other schemes stay off limits
+fn is_http_url(url: &str) -> bool {
+    let url = url.to_lowercase();
+
+    (url.starts_with("https://") || url.starts_with("http://"))
+        && !url.contains(char::is_whitespace)
+}
+
+fn is_reschedulable(schedule: &LiveSchedule) -> bool {
+    schedule.status != LiveScheduleStatus::Live.to_str()
+}
+
+/// Hosts who join early go live early, the slate is only for the wait after the start time
+fn is_startable(schedule: &LiveSchedule) -> bool {
+    schedule.status == LiveScheduleStatus::Scheduled.to_str()
+        || schedule.status == LiveScheduleStatus::Slate.to_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn sample_schedule(status: LiveScheduleStatus) -> LiveSchedule {
+        let now = DateTime::from_timestamp(0, 0).unwrap().naive_utc();
+
+        LiveSchedule {
+            id: 1,
+            room_id: 1,
+            created_by_id: 1,
+            start_at: now,
+            slate_url: None,
+            status: status.to_str().to_string(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_is_http_url() {
+        assert!(is_http_url("https://cdn.example.com/slate.png"));
+        assert!(is_http_url("HTTP://cdn.example.com/slate.mp4"));
+        assert!(!is_http_url("file:///etc/passwd"));
+        assert!(!is_http_url("rtsp://camera.local/stream"));
+        assert!(!is_http_url("https://cdn.example.com/my slate.png"));
+    }
+
+    #[test]
+    fn test_live_schedule_cannot_be_replaced() {
+        assert!(is_reschedulable(&sample_schedule(
+            LiveScheduleStatus::Scheduled
+        )));
+        assert!(is_reschedulable(&sample_schedule(
+            LiveScheduleStatus::Slate
+        )));
+        assert!(is_reschedulable(&sample_schedule(
+            LiveScheduleStatus::Cancelled
+        )));
+        assert!(!is_reschedulable(&sample_schedule(
+            LiveScheduleStatus::Live
+        )));
+    }
+
+    #[test]
+    fn test_only_pending_schedules_are_startable() {
+        assert!(is_startable(&sample_schedule(
+            LiveScheduleStatus::Scheduled
+        )));
+        assert!(is_startable(&sample_schedule(LiveScheduleStatus::Slate)));
+        assert!(!is_startable(&sample_schedule(LiveScheduleStatus::Live)));
+        assert!(!is_startable(&sample_schedule(
+            LiveScheduleStatus::Cancelled
+        )));
+    }
+}
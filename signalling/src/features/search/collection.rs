@@ -0,0 +1,212 @@
+use serde_json::{Value, json};
+
+use crate::core::{
+    entities::models::{Message, Room, User},
+    types::errors::search_error::SearchError,
+};
+
+use super::{
+    repository::{SearchRepository, SyncCursor},
+    typesense::{MESSAGES_COLLECTION, ROOMS_COLLECTION, USERS_COLLECTION},
+};
+
+/// The indexed tables, with their Typesense schema and how rows become documents
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchCollection {
+    Users,
+    Rooms,
+    Messages,
+}
+
+impl SearchCollection {
+    pub const ALL: [SearchCollection; 3] = [Self::Users, Self::Rooms, Self::Messages];
+
+    /// Searches go through the alias, never a concrete collection
+    pub fn alias(self) -> &'static str {
+        match self {
+            Self::Users => USERS_COLLECTION,
+            Self::Rooms => ROOMS_COLLECTION,
+            Self::Messages => MESSAGES_COLLECTION,
+        }
+    }
+
+    pub fn fields(self) -> Value {
+        match self {
+            Self::Users => json!([
+                { "name": "user_name", "type": "string" },
+                { "name": "full_name", "type": "string", "optional": true },
+            ]),
+            Self::Rooms => json!([
+                { "name": "title", "type": "string" },
+                { "name": "room_id", "type": "int32" },
+            ]),
+            Self::Messages => json!([
+                { "name": "data", "type": "string" },
+                { "name": "room_id", "type": "int32" },
+                { "name": "status", "type": "int32" },
+                { "name": "created_at", "type": "int64" },
+            ]),
+        }
+    }
+
+    /// The next chunk of documents and the cursor past them, `None` once caught up
+    pub async fn fetch_after<S: SearchRepository>(
+        self,
+        search_repository: &S,
+        cursor: SyncCursor,
+        limit: i64,
+    ) -> Result<Option<(Vec<Value>, SyncCursor)>, SearchError> {
+        let chunk = match self {
+            Self::Users => {
+                let users = search_repository.find_users_after(cursor, limit).await?;
+                users.last().map(|last| {
+                    (
+                        users.iter().map(user_document).collect(),
+                        (last.updated_at, last.id),
+                    )
+                })
+            }
+            Self::Rooms => {
+                let rooms = search_repository.find_rooms_after(cursor, limit).await?;
+                rooms.last().map(|last| {
+                    (
+                        rooms.iter().map(room_document).collect(),
+                        (last.updated_at, last.id),
+                    )
+                })
+            }
+            Self::Messages => {
+                let messages = search_repository.find_messages_after(cursor, limit).await?;
+                messages.last().map(|last| {
+                    (
+                        messages.iter().map(message_document).collect(),
+                        (last.updated_at, last.id),
+                    )
+                })
+            }
+        };
+
+        Ok(chunk)
+    }
+}
+
+fn user_document(user: &User) -> Value {
+    json!({
+        "id": user.id.to_string(),
+        "user_name": user.user_name,
+        "full_name": user.full_name.clone().unwrap_or_default(),
+    })
+}
+
+fn room_document(room: &Room) -> Value {
+    json!({
+        "id": room.id.to_string(),
+        "title": room.title,
+        "room_id": room.id,
+    })
+}
+
+fn message_document(message: &Message) -> Value {
+    json!({
+        "id": message.id.to_string(),
+        "data": message.data,
+        "room_id": message.room_id,
+        "status": message.status,
+        "created_at": message.created_at.and_utc().timestamp(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use super::*;
+
+    fn sample_user() -> User {
+        let now = DateTime::from_timestamp(0, 0).unwrap().naive_utc();
+        User {
+            id: 7,
+            full_name: None,
+            user_name: "alice".to_string(),
+            bio: None,
+            external_id: "ext7".to_string(),
+            avatar: None,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+            last_seen_at: None,
+            public_id: format!("U{:025}", 7),
+            locale: "en".to_string(),
+            timezone: "UTC".to_string(),
+        }
+    }
+
+    fn sample_message() -> Message {
+        let created_at = DateTime::from_timestamp(1_700_000_000, 0)
+            .unwrap()
+            .naive_utc();
+        Message {
+            id: 3,
+            data: "hello".to_string(),
+            created_at,
+            updated_at: created_at,
+            deleted_at: None,
+            created_by_id: 7,
+            room_id: 2,
+            type_: 0,
+            status: 0,
+        }
+    }
+
+    /// Every field of the document other than its id is declared in the schema
+    fn assert_matches_schema(collection: SearchCollection, document: &Value) {
+        let fields = collection.fields();
+        let declared: Vec<&str> = fields
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|field| field["name"].as_str().unwrap())
+            .collect();
+
+        for key in document.as_object().unwrap().keys() {
+            assert!(
+                key == "id" || declared.contains(&key.as_str()),
+                "{key} is not in the {} schema",
+                collection.alias()
+            );
+        }
+    }
+
+    #[test]
+    fn test_user_document() {
+        let document = user_document(&sample_user());
+
+        assert_eq!(document["id"], "7");
+        assert_eq!(document["user_name"], "alice");
+        assert_eq!(document["full_name"], "");
+        assert_matches_schema(SearchCollection::Users, &document);
+    }
+
+    #[test]
+    fn test_message_document() {
+        let document = message_document(&sample_message());
+
+        assert_eq!(document["id"], "3");
+        assert_eq!(document["room_id"], 2);
+        assert_eq!(document["created_at"], 1_700_000_000);
+        assert_matches_schema(SearchCollection::Messages, &document);
+    }
+
+    #[test]
+    fn test_every_collection_has_its_own_alias() {
+        let aliases: Vec<&str> = SearchCollection::ALL
+            .into_iter()
+            .map(SearchCollection::alias)
+            .collect();
+
+        assert_eq!(
+            aliases,
+            [USERS_COLLECTION, ROOMS_COLLECTION, MESSAGES_COLLECTION]
+        );
+    }
+}
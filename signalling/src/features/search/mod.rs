@@ -0,0 +1,7 @@
+pub mod collection;
+pub mod reindex;
+pub mod repository;
+pub mod router;
+pub mod service;
+pub mod sync;
+pub mod typesense;
@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use tracing::{info, warn};
+
+use crate::core::{
+    types::errors::search_error::SearchError, utils::search_reindex_store::SearchReindexStore,
+};
+
+use super::{
+    collection::SearchCollection,
+    repository::{SearchRepository, SyncCursor},
+    typesense::TypesenseClient,
+};
+
+const CHUNK_SIZE: i64 = 500;
+
+/// Breather between chunks so a rebuild doesn't hog Postgres or Typesense
+const CHUNK_PAUSE: Duration = Duration::from_millis(250);
+
+/// `updated_at` is stamped before the row commits, rows changed slightly
+/// before the rebuild started may not have been visible to it yet
+const CATCH_UP_MARGIN: TimeDelta = TimeDelta::minutes(1);
+
+/// Rebuilds the Typesense collections from Postgres without taking search down.
+///
+/// Each collection is filled under a fresh name while searches keep using the
+/// old one, then the aliases are swapped over and the old collections dropped.
+/// Rows changed during the rebuild are imported again after the swap. A rebuild
+/// is claimed in Redis, so only one runs across the signalling nodes.
+#[derive(Debug, Clone)]
+pub struct SearchReindexer<S: SearchRepository> {
+    search_repository: S,
+    typesense: TypesenseClient,
+    progress_store: SearchReindexStore,
+}
+
+impl<S: SearchRepository + Clone + 'static> SearchReindexer<S> {
+    pub fn new(
+        search_repository: S,
+        typesense: TypesenseClient,
+        progress_store: SearchReindexStore,
+    ) -> Self {
+        Self {
+            search_repository,
+            typesense,
+            progress_store,
+        }
+    }
+
+    /// Claims a rebuild and runs it in the background
+    pub async fn start(&self) -> Result<(), SearchError> {
+        let started_at = Utc::now().naive_utc();
+
+        let claimed = self
+            .progress_store
+            .start_run(started_at)
+            .await
+            .map_err(|err| SearchError::UnexpectedError(err.to_string()))?;
+        if !claimed {
+            return Err(SearchError::ReindexInProgress);
+        }
+
+        let reindexer = self.clone();
+        tokio::spawn(async move {
+            let is_failed = match reindexer.run(started_at).await {
+                Ok(()) => {
+                    info!("Search index rebuilt");
+                    false
+                }
+                Err(err) => {
+                    warn!("Failed to rebuild the search index: {:?}", err);
+                    true
+                }
+            };
+
+            if let Err(err) = reindexer
+                .progress_store
+                .finish_run(Utc::now().naive_utc(), is_failed)
+                .await
+            {
+                warn!("Failed to record search reindex progress: {:?}", err);
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn run(&self, started_at: NaiveDateTime) -> Result<(), anyhow::Error> {
+        let counts = self.search_repository.count_rows().await?;
+        for (collection, total) in [
+            (SearchCollection::Users, counts.users),
+            (SearchCollection::Rooms, counts.rooms),
+            (SearchCollection::Messages, counts.messages),
+        ] {
+            self.progress_store
+                .set_total(collection.alias(), total)
+                .await?;
+        }
+
+        let suffix = started_at.and_utc().timestamp_millis();
+        let mut built = Vec::new();
+
+        for collection in SearchCollection::ALL {
+            let name = format!("{}_{suffix}", collection.alias());
+
+            let result = self.build(collection, &name).await;
+            built.push(name);
+
+            if let Err(err) = result {
+                // Nothing points at them yet, searches never saw them
+                for name in &built {
+                    if let Err(err) = self.typesense.delete_collection(name).await {
+                        warn!("Failed to drop the unfinished collection {name}: {:?}", err);
+                    }
+                }
+                return Err(err);
+            }
+        }
+
+        for (collection, name) in SearchCollection::ALL.into_iter().zip(&built) {
+            self.swap(collection, name).await?;
+        }
+
+        let since = (started_at - CATCH_UP_MARGIN, 0);
+        for (collection, name) in SearchCollection::ALL.into_iter().zip(&built) {
+            self.import(collection, name, since, false).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn build(&self, collection: SearchCollection, name: &str) -> Result<(), anyhow::Error> {
+        self.typesense
+            .create_collection(name, collection.fields())
+            .await?;
+
+        // The default is the unix epoch, before any row
+        self.import(collection, name, (NaiveDateTime::default(), 0), true)
+            .await
+    }
+
+    async fn import(
+        &self,
+        collection: SearchCollection,
+        name: &str,
+        mut cursor: SyncCursor,
+        is_counted: bool,
+    ) -> Result<(), anyhow::Error> {
+        while let Some((documents, next)) = collection
+            .fetch_after(&self.search_repository, cursor, CHUNK_SIZE)
+            .await?
+        {
+            self.typesense.import(name, &documents).await?;
+            cursor = next;
+
+            if is_counted {
+                self.progress_store
+                    .record_chunk(collection.alias(), documents.len())
+                    .await?;
+            }
+
+            tokio::time::sleep(CHUNK_PAUSE).await;
+        }
+
+        Ok(())
+    }
+
+    async fn swap(&self, collection: SearchCollection, name: &str) -> Result<(), anyhow::Error> {
+        let alias = collection.alias();
+        let previous = self.typesense.get_alias(alias).await?;
+
+        // Indexes from before aliases were used are a plain collection under the alias name
+        if previous.is_none() && self.typesense.collection_exists(alias).await? {
+            self.typesense.delete_collection(alias).await?;
+        }
+
+        self.typesense.upsert_alias(alias, name).await?;
+
+        if let Some(previous) = previous {
+            self.typesense.delete_collection(&previous).await?;
+        }
+
+        Ok(())
+    }
+}
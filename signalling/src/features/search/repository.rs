@@ -0,0 +1,314 @@
+use chrono::NaiveDateTime;
+use diesel::{
+    BoolExpressionMethods, ExpressionMethods, JoinOnDsl, NullableExpressionMethods, PgConnection,
+    PgTextExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper,
+    r2d2::{ConnectionManager, Pool, PooledConnection},
+};
+use salvo::async_trait;
+
+use crate::core::{
+    database::{
+        db::checkout,
+        schema::{members, messages, rooms, users},
+        soft_delete::{DeletedScope, rooms_in_scope, users_in_scope},
+    },
+    entities::models::{Message, MessagesStatusEnum, Room, User},
+    types::{
+        errors::{general::GeneralError, search_error::SearchError},
+        responses::message_response::MessageResponse,
+    },
+};
+
+/// How rows are matched: by text in Postgres, or by the ids Typesense found
+#[derive(Debug, Clone, Copy)]
+pub enum SearchFilter<'a> {
+    Text(&'a str),
+    Ids(&'a [i32]),
+}
+
+/// Position of a row in the index sync, rows are visited by `(updated_at, id)`
+pub type SyncCursor = (NaiveDateTime, i32);
+
+/// Rows a rebuild has to visit, deleted ones included
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RowCounts {
+    pub users: i64,
+    pub rooms: i64,
+    pub messages: i64,
+}
+
+/// Searches only return what the user could see anyway: rooms they are a member
+/// of, and messages of those rooms after they last cleared the conversation.
+#[async_trait]
+pub trait SearchRepository: Send + Sync {
+    async fn search_users(
+        &self,
+        filter: SearchFilter<'_>,
+        skip: i64,
+        limit: i64,
+    ) -> Result<Vec<User>, SearchError>;
+
+    async fn search_rooms(
+        &self,
+        user_id: i32,
+        filter: SearchFilter<'_>,
+        skip: i64,
+        limit: i64,
+    ) -> Result<Vec<Room>, SearchError>;
+
+    async fn search_messages(
+        &self,
+        user_id: i32,
+        filter: SearchFilter<'_>,
+        skip: i64,
+        limit: i64,
+    ) -> Result<Vec<MessageResponse>, SearchError>;
+
+    async fn find_room_ids(&self, user_id: i32) -> Result<Vec<i32>, SearchError>;
+
+    async fn find_users_after(
+        &self,
+        cursor: SyncCursor,
+        limit: i64,
+    ) -> Result<Vec<User>, SearchError>;
+
+    async fn find_rooms_after(
+        &self,
+        cursor: SyncCursor,
+        limit: i64,
+    ) -> Result<Vec<Room>, SearchError>;
+
+    async fn find_messages_after(
+        &self,
+        cursor: SyncCursor,
+        limit: i64,
+    ) -> Result<Vec<Message>, SearchError>;
+
+    async fn count_rows(&self) -> Result<RowCounts, SearchError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchRepositoryImpl {
+    pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl SearchRepositoryImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Self {
+        Self { pool }
+    }
+
+    fn get_conn(&self) -> Result<PooledConnection<ConnectionManager<PgConnection>>, GeneralError> {
+        checkout(&self.pool)
+    }
+}
+
+#[async_trait]
+impl SearchRepository for SearchRepositoryImpl {
+    async fn search_users(
+        &self,
+        filter: SearchFilter<'_>,
+        skip: i64,
+        limit: i64,
+    ) -> Result<Vec<User>, SearchError> {
+        let mut conn = self.get_conn()?;
+
+        let mut statement = users::table
+            .filter(users_in_scope(DeletedScope::Exclude))
+            .select(User::as_select())
+            .into_boxed();
+
+        statement = match filter {
+            SearchFilter::Text(query) => {
+                let pattern = like_pattern(query);
+                statement.filter(
+                    users::user_name
+                        .ilike(pattern.clone())
+                        .or(users::full_name.ilike(pattern)),
+                )
+            }
+            SearchFilter::Ids(ids) => statement.filter(users::id.eq_any(ids)),
+        };
+
+        statement
+            .order(users::id.asc())
+            .offset(skip)
+            .limit(limit)
+            .load(&mut conn)
+            .map_err(|err| SearchError::UnexpectedError(err.to_string()))
+    }
+
+    async fn search_rooms(
+        &self,
+        user_id: i32,
+        filter: SearchFilter<'_>,
+        skip: i64,
+        limit: i64,
+    ) -> Result<Vec<Room>, SearchError> {
+        let mut conn = self.get_conn()?;
+
+        let mut statement = rooms::table
+            .inner_join(members::table.on(members::room_id.eq(rooms::id)))
+            .filter(members::user_id.eq(user_id))
+            .filter(rooms_in_scope(DeletedScope::Exclude))
+            .select(Room::as_select())
+            .into_boxed();
+
+        statement = match filter {
+            SearchFilter::Text(query) => statement.filter(rooms::title.ilike(like_pattern(query))),
+            SearchFilter::Ids(ids) => statement.filter(rooms::id.eq_any(ids)),
+        };
+
+        statement
+            .order(rooms::updated_at.desc())
+            .offset(skip)
+            .limit(limit)
+            .load(&mut conn)
+            .map_err(|err| SearchError::UnexpectedError(err.to_string()))
+    }
+
+    async fn search_messages(
+        &self,
+        user_id: i32,
+        filter: SearchFilter<'_>,
+        skip: i64,
+        limit: i64,
+    ) -> Result<Vec<MessageResponse>, SearchError> {
+        let mut conn = self.get_conn()?;
+
+        let message_status: i16 = MessagesStatusEnum::Active.into();
+
+        let mut statement = messages::table
+            .inner_join(members::table.on(members::room_id.eq(messages::room_id)))
+            .inner_join(rooms::table.on(rooms::id.eq(messages::room_id)))
+            .inner_join(users::table.on(users::id.eq(messages::created_by_id)))
+            .filter(members::user_id.eq(user_id))
+            .filter(
+                members::soft_deleted_at
+                    .is_null()
+                    .or(messages::created_at.nullable().gt(members::soft_deleted_at)),
+            )
+            .filter(messages::status.eq(message_status))
+            .filter(rooms_in_scope(DeletedScope::Exclude))
+            .select((Message::as_select(), Room::as_select(), User::as_select()))
+            .into_boxed();
+
+        statement = match filter {
+            SearchFilter::Text(query) => {
+                statement.filter(messages::data.ilike(like_pattern(query)))
+            }
+            SearchFilter::Ids(ids) => statement.filter(messages::id.eq_any(ids)),
+        };
+
+        let messages = statement
+            .order(messages::created_at.desc())
+            .offset(skip)
+            .limit(limit)
+            .load::<(Message, Room, User)>(&mut conn)
+            .map_err(|err| SearchError::UnexpectedError(err.to_string()))?;
+
+        Ok(messages
+            .into_iter()
+            .map(|(message, room, user)| MessageResponse {
+                message,
+                created_by: Some(user),
+                room: Some(room),
+            })
+            .collect())
+    }
+
+    async fn find_room_ids(&self, user_id: i32) -> Result<Vec<i32>, SearchError> {
+        let mut conn = self.get_conn()?;
+
+        rooms::table
+            .inner_join(members::table.on(members::room_id.eq(rooms::id)))
+            .filter(members::user_id.eq(user_id))
+            .filter(rooms_in_scope(DeletedScope::Exclude))
+            .select(rooms::id)
+            .load(&mut conn)
+            .map_err(|err| SearchError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_users_after(
+        &self,
+        (updated_at, id): SyncCursor,
+        limit: i64,
+    ) -> Result<Vec<User>, SearchError> {
+        let mut conn = self.get_conn()?;
+
+        users::table
+            .filter(
+                users::updated_at
+                    .gt(updated_at)
+                    .or(users::updated_at.eq(updated_at).and(users::id.gt(id))),
+            )
+            .order((users::updated_at.asc(), users::id.asc()))
+            .limit(limit)
+            .select(User::as_select())
+            .load(&mut conn)
+            .map_err(|err| SearchError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_rooms_after(
+        &self,
+        (updated_at, id): SyncCursor,
+        limit: i64,
+    ) -> Result<Vec<Room>, SearchError> {
+        let mut conn = self.get_conn()?;
+
+        rooms::table
+            .filter(
+                rooms::updated_at
+                    .gt(updated_at)
+                    .or(rooms::updated_at.eq(updated_at).and(rooms::id.gt(id))),
+            )
+            .order((rooms::updated_at.asc(), rooms::id.asc()))
+            .limit(limit)
+            .select(Room::as_select())
+            .load(&mut conn)
+            .map_err(|err| SearchError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_messages_after(
+        &self,
+        (updated_at, id): SyncCursor,
+        limit: i64,
+    ) -> Result<Vec<Message>, SearchError> {
+        let mut conn = self.get_conn()?;
+
+        messages::table
+            .filter(
+                messages::updated_at
+                    .gt(updated_at)
+                    .or(messages::updated_at.eq(updated_at).and(messages::id.gt(id))),
+            )
+            .order((messages::updated_at.asc(), messages::id.asc()))
+            .limit(limit)
+            .select(Message::as_select())
+            .load(&mut conn)
+            .map_err(|err| SearchError::UnexpectedError(err.to_string()))
+    }
+
+    async fn count_rows(&self) -> Result<RowCounts, SearchError> {
+        let mut conn = self.get_conn()?;
+
+        let count = |result: Result<i64, diesel::result::Error>| {
+            result.map_err(|err| SearchError::UnexpectedError(err.to_string()))
+        };
+
+        Ok(RowCounts {
+            users: count(users::table.count().get_result(&mut conn))?,
+            rooms: count(rooms::table.count().get_result(&mut conn))?,
+            messages: count(messages::table.count().get_result(&mut conn))?,
+        })
+    }
+}
+
+/// Wildcards typed by the user match literally
+pub fn like_pattern(query: &str) -> String {
+    let escaped = query
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+
+    format!("%{escaped}%")
+}
@@ -0,0 +1,85 @@
+use salvo::{oapi::extract::QueryParam, prelude::*};
+
+use crate::{
+    core::{
+        dtos::common::pagination_dto::PaginationDto,
+        types::{
+            errors::search_error::SearchError,
+            responses::{
+                list_message_response::ListMessageResponse,
+                search_response::{SearchRoomResponse, SearchUserResponse},
+            },
+        },
+        utils::jwt_utils::JwtUtils,
+    },
+    features::search::repository::SearchRepositoryImpl,
+};
+
+use super::service::{SearchService, SearchServiceImpl};
+
+pub fn get_search_router(jwt_utils: JwtUtils) -> Router {
+    Router::with_hoop(jwt_utils.auth_middleware())
+        .path("search")
+        .push(Router::with_path("users").get(search_users))
+        .push(Router::with_path("rooms").get(search_rooms))
+        .push(Router::with_path("messages").get(search_messages))
+}
+
+/// Finds users by user name or full name.
+#[endpoint(tags("search"), status_codes(200, 400, 401, 500))]
+async fn search_users(
+    _res: &mut Response,
+    q: QueryParam<String, true>,
+    pagination_dto: PaginationDto,
+    depot: &mut Depot,
+) -> Result<SearchUserResponse, SearchError> {
+    let search_service = depot
+        .obtain::<SearchServiceImpl<SearchRepositoryImpl>>()
+        .unwrap();
+
+    let users = search_service
+        .search_users(&q.into_inner(), pagination_dto)
+        .await?;
+
+    Ok(SearchUserResponse { users })
+}
+
+/// Finds rooms the user is a member of by title.
+#[endpoint(tags("search"), status_codes(200, 400, 401, 500))]
+async fn search_rooms(
+    _res: &mut Response,
+    q: QueryParam<String, true>,
+    pagination_dto: PaginationDto,
+    depot: &mut Depot,
+) -> Result<SearchRoomResponse, SearchError> {
+    let search_service = depot
+        .obtain::<SearchServiceImpl<SearchRepositoryImpl>>()
+        .unwrap();
+    let user_id = depot.get::<String>("user_id").unwrap();
+
+    let rooms = search_service
+        .search_rooms(user_id.parse().unwrap(), &q.into_inner(), pagination_dto)
+        .await?;
+
+    Ok(SearchRoomResponse { rooms })
+}
+
+/// Finds messages in the user's rooms, deleted messages are left out.
+#[endpoint(tags("search"), status_codes(200, 400, 401, 500))]
+async fn search_messages(
+    _res: &mut Response,
+    q: QueryParam<String, true>,
+    pagination_dto: PaginationDto,
+    depot: &mut Depot,
+) -> Result<ListMessageResponse, SearchError> {
+    let search_service = depot
+        .obtain::<SearchServiceImpl<SearchRepositoryImpl>>()
+        .unwrap();
+    let user_id = depot.get::<String>("user_id").unwrap();
+
+    let messages = search_service
+        .search_messages(user_id.parse().unwrap(), &q.into_inner(), pagination_dto)
+        .await?;
+
+    Ok(ListMessageResponse { messages })
+}
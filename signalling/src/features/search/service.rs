@@ -0,0 +1,433 @@
+use salvo::async_trait;
+use tracing::warn;
+
+use crate::core::{
+    dtos::common::pagination_dto::PaginationDto,
+    entities::models::{Room, User},
+    types::{errors::search_error::SearchError, responses::message_response::MessageResponse},
+};
+
+use super::{
+    repository::{SearchFilter, SearchRepository},
+    typesense::{MESSAGES_COLLECTION, ROOMS_COLLECTION, TypesenseClient, USERS_COLLECTION},
+};
+
+/// Searches go to Typesense when it is configured and up, and to Postgres
+/// otherwise. Callers can't tell the difference beyond ranking.
+#[async_trait]
+pub trait SearchService: Send + Sync {
+    async fn search_users(
+        &self,
+        query: &str,
+        pagination_dto: PaginationDto,
+    ) -> Result<Vec<User>, SearchError>;
+
+    async fn search_rooms(
+        &self,
+        user_id: i32,
+        query: &str,
+        pagination_dto: PaginationDto,
+    ) -> Result<Vec<Room>, SearchError>;
+
+    async fn search_messages(
+        &self,
+        user_id: i32,
+        query: &str,
+        pagination_dto: PaginationDto,
+    ) -> Result<Vec<MessageResponse>, SearchError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchServiceImpl<S: SearchRepository> {
+    search_repository: S,
+    typesense: Option<TypesenseClient>,
+}
+
+impl<S: SearchRepository> SearchServiceImpl<S> {
+    pub fn new(search_repository: S) -> Self {
+        Self {
+            search_repository,
+            typesense: None,
+        }
+    }
+
+    pub fn with_typesense(mut self, typesense: TypesenseClient) -> Self {
+        self.typesense = Some(typesense);
+        self
+    }
+
+    /// Ids of the hits, `None` when Postgres has to answer instead
+    async fn _search_index(
+        &self,
+        collection: &str,
+        query: &str,
+        query_by: &str,
+        filter_by: Option<&str>,
+        pagination_dto: &PaginationDto,
+    ) -> Option<Vec<i32>> {
+        let typesense = self.typesense.as_ref().filter(|t| t.is_available())?;
+
+        match typesense
+            .search(
+                collection,
+                query,
+                query_by,
+                filter_by,
+                pagination_dto.skip,
+                pagination_dto.limit,
+            )
+            .await
+        {
+            Ok(ids) => Some(ids),
+            Err(err) => {
+                warn!("Typesense search failed, using Postgres: {:?}", err);
+                typesense.mark_unavailable();
+                None
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<S: SearchRepository> SearchService for SearchServiceImpl<S> {
+    async fn search_users(
+        &self,
+        query: &str,
+        pagination_dto: PaginationDto,
+    ) -> Result<Vec<User>, SearchError> {
+        let query = normalize_query(query)?;
+
+        let Some(ids) = self
+            ._search_index(
+                USERS_COLLECTION,
+                query,
+                "user_name,full_name",
+                None,
+                &pagination_dto,
+            )
+            .await
+        else {
+            return self
+                .search_repository
+                .search_users(
+                    SearchFilter::Text(query),
+                    pagination_dto.skip,
+                    pagination_dto.limit,
+                )
+                .await;
+        };
+
+        let users = self
+            .search_repository
+            .search_users(SearchFilter::Ids(&ids), 0, ids.len() as i64)
+            .await?;
+
+        Ok(order_by_ids(users, &ids, |user| user.id))
+    }
+
+    async fn search_rooms(
+        &self,
+        user_id: i32,
+        query: &str,
+        pagination_dto: PaginationDto,
+    ) -> Result<Vec<Room>, SearchError> {
+        let query = normalize_query(query)?;
+
+        let room_ids = self.search_repository.find_room_ids(user_id).await?;
+        if room_ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let Some(ids) = self
+            ._search_index(
+                ROOMS_COLLECTION,
+                query,
+                "title",
+                Some(&room_filter(&room_ids)),
+                &pagination_dto,
+            )
+            .await
+        else {
+            return self
+                .search_repository
+                .search_rooms(
+                    user_id,
+                    SearchFilter::Text(query),
+                    pagination_dto.skip,
+                    pagination_dto.limit,
+                )
+                .await;
+        };
+
+        let rooms = self
+            .search_repository
+            .search_rooms(user_id, SearchFilter::Ids(&ids), 0, ids.len() as i64)
+            .await?;
+
+        Ok(order_by_ids(rooms, &ids, |room| room.id))
+    }
+
+    async fn search_messages(
+        &self,
+        user_id: i32,
+        query: &str,
+        pagination_dto: PaginationDto,
+    ) -> Result<Vec<MessageResponse>, SearchError> {
+        let query = normalize_query(query)?;
+
+        let room_ids = self.search_repository.find_room_ids(user_id).await?;
+        if room_ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        // Deleted messages stay in the index with their status, they are filtered here
+        let filter_by = format!("{} && status:=0", room_filter(&room_ids));
+
+        let Some(ids) = self
+            ._search_index(
+                MESSAGES_COLLECTION,
+                query,
+                "data",
+                Some(&filter_by),
+                &pagination_dto,
+            )
+            .await
+        else {
+            return self
+                .search_repository
+                .search_messages(
+                    user_id,
+                    SearchFilter::Text(query),
+                    pagination_dto.skip,
+                    pagination_dto.limit,
+                )
+                .await;
+        };
+
+        let messages = self
+            .search_repository
+            .search_messages(user_id, SearchFilter::Ids(&ids), 0, ids.len() as i64)
+            .await?;
+
+        Ok(order_by_ids(messages, &ids, |message| message.message.id))
+    }
+}
+
+fn normalize_query(query: &str) -> Result<&str, SearchError> {
+    let query = query.trim();
+
+    if query.is_empty() {
+        return Err(SearchError::EmptyQuery);
+    }
+
+    Ok(query)
+}
+
+/// Membership is looked up in Postgres, the index doesn't know who is in which room
+fn room_filter(room_ids: &[i32]) -> String {
+    let room_ids = room_ids
+        .iter()
+        .map(i32::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+
+    format!("room_id:[{room_ids}]")
+}
+
+/// Rows come back from Postgres in its own order, hits are ranked by relevance
+fn order_by_ids<T>(mut rows: Vec<T>, ids: &[i32], id_of: impl Fn(&T) -> i32) -> Vec<T> {
+    rows.sort_by_key(|row| {
+        ids.iter()
+            .position(|id| *id == id_of(row))
+            .unwrap_or(usize::MAX)
+    });
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::entities::models::Message;
+    use crate::core::env::app_env::TypesenseConfigs;
+    use crate::features::search::repository::{RowCounts, SyncCursor, like_pattern};
+    use chrono::DateTime;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct MockSearchRepository {
+        users: Vec<User>,
+        text_queries: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl SearchRepository for MockSearchRepository {
+        async fn search_users(
+            &self,
+            filter: SearchFilter<'_>,
+            _skip: i64,
+            _limit: i64,
+        ) -> Result<Vec<User>, SearchError> {
+            match filter {
+                SearchFilter::Text(query) => {
+                    self.text_queries.lock().unwrap().push(query.to_string());
+                    Ok(self
+                        .users
+                        .iter()
+                        .filter(|user| user.user_name.contains(query))
+                        .cloned()
+                        .collect())
+                }
+                SearchFilter::Ids(ids) => Ok(self
+                    .users
+                    .iter()
+                    .filter(|user| ids.contains(&user.id))
+                    .cloned()
+                    .collect()),
+            }
+        }
+
+        async fn search_rooms(
+            &self,
+            _user_id: i32,
+            _filter: SearchFilter<'_>,
+            _skip: i64,
+            _limit: i64,
+        ) -> Result<Vec<Room>, SearchError> {
+            Ok(vec![])
+        }
+
+        async fn search_messages(
+            &self,
+            _user_id: i32,
+            _filter: SearchFilter<'_>,
+            _skip: i64,
+            _limit: i64,
+        ) -> Result<Vec<MessageResponse>, SearchError> {
+            Ok(vec![])
+        }
+
+        async fn find_room_ids(&self, _user_id: i32) -> Result<Vec<i32>, SearchError> {
+            Ok(vec![])
+        }
+
+        async fn find_users_after(
+            &self,
+            _cursor: SyncCursor,
+            _limit: i64,
+        ) -> Result<Vec<User>, SearchError> {
+            Ok(vec![])
+        }
+
+        async fn find_rooms_after(
+            &self,
+            _cursor: SyncCursor,
+            _limit: i64,
+        ) -> Result<Vec<Room>, SearchError> {
+            Ok(vec![])
+        }
+
+        async fn find_messages_after(
+            &self,
+            _cursor: SyncCursor,
+            _limit: i64,
+        ) -> Result<Vec<Message>, SearchError> {
+            Ok(vec![])
+        }
+
+        async fn count_rows(&self) -> Result<RowCounts, SearchError> {
+            Ok(RowCounts::default())
+        }
+    }
+
+    fn sample_user(id: i32, user_name: &str) -> User {
+        let now = DateTime::from_timestamp(0, 0).unwrap().naive_utc();
+        User {
+            id,
+            full_name: None,
+            user_name: user_name.to_string(),
+            bio: None,
+            external_id: format!("ext{id}"),
+            avatar: None,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+            last_seen_at: None,
+            public_id: format!("U{id:025}"),
+            locale: "en".to_string(),
+            timezone: "UTC".to_string(),
+        }
+    }
+
+    fn pagination() -> PaginationDto {
+        PaginationDto { skip: 0, limit: 10 }
+    }
+
+    #[tokio::test]
+    async fn test_search_without_typesense_uses_postgres() {
+        let repository = MockSearchRepository {
+            users: vec![sample_user(1, "alice"), sample_user(2, "bob")],
+            ..Default::default()
+        };
+        let service = SearchServiceImpl::new(repository.clone());
+
+        let users = service.search_users(" ali ", pagination()).await.unwrap();
+
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].user_name, "alice");
+        assert_eq!(*repository.text_queries.lock().unwrap(), vec!["ali"]);
+    }
+
+    #[tokio::test]
+    async fn test_search_falls_back_when_typesense_is_down() {
+        let repository = MockSearchRepository {
+            users: vec![sample_user(1, "alice")],
+            ..Default::default()
+        };
+        // Nothing listens on the discard port
+        let typesense = TypesenseClient::new(TypesenseConfigs {
+            url: "http://127.0.0.1:9".to_string(),
+            api_key: "key".to_string(),
+        });
+        let service = SearchServiceImpl::new(repository.clone()).with_typesense(typesense.clone());
+
+        let users = service.search_users("alice", pagination()).await.unwrap();
+
+        assert_eq!(users.len(), 1);
+        assert!(!typesense.is_available());
+        assert_eq!(repository.text_queries.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_search_rejects_empty_query() {
+        let service = SearchServiceImpl::new(MockSearchRepository::default());
+
+        let result = service.search_users("   ", pagination()).await;
+
+        assert!(matches!(result, Err(SearchError::EmptyQuery)));
+    }
+
+    #[test]
+    fn test_like_pattern_escapes_wildcards() {
+        assert_eq!(like_pattern("50%_off"), "%50\\%\\_off%");
+        assert_eq!(like_pattern("a\\b"), "%a\\\\b%");
+    }
+
+    #[test]
+    fn test_hits_keep_relevance_order() {
+        let users = vec![
+            sample_user(1, "a"),
+            sample_user(2, "b"),
+            sample_user(3, "c"),
+        ];
+
+        let ordered = order_by_ids(users, &[3, 1, 2], |user| user.id);
+
+        let ids: Vec<i32> = ordered.iter().map(|user| user.id).collect();
+        assert_eq!(ids, vec![3, 1, 2]);
+    }
+
+    #[test]
+    fn test_room_filter() {
+        assert_eq!(room_filter(&[1, 2, 3]), "room_id:[1,2,3]");
+    }
+}
@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use tracing::{info, warn};
+
+use crate::core::types::errors::search_error::SearchError;
+
+use super::{
+    collection::SearchCollection,
+    reindex::SearchReindexer,
+    repository::{SearchRepository, SyncCursor},
+    typesense::TypesenseClient,
+};
+
+/// Changes show up in search within this long
+const SYNC_INTERVAL: Duration = Duration::from_secs(10);
+
+const CHUNK_SIZE: i64 = 500;
+
+/// Keeps Typesense in step with Postgres by upserting the rows changed since
+/// the last pass. It only follows changes from the time it started, filling
+/// the index from scratch is left to [`SearchReindexer`], which it kicks off
+/// when the index doesn't exist yet.
+///
+/// Every signalling node runs one, upserts are idempotent.
+pub struct SearchSync<S: SearchRepository> {
+    search_repository: S,
+    typesense: TypesenseClient,
+    reindexer: SearchReindexer<S>,
+}
+
+impl<S: SearchRepository + Clone + 'static> SearchSync<S> {
+    pub fn new(
+        search_repository: S,
+        typesense: TypesenseClient,
+        reindexer: SearchReindexer<S>,
+    ) -> Self {
+        Self {
+            search_repository,
+            typesense,
+            reindexer,
+        }
+    }
+
+    pub fn spawn(self) {
+        tokio::spawn(async move {
+            // A rebuild picks up everything before this
+            let start = (Utc::now().naive_utc(), 0);
+            let mut cursors = SearchCollection::ALL.map(|collection| (collection, start));
+            let mut has_index = false;
+            let mut has_requested_reindex = false;
+
+            loop {
+                if !has_index {
+                    match self.has_index().await {
+                        Ok(true) => {
+                            info!("Typesense index is ready");
+                            has_index = true;
+                        }
+                        Ok(false) if !has_requested_reindex => {
+                            has_requested_reindex = true;
+                            match self.reindexer.start().await {
+                                Ok(()) => info!("Typesense index is missing, rebuilding it"),
+                                Err(SearchError::ReindexInProgress) => {}
+                                Err(err) => warn!("Failed to start a search reindex: {:?}", err),
+                            }
+                        }
+                        Ok(false) => {}
+                        Err(err) => {
+                            warn!("Typesense is unreachable, search uses Postgres: {:?}", err)
+                        }
+                    }
+                }
+
+                if has_index && let Err(err) = self.sync(&mut cursors).await {
+                    warn!("Failed to sync the search index: {:?}", err);
+                }
+
+                tokio::time::sleep(SYNC_INTERVAL).await;
+            }
+        });
+    }
+
+    /// Searches would fail until every alias points at a collection
+    async fn has_index(&self) -> Result<bool, anyhow::Error> {
+        for collection in SearchCollection::ALL {
+            if self
+                .typesense
+                .get_alias(collection.alias())
+                .await?
+                .is_none()
+            {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Cursors only move past rows that made it into the index
+    async fn sync(
+        &self,
+        cursors: &mut [(SearchCollection, SyncCursor); 3],
+    ) -> Result<(), anyhow::Error> {
+        for (collection, cursor) in cursors.iter_mut() {
+            while let Some((documents, next)) = collection
+                .fetch_after(&self.search_repository, *cursor, CHUNK_SIZE)
+                .await?
+            {
+                self.typesense
+                    .import(collection.alias(), &documents)
+                    .await?;
+                *cursor = next;
+            }
+        }
+
+        Ok(())
+    }
+}
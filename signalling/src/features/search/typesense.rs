@@ -0,0 +1,229 @@
+use std::{
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicI64, Ordering},
+    },
+    time::Duration,
+};
+
+use chrono::Utc;
+use reqwest::{Method, StatusCode};
+use serde_json::{Value, json};
+
+use crate::core::env::app_env::TypesenseConfigs;
+
+pub const USERS_COLLECTION: &str = "users";
+pub const ROOMS_COLLECTION: &str = "rooms";
+pub const MESSAGES_COLLECTION: &str = "messages";
+
+/// A search waits at most this long before falling back to Postgres
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// After a failure Typesense is left alone for a while, so every search
+/// doesn't pay the timeout while it is down
+const RETRY_AFTER_SECS: i64 = 30;
+
+/// Indexing a chunk is slower than a search, and nobody is waiting on it
+const IMPORT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Typesense over its HTTP API. Documents only carry the searchable text and
+/// the row id, hits are loaded back from Postgres.
+///
+/// `users`, `rooms` and `messages` are aliases, each rebuild fills a fresh
+/// collection and swaps the alias over once it is complete.
+#[derive(Clone)]
+pub struct TypesenseClient {
+    http: reqwest::Client,
+    configs: TypesenseConfigs,
+    unavailable_until: Arc<AtomicI64>,
+}
+
+impl fmt::Debug for TypesenseClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypesenseClient")
+            .field("url", &self.configs.url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TypesenseClient {
+    pub fn new(configs: TypesenseConfigs) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            configs,
+            unavailable_until: Arc::new(AtomicI64::new(0)),
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        Utc::now().timestamp() >= self.unavailable_until.load(Ordering::Relaxed)
+    }
+
+    pub fn mark_unavailable(&self) {
+        self.unavailable_until
+            .store(Utc::now().timestamp() + RETRY_AFTER_SECS, Ordering::Relaxed);
+    }
+
+    /// Ids of the matching rows, best match first
+    pub async fn search(
+        &self,
+        collection: &str,
+        query: &str,
+        query_by: &str,
+        filter_by: Option<&str>,
+        skip: i64,
+        limit: i64,
+    ) -> Result<Vec<i32>, anyhow::Error> {
+        let mut params = vec![
+            ("q", query.to_owned()),
+            ("query_by", query_by.to_owned()),
+            ("include_fields", "id".to_owned()),
+            ("offset", skip.to_string()),
+            ("limit", limit.to_string()),
+        ];
+        if let Some(filter_by) = filter_by {
+            params.push(("filter_by", filter_by.to_owned()));
+        }
+
+        let response: Value = self
+            .request(
+                Method::GET,
+                &format!("/collections/{collection}/documents/search"),
+            )
+            .query(&params)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(response["hits"]
+            .as_array()
+            .map(|hits| {
+                hits.iter()
+                    .filter_map(|hit| hit["document"]["id"].as_str()?.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Whether a collection, not an alias, goes by this name
+    pub async fn collection_exists(&self, collection: &str) -> Result<bool, anyhow::Error> {
+        let response = self
+            .request(Method::GET, &format!("/collections/{collection}"))
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+
+        let response: Value = response.error_for_status()?.json().await?;
+
+        Ok(response["name"].as_str() == Some(collection))
+    }
+
+    pub async fn create_collection(
+        &self,
+        collection: &str,
+        fields: Value,
+    ) -> Result<(), anyhow::Error> {
+        self.request(Method::POST, "/collections")
+            .json(&json!({ "name": collection, "fields": fields }))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Deleting a collection that is already gone is not an error
+    pub async fn delete_collection(&self, collection: &str) -> Result<(), anyhow::Error> {
+        let response = self
+            .request(Method::DELETE, &format!("/collections/{collection}"))
+            .send()
+            .await?;
+
+        if response.status() != StatusCode::NOT_FOUND {
+            response.error_for_status()?;
+        }
+
+        Ok(())
+    }
+
+    /// The collection an alias points at, `None` when there is no such alias
+    pub async fn get_alias(&self, alias: &str) -> Result<Option<String>, anyhow::Error> {
+        let response = self
+            .request(Method::GET, &format!("/aliases/{alias}"))
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let response: Value = response.error_for_status()?.json().await?;
+
+        Ok(response["collection_name"].as_str().map(str::to_owned))
+    }
+
+    /// Points the alias at the collection, searches follow it right away
+    pub async fn upsert_alias(&self, alias: &str, collection: &str) -> Result<(), anyhow::Error> {
+        self.request(Method::PUT, &format!("/aliases/{alias}"))
+            .json(&json!({ "collection_name": collection }))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Upserts the documents in one request, every document needs an `id`
+    pub async fn import(&self, collection: &str, documents: &[Value]) -> Result<(), anyhow::Error> {
+        if documents.is_empty() {
+            return Ok(());
+        }
+
+        let body = documents
+            .iter()
+            .map(Value::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        // Results come back one JSON line per document, the status is 200 either way
+        let results = self
+            .request(
+                Method::POST,
+                &format!("/collections/{collection}/documents/import"),
+            )
+            .query(&[("action", "upsert")])
+            .timeout(IMPORT_TIMEOUT)
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        let failed = results
+            .lines()
+            .filter(|line| !line.contains("\"success\":true"))
+            .count();
+
+        if failed > 0 {
+            anyhow::bail!("{failed} of {} documents failed to import", documents.len());
+        }
+
+        Ok(())
+    }
+
+    fn request(&self, method: Method, path: &str) -> reqwest::RequestBuilder {
+        self.http
+            .request(method, format!("{}{}", self.configs.url, path))
+            .header("X-TYPESENSE-API-KEY", &self.configs.api_key)
+    }
+}
@@ -0,0 +1,194 @@
+use chrono::NaiveDateTime;
+use diesel::{
+    ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper,
+    dsl::{exists, insert_into, not, update},
+    r2d2::{ConnectionManager, Pool, PooledConnection},
+};
+use salvo::async_trait;
+
+use crate::core::{
+    database::{
+        db::checkout,
+        schema::{participants, room_events, room_sessions, users},
+    },
+    entities::models::{NewRoomEvent, NewRoomSession, RoomEvent, RoomSession, User},
+    types::errors::{general::GeneralError, timeline_error::TimelineError},
+};
+
+#[async_trait]
+pub trait TimelineRepository: Send + Sync {
+    /// Starts a session for the room unless one is in progress already, and
+    /// returns whichever is open
+    async fn open_session(
+        &self,
+        room_id: i32,
+        now: NaiveDateTime,
+    ) -> Result<RoomSession, TimelineError>;
+
+    async fn find_open_session(&self, room_id: i32) -> Result<Option<RoomSession>, TimelineError>;
+
+    /// Ends the open session once nobody is left in the room, `None` otherwise
+    async fn close_session_if_empty(
+        &self,
+        room_id: i32,
+        now: NaiveDateTime,
+    ) -> Result<Option<RoomSession>, TimelineError>;
+
+    async fn create_event(&self, event: NewRoomEvent<'_>) -> Result<RoomEvent, TimelineError>;
+
+    async fn find_session(
+        &self,
+        room_id: i32,
+        public_id: &str,
+    ) -> Result<Option<RoomSession>, TimelineError>;
+
+    /// Newest first
+    async fn find_sessions(
+        &self,
+        room_id: i32,
+        skip: i64,
+        limit: i64,
+    ) -> Result<Vec<RoomSession>, TimelineError>;
+
+    /// In the order they happened
+    async fn find_events(
+        &self,
+        session_id: i32,
+    ) -> Result<Vec<(RoomEvent, Option<User>)>, TimelineError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct TimelineRepositoryImpl {
+    pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl TimelineRepositoryImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Self {
+        Self { pool }
+    }
+
+    fn get_conn(&self) -> Result<PooledConnection<ConnectionManager<PgConnection>>, GeneralError> {
+        checkout(&self.pool)
+    }
+}
+
+#[async_trait]
+impl TimelineRepository for TimelineRepositoryImpl {
+    async fn open_session(
+        &self,
+        room_id: i32,
+        now: NaiveDateTime,
+    ) -> Result<RoomSession, TimelineError> {
+        let mut conn = self.get_conn()?;
+
+        // Two first joins race here, the partial unique index keeps one open
+        insert_into(room_sessions::table)
+            .values(&NewRoomSession {
+                room_id,
+                started_at: now,
+            })
+            .on_conflict_do_nothing()
+            .execute(&mut conn)
+            .map_err(|err| TimelineError::UnexpectedError(err.to_string()))?;
+
+        room_sessions::table
+            .filter(room_sessions::room_id.eq(room_id))
+            .filter(room_sessions::ended_at.is_null())
+            .select(RoomSession::as_select())
+            .first::<RoomSession>(&mut conn)
+            .map_err(|err| TimelineError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_open_session(&self, room_id: i32) -> Result<Option<RoomSession>, TimelineError> {
+        let mut conn = self.get_conn()?;
+
+        room_sessions::table
+            .filter(room_sessions::room_id.eq(room_id))
+            .filter(room_sessions::ended_at.is_null())
+            .select(RoomSession::as_select())
+            .first::<RoomSession>(&mut conn)
+            .optional()
+            .map_err(|err| TimelineError::UnexpectedError(err.to_string()))
+    }
+
+    async fn close_session_if_empty(
+        &self,
+        room_id: i32,
+        now: NaiveDateTime,
+    ) -> Result<Option<RoomSession>, TimelineError> {
+        let mut conn = self.get_conn()?;
+
+        update(room_sessions::table)
+            .filter(room_sessions::room_id.eq(room_id))
+            .filter(room_sessions::ended_at.is_null())
+            .filter(not(exists(
+                participants::table
+                    .filter(participants::room_id.eq(room_id))
+                    .filter(participants::deleted_at.is_null()),
+            )))
+            .set(room_sessions::ended_at.eq(now))
+            .returning(RoomSession::as_select())
+            .get_result(&mut conn)
+            .optional()
+            .map_err(|err| TimelineError::UnexpectedError(err.to_string()))
+    }
+
+    async fn create_event(&self, event: NewRoomEvent<'_>) -> Result<RoomEvent, TimelineError> {
+        let mut conn = self.get_conn()?;
+
+        insert_into(room_events::table)
+            .values(&event)
+            .returning(RoomEvent::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| TimelineError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_session(
+        &self,
+        room_id: i32,
+        public_id: &str,
+    ) -> Result<Option<RoomSession>, TimelineError> {
+        let mut conn = self.get_conn()?;
+
+        room_sessions::table
+            .filter(room_sessions::room_id.eq(room_id))
+            .filter(room_sessions::public_id.eq(public_id))
+            .select(RoomSession::as_select())
+            .first::<RoomSession>(&mut conn)
+            .optional()
+            .map_err(|err| TimelineError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_sessions(
+        &self,
+        room_id: i32,
+        skip: i64,
+        limit: i64,
+    ) -> Result<Vec<RoomSession>, TimelineError> {
+        let mut conn = self.get_conn()?;
+
+        room_sessions::table
+            .filter(room_sessions::room_id.eq(room_id))
+            .select(RoomSession::as_select())
+            .order((room_sessions::started_at.desc(), room_sessions::id.desc()))
+            .offset(skip)
+            .limit(limit)
+            .load::<RoomSession>(&mut conn)
+            .map_err(|err| TimelineError::UnexpectedError(err.to_string()))
+    }
+
+    async fn find_events(
+        &self,
+        session_id: i32,
+    ) -> Result<Vec<(RoomEvent, Option<User>)>, TimelineError> {
+        let mut conn = self.get_conn()?;
+
+        room_events::table
+            .left_join(users::table)
+            .filter(room_events::session_id.eq(session_id))
+            .select((RoomEvent::as_select(), Option::<User>::as_select()))
+            .order((room_events::created_at.asc(), room_events::id.asc()))
+            .load::<(RoomEvent, Option<User>)>(&mut conn)
+            .map_err(|err| TimelineError::UnexpectedError(err.to_string()))
+    }
+}
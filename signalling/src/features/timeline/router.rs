@@ -0,0 +1,75 @@
+use salvo::{oapi::extract::PathParam, prelude::*};
+
+use crate::{
+    core::{
+        dtos::common::pagination_dto::PaginationDto,
+        types::{
+            errors::timeline_error::TimelineError,
+            responses::room_timeline_response::{ListRoomSessionResponse, RoomTimelineResponse},
+        },
+        utils::jwt_utils::JwtUtils,
+    },
+    features::{
+        room::{
+            repository::RoomRepositoryImpl,
+            service::{RoomService, RoomServiceImpl},
+        },
+        user::repository::UserRepositoryImpl,
+    },
+};
+
+use super::service::{RoomTimelines, TimelineService};
+
+pub fn get_timeline_router(jwt_utils: JwtUtils) -> Router {
+    Router::with_hoop(jwt_utils.auth_middleware())
+        .path("rooms/{room_id}/sessions")
+        .get(get_sessions)
+        .push(Router::with_path("{session_id}/timeline").get(get_timeline))
+}
+
+/// Past and ongoing sessions of the room, newest first
+#[endpoint(tags("timeline"), status_codes(200, 401, 403, 404, 500))]
+async fn get_sessions(
+    _res: &mut Response,
+    room_id: PathParam<String>,
+    pagination_dto: PaginationDto,
+    depot: &mut Depot,
+) -> Result<ListRoomSessionResponse, TimelineError> {
+    let timeline_service = depot.obtain::<RoomTimelines>().unwrap();
+    let user_id = depot.get::<String>("user_id").unwrap();
+    let room_id = resolve_room_id(depot, &room_id.into_inner()).await?;
+
+    let sessions = timeline_service
+        .get_sessions(room_id, user_id.parse().unwrap(), pagination_dto)
+        .await?;
+
+    Ok(ListRoomSessionResponse { sessions })
+}
+
+/// Joins, leaves, mutes, screen shares, recording marks and chat messages of
+/// one session in the order they happened. Members see every session, guests
+/// the ones they joined.
+#[endpoint(tags("timeline"), status_codes(200, 401, 403, 404, 500))]
+async fn get_timeline(
+    _res: &mut Response,
+    room_id: PathParam<String>,
+    session_id: PathParam<String>,
+    depot: &mut Depot,
+) -> Result<RoomTimelineResponse, TimelineError> {
+    let timeline_service = depot.obtain::<RoomTimelines>().unwrap();
+    let user_id = depot.get::<String>("user_id").unwrap();
+    let room_id = resolve_room_id(depot, &room_id.into_inner()).await?;
+
+    timeline_service
+        .get_timeline(room_id, &session_id.into_inner(), user_id.parse().unwrap())
+        .await
+}
+
+async fn resolve_room_id(depot: &Depot, room_id: &str) -> Result<i32, TimelineError> {
+    depot
+        .obtain::<RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>>()
+        .unwrap()
+        .resolve_room_id(room_id)
+        .await
+        .map_err(|_| TimelineError::RoomNotFound(room_id.to_string()))
+}
@@ -0,0 +1,268 @@
+use chrono::Utc;
+use salvo::async_trait;
+
+use crate::{
+    core::{
+        dtos::common::pagination_dto::PaginationDto,
+        entities::models::{NewRoomEvent, RoomEvent, RoomSession},
+        types::{
+            enums::room_event_kind::RoomEventKind,
+            errors::timeline_error::TimelineError,
+            responses::room_timeline_response::{RoomTimelineResponse, TimelineEventResponse},
+        },
+    },
+    features::{
+        room::{
+            repository::RoomRepositoryImpl,
+            service::{RoomService, RoomServiceImpl},
+        },
+        user::repository::UserRepositoryImpl,
+    },
+};
+
+use super::repository::{TimelineRepository, TimelineRepositoryImpl};
+
+/// Room timelines as wired up against Postgres
+pub type RoomTimelines = TimelineServiceImpl<
+    TimelineRepositoryImpl,
+    RoomServiceImpl<RoomRepositoryImpl, UserRepositoryImpl>,
+>;
+
+/// Something that happened in a room, as the socket layer reports it
+#[derive(Debug, Clone)]
+pub struct TimelineEntry {
+    pub kind: RoomEventKind,
+    pub participant_id: Option<String>,
+    pub user_id: Option<i32>,
+    pub is_enabled: Option<bool>,
+    pub message_id: Option<i32>,
+}
+
+impl TimelineEntry {
+    pub fn new(kind: RoomEventKind) -> Self {
+        Self {
+            kind,
+            participant_id: None,
+            user_id: None,
+            is_enabled: None,
+            message_id: None,
+        }
+    }
+
+    pub fn participant(mut self, participant_id: &str) -> Self {
+        self.participant_id = Some(participant_id.to_string());
+        self
+    }
+
+    pub fn user(mut self, user_id: Option<i32>) -> Self {
+        self.user_id = user_id;
+        self
+    }
+
+    pub fn enabled(mut self, is_enabled: bool) -> Self {
+        self.is_enabled = Some(is_enabled);
+        self
+    }
+
+    pub fn message(mut self, message_id: i32) -> Self {
+        self.message_id = Some(message_id);
+        self
+    }
+}
+
+#[async_trait]
+pub trait TimelineService: Send + Sync {
+    /// Journals an event of the room's current session. A join starts a
+    /// session when none is open, the last leave ends it. Anything else
+    /// outside a session is dropped.
+    async fn record(&self, room_id: &str, entry: TimelineEntry) -> Result<(), TimelineError>;
+
+    async fn get_sessions(
+        &self,
+        room_id: i32,
+        user_id: i32,
+        pagination_dto: PaginationDto,
+    ) -> Result<Vec<RoomSession>, TimelineError>;
+
+    async fn get_timeline(
+        &self,
+        room_id: i32,
+        session_id: &str,
+        user_id: i32,
+    ) -> Result<RoomTimelineResponse, TimelineError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct TimelineServiceImpl<T: TimelineRepository, R: RoomService> {
+    timeline_repository: T,
+    room_service: R,
+}
+
+impl<T: TimelineRepository, R: RoomService> TimelineServiceImpl<T, R> {
+    pub fn new(timeline_repository: T, room_service: R) -> Self {
+        Self {
+            timeline_repository,
+            room_service,
+        }
+    }
+
+    async fn _is_member(&self, room_id: i32, user_id: i32) -> Result<bool, TimelineError> {
+        let room = self
+            .room_service
+            .get_room_by_id(room_id)
+            .await
+            .map_err(|_| TimelineError::RoomNotFound(room_id.to_string()))?;
+
+        Ok(room
+            .members
+            .iter()
+            .any(|member| member.member.user_id == user_id))
+    }
+}
+
+#[async_trait]
+impl<T: TimelineRepository, R: RoomService> TimelineService for TimelineServiceImpl<T, R> {
+    async fn record(&self, room_id: &str, entry: TimelineEntry) -> Result<(), TimelineError> {
+        let room_id = self
+            .room_service
+            .resolve_room_id(room_id)
+            .await
+            .map_err(|_| TimelineError::RoomNotFound(room_id.to_string()))?;
+        let now = Utc::now().naive_utc();
+
+        let session = match entry.kind {
+            RoomEventKind::ParticipantJoined => {
+                Some(self.timeline_repository.open_session(room_id, now).await?)
+            }
+            _ => self.timeline_repository.find_open_session(room_id).await?,
+        };
+
+        let Some(session) = session else {
+            return Ok(());
+        };
+
+        self.timeline_repository
+            .create_event(NewRoomEvent {
+                session_id: session.id,
+                kind: entry.kind.to_str(),
+                participant_id: entry.participant_id.as_deref(),
+                user_id: entry.user_id,
+                is_enabled: entry.is_enabled,
+                message_id: entry.message_id,
+                created_at: now,
+            })
+            .await?;
+
+        if entry.kind == RoomEventKind::ParticipantLeft {
+            self.timeline_repository
+                .close_session_if_empty(room_id, now)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn get_sessions(
+        &self,
+        room_id: i32,
+        user_id: i32,
+        pagination_dto: PaginationDto,
+    ) -> Result<Vec<RoomSession>, TimelineError> {
+        if !self._is_member(room_id, user_id).await? {
+            return Err(TimelineError::NotAMember);
+        }
+
+        self.timeline_repository
+            .find_sessions(room_id, pagination_dto.skip, pagination_dto.limit)
+            .await
+    }
+
+    async fn get_timeline(
+        &self,
+        room_id: i32,
+        session_id: &str,
+        user_id: i32,
+    ) -> Result<RoomTimelineResponse, TimelineError> {
+        let is_member = self._is_member(room_id, user_id).await?;
+
+        let session = self
+            .timeline_repository
+            .find_session(room_id, session_id)
+            .await?
+            .ok_or_else(|| TimelineError::SessionNotFound(session_id.to_string()))?;
+
+        let events = self.timeline_repository.find_events(session.id).await?;
+
+        // Guests of an open room review the sessions they were in
+        if !is_member && !has_attended(&events, user_id) {
+            return Err(TimelineError::NotAMember);
+        }
+
+        Ok(RoomTimelineResponse {
+            session,
+            events: events
+                .into_iter()
+                .map(|(event, user)| TimelineEventResponse { event, user })
+                .collect(),
+        })
+    }
+}
+
+fn has_attended<U>(events: &[(RoomEvent, U)], user_id: i32) -> bool {
+    events.iter().any(|(event, _)| {
+        event.kind == RoomEventKind::ParticipantJoined.to_str() && event.user_id == Some(user_id)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn sample_event(kind: RoomEventKind, user_id: Option<i32>) -> (RoomEvent, ()) {
+        let now = DateTime::from_timestamp(0, 0).unwrap().naive_utc();
+
+        (
+            RoomEvent {
+                id: 1,
+                session_id: 1,
+                kind: kind.to_str().to_string(),
+                participant_id: None,
+                user_id,
+                is_enabled: None,
+                message_id: None,
+                created_at: now,
+            },
+            (),
+        )
+    }
+
+    #[test]
+    fn test_joining_the_session_counts_as_attended() {
+        let events = vec![
+            sample_event(RoomEventKind::ParticipantJoined, Some(1)),
+            sample_event(RoomEventKind::Message, Some(2)),
+        ];
+
+        assert!(has_attended(&events, 1));
+        assert!(!has_attended(&events, 2));
+        assert!(!has_attended(&events, 3));
+    }
+
+    #[test]
+    fn test_timeline_entry_builder() {
+        let entry = TimelineEntry::new(RoomEventKind::Audio)
+            .participant("01J0000000000000000000000")
+            .user(Some(7))
+            .enabled(false);
+
+        assert_eq!(entry.kind, RoomEventKind::Audio);
+        assert_eq!(
+            entry.participant_id.as_deref(),
+            Some("01J0000000000000000000000")
+        );
+        assert_eq!(entry.user_id, Some(7));
+        assert_eq!(entry.is_enabled, Some(false));
+        assert_eq!(entry.message_id, None);
+    }
+}
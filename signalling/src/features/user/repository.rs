@@ -6,7 +6,11 @@ use diesel::{
 use salvo::async_trait;
 
 use crate::core::{
-    database::schema::users,
+    database::{
+        db::checkout,
+        schema::users,
+        soft_delete::{DeletedScope, users_in_scope},
+    },
     entities::models::User,
     types::errors::{general::GeneralError, user_error::UserError},
 };
@@ -14,6 +18,11 @@ use crate::core::{
 #[async_trait]
 pub trait UserRepository {
     async fn get_user_by_id(&self, user_id: i32) -> Result<User, UserError>;
+    async fn get_user_by_public_id(
+        &self,
+        public_id: &str,
+        scope: DeletedScope,
+    ) -> Result<User, UserError>;
     async fn update_user(&self, user: User) -> Result<User, UserError>;
     async fn get_username(&self, username: &str) -> Result<String, UserError>;
     async fn update_username(&self, user_id: i32, username: &str) -> Result<User, UserError>;
@@ -30,7 +39,7 @@ impl UserRepositoryImpl {
     }
 
     fn get_conn(&self) -> Result<PooledConnection<ConnectionManager<PgConnection>>, GeneralError> {
-        self.pool.get().map_err(|_| GeneralError::DbConnectionError)
+        checkout(&self.pool)
     }
 }
 
@@ -41,6 +50,7 @@ impl UserRepository for UserRepositoryImpl {
 
         let user = users::table
             .filter(users::id.eq(user_id))
+            .filter(users_in_scope(DeletedScope::Exclude))
             .first::<User>(&mut conn);
 
         match user {
@@ -49,15 +59,36 @@ impl UserRepository for UserRepositoryImpl {
         }
     }
 
+    async fn get_user_by_public_id(
+        &self,
+        public_id: &str,
+        scope: DeletedScope,
+    ) -> Result<User, UserError> {
+        let mut conn = self.get_conn()?;
+
+        let user = users::table
+            .filter(users::public_id.eq(public_id))
+            .filter(users_in_scope(scope))
+            .first::<User>(&mut conn);
+
+        match user {
+            Ok(user) => Ok(user),
+            Err(_) => Err(UserError::PublicIdNotFound(public_id.to_string())),
+        }
+    }
+
     async fn update_user(&self, user: User) -> Result<User, UserError> {
         let mut conn = self.get_conn()?;
 
         let updated_user = update(users::table)
             .filter(users::id.eq(user.id))
+            .filter(users_in_scope(DeletedScope::Exclude))
             .set((
                 users::full_name.eq(user.full_name),
                 users::avatar.eq(user.avatar),
                 users::bio.eq(user.bio),
+                users::locale.eq(user.locale),
+                users::timezone.eq(user.timezone),
             ))
             .returning(User::as_select())
             .get_result(&mut conn);
@@ -75,6 +106,7 @@ impl UserRepository for UserRepositoryImpl {
 
         let user_name = users::table
             .filter(users::user_name.eq(username))
+            .filter(users_in_scope(DeletedScope::Exclude))
             .select(users::user_name)
             .first::<String>(&mut conn);
 
@@ -89,6 +121,7 @@ impl UserRepository for UserRepositoryImpl {
 
         let updated_user = update(users::table)
             .filter(users::id.eq(user_id))
+            .filter(users_in_scope(DeletedScope::Exclude))
             .set(users::user_name.eq(username))
             .returning(User::as_select())
             .get_result(&mut conn);
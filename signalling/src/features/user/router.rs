@@ -1,17 +1,22 @@
+use std::time::Duration;
+
 use salvo::{
+    cache::{Cache, MokaStore, RequestIssuer},
+    http::header::{CACHE_CONTROL, CONTENT_TYPE},
     oapi::extract::{JsonBody, PathParam},
     prelude::*,
 };
 
 use crate::{
     core::{
+        database::soft_delete::DeletedScope,
         dtos::user::update_user_dto::UpdateUserDto,
         entities::models::User,
         types::{
             errors::user_error::UserError,
             responses::check_username_response::CheckUsernameResponse,
         },
-        utils::jwt_utils::JwtUtils,
+        utils::{avatar_utils::render_placeholder, jwt_utils::JwtUtils},
     },
     features::user::repository::UserRepositoryImpl,
 };
@@ -30,6 +35,48 @@ pub fn get_user_router(jwt_utils: JwtUtils) -> Router {
         )
 }
 
+/// Placeholder tiles are loaded by `<img>` tags, so they can't require the API
+/// key or a token. Rendered tiles are kept in memory, the url is versioned.
+pub fn get_avatar_router() -> Router {
+    let cache = Cache::new(
+        MokaStore::builder()
+            .max_capacity(10_000)
+            .time_to_live(Duration::from_secs(60 * 60))
+            .build(),
+        RequestIssuer::default(),
+    );
+
+    Router::with_path("avatars/{public_id}/placeholder.svg")
+        .hoop(cache)
+        .get(get_placeholder)
+}
+
+/// Initials tile shown for participants whose video is off
+#[handler]
+async fn get_placeholder(req: &mut Request, depot: &mut Depot, res: &mut Response) {
+    let user_service = depot
+        .obtain::<UserServiceImpl<UserRepositoryImpl>>()
+        .unwrap();
+
+    let Some(public_id) = req.param::<String>("public_id") else {
+        res.status_code(StatusCode::NOT_FOUND);
+        return;
+    };
+
+    match user_service
+        .get_user_by_public_id(&public_id, DeletedScope::Exclude)
+        .await
+    {
+        Ok(user) => {
+            res.add_header(CONTENT_TYPE, "image/svg+xml", true).ok();
+            res.add_header(CACHE_CONTROL, "public, max-age=31536000, immutable", true)
+                .ok();
+            res.render(render_placeholder(&user));
+        }
+        Err(err) => res.render(err),
+    }
+}
+
 /// Fetch user info
 #[endpoint(tags("user"), status_codes(200, 400, 404, 500))]
 async fn get_user_by_token(_res: &mut Response, depot: &mut Depot) -> Result<User, UserError> {
@@ -1,8 +1,11 @@
 use salvo::async_trait;
 
 use crate::core::{
-    dtos::user::update_user_dto::UpdateUserDto, entities::models::User,
+    database::soft_delete::DeletedScope,
+    dtos::user::update_user_dto::UpdateUserDto,
+    entities::models::User,
     types::errors::user_error::UserError,
+    utils::locale_utils::{is_valid_locale, parse_timezone},
 };
 
 use super::repository::UserRepository;
@@ -10,6 +13,11 @@ use super::repository::UserRepository;
 #[async_trait]
 pub trait UserService: Send + Sync {
     async fn get_user_by_id(&self, user_id: i32) -> Result<User, UserError>;
+    async fn get_user_by_public_id(
+        &self,
+        public_id: &str,
+        scope: DeletedScope,
+    ) -> Result<User, UserError>;
     async fn update_user(&self, user_id: i32, data: UpdateUserDto) -> Result<User, UserError>;
     async fn check_username_exists(&self, username: &str) -> bool;
     async fn update_username(&self, user_id: i32, username: &str) -> Result<User, UserError>;
@@ -33,6 +41,16 @@ impl<R: UserRepository + Send + Sync> UserService for UserServiceImpl<R> {
         self.repository.get_user_by_id(user_id).await
     }
 
+    async fn get_user_by_public_id(
+        &self,
+        public_id: &str,
+        scope: DeletedScope,
+    ) -> Result<User, UserError> {
+        self.repository
+            .get_user_by_public_id(public_id, scope)
+            .await
+    }
+
     async fn update_user(&self, user_id: i32, data: UpdateUserDto) -> Result<User, UserError> {
         let new_user_info = data.clone();
         let user = self.repository.get_user_by_id(user_id).await;
@@ -51,6 +69,20 @@ impl<R: UserRepository + Send + Sync> UserService for UserServiceImpl<R> {
                     updated_user.bio = Some(bio);
                 }
 
+                if let Some(locale) = new_user_info.locale {
+                    if !is_valid_locale(&locale) {
+                        return Err(UserError::InvalidLocale(locale));
+                    }
+                    updated_user.locale = locale;
+                }
+
+                if let Some(timezone) = new_user_info.timezone {
+                    if parse_timezone(&timezone).is_none() {
+                        return Err(UserError::InvalidTimezone(timezone));
+                    }
+                    updated_user.timezone = timezone;
+                }
+
                 self.repository.update_user(updated_user).await
             }
             Err(err) => Err(err),
@@ -88,6 +120,16 @@ mod tests {
         async fn get_user_by_id(&self, user_id: i32) -> Result<User, UserError> {
             self.user.clone().ok_or(UserError::UserNotFound(user_id))
         }
+        async fn get_user_by_public_id(
+            &self,
+            public_id: &str,
+            scope: DeletedScope,
+        ) -> Result<User, UserError> {
+            self.user
+                .clone()
+                .filter(|user| user.public_id == public_id && scope.allows(user.deleted_at))
+                .ok_or(UserError::PublicIdNotFound(public_id.to_string()))
+        }
         async fn update_user(&self, _user: User) -> Result<User, UserError> {
             self.update_user_result
                 .clone()
@@ -124,6 +166,9 @@ mod tests {
 
             deleted_at: None,
             last_seen_at: None,
+            public_id: "01J5Z3K4N8QXW2V7B6C9D0E1FG".to_string(),
+            locale: "en-US".to_string(),
+            timezone: "UTC".to_string(),
         }
     }
 
@@ -153,6 +198,52 @@ mod tests {
         assert!(matches!(result, Err(UserError::UserNotFound(1))));
     }
 
+    #[tokio::test]
+    async fn test_get_user_by_public_id() {
+        let repo = MockUserRepository {
+            user: Some(sample_user()),
+            username_exists: true,
+            update_user_result: None,
+            update_username_result: None,
+        };
+        let service = UserServiceImpl::new(repo);
+        let user = service
+            .get_user_by_public_id("01J5Z3K4N8QXW2V7B6C9D0E1FG", DeletedScope::Exclude)
+            .await
+            .unwrap();
+        assert_eq!(user.id, 1);
+
+        let result = service
+            .get_user_by_public_id("1", DeletedScope::Exclude)
+            .await;
+        assert!(matches!(result, Err(UserError::PublicIdNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_get_deleted_user_by_public_id() {
+        let deleted_user = User {
+            deleted_at: Some(DateTime::from_timestamp(60, 0).unwrap().naive_utc()),
+            ..sample_user()
+        };
+        let repo = MockUserRepository {
+            user: Some(deleted_user),
+            username_exists: true,
+            update_user_result: None,
+            update_username_result: None,
+        };
+        let service = UserServiceImpl::new(repo);
+        let result = service
+            .get_user_by_public_id("01J5Z3K4N8QXW2V7B6C9D0E1FG", DeletedScope::Exclude)
+            .await;
+        assert!(matches!(result, Err(UserError::PublicIdNotFound(_))));
+
+        let user = service
+            .get_user_by_public_id("01J5Z3K4N8QXW2V7B6C9D0E1FG", DeletedScope::Include)
+            .await
+            .unwrap();
+        assert!(user.deleted_at.is_some());
+    }
+
     #[tokio::test]
     async fn test_update_user_success() {
         let user = sample_user();
@@ -171,6 +262,8 @@ mod tests {
             full_name: "Updated Name".to_string(),
             avatar: Some("new_avatar.png".to_string()),
             bio: Some("new bio".to_string()),
+            locale: None,
+            timezone: None,
         };
         let result = service.update_user(1, dto).await.unwrap();
         assert_eq!(result.full_name, Some("Updated Name".to_string()));
@@ -189,11 +282,73 @@ mod tests {
             full_name: "Updated Name".to_string(),
             avatar: Some("new_avatar.png".to_string()),
             bio: Some("new bio".to_string()),
+            locale: None,
+            timezone: None,
         };
         let result = service.update_user(1, dto).await;
         assert!(matches!(result, Err(UserError::UserNotFound(1))));
     }
 
+    #[tokio::test]
+    async fn test_update_user_locale_and_timezone() {
+        let user = sample_user();
+        let repo = MockUserRepository {
+            user: Some(user.clone()),
+            username_exists: true,
+            update_user_result: Some(user),
+            update_username_result: None,
+        };
+        let service = UserServiceImpl::new(repo);
+        let dto = UpdateUserDto {
+            full_name: "Test User".to_string(),
+            avatar: None,
+            bio: None,
+            locale: Some("vi-VN".to_string()),
+            timezone: Some("Asia/Ho_Chi_Minh".to_string()),
+        };
+        assert!(service.update_user(1, dto.clone()).await.is_ok());
+
+        let bad_timezone = UpdateUserDto {
+            timezone: Some("Mars/Olympus_Mons".to_string()),
+            ..dto.clone()
+        };
+        assert!(matches!(
+            service.update_user(1, bad_timezone).await,
+            Err(UserError::InvalidTimezone(_))
+        ));
+
+        let bad_locale = UpdateUserDto {
+            locale: Some("not a locale".to_string()),
+            ..dto
+        };
+        assert!(matches!(
+            service.update_user(1, bad_locale).await,
+            Err(UserError::InvalidLocale(_))
+        ));
+    }
+
+    #[test]
+    fn test_format_for_user() {
+        use crate::core::utils::locale_utils::format_for_user;
+
+        let at = DateTime::from_timestamp(1_755_860_400, 0)
+            .unwrap()
+            .naive_utc();
+        let user = User {
+            locale: "vi-VN".to_string(),
+            timezone: "Asia/Ho_Chi_Minh".to_string(),
+            ..sample_user()
+        };
+        assert_eq!(format_for_user(at, &user), "2025-08-22 18:00 +07");
+
+        let user = User {
+            locale: "en-US".to_string(),
+            timezone: "America/New_York".to_string(),
+            ..sample_user()
+        };
+        assert_eq!(format_for_user(at, &user), "Aug 22, 2025 7:00 AM EDT");
+    }
+
     #[tokio::test]
     async fn test_check_username_exists_true() {
         let repo = MockUserRepository {